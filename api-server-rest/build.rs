@@ -61,18 +61,54 @@ fn _evidence() {}
 )]
 fn _resource() {}
 
+#[utoipa::path(
+    post,
+    path = "/cdh/secret",
+    request_body(content = String, content_type = "application/octet-stream",
+                description = "sealed secret", example = json!("sealed.fakejwsheader.eyJ2ZXJzaW9uIjoiMC4xLjAiLCJ0eXBlIjoidmF1bHQiLC4uLn0.fakesignature")),
+    security(("bearer_token" = [])),
+    responses(
+        (status = 200, description = "success response",
+                content_type = "application/octet-stream",
+                body = String,
+                example = json!("plaintext of the secret")),
+        (status = 400, description = "bad request for empty sealed secret"),
+        (status = 401, description = "missing or wrong bearer token"),
+        (status = 403, description = "forbid external access, or the API is disabled"),
+        (status = 405, description = "only Post method allowed"),
+        (status = 413, description = "sealed secret larger than the max body size"),
+        (status = 500, description = "failed to unseal the secret")
+    )
+)]
+fn _secret() {}
+
+struct SecurityAddon;
+
+impl utoipa::Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
 fn generate_openapi_document() -> std::io::Result<()> {
     #[derive(OpenApi)]
     #[openapi(
     info(
         title = "CoCo Restful API",
-        description = "HTTP based API for CoCo containers to get resource/evidence/token from confidential-data-hub and attestation-agent."),
+        description = "HTTP based API for CoCo containers to get resource/evidence/token and unseal secrets from confidential-data-hub and attestation-agent."),
 
     servers(
         (url = "http://127.0.0.1:8006", description = "CoCo Restful API")
      ),
 
-    paths(_token, _evidence, _resource)
+    paths(_token, _evidence, _resource, _secret),
+    modifiers(&SecurityAddon)
  )]
     struct ApiDoc;
     let mut file = File::create("openapi/api.json")?;
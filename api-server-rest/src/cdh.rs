@@ -4,14 +4,16 @@
 //
 
 use crate::router::ApiHandler;
-use crate::ttrpc_proto::confidential_data_hub::GetResourceRequest;
-use crate::ttrpc_proto::confidential_data_hub_ttrpc::GetResourceServiceClient;
+use crate::ttrpc_proto::confidential_data_hub::{GetResourceRequest, UnsealSecretInput};
+use crate::ttrpc_proto::confidential_data_hub_ttrpc::{
+    GetResourceServiceClient, SealedSecretServiceClient,
+};
 use anyhow::*;
 use async_trait::async_trait;
-use hyper::{Body, Method, Request, Response};
+use hyper::{header, Body, Method, Request, Response};
 use std::net::SocketAddr;
 
-use crate::utils::split_nth_slash;
+use crate::utils::{bearer_token, read_body, split_nth_slash, tokens_equal};
 use crate::TTRPC_TIMEOUT;
 
 /// ROOT path for Confidential Data Hub API
@@ -20,11 +22,27 @@ pub const CDH_ROOT: &str = "/cdh";
 /// URL for querying CDH get resource API
 pub const CDH_RESOURCE_URL: &str = "/resource";
 
+/// URL for CDH unseal secret API
+pub const CDH_SECRET_URL: &str = "/secret";
+
 const KBS_PREFIX: &str = "kbs://";
 
+/// Configuration of the unseal secret API.
+#[derive(Default)]
+pub struct UnsealConfig {
+    /// The bearer token the requests must carry, which is given to the pod.
+    /// The API is disabled if it is not set.
+    pub auth_token: Option<String>,
+
+    /// Max size in bytes of the sealed secret of a request.
+    pub max_body_size: usize,
+}
+
 pub struct CDHClient {
     client: GetResourceServiceClient,
+    sealed_secret_client: SealedSecretServiceClient,
     accepted_method: Vec<Method>,
+    unseal_config: UnsealConfig,
 }
 
 #[async_trait]
@@ -45,9 +63,17 @@ impl ApiHandler for CDHClient {
             return self.not_allowed();
         }
 
+        if url_path == CDH_SECRET_URL {
+            return self.handle_unseal_secret(req).await;
+        }
+
         if let Some((api, resource_path)) = split_nth_slash(url_path, 2) {
             match api {
                 CDH_RESOURCE_URL => {
+                    if req.method() != Method::GET {
+                        return self.not_allowed();
+                    }
+
                     let results = self
                         .get_resource(resource_path)
                         .await
@@ -65,16 +91,68 @@ impl ApiHandler for CDHClient {
 }
 
 impl CDHClient {
-    pub fn new(cdh_addr: &str, accepted_method: Vec<Method>) -> Result<Self> {
+    pub fn new(
+        cdh_addr: &str,
+        accepted_method: Vec<Method>,
+        unseal_config: UnsealConfig,
+    ) -> Result<Self> {
         let inner = ttrpc::asynchronous::Client::connect(cdh_addr)?;
-        let client = GetResourceServiceClient::new(inner);
+        let client = GetResourceServiceClient::new(inner.clone());
+        let sealed_secret_client = SealedSecretServiceClient::new(inner);
 
         Ok(Self {
             client,
+            sealed_secret_client,
             accepted_method,
+            unseal_config,
         })
     }
 
+    /// Handle a `POST` request whose body is a sealed secret, and reply the
+    /// plaintext of it. The request must carry the bearer token of the API.
+    async fn handle_unseal_secret(&self, req: Request<Body>) -> Result<Response<Body>> {
+        if req.method() != Method::POST {
+            return self.not_allowed();
+        }
+
+        let Some(auth_token) = &self.unseal_config.auth_token else {
+            // The API is disabled without a token.
+            return self.forbidden();
+        };
+        let authorized = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(bearer_token)
+            .is_some_and(|token| tokens_equal(token.as_bytes(), auth_token.as_bytes()));
+        if !authorized {
+            return self.unauthorized();
+        }
+
+        let max_body_size = self.unseal_config.max_body_size;
+        let too_large = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|len| len.parse::<usize>().ok())
+            .is_some_and(|len| len > max_body_size);
+        if too_large {
+            return self.payload_too_large();
+        }
+
+        let Some(secret) = read_body(req.into_body(), max_body_size).await? else {
+            return self.payload_too_large();
+        };
+        if secret.is_empty() {
+            return self.bad_request();
+        }
+
+        match self.unseal_secret(secret).await {
+            Result::Ok(plaintext) => self.octet_stream_response(plaintext),
+            Err(e) => self.internal_error(e.to_string()),
+        }
+    }
+
     pub async fn get_resource(&self, resource_path: &str) -> Result<Vec<u8>> {
         let req = GetResourceRequest {
             ResourcePath: format!("{}{}", KBS_PREFIX, resource_path),
@@ -86,4 +164,16 @@ impl CDHClient {
             .await?;
         Ok(res.Resource)
     }
+
+    pub async fn unseal_secret(&self, secret: Vec<u8>) -> Result<Vec<u8>> {
+        let req = UnsealSecretInput {
+            secret,
+            ..Default::default()
+        };
+        let res = self
+            .sealed_secret_client
+            .unseal_secret(ttrpc::context::with_timeout(TTRPC_TIMEOUT), &req)
+            .await?;
+        Ok(res.plaintext)
+    }
 }
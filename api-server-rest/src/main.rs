@@ -8,17 +8,20 @@ use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Method, Server};
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 mod aa;
 mod cdh;
 mod router;
+mod tls;
 mod ttrpc_proto;
 mod utils;
 
 use aa::{AAClient, AA_ROOT};
-use cdh::{CDHClient, CDH_ROOT};
+use cdh::{CDHClient, UnsealConfig, CDH_ROOT};
 use router::Router;
+use tls::{TLS_KBS, TLS_SELF_SIGNED};
 
 type GenericError = Box<dyn std::error::Error + Send + Sync>;
 type Result<T> = std::result::Result<T, GenericError>;
@@ -26,6 +29,8 @@ type Result<T> = std::result::Result<T, GenericError>;
 pub const TTRPC_TIMEOUT: i64 = 50 * 1000 * 1000 * 1000;
 const DEFAULT_BIND: &str = "127.0.0.1:8006";
 const DEFAULT_FEATURE: &str = "resource";
+const DEFAULT_MAX_BODY_SIZE: usize = 64 * 1024;
+const DEFAULT_TLS_CERT_PATH: &str = "/run/confidential-containers/api-server-rest/cert.pem";
 const CDH_ADDR: &str = "unix:///run/confidential-containers/cdh.sock";
 const AA_ADDR: &str =
     "unix:///run/confidential-containers/attestation-agent/attestation-agent.sock";
@@ -49,6 +54,43 @@ struct Args {
     /// Listen address of attestation-agent TTRPC Service
     #[arg(default_value_t = AA_ADDR.to_string(), short, long = "cdh_addr")]
     aa_addr: String,
+
+    /// File of the bearer token the requests of the unseal secret API must
+    /// carry. The API is disabled if it is not given.
+    #[arg(long = "unseal_token_file")]
+    unseal_token_file: Option<PathBuf>,
+
+    /// Max size in bytes of the sealed secret of an unseal secret request
+    #[arg(default_value_t = DEFAULT_MAX_BODY_SIZE, long = "max_body_size")]
+    max_body_size: usize,
+
+    /// Serve HTTPS rather than HTTP with a certificate, allowed options:
+    /// self-signed, which is generated at boot and measured into the runtime
+    /// measurement, or kbs, which is got together with its key from the KBS
+    #[arg(long = "tls")]
+    tls: Option<String>,
+
+    /// Path to write the self-signed certificate to, for the clients to trust
+    #[arg(default_value_t = DEFAULT_TLS_CERT_PATH.to_string(), long = "tls_cert_path")]
+    tls_cert_path: String,
+
+    /// KBS resource path of the PEM certificate chain, e.g. default/api-server/cert
+    #[arg(long = "tls_cert_resource")]
+    tls_cert_resource: Option<String>,
+
+    /// KBS resource path of the PEM private key, e.g. default/api-server/key
+    #[arg(long = "tls_key_resource")]
+    tls_key_resource: Option<String>,
+}
+
+/// Read the bearer token of the unseal secret API from the `path`.
+fn read_auth_token(path: PathBuf) -> Result<String> {
+    let token = std::fs::read_to_string(&path)?.trim().to_string();
+    if token.is_empty() {
+        return Err(format!("auth token file {} is empty", path.display()).into());
+    }
+
+    Ok(token)
 }
 
 #[tokio::main]
@@ -62,13 +104,22 @@ async fn main() -> Result<()> {
 
     let address: SocketAddr = args.bind.parse().expect("Failed to parse the address");
 
+    let unseal_config = UnsealConfig {
+        auth_token: args.unseal_token_file.map(read_auth_token).transpose()?,
+        max_body_size: args.max_body_size,
+    };
+
     let mut router = Router::new();
 
     match args.features.as_str() {
         "resource" => {
             router.register_route(
                 CDH_ROOT,
-                Box::new(CDHClient::new(&args.cdh_addr, vec![Method::GET])?),
+                Box::new(CDHClient::new(
+                    &args.cdh_addr,
+                    vec![Method::GET, Method::POST],
+                    unseal_config,
+                )?),
             );
         }
 
@@ -82,7 +133,11 @@ async fn main() -> Result<()> {
         "all" => {
             router.register_route(
                 CDH_ROOT,
-                Box::new(CDHClient::new(&args.cdh_addr, vec![Method::GET])?),
+                Box::new(CDHClient::new(
+                    &args.cdh_addr,
+                    vec![Method::GET, Method::POST],
+                    unseal_config,
+                )?),
             );
 
             router.register_route(
@@ -97,8 +152,40 @@ async fn main() -> Result<()> {
         }
     }
 
+    let tls_config = match args.tls.as_deref() {
+        None => None,
+        Some(TLS_SELF_SIGNED) => {
+            let aa = AAClient::new(&args.aa_addr, vec![])?;
+            Some(tls::self_signed_config(Path::new(&args.tls_cert_path), &aa).await?)
+        }
+        Some(TLS_KBS) => {
+            let (Some(cert_resource), Some(key_resource)) =
+                (&args.tls_cert_resource, &args.tls_key_resource)
+            else {
+                eprintln!("Both tls_cert_resource and tls_key_resource are required by kbs TLS.");
+                std::process::exit(1);
+            };
+            let cdh = CDHClient::new(&args.cdh_addr, vec![], UnsealConfig::default())?;
+            Some(tls::kbs_config(&cdh, cert_resource, key_resource).await?)
+        }
+        Some(_) => {
+            eprintln!("Unknown TLS certificate. Supported options are: self-signed, kbs.");
+            std::process::exit(1);
+        }
+    };
+
     let router = Arc::new(tokio::sync::Mutex::new(router));
 
+    if let Some(config) = tls_config {
+        println!("API Server listening on https://{}", args.bind);
+
+        if let Err(e) = tls::serve(address, config, router).await {
+            eprintln!("API server error: {}", e);
+        }
+
+        return Ok(());
+    }
+
     let api_service = make_service_fn(|conn: &AddrStream| {
         let remote_addr = conn.remote_addr();
         let local_router = router.clone();
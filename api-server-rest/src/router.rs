@@ -45,6 +45,14 @@ pub trait ApiHandler: Send {
             .body(Body::from("BAD REQUEST"))?)
     }
 
+    // Build 401 Unauthorized response.
+    fn unauthorized(&self) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(header::WWW_AUTHENTICATE, "Bearer")
+            .body(Body::from("Unauthorized"))?)
+    }
+
     // Build 403 Forbidden response.
     fn forbidden(&self) -> Result<Response<Body>> {
         Ok(Response::builder()
@@ -65,6 +73,20 @@ pub trait ApiHandler: Send {
             .status(StatusCode::METHOD_NOT_ALLOWED)
             .body(Body::from("Method Not Allowed"))?)
     }
+
+    // Build 413 Payload Too Large response.
+    fn payload_too_large(&self) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .status(StatusCode::PAYLOAD_TOO_LARGE)
+            .body(Body::from("Payload Too Large"))?)
+    }
+
+    // Build 500 Internal Server Error response.
+    fn internal_error(&self, message: String) -> Result<Response<Body>> {
+        Ok(Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(message))?)
+    }
 }
 
 pub struct Router {
@@ -0,0 +1,226 @@
+// Copyright (c) 2023 Intel Corporation
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! HTTPS listener of the API server, so that the other containers sharing
+//! the network namespace of the pod, e.g. sidecars of peer pods, cannot
+//! sniff the secrets from the plaintext traffic.
+
+use anyhow::*;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::aa::AAClient;
+use crate::cdh::CDHClient;
+use crate::router::Router;
+
+/// The certificate is generated at boot and self-signed.
+pub const TLS_SELF_SIGNED: &str = "self-signed";
+
+/// The certificate and the key are KBS resources.
+pub const TLS_KBS: &str = "kbs";
+
+/// Domain of the runtime measurement of the self-signed certificate.
+const MEASUREMENT_DOMAIN: &str = "github.com/confidential-containers/api-server-rest";
+
+const MEASUREMENT_OPERATION: &str = "TlsCertificate";
+
+const SELF_SIGNED_VALID_DAYS: u32 = 365;
+
+/// Generate a P-256 key and a certificate of it self-signed for localhost.
+/// Return the DER certificate and the PKCS#8 DER key.
+fn self_signed() -> Result<(Vec<u8>, Vec<u8>)> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = PKey::from_ec_key(EcKey::generate(&group)?)?;
+
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, "api-server-rest")?;
+    let name = name.build();
+
+    let mut serial = BigNum::new()?;
+    serial.rand(128, MsbOption::MAYBE_ZERO, false)?;
+    let serial = serial.to_asn1_integer()?;
+    let not_before = Asn1Time::days_from_now(0)?;
+    let not_after = Asn1Time::days_from_now(SELF_SIGNED_VALID_DAYS)?;
+
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(&key)?;
+    builder.set_not_before(&not_before)?;
+    builder.set_not_after(&not_after)?;
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .ip("::1")
+        .build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(san)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+
+    Ok((builder.build().to_der()?, key.private_key_to_pkcs8()?))
+}
+
+/// Parse the PEM certificate chain and the PEM private key, in PKCS#8, SEC1
+/// or PKCS#1 format.
+fn parse_pem(cert_chain: &[u8], key: &[u8]) -> Result<(Vec<Certificate>, PrivateKey)> {
+    let cert_chain: Vec<_> = rustls_pemfile::certs(&mut &cert_chain[..])?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if cert_chain.is_empty() {
+        bail!("no certificate in the PEM certificate chain");
+    }
+
+    let key = rustls_pemfile::read_all(&mut &key[..])?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key)
+            | rustls_pemfile::Item::RSAKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("no private key in the PEM key"))?;
+
+    Ok((cert_chain, key))
+}
+
+/// Generate the self-signed certificate, write it to `cert_path` in PEM for
+/// the clients to trust, and extend the runtime measurement with its digest,
+/// so that a verifier of the evidence can tell the certificate is generated
+/// inside the TEE.
+pub async fn self_signed_config(cert_path: &Path, aa: &AAClient) -> Result<ServerConfig> {
+    let (cert, key) = self_signed()?;
+
+    let pem = X509::from_der(&cert)?.to_pem()?;
+    if let Some(dir) = cert_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(cert_path, pem)
+        .with_context(|| format!("write certificate to {} failed", cert_path.display()))?;
+
+    let digest = format!("sha256:{}", hex::encode(openssl::sha::sha256(&cert)));
+    aa.extend_runtime_measurement(MEASUREMENT_DOMAIN, MEASUREMENT_OPERATION, &digest)
+        .await
+        .context("measure the certificate failed")?;
+    println!("Generated TLS certificate {digest}");
+
+    server_config(vec![Certificate(cert)], PrivateKey(key))
+}
+
+/// The resource path in the `kbs://` URI, e.g. `/default/api-server/cert`.
+fn resource_path(path: &str) -> String {
+    format!("/{}", path.trim_start_matches('/'))
+}
+
+/// Get the PEM certificate chain and the PEM private key from the KBS
+/// resources `cert_resource` and `key_resource`.
+pub async fn kbs_config(
+    cdh: &CDHClient,
+    cert_resource: &str,
+    key_resource: &str,
+) -> Result<ServerConfig> {
+    let cert_chain = cdh
+        .get_resource(&resource_path(cert_resource))
+        .await
+        .context("get TLS certificate from KBS failed")?;
+    let key = cdh
+        .get_resource(&resource_path(key_resource))
+        .await
+        .context("get TLS key from KBS failed")?;
+    let (cert_chain, key) = parse_pem(&cert_chain, &key)?;
+
+    server_config(cert_chain, key)
+}
+
+fn server_config(cert_chain: Vec<Certificate>, key: PrivateKey) -> Result<ServerConfig> {
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, key)
+        .context("illegal TLS certificate or key")
+}
+
+/// Serve the `router` over HTTPS at `address`.
+pub async fn serve(
+    address: SocketAddr,
+    config: ServerConfig,
+    router: Arc<Mutex<Router>>,
+) -> Result<()> {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind(address).await?;
+
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Result::Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("Accept connection failed: {}", e);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Result::Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("TLS handshake with {} failed: {}", remote_addr, e);
+                    return;
+                }
+            };
+
+            let service = service_fn(move |req| {
+                let router = router.clone();
+                async move { router.lock().await.route(remote_addr, req).await }
+            });
+            if let Err(e) = Http::new().serve_connection(stream, service).await {
+                eprintln!("Serve connection of {} failed: {}", remote_addr, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_signed() {
+        let (cert, key) = self_signed().unwrap();
+        let pem_cert = X509::from_der(&cert).unwrap().to_pem().unwrap();
+        let pem_key = PKey::private_key_from_pkcs8(&key)
+            .unwrap()
+            .private_key_to_pem_pkcs8()
+            .unwrap();
+
+        let (cert_chain, key) = parse_pem(&pem_cert, &pem_key).unwrap();
+        assert_eq!(cert_chain, vec![Certificate(cert)]);
+        assert!(server_config(cert_chain, key).is_ok());
+    }
+
+    #[test]
+    fn test_parse_pem_without_key() {
+        let (cert, _) = self_signed().unwrap();
+        let pem_cert = X509::from_der(&cert).unwrap().to_pem().unwrap();
+        assert!(parse_pem(&pem_cert, &pem_cert).is_err());
+        assert!(parse_pem(b"", b"").is_err());
+    }
+}
@@ -25,6 +25,250 @@
 /// of protobuf runtime.
 const _PROTOBUF_VERSION_CHECK: () = ::protobuf::VERSION_3_2_0;
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnsealSecretInput)
+pub struct UnsealSecretInput {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnsealSecretInput.secret)
+    pub secret: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnsealSecretInput.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnsealSecretInput {
+    fn default() -> &'a UnsealSecretInput {
+        <UnsealSecretInput as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnsealSecretInput {
+    pub fn new() -> UnsealSecretInput {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "secret",
+            |m: &UnsealSecretInput| { &m.secret },
+            |m: &mut UnsealSecretInput| { &mut m.secret },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnsealSecretInput>(
+            "UnsealSecretInput",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnsealSecretInput {
+    const NAME: &'static str = "UnsealSecretInput";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.secret = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.secret.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.secret);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.secret.is_empty() {
+            os.write_bytes(1, &self.secret)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnsealSecretInput {
+        UnsealSecretInput::new()
+    }
+
+    fn clear(&mut self) {
+        self.secret.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnsealSecretInput {
+        static instance: UnsealSecretInput = UnsealSecretInput {
+            secret: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnsealSecretInput {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnsealSecretInput").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnsealSecretInput {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnsealSecretInput {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnsealSecretOutput)
+pub struct UnsealSecretOutput {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnsealSecretOutput.plaintext)
+    pub plaintext: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnsealSecretOutput.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnsealSecretOutput {
+    fn default() -> &'a UnsealSecretOutput {
+        <UnsealSecretOutput as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnsealSecretOutput {
+    pub fn new() -> UnsealSecretOutput {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "plaintext",
+            |m: &UnsealSecretOutput| { &m.plaintext },
+            |m: &mut UnsealSecretOutput| { &mut m.plaintext },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnsealSecretOutput>(
+            "UnsealSecretOutput",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnsealSecretOutput {
+    const NAME: &'static str = "UnsealSecretOutput";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.plaintext = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.plaintext.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.plaintext);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.plaintext.is_empty() {
+            os.write_bytes(1, &self.plaintext)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnsealSecretOutput {
+        UnsealSecretOutput::new()
+    }
+
+    fn clear(&mut self) {
+        self.plaintext.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnsealSecretOutput {
+        static instance: UnsealSecretOutput = UnsealSecretOutput {
+            plaintext: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnsealSecretOutput {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnsealSecretOutput").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnsealSecretOutput {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnsealSecretOutput {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:api.GetResourceRequest)
 pub struct GetResourceRequest {
@@ -270,11 +514,15 @@ impl ::protobuf::reflect::ProtobufValue for GetResourceResponse {
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
-    \n\x1bconfidential_data_hub.proto\x12\x03api\"8\n\x12GetResourceRequest\
-    \x12\"\n\x0cResourcePath\x18\x01\x20\x01(\tR\x0cResourcePath\"1\n\x13Get\
-    ResourceResponse\x12\x1a\n\x08Resource\x18\x01\x20\x01(\x0cR\x08Resource\
-    2V\n\x12GetResourceService\x12@\n\x0bGetResource\x12\x17.api.GetResource\
-    Request\x1a\x18.api.GetResourceResponseb\x06proto3\
+    \n\x1bconfidential_data_hub.proto\x12\x03api\"+\n\x11UnsealSecretInput\
+    \x12\x16\n\x06secret\x18\x01\x20\x01(\x0cR\x06secret\"2\n\x12UnsealSecre\
+    tOutput\x12\x1c\n\tplaintext\x18\x01\x20\x01(\x0cR\tplaintext\"8\n\x12Ge\
+    tResourceRequest\x12\"\n\x0cResourcePath\x18\x01\x20\x01(\tR\x0cResource\
+    Path\"1\n\x13GetResourceResponse\x12\x1a\n\x08Resource\x18\x01\x20\x01(\
+    \x0cR\x08Resource2V\n\x13SealedSecretService\x12?\n\x0cUnsealSecret\x12\
+    \x16.api.UnsealSecretInput\x1a\x17.api.UnsealSecretOutput2V\n\x12GetReso\
+    urceService\x12@\n\x0bGetResource\x12\x17.api.GetResourceRequest\x1a\x18\
+    .api.GetResourceResponseb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -292,7 +540,9 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
     file_descriptor.get(|| {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(0);
-            let mut messages = ::std::vec::Vec::with_capacity(2);
+            let mut messages = ::std::vec::Vec::with_capacity(4);
+            messages.push(UnsealSecretInput::generated_message_descriptor_data());
+            messages.push(UnsealSecretOutput::generated_message_descriptor_data());
             messages.push(GetResourceRequest::generated_message_descriptor_data());
             messages.push(GetResourceResponse::generated_message_descriptor_data());
             let mut enums = ::std::vec::Vec::with_capacity(0);
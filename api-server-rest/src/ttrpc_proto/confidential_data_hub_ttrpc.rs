@@ -22,6 +22,54 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use async_trait::async_trait;
 
+#[derive(Clone)]
+pub struct SealedSecretServiceClient {
+    client: ::ttrpc::r#async::Client,
+}
+
+impl SealedSecretServiceClient {
+    pub fn new(client: ::ttrpc::r#async::Client) -> Self {
+        SealedSecretServiceClient {
+            client: client,
+        }
+    }
+
+    pub async fn unseal_secret(&self, ctx: ttrpc::context::Context, req: &super::confidential_data_hub::UnsealSecretInput) -> ::ttrpc::Result<super::confidential_data_hub::UnsealSecretOutput> {
+        let mut cres = super::confidential_data_hub::UnsealSecretOutput::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SealedSecretService", "UnsealSecret", cres);
+    }
+}
+
+struct UnsealSecretMethod {
+    service: Arc<Box<dyn SealedSecretService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnsealSecretMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, confidential_data_hub, UnsealSecretInput, unseal_secret);
+    }
+}
+
+#[async_trait]
+pub trait SealedSecretService: Sync {
+    async fn unseal_secret(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::confidential_data_hub::UnsealSecretInput) -> ::ttrpc::Result<super::confidential_data_hub::UnsealSecretOutput> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SealedSecretService/UnsealSecret is not supported".to_string())))
+    }
+}
+
+pub fn create_sealed_secret_service(service: Arc<Box<dyn SealedSecretService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
+    let mut ret = HashMap::new();
+    let mut methods = HashMap::new();
+    let streams = HashMap::new();
+
+    methods.insert("UnsealSecret".to_string(),
+                    Box::new(UnsealSecretMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    ret.insert("api.SealedSecretService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
+    ret
+}
+
 #[derive(Clone)]
 pub struct GetResourceServiceClient {
     client: ::ttrpc::r#async::Client,
@@ -3,6 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use hyper::body::HttpBody;
+use hyper::Body;
+
 pub fn split_nth_slash(url: &str, n: usize) -> Option<(&str, &str)> {
     let mut split_pos = None;
     let mut splits = url.match_indices('/');
@@ -14,6 +17,35 @@ pub fn split_nth_slash(url: &str, n: usize) -> Option<(&str, &str)> {
     split_pos.map(|(idx, pat)| url.split_at(idx + pat.len() - 1))
 }
 
+/// Get the token of an `Authorization` header value of the bearer scheme.
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then_some(token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Compare the tokens in time independent of their contents, so that a token
+/// cannot be guessed byte by byte from the response time.
+pub fn tokens_equal(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Read the whole `body`, or `None` if it is larger than `limit` bytes.
+pub async fn read_body(mut body: Body, limit: usize) -> Result<Option<Vec<u8>>, hyper::Error> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if data.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(Some(data))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,4 +72,29 @@ mod tests {
         );
         assert_eq!(split_nth_slash(url_path, 5), None);
     }
+
+    #[test]
+    fn test_bearer_token() {
+        assert_eq!(bearer_token("Bearer abc"), Some("abc"));
+        assert_eq!(bearer_token("bearer  abc "), Some("abc"));
+        assert_eq!(bearer_token("Basic abc"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+        assert_eq!(bearer_token("abc"), None);
+    }
+
+    #[test]
+    fn test_tokens_equal() {
+        assert!(tokens_equal(b"token", b"token"));
+        assert!(!tokens_equal(b"token", b"tokem"));
+        assert!(!tokens_equal(b"token", b"token1"));
+    }
+
+    #[tokio::test]
+    async fn test_read_body() {
+        let body = Body::from(vec![1; 16]);
+        assert_eq!(read_body(body, 16).await.unwrap(), Some(vec![1; 16]));
+
+        let body = Body::from(vec![1; 17]);
+        assert_eq!(read_body(body, 16).await.unwrap(), None);
+    }
 }
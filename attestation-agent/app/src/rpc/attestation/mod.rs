@@ -3,14 +3,50 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use attestation_agent::AttestationAPIs;
+use attestation_agent::{
+    freshness::{EvidenceFormat, HashAlgorithm},
+    AttestationAPIs,
+};
 use log::*;
 use std::sync::Arc;
 
+#[cfg(feature = "ttrpc")]
+use crate::rpc::peer::PeerPolicy;
 use crate::rpc::AGENT_NAME;
 
 #[derive(Debug, Default)]
-pub struct Attestation {}
+pub struct Attestation {
+    #[cfg(feature = "ttrpc")]
+    policy: Arc<PeerPolicy>,
+}
+
+/// Empty string fields of the requests are not set.
+#[allow(dead_code)]
+fn non_empty(field: &str) -> Option<&str> {
+    (!field.is_empty()).then_some(field)
+}
+
+/// The hash algorithm of the request, or the default if it is not set.
+#[allow(dead_code)]
+fn hash_algorithm(field: &str) -> anyhow::Result<Option<HashAlgorithm>> {
+    non_empty(field)
+        .map(|name| {
+            name.parse()
+                .map_err(|_| anyhow::anyhow!("unsupported hash algorithm {name}"))
+        })
+        .transpose()
+}
+
+/// The evidence format of the request, or the default if it is not set.
+#[allow(dead_code)]
+fn evidence_format(field: &str) -> anyhow::Result<Option<EvidenceFormat>> {
+    non_empty(field)
+        .map(|name| {
+            name.parse()
+                .map_err(|_| anyhow::anyhow!("unsupported evidence format {name}"))
+        })
+        .transpose()
+}
 
 #[cfg(feature = "grpc")]
 pub mod grpc {
@@ -20,7 +56,11 @@ pub mod grpc {
     use attestation::attestation_agent_service_server::{
         AttestationAgentService, AttestationAgentServiceServer,
     };
-    use attestation::{GetEvidenceRequest, GetEvidenceResponse, GetTokenRequest, GetTokenResponse};
+    use attestation::{
+        ExtendRuntimeMeasurementRequest, ExtendRuntimeMeasurementResponse, GetEvidenceRequest,
+        GetEvidenceResponse, GetEvidenceWithNonceRequest, GetEvidenceWithNonceResponse,
+        GetTokenRequest, GetTokenResponse,
+    };
     use std::net::SocketAddr;
     use tonic::{transport::Server, Request, Response, Status};
 
@@ -42,7 +82,12 @@ pub mod grpc {
             debug!("Call AA to get token ...");
 
             let token = attestation_agent
-                .get_token(&request.token_type)
+                .get_token_for_audience(
+                    &request.token_type,
+                    non_empty(&request.audience),
+                    non_empty(&request.nonce),
+                    (!request.runtime_data.is_empty()).then_some(&request.runtime_data[..]),
+                )
                 .await
                 .map_err(|e| {
                     error!("Call AA to get token failed: {}", e);
@@ -84,6 +129,78 @@ pub mod grpc {
 
             Result::Ok(Response::new(reply))
         }
+
+        async fn get_evidence_with_nonce(
+            &self,
+            request: Request<GetEvidenceWithNonceRequest>,
+        ) -> Result<Response<GetEvidenceWithNonceResponse>, Status> {
+            let request = request.into_inner();
+            let hash_algorithm = hash_algorithm(&request.hash_algorithm)
+                .map_err(|e| Status::invalid_argument(format!("[ERROR:{AGENT_NAME}] {e}")))?;
+            let format = evidence_format(&request.format)
+                .map_err(|e| Status::invalid_argument(format!("[ERROR:{AGENT_NAME}] {e}")))?;
+
+            let attestation_agent_mutex_clone = Arc::clone(&ASYNC_ATTESTATION_AGENT);
+            let mut attestation_agent = attestation_agent_mutex_clone.lock().await;
+
+            debug!("Call AA to get evidence with nonce ...");
+
+            let evidence = attestation_agent
+                .get_evidence_with_nonce(&request.nonce, hash_algorithm, format)
+                .await
+                .map_err(|e| {
+                    error!("Call AA to get evidence with nonce failed: {}", e);
+                    Status::internal(format!(
+                        "[ERROR:{}] AA get evidence with nonce failed: {}",
+                        AGENT_NAME, e
+                    ))
+                })?;
+
+            debug!("Get evidence with nonce successfully!");
+
+            let reply = GetEvidenceWithNonceResponse {
+                evidence: evidence.evidence,
+                tee: evidence.tee,
+                hash_algorithm: evidence.hash_algorithm.to_string(),
+                format: evidence.format.to_string(),
+            };
+
+            Result::Ok(Response::new(reply))
+        }
+
+        async fn extend_runtime_measurement(
+            &self,
+            request: Request<ExtendRuntimeMeasurementRequest>,
+        ) -> Result<Response<ExtendRuntimeMeasurementResponse>, Status> {
+            let request = request.into_inner();
+
+            let attestation_agent_mutex_clone = Arc::clone(&ASYNC_ATTESTATION_AGENT);
+            let mut attestation_agent = attestation_agent_mutex_clone.lock().await;
+
+            debug!("Call AA to extend runtime measurement ...");
+
+            attestation_agent
+                .extend_runtime_measurement(
+                    &request.domain,
+                    &request.operation,
+                    &request.content,
+                    request.register_index,
+                )
+                .await
+                .map_err(|e| {
+                    error!("Call AA to extend runtime measurement failed: {}", e);
+                    Status::internal(format!(
+                        "[ERROR:{}] AA extend runtime measurement failed: {}",
+                        AGENT_NAME, e
+                    ))
+                })?;
+
+            debug!("Extend runtime measurement successfully!");
+
+            let reply = ExtendRuntimeMeasurementResponse {};
+
+            Result::Ok(Response::new(reply))
+        }
     }
 
     pub async fn start_grpc_service(socket: SocketAddr) -> Result<()> {
@@ -115,16 +232,22 @@ pub mod ttrpc {
     impl attestation_agent_ttrpc::AttestationAgentService for Attestation {
         async fn get_token(
             &self,
-            _ctx: &::ttrpc::r#async::TtrpcContext,
+            ctx: &::ttrpc::r#async::TtrpcContext,
             req: attestation_agent::GetTokenRequest,
         ) -> ::ttrpc::Result<attestation_agent::GetTokenResponse> {
+            self.policy.authorize_ttrpc(ctx)?;
             debug!("Call AA to get token ...");
 
             let attestation_agent_mutex_clone = ASYNC_ATTESTATION_AGENT.clone();
             let mut attestation_agent = attestation_agent_mutex_clone.lock().await;
 
             let token = attestation_agent
-                .get_token(&req.TokenType)
+                .get_token_for_audience(
+                    &req.TokenType,
+                    non_empty(&req.Audience),
+                    non_empty(&req.Nonce),
+                    (!req.RuntimeData.is_empty()).then_some(&req.RuntimeData[..]),
+                )
                 .await
                 .map_err(|e| {
                     error!("Call AA-KBC to get token failed: {}", e);
@@ -147,9 +270,10 @@ pub mod ttrpc {
 
         async fn get_evidence(
             &self,
-            _ctx: &::ttrpc::r#async::TtrpcContext,
+            ctx: &::ttrpc::r#async::TtrpcContext,
             req: attestation_agent::GetEvidenceRequest,
         ) -> ::ttrpc::Result<attestation_agent::GetEvidenceResponse> {
+            self.policy.authorize_ttrpc(ctx)?;
             debug!("Call AA to get evidence ...");
 
             let attestation_agent_mutex_clone = ASYNC_ATTESTATION_AGENT.clone();
@@ -176,10 +300,93 @@ pub mod ttrpc {
 
             ::ttrpc::Result::Ok(reply)
         }
+
+        async fn get_evidence_with_nonce(
+            &self,
+            ctx: &::ttrpc::r#async::TtrpcContext,
+            req: attestation_agent::GetEvidenceWithNonceRequest,
+        ) -> ::ttrpc::Result<attestation_agent::GetEvidenceWithNonceResponse> {
+            self.policy.authorize_ttrpc(ctx)?;
+            debug!("Call AA to get evidence with nonce ...");
+
+            let invalid_argument = |e: anyhow::Error| {
+                let mut error_status = ::ttrpc::proto::Status::new();
+                error_status.set_code(Code::INVALID_ARGUMENT);
+                error_status.set_message(format!("[ERROR:{}] {}", AGENT_NAME, e));
+                ::ttrpc::Error::RpcStatus(error_status)
+            };
+            let hash_algorithm = hash_algorithm(&req.HashAlgorithm).map_err(invalid_argument)?;
+            let format = evidence_format(&req.Format).map_err(invalid_argument)?;
+
+            let attestation_agent_mutex_clone = ASYNC_ATTESTATION_AGENT.clone();
+            let mut attestation_agent = attestation_agent_mutex_clone.lock().await;
+
+            let evidence = attestation_agent
+                .get_evidence_with_nonce(&req.Nonce, hash_algorithm, format)
+                .await
+                .map_err(|e| {
+                    error!("Call AA-KBC to get evidence with nonce failed: {}", e);
+                    let mut error_status = ::ttrpc::proto::Status::new();
+                    error_status.set_code(Code::INTERNAL);
+                    error_status.set_message(format!(
+                        "[ERROR:{}] AA-KBC get evidence with nonce failed: {}",
+                        AGENT_NAME, e
+                    ));
+                    ::ttrpc::Error::RpcStatus(error_status)
+                })?;
+
+            debug!("Get evidence with nonce successfully!");
+
+            let mut reply = attestation_agent::GetEvidenceWithNonceResponse::new();
+            reply.Evidence = evidence.evidence;
+            reply.Tee = evidence.tee;
+            reply.HashAlgorithm = evidence.hash_algorithm.to_string();
+            reply.Format = evidence.format.to_string();
+
+            ::ttrpc::Result::Ok(reply)
+        }
+
+        async fn extend_runtime_measurement(
+            &self,
+            ctx: &::ttrpc::r#async::TtrpcContext,
+            req: attestation_agent::ExtendRuntimeMeasurementRequest,
+        ) -> ::ttrpc::Result<attestation_agent::ExtendRuntimeMeasurementResponse> {
+            self.policy.authorize_ttrpc(ctx)?;
+            debug!("Call AA to extend runtime measurement ...");
+
+            let attestation_agent_mutex_clone = ASYNC_ATTESTATION_AGENT.clone();
+            let mut attestation_agent = attestation_agent_mutex_clone.lock().await;
+
+            attestation_agent
+                .extend_runtime_measurement(
+                    &req.Domain,
+                    &req.Operation,
+                    &req.Content,
+                    req.RegisterIndex,
+                )
+                .await
+                .map_err(|e| {
+                    error!("Call AA to extend runtime measurement failed: {}", e);
+                    let mut error_status = ::ttrpc::proto::Status::new();
+                    error_status.set_code(Code::INTERNAL);
+                    error_status.set_message(format!(
+                        "[ERROR:{}] AA extend runtime measurement failed: {}",
+                        AGENT_NAME, e
+                    ));
+                    ::ttrpc::Error::RpcStatus(error_status)
+                })?;
+
+            debug!("Extend runtime measurement successfully!");
+
+            let reply = attestation_agent::ExtendRuntimeMeasurementResponse::new();
+
+            ::ttrpc::Result::Ok(reply)
+        }
     }
 
-    pub fn start_ttrpc_service() -> Result<HashMap<String, Service>> {
-        let service = Box::new(Attestation {}) as Box<dyn AttestationAgentService + Send + Sync>;
+    pub fn start_ttrpc_service(policy: Arc<PeerPolicy>) -> Result<HashMap<String, Service>> {
+        let service =
+            Box::new(Attestation { policy }) as Box<dyn AttestationAgentService + Send + Sync>;
 
         let service = Arc::new(service);
         let get_resource_service = create_attestation_agent_service(service);
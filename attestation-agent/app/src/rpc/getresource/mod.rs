@@ -7,10 +7,15 @@ use attestation_agent::AttestationAPIs;
 use log::*;
 use std::sync::Arc;
 
+#[cfg(feature = "ttrpc")]
+use crate::rpc::peer::PeerPolicy;
 use crate::rpc::AGENT_NAME;
 
 #[derive(Debug, Default)]
-pub struct GetResource {}
+pub struct GetResource {
+    #[cfg(feature = "ttrpc")]
+    policy: Arc<PeerPolicy>,
+}
 
 #[cfg(feature = "grpc")]
 pub mod grpc {
@@ -93,9 +98,10 @@ pub mod ttrpc {
     impl getresource_ttrpc::GetResourceService for GetResource {
         async fn get_resource(
             &self,
-            _ctx: &::ttrpc::r#async::TtrpcContext,
+            ctx: &::ttrpc::r#async::TtrpcContext,
             req: getresource::GetResourceRequest,
         ) -> ::ttrpc::Result<getresource::GetResourceResponse> {
+            self.policy.authorize_ttrpc(ctx)?;
             debug!("Call AA-KBC to download resource ...");
 
             let attestation_agent_mutex_clone = ASYNC_ATTESTATION_AGENT.clone();
@@ -124,8 +130,8 @@ pub mod ttrpc {
         }
     }
 
-    pub fn start_ttrpc_service() -> Result<HashMap<String, Service>> {
-        let service = Box::new(GetResource {}) as Box<dyn GetResourceService + Send + Sync>;
+    pub fn start_ttrpc_service(policy: Arc<PeerPolicy>) -> Result<HashMap<String, Service>> {
+        let service = Box::new(GetResource { policy }) as Box<dyn GetResourceService + Send + Sync>;
 
         let service = Arc::new(service);
         let get_resource_service = create_get_resource_service(service);
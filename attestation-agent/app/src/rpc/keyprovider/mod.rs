@@ -12,6 +12,8 @@ use std::convert::TryFrom;
 use std::str;
 use std::sync::Arc;
 
+#[cfg(feature = "ttrpc")]
+use crate::rpc::peer::PeerPolicy;
 use crate::rpc::AGENT_NAME;
 use message::*;
 
@@ -28,7 +30,10 @@ const ERR_WRONG_DC_PARAM: &str = "Dc parameter not destined for agent";
 const KBC_KBS_PAIR_SEP: &str = "::";
 
 #[derive(Debug, Default)]
-pub struct KeyProvider {}
+pub struct KeyProvider {
+    #[cfg(feature = "ttrpc")]
+    policy: Arc<PeerPolicy>,
+}
 
 impl TryFrom<Vec<u8>> for InputPayload {
     type Error = anyhow::Error;
@@ -159,9 +164,10 @@ pub mod ttrpc {
     impl keyprovider_ttrpc::KeyProviderService for KeyProvider {
         async fn un_wrap_key(
             &self,
-            _ctx: &::ttrpc::r#async::TtrpcContext,
+            ctx: &::ttrpc::r#async::TtrpcContext,
             req: keyprovider::KeyProviderKeyWrapProtocolInput,
         ) -> ::ttrpc::Result<keyprovider::KeyProviderKeyWrapProtocolOutput> {
+            self.policy.authorize_ttrpc(ctx)?;
             debug!("The UnWrapKey API is called...");
 
             // Deserialize and parse the gRPC input to get KBC name, KBS URI and annotation.
@@ -238,9 +244,10 @@ pub mod ttrpc {
 
         async fn wrap_key(
             &self,
-            _ctx: &::ttrpc::r#async::TtrpcContext,
+            ctx: &::ttrpc::r#async::TtrpcContext,
             _req: keyprovider::KeyProviderKeyWrapProtocolInput,
         ) -> ::ttrpc::Result<keyprovider::KeyProviderKeyWrapProtocolOutput> {
+            self.policy.authorize_ttrpc(ctx)?;
             debug!("The WrapKey API is called...");
             debug!("WrapKey API is unimplemented!");
             let mut error_status = ::ttrpc::proto::Status::new();
@@ -250,8 +257,8 @@ pub mod ttrpc {
         }
     }
 
-    pub fn start_ttrpc_service() -> Result<HashMap<String, Service>> {
-        let service = Box::new(KeyProvider {}) as Box<dyn KeyProviderService + Send + Sync>;
+    pub fn start_ttrpc_service(policy: Arc<PeerPolicy>) -> Result<HashMap<String, Service>> {
+        let service = Box::new(KeyProvider { policy }) as Box<dyn KeyProviderService + Send + Sync>;
         let service = Arc::new(service);
 
         let key_provider_service = create_key_provider_service(service);
@@ -7,6 +7,9 @@ pub mod attestation;
 pub mod getresource;
 pub mod keyprovider;
 
+#[cfg(feature = "ttrpc")]
+pub mod peer;
+
 #[cfg(feature = "ttrpc")]
 pub mod ttrpc_protocol;
 
@@ -0,0 +1,114 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Authorization of the callers of the ttRPC services by the credentials of
+//! the peer processes of the unix sockets, so that an arbitrary process
+//! inside the guest cannot get the keys, resources and tokens from AA.
+
+use ::ttrpc::{proto::Code, r#async::TtrpcContext};
+use anyhow::{bail, Result};
+use log::warn;
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials as PeerCred};
+use std::os::unix::io::RawFd;
+
+use crate::rpc::AGENT_NAME;
+
+/// The credentials of the peer process of a unix socket connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub pid: i32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl PeerCredentials {
+    /// Get the credentials of the peer of the unix socket `fd` by
+    /// `SO_PEERCRED`.
+    pub fn from_fd(fd: RawFd) -> nix::Result<Self> {
+        let cred = getsockopt(fd, PeerCred)?;
+        Ok(Self {
+            pid: cred.pid(),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        })
+    }
+}
+
+/// The peers allowed to call the services. A peer is allowed if its uid or
+/// its gid is in the allowlists, and any peer is allowed if both are empty.
+#[derive(Debug, Default)]
+pub struct PeerPolicy {
+    uids: Vec<u32>,
+    gids: Vec<u32>,
+}
+
+impl PeerPolicy {
+    pub fn new(uids: Vec<u32>, gids: Vec<u32>) -> Self {
+        Self { uids, gids }
+    }
+
+    /// Check whether the `peer` is allowed to call the services. A peer
+    /// whose credentials are unknown is only allowed if any peer is allowed.
+    pub fn authorize(&self, peer: Option<PeerCredentials>) -> Result<()> {
+        if self.uids.is_empty() && self.gids.is_empty() {
+            return Ok(());
+        }
+
+        match peer {
+            Some(peer) if self.uids.contains(&peer.uid) || self.gids.contains(&peer.gid) => Ok(()),
+            Some(peer) => {
+                warn!("reject the request of unauthorized peer {peer:?}");
+                bail!(
+                    "peer of uid {} and gid {} is not authorized",
+                    peer.uid,
+                    peer.gid
+                )
+            }
+            None => {
+                warn!("reject the request of the peer without credentials");
+                bail!("peer without credentials is not authorized")
+            }
+        }
+    }
+
+    /// Check whether the peer of the ttRPC connection `ctx` is allowed to
+    /// call the services.
+    pub fn authorize_ttrpc(&self, ctx: &TtrpcContext) -> ::ttrpc::Result<()> {
+        let peer = PeerCredentials::from_fd(ctx.fd).ok();
+        self.authorize(peer).map_err(|e| {
+            let mut error_status = ::ttrpc::proto::Status::new();
+            error_status.set_code(Code::PERMISSION_DENIED);
+            error_status.set_message(format!("[ERROR:{}] {}", AGENT_NAME, e));
+            ::ttrpc::Error::RpcStatus(error_status)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorize() {
+        let peer = PeerCredentials {
+            pid: 100,
+            uid: 1000,
+            gid: 2000,
+        };
+
+        assert!(PeerPolicy::default().authorize(Some(peer)).is_ok());
+        assert!(PeerPolicy::default().authorize(None).is_ok());
+        assert!(PeerPolicy::new(vec![0, 1000], vec![])
+            .authorize(Some(peer))
+            .is_ok());
+        assert!(PeerPolicy::new(vec![], vec![2000])
+            .authorize(Some(peer))
+            .is_ok());
+        assert!(PeerPolicy::new(vec![0], vec![0])
+            .authorize(Some(peer))
+            .is_err());
+        assert!(PeerPolicy::new(vec![1000], vec![]).authorize(None).is_err());
+    }
+}
@@ -7,6 +7,7 @@ use super::*;
 use ::ttrpc::asynchronous::Server;
 use clap::{arg, command, Parser};
 use const_format::concatcp;
+use crate::rpc::peer::PeerPolicy;
 use std::path::Path;
 use tokio::{
     signal::unix::{signal, SignalKind},
@@ -65,6 +66,23 @@ struct Cli {
     /// `--attestation_sock unix:///tmp/attestation`
     #[arg(default_value_t = DEFAULT_ATTESTATION_SOCKET_ADDR.to_string(), short, long = "attestation_sock")]
     attestation_sock: String,
+
+    /// Uids of the processes allowed to call the ttRPC services.
+    ///
+    /// The credentials of the callers are got from the unix sockets by
+    /// `SO_PEERCRED`. A caller is allowed if its uid is in `--allowed_uids`
+    /// or its gid is in `--allowed_gids`. Any caller is allowed if neither
+    /// is given, for example:
+    ///
+    /// `--allowed_uids 0,1000`
+    #[arg(long = "allowed_uids", value_delimiter = ',')]
+    allowed_uids: Vec<u32>,
+
+    /// Gids of the processes allowed to call the ttRPC services, for example:
+    ///
+    /// `--allowed_gids 0`
+    #[arg(long = "allowed_gids", value_delimiter = ',')]
+    allowed_gids: Vec<u32>,
 }
 
 pub async fn ttrpc_main() -> Result<()> {
@@ -81,9 +99,10 @@ pub async fn ttrpc_main() -> Result<()> {
     clean_previous_sock_file(&cli.attestation_sock)
         .context("clean previous attestation socket file")?;
 
-    let kp = rpc::keyprovider::ttrpc::start_ttrpc_service()?;
-    let gs = rpc::getresource::ttrpc::start_ttrpc_service()?;
-    let att = rpc::attestation::ttrpc::start_ttrpc_service()?;
+    let policy = Arc::new(PeerPolicy::new(cli.allowed_uids, cli.allowed_gids));
+    let kp = rpc::keyprovider::ttrpc::start_ttrpc_service(policy.clone())?;
+    let gs = rpc::getresource::ttrpc::start_ttrpc_service(policy.clone())?;
+    let att = rpc::attestation::ttrpc::start_ttrpc_service(policy)?;
 
     let mut kps = Server::new()
         .bind(&cli.getresource_sock)
@@ -0,0 +1,98 @@
+// Copyright (c) 2023 Arm Ltd.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use super::Attester;
+use anyhow::*;
+use nix::fcntl::{open, OFlag};
+use nix::sys::stat::Mode;
+use nix::unistd::close;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The realm interface exposed by the CCA guest driver.
+const CCA_DEVICE_PATH: &str = "/dev/cca_attestation";
+
+/// The challenge of the CCA token, which binds the report data.
+const CCA_CHALLENGE_SIZE: usize = 64;
+
+/// The max size of the CCA token returned by the RMM.
+const CCA_TOKEN_MAX_SIZE: usize = 4096;
+
+pub fn detect_platform() -> bool {
+    Path::new(CCA_DEVICE_PATH).exists()
+}
+
+#[derive(Debug, Default)]
+pub struct CcaAttester {}
+
+/// The CCA token is a CBOR collection of the platform token and the realm
+/// token. The realm claims, i.e. the Realm Initial Measurement, the Realm
+/// Extensible Measurements, the personalization value and the challenge,
+/// are mapped and verified by the Attestation Service.
+#[derive(Serialize, Deserialize)]
+struct CcaEvidence {
+    token: Vec<u8>,
+}
+
+#[repr(C)]
+struct CcaIoctlRequest {
+    challenge: [u8; CCA_CHALLENGE_SIZE],
+    token: [u8; CCA_TOKEN_MAX_SIZE],
+    token_length: u64,
+}
+
+nix::ioctl_readwrite!(cca_attestation_request, b'A', 1, CcaIoctlRequest);
+
+#[async_trait::async_trait]
+impl Attester for CcaAttester {
+    async fn get_evidence(&self, mut report_data: Vec<u8>) -> Result<String> {
+        if report_data.len() > CCA_CHALLENGE_SIZE {
+            bail!("CCA Attester: Report data must be no more than {CCA_CHALLENGE_SIZE} bytes");
+        }
+
+        report_data.resize(CCA_CHALLENGE_SIZE, 0);
+        let token = get_token(&report_data)?;
+        let evidence = CcaEvidence { token };
+
+        serde_json::to_string(&evidence).context("Serialize CCA evidence failed")
+    }
+}
+
+fn get_token(challenge: &[u8]) -> Result<Vec<u8>> {
+    let mut request = CcaIoctlRequest {
+        challenge: challenge.try_into()?,
+        token: [0; CCA_TOKEN_MAX_SIZE],
+        token_length: 0,
+    };
+
+    let fd = open(CCA_DEVICE_PATH, OFlag::O_RDWR, Mode::empty())
+        .context("Failed to open CCA attestation device")?;
+    // SAFETY: `request` is a valid `CcaIoctlRequest` which outlives the call.
+    let res = unsafe { cca_attestation_request(fd, &mut request) };
+    close(fd).context("Failed to close CCA attestation device")?;
+    res.context("Failed to get CCA token")?;
+
+    let length = request.token_length as usize;
+    if length > CCA_TOKEN_MAX_SIZE {
+        bail!("CCA Attester: illegal token length {length}");
+    }
+
+    Ok(request.token[..length].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_cca_get_evidence() {
+        let attester = CcaAttester::default();
+        let report_data: Vec<u8> = vec![0; 48];
+
+        let evidence = attester.get_evidence(report_data).await;
+        assert!(evidence.is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use super::{Attester, BoxedAttester};
+use anyhow::*;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kbs_types::Tee;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, path::Path, str::FromStr};
+use strum::{AsRefStr, EnumString};
+
+// If the environment variable "AA_RUNTIME_MEASUREMENTS" is set, e.g. to
+// "tpm_event_log,ima", the runtime measurements in it are bundled together
+// with the TEE evidence into a composite evidence.
+pub const RUNTIME_MEASUREMENTS_ENV: &str = "AA_RUNTIME_MEASUREMENTS";
+
+/// The sysfs directory of the PCR banks of the (v)TPM.
+const TPM_SYSFS_DIR: &str = "/sys/class/tpm/tpm0";
+
+/// The PCR banks tried to read a PCR value from, in order.
+const PCR_BANKS: [&str; 3] = ["sha256", "sha384", "sha1"];
+
+/// The runtime measurements which can be bundled with the TEE evidence.
+/// They are not signed by themselves, but replayed by the relying party
+/// against the registers they are extended to, e.g. the PCRs of a vTPM
+/// quote or the RTMRs of a TDX quote.
+#[derive(AsRefStr, EnumString, Clone, Copy, Debug, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum RuntimeMeasurement {
+    /// The binary event log of the measured boot of the (v)TPM.
+    TpmEventLog,
+
+    /// The runtime measurement list of the Linux Integrity Measurement
+    /// Architecture (IMA).
+    Ima,
+}
+
+impl RuntimeMeasurement {
+    fn path(&self) -> &'static str {
+        match self {
+            RuntimeMeasurement::TpmEventLog => "/sys/kernel/security/tpm0/binary_bios_measurements",
+            RuntimeMeasurement::Ima => "/sys/kernel/security/ima/ascii_runtime_measurements",
+        }
+    }
+
+    /// The PCR the measurements are extended to, whose current value is
+    /// attached to the evidence. The PCRs of the measured boot are covered
+    /// by the quote of the vTPM instead.
+    fn pcr(&self) -> Option<u64> {
+        match self {
+            RuntimeMeasurement::TpmEventLog => None,
+            RuntimeMeasurement::Ima => Some(10),
+        }
+    }
+}
+
+/// Read the value of the PCR `index` from the first available bank in
+/// [`PCR_BANKS`] under the sysfs `dir`, in format `<bank>:<hex digest>`.
+/// `None` is returned if there is no TPM, e.g. IMA is extended to an RTMR
+/// held by the TEE evidence itself.
+fn read_pcr(dir: &Path, index: u64) -> Option<String> {
+    PCR_BANKS.iter().find_map(|bank| {
+        let path = dir.join(format!("pcr-{bank}")).join(index.to_string());
+        let value = std::fs::read_to_string(path).ok()?;
+        Some(format!("{bank}:{}", value.trim().to_lowercase()))
+    })
+}
+
+/// Parse the comma separated list of [`RuntimeMeasurement`]s.
+pub fn parse_runtime_measurements(list: &str) -> Result<Vec<RuntimeMeasurement>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            RuntimeMeasurement::from_str(name)
+                .map_err(|_| anyhow!("Unknown runtime measurement {name}"))
+        })
+        .collect()
+}
+
+/// Get the runtime measurements set by [`RUNTIME_MEASUREMENTS_ENV`].
+pub fn runtime_measurements_from_env() -> Result<Vec<RuntimeMeasurement>> {
+    match env::var(RUNTIME_MEASUREMENTS_ENV) {
+        std::result::Result::Ok(list) => parse_runtime_measurements(&list),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompositeEvidence {
+    tee: Tee,
+    /// The evidence of the TEE attester, as it is.
+    tee_evidence: String,
+    /// The base64 encoded runtime measurements, by their names.
+    runtime_measurements: BTreeMap<String, String>,
+    /// The values of the PCRs the runtime measurements are extended to,
+    /// by the names of the PCRs, e.g. `pcr10`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pcrs: BTreeMap<String, String>,
+}
+
+/// An attester bundling the evidence of the TEE attester with the
+/// runtime measurements into one evidence document. The report data is
+/// only given to the TEE attester.
+pub struct CompositeAttester {
+    tee: Tee,
+    attester: BoxedAttester,
+    measurements: Vec<RuntimeMeasurement>,
+}
+
+impl CompositeAttester {
+    pub fn new(tee: Tee, measurements: Vec<RuntimeMeasurement>) -> Result<Self> {
+        let attester = tee.try_into()?;
+        Ok(Self {
+            tee,
+            attester,
+            measurements,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Attester for CompositeAttester {
+    async fn get_evidence(&self, report_data: Vec<u8>) -> Result<String> {
+        let tee_evidence = self.attester.get_evidence(report_data).await?;
+
+        let mut runtime_measurements = BTreeMap::new();
+        let mut pcrs = BTreeMap::new();
+        for measurement in &self.measurements {
+            let content = std::fs::read(measurement.path())
+                .with_context(|| format!("Failed to read {}", measurement.path()))?;
+            runtime_measurements.insert(measurement.as_ref().to_string(), STANDARD.encode(content));
+
+            // The PCR is read after the measurement list, so that it covers
+            // at least all the entries of the list.
+            if let Some(index) = measurement.pcr() {
+                if let Some(value) = read_pcr(Path::new(TPM_SYSFS_DIR), index) {
+                    pcrs.insert(format!("pcr{index}"), value);
+                }
+            }
+        }
+
+        let evidence = CompositeEvidence {
+            tee: self.tee,
+            tee_evidence,
+            runtime_measurements,
+            pcrs,
+        };
+
+        serde_json::to_string(&evidence).context("Serialize composite evidence failed")
+    }
+
+    async fn extend_runtime_measurement(
+        &self,
+        event_digest: Vec<u8>,
+        register_index: u64,
+    ) -> Result<()> {
+        self.attester
+            .extend_runtime_measurement(event_digest, register_index)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("", vec![])]
+    #[case("ima", vec![RuntimeMeasurement::Ima])]
+    #[case(
+        "tpm_event_log, ima",
+        vec![RuntimeMeasurement::TpmEventLog, RuntimeMeasurement::Ima]
+    )]
+    fn parse(#[case] list: &str, #[case] expected: Vec<RuntimeMeasurement>) {
+        assert_eq!(parse_runtime_measurements(list).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_unknown() {
+        assert!(parse_runtime_measurements("ima,pcr").is_err());
+    }
+
+    #[test]
+    fn pcr_banks() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_pcr(dir.path(), 10), None);
+
+        std::fs::create_dir(dir.path().join("pcr-sha1")).unwrap();
+        std::fs::write(dir.path().join("pcr-sha1/10"), "AB12\n").unwrap();
+        assert_eq!(read_pcr(dir.path(), 10), Some("sha1:ab12".into()));
+
+        std::fs::create_dir(dir.path().join("pcr-sha256")).unwrap();
+        std::fs::write(dir.path().join("pcr-sha256/10"), "CD34\n").unwrap();
+        assert_eq!(read_pcr(dir.path(), 10), Some("sha256:cd34".into()));
+    }
+
+    #[tokio::test]
+    async fn composite_sample() {
+        let attester = CompositeAttester::new(Tee::Sample, vec![]).unwrap();
+        let evidence = attester.get_evidence(vec![0; 32]).await.unwrap();
+        let evidence: CompositeEvidence = serde_json::from_str(&evidence).unwrap();
+
+        assert_eq!(evidence.tee, Tee::Sample);
+        assert!(evidence.runtime_measurements.is_empty());
+        assert!(evidence.pcrs.is_empty());
+        let sample = attester.attester.get_evidence(vec![0; 32]).await.unwrap();
+        assert_eq!(evidence.tee_evidence, sample);
+    }
+}
@@ -4,7 +4,7 @@
 //
 
 use super::Attester;
-use anyhow::{bail, Context, Ok, Result};
+use anyhow::{anyhow, bail, Context, Ok, Result};
 use codicon::Decoder;
 use csv_rs::{
     api::guest::{AttestationReport, CsvGuest},
@@ -46,15 +46,21 @@ impl Attester for CsvAttester {
         report_data.resize(64, 0);
 
         let data = report_data.as_slice().try_into()?;
-        let mut csv_guest = CsvGuest::open().unwrap();
+        let mut csv_guest =
+            CsvGuest::open().map_err(|e| anyhow!("Failed to open CSV guest device: {e:?}"))?;
 
-        let (attestation_report, report_signer) = csv_guest.get_report(Some(data), None).unwrap();
+        let (attestation_report, report_signer) = csv_guest
+            .get_report(Some(data), None)
+            .map_err(|e| anyhow!("Failed to get CSV attestation report: {e:?}"))?;
 
         let cert_data = download_hskcek_from_kds(&report_signer.sn).await?;
         let mut cert_data = &cert_data[..];
-        let hsk = ca::Certificate::decode(&mut cert_data, ()).unwrap();
-        let cek = csv::Certificate::decode(&mut cert_data, ()).unwrap();
-        let pek = csv::Certificate::decode(&mut &report_signer.pek_cert[..], ())?;
+        let hsk = ca::Certificate::decode(&mut cert_data, ())
+            .map_err(|e| anyhow!("Failed to decode HSK certificate: {e:?}"))?;
+        let cek = csv::Certificate::decode(&mut cert_data, ())
+            .map_err(|e| anyhow!("Failed to decode CEK certificate: {e:?}"))?;
+        let pek = csv::Certificate::decode(&mut &report_signer.pek_cert[..], ())
+            .map_err(|e| anyhow!("Failed to decode PEK certificate: {e:?}"))?;
 
         let evidence = CsvEvidence {
             attestation_report,
@@ -80,6 +86,12 @@ async fn download_hskcek_from_kds(sn: &[u8]) -> Result<Vec<u8>> {
         .body(hyper::Body::empty())?;
 
     let response = client.request(request).await?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to download HSK and CEK from KDS: {}",
+            response.status()
+        );
+    }
 
     let mut response_body = Vec::new();
     let mut response = response.into_body();
@@ -6,14 +6,28 @@
 use anyhow::*;
 use kbs_types::Tee;
 
+pub mod composite;
 pub mod sample;
 
 #[cfg(feature = "az-snp-vtpm-attester")]
 pub mod az_snp_vtpm;
 
+#[cfg(feature = "az-tdx-vtpm-attester")]
+pub mod az_tdx_vtpm;
+
+#[cfg(any(
+    feature = "az-snp-vtpm-attester",
+    feature = "az-tdx-vtpm-attester",
+    feature = "snp-attester"
+))]
+pub mod maa;
+
 #[cfg(feature = "tdx-attester")]
 pub mod tdx;
 
+#[cfg(any(feature = "tdx-attester", feature = "snp-attester"))]
+pub mod tsm_report;
+
 #[cfg(feature = "sgx-attester")]
 pub mod sgx_dcap;
 
@@ -23,6 +37,12 @@ pub mod snp;
 #[cfg(feature = "csv-attester")]
 pub mod csv;
 
+#[cfg(feature = "cca-attester")]
+pub mod cca;
+
+#[cfg(feature = "se-attester")]
+pub mod se;
+
 pub type BoxedAttester = Box<dyn Attester + Send + Sync>;
 
 impl TryFrom<Tee> for BoxedAttester {
@@ -37,10 +57,16 @@ impl TryFrom<Tee> for BoxedAttester {
             Tee::Sgx => Box::<sgx_dcap::SgxDcapAttester>::default(),
             #[cfg(feature = "az-snp-vtpm-attester")]
             Tee::AzSnpVtpm => Box::<az_snp_vtpm::AzSnpVtpmAttester>::default(),
+            #[cfg(feature = "az-tdx-vtpm-attester")]
+            Tee::AzTdxVtpm => Box::<az_tdx_vtpm::AzTdxVtpmAttester>::default(),
             #[cfg(feature = "snp-attester")]
             Tee::Snp => Box::<snp::SnpAttester>::default(),
             #[cfg(feature = "csv-attester")]
             Tee::Csv => Box::<csv::CsvAttester>::default(),
+            #[cfg(feature = "cca-attester")]
+            Tee::Cca => Box::<cca::CcaAttester>::default(),
+            #[cfg(feature = "se-attester")]
+            Tee::Se => Box::<se::SeAttester>::default(),
             _ => bail!("TEE is not supported!"),
         };
 
@@ -48,44 +74,161 @@ impl TryFrom<Tee> for BoxedAttester {
     }
 }
 
+/// Create the attester of the `tee`. If any runtime measurements are set by
+/// [`composite::RUNTIME_MEASUREMENTS_ENV`], they are bundled together with
+/// the evidence of the `tee` by a [`composite::CompositeAttester`].
+pub fn new_attester(tee: Tee) -> Result<BoxedAttester> {
+    let measurements = composite::runtime_measurements_from_env()?;
+    if measurements.is_empty() {
+        return tee.try_into();
+    }
+
+    let attester = composite::CompositeAttester::new(tee, measurements)?;
+    Ok(Box::new(attester))
+}
+
 #[async_trait::async_trait]
 pub trait Attester {
     /// Call the hardware driver to get the Hardware specific evidence.
     /// The parameter `report_data` will be used as the user input of the
     /// evidence to avoid reply attack.
     async fn get_evidence(&self, report_data: Vec<u8>) -> Result<String>;
-}
 
-// Detect which TEE platform the KBC running environment is.
-pub fn detect_tee_type() -> Option<Tee> {
-    if sample::detect_platform() {
-        return Some(Tee::Sample);
+    /// Extend the TEE specific dynamic measurement register with the
+    /// `event_digest`. The `register_index` is the index of the PCR, which
+    /// is mapped to the register of the TEE, e.g. an RTMR of TDX.
+    async fn extend_runtime_measurement(
+        &self,
+        _event_digest: Vec<u8>,
+        _register_index: u64,
+    ) -> Result<()> {
+        bail!("Unimplemented")
     }
+}
 
-    #[cfg(feature = "tdx-attester")]
-    if tdx::detect_platform() {
-        return Some(Tee::Tdx);
-    }
+// If the environment variable "AA_ATTESTER" is set, e.g. to "tdx", the TEE is
+// not detected, but the attester of it is used explicitly. The names are
+// those of `Tee` in the KBS protocol.
+pub const ATTESTER_ENV: &str = "AA_ATTESTER";
 
-    #[cfg(feature = "sgx-attester")]
-    if sgx_dcap::detect_platform() {
-        return Some(Tee::Sgx);
-    }
+/// A platform the TEE detection probes, with what is checked to detect it.
+struct Probe {
+    tee: Tee,
+    checked: &'static str,
+    detect: fn() -> bool,
+}
+
+/// The platforms built in, in the order they are probed. An Azure TDX CVM
+/// is probed before a plain TD, as the TD quote of it is got through the
+/// paravisor rather than the TDX guest driver.
+fn probes() -> Vec<Probe> {
+    vec![
+        Probe {
+            tee: Tee::Sample,
+            checked: "env AA_SAMPLE_ATTESTER_TEST",
+            detect: sample::detect_platform,
+        },
+        #[cfg(feature = "az-tdx-vtpm-attester")]
+        Probe {
+            tee: Tee::AzTdxVtpm,
+            checked: "TDX isolation of the Azure CVM by cpuid",
+            detect: az_tdx_vtpm::detect_platform,
+        },
+        #[cfg(feature = "tdx-attester")]
+        Probe {
+            tee: Tee::Tdx,
+            checked: "/dev/tdx-attest, /dev/tdx-guest",
+            detect: tdx::detect_platform,
+        },
+        #[cfg(feature = "sgx-attester")]
+        Probe {
+            tee: Tee::Sgx,
+            checked: "env OCCLUM, /dev/attestation/attestation_type",
+            detect: sgx_dcap::detect_platform,
+        },
+        #[cfg(feature = "az-snp-vtpm-attester")]
+        Probe {
+            tee: Tee::AzSnpVtpm,
+            checked: "HCL report in the vTPM NV",
+            detect: az_snp_vtpm::detect_platform,
+        },
+        #[cfg(feature = "snp-attester")]
+        Probe {
+            tee: Tee::Snp,
+            checked: "/sys/devices/platform/sev-guest",
+            detect: snp::detect_platform,
+        },
+        #[cfg(feature = "csv-attester")]
+        Probe {
+            tee: Tee::Csv,
+            checked: "/dev/csv-guest",
+            detect: csv::detect_platform,
+        },
+        #[cfg(feature = "cca-attester")]
+        Probe {
+            tee: Tee::Cca,
+            checked: "/dev/cca_attestation",
+            detect: cca::detect_platform,
+        },
+        #[cfg(feature = "se-attester")]
+        Probe {
+            tee: Tee::Se,
+            checked: "/dev/uv",
+            detect: se::detect_platform,
+        },
+    ]
+}
 
-    #[cfg(feature = "az-snp-vtpm-attester")]
-    if az_snp_vtpm::detect_platform() {
-        return Some(Tee::AzSnpVtpm);
+/// Parse the `name` of the TEE set by [`ATTESTER_ENV`], whose attester must
+/// be built in.
+fn parse_attester(name: &str) -> Result<Tee> {
+    let tee: Tee = serde_json::from_value(serde_json::Value::String(name.to_lowercase()))
+        .map_err(|_| anyhow!("Unknown TEE {name} in {ATTESTER_ENV}"))?;
+    if !probes().iter().any(|probe| probe.tee == tee) {
+        bail!("The attester of TEE {name} in {ATTESTER_ENV} is not built in");
     }
 
-    #[cfg(feature = "snp-attester")]
-    if snp::detect_platform() {
-        return Some(Tee::Snp);
+    Ok(tee)
+}
+
+/// Detect which TEE platform the KBC running environment is, or use the one
+/// set by [`ATTESTER_ENV`]. If no TEE is detected, the error reports the
+/// platforms probed and what is checked for each of them.
+pub fn detect_tee() -> Result<Tee> {
+    if let Result::Ok(name) = std::env::var(ATTESTER_ENV) {
+        return parse_attester(&name);
     }
 
-    #[cfg(feature = "csv-attester")]
-    if csv::detect_platform() {
-        return Some(Tee::Csv);
+    let probes = probes();
+    if let Some(probe) = probes.iter().find(|probe| (probe.detect)()) {
+        return Ok(probe.tee);
     }
 
-    None
+    let report = probes
+        .iter()
+        .map(|probe| format!("{:?} ({})", probe.tee, probe.checked))
+        .collect::<Vec<_>>()
+        .join(", ");
+    bail!("No supported TEE is detected. Probed: {report}. Set {ATTESTER_ENV} to choose one")
+}
+
+// Detect which TEE platform the KBC running environment is.
+pub fn detect_tee_type() -> Option<Tee> {
+    detect_tee().map_err(|e| log::debug!("{e}")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use kbs_types::Tee;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("sample", Some(Tee::Sample))]
+    #[case("Sample", Some(Tee::Sample))]
+    #[case("sev", None)]
+    #[case("unknown", None)]
+    #[case("", None)]
+    fn parse_attester(#[case] name: &str, #[case] expected: Option<Tee>) {
+        assert_eq!(super::parse_attester(name).ok(), expected);
+    }
 }
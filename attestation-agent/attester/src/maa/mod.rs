@@ -0,0 +1,191 @@
+// Copyright (c) 2023 Microsoft Corporation
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Requests of the Microsoft Azure Attestation (MAA) service, which issues
+//! JWTs trusted by the Azure services, e.g. the Secure Key Release of Azure
+//! Key Vault.
+
+use anyhow::*;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use kbs_types::Tee;
+use serde_json::{json, Value};
+#[cfg(feature = "snp-attester")]
+use sev::firmware::{
+    guest::{AttestationReport, Firmware},
+    host::{CertTableEntry, CertType},
+};
+#[cfg(feature = "snp-attester")]
+use sha2::{Digest, Sha256};
+
+/// Size of the attestation header of the HCL report.
+#[cfg(any(feature = "az-snp-vtpm-attester", feature = "az-tdx-vtpm-attester"))]
+const HCL_HEADER_SIZE: usize = 32;
+
+/// Sizes of the hardware reports inside the HCL report.
+#[cfg(feature = "az-snp-vtpm-attester")]
+const SNP_REPORT_SIZE: usize = 1184;
+#[cfg(feature = "az-tdx-vtpm-attester")]
+const TD_REPORT_SIZE: usize = 1024;
+
+/// Size of the header of the IGVM request data following the hardware
+/// report, whose last field is the size of the variable data.
+#[cfg(any(feature = "az-snp-vtpm-attester", feature = "az-tdx-vtpm-attester"))]
+const IGVM_REQUEST_DATA_HEADER_SIZE: usize = 20;
+
+/// Split the HCL `report` into the hardware report of `hw_report_size` and
+/// the variable data, i.e. the runtime claims of the guest holding the
+/// vTPM attestation key, whose digest is the report data of the hardware
+/// report.
+#[cfg(any(feature = "az-snp-vtpm-attester", feature = "az-tdx-vtpm-attester"))]
+fn split_hcl_report(report: &[u8], hw_report_size: usize) -> Result<(&[u8], &[u8])> {
+    let hw_report_end = HCL_HEADER_SIZE + hw_report_size;
+    let var_data_start = hw_report_end + IGVM_REQUEST_DATA_HEADER_SIZE;
+    if report.len() < var_data_start {
+        bail!("HCL report is too short");
+    }
+
+    let size_field = &report[var_data_start - 4..var_data_start];
+    let var_data_size = u32::from_le_bytes(size_field.try_into()?) as usize;
+    let var_data = report
+        .get(var_data_start..var_data_start + var_data_size)
+        .ok_or_else(|| anyhow!("HCL report is too short for the variable data"))?;
+
+    Ok((&report[HCL_HEADER_SIZE..hw_report_end], var_data))
+}
+
+fn runtime_data_field(data: &[u8]) -> Value {
+    json!({
+        "data": URL_SAFE_NO_PAD.encode(data),
+        "dataType": "JSON",
+    })
+}
+
+/// Build the attestation request of the `tee` to MAA. Return the path of
+/// the MAA endpoint, e.g. `attest/SevSnpVm`, and the json body.
+///
+/// `runtime_data` is the json of the claims to bind to the token, e.g. the
+/// public key the released keys of Azure Key Vault are wrapped by, which
+/// MAA issues as `x-ms-runtime`. They can only be set on SEV-SNP guests
+/// owning the report data, while the runtime claims on the Azure CVMs are
+/// those of the vTPM.
+pub fn attestation_request(tee: Tee, runtime_data: Option<&[u8]>) -> Result<(&'static str, Value)> {
+    if runtime_data.is_some() && tee != Tee::Snp {
+        bail!("Runtime data of MAA tokens is not supported on TEE {tee:?}");
+    }
+
+    match tee {
+        #[cfg(feature = "snp-attester")]
+        Tee::Snp => {
+            let data = runtime_data.unwrap_or(b"{}");
+            let mut report_data = Sha256::digest(data).to_vec();
+            report_data.resize(64, 0);
+
+            let mut firmware = Firmware::open()?;
+            let (report, certs) = firmware
+                .get_ext_report(None, Some(report_data.as_slice().try_into()?), Some(0))
+                .context("Failed to get attestation report")?;
+
+            // SAFETY: `AttestationReport` is a `repr(C)` struct of the raw
+            // report got from the firmware.
+            let snp_report = unsafe {
+                std::slice::from_raw_parts(
+                    &report as *const AttestationReport as *const u8,
+                    std::mem::size_of::<AttestationReport>(),
+                )
+            };
+            let report = json!({
+                "SnpReport": URL_SAFE_NO_PAD.encode(snp_report),
+                "VcekCertChain": URL_SAFE_NO_PAD.encode(pem_cert_chain(&certs)?),
+            });
+
+            let body = json!({
+                "report": URL_SAFE_NO_PAD.encode(report.to_string()),
+                "runtimeData": runtime_data_field(data),
+            });
+            Ok(("attest/SevSnpVm", body))
+        }
+        #[cfg(feature = "az-snp-vtpm-attester")]
+        Tee::AzSnpVtpm => {
+            let report = az_snp_vtpm::vtpm::get_report()?;
+            let (snp_report, var_data) = split_hcl_report(&report, SNP_REPORT_SIZE)?;
+            let certs = az_snp_vtpm::imds::get_certs()?;
+            let cert_chain = format!("{}{}", certs.vcek, certs.amd_chain);
+            let report = json!({
+                "SnpReport": URL_SAFE_NO_PAD.encode(snp_report),
+                "VcekCertChain": URL_SAFE_NO_PAD.encode(cert_chain),
+            });
+
+            let body = json!({
+                "report": URL_SAFE_NO_PAD.encode(report.to_string()),
+                "runtimeData": runtime_data_field(var_data),
+            });
+            Ok(("attest/SevSnpVm", body))
+        }
+        #[cfg(feature = "az-tdx-vtpm-attester")]
+        Tee::AzTdxVtpm => {
+            let report = az_tdx_vtpm::vtpm::get_report()?;
+            let (_, var_data) = split_hcl_report(&report, TD_REPORT_SIZE)?;
+            let hcl_report = az_tdx_vtpm::hcl::HclReport::new(report.clone())?;
+            let td_report = hcl_report.try_into()?;
+            let td_quote = az_tdx_vtpm::imds::get_td_quote(&td_report)?;
+
+            let body = json!({
+                "quote": URL_SAFE_NO_PAD.encode(td_quote),
+                "runtimeData": runtime_data_field(var_data),
+            });
+            Ok(("attest/TdxVm", body))
+        }
+        tee => bail!("MAA does not support TEE {tee:?}"),
+    }
+}
+
+/// PEM chain of the VCEK, ASK and ARK certificates in the ext report.
+#[cfg(feature = "snp-attester")]
+fn pem_cert_chain(certs: &[CertTableEntry]) -> Result<String> {
+    let mut chain = String::new();
+    for cert_type in [CertType::VCEK, CertType::ASK, CertType::ARK] {
+        let cert = certs
+            .iter()
+            .find(|cert| cert.cert_type == cert_type)
+            .ok_or_else(|| anyhow!("{cert_type:?} certificate is not in the ext report"))?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&cert.data);
+        chain.push_str("-----BEGIN CERTIFICATE-----\n");
+        // `encoded` is ascii, so that each chunk is valid utf-8.
+        for line in encoded.as_bytes().chunks(64) {
+            chain.push_str(std::str::from_utf8(line)?);
+            chain.push('\n');
+        }
+        chain.push_str("-----END CERTIFICATE-----\n");
+    }
+
+    Ok(chain)
+}
+
+#[cfg(all(
+    test,
+    any(feature = "az-snp-vtpm-attester", feature = "az-tdx-vtpm-attester")
+))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_report() {
+        let hw_report = [0xaa; 8];
+        let var_data = br#"{"keys":[]}"#;
+        let mut report = vec![0; HCL_HEADER_SIZE];
+        report.extend_from_slice(&hw_report);
+        report.extend_from_slice(&[0; IGVM_REQUEST_DATA_HEADER_SIZE - 4]);
+        report.extend_from_slice(&(var_data.len() as u32).to_le_bytes());
+        report.extend_from_slice(var_data);
+        report.extend_from_slice(&[0; 16]);
+
+        let (hw, var) = split_hcl_report(&report, hw_report.len()).unwrap();
+        assert_eq!(hw, hw_report);
+        assert_eq!(var, var_data);
+
+        assert!(split_hcl_report(&report[..report.len() - 20], hw_report.len()).is_err());
+        assert!(split_hcl_report(&report[..40], hw_report.len()).is_err());
+    }
+}
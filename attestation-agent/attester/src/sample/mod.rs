@@ -35,4 +35,14 @@ impl Attester for SampleAttester {
 
         serde_json::to_string(&evidence).map_err(|_| anyhow!("Serialize sample evidence failed"))
     }
+
+    async fn extend_runtime_measurement(
+        &self,
+        _event_digest: Vec<u8>,
+        _register_index: u64,
+    ) -> Result<()> {
+        // The sample TEE has no measurement register, the event is only
+        // recorded in the eventlog.
+        Ok(())
+    }
 }
@@ -0,0 +1,212 @@
+// Copyright (c) 2023 IBM Corporation
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use super::Attester;
+use anyhow::*;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use nix::fcntl::{open, OFlag};
+use nix::sys::stat::Mode;
+use nix::unistd::close;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The Ultravisor device of a Secure Execution guest.
+const UV_DEVICE_PATH: &str = "/dev/uv";
+
+/// The limits of the attestation buffers, see `asm/uvdevice.h` of the kernel.
+const UVIO_ATT_ARCB_MAX_LEN: usize = 0x100000;
+const UVIO_ATT_MEASUREMENT_MAX_LEN: usize = 0x8000;
+const UVIO_ATT_ADDITIONAL_MAX_LEN: usize = 0x8000;
+const UVIO_ATT_USER_DATA_LEN: usize = 0x100;
+const UVIO_ATT_UID_LEN: usize = 0x10;
+
+/// The Ultravisor return code of a successful call.
+const UVC_RC_EXECUTED: u16 = 0x0001;
+
+pub fn detect_platform() -> bool {
+    Path::new(UV_DEVICE_PATH).exists()
+}
+
+/// The attestation request generated by the verifier, which is the nonce of
+/// the KBS challenge. The Attestation Request Control Block (ARCB) is
+/// encrypted for the host keys of the machines the guest is allowed to run
+/// on, so it cannot be created by the guest itself. The `user_data` is set
+/// by the KBS client to bind the tee key. All the binary fields are base64
+/// encoded.
+#[derive(Serialize, Deserialize)]
+struct SeAttestationRequest {
+    request_blob: String,
+    measurement_size: u32,
+    additional_size: u32,
+    #[serde(default)]
+    user_data: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SeEvidence {
+    request_blob: String,
+    measurement: String,
+    additional_data: String,
+    user_data: String,
+    config_uid: String,
+}
+
+#[repr(C)]
+struct UvioIoctlCb {
+    flags: u32,
+    uv_rc: u16,
+    uv_rrc: u16,
+    argument_addr: u64,
+    argument_len: u32,
+    reserved14: [u8; 0x40 - 0x14],
+}
+
+#[repr(C)]
+struct UvioAttest {
+    arcb_addr: u64,
+    meas_addr: u64,
+    add_data_addr: u64,
+    user_data: [u8; UVIO_ATT_USER_DATA_LEN],
+    config_uid: [u8; UVIO_ATT_UID_LEN],
+    arcb_len: u32,
+    meas_len: u32,
+    add_data_len: u32,
+    user_data_len: u16,
+    reserved136: u16,
+}
+
+nix::ioctl_readwrite!(uvio_ioctl_att, b'u', 1, UvioIoctlCb);
+
+#[derive(Debug, Default)]
+pub struct SeAttester {}
+
+#[async_trait::async_trait]
+impl Attester for SeAttester {
+    /// The `report_data` of SE is the json [`SeAttestationRequest`] given
+    /// by the verifier, whose nonce is inside the encrypted ARCB.
+    async fn get_evidence(&self, report_data: Vec<u8>) -> Result<String> {
+        let request: SeAttestationRequest = serde_json::from_slice(&report_data)
+            .context("SE Attester: illegal attestation request")?;
+        let request_blob = STANDARD.decode(&request.request_blob)?;
+        let user_data = STANDARD.decode(&request.user_data)?;
+
+        let (measurement, additional_data, config_uid) = attest(
+            &request_blob,
+            &user_data,
+            request.measurement_size as usize,
+            request.additional_size as usize,
+        )?;
+
+        let evidence = SeEvidence {
+            request_blob: request.request_blob,
+            measurement: STANDARD.encode(measurement),
+            additional_data: STANDARD.encode(additional_data),
+            user_data: request.user_data,
+            config_uid: STANDARD.encode(config_uid),
+        };
+
+        serde_json::to_string(&evidence).context("Serialize SE evidence failed")
+    }
+}
+
+/// Send the Retrieve Attestation Measurement UVC of the `arcb` to the
+/// Ultravisor, and return the measurement, the additional data and the
+/// configuration unique id of the guest.
+fn attest(
+    arcb: &[u8],
+    user_data: &[u8],
+    measurement_size: usize,
+    additional_size: usize,
+) -> Result<(Vec<u8>, Vec<u8>, [u8; UVIO_ATT_UID_LEN])> {
+    if arcb.is_empty() || arcb.len() > UVIO_ATT_ARCB_MAX_LEN {
+        bail!("SE Attester: illegal request blob size {}", arcb.len());
+    }
+    if measurement_size == 0 || measurement_size > UVIO_ATT_MEASUREMENT_MAX_LEN {
+        bail!("SE Attester: illegal measurement size {measurement_size}");
+    }
+    if additional_size > UVIO_ATT_ADDITIONAL_MAX_LEN {
+        bail!("SE Attester: illegal additional data size {additional_size}");
+    }
+    if user_data.len() > UVIO_ATT_USER_DATA_LEN {
+        bail!("SE Attester: User data must be no more than {UVIO_ATT_USER_DATA_LEN} bytes");
+    }
+
+    let mut measurement = vec![0; measurement_size];
+    let mut additional_data = vec![0; additional_size];
+    let mut attest = UvioAttest {
+        arcb_addr: arcb.as_ptr() as u64,
+        meas_addr: measurement.as_mut_ptr() as u64,
+        add_data_addr: if additional_size == 0 {
+            0
+        } else {
+            additional_data.as_mut_ptr() as u64
+        },
+        user_data: [0; UVIO_ATT_USER_DATA_LEN],
+        config_uid: [0; UVIO_ATT_UID_LEN],
+        arcb_len: arcb.len() as u32,
+        meas_len: measurement_size as u32,
+        add_data_len: additional_size as u32,
+        user_data_len: user_data.len() as u16,
+        reserved136: 0,
+    };
+    attest.user_data[..user_data.len()].copy_from_slice(user_data);
+
+    let mut cb = UvioIoctlCb {
+        flags: 0,
+        uv_rc: 0,
+        uv_rrc: 0,
+        argument_addr: &mut attest as *mut UvioAttest as u64,
+        argument_len: std::mem::size_of::<UvioAttest>() as u32,
+        reserved14: [0; 0x40 - 0x14],
+    };
+
+    let fd = open(UV_DEVICE_PATH, OFlag::O_RDWR, Mode::empty())
+        .context("Failed to open Ultravisor device")?;
+    // SAFETY: `cb` points to `attest`, whose buffers are all valid and
+    // outlive the call.
+    let res = unsafe { uvio_ioctl_att(fd, &mut cb) };
+    close(fd).context("Failed to close Ultravisor device")?;
+    res.context("Failed to get SE attestation measurement")?;
+
+    if cb.uv_rc != UVC_RC_EXECUTED {
+        bail!(
+            "SE Attester: Ultravisor call failed with rc {:#06x}, rrc {:#06x}",
+            cb.uv_rc,
+            cb.uv_rrc
+        );
+    }
+
+    // The Ultravisor updates the lengths to the sizes actually written.
+    measurement.truncate(attest.meas_len as usize);
+    additional_data.truncate(attest.add_data_len as usize);
+    Ok((measurement, additional_data, attest.config_uid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uvio_layout() {
+        assert_eq!(std::mem::size_of::<UvioIoctlCb>(), 0x40);
+        assert_eq!(std::mem::size_of::<UvioAttest>(), 0x138);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_se_get_evidence() {
+        let attester = SeAttester::default();
+        let request = SeAttestationRequest {
+            request_blob: STANDARD.encode([0; 16]),
+            measurement_size: 64,
+            additional_size: 0,
+            user_data: String::new(),
+        };
+        let report_data = serde_json::to_vec(&request).unwrap();
+
+        let evidence = attester.get_evidence(report_data).await;
+        assert!(evidence.is_ok());
+    }
+}
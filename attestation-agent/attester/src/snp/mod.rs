@@ -3,13 +3,31 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use super::tsm_report;
 use super::Attester;
 use anyhow::*;
 use serde::{Deserialize, Serialize};
 use sev::firmware::guest::AttestationReport;
 use sev::firmware::guest::Firmware;
-use sev::firmware::host::CertTableEntry;
-use std::path::Path;
+use sev::firmware::host::{CertTableEntry, CertType};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// If the environment variable "AA_SNP_CERTS" is set, e.g. to
+// "/etc/attestation-agent/snp-certs", the certificates of the chain missing
+// from the extended guest request of the host are read from this directory.
+// By default the certificates are read from `DEFAULT_CERTS_DIR`, if any.
+pub const CERTS_DIR_ENV: &str = "AA_SNP_CERTS";
+
+const DEFAULT_CERTS_DIR: &str = "/etc/attestation-agent/snp-certs";
+
+/// The GUID of the Versioned Loaded Endorsement Key (VLEK) certificate in
+/// the certificate table of the GHCB, which `sev` has no `CertType` of.
+const VLEK_GUID: &str = "a8074bc2-a25a-483e-aae6-39c045a0b8a1";
+
+/// The offset of the field of the key signing the attestation report, whose
+/// bits 4:2 are `SIGNING_KEY` of the SNP firmware ABI.
+const SIGNING_KEY_OFFSET: usize = 0x48;
 
 pub fn detect_platform() -> bool {
     Path::new("/sys/devices/platform/sev-guest").exists()
@@ -33,18 +51,267 @@ impl Attester for SnpAttester {
 
         report_data.resize(64, 0);
 
-        let mut firmware = Firmware::open()?;
-        let data = report_data.as_slice().try_into()?;
+        // The attestation report is got by the TSM reports of configfs if
+        // the kernel exposes them, or by the ioctl of `/dev/sev-guest`
+        // otherwise.
+        let (report, certs) = if tsm_report::is_available() {
+            match get_tsm_report(&report_data) {
+                Result::Ok(report) => report,
+                Result::Err(e) => {
+                    log::warn!(
+                        "SNP Attester: Failed to get attestation report by TSM report, \
+                        fall back to /dev/sev-guest: {e:?}"
+                    );
+                    get_ext_report(&report_data)?
+                }
+            }
+        } else {
+            get_ext_report(&report_data)?
+        };
 
-        let (report, certs) = firmware
-            .get_ext_report(None, Some(data), Some(0))
-            .context("Failed to get attestation report")?;
+        let certs_dir = std::env::var(CERTS_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_CERTS_DIR));
+        let cert_chain = complete_cert_chain(certs, signing_key(&report)?, &certs_dir)?;
 
         let evidence = SnpEvidence {
             attestation_report: report,
-            cert_chain: certs,
+            cert_chain,
         };
 
         serde_json::to_string(&evidence).context("Serialize SNP evidence failed")
     }
 }
+
+/// Get the attestation report of `report_data` and the certificates of the
+/// host by the ioctl of `/dev/sev-guest`.
+fn get_ext_report(report_data: &[u8]) -> Result<(AttestationReport, Vec<CertTableEntry>)> {
+    let mut firmware = Firmware::open()?;
+    let data = report_data.try_into()?;
+
+    firmware
+        .get_ext_report(None, Some(data), Some(0))
+        .context("Failed to get attestation report")
+}
+
+/// Get the attestation report of `report_data` and the certificates of the
+/// host by the TSM reports of configfs.
+fn get_tsm_report(report_data: &[u8]) -> Result<(AttestationReport, Vec<CertTableEntry>)> {
+    let report = tsm_report::get_report(tsm_report::SNP_PROVIDER, report_data, Some(0))?;
+
+    if report.outblob.len() != std::mem::size_of::<AttestationReport>() {
+        bail!(
+            "SNP Attester: Illegal attestation report of {} bytes",
+            report.outblob.len()
+        );
+    }
+
+    // SAFETY: `AttestationReport` is a plain `repr(C)` struct of the
+    // layout of the firmware, whose size is checked above.
+    let attestation_report =
+        unsafe { std::ptr::read_unaligned(report.outblob.as_ptr() as *const AttestationReport) };
+
+    let cert_chain = match report.auxblob {
+        Some(auxblob) => parse_cert_table(&auxblob)?,
+        None => vec![],
+    };
+
+    Ok((attestation_report, cert_chain))
+}
+
+/// Parse the certificate table of the GHCB extended guest request, i.e.
+/// the entries of a 16-byte GUID, a 4-byte offset and a 4-byte length,
+/// ended by an entry of the zero GUID, followed by the certificates.
+fn parse_cert_table(table: &[u8]) -> Result<Vec<CertTableEntry>> {
+    const ENTRY_SIZE: usize = 24;
+
+    let mut certs = vec![];
+    for entry in table.chunks(ENTRY_SIZE) {
+        if entry.len() != ENTRY_SIZE {
+            bail!("SNP Attester: Certificate table is not ended");
+        }
+
+        let guid = Uuid::from_slice(&entry[..16])?;
+        if guid.is_nil() {
+            return Ok(certs);
+        }
+
+        let offset = u32::from_le_bytes(entry[16..20].try_into()?) as usize;
+        let length = u32::from_le_bytes(entry[20..24].try_into()?) as usize;
+        let cert = offset
+            .checked_add(length)
+            .and_then(|end| table.get(offset..end))
+            .ok_or_else(|| anyhow!("SNP Attester: Certificate {guid} is out of the table"))?;
+
+        certs.push(CertTableEntry::from_guid(&guid, cert.to_vec())?);
+    }
+
+    bail!("SNP Attester: Certificate table is not ended")
+}
+
+/// The endorsement key signing the attestation report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SigningKey {
+    Vcek,
+    Vlek,
+}
+
+impl SigningKey {
+    fn cert_type(self) -> Result<CertType> {
+        Ok(match self {
+            SigningKey::Vcek => CertType::VCEK,
+            SigningKey::Vlek => CertType::OTHER(Uuid::parse_str(VLEK_GUID)?),
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SigningKey::Vcek => "vcek",
+            SigningKey::Vlek => "vlek",
+        }
+    }
+}
+
+/// The endorsement key signing `report`, due to its `SIGNING_KEY` field,
+/// which `sev` keeps private.
+fn signing_key(report: &AttestationReport) -> Result<SigningKey> {
+    // SAFETY: `AttestationReport` is a plain `repr(C)` struct of the
+    // layout of the firmware, whose bytes are all initialized.
+    let bytes = unsafe {
+        std::slice::from_raw_parts(
+            report as *const AttestationReport as *const u8,
+            std::mem::size_of::<AttestationReport>(),
+        )
+    };
+    let field = u32::from_le_bytes(bytes[SIGNING_KEY_OFFSET..SIGNING_KEY_OFFSET + 4].try_into()?);
+
+    match (field >> 2) & 0x7 {
+        0 => Ok(SigningKey::Vcek),
+        1 => Ok(SigningKey::Vlek),
+        key => bail!("SNP Attester: Attestation report is not signed, signing key {key}"),
+    }
+}
+
+/// Complete the certificate chain of the host with the certificates in
+/// `certs_dir` it misses, i.e. the ARK, the ASK and the VCEK or VLEK
+/// signing the report. The certificates are read from the files named
+/// `ark`, `ask`, `vcek` or `vlek`, with an extension of `.der` or `.pem`.
+/// The verifiers need the AMD KDS for the certificates still missing.
+fn complete_cert_chain(
+    mut certs: Vec<CertTableEntry>,
+    signing_key: SigningKey,
+    certs_dir: &Path,
+) -> Result<Vec<CertTableEntry>> {
+    let chain = [
+        (CertType::ARK, "ark"),
+        (CertType::ASK, "ask"),
+        (signing_key.cert_type()?, signing_key.name()),
+    ];
+
+    for (cert_type, name) in chain {
+        if certs.iter().any(|cert| cert.cert_type == cert_type) {
+            continue;
+        }
+
+        let cert = ["der", "pem"]
+            .iter()
+            .map(|extension| certs_dir.join(format!("{name}.{extension}")))
+            .find(|path| path.exists());
+        match cert {
+            Some(path) => {
+                let data = std::fs::read(&path)
+                    .with_context(|| format!("SNP Attester: Read {}", path.display()))?;
+                certs.push(CertTableEntry::new(cert_type, data));
+            }
+            None => log::warn!(
+                "SNP Attester: No {} certificate from the host or in {}",
+                name.to_uppercase(),
+                certs_dir.display()
+            ),
+        }
+    }
+
+    Ok(certs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VCEK_GUID: &str = "63da758d-e664-4564-adc5-f4b93be8accd";
+
+    fn entry(guid: &str, offset: u32, length: u32) -> Vec<u8> {
+        let mut entry = Uuid::parse_str(guid).unwrap().as_bytes().to_vec();
+        entry.extend(offset.to_le_bytes());
+        entry.extend(length.to_le_bytes());
+        entry
+    }
+
+    #[test]
+    fn test_parse_cert_table() {
+        let mut table = entry(VCEK_GUID, 48, 4);
+        table.extend(entry("00000000-0000-0000-0000-000000000000", 0, 0));
+        table.extend(b"vcek");
+
+        let certs = parse_cert_table(&table).unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].guid_string(), VCEK_GUID);
+        assert_eq!(certs[0].data(), b"vcek");
+
+        assert!(parse_cert_table(&table[..24]).is_err());
+        assert!(parse_cert_table(&entry(VCEK_GUID, 48, 4)[..]).is_err());
+
+        let mut table = entry(VCEK_GUID, 48, 8);
+        table.extend(entry("00000000-0000-0000-0000-000000000000", 0, 0));
+        table.extend(b"vcek");
+        assert!(parse_cert_table(&table).is_err());
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let mut report = AttestationReport::default();
+        assert_eq!(signing_key(&report).unwrap(), SigningKey::Vcek);
+
+        for (field, key) in [(1u32 << 2, Some(SigningKey::Vlek)), (7 << 2, None)] {
+            // SAFETY: the field is within the report, see `signing_key`.
+            unsafe {
+                let bytes = &mut report as *mut AttestationReport as *mut u8;
+                std::ptr::write_unaligned(bytes.add(SIGNING_KEY_OFFSET) as *mut u32, field);
+            }
+            assert_eq!(signing_key(&report).ok(), key);
+        }
+    }
+
+    #[test]
+    fn test_complete_cert_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ark.pem"), b"ark").unwrap();
+        std::fs::write(dir.path().join("ask.pem"), b"ask").unwrap();
+        std::fs::write(dir.path().join("vcek.der"), b"cached vcek").unwrap();
+        std::fs::write(dir.path().join("vlek.der"), b"vlek").unwrap();
+
+        let host = vec![CertTableEntry::new(CertType::VCEK, b"vcek".to_vec())];
+        let certs = complete_cert_chain(host, SigningKey::Vcek, dir.path()).unwrap();
+        let certs: Vec<_> = certs
+            .iter()
+            .map(|cert| (cert.guid_string(), cert.data()))
+            .collect();
+        assert_eq!(
+            certs,
+            [
+                (CertType::VCEK.to_string(), &b"vcek"[..]),
+                (CertType::ARK.to_string(), &b"ark"[..]),
+                (CertType::ASK.to_string(), &b"ask"[..]),
+            ]
+        );
+
+        let certs = complete_cert_chain(vec![], SigningKey::Vlek, dir.path()).unwrap();
+        assert_eq!(certs.len(), 3);
+        assert_eq!(certs[2].guid_string(), VLEK_GUID);
+        assert_eq!(certs[2].data(), b"vlek");
+
+        let empty = tempfile::tempdir().unwrap();
+        let certs = complete_cert_chain(vec![], SigningKey::Vcek, empty.path()).unwrap();
+        assert!(certs.is_empty());
+    }
+}
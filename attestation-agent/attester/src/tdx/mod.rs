@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use super::tsm_report;
 use super::Attester;
 use anyhow::*;
 use base64::Engine;
@@ -37,20 +38,26 @@ impl Attester for TdxAttester {
 
         report_data.resize(64, 0);
 
-        let tdx_report_data = tdx_attest_rs::tdx_report_data_t {
-            d: report_data.as_slice().try_into()?,
+        // The TD quote is got by the TSM reports of configfs if the kernel
+        // exposes them, or by the TDX attestation library, i.e. the
+        // `/dev/tdx-guest` ioctl or the vsock of the QGS, otherwise.
+        let quote = if tsm_report::is_available() {
+            match tsm_report::get_report(tsm_report::TDX_PROVIDER, &report_data, None) {
+                Result::Ok(report) => report.outblob,
+                Result::Err(e) => {
+                    log::warn!(
+                        "TDX Attester: Failed to get TD quote by TSM report, \
+                        fall back to the TDX attestation library: {e:?}"
+                    );
+                    get_quote(&report_data)?
+                }
+            }
+        } else {
+            get_quote(&report_data)?
         };
 
         let engine = base64::engine::general_purpose::STANDARD;
-        let quote = match tdx_attest_rs::tdx_att_get_quote(Some(&tdx_report_data), None, None, 0) {
-            (tdx_attest_rs::tdx_attest_error_t::TDX_ATTEST_SUCCESS, Some(q)) => engine.encode(q),
-            (error_code, _) => {
-                return Err(anyhow!(
-                    "TDX Attester: Failed to get TD quote. Error code: {:?}",
-                    error_code
-                ));
-            }
-        };
+        let quote = engine.encode(quote);
 
         let cc_eventlog = match std::fs::read(CCEL_PATH) {
             Result::Ok(el) => Some(engine.encode(el)),
@@ -65,6 +72,83 @@ impl Attester for TdxAttester {
         serde_json::to_string(&evidence)
             .map_err(|e| anyhow!("Serialize TDX evidence failed: {:?}", e))
     }
+
+    async fn extend_runtime_measurement(
+        &self,
+        event_digest: Vec<u8>,
+        register_index: u64,
+    ) -> Result<()> {
+        if event_digest.len() > 48 {
+            bail!("TDX Attester: Event digest must be no more than 48 bytes");
+        }
+
+        let rtmr_index = pcr_to_rtmr(register_index);
+        if rtmr_index < 2 {
+            bail!("TDX Attester: RTMR{rtmr_index} cannot be extended at runtime");
+        }
+
+        let mut event = RtmrEvent {
+            version: 1,
+            rtmr_index,
+            extend_data: [0; 48],
+            event_type: 0,
+            event_data_size: 0,
+        };
+        event.extend_data[..event_digest.len()].copy_from_slice(&event_digest);
+
+        // SAFETY: `RtmrEvent` is a plain `repr(C)` struct, whose bytes are
+        // all initialized.
+        let event = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const RtmrEvent as *const u8,
+                std::mem::size_of::<RtmrEvent>(),
+            )
+        };
+        match tdx_attest_rs::tdx_att_extend(event) {
+            tdx_attest_rs::tdx_attest_error_t::TDX_ATTEST_SUCCESS => Ok(()),
+            error_code => bail!(
+                "TDX Attester: Failed to extend RTMR{rtmr_index}. Error code: {:?}",
+                error_code
+            ),
+        }
+    }
+}
+
+/// Get the TD quote of `report_data` by the TDX attestation library.
+fn get_quote(report_data: &[u8]) -> Result<Vec<u8>> {
+    let tdx_report_data = tdx_attest_rs::tdx_report_data_t {
+        d: report_data.try_into()?,
+    };
+
+    match tdx_attest_rs::tdx_att_get_quote(Some(&tdx_report_data), None, None, 0) {
+        (tdx_attest_rs::tdx_attest_error_t::TDX_ATTEST_SUCCESS, Some(q)) => Ok(q),
+        (error_code, _) => Err(anyhow!(
+            "TDX Attester: Failed to get TD quote. Error code: {:?}",
+            error_code
+        )),
+    }
+}
+
+/// `tdx_rtmr_event_t` of the TDX attestation library, without event data.
+#[repr(C)]
+struct RtmrEvent {
+    version: u32,
+    rtmr_index: u64,
+    extend_data: [u8; 48],
+    event_type: u32,
+    event_data_size: u32,
+}
+
+/// Map the index of a PCR to the RTMR it is measured into, due to the
+/// TCG PC Client Platform Firmware Profile and the TDX Virtual Firmware
+/// Design Guide. PCRs out of the firmware range are mapped to RTMR3.
+fn pcr_to_rtmr(register_index: u64) -> u64 {
+    match register_index {
+        1 | 7 => 0,
+        2..=6 => 1,
+        8..=15 => 2,
+        _ => 3,
+    }
 }
 
 #[cfg(test)]
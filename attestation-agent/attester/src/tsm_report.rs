@@ -0,0 +1,198 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Reports of the TEE by the configfs-tsm interface of Linux, i.e. the TD
+//! quotes of TDX and the attestation reports of SEV-SNP, on the kernels
+//! exposing TSM reports.
+//!
+//! A report is requested by creating an entry under
+//! `/sys/kernel/config/tsm/report`, writing the report data to its `inblob`
+//! and reading the report from its `outblob`. The `generation` of the entry
+//! is checked before and after the report is read, so that a report of
+//! report data written concurrently by another process is never returned.
+
+use anyhow::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The directory of the TSM reports of configfs.
+pub const TSM_REPORT_PATH: &str = "/sys/kernel/config/tsm/report";
+
+/// The TSM provider of TDX.
+pub const TDX_PROVIDER: &str = "tdx_guest";
+
+/// The TSM provider of SEV-SNP.
+pub const SNP_PROVIDER: &str = "sev_guest";
+
+static ENTRY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether the kernel exposes the TSM reports.
+pub fn is_available() -> bool {
+    Path::new(TSM_REPORT_PATH).is_dir()
+}
+
+/// A report of the TSM, in the form of the provider.
+pub struct TsmReport {
+    /// The report, i.e. the TD quote of TDX or the attestation report of
+    /// SEV-SNP.
+    pub outblob: Vec<u8>,
+
+    /// The auxiliary data of the report, i.e. the certificate table of
+    /// SEV-SNP, if any.
+    pub auxblob: Option<Vec<u8>>,
+}
+
+/// An entry of the TSM reports, removed once dropped.
+pub struct TsmReportEntry {
+    path: PathBuf,
+}
+
+impl TsmReportEntry {
+    /// Create an entry of the TSM reports of configfs.
+    pub fn new() -> Result<Self> {
+        Self::new_in(TSM_REPORT_PATH)
+    }
+
+    /// Create an entry in the TSM report directory `root`.
+    pub fn new_in(root: impl AsRef<Path>) -> Result<Self> {
+        let name = format!(
+            "aa-{}-{}",
+            std::process::id(),
+            ENTRY_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = root.as_ref().join(name);
+        fs::create_dir(&path)
+            .with_context(|| format!("TSM report: create entry {}", path.display()))?;
+        Ok(Self { path })
+    }
+
+    /// Get the report of the `report_data` by the TSM of `provider`.
+    /// `privlevel` is the privilege level of the report, e.g. the VMPL of
+    /// SEV-SNP, or that of the TSM if not given.
+    pub fn get_report(
+        &self,
+        provider: &str,
+        report_data: &[u8],
+        privlevel: Option<u32>,
+    ) -> Result<TsmReport> {
+        let actual = self.read_string("provider")?;
+        if actual != provider {
+            bail!("TSM report: provider is {actual}, not {provider}");
+        }
+
+        if let Some(privlevel) = privlevel {
+            self.write("privlevel", privlevel.to_string().as_bytes())?;
+        }
+        self.write("inblob", report_data)?;
+
+        let generation = self.read_string("generation")?;
+        let outblob = self.read("outblob")?;
+        let auxblob = match self.read("auxblob") {
+            Result::Ok(auxblob) if !auxblob.is_empty() => Some(auxblob),
+            _ => None,
+        };
+        if self.read_string("generation")? != generation {
+            bail!("TSM report: entry is written concurrently");
+        }
+
+        if outblob.is_empty() {
+            bail!("TSM report: empty report");
+        }
+
+        Ok(TsmReport { outblob, auxblob })
+    }
+
+    fn read(&self, attribute: &str) -> Result<Vec<u8>> {
+        fs::read(self.path.join(attribute)).with_context(|| format!("TSM report: read {attribute}"))
+    }
+
+    fn read_string(&self, attribute: &str) -> Result<String> {
+        let value = self.read(attribute)?;
+        Ok(String::from_utf8_lossy(&value).trim().to_string())
+    }
+
+    fn write(&self, attribute: &str, value: &[u8]) -> Result<()> {
+        fs::write(self.path.join(attribute), value)
+            .with_context(|| format!("TSM report: write {attribute}"))
+    }
+}
+
+impl Drop for TsmReportEntry {
+    fn drop(&mut self) {
+        // The attributes of the entry are removed together with it by
+        // configfs.
+        if let Err(e) = fs::remove_dir(&self.path) {
+            log::debug!("TSM report: remove entry {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Get the report of the `report_data` by the TSM of `provider`, if the
+/// kernel exposes the TSM reports.
+pub fn get_report(provider: &str, report_data: &[u8], privlevel: Option<u32>) -> Result<TsmReport> {
+    if !is_available() {
+        bail!("TSM report: {TSM_REPORT_PATH} is not available");
+    }
+
+    TsmReportEntry::new()?.get_report(provider, report_data, privlevel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_with(root: &Path, provider: &str, outblob: &[u8]) -> TsmReportEntry {
+        let entry = TsmReportEntry::new_in(root).unwrap();
+        fs::write(entry.path.join("provider"), format!("{provider}\n")).unwrap();
+        fs::write(entry.path.join("generation"), "1\n").unwrap();
+        fs::write(entry.path.join("outblob"), outblob).unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_get_report() {
+        let root = tempfile::tempdir().unwrap();
+        let entry = entry_with(root.path(), TDX_PROVIDER, b"quote");
+        let report = entry.get_report(TDX_PROVIDER, &[1; 64], None).unwrap();
+        assert_eq!(report.outblob, b"quote");
+        assert_eq!(report.auxblob, None);
+        assert_eq!(fs::read(entry.path.join("inblob")).unwrap(), [1; 64]);
+        assert!(!entry.path.join("privlevel").exists());
+
+        let entry = entry_with(root.path(), SNP_PROVIDER, b"report");
+        fs::write(entry.path.join("auxblob"), b"certs").unwrap();
+        let report = entry.get_report(SNP_PROVIDER, &[2; 64], Some(0)).unwrap();
+        assert_eq!(report.outblob, b"report");
+        assert_eq!(report.auxblob.as_deref(), Some(&b"certs"[..]));
+        assert_eq!(
+            fs::read_to_string(entry.path.join("privlevel")).unwrap(),
+            "0"
+        );
+    }
+
+    #[test]
+    fn test_get_report_failed() {
+        let root = tempfile::tempdir().unwrap();
+        let entry = entry_with(root.path(), SNP_PROVIDER, b"report");
+        assert!(entry.get_report(TDX_PROVIDER, &[0; 64], None).is_err());
+
+        let entry = entry_with(root.path(), TDX_PROVIDER, b"");
+        assert!(entry.get_report(TDX_PROVIDER, &[0; 64], None).is_err());
+    }
+
+    #[test]
+    fn test_entry_removed() {
+        let root = tempfile::tempdir().unwrap();
+        let entry = TsmReportEntry::new_in(root.path()).unwrap();
+        let path = entry.path.clone();
+        assert!(path.is_dir());
+        drop(entry);
+        assert!(!path.exists());
+
+        let another = TsmReportEntry::new_in(root.path()).unwrap();
+        assert_ne!(another.path, path);
+    }
+}
@@ -11,20 +11,33 @@ use openssl::symm::Cipher;
 const TAG_LENGTH: usize = 16;
 
 pub fn decrypt(encrypted_data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    decrypt_with_aad(encrypted_data, key, iv, &[])
+}
+
+pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    encrypt_with_aad(data, key, iv, &[])
+}
+
+pub fn decrypt_with_aad(
+    encrypted_data: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
     let cipher = Cipher::aes_256_gcm();
     if encrypted_data.len() < TAG_LENGTH {
         bail!("Illegal length of ciphertext");
     }
 
     let (data, tag) = encrypted_data.split_at(encrypted_data.len() - TAG_LENGTH);
-    openssl::symm::decrypt_aead(cipher, key, Some(iv), &[], data, tag)
+    openssl::symm::decrypt_aead(cipher, key, Some(iv), aad, data, tag)
         .map_err(|e| anyhow!(e.to_string()))
 }
 
-pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt_with_aad(data: &[u8], key: &[u8], iv: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let cipher = Cipher::aes_256_gcm();
     let mut tag = [0u8; TAG_LENGTH];
-    let mut ciphertext = openssl::symm::encrypt_aead(cipher, key, Some(iv), &[], data, &mut tag)
+    let mut ciphertext = openssl::symm::encrypt_aead(cipher, key, Some(iv), aad, data, &mut tag)
         .map_err(|e| anyhow!(e.to_string()))?;
     ciphertext.extend_from_slice(&tag);
     Ok(ciphertext)
@@ -34,7 +47,7 @@ pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
 mod tests {
     use rstest::rstest;
 
-    use super::{decrypt, encrypt};
+    use super::{decrypt, decrypt_with_aad, encrypt, encrypt_with_aad};
 
     #[rstest]
     #[case(b"plaintext1", b"0123456789abcdefghijklmnopqrstuv", b"unique nonce")]
@@ -44,4 +57,17 @@ mod tests {
         let plaintext_de = decrypt(&ciphertext, key, iv).expect("decryption failed");
         assert_eq!(plaintext, plaintext_de);
     }
+
+    #[test]
+    fn en_decrypt_with_aad() {
+        let key = b"0123456789abcdefghijklmnopqrstuv";
+        let iv = b"unique nonce";
+        let ciphertext =
+            encrypt_with_aad(b"plaintext", key, iv, b"aad").expect("encryption failed");
+        let plaintext = decrypt_with_aad(&ciphertext, key, iv, b"aad").expect("decryption failed");
+        assert_eq!(plaintext, b"plaintext");
+
+        assert!(decrypt_with_aad(&ciphertext, key, iv, b"tampered").is_err());
+        assert!(decrypt(&ciphertext, key, iv).is_err());
+    }
 }
@@ -5,6 +5,7 @@
 
 use anyhow::*;
 use openssl::{
+    bn::BigNum,
     pkey::Private,
     rsa::{Padding, Rsa},
 };
@@ -59,3 +60,21 @@ impl RSAKeyPair {
         Ok(Self { private_key })
     }
 }
+
+/// Encrypt the `plaintext` by the public key of the big-endian modulus `n`
+/// and exponent `e`, e.g. of a TEE key pair at the KBS side.
+pub fn encrypt(n: &[u8], e: &[u8], mode: PaddingMode, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let public_key = Rsa::from_public_components(BigNum::from_slice(n)?, BigNum::from_slice(e)?)?;
+    let mut ciphertext = vec![0; public_key.size() as usize];
+    let size = match mode {
+        PaddingMode::OAEP => public_key
+            .public_encrypt(plaintext, &mut ciphertext, Padding::PKCS1_OAEP)
+            .map_err(|e| anyhow!("RSA key encrypt OAEP failed: {:?}", e))?,
+        PaddingMode::PKCS1v15 => public_key
+            .public_encrypt(plaintext, &mut ciphertext, Padding::PKCS1)
+            .map_err(|e| anyhow!("RSA key pkcs1v15 encrypt failed: {:?}", e))?,
+    };
+    ciphertext.truncate(size);
+
+    Ok(ciphertext)
+}
@@ -5,26 +5,47 @@
 
 //! This mod implements aes-256-gcm encryption & decryption.
 
-use aes_gcm::{aead::Aead, Aes256Gcm, Key, KeyInit, Nonce};
+use aes_gcm::{
+    aead::{Aead, Payload},
+    Aes256Gcm, Key, KeyInit, Nonce,
+};
 use anyhow::*;
 
 pub fn decrypt(encrypted_data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    decrypt_with_aad(encrypted_data, key, iv, &[])
+}
+
+pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    encrypt_with_aad(data, key, iv, &[])
+}
+
+pub fn decrypt_with_aad(
+    encrypted_data: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
     let decrypting_key = Key::<Aes256Gcm>::from_slice(key);
     let cipher = Aes256Gcm::new(decrypting_key);
     let nonce = Nonce::from_slice(iv);
+    let payload = Payload {
+        msg: encrypted_data,
+        aad,
+    };
     let plain_text = cipher
-        .decrypt(nonce, encrypted_data)
+        .decrypt(nonce, payload)
         .map_err(|e| anyhow!("aes-256-gcm decrypt failed: {:?}", e))?;
 
     Ok(plain_text)
 }
 
-pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt_with_aad(data: &[u8], key: &[u8], iv: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
     let encrypting_key = Key::<Aes256Gcm>::from_slice(key);
     let cipher = Aes256Gcm::new(encrypting_key);
     let nonce = Nonce::from_slice(iv);
+    let payload = Payload { msg: data, aad };
     let ciphertext = cipher
-        .encrypt(nonce, data)
+        .encrypt(nonce, payload)
         .map_err(|e| anyhow!("aes-256-gcm encrypt failed: {:?}", e))?;
 
     Ok(ciphertext)
@@ -34,7 +55,7 @@ pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
 mod tests {
     use rstest::rstest;
 
-    use super::{decrypt, encrypt};
+    use super::{decrypt, decrypt_with_aad, encrypt, encrypt_with_aad};
 
     #[rstest]
     #[case(b"plaintext1", b"0123456789abcdefghijklmnopqrstuv", b"unique nonce")]
@@ -44,4 +65,17 @@ mod tests {
         let plaintext_de = decrypt(&ciphertext, key, iv).expect("decryption failed");
         assert_eq!(plaintext, plaintext_de);
     }
+
+    #[test]
+    fn en_decrypt_with_aad() {
+        let key = b"0123456789abcdefghijklmnopqrstuv";
+        let iv = b"unique nonce";
+        let ciphertext =
+            encrypt_with_aad(b"plaintext", key, iv, b"aad").expect("encryption failed");
+        let plaintext = decrypt_with_aad(&ciphertext, key, iv, b"aad").expect("decryption failed");
+        assert_eq!(plaintext, b"plaintext");
+
+        assert!(decrypt_with_aad(&ciphertext, key, iv, b"tampered").is_err());
+        assert!(decrypt(&ciphertext, key, iv).is_err());
+    }
 }
@@ -10,7 +10,7 @@ use rsa::{
     pkcs1::{DecodeRsaPrivateKey, EncodeRsaPrivateKey},
     pkcs8::LineEnding,
     traits::PublicKeyParts,
-    Oaep, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey,
+    BigUint, Oaep, Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey,
 };
 use zeroize::Zeroizing;
 
@@ -71,3 +71,18 @@ impl RSAKeyPair {
         })
     }
 }
+
+/// Encrypt the `plaintext` by the public key of the big-endian modulus `n`
+/// and exponent `e`, e.g. of a TEE key pair at the KBS side.
+pub fn encrypt(n: &[u8], e: &[u8], mode: PaddingMode, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let public_key = RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))?;
+    let mut rng = rand::thread_rng();
+    match mode {
+        PaddingMode::OAEP => public_key
+            .encrypt(&mut rng, Oaep::new::<sha2::Sha256>(), plaintext)
+            .map_err(|e| anyhow!("RSA key encrypt OAEP failed: {:?}", e)),
+        PaddingMode::PKCS1v15 => public_key
+            .encrypt(&mut rng, Pkcs1v15Encrypt, plaintext)
+            .map_err(|e| anyhow!("RSA key pkcs1v15 encrypt failed: {:?}", e)),
+    }
+}
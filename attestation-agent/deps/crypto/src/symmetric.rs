@@ -5,7 +5,7 @@
 
 //! APIs for symmetric keys
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroizing;
 
@@ -63,3 +63,33 @@ pub fn encrypt(
         WrapType::Aes256Ctr => aes256ctr::encrypt(&plaintext, &key, &iv),
     }
 }
+
+/// Decrypt the given `ciphertext`, which is authenticated together with the
+/// additional data `aad`. Only AEAD schemes, i.e. A256GCM, are supported.
+pub fn decrypt_with_aad(
+    key: Zeroizing<Vec<u8>>,
+    ciphertext: Vec<u8>,
+    iv: Vec<u8>,
+    aad: &[u8],
+    wrap_type: WrapType,
+) -> Result<Vec<u8>> {
+    match wrap_type {
+        WrapType::Aes256Gcm => aes256gcm::decrypt_with_aad(&ciphertext, &key, &iv, aad),
+        WrapType::Aes256Ctr => bail!("A256CTR is not AEAD, cannot authenticate additional data"),
+    }
+}
+
+/// Encrypt the given `plaintext`, and authenticate it together with the
+/// additional data `aad`. Only AEAD schemes, i.e. A256GCM, are supported.
+pub fn encrypt_with_aad(
+    key: Zeroizing<Vec<u8>>,
+    plaintext: Vec<u8>,
+    iv: Vec<u8>,
+    aad: &[u8],
+    wrap_type: WrapType,
+) -> Result<Vec<u8>> {
+    match wrap_type {
+        WrapType::Aes256Gcm => aes256gcm::encrypt_with_aad(&plaintext, &key, &iv, aad),
+        WrapType::Aes256Ctr => bail!("A256CTR is not AEAD, cannot authenticate additional data"),
+    }
+}
@@ -10,4 +10,8 @@ pub use resource_uri::ResourceUri;
 #[async_trait]
 pub trait KbsClientCapabilities {
     async fn get_resource(&mut self, resource_uri: ResourceUri) -> Result<Vec<u8>>;
+
+    /// Write the `content` to the resource repository of the KBS. Whether the
+    /// write is allowed is determined by the policy of the KBS.
+    async fn set_resource(&mut self, resource_uri: ResourceUri, content: Vec<u8>) -> Result<()>;
 }
@@ -19,7 +19,7 @@ pub mod rcar_client;
 #[cfg(feature = "passport")]
 pub mod token_client;
 
-use kbs_types::Tee;
+use kbs_types::{ErrorInformation, Tee};
 
 use crate::{keypair::TeeKeyPair, token_provider::Token};
 
@@ -33,7 +33,8 @@ pub struct KbsClient<T> {
     /// TEE Type
     pub(crate) _tee: ClientTee,
 
-    /// The asymmetric key pair inside the TEE
+    /// The asymmetric key pair inside the TEE. The RCAR client rotates it
+    /// with each new session.
     pub(crate) tee_key: TeeKeyPair,
 
     pub(crate) provider: T,
@@ -46,6 +47,10 @@ pub struct KbsClient<T> {
 
     /// token
     pub(crate) token: Option<Token>,
+
+    /// The `extra-params` of the RCAR request
+    #[cfg_attr(not(feature = "background_check"), allow(dead_code))]
+    pub(crate) extra_params: String,
 }
 
 pub const KBS_PROTOCOL_VERSION: &str = "0.1.0";
@@ -53,3 +58,10 @@ pub const KBS_PROTOCOL_VERSION: &str = "0.1.0";
 pub const KBS_GET_RESOURCE_MAX_ATTEMPT: u64 = 3;
 
 pub const KBS_PREFIX: &str = "kbs/v0";
+
+/// Whether the error returned by the KBS means that the request is denied
+/// by the policy of the KBS. Such a request will not succeed even after a
+/// new attestation.
+pub(crate) fn is_policy_denied(error_info: &ErrorInformation) -> bool {
+    error_info.error_type.ends_with("PolicyDeny")
+}
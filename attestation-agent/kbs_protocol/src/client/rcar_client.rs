@@ -5,16 +5,20 @@
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
-use kbs_types::{Attestation, Challenge, ErrorInformation, Request, Response};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use kbs_types::{Attestation, Challenge, ErrorInformation, Request, Response, Tee};
 use log::{debug, warn};
 use resource_uri::ResourceUri;
 use serde::Deserialize;
+use serde_json::{Map, Value};
 use sha2::{Digest, Sha384};
+use tracing::instrument;
 
 use crate::{
     api::KbsClientCapabilities,
     client::{
-        ClientTee, KbsClient, KBS_GET_RESOURCE_MAX_ATTEMPT, KBS_PREFIX, KBS_PROTOCOL_VERSION,
+        is_policy_denied, ClientTee, KbsClient, KBS_GET_RESOURCE_MAX_ATTEMPT, KBS_PREFIX,
+        KBS_PROTOCOL_VERSION,
     },
     evidence_provider::EvidenceProvider,
     keypair::TeeKeyPair,
@@ -56,8 +60,15 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
     /// Perform RCAR handshake with the given kbs host. If succeeds, the client will
     /// store the token.
     ///
+    /// Every handshake starts a new session with a freshly generated
+    /// ephemeral [`TeeKeyPair`], whose public key is bound into the evidence.
+    /// The key pair of the previous session is replaced only once the new
+    /// token is got, so that responses of a session cannot be decrypted with
+    /// the key of another one.
+    ///
     /// Note: if RCAR succeeds, the http client will record the cookie with the kbs server,
     /// which means that this client can be then used to retrieve resources.
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url))]
     async fn rcar_handshake(&mut self) -> anyhow::Result<()> {
         let auth_endpoint = format!("{}/{KBS_PREFIX}/auth", self.kbs_host_url);
 
@@ -73,7 +84,7 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
         let request = Request {
             version: String::from(KBS_PROTOCOL_VERSION),
             tee,
-            extra_params: String::new(),
+            extra_params: self.extra_params.clone(),
         };
 
         debug!("send auth request to {auth_endpoint}");
@@ -89,9 +100,12 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
             .await?;
 
         debug!("get challenge: {challenge:#?}");
-        let tee_pubkey = self.tee_key.export_pubkey()?;
+        let tee_key = TeeKeyPair::new()?;
+        let tee_pubkey = tee_key.export_pubkey()?;
         let materials = vec![tee_pubkey.k_mod.as_bytes(), tee_pubkey.k_exp.as_bytes()];
-        let evidence = self.generate_evidence(challenge.nonce, materials).await?;
+        let evidence = self
+            .generate_evidence(tee, challenge.nonce, materials)
+            .await?;
         debug!("get evidence with challenge: {evidence}");
 
         let attest_endpoint = format!("{}/{KBS_PREFIX}/attest", self.kbs_host_url);
@@ -114,6 +128,7 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
                 let resp = attest_response.json::<AttestationResponseData>().await?;
                 let token = Token::new(resp.token)?;
                 self.token = Some(token);
+                self.tee_key = tee_key;
             }
             reqwest::StatusCode::UNAUTHORIZED => {
                 let error_info = attest_response.json::<ErrorInformation>().await?;
@@ -130,7 +145,12 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
         Ok(())
     }
 
-    async fn generate_evidence(&self, nonce: String, key_materials: Vec<&[u8]>) -> Result<String> {
+    async fn generate_evidence(
+        &self,
+        tee: Tee,
+        nonce: String,
+        key_materials: Vec<&[u8]>,
+    ) -> Result<String> {
         let mut hasher = Sha384::new();
         hasher.update(nonce.as_bytes());
         key_materials
@@ -138,10 +158,14 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
             .for_each(|key_material| hasher.update(key_material));
 
         let ehd = hasher.finalize().to_vec();
+        let report_data = match tee {
+            Tee::Se => se_attestation_request(&nonce, &ehd)?,
+            _ => ehd,
+        };
 
         let tee_evidence = self
             .provider
-            .get_evidence(ehd)
+            .get_evidence(report_data)
             .await
             .context("Get TEE evidence failed")
             .map_err(|e| Error::GetEvidence(e.to_string()))?;
@@ -150,8 +174,21 @@ impl KbsClient<Box<dyn EvidenceProvider>> {
     }
 }
 
+/// The nonce of the challenge of IBM SE is the attestation request of the
+/// verifier, as the ARCB can only be created by the verifier. The request is
+/// given to the attester with the hash of the nonce and the key materials as
+/// the user data, which binds them into the evidence as the report data of
+/// the other TEEs does.
+fn se_attestation_request(nonce: &str, ehd: &[u8]) -> Result<Vec<u8>> {
+    let mut request: Map<String, Value> = serde_json::from_str(nonce)
+        .map_err(|e| Error::GetEvidence(format!("illegal SE attestation request: {e}")))?;
+    request.insert("user_data".into(), Value::String(STANDARD.encode(ehd)));
+    serde_json::to_vec(&request).map_err(|e| Error::GetEvidence(e.to_string()))
+}
+
 #[async_trait]
 impl KbsClientCapabilities for KbsClient<Box<dyn EvidenceProvider>> {
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url, resource = %resource_uri.resource_path()))]
     async fn get_resource(&mut self, resource_uri: ResourceUri) -> Result<Vec<u8>> {
         let remote_url = format!(
             "{}/{KBS_PREFIX}/resource/{}/{}/{}",
@@ -181,11 +218,16 @@ impl KbsClientCapabilities for KbsClient<Box<dyn EvidenceProvider>> {
                     return Ok(payload_data);
                 }
                 reqwest::StatusCode::UNAUTHORIZED => {
+                    let error_info = res
+                        .json::<ErrorInformation>()
+                        .await
+                        .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?;
+                    if is_policy_denied(&error_info) {
+                        return Err(Error::PolicyDenied(error_info.detail));
+                    }
+
                     warn!(
-                        "Authenticating with KBS failed. Perform a new RCAR handshake: {:#?}",
-                        res.json::<ErrorInformation>()
-                            .await
-                            .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?,
+                        "Authenticating with KBS failed. Perform a new RCAR handshake: {error_info:#?}"
                     );
                     self.rcar_handshake()
                         .await
@@ -218,6 +260,61 @@ impl KbsClientCapabilities for KbsClient<Box<dyn EvidenceProvider>> {
 
         Err(Error::UnAuthorized)
     }
+
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url, resource = %resource_uri.resource_path()))]
+    async fn set_resource(&mut self, resource_uri: ResourceUri, content: Vec<u8>) -> Result<()> {
+        let remote_url = format!(
+            "{}/{KBS_PREFIX}/resource/{}/{}/{}",
+            self.kbs_host_url, resource_uri.repository, resource_uri.r#type, resource_uri.tag
+        );
+
+        for attempt in 1..=KBS_GET_RESOURCE_MAX_ATTEMPT {
+            debug!("KBS client: trying to set resource to KBS, attempt {attempt}");
+
+            let res = self
+                .http_client
+                .post(&remote_url)
+                .header("Content-Type", "application/octet-stream")
+                .body(content.clone())
+                .send()
+                .await
+                .map_err(|e| Error::HttpError(format!("post failed: {e}")))?;
+
+            match res.status() {
+                reqwest::StatusCode::OK => return Ok(()),
+                reqwest::StatusCode::UNAUTHORIZED => {
+                    let error_info = res
+                        .json::<ErrorInformation>()
+                        .await
+                        .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?;
+                    if is_policy_denied(&error_info) {
+                        return Err(Error::PolicyDenied(error_info.detail));
+                    }
+
+                    warn!(
+                        "Authenticating with KBS failed. Perform a new RCAR handshake: {error_info:#?}"
+                    );
+                    self.rcar_handshake()
+                        .await
+                        .map_err(|e| Error::RcarHandshake(e.to_string()))?;
+
+                    continue;
+                }
+                _ => {
+                    let errorinfo = format!(
+                        "KBS set resource Failed, Response: {:#?}",
+                        res.json::<ErrorInformation>()
+                            .await
+                            .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?
+                    );
+
+                    return Err(Error::KbsInternalError(errorinfo));
+                }
+            }
+        }
+
+        Err(Error::UnAuthorized)
+    }
 }
 
 #[cfg(test)]
@@ -232,6 +329,20 @@ mod test {
 
     const CONTENT: &[u8] = b"test content";
 
+    #[test]
+    fn se_attestation_request() {
+        let request = super::se_attestation_request(
+            r#"{"request_blob":"AAAA","measurement_size":64,"additional_size":0}"#,
+            &[1; 48],
+        )
+        .expect("bind user data");
+        let request: serde_json::Value = serde_json::from_slice(&request).unwrap();
+        assert_eq!(request["request_blob"], "AAAA");
+        assert_eq!(request["user_data"].as_str().unwrap().len(), 64);
+
+        assert!(super::se_attestation_request("nonce", &[1; 48]).is_err());
+    }
+
     #[tokio::test]
     #[serial_test::serial]
     async fn test_client() {
@@ -294,5 +405,12 @@ mod test {
         let (token, key) = client.get_token().await.expect("get token");
         println!("Get token : {token:?}");
         println!("Get key: {key:?}");
+
+        // a new session rotates the ephemeral tee key
+        client.rcar_handshake().await.expect("new handshake");
+        assert_ne!(
+            *client.tee_key.to_pkcs1_pem().unwrap(),
+            *key.to_pkcs1_pem().unwrap()
+        );
     }
 }
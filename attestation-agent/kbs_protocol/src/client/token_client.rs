@@ -7,15 +7,17 @@ use async_trait::async_trait;
 use kbs_types::{ErrorInformation, Response};
 use log::{debug, warn};
 use resource_uri::ResourceUri;
+use tracing::instrument;
 
 use crate::{
     api::KbsClientCapabilities,
-    client::{KbsClient, KBS_GET_RESOURCE_MAX_ATTEMPT, KBS_PREFIX},
-    token_provider::TokenProvider,
+    client::{is_policy_denied, KbsClient, KBS_GET_RESOURCE_MAX_ATTEMPT, KBS_PREFIX},
+    token_provider::{Token, TokenProvider},
     Error, Result,
 };
 
 impl KbsClient<Box<dyn TokenProvider>> {
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url))]
     async fn update_token(&mut self) -> Result<()> {
         let (token, teekey) = self
             .provider
@@ -26,10 +28,22 @@ impl KbsClient<Box<dyn TokenProvider>> {
         self.tee_key = teekey;
         Ok(())
     }
+
+    /// Get a new token from the token provider, e.g. to renew the token
+    /// before it expires.
+    pub async fn refresh_token(&mut self) -> Result<()> {
+        self.update_token().await
+    }
+
+    /// The current token, if any has been got.
+    pub fn token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
 }
 
 #[async_trait]
 impl KbsClientCapabilities for KbsClient<Box<dyn TokenProvider>> {
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url, resource = %resource_uri.resource_path()))]
     async fn get_resource(&mut self, resource_uri: ResourceUri) -> Result<Vec<u8>> {
         let remote_url = format!(
             "{}/{KBS_PREFIX}/resource/{}/{}/{}",
@@ -64,9 +78,16 @@ impl KbsClientCapabilities for KbsClient<Box<dyn TokenProvider>> {
                     return Ok(payload_data);
                 }
                 reqwest::StatusCode::UNAUTHORIZED => {
+                    let error_info = res
+                        .json::<ErrorInformation>()
+                        .await
+                        .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?;
+                    if is_policy_denied(&error_info) {
+                        return Err(Error::PolicyDenied(error_info.detail));
+                    }
+
                     warn!(
-                        "Authenticating with KBS failed. Get a new token from the token provider: {:#?}",
-                        res.json::<ErrorInformation>().await.map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?
+                        "Authenticating with KBS failed. Get a new token from the token provider: {error_info:#?}"
                     );
                     self.update_token().await?;
 
@@ -97,4 +118,62 @@ impl KbsClientCapabilities for KbsClient<Box<dyn TokenProvider>> {
 
         Err(Error::UnAuthorized)
     }
+
+    #[instrument(skip_all, fields(kbs_host = %self.kbs_host_url, resource = %resource_uri.resource_path()))]
+    async fn set_resource(&mut self, resource_uri: ResourceUri, content: Vec<u8>) -> Result<()> {
+        let remote_url = format!(
+            "{}/{KBS_PREFIX}/resource/{}/{}/{}",
+            self.kbs_host_url, resource_uri.repository, resource_uri.r#type, resource_uri.tag
+        );
+        for attempt in 1..=KBS_GET_RESOURCE_MAX_ATTEMPT {
+            debug!("KBS client: trying to set resource to KBS, attempt {attempt}");
+            if self.token.is_none() {
+                self.update_token().await?;
+            }
+
+            let token = self.token.as_ref().expect("token must have been got");
+
+            let res = self
+                .http_client
+                .post(&remote_url)
+                .bearer_auth(&token.content)
+                .header("Content-Type", "application/octet-stream")
+                .body(content.clone())
+                .send()
+                .await
+                .map_err(|e| Error::HttpError(format!("post failed: {e}")))?;
+
+            match res.status() {
+                reqwest::StatusCode::OK => return Ok(()),
+                reqwest::StatusCode::UNAUTHORIZED => {
+                    let error_info = res
+                        .json::<ErrorInformation>()
+                        .await
+                        .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?;
+                    if is_policy_denied(&error_info) {
+                        return Err(Error::PolicyDenied(error_info.detail));
+                    }
+
+                    warn!(
+                        "Authenticating with KBS failed. Get a new token from the token provider: {error_info:#?}"
+                    );
+                    self.update_token().await?;
+
+                    continue;
+                }
+                _ => {
+                    let errorinfo = format!(
+                        "KBS set resource Failed, Response: {:#?}",
+                        res.json::<ErrorInformation>()
+                            .await
+                            .map_err(|e| Error::KbsResponseDeserializationFailed(e.to_string()))?
+                    );
+
+                    return Err(Error::KbsInternalError(errorinfo));
+                }
+            }
+        }
+
+        Err(Error::UnAuthorized)
+    }
 }
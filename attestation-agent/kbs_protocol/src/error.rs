@@ -39,6 +39,9 @@ pub enum Error {
     #[error("Native Evidence Provider error: {0}")]
     NativeEvidenceProvider(String),
 
+    #[error("request denied by KBS policy: {0}")]
+    PolicyDenied(String),
+
     #[error("RCAR handshake failed: {0}")]
     RcarHandshake(String),
 
@@ -4,7 +4,7 @@
 //
 
 use async_trait::async_trait;
-use attester::{detect_tee_type, BoxedAttester};
+use attester::{detect_tee, new_attester, BoxedAttester};
 use kbs_types::Tee;
 
 use super::EvidenceProvider;
@@ -15,12 +15,10 @@ pub struct NativeEvidenceProvider(BoxedAttester);
 
 impl NativeEvidenceProvider {
     pub fn new() -> Result<Self> {
-        let tee = detect_tee_type()
-            .ok_or_else(|| Error::GetTeeTypeFailed("no supported Tee type detected.".into()))?
-            .try_into()
-            .map_err(|e| {
-                Error::NativeEvidenceProvider(format!("failed to initialize tee driver: {e}"))
-            })?;
+        let tee = detect_tee().map_err(|e| Error::GetTeeTypeFailed(e.to_string()))?;
+        let tee = new_attester(tee).map_err(|e| {
+            Error::NativeEvidenceProvider(format!("failed to initialize tee driver: {e}"))
+        })?;
         Ok(Self(tee))
     }
 }
@@ -35,7 +33,6 @@ impl EvidenceProvider for NativeEvidenceProvider {
     }
 
     async fn get_tee_type(&self) -> Result<Tee> {
-        detect_tee_type()
-            .ok_or_else(|| Error::GetTeeTypeFailed("no supported Tee type detected.".into()))
+        detect_tee().map_err(|e| Error::GetTeeTypeFailed(e.to_string()))
     }
 }
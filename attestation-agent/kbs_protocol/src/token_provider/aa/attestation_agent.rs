@@ -269,12 +269,352 @@ impl ::protobuf::reflect::ProtobufValue for GetEvidenceResponse {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:attestation_agent.GetEvidenceWithNonceRequest)
+pub struct GetEvidenceWithNonceRequest {
+    // message fields
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceRequest.Nonce)
+    pub Nonce: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceRequest.HashAlgorithm)
+    pub HashAlgorithm: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceRequest.Format)
+    pub Format: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:attestation_agent.GetEvidenceWithNonceRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetEvidenceWithNonceRequest {
+    fn default() -> &'a GetEvidenceWithNonceRequest {
+        <GetEvidenceWithNonceRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetEvidenceWithNonceRequest {
+    pub fn new() -> GetEvidenceWithNonceRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Nonce",
+            |m: &GetEvidenceWithNonceRequest| { &m.Nonce },
+            |m: &mut GetEvidenceWithNonceRequest| { &mut m.Nonce },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "HashAlgorithm",
+            |m: &GetEvidenceWithNonceRequest| { &m.HashAlgorithm },
+            |m: &mut GetEvidenceWithNonceRequest| { &mut m.HashAlgorithm },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Format",
+            |m: &GetEvidenceWithNonceRequest| { &m.Format },
+            |m: &mut GetEvidenceWithNonceRequest| { &mut m.Format },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetEvidenceWithNonceRequest>(
+            "GetEvidenceWithNonceRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetEvidenceWithNonceRequest {
+    const NAME: &'static str = "GetEvidenceWithNonceRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Nonce = is.read_string()?;
+                },
+                18 => {
+                    self.HashAlgorithm = is.read_string()?;
+                },
+                26 => {
+                    self.Format = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Nonce.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.Nonce);
+        }
+        if !self.HashAlgorithm.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.HashAlgorithm);
+        }
+        if !self.Format.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.Format);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Nonce.is_empty() {
+            os.write_string(1, &self.Nonce)?;
+        }
+        if !self.HashAlgorithm.is_empty() {
+            os.write_string(2, &self.HashAlgorithm)?;
+        }
+        if !self.Format.is_empty() {
+            os.write_string(3, &self.Format)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetEvidenceWithNonceRequest {
+        GetEvidenceWithNonceRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.Nonce.clear();
+        self.HashAlgorithm.clear();
+        self.Format.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetEvidenceWithNonceRequest {
+        static instance: GetEvidenceWithNonceRequest = GetEvidenceWithNonceRequest {
+            Nonce: ::std::string::String::new(),
+            HashAlgorithm: ::std::string::String::new(),
+            Format: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetEvidenceWithNonceRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetEvidenceWithNonceRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetEvidenceWithNonceRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetEvidenceWithNonceRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:attestation_agent.GetEvidenceWithNonceResponse)
+pub struct GetEvidenceWithNonceResponse {
+    // message fields
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceResponse.Evidence)
+    pub Evidence: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceResponse.Tee)
+    pub Tee: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceResponse.HashAlgorithm)
+    pub HashAlgorithm: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetEvidenceWithNonceResponse.Format)
+    pub Format: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:attestation_agent.GetEvidenceWithNonceResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetEvidenceWithNonceResponse {
+    fn default() -> &'a GetEvidenceWithNonceResponse {
+        <GetEvidenceWithNonceResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetEvidenceWithNonceResponse {
+    pub fn new() -> GetEvidenceWithNonceResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Evidence",
+            |m: &GetEvidenceWithNonceResponse| { &m.Evidence },
+            |m: &mut GetEvidenceWithNonceResponse| { &mut m.Evidence },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Tee",
+            |m: &GetEvidenceWithNonceResponse| { &m.Tee },
+            |m: &mut GetEvidenceWithNonceResponse| { &mut m.Tee },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "HashAlgorithm",
+            |m: &GetEvidenceWithNonceResponse| { &m.HashAlgorithm },
+            |m: &mut GetEvidenceWithNonceResponse| { &mut m.HashAlgorithm },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Format",
+            |m: &GetEvidenceWithNonceResponse| { &m.Format },
+            |m: &mut GetEvidenceWithNonceResponse| { &mut m.Format },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetEvidenceWithNonceResponse>(
+            "GetEvidenceWithNonceResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetEvidenceWithNonceResponse {
+    const NAME: &'static str = "GetEvidenceWithNonceResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Evidence = is.read_bytes()?;
+                },
+                18 => {
+                    self.Tee = is.read_string()?;
+                },
+                26 => {
+                    self.HashAlgorithm = is.read_string()?;
+                },
+                34 => {
+                    self.Format = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Evidence.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Evidence);
+        }
+        if !self.Tee.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Tee);
+        }
+        if !self.HashAlgorithm.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.HashAlgorithm);
+        }
+        if !self.Format.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.Format);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Evidence.is_empty() {
+            os.write_bytes(1, &self.Evidence)?;
+        }
+        if !self.Tee.is_empty() {
+            os.write_string(2, &self.Tee)?;
+        }
+        if !self.HashAlgorithm.is_empty() {
+            os.write_string(3, &self.HashAlgorithm)?;
+        }
+        if !self.Format.is_empty() {
+            os.write_string(4, &self.Format)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetEvidenceWithNonceResponse {
+        GetEvidenceWithNonceResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Evidence.clear();
+        self.Tee.clear();
+        self.HashAlgorithm.clear();
+        self.Format.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetEvidenceWithNonceResponse {
+        static instance: GetEvidenceWithNonceResponse = GetEvidenceWithNonceResponse {
+            Evidence: ::std::vec::Vec::new(),
+            Tee: ::std::string::String::new(),
+            HashAlgorithm: ::std::string::String::new(),
+            Format: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetEvidenceWithNonceResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetEvidenceWithNonceResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetEvidenceWithNonceResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetEvidenceWithNonceResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:attestation_agent.GetTokenRequest)
 pub struct GetTokenRequest {
     // message fields
     // @@protoc_insertion_point(field:attestation_agent.GetTokenRequest.TokenType)
     pub TokenType: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetTokenRequest.Audience)
+    pub Audience: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetTokenRequest.Nonce)
+    pub Nonce: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.GetTokenRequest.RuntimeData)
+    pub RuntimeData: ::std::vec::Vec<u8>,
     // special fields
     // @@protoc_insertion_point(special_field:attestation_agent.GetTokenRequest.special_fields)
     pub special_fields: ::protobuf::SpecialFields,
@@ -292,13 +632,28 @@ impl GetTokenRequest {
     }
 
     fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
-        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut fields = ::std::vec::Vec::with_capacity(4);
         let mut oneofs = ::std::vec::Vec::with_capacity(0);
         fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
             "TokenType",
             |m: &GetTokenRequest| { &m.TokenType },
             |m: &mut GetTokenRequest| { &mut m.TokenType },
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Audience",
+            |m: &GetTokenRequest| { &m.Audience },
+            |m: &mut GetTokenRequest| { &mut m.Audience },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Nonce",
+            |m: &GetTokenRequest| { &m.Nonce },
+            |m: &mut GetTokenRequest| { &mut m.Nonce },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "RuntimeData",
+            |m: &GetTokenRequest| { &m.RuntimeData },
+            |m: &mut GetTokenRequest| { &mut m.RuntimeData },
+        ));
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetTokenRequest>(
             "GetTokenRequest",
             fields,
@@ -320,6 +675,15 @@ impl ::protobuf::Message for GetTokenRequest {
                 10 => {
                     self.TokenType = is.read_string()?;
                 },
+                18 => {
+                    self.Audience = is.read_string()?;
+                },
+                26 => {
+                    self.Nonce = is.read_string()?;
+                },
+                34 => {
+                    self.RuntimeData = is.read_bytes()?;
+                },
                 tag => {
                     ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
                 },
@@ -335,6 +699,15 @@ impl ::protobuf::Message for GetTokenRequest {
         if !self.TokenType.is_empty() {
             my_size += ::protobuf::rt::string_size(1, &self.TokenType);
         }
+        if !self.Audience.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Audience);
+        }
+        if !self.Nonce.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.Nonce);
+        }
+        if !self.RuntimeData.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.RuntimeData);
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
         self.special_fields.cached_size().set(my_size as u32);
         my_size
@@ -344,6 +717,15 @@ impl ::protobuf::Message for GetTokenRequest {
         if !self.TokenType.is_empty() {
             os.write_string(1, &self.TokenType)?;
         }
+        if !self.Audience.is_empty() {
+            os.write_string(2, &self.Audience)?;
+        }
+        if !self.Nonce.is_empty() {
+            os.write_string(3, &self.Nonce)?;
+        }
+        if !self.RuntimeData.is_empty() {
+            os.write_bytes(4, &self.RuntimeData)?;
+        }
         os.write_unknown_fields(self.special_fields.unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -362,12 +744,18 @@ impl ::protobuf::Message for GetTokenRequest {
 
     fn clear(&mut self) {
         self.TokenType.clear();
+        self.Audience.clear();
+        self.Nonce.clear();
+        self.RuntimeData.clear();
         self.special_fields.clear();
     }
 
     fn default_instance() -> &'static GetTokenRequest {
         static instance: GetTokenRequest = GetTokenRequest {
             TokenType: ::std::string::String::new(),
+            Audience: ::std::string::String::new(),
+            Nonce: ::std::string::String::new(),
+            RuntimeData: ::std::vec::Vec::new(),
             special_fields: ::protobuf::SpecialFields::new(),
         };
         &instance
@@ -513,16 +901,313 @@ impl ::protobuf::reflect::ProtobufValue for GetTokenResponse {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:attestation_agent.ExtendRuntimeMeasurementRequest)
+pub struct ExtendRuntimeMeasurementRequest {
+    // message fields
+    // @@protoc_insertion_point(field:attestation_agent.ExtendRuntimeMeasurementRequest.Domain)
+    pub Domain: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.ExtendRuntimeMeasurementRequest.Operation)
+    pub Operation: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.ExtendRuntimeMeasurementRequest.Content)
+    pub Content: ::std::string::String,
+    // @@protoc_insertion_point(field:attestation_agent.ExtendRuntimeMeasurementRequest.RegisterIndex)
+    pub RegisterIndex: ::std::option::Option<u64>,
+    // special fields
+    // @@protoc_insertion_point(special_field:attestation_agent.ExtendRuntimeMeasurementRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ExtendRuntimeMeasurementRequest {
+    fn default() -> &'a ExtendRuntimeMeasurementRequest {
+        <ExtendRuntimeMeasurementRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ExtendRuntimeMeasurementRequest {
+    pub fn new() -> ExtendRuntimeMeasurementRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Domain",
+            |m: &ExtendRuntimeMeasurementRequest| { &m.Domain },
+            |m: &mut ExtendRuntimeMeasurementRequest| { &mut m.Domain },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Operation",
+            |m: &ExtendRuntimeMeasurementRequest| { &m.Operation },
+            |m: &mut ExtendRuntimeMeasurementRequest| { &mut m.Operation },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Content",
+            |m: &ExtendRuntimeMeasurementRequest| { &m.Content },
+            |m: &mut ExtendRuntimeMeasurementRequest| { &mut m.Content },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_option_accessor::<_, _>(
+            "RegisterIndex",
+            |m: &ExtendRuntimeMeasurementRequest| { &m.RegisterIndex },
+            |m: &mut ExtendRuntimeMeasurementRequest| { &mut m.RegisterIndex },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ExtendRuntimeMeasurementRequest>(
+            "ExtendRuntimeMeasurementRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ExtendRuntimeMeasurementRequest {
+    const NAME: &'static str = "ExtendRuntimeMeasurementRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Domain = is.read_string()?;
+                },
+                18 => {
+                    self.Operation = is.read_string()?;
+                },
+                26 => {
+                    self.Content = is.read_string()?;
+                },
+                32 => {
+                    self.RegisterIndex = ::std::option::Option::Some(is.read_uint64()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Domain.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.Domain);
+        }
+        if !self.Operation.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Operation);
+        }
+        if !self.Content.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.Content);
+        }
+        if let Some(v) = self.RegisterIndex {
+            my_size += ::protobuf::rt::uint64_size(4, v);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Domain.is_empty() {
+            os.write_string(1, &self.Domain)?;
+        }
+        if !self.Operation.is_empty() {
+            os.write_string(2, &self.Operation)?;
+        }
+        if !self.Content.is_empty() {
+            os.write_string(3, &self.Content)?;
+        }
+        if let Some(v) = self.RegisterIndex {
+            os.write_uint64(4, v)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ExtendRuntimeMeasurementRequest {
+        ExtendRuntimeMeasurementRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.Domain.clear();
+        self.Operation.clear();
+        self.Content.clear();
+        self.RegisterIndex = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ExtendRuntimeMeasurementRequest {
+        static instance: ExtendRuntimeMeasurementRequest = ExtendRuntimeMeasurementRequest {
+            Domain: ::std::string::String::new(),
+            Operation: ::std::string::String::new(),
+            Content: ::std::string::String::new(),
+            RegisterIndex: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ExtendRuntimeMeasurementRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ExtendRuntimeMeasurementRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ExtendRuntimeMeasurementRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ExtendRuntimeMeasurementRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:attestation_agent.ExtendRuntimeMeasurementResponse)
+pub struct ExtendRuntimeMeasurementResponse {
+    // special fields
+    // @@protoc_insertion_point(special_field:attestation_agent.ExtendRuntimeMeasurementResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ExtendRuntimeMeasurementResponse {
+    fn default() -> &'a ExtendRuntimeMeasurementResponse {
+        <ExtendRuntimeMeasurementResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ExtendRuntimeMeasurementResponse {
+    pub fn new() -> ExtendRuntimeMeasurementResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(0);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ExtendRuntimeMeasurementResponse>(
+            "ExtendRuntimeMeasurementResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ExtendRuntimeMeasurementResponse {
+    const NAME: &'static str = "ExtendRuntimeMeasurementResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ExtendRuntimeMeasurementResponse {
+        ExtendRuntimeMeasurementResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ExtendRuntimeMeasurementResponse {
+        static instance: ExtendRuntimeMeasurementResponse = ExtendRuntimeMeasurementResponse {
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ExtendRuntimeMeasurementResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ExtendRuntimeMeasurementResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ExtendRuntimeMeasurementResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ExtendRuntimeMeasurementResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x17attestation-agent.proto\x12\x11attestation_agent\"6\n\x12GetEviden\
     ceRequest\x12\x20\n\x0bRuntimeData\x18\x01\x20\x01(\x0cR\x0bRuntimeData\
     \"1\n\x13GetEvidenceResponse\x12\x1a\n\x08Evidence\x18\x01\x20\x01(\x0cR\
-    \x08Evidence\"/\n\x0fGetTokenRequest\x12\x1c\n\tTokenType\x18\x01\x20\
-    \x01(\tR\tTokenType\"(\n\x10GetTokenResponse\x12\x14\n\x05Token\x18\x01\
-    \x20\x01(\x0cR\x05Token2\xcc\x01\n\x17AttestationAgentService\x12\\\n\
-    \x0bGetEvidence\x12%.attestation_agent.GetEvidenceRequest\x1a&.attestati\
-    on_agent.GetEvidenceResponse\x12S\n\x08GetToken\x12\".attestation_agent.\
-    GetTokenRequest\x1a#.attestation_agent.GetTokenResponseb\x06proto3\
+    \x08Evidence\"q\n\x1bGetEvidenceWithNonceRequest\x12\x14\n\x05Nonce\x18\
+    \x01\x20\x01(\tR\x05Nonce\x12$\n\rHashAlgorithm\x18\x02\x20\x01(\tR\rHas\
+    hAlgorithm\x12\x16\n\x06Format\x18\x03\x20\x01(\tR\x06Format\"\x8a\x01\n\
+    \x1cGetEvidenceWithNonceResponse\x12\x1a\n\x08Evidence\x18\x01\x20\x01(\
+    \x0cR\x08Evidence\x12\x10\n\x03Tee\x18\x02\x20\x01(\tR\x03Tee\x12$\n\rHa\
+    shAlgorithm\x18\x03\x20\x01(\tR\rHashAlgorithm\x12\x16\n\x06Format\x18\
+    \x04\x20\x01(\tR\x06Format\"\x83\x01\n\x0fGetTokenRequest\x12\x1c\n\tTok\
+    enType\x18\x01\x20\x01(\tR\tTokenType\x12\x1a\n\x08Audience\x18\x02\x20\
+    \x01(\tR\x08Audience\x12\x14\n\x05Nonce\x18\x03\x20\x01(\tR\x05Nonce\x12\
+    \x20\n\x0bRuntimeData\x18\x04\x20\x01(\x0cR\x0bRuntimeData\"(\n\x10GetTo\
+    kenResponse\x12\x14\n\x05Token\x18\x01\x20\x01(\x0cR\x05Token\"\xae\x01\
+    \n\x1fExtendRuntimeMeasurementRequest\x12\x16\n\x06Domain\x18\x01\x20\
+    \x01(\tR\x06Domain\x12\x1c\n\tOperation\x18\x02\x20\x01(\tR\tOperation\
+    \x12\x18\n\x07Content\x18\x03\x20\x01(\tR\x07Content\x12)\n\rRegisterInd\
+    ex\x18\x04\x20\x01(\x04H\0R\rRegisterIndex\x88\x01\x01B\x10\n\x0e_Regist\
+    erIndex\"\"\n\x20ExtendRuntimeMeasurementResponse2\xcb\x03\n\x17Attestat\
+    ionAgentService\x12\\\n\x0bGetEvidence\x12%.attestation_agent.GetEvidenc\
+    eRequest\x1a&.attestation_agent.GetEvidenceResponse\x12w\n\x14GetEvidenc\
+    eWithNonce\x12..attestation_agent.GetEvidenceWithNonceRequest\x1a/.attes\
+    tation_agent.GetEvidenceWithNonceResponse\x12S\n\x08GetToken\x12\".attes\
+    tation_agent.GetTokenRequest\x1a#.attestation_agent.GetTokenResponse\x12\
+    \x83\x01\n\x18ExtendRuntimeMeasurement\x122.attestation_agent.ExtendRunt\
+    imeMeasurementRequest\x1a3.attestation_agent.ExtendRuntimeMeasurementRes\
+    ponseb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -540,11 +1225,15 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
     file_descriptor.get(|| {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(0);
-            let mut messages = ::std::vec::Vec::with_capacity(4);
+            let mut messages = ::std::vec::Vec::with_capacity(8);
             messages.push(GetEvidenceRequest::generated_message_descriptor_data());
             messages.push(GetEvidenceResponse::generated_message_descriptor_data());
+            messages.push(GetEvidenceWithNonceRequest::generated_message_descriptor_data());
+            messages.push(GetEvidenceWithNonceResponse::generated_message_descriptor_data());
             messages.push(GetTokenRequest::generated_message_descriptor_data());
             messages.push(GetTokenResponse::generated_message_descriptor_data());
+            messages.push(ExtendRuntimeMeasurementRequest::generated_message_descriptor_data());
+            messages.push(ExtendRuntimeMeasurementResponse::generated_message_descriptor_data());
             let mut enums = ::std::vec::Vec::with_capacity(0);
             ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
                 file_descriptor_proto(),
@@ -15,7 +15,8 @@ use ttrpc::context;
 use crate::{Error, Result, TeeKeyPair, Token};
 
 use self::{
-    attestation_agent::GetTokenRequest, attestation_agent_ttrpc::AttestationAgentServiceClient,
+    attestation_agent::{ExtendRuntimeMeasurementRequest, GetTokenRequest},
+    attestation_agent_ttrpc::AttestationAgentServiceClient,
 };
 
 use super::TokenProvider;
@@ -42,6 +43,49 @@ impl AATokenProvider {
         let client = AttestationAgentServiceClient::new(c);
         Ok(Self { client })
     }
+
+    /// Get a raw token of `token_type` from the attestation-agent, carrying
+    /// the json `runtime_data` claims, e.g. an `maa` token binding the public
+    /// key the secrets released to the caller are wrapped by.
+    pub async fn get_token_with_runtime_data(
+        &self,
+        token_type: &str,
+        runtime_data: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let req = GetTokenRequest {
+            TokenType: token_type.to_string(),
+            RuntimeData: runtime_data,
+            ..Default::default()
+        };
+        let reply = self
+            .client
+            .get_token(context::with_timeout(50 * 1000 * 1000 * 1000), &req)
+            .await
+            .map_err(|e| Error::AATokenProvider(format!("cal ttrpc failed: {e}")))?;
+        Ok(reply.Token)
+    }
+
+    /// Extend the runtime measurements of the TEE with the event
+    /// `<domain> <operation> <content>` by the attestation-agent, e.g. the
+    /// digest of a policy the caller relies on, so that it can be attested.
+    pub async fn extend_runtime_measurement(
+        &self,
+        domain: &str,
+        operation: &str,
+        content: &str,
+    ) -> Result<()> {
+        let req = ExtendRuntimeMeasurementRequest {
+            Domain: domain.to_string(),
+            Operation: operation.to_string(),
+            Content: content.to_string(),
+            ..Default::default()
+        };
+        self.client
+            .extend_runtime_measurement(context::with_timeout(50 * 1000 * 1000 * 1000), &req)
+            .await
+            .map_err(|e| Error::AATokenProvider(format!("cal ttrpc failed: {e}")))?;
+        Ok(())
+    }
 }
 
 #[async_trait]
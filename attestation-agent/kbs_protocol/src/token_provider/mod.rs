@@ -52,6 +52,15 @@ impl Token {
         })
     }
 
+    /// How long the token is still valid. `None` means the token never
+    /// expires, and a zero duration means the token has expired.
+    pub fn expires_in(&self) -> Option<std::time::Duration> {
+        self.exp.map(|exp| {
+            let now = Clock::now_since_epoch();
+            std::time::Duration::from_secs(exp.as_secs().saturating_sub(now.as_secs()))
+        })
+    }
+
     pub fn check_valid(&self) -> Result<()> {
         let now = Clock::now_since_epoch();
         if let Some(exp) = self.exp {
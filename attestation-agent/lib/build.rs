@@ -0,0 +1,16 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+fn main() -> std::io::Result<()> {
+    #[cfg(feature = "gen-proto")]
+    {
+        tonic_build::configure()
+            .build_server(false)
+            .out_dir("src/verifier/coco_as/")
+            .compile(&["src/verifier/coco_as/attestation.proto"], &[""])?;
+    }
+
+    Ok(())
+}
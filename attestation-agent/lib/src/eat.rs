@@ -0,0 +1,366 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Evidence as Entity Attestation Tokens (EAT, RFC 9711) for the RATS
+//! verifiers other than the CoCo AS.
+//!
+//! The EAT is a CWT of the claims below, signed by COSE_Sign1 with ES256 by
+//! a key generated for each EAT:
+//! - `iat` (6): when the EAT is issued.
+//! - `cnf` (8): the `COSE_Key` of the signing key.
+//! - `eat_nonce` (10): the nonce of the verifier.
+//! - `eat_profile` (265): [`EAT_PROFILE`].
+//! - `submods` (266): the submodule of the TEE, e.g. `tdx`, whose claims are
+//!   the evidence of the TEE in [`EVIDENCE_CLAIM`] and the hash algorithm of
+//!   its report data in [`HASH_ALGORITHM_CLAIM`].
+//!
+//! The report data of the evidence is the digest of the nonce followed by
+//! the encoded `COSE_Key`, so that the verifier trusting the evidence also
+//! trusts the key, and then the claims signed by it.
+
+use anyhow::{bail, Result};
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand::rngs::OsRng;
+
+use crate::freshness::HashAlgorithm;
+
+/// The profile of the EATs of AA.
+pub const EAT_PROFILE: &str = "tag:github.com,2023:confidential-containers/guest-components/eat";
+
+/// The private claim of the evidence of the TEE.
+pub const EVIDENCE_CLAIM: i64 = -70000;
+
+/// The private claim of the hash algorithm of the report data.
+pub const HASH_ALGORITHM_CLAIM: i64 = -70001;
+
+const IAT_CLAIM: i64 = 6;
+const CNF_CLAIM: i64 = 8;
+const EAT_NONCE_CLAIM: i64 = 10;
+const EAT_PROFILE_CLAIM: i64 = 265;
+const SUBMODS_CLAIM: i64 = 266;
+
+/// The `COSE_Key` member of the `cnf` claim.
+const CNF_COSE_KEY: i64 = 1;
+
+/// The tag of COSE_Sign1.
+const COSE_SIGN1_TAG: u64 = 18;
+
+/// The protected header `{alg: ES256}`.
+const PROTECTED_ES256: [u8; 3] = [0xa1, 0x01, 0x26];
+
+/// The nonce of an EAT is no shorter than 8 bytes and no longer than 64.
+const NONCE_LEN: std::ops::RangeInclusive<usize> = 8..=64;
+
+/// Signer of an EAT with a newly generated key.
+pub struct EatSigner {
+    key: SigningKey,
+}
+
+impl Default for EatSigner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EatSigner {
+    pub fn new() -> Self {
+        Self {
+            key: SigningKey::random(&mut OsRng),
+        }
+    }
+
+    /// The `COSE_Key` of the signing key, identified by the EC2 `x` and `y`
+    /// coordinates on P-256.
+    pub fn cose_key(&self) -> Vec<u8> {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        let mut key = Vec::new();
+        cbor::map(&mut key, 4);
+        // kty: EC2
+        cbor::int(&mut key, 1);
+        cbor::int(&mut key, 2);
+        // crv: P-256
+        cbor::int(&mut key, -1);
+        cbor::int(&mut key, 1);
+        cbor::int(&mut key, -2);
+        cbor::bytes(&mut key, point.x().expect("uncompressed point"));
+        cbor::int(&mut key, -3);
+        cbor::bytes(&mut key, point.y().expect("uncompressed point"));
+        key
+    }
+
+    /// The report data of the evidence of the EAT, binding the `nonce` and
+    /// the signing key.
+    pub fn report_data(&self, nonce: &str, hash_algorithm: HashAlgorithm) -> Result<Vec<u8>> {
+        check_nonce(nonce)?;
+        let mut data = nonce.as_bytes().to_vec();
+        data.extend(self.cose_key());
+        Ok(hash_algorithm.digest(&data))
+    }
+
+    /// Sign the EAT of the `evidence` of `tee`, whose report data is the
+    /// [`Self::report_data`] of `nonce`, issued at `iat` in seconds since
+    /// the epoch.
+    pub fn sign(
+        &self,
+        nonce: &str,
+        hash_algorithm: HashAlgorithm,
+        tee: &str,
+        evidence: &[u8],
+        iat: u64,
+    ) -> Result<Vec<u8>> {
+        check_nonce(nonce)?;
+
+        // The claims are in the order of their encoded labels, for the
+        // deterministic encoding of CBOR.
+        let mut claims = Vec::new();
+        cbor::map(&mut claims, 5);
+        cbor::int(&mut claims, IAT_CLAIM);
+        cbor::uint(&mut claims, iat);
+        cbor::int(&mut claims, CNF_CLAIM);
+        cbor::map(&mut claims, 1);
+        cbor::int(&mut claims, CNF_COSE_KEY);
+        claims.extend(self.cose_key());
+        cbor::int(&mut claims, EAT_NONCE_CLAIM);
+        cbor::bytes(&mut claims, nonce.as_bytes());
+        cbor::int(&mut claims, EAT_PROFILE_CLAIM);
+        cbor::text(&mut claims, EAT_PROFILE);
+        cbor::int(&mut claims, SUBMODS_CLAIM);
+        cbor::map(&mut claims, 1);
+        cbor::text(&mut claims, tee);
+        cbor::map(&mut claims, 2);
+        cbor::int(&mut claims, EVIDENCE_CLAIM);
+        cbor::bytes(&mut claims, evidence);
+        cbor::int(&mut claims, HASH_ALGORITHM_CLAIM);
+        cbor::text(&mut claims, &hash_algorithm.to_string());
+
+        let signature: Signature = self.key.sign(&sig_structure(&claims));
+
+        let mut token = Vec::new();
+        cbor::tag(&mut token, COSE_SIGN1_TAG);
+        cbor::array(&mut token, 4);
+        cbor::bytes(&mut token, &PROTECTED_ES256);
+        cbor::map(&mut token, 0);
+        cbor::bytes(&mut token, &claims);
+        cbor::bytes(&mut token, &signature.to_bytes());
+        Ok(token)
+    }
+}
+
+fn check_nonce(nonce: &str) -> Result<()> {
+    if !NONCE_LEN.contains(&nonce.len()) {
+        bail!(
+            "Nonce of EAT must be of {} to {} bytes",
+            NONCE_LEN.start(),
+            NONCE_LEN.end()
+        );
+    }
+
+    Ok(())
+}
+
+/// The `Sig_structure` of COSE_Sign1 signed for the `payload`, without
+/// external aad.
+fn sig_structure(payload: &[u8]) -> Vec<u8> {
+    let mut structure = Vec::new();
+    cbor::array(&mut structure, 4);
+    cbor::text(&mut structure, "Signature1");
+    cbor::bytes(&mut structure, &PROTECTED_ES256);
+    cbor::bytes(&mut structure, &[]);
+    cbor::bytes(&mut structure, payload);
+    structure
+}
+
+/// The CBOR (RFC 8949) items of the EATs, in the shortest form of their
+/// heads.
+mod cbor {
+    const UINT: u8 = 0;
+    const NINT: u8 = 1;
+    const BYTES: u8 = 2;
+    const TEXT: u8 = 3;
+    const ARRAY: u8 = 4;
+    const MAP: u8 = 5;
+    const TAG: u8 = 6;
+
+    fn head(buf: &mut Vec<u8>, major: u8, value: u64) {
+        let major = major << 5;
+        match value {
+            0..=23 => buf.push(major | value as u8),
+            24..=0xff => buf.extend([major | 24, value as u8]),
+            0x100..=0xffff => {
+                buf.push(major | 25);
+                buf.extend((value as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                buf.push(major | 26);
+                buf.extend((value as u32).to_be_bytes());
+            }
+            _ => {
+                buf.push(major | 27);
+                buf.extend(value.to_be_bytes());
+            }
+        }
+    }
+
+    pub fn uint(buf: &mut Vec<u8>, value: u64) {
+        head(buf, UINT, value);
+    }
+
+    pub fn int(buf: &mut Vec<u8>, value: i64) {
+        if value < 0 {
+            head(buf, NINT, !value as u64);
+        } else {
+            head(buf, UINT, value as u64);
+        }
+    }
+
+    pub fn bytes(buf: &mut Vec<u8>, value: &[u8]) {
+        head(buf, BYTES, value.len() as u64);
+        buf.extend(value);
+    }
+
+    pub fn text(buf: &mut Vec<u8>, value: &str) {
+        head(buf, TEXT, value.len() as u64);
+        buf.extend(value.as_bytes());
+    }
+
+    pub fn array(buf: &mut Vec<u8>, len: u64) {
+        head(buf, ARRAY, len);
+    }
+
+    pub fn map(buf: &mut Vec<u8>, len: u64) {
+        head(buf, MAP, len);
+    }
+
+    pub fn tag(buf: &mut Vec<u8>, tag: u64) {
+        head(buf, TAG, tag);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn encode(f: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut buf = Vec::new();
+        f(&mut buf);
+        buf
+    }
+
+    // The examples of the Appendix A of RFC 8949.
+    #[rstest]
+    #[case(0, "00")]
+    #[case(23, "17")]
+    #[case(24, "1818")]
+    #[case(1000, "1903e8")]
+    #[case(1000000, "1a000f4240")]
+    #[case(1000000000000, "1b000000e8d4a51000")]
+    #[case(-1, "20")]
+    #[case(-1000, "3903e7")]
+    #[case(-70000, "3a0001116f")]
+    fn cbor_int(#[case] value: i64, #[case] expected: &str) {
+        let encoded = encode(|buf| cbor::int(buf, value));
+        assert_eq!(hex(&encoded), expected);
+    }
+
+    #[test]
+    fn cbor_items() {
+        assert_eq!(
+            hex(&encode(|buf| cbor::bytes(buf, &[1, 2, 3, 4]))),
+            "4401020304"
+        );
+        assert_eq!(hex(&encode(|buf| cbor::text(buf, "IETF"))), "6449455446");
+        assert_eq!(hex(&encode(|buf| cbor::array(buf, 25))), "9819");
+        assert_eq!(hex(&encode(|buf| cbor::map(buf, 0))), "a0");
+        assert_eq!(hex(&encode(|buf| cbor::tag(buf, 18))), "d2");
+    }
+
+    #[test]
+    fn report_data() {
+        let signer = EatSigner::new();
+        let report_data = signer
+            .report_data("nonce of verifier", HashAlgorithm::Sha384)
+            .unwrap();
+        assert_eq!(report_data.len(), 48);
+
+        let mut data = b"nonce of verifier".to_vec();
+        data.extend(signer.cose_key());
+        assert_eq!(report_data, HashAlgorithm::Sha384.digest(&data));
+
+        // The report data binds the key.
+        let another = EatSigner::new()
+            .report_data("nonce of verifier", HashAlgorithm::Sha384)
+            .unwrap();
+        assert_ne!(report_data, another);
+
+        assert!(signer.report_data("short", HashAlgorithm::Sha384).is_err());
+        assert!(signer
+            .report_data(&"n".repeat(65), HashAlgorithm::Sha384)
+            .is_err());
+    }
+
+    #[test]
+    fn sign() {
+        let signer = EatSigner::new();
+        let nonce = "nonce of verifier";
+        let token = signer
+            .sign(nonce, HashAlgorithm::Sha256, "tdx", b"evidence", 1700000000)
+            .unwrap();
+
+        // 18([h'a10126', {}, payload, signature])
+        assert_eq!(token[..6], [0xd2, 0x84, 0x43, 0xa1, 0x01, 0x26]);
+        assert_eq!(token[6], 0xa0);
+        let signature = Signature::from_slice(&token[token.len() - 64..]).unwrap();
+        assert_eq!(token[token.len() - 66..token.len() - 64], [0x58, 0x40]);
+        let payload = &token[7..token.len() - 66];
+        let (len, payload) = match payload[0] {
+            0x58 => (payload[1] as usize, &payload[2..]),
+            0x59 => (
+                u16::from_be_bytes([payload[1], payload[2]]) as usize,
+                &payload[3..],
+            ),
+            head => panic!("unexpected head of payload {head:#x}"),
+        };
+        assert_eq!(len, payload.len());
+
+        let mut claims = encode(|buf| {
+            cbor::map(buf, 5);
+            cbor::int(buf, 6);
+            cbor::uint(buf, 1700000000);
+            cbor::int(buf, 8);
+            cbor::map(buf, 1);
+            cbor::int(buf, 1);
+        });
+        claims.extend(signer.cose_key());
+        claims.extend(encode(|buf| {
+            cbor::int(buf, 10);
+            cbor::bytes(buf, nonce.as_bytes());
+            cbor::int(buf, 265);
+            cbor::text(buf, EAT_PROFILE);
+            cbor::int(buf, 266);
+            cbor::map(buf, 1);
+            cbor::text(buf, "tdx");
+            cbor::map(buf, 2);
+            cbor::int(buf, EVIDENCE_CLAIM);
+            cbor::bytes(buf, b"evidence");
+            cbor::int(buf, HASH_ALGORITHM_CLAIM);
+            cbor::text(buf, "sha256");
+        }));
+        assert_eq!(payload, claims);
+
+        let key = VerifyingKey::from(&signer.key);
+        key.verify(&sig_structure(payload), &signature).unwrap();
+
+        assert!(signer
+            .sign("short", HashAlgorithm::Sha256, "tdx", b"evidence", 0)
+            .is_err());
+    }
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
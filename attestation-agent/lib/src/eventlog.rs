@@ -0,0 +1,133 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The eventlog of the runtime measurements extended by AA.
+//!
+//! Every line of the eventlog is an event entry in format
+//! `<domain> <operation> <content>`, whose SHA-384 digest is extended to the
+//! runtime measurement register of the TEE, so that the relying party can
+//! replay the eventlog against the register.
+
+use std::{fmt, path::Path};
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha384};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Path of the eventlog.
+pub const EVENTLOG_PATH: &str = "/run/attestation-agent/eventlog";
+
+/// The PCR extended if no register index is given. PCR 17 is not used by
+/// the firmware nor the bootloader.
+pub const DEFAULT_PCR_INDEX: u64 = 17;
+
+/// An entry of the eventlog.
+pub struct EventEntry<'a> {
+    /// The domain the event belongs to, to distinguish the semantics of
+    /// the events of different components, e.g. `github.com/image-rs`.
+    domain: &'a str,
+
+    /// The operation recorded by the event, e.g. `PullImage`.
+    operation: &'a str,
+
+    /// The content of the operation, e.g. the digest of the pulled image.
+    content: &'a str,
+}
+
+impl<'a> EventEntry<'a> {
+    pub fn new(domain: &'a str, operation: &'a str, content: &'a str) -> Result<Self> {
+        if domain.is_empty() || domain.contains(char::is_whitespace) {
+            bail!("Illegal event domain `{domain}`");
+        }
+        if operation.is_empty() || operation.contains(char::is_whitespace) {
+            bail!("Illegal event operation `{operation}`");
+        }
+        if content.contains(['\n', '\r']) {
+            bail!("Event content must not contain line breaks");
+        }
+
+        Ok(Self {
+            domain,
+            operation,
+            content,
+        })
+    }
+
+    /// The digest extended to the runtime measurement register.
+    pub fn digest(&self) -> Vec<u8> {
+        Sha384::digest(self.to_string()).to_vec()
+    }
+}
+
+impl fmt::Display for EventEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.domain, self.operation, self.content)
+    }
+}
+
+/// Append the `entry` as a line to the eventlog at `path`.
+pub async fn append(path: impl AsRef<Path>, entry: &EventEntry<'_>) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open eventlog {}", path.display()))?;
+    file.write_all(format!("{entry}\n").as_bytes())
+        .await
+        .context("Failed to write eventlog")?;
+    file.flush().await.context("Failed to write eventlog")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("github.com/image-rs", "PullImage", "sha256:abcd", true)]
+    #[case("image-rs", "PullImage", "", true)]
+    #[case("", "PullImage", "sha256:abcd", false)]
+    #[case("image rs", "PullImage", "sha256:abcd", false)]
+    #[case("image-rs", "Pull\tImage", "sha256:abcd", false)]
+    #[case("image-rs", "PullImage", "sha256:abcd\nx y z", false)]
+    fn new_entry(
+        #[case] domain: &str,
+        #[case] operation: &str,
+        #[case] content: &str,
+        #[case] legal: bool,
+    ) {
+        assert_eq!(EventEntry::new(domain, operation, content).is_ok(), legal);
+    }
+
+    #[tokio::test]
+    async fn append_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aa").join("eventlog");
+
+        let first = EventEntry::new("image-rs", "PullImage", "sha256:1234").unwrap();
+        let second = EventEntry::new("image-rs", "PullImage", "sha256:5678").unwrap();
+        append(&path, &first).await.unwrap();
+        append(&path, &second).await.unwrap();
+
+        let eventlog = fs::read_to_string(&path).await.unwrap();
+        assert_eq!(
+            eventlog,
+            "image-rs PullImage sha256:1234\nimage-rs PullImage sha256:5678\n"
+        );
+        assert_eq!(
+            first.digest(),
+            Sha384::digest("image-rs PullImage sha256:1234").to_vec()
+        );
+    }
+}
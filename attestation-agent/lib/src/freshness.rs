@@ -0,0 +1,114 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Evidence for the verifiers out of the KBS flow.
+//!
+//! A verifier challenges the guest with a nonce, and the digest of the nonce
+//! is set as the report data of the evidence, so that the verifier can
+//! check the evidence is fresh by recalculating the digest.
+//!
+//! The evidence is either in the native format of the KBS attestation, or
+//! wrapped in an EAT for the RATS verifiers with the `eat` feature.
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// The hash algorithms the nonce can be digested by. The digests are all
+/// no more than the 64 bytes report data of the TEEs.
+#[derive(EnumString, Display, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha256,
+    #[default]
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// The report data binding the `nonce`.
+    pub fn report_data(&self, nonce: &str) -> Result<Vec<u8>> {
+        if nonce.is_empty() {
+            bail!("Nonce must not be empty");
+        }
+
+        Ok(self.digest(nonce.as_bytes()))
+    }
+
+    /// The digest of `data`.
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// The formats the evidence can be output in.
+#[derive(EnumString, Display, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum EvidenceFormat {
+    /// The evidence of the TEE, in the same format as that of the KBS
+    /// attestation.
+    #[default]
+    Native,
+
+    /// The evidence of the TEE wrapped in an Entity Attestation Token,
+    /// encoded in CBOR and signed by COSE.
+    Eat,
+}
+
+/// Evidence binding the nonce of a verifier.
+pub struct NonceEvidence {
+    /// The TEE of the evidence, e.g. `tdx`, due to the `Tee` of kbs-types.
+    pub tee: String,
+
+    /// The hash algorithm the nonce is digested by.
+    pub hash_algorithm: HashAlgorithm,
+
+    /// The format of the evidence.
+    pub format: EvidenceFormat,
+
+    /// The evidence in the format of `format`.
+    pub evidence: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{EvidenceFormat, HashAlgorithm};
+
+    #[rstest]
+    #[case("sha256", HashAlgorithm::Sha256, 32)]
+    #[case("sha384", HashAlgorithm::Sha384, 48)]
+    #[case("sha512", HashAlgorithm::Sha512, 64)]
+    fn report_data(#[case] name: &str, #[case] algorithm: HashAlgorithm, #[case] len: usize) {
+        let parsed: HashAlgorithm = name.parse().unwrap();
+        assert_eq!(parsed, algorithm);
+        assert_eq!(algorithm.to_string(), name);
+
+        let report_data = algorithm.report_data("nonce").unwrap();
+        assert_eq!(report_data.len(), len);
+        assert_ne!(report_data, algorithm.report_data("another nonce").unwrap());
+        assert!(algorithm.report_data("").is_err());
+    }
+
+    #[test]
+    fn default_algorithm() {
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Sha384);
+        assert!("md5".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[rstest]
+    #[case("native", EvidenceFormat::Native)]
+    #[case("eat", EvidenceFormat::Eat)]
+    fn evidence_format(#[case] name: &str, #[case] format: EvidenceFormat) {
+        assert_eq!(name.parse::<EvidenceFormat>().unwrap(), format);
+        assert_eq!(format.to_string(), name);
+        assert_eq!(EvidenceFormat::default(), EvidenceFormat::Native);
+        assert!("jwt".parse::<EvidenceFormat>().is_err());
+    }
+}
@@ -7,17 +7,36 @@
 #[macro_use]
 extern crate strum;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use attester::{detect_tee_type, BoxedAttester};
+use attester::{detect_tee, new_attester};
+use eventlog::{EventEntry, DEFAULT_PCR_INDEX, EVENTLOG_PATH};
+use freshness::{EvidenceFormat, HashAlgorithm, NonceEvidence};
 use kbc::{AnnotationPacket, KbcCheckInfo, KbcInstance, KbcModuleList};
 use resource_uri::ResourceUri;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
+use token_cache::TokenCache;
+
+#[cfg(feature = "eat")]
+pub mod eat;
+pub mod eventlog;
+pub mod freshness;
+mod token_cache;
+
+pub mod verifier;
 
 #[cfg(feature = "cc_kbc")]
 mod token;
-#[cfg(feature = "cc_kbc")]
-use token::get_kbs_token;
+
+#[cfg(all(
+    feature = "maa_token",
+    any(
+        feature = "az-snp-vtpm-attester",
+        feature = "az-tdx-vtpm-attester",
+        feature = "snp-attester"
+    )
+))]
+mod maa;
 
 /// Attestation Agent (AA for short) is a rust library crate for attestation procedure
 /// in confidential containers. It provides kinds of service APIs that need to make
@@ -77,14 +96,65 @@ pub trait AttestationAPIs {
     /// Get attestation Token
     async fn get_token(&mut self, token_type: &str) -> Result<Vec<u8>>;
 
+    /// Get an attestation token of `token_type` for the `audience`. The
+    /// tokens are cached by their type and audience until they expire, so
+    /// that the consumers do not each trigger a new attestation. A token
+    /// bound to a `nonce` or carrying the json `runtime_data` claims of the
+    /// caller is always newly got and never cached.
+    async fn get_token_for_audience(
+        &mut self,
+        token_type: &str,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<Vec<u8>>;
+
     /// Get TEE hardware signed evidence that includes the runtime data.
     async fn get_evidence(&mut self, runtime_data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Get TEE hardware signed evidence binding the `nonce` of a verifier
+    /// out of the KBS flow, whose report data is the digest of the nonce by
+    /// `hash_algorithm`, SHA-384 by default. The evidence is output in
+    /// `format`, [`EvidenceFormat::Native`] by default.
+    async fn get_evidence_with_nonce(
+        &mut self,
+        nonce: &str,
+        hash_algorithm: Option<HashAlgorithm>,
+        format: Option<EvidenceFormat>,
+    ) -> Result<NonceEvidence>;
+
+    /// Extend the runtime measurement register of the TEE with the digest
+    /// of the event `<domain> <operation> <content>`, and record the event
+    /// in the eventlog. `register_index` is the index of the PCR to extend,
+    /// or [`DEFAULT_PCR_INDEX`] if not given, which is mapped to the
+    /// register of the TEE.
+    async fn extend_runtime_measurement(
+        &mut self,
+        domain: &str,
+        operation: &str,
+        content: &str,
+        register_index: Option<u64>,
+    ) -> Result<()>;
+}
+
+/// Get a new token of `token_type` and how long it is valid.
+async fn new_token(
+    token_type: &str,
+    audience: Option<&str>,
+    nonce: Option<&str>,
+    runtime_data: Option<&[u8]>,
+) -> Result<(Vec<u8>, Option<Duration>)> {
+    verifier::new_verifier(token_type)
+        .await?
+        .get_token(audience, nonce, runtime_data)
+        .await
 }
 
 /// Attestation agent to provide attestation service.
 pub struct AttestationAgent {
     kbc_module_list: KbcModuleList,
     kbc_instance_map: HashMap<String, KbcInstance>,
+    token_cache: TokenCache,
 }
 
 impl Default for AttestationAgent {
@@ -99,6 +169,7 @@ impl AttestationAgent {
         AttestationAgent {
             kbc_module_list: KbcModuleList::new(),
             kbc_instance_map: HashMap::new(),
+            token_cache: TokenCache::default(),
         }
     }
 
@@ -167,30 +238,100 @@ impl AttestationAPIs for AttestationAgent {
             .await
     }
 
-    async fn get_token(&mut self, _token_type: &str) -> Result<Vec<u8>> {
-        #[cfg(feature = "cc_kbc")]
-        {
-            let token = match _token_type {
-                "kbs" => get_kbs_token().await?,
-                typ => bail!("Unsupported token type {typ}"),
-            };
+    async fn get_token(&mut self, token_type: &str) -> Result<Vec<u8>> {
+        self.get_token_for_audience(token_type, None, None, None)
+            .await
+    }
 
-            Ok(token)
+    async fn get_token_for_audience(
+        &mut self,
+        token_type: &str,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        let cacheable = nonce.is_none() && runtime_data.is_none();
+        if cacheable {
+            if let Some(token) = self.token_cache.get(token_type, audience) {
+                return Ok(token);
+            }
         }
 
-        // TODO: remove the feature flags after refactoring AA. Currently, kbs_host_url
-        // is only set by user in aa_kbc_params when cc_kbc is enabled.
-        #[cfg(not(feature = "cc_kbc"))]
-        {
-            bail!("unimplemented!");
+        let (token, expires_in) = new_token(token_type, audience, nonce, runtime_data).await?;
+
+        if cacheable {
+            self.token_cache
+                .insert(token_type, audience, token.clone(), expires_in);
         }
+
+        Ok(token)
     }
 
     /// Get TEE hardware signed evidence that includes the runtime data.
     async fn get_evidence(&mut self, runtime_data: &[u8]) -> Result<Vec<u8>> {
-        let tee_type = detect_tee_type().ok_or(anyhow!("no supported tee type found!"))?;
-        let attester = TryInto::<BoxedAttester>::try_into(tee_type)?;
+        let tee_type = detect_tee()?;
+        let attester = new_attester(tee_type)?;
         let evidence = attester.get_evidence(runtime_data.to_vec()).await?;
         Ok(evidence.into_bytes())
     }
+
+    async fn get_evidence_with_nonce(
+        &mut self,
+        nonce: &str,
+        hash_algorithm: Option<HashAlgorithm>,
+        format: Option<EvidenceFormat>,
+    ) -> Result<NonceEvidence> {
+        let hash_algorithm = hash_algorithm.unwrap_or_default();
+        let format = format.unwrap_or_default();
+        let tee_type = detect_tee()?;
+        let attester = new_attester(tee_type)?;
+        let tee = serde_json::to_value(tee_type)?
+            .as_str()
+            .ok_or(anyhow!("illegal tee type {tee_type:?}"))?
+            .to_string();
+
+        let evidence = match format {
+            EvidenceFormat::Native => {
+                let report_data = hash_algorithm.report_data(nonce)?;
+                attester.get_evidence(report_data).await?.into_bytes()
+            }
+            #[cfg(feature = "eat")]
+            EvidenceFormat::Eat => {
+                let signer = eat::EatSigner::new();
+                let report_data = signer.report_data(nonce, hash_algorithm)?;
+                let evidence = attester.get_evidence(report_data).await?;
+                let iat = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_secs();
+                signer.sign(nonce, hash_algorithm, &tee, evidence.as_bytes(), iat)?
+            }
+            #[cfg(not(feature = "eat"))]
+            EvidenceFormat::Eat => {
+                anyhow::bail!("EAT evidence is not supported, build AA with `eat`")
+            }
+        };
+
+        Ok(NonceEvidence {
+            tee,
+            hash_algorithm,
+            format,
+            evidence,
+        })
+    }
+
+    async fn extend_runtime_measurement(
+        &mut self,
+        domain: &str,
+        operation: &str,
+        content: &str,
+        register_index: Option<u64>,
+    ) -> Result<()> {
+        let entry = EventEntry::new(domain, operation, content)?;
+        let tee_type = detect_tee()?;
+        let attester = new_attester(tee_type)?;
+        attester
+            .extend_runtime_measurement(entry.digest(), register_index.unwrap_or(DEFAULT_PCR_INDEX))
+            .await?;
+        eventlog::append(EVENTLOG_PATH, &entry).await
+    }
 }
@@ -0,0 +1,89 @@
+// Copyright (c) 2023 Microsoft Corporation
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Token provider of the Microsoft Azure Attestation (MAA) service, for the
+//! services trusting MAA, e.g. the Secure Key Release of Azure Key Vault.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use attester::detect_tee;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::fs;
+
+use crate::verifier::{jwt_expires_in, Verifier};
+
+const MAA_API_VERSION: &str = "2022-08-01";
+
+/// The kernel commandline parameter of the MAA instance url, e.g.
+/// `agent.aa_maa_url=https://sharedeus.eus.attest.azure.net`.
+const MAA_URL_PARAM: &str = "agent.aa_maa_url=";
+
+#[derive(Deserialize)]
+struct MaaResponse {
+    token: String,
+}
+
+/// The MAA instance of the guest, see [`get_maa_token`].
+pub(crate) struct Maa;
+
+#[async_trait]
+impl Verifier for Maa {
+    async fn get_token(
+        &self,
+        _audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)> {
+        get_maa_token(nonce, runtime_data).await
+    }
+}
+
+/// Get an MAA token of the guest, bound to the `nonce` and carrying the
+/// json `runtime_data` claims if given. Return the token and how long it is
+/// still valid.
+pub(crate) async fn get_maa_token(
+    nonce: Option<&str>,
+    runtime_data: Option<&[u8]>,
+) -> Result<(Vec<u8>, Option<Duration>)> {
+    let maa_url = get_maa_url_from_cmdline().await?;
+    let tee = detect_tee()?;
+    let (path, mut body) = attester::maa::attestation_request(tee, runtime_data)?;
+    if let Some(nonce) = nonce {
+        body["nonce"] = Value::String(nonce.to_string());
+    }
+
+    let url = format!("{maa_url}/{path}?api-version={MAA_API_VERSION}");
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .context("MAA attestation request failed")?;
+    if !response.status().is_success() {
+        bail!(
+            "MAA attestation failed with {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        );
+    }
+
+    let token = response.json::<MaaResponse>().await?.token;
+    let expires_in = jwt_expires_in(&token)?;
+    Ok((token.into_bytes(), expires_in))
+}
+
+async fn get_maa_url_from_cmdline() -> Result<String> {
+    let cmdline = fs::read_to_string("/proc/cmdline").await?;
+    let maa_url = cmdline
+        .split_ascii_whitespace()
+        .find_map(|para| para.strip_prefix(MAA_URL_PARAM))
+        .ok_or(anyhow!(
+            "no `agent.aa_maa_url` provided in kernel commandline!"
+        ))?;
+    Ok(maa_url.trim_end_matches('/').to_string())
+}
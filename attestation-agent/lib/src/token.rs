@@ -3,31 +3,69 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
 use kbs_protocol::{evidence_provider::NativeEvidenceProvider, KbsClientBuilder};
 use serde::Serialize;
+use serde_json::json;
 use tokio::fs;
 
+use crate::verifier::Verifier;
+
 #[derive(Serialize)]
 struct Message {
     token: String,
     tee_keypair: String,
 }
 
-pub(crate) async fn get_kbs_token() -> Result<Vec<u8>> {
+/// The KBS of `agent.aa_kbc_params`, see [`get_kbs_token`].
+pub(crate) struct Kbs;
+
+#[async_trait]
+impl Verifier for Kbs {
+    async fn get_token(
+        &self,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)> {
+        if runtime_data.is_some() {
+            bail!("Runtime data of kbs tokens is not supported");
+        }
+        get_kbs_token(audience, nonce).await
+    }
+}
+
+/// Get a KBS token for the `audience`, bound to the `nonce` if given, which
+/// are given to the KBS as the `extra-params` of the RCAR request. Return the
+/// token and how long it is still valid, `None` if it never expires.
+pub(crate) async fn get_kbs_token(
+    audience: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<(Vec<u8>, Option<Duration>)> {
     let evidence_provider = Box::new(NativeEvidenceProvider::new()?);
     let kbs_host_addr = get_kbs_host_from_cmdline().await?;
-    let mut client =
-        KbsClientBuilder::with_evidence_provider(evidence_provider, &kbs_host_addr).build()?;
+    let mut builder = KbsClientBuilder::with_evidence_provider(evidence_provider, &kbs_host_addr);
+    if audience.is_some() || nonce.is_some() {
+        let extra_params = json!({
+            "audience": audience,
+            "nonce": nonce,
+        });
+        builder = builder.set_extra_params(&extra_params.to_string());
+    }
+    let mut client = builder.build()?;
 
     let (token, tee_keypair) = client.get_token().await?;
+    let expires_in = token.expires_in();
     let message = Message {
         token: token.content,
         tee_keypair: tee_keypair.to_pkcs1_pem()?.to_string(),
     };
 
     let res = serde_json::to_vec(&message)?;
-    Ok(res)
+    Ok((res, expires_in))
 }
 
 pub(crate) async fn get_kbs_host_from_cmdline() -> Result<String> {
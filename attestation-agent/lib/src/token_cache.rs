@@ -0,0 +1,95 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! A cache of the attestation tokens, so that the consumers of AA, e.g. CDH
+//! and the workload, share a token rather than each triggering a new quote
+//! generation.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// A token is taken as expired this long before its real expiry, so that
+/// it does not expire on the way to the relying party.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(10);
+
+struct CachedToken {
+    content: Vec<u8>,
+
+    /// `None` means the token never expires.
+    expires_at: Option<Instant>,
+}
+
+/// The tokens cached by their token type and audience.
+#[derive(Default)]
+pub struct TokenCache {
+    tokens: HashMap<(String, Option<String>), CachedToken>,
+}
+
+impl TokenCache {
+    /// Get the unexpired token of `token_type` for the `audience`.
+    pub fn get(&mut self, token_type: &str, audience: Option<&str>) -> Option<Vec<u8>> {
+        let key = (token_type.to_string(), audience.map(str::to_string));
+        let token = self.tokens.get(&key)?;
+        match token.expires_at {
+            Some(expires_at) if expires_at <= Instant::now() + EXPIRY_MARGIN => {
+                self.tokens.remove(&key);
+                None
+            }
+            _ => Some(token.content.clone()),
+        }
+    }
+
+    /// Cache the token of `token_type` for the `audience`, which expires in
+    /// `expires_in`, or never if `None`.
+    pub fn insert(
+        &mut self,
+        token_type: &str,
+        audience: Option<&str>,
+        content: Vec<u8>,
+        expires_in: Option<Duration>,
+    ) {
+        let key = (token_type.to_string(), audience.map(str::to_string));
+        let token = CachedToken {
+            content,
+            expires_at: expires_in.map(|expires_in| Instant::now() + expires_in),
+        };
+        self.tokens.insert(key, token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TokenCache;
+
+    #[test]
+    fn audience_scoped() {
+        let mut cache = TokenCache::default();
+        cache.insert("kbs", None, b"default".to_vec(), None);
+        cache.insert(
+            "kbs",
+            Some("cdh"),
+            b"cdh".to_vec(),
+            Some(Duration::from_secs(3600)),
+        );
+
+        assert_eq!(cache.get("kbs", None), Some(b"default".to_vec()));
+        assert_eq!(cache.get("kbs", Some("cdh")), Some(b"cdh".to_vec()));
+        assert_eq!(cache.get("kbs", Some("workload")), None);
+        assert_eq!(cache.get("other", None), None);
+    }
+
+    #[test]
+    fn expired() {
+        let mut cache = TokenCache::default();
+        cache.insert("kbs", None, b"token".to_vec(), Some(Duration::from_secs(5)));
+
+        assert_eq!(cache.get("kbs", None), None);
+        assert!(cache.tokens.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttestationRequest {
+    /// The TEE of the evidence, e.g. `tdx`.
+    #[prost(string, tag = "1")]
+    pub tee: ::prost::alloc::string::String,
+    /// The evidence encoded by base64 URL_SAFE_NO_PAD.
+    #[prost(string, tag = "2")]
+    pub evidence: ::prost::alloc::string::String,
+    /// The hash algorithm of the digest of the runtime data, one of
+    /// `sha256`, `sha384` and `sha512`. `sha384` is used if not given.
+    #[prost(string, tag = "7")]
+    pub runtime_data_hash_algorithm: ::prost::alloc::string::String,
+    /// The ids of the policies to check the evidence against. The `default`
+    /// policy is used if not given.
+    #[prost(string, repeated, tag = "9")]
+    pub policy_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The runtime data whose digest is the report data of the evidence.
+    #[prost(oneof = "attestation_request::RuntimeData", tags = "3, 4")]
+    pub runtime_data: ::core::option::Option<attestation_request::RuntimeData>,
+}
+/// Nested message and enum types in `AttestationRequest`.
+pub mod attestation_request {
+    /// The runtime data whose digest is the report data of the evidence.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum RuntimeData {
+        /// The runtime data encoded by base64 URL_SAFE_NO_PAD.
+        #[prost(string, tag = "3")]
+        RawRuntimeData(::prost::alloc::string::String),
+        /// The runtime data as a json object, whose digest is that of the
+        /// compact string of the object with the keys of each layer in
+        /// dictionary order.
+        #[prost(string, tag = "4")]
+        StructuredRuntimeData(::prost::alloc::string::String),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttestationResponse {
+    #[prost(string, tag = "1")]
+    pub attestation_token: ::prost::alloc::string::String,
+}
+/// Generated client implementations.
+pub mod attestation_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct AttestationServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl AttestationServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> AttestationServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> AttestationServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            AttestationServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        pub async fn attestation_evaluate(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AttestationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::AttestationResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/attestation.AttestationService/AttestationEvaluate",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "attestation.AttestationService",
+                        "AttestationEvaluate",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
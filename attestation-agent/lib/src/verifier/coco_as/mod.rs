@@ -0,0 +1,75 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Tokens of the gRPC CoCo Attestation Service, which appraises the
+//! evidence of the guest directly, out of the KBS.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use tonic::{codegen::http::Uri, transport::Channel};
+
+use super::{get_evidence, jwt_expires_in, report_data, structured_runtime_data, Verifier};
+use crate::freshness::HashAlgorithm;
+use attestation::attestation_request::RuntimeData;
+use attestation::attestation_service_client::AttestationServiceClient;
+use attestation::AttestationRequest;
+
+#[rustfmt::skip]
+mod attestation;
+
+pub(crate) struct CocoAs {
+    client: AttestationServiceClient<Channel>,
+    policy_ids: Vec<String>,
+}
+
+impl CocoAs {
+    pub fn new(url: &str, policy_ids: Vec<String>) -> Result<Self> {
+        let uri: Uri = url
+            .parse()
+            .with_context(|| format!("illegal CoCo AS address {url}"))?;
+        let channel = Channel::builder(uri).connect_lazy();
+        Ok(Self {
+            client: AttestationServiceClient::new(channel),
+            policy_ids,
+        })
+    }
+}
+
+#[async_trait]
+impl Verifier for CocoAs {
+    async fn get_token(
+        &self,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)> {
+        let hash_algorithm = HashAlgorithm::default();
+        let runtime_data = structured_runtime_data(audience, nonce, runtime_data)?;
+        let (tee, evidence) =
+            get_evidence(report_data(runtime_data.as_deref(), hash_algorithm)).await?;
+
+        let request = AttestationRequest {
+            tee,
+            evidence: URL_SAFE_NO_PAD.encode(evidence),
+            runtime_data: runtime_data.map(RuntimeData::StructuredRuntimeData),
+            runtime_data_hash_algorithm: hash_algorithm.to_string(),
+            policy_ids: self.policy_ids.clone(),
+        };
+        let token = self
+            .client
+            .clone()
+            .attestation_evaluate(request)
+            .await
+            .context("CoCo AS attestation request failed")?
+            .into_inner()
+            .attestation_token;
+
+        let expires_in = jwt_expires_in(&token)?;
+        Ok((token.into_bytes(), expires_in))
+    }
+}
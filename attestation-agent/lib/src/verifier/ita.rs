@@ -0,0 +1,199 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Tokens of Intel Trust Authority (ITA), for the TDX and SGX guests.
+//!
+//! The nonce of ITA is got first, and the report data of the quote is the
+//! SHA-512 digest of the nonce, its issue time and the runtime data, which
+//! ITA recalculates to appraise the quote. The audience of the token is
+//! bound in the runtime data, which ITA puts in the token as the
+//! `attester_runtime_data` claim, so that the relying party can check the
+//! token is issued for it.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{get_evidence, jwt_expires_in, structured_runtime_data, ItaConfig, Verifier};
+use crate::freshness::HashAlgorithm;
+
+const NONCE_PATH: &str = "appraisal/v1/nonce";
+const ATTEST_PATH: &str = "appraisal/v1/attest";
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// The nonce of ITA, whose fields are encoded by base64.
+#[derive(Serialize, Deserialize)]
+struct VerifierNonce {
+    val: String,
+    iat: String,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct AttestRequest<'a> {
+    quote: String,
+    verifier_nonce: VerifierNonce,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    runtime_data: Option<String>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    policy_ids: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct AttestResponse {
+    token: String,
+}
+
+pub(crate) struct Ita {
+    url: String,
+    api_key: String,
+    policy_ids: Vec<String>,
+    client: reqwest::Client,
+}
+
+impl Ita {
+    pub async fn new(config: &ItaConfig) -> Result<Self> {
+        Ok(Self {
+            url: config.url().to_string(),
+            api_key: config.api_key().await?,
+            policy_ids: config.policy_ids.clone(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    async fn get_nonce(&self) -> Result<VerifierNonce> {
+        let response = self
+            .client
+            .get(format!("{}/{NONCE_PATH}", self.url))
+            .header(API_KEY_HEADER, &self.api_key)
+            .header(header::ACCEPT, "application/json")
+            .send()
+            .await
+            .context("ITA nonce request failed")?;
+        if !response.status().is_success() {
+            bail!(
+                "ITA nonce failed with {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl Verifier for Ita {
+    async fn get_token(
+        &self,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)> {
+        let runtime_data = structured_runtime_data(audience, nonce, runtime_data)?;
+        let verifier_nonce = self.get_nonce().await?;
+        let report_data = report_data(&verifier_nonce, runtime_data.as_deref())?;
+        let (tee, evidence) = get_evidence(report_data).await?;
+        if tee != "tdx" && tee != "sgx" {
+            bail!("ITA does not support the evidence of {tee}");
+        }
+
+        let request = AttestRequest {
+            quote: quote(&evidence)?,
+            verifier_nonce,
+            runtime_data: runtime_data.map(|runtime_data| STANDARD.encode(runtime_data)),
+            policy_ids: &self.policy_ids,
+        };
+        let response = self
+            .client
+            .post(format!("{}/{ATTEST_PATH}", self.url))
+            .header(API_KEY_HEADER, &self.api_key)
+            .header(header::ACCEPT, "application/json")
+            .json(&request)
+            .send()
+            .await
+            .context("ITA attestation request failed")?;
+        if !response.status().is_success() {
+            bail!(
+                "ITA attestation failed with {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+
+        let token = response.json::<AttestResponse>().await?.token;
+        let expires_in = jwt_expires_in(&token)?;
+        Ok((token.into_bytes(), expires_in))
+    }
+}
+
+/// The report data of the quote, binding the nonce of ITA and the runtime
+/// data.
+fn report_data(nonce: &VerifierNonce, runtime_data: Option<&str>) -> Result<Vec<u8>> {
+    let mut data = STANDARD
+        .decode(&nonce.val)
+        .context("illegal nonce of ITA")?;
+    data.extend(
+        STANDARD
+            .decode(&nonce.iat)
+            .context("illegal nonce of ITA")?,
+    );
+    if let Some(runtime_data) = runtime_data {
+        data.extend(runtime_data.as_bytes());
+    }
+    Ok(HashAlgorithm::Sha512.digest(&data))
+}
+
+/// The base64 quote of the TDX or SGX evidence.
+fn quote(evidence: &str) -> Result<String> {
+    let evidence: Value = serde_json::from_str(evidence)?;
+    evidence
+        .get("quote")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("no quote in evidence"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_data() {
+        let nonce = VerifierNonce {
+            val: STANDARD.encode("val"),
+            iat: STANDARD.encode("iat"),
+            signature: STANDARD.encode("signature"),
+        };
+        assert_eq!(
+            report_data(&nonce, None).unwrap(),
+            HashAlgorithm::Sha512.digest(b"valiat")
+        );
+        assert_eq!(
+            report_data(&nonce, Some(r#"{"nonce":"n"}"#)).unwrap(),
+            HashAlgorithm::Sha512.digest(br#"valiat{"nonce":"n"}"#)
+        );
+
+        let nonce = VerifierNonce {
+            val: "!".to_string(),
+            ..nonce
+        };
+        assert!(report_data(&nonce, None).is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(
+            quote(r#"{"cc_eventlog":null,"quote":"cXVvdGU="}"#).unwrap(),
+            "cXVvdGU="
+        );
+        assert!(quote("{}").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The verification services AA gets the attestation tokens from.
+//!
+//! Each token type is got from a [`Verifier`], the KBS for `kbs`, MAA for
+//! `maa` and Intel Trust Authority of the config in `AA_ITA_CONFIG` for
+//! `ita`. The `verifier` tokens are got from the verification service
+//! of the config in `AA_VERIFIER_CONFIG`, `/etc/attestation-agent/verifier.json`
+//! by default, so that users can pick their verification service without
+//! patching AA, e.g.
+//!
+//! ```json
+//! {
+//!     "type": "coco_as",
+//!     "url": "http://as.example.com:50004",
+//!     "policy_ids": ["default"]
+//! }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use attester::{detect_tee, new_attester};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::freshness::HashAlgorithm;
+
+#[cfg(feature = "coco_as_token")]
+mod coco_as;
+#[cfg(feature = "ita_token")]
+mod ita;
+#[cfg(feature = "veraison_token")]
+mod veraison;
+
+/// The environment variable of the path of the verifier config.
+pub const VERIFIER_CONFIG_ENV: &str = "AA_VERIFIER_CONFIG";
+
+/// The default path of the verifier config.
+pub const DEFAULT_VERIFIER_CONFIG_PATH: &str = "/etc/attestation-agent/verifier.json";
+
+/// A verification service, which appraises the evidence of the guest and
+/// issues an attestation token of the result.
+#[async_trait]
+pub trait Verifier: Send + Sync {
+    /// Get a token for the `audience`, bound to the `nonce` and carrying the
+    /// json `runtime_data` claims if given. Return the token and how long it
+    /// is still valid, `None` if it never expires.
+    async fn get_token(
+        &self,
+        audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)>;
+}
+
+/// The config of the verification service of the `verifier` tokens.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VerifierConfig {
+    /// The gRPC CoCo Attestation Service.
+    CocoAs {
+        /// The address of the gRPC service, e.g. `http://127.0.0.1:50004`.
+        url: String,
+
+        /// The policies to check the evidence against, `default` if empty.
+        #[serde(default)]
+        policy_ids: Vec<String>,
+    },
+
+    /// The challenge-response API of Veraison.
+    Veraison {
+        /// The base url of the API, e.g. `https://veraison.example.com:8080`.
+        url: String,
+
+        /// The media type of the evidence, by default
+        /// `application/eat-collection; profile="http://arm.com/CCA-SSD/1.0.0"`
+        /// on Arm CCA, the only TEE with a default media type.
+        #[serde(default)]
+        media_type: Option<String>,
+    },
+
+    /// Intel Trust Authority.
+    Ita(ItaConfig),
+}
+
+/// The regions of Intel Trust Authority.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ItaRegion {
+    #[default]
+    Us,
+    Eu,
+}
+
+impl ItaRegion {
+    /// The base url of the API of the region.
+    pub fn url(&self) -> &'static str {
+        match self {
+            ItaRegion::Us => "https://api.trustauthority.intel.com",
+            ItaRegion::Eu => "https://api.eu.trustauthority.intel.com",
+        }
+    }
+}
+
+/// The config of Intel Trust Authority, of the `ita` tokens, or of the
+/// `verifier` tokens of the `ita` type.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ItaConfig {
+    /// The region of the ITA subscription, `us` by default.
+    #[serde(default)]
+    pub region: ItaRegion,
+
+    /// The base url of the API, that of the `region` if not given.
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// The attestation API key of the ITA subscription.
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// The file of the API key, e.g. of a sealed secret unsealed by CDH into
+    /// the volume of the pod, if `api_key` is not given.
+    #[serde(default)]
+    pub api_key_file: Option<String>,
+
+    /// The policies to check the evidence against.
+    #[serde(default)]
+    pub policy_ids: Vec<String>,
+}
+
+impl ItaConfig {
+    /// The environment variable of the path of the ITA config.
+    pub const ENV: &'static str = "AA_ITA_CONFIG";
+
+    /// The default path of the ITA config.
+    pub const DEFAULT_PATH: &'static str = "/etc/attestation-agent/ita.json";
+
+    /// Load the config of `AA_ITA_CONFIG`.
+    pub async fn load() -> Result<Self> {
+        let path = std::env::var(Self::ENV).unwrap_or_else(|_| Self::DEFAULT_PATH.to_string());
+        let config = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("read ITA config {path}"))?;
+        serde_json::from_slice(&config).with_context(|| format!("parse ITA config {path}"))
+    }
+
+    /// The base url of the API.
+    pub fn url(&self) -> &str {
+        self.url
+            .as_deref()
+            .unwrap_or_else(|| self.region.url())
+            .trim_end_matches('/')
+    }
+
+    /// Get the API key, of `api_key` or read from `api_key_file`.
+    pub async fn api_key(&self) -> Result<String> {
+        let api_key = match (&self.api_key, &self.api_key_file) {
+            (Some(api_key), _) => api_key.clone(),
+            (None, Some(path)) => tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("read ITA API key {path}"))?
+                .trim()
+                .to_string(),
+            (None, None) => bail!("No API key of ITA, `api_key` or `api_key_file` must be set"),
+        };
+
+        if api_key.starts_with(SEALED_SECRET_PREFIX) {
+            bail!("API key of ITA is a sealed secret not unsealed yet");
+        }
+        if api_key.is_empty() {
+            bail!("API key of ITA is empty");
+        }
+
+        Ok(api_key)
+    }
+}
+
+/// The prefix of the sealed secrets of CDH.
+const SEALED_SECRET_PREFIX: &str = "sealed.";
+
+impl VerifierConfig {
+    /// Load the config of `path`, or of `AA_VERIFIER_CONFIG` if not given.
+    pub async fn load(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_string(),
+            None => std::env::var(VERIFIER_CONFIG_ENV)
+                .unwrap_or_else(|_| DEFAULT_VERIFIER_CONFIG_PATH.to_string()),
+        };
+        let config = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("read verifier config {path}"))?;
+        serde_json::from_slice(&config).with_context(|| format!("parse verifier config {path}"))
+    }
+
+    /// Create the verifier of the config.
+    pub async fn to_verifier(&self) -> Result<Box<dyn Verifier>> {
+        match self {
+            #[cfg(feature = "coco_as_token")]
+            VerifierConfig::CocoAs { url, policy_ids } => {
+                Ok(Box::new(coco_as::CocoAs::new(url, policy_ids.clone())?))
+            }
+            #[cfg(feature = "veraison_token")]
+            VerifierConfig::Veraison { url, media_type } => {
+                Ok(Box::new(veraison::Veraison::new(url, media_type.clone())))
+            }
+            #[cfg(feature = "ita_token")]
+            VerifierConfig::Ita(config) => Ok(Box::new(ita::Ita::new(config).await?)),
+            #[allow(unreachable_patterns)]
+            config => bail!("Unsupported verifier {config:?}, not built in AA"),
+        }
+    }
+}
+
+/// Create the verifier of `token_type`.
+pub async fn new_verifier(token_type: &str) -> Result<Box<dyn Verifier>> {
+    match token_type {
+        #[cfg(feature = "cc_kbc")]
+        "kbs" => Ok(Box::new(crate::token::Kbs)),
+        #[cfg(all(
+            feature = "maa_token",
+            any(
+                feature = "az-snp-vtpm-attester",
+                feature = "az-tdx-vtpm-attester",
+                feature = "snp-attester"
+            )
+        ))]
+        "maa" => Ok(Box::new(crate::maa::Maa)),
+        #[cfg(feature = "ita_token")]
+        "ita" => Ok(Box::new(ita::Ita::new(&ItaConfig::load().await?).await?)),
+        "verifier" => VerifierConfig::load(None).await?.to_verifier().await,
+        typ => bail!("Unsupported token type {typ}"),
+    }
+}
+
+/// Get the evidence of the TEE of the guest with the `report_data`. Return
+/// the TEE, e.g. `tdx`, and the evidence.
+#[allow(dead_code)]
+pub(crate) async fn get_evidence(report_data: Vec<u8>) -> Result<(String, String)> {
+    let tee_type = detect_tee()?;
+    let attester = new_attester(tee_type)?;
+    let evidence = attester.get_evidence(report_data).await?;
+    let tee = serde_json::to_value(tee_type)?
+        .as_str()
+        .ok_or(anyhow!("illegal tee type {tee_type:?}"))?
+        .to_string();
+    Ok((tee, evidence))
+}
+
+/// The runtime data bound to the evidence, i.e. the json `runtime_data`
+/// claims with the `audience` and the `nonce`, if any of them is given. The
+/// runtime data is the compact string of the claims with the keys of each
+/// layer in dictionary order, as the CoCo AS recalculates it.
+#[allow(dead_code)]
+pub(crate) fn structured_runtime_data(
+    audience: Option<&str>,
+    nonce: Option<&str>,
+    runtime_data: Option<&[u8]>,
+) -> Result<Option<String>> {
+    let mut claims = match runtime_data {
+        Some(runtime_data) => match serde_json::from_slice(runtime_data)? {
+            Value::Object(claims) => claims,
+            _ => bail!("Runtime data must be a json object"),
+        },
+        None if audience.is_none() && nonce.is_none() => return Ok(None),
+        None => Map::new(),
+    };
+    for (name, claim) in [("audience", audience), ("nonce", nonce)] {
+        let Some(claim) = claim else {
+            continue;
+        };
+        if claims
+            .insert(name.to_string(), Value::String(claim.to_string()))
+            .is_some()
+        {
+            bail!("Runtime data must not have a `{name}` claim with the {name} given");
+        }
+    }
+
+    Ok(Some(sort_keys(Value::Object(claims)).to_string()))
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// The report data binding the structured `runtime_data`, the digest of it
+/// by `hash_algorithm`, or empty if there is no runtime data.
+#[allow(dead_code)]
+pub(crate) fn report_data(runtime_data: Option<&str>, hash_algorithm: HashAlgorithm) -> Vec<u8> {
+    runtime_data
+        .map(|runtime_data| hash_algorithm.digest(runtime_data.as_bytes()))
+        .unwrap_or_default()
+}
+
+/// How long the JWT `token` is still valid due to its `exp` claim.
+#[allow(dead_code)]
+pub(crate) fn jwt_expires_in(token: &str) -> Result<Option<Duration>> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let claims = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| anyhow!("illegal JWT token format"))?;
+    let claims: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(claims)?)?;
+    let Some(exp) = claims.get("exp").and_then(Value::as_u64) else {
+        return Ok(None);
+    };
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(Some(Duration::from_secs(exp.saturating_sub(now))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    #[test]
+    fn verifier_config() {
+        let config: VerifierConfig =
+            serde_json::from_str(r#"{"type": "coco_as", "url": "http://127.0.0.1:50004"}"#)
+                .unwrap();
+        assert_eq!(
+            config,
+            VerifierConfig::CocoAs {
+                url: "http://127.0.0.1:50004".to_string(),
+                policy_ids: vec![],
+            }
+        );
+
+        let config: VerifierConfig = serde_json::from_str(
+            r#"{"type": "ita", "url": "https://ita", "api_key": "key", "policy_ids": ["p"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            VerifierConfig::Ita(ItaConfig {
+                url: Some("https://ita".to_string()),
+                api_key: Some("key".to_string()),
+                policy_ids: vec!["p".to_string()],
+                ..Default::default()
+            })
+        );
+
+        let config: VerifierConfig =
+            serde_json::from_str(r#"{"type": "veraison", "url": "https://veraison"}"#).unwrap();
+        assert_eq!(
+            config,
+            VerifierConfig::Veraison {
+                url: "https://veraison".to_string(),
+                media_type: None,
+            }
+        );
+
+        assert!(serde_json::from_str::<VerifierConfig>(r#"{"type": "veraison"}"#).is_err());
+        assert!(serde_json::from_str::<VerifierConfig>(r#"{"type": "unknown"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn ita_config() {
+        let config: ItaConfig = serde_json::from_str(r#"{"api_key": "key"}"#).unwrap();
+        assert_eq!(config.url(), "https://api.trustauthority.intel.com");
+        assert_eq!(config.api_key().await.unwrap(), "key");
+
+        let config: ItaConfig = serde_json::from_str(r#"{"region": "eu"}"#).unwrap();
+        assert_eq!(config.url(), "https://api.eu.trustauthority.intel.com");
+        assert!(config.api_key().await.is_err());
+
+        let config: ItaConfig =
+            serde_json::from_str(r#"{"region": "eu", "url": "https://ita/"}"#).unwrap();
+        assert_eq!(config.url(), "https://ita");
+        assert!(serde_json::from_str::<ItaConfig>(r#"{"region": "cn"}"#).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api-key");
+        tokio::fs::write(&path, "key\n").await.unwrap();
+        let config = ItaConfig {
+            api_key_file: Some(path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.api_key().await.unwrap(), "key");
+
+        tokio::fs::write(&path, "sealed.header.payload.signature")
+            .await
+            .unwrap();
+        assert!(config.api_key().await.is_err());
+    }
+
+    #[test]
+    fn runtime_data() {
+        assert_eq!(structured_runtime_data(None, None, None).unwrap(), None);
+        assert_eq!(
+            structured_runtime_data(None, Some("nonce"), None).unwrap(),
+            Some(r#"{"nonce":"nonce"}"#.to_string())
+        );
+        assert_eq!(
+            structured_runtime_data(
+                Some("kms"),
+                Some("nonce"),
+                Some(br#"{"z": {"b": 1, "a": [2]}, "k": "v"}"#)
+            )
+            .unwrap(),
+            Some(r#"{"audience":"kms","k":"v","nonce":"nonce","z":{"a":[2],"b":1}}"#.to_string())
+        );
+        assert!(structured_runtime_data(None, None, Some(b"[1]")).is_err());
+        assert!(structured_runtime_data(None, Some("nonce"), Some(br#"{"nonce": "x"}"#)).is_err());
+        assert!(structured_runtime_data(Some("kms"), None, Some(br#"{"audience": "x"}"#)).is_err());
+
+        let runtime_data = structured_runtime_data(None, Some("nonce"), None).unwrap();
+        assert_eq!(
+            report_data(runtime_data.as_deref(), HashAlgorithm::Sha384),
+            HashAlgorithm::Sha384.digest(br#"{"nonce":"nonce"}"#)
+        );
+        assert!(report_data(None, HashAlgorithm::Sha384).is_empty());
+    }
+
+    #[test]
+    fn token_expiry() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let claims = format!(r#"{{"exp":{}}}"#, now.as_secs() + 3600);
+        let token = format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims));
+        let valid_for = jwt_expires_in(&token).unwrap().unwrap();
+        assert!(valid_for > Duration::from_secs(3590));
+
+        let token = format!("e30.{}.sig", URL_SAFE_NO_PAD.encode("{}"));
+        assert_eq!(jwt_expires_in(&token).unwrap(), None);
+
+        assert!(jwt_expires_in("illegal").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Tokens of Veraison, i.e. the EAR attestation results of its
+//! challenge-response API.
+//!
+//! A session is created with the nonce to bind, or with a nonce generated
+//! by Veraison, and the evidence whose report data is the nonce of the
+//! session is posted to it. The EAR of the session is the token.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE},
+    Engine,
+};
+use log::warn;
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{get_evidence, jwt_expires_in, Verifier};
+
+const SESSION_PATH: &str = "challenge-response/v1/newSession";
+
+/// The size of the nonces generated by Veraison, that of the challenge of
+/// Arm CCA.
+const NONCE_SIZE: usize = 64;
+
+const CCA_MEDIA_TYPE: &str =
+    r#"application/eat-collection; profile="http://arm.com/CCA-SSD/1.0.0""#;
+
+#[derive(Deserialize)]
+struct Session {
+    nonce: String,
+    status: String,
+    #[serde(default)]
+    result: Option<String>,
+}
+
+pub(crate) struct Veraison {
+    url: String,
+    media_type: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Veraison {
+    pub fn new(url: &str, media_type: Option<String>) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            media_type,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// The media type of the evidence of `tee`.
+    fn media_type(&self, tee: &str) -> Result<&str> {
+        match (&self.media_type, tee) {
+            (Some(media_type), _) => Ok(media_type),
+            (None, "cca") => Ok(CCA_MEDIA_TYPE),
+            (None, tee) => bail!("No media type of the evidence of {tee} for Veraison"),
+        }
+    }
+
+    async fn new_session(&self, nonce: Option<&str>) -> Result<(String, Session)> {
+        let query = match nonce {
+            Some(nonce) => format!("nonce={}", URL_SAFE.encode(nonce)),
+            None => format!("nonceSize={NONCE_SIZE}"),
+        };
+        let response = self
+            .client
+            .post(format!("{}/{SESSION_PATH}?{query}", self.url))
+            .header(
+                header::ACCEPT,
+                "application/vnd.veraison.challenge-response-session+json",
+            )
+            .send()
+            .await
+            .context("Veraison session request failed")?;
+        if response.status() != StatusCode::CREATED {
+            bail!(
+                "Veraison session failed with {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| anyhow!("no location of Veraison session"))?;
+        let location = session_url(&self.url, location);
+        Ok((location, response.json().await?))
+    }
+
+    async fn appraise(&self, location: &str, session: Session) -> Result<String> {
+        let nonce = STANDARD
+            .decode(&session.nonce)
+            .context("illegal nonce of Veraison session")?;
+        let (tee, evidence) = get_evidence(nonce).await?;
+        let media_type = self.media_type(&tee)?;
+
+        let response = self
+            .client
+            .post(location)
+            .header(header::CONTENT_TYPE, media_type)
+            .header(
+                header::ACCEPT,
+                "application/vnd.veraison.challenge-response-session+json",
+            )
+            .body(evidence_body(&tee, evidence)?)
+            .send()
+            .await
+            .context("Veraison evidence request failed")?;
+        if !response.status().is_success() {
+            bail!(
+                "Veraison appraisal failed with {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            );
+        }
+
+        let session: Session = response.json().await?;
+        match (session.status.as_str(), session.result) {
+            ("complete", Some(result)) => Ok(result),
+            (status, _) => bail!("Veraison appraisal is {status}"),
+        }
+    }
+}
+
+#[async_trait]
+impl Verifier for Veraison {
+    async fn get_token(
+        &self,
+        _audience: Option<&str>,
+        nonce: Option<&str>,
+        runtime_data: Option<&[u8]>,
+    ) -> Result<(Vec<u8>, Option<Duration>)> {
+        if runtime_data.is_some() {
+            bail!("Runtime data of Veraison tokens is not supported");
+        }
+
+        let (location, session) = self.new_session(nonce).await?;
+        let result = self.appraise(&location, session).await;
+
+        // The session expires anyway if this fails.
+        if let Err(e) = self.client.delete(&location).send().await {
+            warn!("failed to delete Veraison session {location}: {e}");
+        }
+
+        let token = result?;
+        let expires_in = jwt_expires_in(&token)?;
+        Ok((token.into_bytes(), expires_in))
+    }
+}
+
+/// The url of the session of `location`, relative to the session API.
+fn session_url(url: &str, location: &str) -> String {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return location.to_string();
+    }
+
+    match location.strip_prefix('/') {
+        Some(path) => format!("{url}/{path}"),
+        None => format!("{url}/challenge-response/v1/{location}"),
+    }
+}
+
+/// The evidence of `tee` as Veraison takes it, i.e. the raw CCA token on
+/// Arm CCA, or the evidence of AA otherwise.
+fn evidence_body(tee: &str, evidence: String) -> Result<Vec<u8>> {
+    if tee != "cca" {
+        return Ok(evidence.into_bytes());
+    }
+
+    let evidence: Value = serde_json::from_str(&evidence)?;
+    let token = evidence
+        .get("token")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("no token in CCA evidence"))?
+        .iter()
+        .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| anyhow!("illegal token in CCA evidence"))?;
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_url() {
+        let url = "https://veraison:8080";
+        assert_eq!(
+            session_url(url, "session/1234"),
+            "https://veraison:8080/challenge-response/v1/session/1234"
+        );
+        assert_eq!(
+            session_url(url, "/challenge-response/v1/session/1234"),
+            "https://veraison:8080/challenge-response/v1/session/1234"
+        );
+        assert_eq!(
+            session_url(url, "https://other/session/1234"),
+            "https://other/session/1234"
+        );
+    }
+
+    #[test]
+    fn test_media_type() {
+        let veraison = Veraison::new("https://veraison", None);
+        assert_eq!(veraison.media_type("cca").unwrap(), CCA_MEDIA_TYPE);
+        assert!(veraison.media_type("tdx").is_err());
+
+        let veraison = Veraison::new("https://veraison", Some("application/tdx".to_string()));
+        assert_eq!(veraison.media_type("tdx").unwrap(), "application/tdx");
+    }
+
+    #[test]
+    fn test_evidence_body() {
+        assert_eq!(
+            evidence_body("cca", r#"{"token":[210,132,0]}"#.to_string()).unwrap(),
+            vec![210, 132, 0]
+        );
+        assert!(evidence_body("cca", r#"{"token":[256]}"#.to_string()).is_err());
+        assert!(evidence_body("cca", "{}".to_string()).is_err());
+        assert_eq!(
+            evidence_body("tdx", r#"{"quote":""}"#.to_string()).unwrap(),
+            br#"{"quote":""}"#
+        );
+    }
+}
@@ -44,4 +44,7 @@ fn main() {
             "client",
         );
     }
+
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("./protos/api.proto").expect("Generate grpc protocol code failed.");
 }
@@ -4,26 +4,75 @@
 //
 
 use async_trait::async_trait;
+use kms::{Annotations, ProviderSettings, SecretBytes};
+use tokio::io::AsyncWrite;
 
-use crate::Result;
+use crate::{Result, SecureMount};
 
 /// The APIs of the DataHub. See
 /// <https://github.com/confidential-containers/documentation/issues/131> for
-/// more information.
+/// more information. The plaintext secrets, keys and resources are returned
+/// as [`SecretBytes`], which are locked in memory and zeroed on drop.
 #[async_trait]
 pub trait DataHub {
     /// Unseal the given sealed secret. The sealed secret format is defined
     /// in <https://github.com/confidential-containers/guest-components/blob/main/confidential-data-hub/docs/SEALED_SECRET.md>
-    async fn unseal_secret(&self, secret: Vec<u8>) -> Result<Vec<u8>>;
+    async fn unseal_secret(&self, secret: Vec<u8>) -> Result<SecretBytes>;
+
+    /// Seal the plaintext of the given envelope sealed secret again with a
+    /// new data encryption key, encrypted by the KMS key `key_id`, or the
+    /// same key if not given, e.g. after the KMS rotates the key. The new
+    /// sealed secret is returned in format `sealed.<base64 json>`.
+    async fn reseal_secret(&self, secret: Vec<u8>, key_id: Option<String>) -> Result<Vec<u8>>;
 
     /// Unwrap the LEK inside the image annotation. This API is used in
     /// `ocicrypt`'s `KeyProvider`. The received parameter should be an
     /// AnnotationPacket. Please refer to
     /// <https://github.com/confidential-containers/guest-components/blob/main/attestation-agent/docs/IMAGE_ENCRYPTION.md#annotation-packet>
-    async fn unwrap_key(&self, annotation: &[u8]) -> Result<Vec<u8>>;
+    async fn unwrap_key(&self, annotation: &[u8]) -> Result<SecretBytes>;
 
     /// Get the resource due to the given KBS Resource URI. The KBS Resource
     /// URI is defined in
     /// <https://github.com/confidential-containers/guest-components/blob/main/attestation-agent/docs/KBS_URI.md>
-    async fn get_resource(&self, uri: String) -> Result<Vec<u8>>;
+    async fn get_resource(&self, uri: String) -> Result<SecretBytes>;
+
+    /// Write the resource of the KBS Resource URI to the `writer`, and return
+    /// the size of it. This is for large resources, which are not cached
+    /// and not put into one message as a whole.
+    async fn write_resource(
+        &self,
+        uri: String,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64>;
+
+    /// Sign the `message` with the private key `key_id` held by the KMS
+    /// `provider`. The `provider_settings` and the `annotations` are those
+    /// of the KMS provider, see the docs of the KMS providers.
+    async fn sign(
+        &self,
+        provider: &str,
+        provider_settings: ProviderSettings,
+        key_id: &str,
+        message: &[u8],
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>>;
+
+    /// Pull the image `image_url`, with its layers decrypted by the keys from
+    /// the KBS and the image verified by the signature policy, and unpack it
+    /// to the bundle `bundle_path`, i.e. its `rootfs` and `config.json`.
+    /// Return the id of the image.
+    async fn pull_image(&self, image_url: &str, bundle_path: &str) -> Result<String>;
+
+    /// Mount the `storage`, e.g. unlock an encrypted block device with a key
+    /// got from the KBS and mount it, and return the path it is mounted at.
+    async fn secure_mount(&self, storage: SecureMount) -> Result<String>;
+
+    /// Unmount the storage mounted by [`DataHub::secure_mount`] at the
+    /// `mount_point`, and tear it down, e.g. close the encrypted device.
+    async fn unmount_secure_storage(&self, mount_point: &str) -> Result<()>;
+
+    /// Unmount the storage mounted by [`DataHub::secure_mount`] at the
+    /// `mount_point` and mount it again, e.g. to recover from a device
+    /// failure. Return the path it is mounted at.
+    async fn remount_secure_storage(&self, mount_point: &str) -> Result<String>;
 }
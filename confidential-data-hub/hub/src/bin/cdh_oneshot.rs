@@ -0,0 +1,183 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `cdh-oneshot` performs a single operation of CDH and exits, so that init
+//! containers and systemd units can unseal secrets, get resources and mount
+//! storages without running the long-lived daemon. The providers are
+//! configured in the same way as the daemon, including its config file.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use confidential_data_hub::{config::CdhConfig, hub::Hub, DataHub, SecureMount};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::{self, AsyncWriteExt},
+};
+
+#[derive(Parser)]
+#[command(name = "cdh-oneshot")]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// path of the config file of CDH, by default the same one as CDH
+    #[arg(short, long)]
+    config: Option<String>,
+
+    #[command(subcommand)]
+    operation: Operation,
+}
+
+#[derive(Subcommand)]
+enum Operation {
+    /// Unseal a sealed secret
+    Unseal(UnsealArgs),
+
+    /// Get a resource by its KBS Resource URI
+    GetResource(GetResourceArgs),
+
+    /// Mount a storage, e.g. an encrypted block device, and print the path
+    /// it is mounted at
+    SecureMount(SecureMountArgs),
+}
+
+#[derive(Args)]
+struct UnsealArgs {
+    /// path of the file which contains the sealed secret
+    #[arg(short, long)]
+    file_path: String,
+
+    /// path to write the plaintext to, or stdout if not given
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct GetResourceArgs {
+    /// KBS Resource URI, e.g. `kbs:///default/key/1`
+    #[arg(short, long)]
+    uri: String,
+
+    /// path to write the resource to, or stdout if not given
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct SecureMountArgs {
+    /// type of the volume, e.g. `luks`
+    #[arg(long)]
+    volume_type: String,
+
+    /// option of the volume in format `<key>=<value>`, see the docs of the
+    /// volume types
+    #[arg(long = "option", value_parser = parse_option)]
+    options: Vec<(String, String)>,
+
+    /// option given to `mount`, e.g. `ro`
+    #[arg(long = "flag")]
+    flags: Vec<String>,
+
+    /// path to mount the storage at
+    #[arg(short, long)]
+    mount_point: String,
+}
+
+fn parse_option(option: &str) -> Result<(String, String)> {
+    let (key, value) = option
+        .split_once('=')
+        .ok_or_else(|| anyhow!("illegal option `{option}`, expected `<key>=<value>`"))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = match &cli.config {
+        Some(path) => CdhConfig::from_file(path).await?,
+        None => CdhConfig::load().await?,
+    };
+    kms::set_settings(config.settings());
+
+    let hub = Hub::new().await.context("initialize CDH failed")?;
+
+    match cli.operation {
+        Operation::Unseal(args) => {
+            let secret = fs::read(&args.file_path)
+                .await
+                .context("read sealed secret failed")?;
+            let plaintext = hub
+                .unseal_secret(secret)
+                .await
+                .context("unseal secret failed")?;
+            write_output(args.output.as_deref(), &plaintext).await?;
+        }
+        Operation::GetResource(args) => {
+            let resource = hub
+                .get_resource(args.uri)
+                .await
+                .context("get resource failed")?;
+            write_output(args.output.as_deref(), &resource).await?;
+        }
+        Operation::SecureMount(args) => {
+            let storage = SecureMount {
+                volume_type: args.volume_type,
+                options: args.options.into_iter().collect::<HashMap<_, _>>(),
+                flags: args.flags,
+                mount_point: args.mount_point,
+            };
+            let mount_path = hub
+                .secure_mount(storage)
+                .await
+                .context("secure mount failed")?;
+            println!("{mount_path}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the `content` to the file of `path`, readable only by the owner,
+/// or to stdout if no path is given.
+async fn write_output(path: Option<&str>, content: &[u8]) -> Result<()> {
+    let Some(path) = path else {
+        let mut stdout = io::stdout();
+        stdout.write_all(content).await?;
+        stdout.flush().await?;
+        return Ok(());
+    };
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .await
+        .with_context(|| format!("open {path} failed"))?;
+    file.write_all(content)
+        .await
+        .with_context(|| format!("write {path} failed"))?;
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_option;
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(
+            parse_option("key=kbs:///default/key/1").unwrap(),
+            ("key".to_string(), "kbs:///default/key/1".to_string())
+        );
+        assert_eq!(
+            parse_option("fs_type=").unwrap(),
+            ("fs_type".to_string(), String::new())
+        );
+        assert!(parse_option("fs_type").is_err());
+    }
+}
@@ -269,6 +269,268 @@ impl ::protobuf::reflect::ProtobufValue for UnsealSecretOutput {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.ResealSecretRequest)
+pub struct ResealSecretRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.ResealSecretRequest.Secret)
+    pub Secret: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:api.ResealSecretRequest.KeyId)
+    pub KeyId: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.ResealSecretRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ResealSecretRequest {
+    fn default() -> &'a ResealSecretRequest {
+        <ResealSecretRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ResealSecretRequest {
+    pub fn new() -> ResealSecretRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Secret",
+            |m: &ResealSecretRequest| { &m.Secret },
+            |m: &mut ResealSecretRequest| { &mut m.Secret },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "KeyId",
+            |m: &ResealSecretRequest| { &m.KeyId },
+            |m: &mut ResealSecretRequest| { &mut m.KeyId },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ResealSecretRequest>(
+            "ResealSecretRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ResealSecretRequest {
+    const NAME: &'static str = "ResealSecretRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Secret = is.read_bytes()?;
+                },
+                18 => {
+                    self.KeyId = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Secret.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Secret);
+        }
+        if !self.KeyId.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.KeyId);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Secret.is_empty() {
+            os.write_bytes(1, &self.Secret)?;
+        }
+        if !self.KeyId.is_empty() {
+            os.write_string(2, &self.KeyId)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ResealSecretRequest {
+        ResealSecretRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.Secret.clear();
+        self.KeyId.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ResealSecretRequest {
+        static instance: ResealSecretRequest = ResealSecretRequest {
+            Secret: ::std::vec::Vec::new(),
+            KeyId: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ResealSecretRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ResealSecretRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ResealSecretRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ResealSecretRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.ResealSecretResponse)
+pub struct ResealSecretResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.ResealSecretResponse.Secret)
+    pub Secret: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.ResealSecretResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ResealSecretResponse {
+    fn default() -> &'a ResealSecretResponse {
+        <ResealSecretResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ResealSecretResponse {
+    pub fn new() -> ResealSecretResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Secret",
+            |m: &ResealSecretResponse| { &m.Secret },
+            |m: &mut ResealSecretResponse| { &mut m.Secret },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ResealSecretResponse>(
+            "ResealSecretResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ResealSecretResponse {
+    const NAME: &'static str = "ResealSecretResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Secret = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Secret.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Secret);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Secret.is_empty() {
+            os.write_bytes(1, &self.Secret)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ResealSecretResponse {
+        ResealSecretResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Secret.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ResealSecretResponse {
+        static instance: ResealSecretResponse = ResealSecretResponse {
+            Secret: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ResealSecretResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ResealSecretResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ResealSecretResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ResealSecretResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 #[derive(PartialEq,Clone,Default,Debug)]
 // @@protoc_insertion_point(message:api.GetResourceRequest)
 pub struct GetResourceRequest {
@@ -513,16 +775,2455 @@ impl ::protobuf::reflect::ProtobufValue for GetResourceResponse {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnsealSecretsRequest)
+pub struct UnsealSecretsRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnsealSecretsRequest.Secrets)
+    pub Secrets: ::std::vec::Vec<::std::vec::Vec<u8>>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnsealSecretsRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnsealSecretsRequest {
+    fn default() -> &'a UnsealSecretsRequest {
+        <UnsealSecretsRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnsealSecretsRequest {
+    pub fn new() -> UnsealSecretsRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "Secrets",
+            |m: &UnsealSecretsRequest| { &m.Secrets },
+            |m: &mut UnsealSecretsRequest| { &mut m.Secrets },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnsealSecretsRequest>(
+            "UnsealSecretsRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnsealSecretsRequest {
+    const NAME: &'static str = "UnsealSecretsRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Secrets.push(is.read_bytes()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.Secrets {
+            my_size += ::protobuf::rt::bytes_size(1, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.Secrets {
+            os.write_bytes(1, &v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnsealSecretsRequest {
+        UnsealSecretsRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.Secrets.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnsealSecretsRequest {
+        static instance: UnsealSecretsRequest = UnsealSecretsRequest {
+            Secrets: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnsealSecretsRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnsealSecretsRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnsealSecretsRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnsealSecretsRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnsealSecretResult)
+pub struct UnsealSecretResult {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnsealSecretResult.Plaintext)
+    pub Plaintext: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:api.UnsealSecretResult.Error)
+    pub Error: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnsealSecretResult.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnsealSecretResult {
+    fn default() -> &'a UnsealSecretResult {
+        <UnsealSecretResult as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnsealSecretResult {
+    pub fn new() -> UnsealSecretResult {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Plaintext",
+            |m: &UnsealSecretResult| { &m.Plaintext },
+            |m: &mut UnsealSecretResult| { &mut m.Plaintext },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Error",
+            |m: &UnsealSecretResult| { &m.Error },
+            |m: &mut UnsealSecretResult| { &mut m.Error },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnsealSecretResult>(
+            "UnsealSecretResult",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnsealSecretResult {
+    const NAME: &'static str = "UnsealSecretResult";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Plaintext = is.read_bytes()?;
+                },
+                18 => {
+                    self.Error = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Plaintext.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Plaintext);
+        }
+        if !self.Error.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Error);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Plaintext.is_empty() {
+            os.write_bytes(1, &self.Plaintext)?;
+        }
+        if !self.Error.is_empty() {
+            os.write_string(2, &self.Error)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnsealSecretResult {
+        UnsealSecretResult::new()
+    }
+
+    fn clear(&mut self) {
+        self.Plaintext.clear();
+        self.Error.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnsealSecretResult {
+        static instance: UnsealSecretResult = UnsealSecretResult {
+            Plaintext: ::std::vec::Vec::new(),
+            Error: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnsealSecretResult {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnsealSecretResult").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnsealSecretResult {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnsealSecretResult {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnsealSecretsResponse)
+pub struct UnsealSecretsResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnsealSecretsResponse.Results)
+    pub Results: ::std::vec::Vec<UnsealSecretResult>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnsealSecretsResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnsealSecretsResponse {
+    fn default() -> &'a UnsealSecretsResponse {
+        <UnsealSecretsResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnsealSecretsResponse {
+    pub fn new() -> UnsealSecretsResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "Results",
+            |m: &UnsealSecretsResponse| { &m.Results },
+            |m: &mut UnsealSecretsResponse| { &mut m.Results },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnsealSecretsResponse>(
+            "UnsealSecretsResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnsealSecretsResponse {
+    const NAME: &'static str = "UnsealSecretsResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Results.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.Results {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.Results {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnsealSecretsResponse {
+        UnsealSecretsResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Results.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnsealSecretsResponse {
+        static instance: UnsealSecretsResponse = UnsealSecretsResponse {
+            Results: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnsealSecretsResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnsealSecretsResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnsealSecretsResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnsealSecretsResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.GetResourcesRequest)
+pub struct GetResourcesRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.GetResourcesRequest.ResourcePaths)
+    pub ResourcePaths: ::std::vec::Vec<::std::string::String>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.GetResourcesRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetResourcesRequest {
+    fn default() -> &'a GetResourcesRequest {
+        <GetResourcesRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetResourcesRequest {
+    pub fn new() -> GetResourcesRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "ResourcePaths",
+            |m: &GetResourcesRequest| { &m.ResourcePaths },
+            |m: &mut GetResourcesRequest| { &mut m.ResourcePaths },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetResourcesRequest>(
+            "GetResourcesRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetResourcesRequest {
+    const NAME: &'static str = "GetResourcesRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.ResourcePaths.push(is.read_string()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.ResourcePaths {
+            my_size += ::protobuf::rt::string_size(1, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.ResourcePaths {
+            os.write_string(1, &v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetResourcesRequest {
+        GetResourcesRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.ResourcePaths.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetResourcesRequest {
+        static instance: GetResourcesRequest = GetResourcesRequest {
+            ResourcePaths: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetResourcesRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetResourcesRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetResourcesRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetResourcesRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.GetResourceResult)
+pub struct GetResourceResult {
+    // message fields
+    // @@protoc_insertion_point(field:api.GetResourceResult.Resource)
+    pub Resource: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:api.GetResourceResult.Error)
+    pub Error: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.GetResourceResult.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetResourceResult {
+    fn default() -> &'a GetResourceResult {
+        <GetResourceResult as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetResourceResult {
+    pub fn new() -> GetResourceResult {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Resource",
+            |m: &GetResourceResult| { &m.Resource },
+            |m: &mut GetResourceResult| { &mut m.Resource },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Error",
+            |m: &GetResourceResult| { &m.Error },
+            |m: &mut GetResourceResult| { &mut m.Error },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetResourceResult>(
+            "GetResourceResult",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetResourceResult {
+    const NAME: &'static str = "GetResourceResult";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Resource = is.read_bytes()?;
+                },
+                18 => {
+                    self.Error = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Resource.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Resource);
+        }
+        if !self.Error.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Error);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Resource.is_empty() {
+            os.write_bytes(1, &self.Resource)?;
+        }
+        if !self.Error.is_empty() {
+            os.write_string(2, &self.Error)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetResourceResult {
+        GetResourceResult::new()
+    }
+
+    fn clear(&mut self) {
+        self.Resource.clear();
+        self.Error.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetResourceResult {
+        static instance: GetResourceResult = GetResourceResult {
+            Resource: ::std::vec::Vec::new(),
+            Error: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetResourceResult {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetResourceResult").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetResourceResult {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetResourceResult {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.GetResourcesResponse)
+pub struct GetResourcesResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.GetResourcesResponse.Results)
+    pub Results: ::std::vec::Vec<GetResourceResult>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.GetResourcesResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetResourcesResponse {
+    fn default() -> &'a GetResourcesResponse {
+        <GetResourcesResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetResourcesResponse {
+    pub fn new() -> GetResourcesResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "Results",
+            |m: &GetResourcesResponse| { &m.Results },
+            |m: &mut GetResourcesResponse| { &mut m.Results },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetResourcesResponse>(
+            "GetResourcesResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetResourcesResponse {
+    const NAME: &'static str = "GetResourcesResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Results.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.Results {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.Results {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetResourcesResponse {
+        GetResourcesResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Results.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetResourcesResponse {
+        static instance: GetResourcesResponse = GetResourcesResponse {
+            Results: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetResourcesResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetResourcesResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetResourcesResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetResourcesResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.StreamResourceRequest)
+pub struct StreamResourceRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.StreamResourceRequest.ResourcePath)
+    pub ResourcePath: ::std::string::String,
+    // @@protoc_insertion_point(field:api.StreamResourceRequest.Destination)
+    pub Destination: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.StreamResourceRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StreamResourceRequest {
+    fn default() -> &'a StreamResourceRequest {
+        <StreamResourceRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StreamResourceRequest {
+    pub fn new() -> StreamResourceRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ResourcePath",
+            |m: &StreamResourceRequest| { &m.ResourcePath },
+            |m: &mut StreamResourceRequest| { &mut m.ResourcePath },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Destination",
+            |m: &StreamResourceRequest| { &m.Destination },
+            |m: &mut StreamResourceRequest| { &mut m.Destination },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StreamResourceRequest>(
+            "StreamResourceRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StreamResourceRequest {
+    const NAME: &'static str = "StreamResourceRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.ResourcePath = is.read_string()?;
+                },
+                18 => {
+                    self.Destination = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.ResourcePath.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.ResourcePath);
+        }
+        if !self.Destination.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.Destination);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.ResourcePath.is_empty() {
+            os.write_string(1, &self.ResourcePath)?;
+        }
+        if !self.Destination.is_empty() {
+            os.write_string(2, &self.Destination)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StreamResourceRequest {
+        StreamResourceRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.ResourcePath.clear();
+        self.Destination.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StreamResourceRequest {
+        static instance: StreamResourceRequest = StreamResourceRequest {
+            ResourcePath: ::std::string::String::new(),
+            Destination: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for StreamResourceRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StreamResourceRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StreamResourceRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StreamResourceRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.StreamResourceResponse)
+pub struct StreamResourceResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.StreamResourceResponse.Size)
+    pub Size: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.StreamResourceResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StreamResourceResponse {
+    fn default() -> &'a StreamResourceResponse {
+        <StreamResourceResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StreamResourceResponse {
+    pub fn new() -> StreamResourceResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Size",
+            |m: &StreamResourceResponse| { &m.Size },
+            |m: &mut StreamResourceResponse| { &mut m.Size },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StreamResourceResponse>(
+            "StreamResourceResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StreamResourceResponse {
+    const NAME: &'static str = "StreamResourceResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.Size = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.Size != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.Size);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.Size != 0 {
+            os.write_uint64(1, self.Size)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StreamResourceResponse {
+        StreamResourceResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Size = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StreamResourceResponse {
+        static instance: StreamResourceResponse = StreamResourceResponse {
+            Size: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for StreamResourceResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StreamResourceResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StreamResourceResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StreamResourceResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.SignRequest)
+pub struct SignRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.SignRequest.Provider)
+    pub Provider: ::std::string::String,
+    // @@protoc_insertion_point(field:api.SignRequest.ProviderSettings)
+    pub ProviderSettings: ::std::string::String,
+    // @@protoc_insertion_point(field:api.SignRequest.KeyId)
+    pub KeyId: ::std::string::String,
+    // @@protoc_insertion_point(field:api.SignRequest.Message)
+    pub Message: ::std::vec::Vec<u8>,
+    // @@protoc_insertion_point(field:api.SignRequest.Annotations)
+    pub Annotations: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.SignRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SignRequest {
+    fn default() -> &'a SignRequest {
+        <SignRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SignRequest {
+    pub fn new() -> SignRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(5);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Provider",
+            |m: &SignRequest| { &m.Provider },
+            |m: &mut SignRequest| { &mut m.Provider },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ProviderSettings",
+            |m: &SignRequest| { &m.ProviderSettings },
+            |m: &mut SignRequest| { &mut m.ProviderSettings },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "KeyId",
+            |m: &SignRequest| { &m.KeyId },
+            |m: &mut SignRequest| { &mut m.KeyId },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Message",
+            |m: &SignRequest| { &m.Message },
+            |m: &mut SignRequest| { &mut m.Message },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Annotations",
+            |m: &SignRequest| { &m.Annotations },
+            |m: &mut SignRequest| { &mut m.Annotations },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SignRequest>(
+            "SignRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SignRequest {
+    const NAME: &'static str = "SignRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Provider = is.read_string()?;
+                },
+                18 => {
+                    self.ProviderSettings = is.read_string()?;
+                },
+                26 => {
+                    self.KeyId = is.read_string()?;
+                },
+                34 => {
+                    self.Message = is.read_bytes()?;
+                },
+                42 => {
+                    self.Annotations = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Provider.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.Provider);
+        }
+        if !self.ProviderSettings.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.ProviderSettings);
+        }
+        if !self.KeyId.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.KeyId);
+        }
+        if !self.Message.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(4, &self.Message);
+        }
+        if !self.Annotations.is_empty() {
+            my_size += ::protobuf::rt::string_size(5, &self.Annotations);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Provider.is_empty() {
+            os.write_string(1, &self.Provider)?;
+        }
+        if !self.ProviderSettings.is_empty() {
+            os.write_string(2, &self.ProviderSettings)?;
+        }
+        if !self.KeyId.is_empty() {
+            os.write_string(3, &self.KeyId)?;
+        }
+        if !self.Message.is_empty() {
+            os.write_bytes(4, &self.Message)?;
+        }
+        if !self.Annotations.is_empty() {
+            os.write_string(5, &self.Annotations)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SignRequest {
+        SignRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.Provider.clear();
+        self.ProviderSettings.clear();
+        self.KeyId.clear();
+        self.Message.clear();
+        self.Annotations.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SignRequest {
+        static instance: SignRequest = SignRequest {
+            Provider: ::std::string::String::new(),
+            ProviderSettings: ::std::string::String::new(),
+            KeyId: ::std::string::String::new(),
+            Message: ::std::vec::Vec::new(),
+            Annotations: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SignRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SignRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SignRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SignRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.SignResponse)
+pub struct SignResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.SignResponse.Signature)
+    pub Signature: ::std::vec::Vec<u8>,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.SignResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SignResponse {
+    fn default() -> &'a SignResponse {
+        <SignResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SignResponse {
+    pub fn new() -> SignResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "Signature",
+            |m: &SignResponse| { &m.Signature },
+            |m: &mut SignResponse| { &mut m.Signature },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SignResponse>(
+            "SignResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SignResponse {
+    const NAME: &'static str = "SignResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.Signature = is.read_bytes()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.Signature.is_empty() {
+            my_size += ::protobuf::rt::bytes_size(1, &self.Signature);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.Signature.is_empty() {
+            os.write_bytes(1, &self.Signature)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SignResponse {
+        SignResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.Signature.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SignResponse {
+        static instance: SignResponse = SignResponse {
+            Signature: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SignResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SignResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SignResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SignResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.SecureMountRequest)
+pub struct SecureMountRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.SecureMountRequest.VolumeType)
+    pub VolumeType: ::std::string::String,
+    // @@protoc_insertion_point(field:api.SecureMountRequest.Options)
+    pub Options: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    // @@protoc_insertion_point(field:api.SecureMountRequest.Flags)
+    pub Flags: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:api.SecureMountRequest.MountPoint)
+    pub MountPoint: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.SecureMountRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SecureMountRequest {
+    fn default() -> &'a SecureMountRequest {
+        <SecureMountRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SecureMountRequest {
+    pub fn new() -> SecureMountRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "VolumeType",
+            |m: &SecureMountRequest| { &m.VolumeType },
+            |m: &mut SecureMountRequest| { &mut m.VolumeType },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "Options",
+            |m: &SecureMountRequest| { &m.Options },
+            |m: &mut SecureMountRequest| { &mut m.Options },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "Flags",
+            |m: &SecureMountRequest| { &m.Flags },
+            |m: &mut SecureMountRequest| { &mut m.Flags },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "MountPoint",
+            |m: &SecureMountRequest| { &m.MountPoint },
+            |m: &mut SecureMountRequest| { &mut m.MountPoint },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SecureMountRequest>(
+            "SecureMountRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SecureMountRequest {
+    const NAME: &'static str = "SecureMountRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.VolumeType = is.read_string()?;
+                },
+                18 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            10 => key = is.read_string()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.Options.insert(key, value);
+                },
+                26 => {
+                    self.Flags.push(is.read_string()?);
+                },
+                34 => {
+                    self.MountPoint = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.VolumeType.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.VolumeType);
+        }
+        for (k, v) in &self.Options {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for value in &self.Flags {
+            my_size += ::protobuf::rt::string_size(3, &value);
+        };
+        if !self.MountPoint.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.MountPoint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.VolumeType.is_empty() {
+            os.write_string(1, &self.VolumeType)?;
+        }
+        for (k, v) in &self.Options {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(18)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_string(1, &k)?;
+            os.write_string(2, &v)?;
+        };
+        for v in &self.Flags {
+            os.write_string(3, &v)?;
+        };
+        if !self.MountPoint.is_empty() {
+            os.write_string(4, &self.MountPoint)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SecureMountRequest {
+        SecureMountRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.VolumeType.clear();
+        self.Options.clear();
+        self.Flags.clear();
+        self.MountPoint.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SecureMountRequest {
+        static instance: ::protobuf::rt::Lazy<SecureMountRequest> = ::protobuf::rt::Lazy::new();
+        instance.get(SecureMountRequest::new)
+    }
+}
+
+impl ::protobuf::MessageFull for SecureMountRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SecureMountRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SecureMountRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SecureMountRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.SecureMountResponse)
+pub struct SecureMountResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.SecureMountResponse.MountPath)
+    pub MountPath: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.SecureMountResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SecureMountResponse {
+    fn default() -> &'a SecureMountResponse {
+        <SecureMountResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SecureMountResponse {
+    pub fn new() -> SecureMountResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "MountPath",
+            |m: &SecureMountResponse| { &m.MountPath },
+            |m: &mut SecureMountResponse| { &mut m.MountPath },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SecureMountResponse>(
+            "SecureMountResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SecureMountResponse {
+    const NAME: &'static str = "SecureMountResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.MountPath = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.MountPath.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.MountPath);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.MountPath.is_empty() {
+            os.write_string(1, &self.MountPath)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SecureMountResponse {
+        SecureMountResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.MountPath.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SecureMountResponse {
+        static instance: SecureMountResponse = SecureMountResponse {
+            MountPath: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SecureMountResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SecureMountResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SecureMountResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SecureMountResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnmountSecureStorageRequest)
+pub struct UnmountSecureStorageRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.UnmountSecureStorageRequest.MountPoint)
+    pub MountPoint: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnmountSecureStorageRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnmountSecureStorageRequest {
+    fn default() -> &'a UnmountSecureStorageRequest {
+        <UnmountSecureStorageRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnmountSecureStorageRequest {
+    pub fn new() -> UnmountSecureStorageRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "MountPoint",
+            |m: &UnmountSecureStorageRequest| { &m.MountPoint },
+            |m: &mut UnmountSecureStorageRequest| { &mut m.MountPoint },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnmountSecureStorageRequest>(
+            "UnmountSecureStorageRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnmountSecureStorageRequest {
+    const NAME: &'static str = "UnmountSecureStorageRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.MountPoint = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.MountPoint.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.MountPoint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.MountPoint.is_empty() {
+            os.write_string(1, &self.MountPoint)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnmountSecureStorageRequest {
+        UnmountSecureStorageRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.MountPoint.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnmountSecureStorageRequest {
+        static instance: UnmountSecureStorageRequest = UnmountSecureStorageRequest {
+            MountPoint: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnmountSecureStorageRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnmountSecureStorageRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnmountSecureStorageRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnmountSecureStorageRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.UnmountSecureStorageResponse)
+pub struct UnmountSecureStorageResponse {
+    // special fields
+    // @@protoc_insertion_point(special_field:api.UnmountSecureStorageResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnmountSecureStorageResponse {
+    fn default() -> &'a UnmountSecureStorageResponse {
+        <UnmountSecureStorageResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnmountSecureStorageResponse {
+    pub fn new() -> UnmountSecureStorageResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(0);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnmountSecureStorageResponse>(
+            "UnmountSecureStorageResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnmountSecureStorageResponse {
+    const NAME: &'static str = "UnmountSecureStorageResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnmountSecureStorageResponse {
+        UnmountSecureStorageResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnmountSecureStorageResponse {
+        static instance: UnmountSecureStorageResponse = UnmountSecureStorageResponse {
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnmountSecureStorageResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnmountSecureStorageResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnmountSecureStorageResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnmountSecureStorageResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.RemountSecureStorageRequest)
+pub struct RemountSecureStorageRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.RemountSecureStorageRequest.MountPoint)
+    pub MountPoint: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.RemountSecureStorageRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RemountSecureStorageRequest {
+    fn default() -> &'a RemountSecureStorageRequest {
+        <RemountSecureStorageRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RemountSecureStorageRequest {
+    pub fn new() -> RemountSecureStorageRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "MountPoint",
+            |m: &RemountSecureStorageRequest| { &m.MountPoint },
+            |m: &mut RemountSecureStorageRequest| { &mut m.MountPoint },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RemountSecureStorageRequest>(
+            "RemountSecureStorageRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RemountSecureStorageRequest {
+    const NAME: &'static str = "RemountSecureStorageRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.MountPoint = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.MountPoint.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.MountPoint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.MountPoint.is_empty() {
+            os.write_string(1, &self.MountPoint)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RemountSecureStorageRequest {
+        RemountSecureStorageRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.MountPoint.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RemountSecureStorageRequest {
+        static instance: RemountSecureStorageRequest = RemountSecureStorageRequest {
+            MountPoint: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RemountSecureStorageRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RemountSecureStorageRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RemountSecureStorageRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RemountSecureStorageRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.RemountSecureStorageResponse)
+pub struct RemountSecureStorageResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.RemountSecureStorageResponse.MountPath)
+    pub MountPath: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.RemountSecureStorageResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RemountSecureStorageResponse {
+    fn default() -> &'a RemountSecureStorageResponse {
+        <RemountSecureStorageResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RemountSecureStorageResponse {
+    pub fn new() -> RemountSecureStorageResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "MountPath",
+            |m: &RemountSecureStorageResponse| { &m.MountPath },
+            |m: &mut RemountSecureStorageResponse| { &mut m.MountPath },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RemountSecureStorageResponse>(
+            "RemountSecureStorageResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RemountSecureStorageResponse {
+    const NAME: &'static str = "RemountSecureStorageResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.MountPath = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.MountPath.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.MountPath);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.MountPath.is_empty() {
+            os.write_string(1, &self.MountPath)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RemountSecureStorageResponse {
+        RemountSecureStorageResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.MountPath.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RemountSecureStorageResponse {
+        static instance: RemountSecureStorageResponse = RemountSecureStorageResponse {
+            MountPath: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RemountSecureStorageResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RemountSecureStorageResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RemountSecureStorageResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RemountSecureStorageResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.PullImageRequest)
+pub struct PullImageRequest {
+    // message fields
+    // @@protoc_insertion_point(field:api.PullImageRequest.ImageUrl)
+    pub ImageUrl: ::std::string::String,
+    // @@protoc_insertion_point(field:api.PullImageRequest.BundlePath)
+    pub BundlePath: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.PullImageRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a PullImageRequest {
+    fn default() -> &'a PullImageRequest {
+        <PullImageRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl PullImageRequest {
+    pub fn new() -> PullImageRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ImageUrl",
+            |m: &PullImageRequest| { &m.ImageUrl },
+            |m: &mut PullImageRequest| { &mut m.ImageUrl },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "BundlePath",
+            |m: &PullImageRequest| { &m.BundlePath },
+            |m: &mut PullImageRequest| { &mut m.BundlePath },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<PullImageRequest>(
+            "PullImageRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for PullImageRequest {
+    const NAME: &'static str = "PullImageRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.ImageUrl = is.read_string()?;
+                },
+                18 => {
+                    self.BundlePath = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.ImageUrl.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.ImageUrl);
+        }
+        if !self.BundlePath.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.BundlePath);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.ImageUrl.is_empty() {
+            os.write_string(1, &self.ImageUrl)?;
+        }
+        if !self.BundlePath.is_empty() {
+            os.write_string(2, &self.BundlePath)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> PullImageRequest {
+        PullImageRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.ImageUrl.clear();
+        self.BundlePath.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static PullImageRequest {
+        static instance: PullImageRequest = PullImageRequest {
+            ImageUrl: ::std::string::String::new(),
+            BundlePath: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for PullImageRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("PullImageRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for PullImageRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PullImageRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+#[derive(PartialEq,Clone,Default,Debug)]
+// @@protoc_insertion_point(message:api.PullImageResponse)
+pub struct PullImageResponse {
+    // message fields
+    // @@protoc_insertion_point(field:api.PullImageResponse.ImageId)
+    pub ImageId: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:api.PullImageResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a PullImageResponse {
+    fn default() -> &'a PullImageResponse {
+        <PullImageResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl PullImageResponse {
+    pub fn new() -> PullImageResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "ImageId",
+            |m: &PullImageResponse| { &m.ImageId },
+            |m: &mut PullImageResponse| { &mut m.ImageId },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<PullImageResponse>(
+            "PullImageResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for PullImageResponse {
+    const NAME: &'static str = "PullImageResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.ImageId = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.ImageId.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.ImageId);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.ImageId.is_empty() {
+            os.write_string(1, &self.ImageId)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> PullImageResponse {
+        PullImageResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.ImageId.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static PullImageResponse {
+        static instance: PullImageResponse = PullImageResponse {
+            ImageId: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for PullImageResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("PullImageResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for PullImageResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PullImageResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\tapi.proto\x12\x03api\"+\n\x11UnsealSecretInput\x12\x16\n\x06secret\
     \x18\x01\x20\x01(\x0cR\x06secret\"2\n\x12UnsealSecretOutput\x12\x1c\n\tp\
-    laintext\x18\x01\x20\x01(\x0cR\tplaintext\"8\n\x12GetResourceRequest\x12\
-    \"\n\x0cResourcePath\x18\x01\x20\x01(\tR\x0cResourcePath\"1\n\x13GetReso\
-    urceResponse\x12\x1a\n\x08Resource\x18\x01\x20\x01(\x0cR\x08Resource2V\n\
-    \x13SealedSecretService\x12?\n\x0cUnsealSecret\x12\x16.api.UnsealSecretI\
-    nput\x1a\x17.api.UnsealSecretOutput2V\n\x12GetResourceService\x12@\n\x0b\
-    GetResource\x12\x17.api.GetResourceRequest\x1a\x18.api.GetResourceRespon\
-    seb\x06proto3\
+    laintext\x18\x01\x20\x01(\x0cR\tplaintext\"C\n\x13ResealSecretRequest\
+    \x12\x16\n\x06Secret\x18\x01\x20\x01(\x0cR\x06Secret\x12\x14\n\x05KeyId\
+    \x18\x02\x20\x01(\tR\x05KeyId\".\n\x14ResealSecretResponse\x12\x16\n\x06\
+    Secret\x18\x01\x20\x01(\x0cR\x06Secret\"8\n\x12GetResourceRequest\x12\"\
+    \n\x0cResourcePath\x18\x01\x20\x01(\tR\x0cResourcePath\"1\n\x13GetResour\
+    ceResponse\x12\x1a\n\x08Resource\x18\x01\x20\x01(\x0cR\x08Resource\"0\n\
+    \x14UnsealSecretsRequest\x12\x18\n\x07Secrets\x18\x01\x20\x03(\x0cR\x07S\
+    ecrets\"H\n\x12UnsealSecretResult\x12\x1c\n\tPlaintext\x18\x01\x20\x01(\
+    \x0cR\tPlaintext\x12\x14\n\x05Error\x18\x02\x20\x01(\tR\x05Error\"J\n\
+    \x15UnsealSecretsResponse\x121\n\x07Results\x18\x01\x20\x03(\x0b2\x17.ap\
+    i.UnsealSecretResultR\x07Results\";\n\x13GetResourcesRequest\x12$\n\rRes\
+    ourcePaths\x18\x01\x20\x03(\tR\rResourcePaths\"E\n\x11GetResourceResult\
+    \x12\x1a\n\x08Resource\x18\x01\x20\x01(\x0cR\x08Resource\x12\x14\n\x05Er\
+    ror\x18\x02\x20\x01(\tR\x05Error\"H\n\x14GetResourcesResponse\x120\n\x07\
+    Results\x18\x01\x20\x03(\x0b2\x16.api.GetResourceResultR\x07Results\"]\n\
+    \x15StreamResourceRequest\x12\"\n\x0cResourcePath\x18\x01\x20\x01(\tR\
+    \x0cResourcePath\x12\x20\n\x0bDestination\x18\x02\x20\x01(\tR\x0bDestina\
+    tion\",\n\x16StreamResourceResponse\x12\x12\n\x04Size\x18\x01\x20\x01(\
+    \x04R\x04Size\"\xa7\x01\n\x0bSignRequest\x12\x1a\n\x08Provider\x18\x01\
+    \x20\x01(\tR\x08Provider\x12*\n\x10ProviderSettings\x18\x02\x20\x01(\tR\
+    \x10ProviderSettings\x12\x14\n\x05KeyId\x18\x03\x20\x01(\tR\x05KeyId\x12\
+    \x18\n\x07Message\x18\x04\x20\x01(\x0cR\x07Message\x12\x20\n\x0bAnnotati\
+    ons\x18\x05\x20\x01(\tR\x0bAnnotations\",\n\x0cSignResponse\x12\x1c\n\tS\
+    ignature\x18\x01\x20\x01(\x0cR\tSignature\"\xe6\x01\n\x12SecureMountRequ\
+    est\x12\x1e\n\nVolumeType\x18\x01\x20\x01(\tR\nVolumeType\x12>\n\x07Opti\
+    ons\x18\x02\x20\x03(\x0b2$.api.SecureMountRequest.OptionsEntryR\x07Optio\
+    ns\x12\x14\n\x05Flags\x18\x03\x20\x03(\tR\x05Flags\x12\x1e\n\nMountPoint\
+    \x18\x04\x20\x01(\tR\nMountPoint\x1a:\n\x0cOptionsEntry\x12\x10\n\x03key\
+    \x18\x01\x20\x01(\tR\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05va\
+    lue:\x028\x01\"3\n\x13SecureMountResponse\x12\x1c\n\tMountPath\x18\x01\
+    \x20\x01(\tR\tMountPath\"=\n\x1bUnmountSecureStorageRequest\x12\x1e\n\nM\
+    ountPoint\x18\x01\x20\x01(\tR\nMountPoint\"\x1e\n\x1cUnmountSecureStorag\
+    eResponse\"=\n\x1bRemountSecureStorageRequest\x12\x1e\n\nMountPoint\x18\
+    \x01\x20\x01(\tR\nMountPoint\"<\n\x1cRemountSecureStorageResponse\x12\
+    \x1c\n\tMountPath\x18\x01\x20\x01(\tR\tMountPath\"N\n\x10PullImageReques\
+    t\x12\x1a\n\x08ImageUrl\x18\x01\x20\x01(\tR\x08ImageUrl\x12\x1e\n\nBundl\
+    ePath\x18\x02\x20\x01(\tR\nBundlePath\"-\n\x11PullImageResponse\x12\x18\
+    \n\x07ImageId\x18\x01\x20\x01(\tR\x07ImageId2\xe3\x01\n\x13SealedSecretS\
+    ervice\x12?\n\x0cUnsealSecret\x12\x16.api.UnsealSecretInput\x1a\x17.api.\
+    UnsealSecretOutput\x12F\n\rUnsealSecrets\x12\x19.api.UnsealSecretsReques\
+    t\x1a\x1a.api.UnsealSecretsResponse\x12C\n\x0cResealSecret\x12\x18.api.R\
+    esealSecretRequest\x1a\x19.api.ResealSecretResponse2\xe6\x01\n\x12GetRes\
+    ourceService\x12@\n\x0bGetResource\x12\x17.api.GetResourceRequest\x1a\
+    \x18.api.GetResourceResponse\x12C\n\x0cGetResources\x12\x18.api.GetResou\
+    rcesRequest\x1a\x19.api.GetResourcesResponse\x12I\n\x0eStreamResource\
+    \x12\x1a.api.StreamResourceRequest\x1a\x1b.api.StreamResourceResponse2:\
+    \n\x0bSignService\x12+\n\x04Sign\x12\x10.api.SignRequest\x1a\x11.api.Sig\
+    nResponse2\x90\x02\n\x12SecureMountService\x12@\n\x0bSecureMount\x12\x17\
+    .api.SecureMountRequest\x1a\x18.api.SecureMountResponse\x12[\n\x14Unmoun\
+    tSecureStorage\x12\x20.api.UnmountSecureStorageRequest\x1a!.api.UnmountS\
+    ecureStorageResponse\x12[\n\x14RemountSecureStorage\x12\x20.api.RemountS\
+    ecureStorageRequest\x1a!.api.RemountSecureStorageResponse2N\n\x10ImagePu\
+    llService\x12:\n\tPullImage\x12\x15.api.PullImageRequest\x1a\x16.api.Pul\
+    lImageResponseb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -540,11 +3241,31 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
     file_descriptor.get(|| {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(0);
-            let mut messages = ::std::vec::Vec::with_capacity(4);
+            let mut messages = ::std::vec::Vec::with_capacity(24);
             messages.push(UnsealSecretInput::generated_message_descriptor_data());
             messages.push(UnsealSecretOutput::generated_message_descriptor_data());
+            messages.push(ResealSecretRequest::generated_message_descriptor_data());
+            messages.push(ResealSecretResponse::generated_message_descriptor_data());
             messages.push(GetResourceRequest::generated_message_descriptor_data());
             messages.push(GetResourceResponse::generated_message_descriptor_data());
+            messages.push(UnsealSecretsRequest::generated_message_descriptor_data());
+            messages.push(UnsealSecretResult::generated_message_descriptor_data());
+            messages.push(UnsealSecretsResponse::generated_message_descriptor_data());
+            messages.push(GetResourcesRequest::generated_message_descriptor_data());
+            messages.push(GetResourceResult::generated_message_descriptor_data());
+            messages.push(GetResourcesResponse::generated_message_descriptor_data());
+            messages.push(StreamResourceRequest::generated_message_descriptor_data());
+            messages.push(StreamResourceResponse::generated_message_descriptor_data());
+            messages.push(SignRequest::generated_message_descriptor_data());
+            messages.push(SignResponse::generated_message_descriptor_data());
+            messages.push(SecureMountRequest::generated_message_descriptor_data());
+            messages.push(SecureMountResponse::generated_message_descriptor_data());
+            messages.push(UnmountSecureStorageRequest::generated_message_descriptor_data());
+            messages.push(UnmountSecureStorageResponse::generated_message_descriptor_data());
+            messages.push(RemountSecureStorageRequest::generated_message_descriptor_data());
+            messages.push(RemountSecureStorageResponse::generated_message_descriptor_data());
+            messages.push(PullImageRequest::generated_message_descriptor_data());
+            messages.push(PullImageResponse::generated_message_descriptor_data());
             let mut enums = ::std::vec::Vec::with_capacity(0);
             ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
                 file_descriptor_proto(),
@@ -38,6 +38,16 @@ impl SealedSecretServiceClient {
         let mut cres = super::api::UnsealSecretOutput::new();
         ::ttrpc::async_client_request!(self, ctx, req, "api.SealedSecretService", "UnsealSecret", cres);
     }
+
+    pub async fn unseal_secrets(&self, ctx: ttrpc::context::Context, req: &super::api::UnsealSecretsRequest) -> ::ttrpc::Result<super::api::UnsealSecretsResponse> {
+        let mut cres = super::api::UnsealSecretsResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SealedSecretService", "UnsealSecrets", cres);
+    }
+
+    pub async fn reseal_secret(&self, ctx: ttrpc::context::Context, req: &super::api::ResealSecretRequest) -> ::ttrpc::Result<super::api::ResealSecretResponse> {
+        let mut cres = super::api::ResealSecretResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SealedSecretService", "ResealSecret", cres);
+    }
 }
 
 struct UnsealSecretMethod {
@@ -51,11 +61,39 @@ impl ::ttrpc::r#async::MethodHandler for UnsealSecretMethod {
     }
 }
 
+struct UnsealSecretsMethod {
+    service: Arc<Box<dyn SealedSecretService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnsealSecretsMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, UnsealSecretsRequest, unseal_secrets);
+    }
+}
+
+struct ResealSecretMethod {
+    service: Arc<Box<dyn SealedSecretService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for ResealSecretMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, ResealSecretRequest, reseal_secret);
+    }
+}
+
 #[async_trait]
 pub trait SealedSecretService: Sync {
     async fn unseal_secret(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::UnsealSecretInput) -> ::ttrpc::Result<super::api::UnsealSecretOutput> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SealedSecretService/UnsealSecret is not supported".to_string())))
     }
+    async fn unseal_secrets(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::UnsealSecretsRequest) -> ::ttrpc::Result<super::api::UnsealSecretsResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SealedSecretService/UnsealSecrets is not supported".to_string())))
+    }
+    async fn reseal_secret(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::ResealSecretRequest) -> ::ttrpc::Result<super::api::ResealSecretResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SealedSecretService/ResealSecret is not supported".to_string())))
+    }
 }
 
 pub fn create_sealed_secret_service(service: Arc<Box<dyn SealedSecretService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
@@ -66,6 +104,12 @@ pub fn create_sealed_secret_service(service: Arc<Box<dyn SealedSecretService + S
     methods.insert("UnsealSecret".to_string(),
                     Box::new(UnsealSecretMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
 
+    methods.insert("UnsealSecrets".to_string(),
+                    Box::new(UnsealSecretsMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("ResealSecret".to_string(),
+                    Box::new(ResealSecretMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
     ret.insert("api.SealedSecretService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
     ret
 }
@@ -86,6 +130,16 @@ impl GetResourceServiceClient {
         let mut cres = super::api::GetResourceResponse::new();
         ::ttrpc::async_client_request!(self, ctx, req, "api.GetResourceService", "GetResource", cres);
     }
+
+    pub async fn get_resources(&self, ctx: ttrpc::context::Context, req: &super::api::GetResourcesRequest) -> ::ttrpc::Result<super::api::GetResourcesResponse> {
+        let mut cres = super::api::GetResourcesResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.GetResourceService", "GetResources", cres);
+    }
+
+    pub async fn stream_resource(&self, ctx: ttrpc::context::Context, req: &super::api::StreamResourceRequest) -> ::ttrpc::Result<super::api::StreamResourceResponse> {
+        let mut cres = super::api::StreamResourceResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.GetResourceService", "StreamResource", cres);
+    }
 }
 
 struct GetResourceMethod {
@@ -99,11 +153,39 @@ impl ::ttrpc::r#async::MethodHandler for GetResourceMethod {
     }
 }
 
+struct GetResourcesMethod {
+    service: Arc<Box<dyn GetResourceService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for GetResourcesMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, GetResourcesRequest, get_resources);
+    }
+}
+
+struct StreamResourceMethod {
+    service: Arc<Box<dyn GetResourceService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for StreamResourceMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, StreamResourceRequest, stream_resource);
+    }
+}
+
 #[async_trait]
 pub trait GetResourceService: Sync {
     async fn get_resource(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::GetResourceRequest) -> ::ttrpc::Result<super::api::GetResourceResponse> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.GetResourceService/GetResource is not supported".to_string())))
     }
+    async fn get_resources(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::GetResourcesRequest) -> ::ttrpc::Result<super::api::GetResourcesResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.GetResourceService/GetResources is not supported".to_string())))
+    }
+    async fn stream_resource(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::StreamResourceRequest) -> ::ttrpc::Result<super::api::StreamResourceResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.GetResourceService/StreamResource is not supported".to_string())))
+    }
 }
 
 pub fn create_get_resource_service(service: Arc<Box<dyn GetResourceService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
@@ -114,6 +196,200 @@ pub fn create_get_resource_service(service: Arc<Box<dyn GetResourceService + Sen
     methods.insert("GetResource".to_string(),
                     Box::new(GetResourceMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
 
+    methods.insert("GetResources".to_string(),
+                    Box::new(GetResourcesMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("StreamResource".to_string(),
+                    Box::new(StreamResourceMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
     ret.insert("api.GetResourceService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
     ret
 }
+
+#[derive(Clone)]
+pub struct SignServiceClient {
+    client: ::ttrpc::r#async::Client,
+}
+
+impl SignServiceClient {
+    pub fn new(client: ::ttrpc::r#async::Client) -> Self {
+        SignServiceClient {
+            client,
+        }
+    }
+
+    pub async fn sign(&self, ctx: ttrpc::context::Context, req: &super::api::SignRequest) -> ::ttrpc::Result<super::api::SignResponse> {
+        let mut cres = super::api::SignResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SignService", "Sign", cres);
+    }
+}
+
+struct SignMethod {
+    service: Arc<Box<dyn SignService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for SignMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, SignRequest, sign);
+    }
+}
+
+#[async_trait]
+pub trait SignService: Sync {
+    async fn sign(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::SignRequest) -> ::ttrpc::Result<super::api::SignResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SignService/Sign is not supported".to_string())))
+    }
+}
+
+pub fn create_sign_service(service: Arc<Box<dyn SignService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
+    let mut ret = HashMap::new();
+    let mut methods = HashMap::new();
+    let streams = HashMap::new();
+
+    methods.insert("Sign".to_string(),
+                    Box::new(SignMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    ret.insert("api.SignService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
+    ret
+}
+
+#[derive(Clone)]
+pub struct SecureMountServiceClient {
+    client: ::ttrpc::r#async::Client,
+}
+
+impl SecureMountServiceClient {
+    pub fn new(client: ::ttrpc::r#async::Client) -> Self {
+        SecureMountServiceClient {
+            client,
+        }
+    }
+
+    pub async fn secure_mount(&self, ctx: ttrpc::context::Context, req: &super::api::SecureMountRequest) -> ::ttrpc::Result<super::api::SecureMountResponse> {
+        let mut cres = super::api::SecureMountResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SecureMountService", "SecureMount", cres);
+    }
+
+    pub async fn unmount_secure_storage(&self, ctx: ttrpc::context::Context, req: &super::api::UnmountSecureStorageRequest) -> ::ttrpc::Result<super::api::UnmountSecureStorageResponse> {
+        let mut cres = super::api::UnmountSecureStorageResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SecureMountService", "UnmountSecureStorage", cres);
+    }
+
+    pub async fn remount_secure_storage(&self, ctx: ttrpc::context::Context, req: &super::api::RemountSecureStorageRequest) -> ::ttrpc::Result<super::api::RemountSecureStorageResponse> {
+        let mut cres = super::api::RemountSecureStorageResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.SecureMountService", "RemountSecureStorage", cres);
+    }
+}
+
+struct SecureMountMethod {
+    service: Arc<Box<dyn SecureMountService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for SecureMountMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, SecureMountRequest, secure_mount);
+    }
+}
+
+struct UnmountSecureStorageMethod {
+    service: Arc<Box<dyn SecureMountService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnmountSecureStorageMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, UnmountSecureStorageRequest, unmount_secure_storage);
+    }
+}
+
+struct RemountSecureStorageMethod {
+    service: Arc<Box<dyn SecureMountService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for RemountSecureStorageMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, RemountSecureStorageRequest, remount_secure_storage);
+    }
+}
+
+#[async_trait]
+pub trait SecureMountService: Sync {
+    async fn secure_mount(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::SecureMountRequest) -> ::ttrpc::Result<super::api::SecureMountResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SecureMountService/SecureMount is not supported".to_string())))
+    }
+    async fn unmount_secure_storage(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::UnmountSecureStorageRequest) -> ::ttrpc::Result<super::api::UnmountSecureStorageResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SecureMountService/UnmountSecureStorage is not supported".to_string())))
+    }
+    async fn remount_secure_storage(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::RemountSecureStorageRequest) -> ::ttrpc::Result<super::api::RemountSecureStorageResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.SecureMountService/RemountSecureStorage is not supported".to_string())))
+    }
+}
+
+pub fn create_secure_mount_service(service: Arc<Box<dyn SecureMountService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
+    let mut ret = HashMap::new();
+    let mut methods = HashMap::new();
+    let streams = HashMap::new();
+
+    methods.insert("SecureMount".to_string(),
+                    Box::new(SecureMountMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("UnmountSecureStorage".to_string(),
+                    Box::new(UnmountSecureStorageMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("RemountSecureStorage".to_string(),
+                    Box::new(RemountSecureStorageMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    ret.insert("api.SecureMountService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
+    ret
+}
+
+#[derive(Clone)]
+pub struct ImagePullServiceClient {
+    client: ::ttrpc::r#async::Client,
+}
+
+impl ImagePullServiceClient {
+    pub fn new(client: ::ttrpc::r#async::Client) -> Self {
+        ImagePullServiceClient {
+            client,
+        }
+    }
+
+    pub async fn pull_image(&self, ctx: ttrpc::context::Context, req: &super::api::PullImageRequest) -> ::ttrpc::Result<super::api::PullImageResponse> {
+        let mut cres = super::api::PullImageResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "api.ImagePullService", "PullImage", cres);
+    }
+}
+
+struct PullImageMethod {
+    service: Arc<Box<dyn ImagePullService + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for PullImageMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, api, PullImageRequest, pull_image);
+    }
+}
+
+#[async_trait]
+pub trait ImagePullService: Sync {
+    async fn pull_image(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::api::PullImageRequest) -> ::ttrpc::Result<super::api::PullImageResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/api.ImagePullService/PullImage is not supported".to_string())))
+    }
+}
+
+pub fn create_image_pull_service(service: Arc<Box<dyn ImagePullService + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
+    let mut ret = HashMap::new();
+    let mut methods = HashMap::new();
+    let streams = HashMap::new();
+
+    methods.insert("PullImage".to_string(),
+                    Box::new(PullImageMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    ret.insert("api.ImagePullService".to_string(), ::ttrpc::r#async::Service{ methods, streams });
+    ret
+}
@@ -0,0 +1,330 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! An append-only audit log of the accesses to the secrets and the
+//! resources, for the forensics after an incident.
+//!
+//! Each access is a json line of the file, recording which peer accessed
+//! which resource uri or sealed secret, when, and whether it succeeded. The
+//! lines are hash-chained, i.e. the `hash` of a line is the sha256 of the
+//! line without it, which includes the `hash` of the previous line as
+//! `prev`. Any line modified or removed breaks the chain, except the ones
+//! at the end, which can be detected by the digest, i.e. the `seq` and the
+//! `hash` of the last line, pushed to the KBS periodically.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use kms::{plugins::kbs::KbcClient, Setter};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::peer::PeerCredentials;
+
+/// The `prev` of the first line.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// A record of an access, i.e. a line of the audit log without its hash.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct AuditRecord {
+    seq: u64,
+
+    /// Seconds since the unix epoch.
+    time: u64,
+    operation: String,
+
+    /// The resource uri, or the sha256 of the sealed secret.
+    target: String,
+    pid: Option<i32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    success: bool,
+    prev: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct AuditEntry {
+    #[serde(flatten)]
+    record: AuditRecord,
+    hash: String,
+}
+
+/// The digest of the audit log, i.e. the `seq` and the `hash` of its last
+/// line, which covers all the lines by the chain.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditDigest {
+    pub seq: u64,
+    pub hash: String,
+}
+
+impl Default for AuditDigest {
+    fn default() -> Self {
+        Self {
+            seq: 0,
+            hash: GENESIS_HASH.into(),
+        }
+    }
+}
+
+struct State {
+    file: File,
+    digest: AuditDigest,
+}
+
+pub struct AuditLog {
+    state: Mutex<State>,
+}
+
+impl AuditLog {
+    /// Open the audit log of `path` to append, which is created if it does
+    /// not exist. The chain of the existing lines is verified, so that the
+    /// new lines continue it.
+    pub fn open(path: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create dir of audit log {path} failed"))?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("open audit log {path} failed"))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .with_context(|| format!("read audit log {path} failed"))?;
+        let digest = verify_entries(&content).with_context(|| format!("audit log {path}"))?;
+
+        Ok(Self {
+            state: Mutex::new(State { file, digest }),
+        })
+    }
+
+    /// Append the record of an `operation` on `target` by `peer`.
+    pub fn record(
+        &self,
+        peer: Option<PeerCredentials>,
+        operation: &str,
+        target: &str,
+        success: bool,
+    ) -> Result<()> {
+        let mut state = self.state.lock().expect("audit log poisoned");
+        let record = AuditRecord {
+            seq: state.digest.seq + 1,
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            operation: operation.into(),
+            target: target.into(),
+            pid: peer.and_then(|peer| peer.pid),
+            uid: peer.map(|peer| peer.uid),
+            gid: peer.map(|peer| peer.gid),
+            success,
+            prev: state.digest.hash.clone(),
+        };
+        let hash = hash_record(&record)?;
+        let entry = AuditEntry { record, hash };
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        state.file.write_all(&line)?;
+        state.file.sync_data()?;
+
+        state.digest = AuditDigest {
+            seq: entry.record.seq,
+            hash: entry.hash,
+        };
+        Ok(())
+    }
+
+    pub fn digest(&self) -> AuditDigest {
+        self.state
+            .lock()
+            .expect("audit log poisoned")
+            .digest
+            .clone()
+    }
+}
+
+/// Set the audit log of CDH. The accesses are not audited if it is never
+/// set.
+pub fn init(log: AuditLog) {
+    if AUDIT_LOG.set(log).is_err() {
+        warn!("audit log is already set");
+    }
+}
+
+/// Record an `operation` on `target` by `peer` to the audit log, if any.
+/// A record failed to append is logged, as the access is already done.
+pub fn record(peer: Option<PeerCredentials>, operation: &str, target: &str, success: bool) {
+    if let Some(log) = AUDIT_LOG.get() {
+        if let Err(e) = log.record(peer, operation, target, success) {
+            error!("record {operation} of {target} to audit log failed: {e:?}");
+        }
+    }
+}
+
+/// The target of a sealed secret in the audit log, i.e. its sha256, so
+/// that the log does not carry the secrets.
+pub fn secret_target(secret: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(secret)))
+}
+
+/// Push the digest of the audit log to the KBS resource `uri` every
+/// `interval` in background, if any access is recorded since the last push.
+pub fn push_digest(uri: String, interval: Duration) {
+    let Some(log) = AUDIT_LOG.get() else {
+        return;
+    };
+
+    info!("Push the digest of the audit log to {uri} every {interval:?}");
+    tokio::spawn(async move {
+        let mut pushed = None;
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let digest = log.digest();
+            if pushed.as_ref() == Some(&digest) {
+                continue;
+            }
+
+            let res = async {
+                let content = serde_json::to_vec(&digest)?;
+                KbcClient::new()
+                    .await?
+                    .set_secret(content, uri.clone())
+                    .await?;
+                anyhow::Ok(())
+            }
+            .await;
+            match res {
+                Ok(()) => pushed = Some(digest),
+                Err(e) => warn!("push the digest of the audit log failed: {e:?}"),
+            }
+        }
+    });
+}
+
+/// Verify the chain of the audit log of `path`, and get its digest.
+pub fn verify(path: &str) -> Result<AuditDigest> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("read audit log {path} failed"))?;
+    verify_entries(&content).with_context(|| format!("audit log {path}"))
+}
+
+fn verify_entries(content: &str) -> Result<AuditDigest> {
+    let mut digest = AuditDigest::default();
+    for (index, line) in content.lines().enumerate() {
+        let entry: AuditEntry =
+            serde_json::from_str(line).with_context(|| format!("illegal line {}", index + 1))?;
+        if entry.record.seq != digest.seq + 1 || entry.record.prev != digest.hash {
+            bail!("chain is broken at line {}", index + 1);
+        }
+        if hash_record(&entry.record)? != entry.hash {
+            bail!("hash mismatches at line {}", index + 1);
+        }
+
+        digest = AuditDigest {
+            seq: entry.record.seq,
+            hash: entry.hash,
+        };
+    }
+
+    Ok(digest)
+}
+
+fn hash_record(record: &AuditRecord) -> Result<String> {
+    let record = serde_json::to_vec(record)?;
+    Ok(hex::encode(Sha256::digest(record)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const PEER: PeerCredentials = PeerCredentials {
+        pid: Some(100),
+        uid: 1000,
+        gid: 2000,
+    };
+
+    #[test]
+    fn audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit/audit.log");
+        let path = path.to_str().unwrap();
+
+        let log = AuditLog::open(path).unwrap();
+        assert_eq!(log.digest(), AuditDigest::default());
+        log.record(Some(PEER), "get_resource", "kbs:///default/key/1", true)
+            .unwrap();
+        log.record(None, "unseal_secret", &secret_target(b"sealed"), false)
+            .unwrap();
+        let digest = log.digest();
+        assert_eq!(digest.seq, 2);
+        drop(log);
+
+        assert_eq!(verify(path).unwrap(), digest);
+        let content = std::fs::read_to_string(path).unwrap();
+        let entry: AuditEntry = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(entry.record.pid, Some(100));
+        assert_eq!(entry.record.target, "kbs:///default/key/1");
+        assert_eq!(entry.record.prev, GENESIS_HASH);
+
+        // The chain is continued after reopened.
+        let log = AuditLog::open(path).unwrap();
+        assert_eq!(log.digest(), digest);
+        log.record(Some(PEER), "get_resource", "kbs:///default/key/2", true)
+            .unwrap();
+        assert_eq!(verify(path).unwrap().seq, 3);
+    }
+
+    #[test]
+    fn tampered_audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let path = path.to_str().unwrap();
+
+        let log = AuditLog::open(path).unwrap();
+        for uri in ["kbs:///default/key/1", "kbs:///default/key/2"] {
+            log.record(Some(PEER), "get_resource", uri, true).unwrap();
+        }
+        drop(log);
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let modified = content.replacen("key/1", "key/3", 1);
+        std::fs::write(path, &modified).unwrap();
+        assert!(verify(path).is_err());
+        assert!(AuditLog::open(path).is_err());
+
+        let removed: String = content
+            .lines()
+            .skip(1)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        std::fs::write(path, removed).unwrap();
+        assert!(verify(path).is_err());
+
+        let mut file = File::create(path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file.write_all(b"not json\n").unwrap();
+        assert!(verify(path).is_err());
+    }
+}
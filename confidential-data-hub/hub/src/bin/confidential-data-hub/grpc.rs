@@ -0,0 +1,499 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The gRPC services of CDH, for the consumers out of the kata guest, e.g.
+//! processes in peer pods or test harnesses. They share the handlers of the
+//! ttRPC services in [`crate::server`].
+
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use confidential_data_hub::{policy::key_resource, SecureMount};
+use kms::{Annotations, ProviderSettings};
+use log::{debug, error, info};
+use ocicrypt_rs::keywrap::keyprovider::server::{
+    KeyProviderServer, KeyProviderServiceServer, KeyUnwrapper,
+};
+use tokio::{
+    fs,
+    net::{TcpListener, UnixListener},
+    sync::oneshot,
+};
+use tokio_stream::wrappers::{TcpListenerStream, UnixListenerStream};
+use tonic::{
+    transport::{
+        server::{Router, UdsConnectInfo},
+        Server as TonicServer,
+    },
+    Extensions, Request, Response, Status,
+};
+
+use crate::{
+    limit::RateLimiter,
+    peer::{PeerCredentials, PeerPolicy},
+    release,
+    server::{self, Server},
+};
+
+use api::{
+    get_resource_service_server::{GetResourceService, GetResourceServiceServer},
+    image_pull_service_server::{ImagePullService, ImagePullServiceServer},
+    sealed_secret_service_server::{SealedSecretService, SealedSecretServiceServer},
+    secure_mount_service_server::{SecureMountService, SecureMountServiceServer},
+    sign_service_server::{SignService, SignServiceServer},
+    GetResourceRequest, GetResourceResponse, GetResourceResult, GetResourcesRequest,
+    GetResourcesResponse, PullImageRequest, PullImageResponse, RemountSecureStorageRequest,
+    RemountSecureStorageResponse, ResealSecretRequest, ResealSecretResponse, SecureMountRequest,
+    SecureMountResponse, SignRequest, SignResponse, StreamResourceRequest, StreamResourceResponse,
+    UnmountSecureStorageRequest, UnmountSecureStorageResponse, UnsealSecretInput,
+    UnsealSecretOutput, UnsealSecretResult, UnsealSecretsRequest, UnsealSecretsResponse,
+};
+
+mod api {
+    tonic::include_proto!("api");
+}
+
+/// Prefix of a gRPC address which is a unix socket.
+const UNIX_SOCKET_PREFIX: &str = "unix://";
+
+fn internal_error(message: String) -> Status {
+    Status::internal(format!("[CDH] [ERROR]: {message}"))
+}
+
+fn resource_exhausted(e: anyhow::Error) -> Status {
+    Status::resource_exhausted(format!("[CDH] [ERROR]: {e}"))
+}
+
+/// The credentials of the peer of a request of the `extensions`. The peers
+/// over TCP have no credentials.
+fn peer_credentials(extensions: &Extensions) -> Option<PeerCredentials> {
+    extensions
+        .get::<UdsConnectInfo>()
+        .and_then(|info| info.peer_cred)
+        .map(PeerCredentials::from)
+}
+
+/// Check whether the peer of the `request` is allowed to call the API for the
+/// `operation`, and get its credentials for the audit log.
+#[allow(clippy::result_large_err)]
+fn authorize<T>(
+    server: &Server,
+    request: &Request<T>,
+    operation: &str,
+) -> Result<Option<PeerCredentials>, Status> {
+    let peer = peer_credentials(request.extensions());
+    server
+        .authorize(peer, operation)
+        .map_err(|e| Status::permission_denied(format!("[CDH] [ERROR]: {e}")))?;
+    Ok(peer)
+}
+
+/// Check whether the release policy allows the `operation` of the `peer` on
+/// the `resources`.
+#[allow(clippy::result_large_err)]
+fn release(
+    peer: Option<PeerCredentials>,
+    operation: &str,
+    resources: &[String],
+) -> Result<(), Status> {
+    release::authorize(peer, operation, resources)
+        .map_err(|e| Status::permission_denied(format!("[CDH] [ERROR]: {e}")))
+}
+
+#[tonic::async_trait]
+impl SealedSecretService for Server {
+    async fn unseal_secret(
+        &self,
+        request: Request<UnsealSecretInput>,
+    ) -> Result<Response<UnsealSecretOutput>, Status> {
+        let peer = authorize(self, &request, "unseal_secret")?;
+        let resource = release::secret_resource(&request.get_ref().secret);
+        release(peer, "unseal_secret", &[resource])?;
+        let _permit = self
+            .limit(peer, "unseal_secret", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC UnsealSecret request");
+        let plaintext = server::unseal_secret(peer, request.into_inner().secret)
+            .await
+            .map_err(|e| internal_error(format!("Unseal Secret failed: {e}")))?;
+
+        Ok(Response::new(UnsealSecretOutput {
+            plaintext: plaintext.to_vec(),
+        }))
+    }
+
+    async fn unseal_secrets(
+        &self,
+        request: Request<UnsealSecretsRequest>,
+    ) -> Result<Response<UnsealSecretsResponse>, Status> {
+        let peer = authorize(self, &request, "unseal_secrets")?;
+        let request = request.into_inner();
+        let resources: Vec<String> = request
+            .secrets
+            .iter()
+            .map(|secret| release::secret_resource(secret))
+            .collect();
+        release(peer, "unseal_secret", &resources)?;
+        let _permit = self
+            .limit(peer, "unseal_secrets", request.secrets.len())
+            .await
+            .map_err(resource_exhausted)?;
+        debug!(
+            "get new gRPC UnsealSecrets request of {} secrets",
+            request.secrets.len()
+        );
+        let results = server::unseal_secrets(peer, request.secrets)
+            .await
+            .into_iter()
+            .map(|res| match res {
+                Ok(plaintext) => UnsealSecretResult {
+                    plaintext: plaintext.to_vec(),
+                    ..Default::default()
+                },
+                Err(e) => UnsealSecretResult {
+                    error: format!("[CDH] [ERROR]: Unseal Secret failed: {e}"),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Ok(Response::new(UnsealSecretsResponse { results }))
+    }
+
+    async fn reseal_secret(
+        &self,
+        request: Request<ResealSecretRequest>,
+    ) -> Result<Response<ResealSecretResponse>, Status> {
+        let peer = authorize(self, &request, "reseal_secret")?;
+        let resource = release::secret_resource(&request.get_ref().secret);
+        release(peer, "reseal_secret", &[resource])?;
+        let _permit = self
+            .limit(peer, "reseal_secret", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC ResealSecret request");
+        let request = request.into_inner();
+        let secret = server::reseal_secret(peer, request.secret, request.key_id)
+            .await
+            .map_err(|e| internal_error(format!("Reseal Secret failed: {e}")))?;
+
+        Ok(Response::new(ResealSecretResponse { secret }))
+    }
+}
+
+#[tonic::async_trait]
+impl GetResourceService for Server {
+    async fn get_resource(
+        &self,
+        request: Request<GetResourceRequest>,
+    ) -> Result<Response<GetResourceResponse>, Status> {
+        let peer = authorize(self, &request, "get_resource")?;
+        release(
+            peer,
+            "get_resource",
+            std::slice::from_ref(&request.get_ref().resource_path),
+        )?;
+        let _permit = self
+            .limit(peer, "get_resource", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC GetResource request");
+        let resource = server::get_resource(peer, request.into_inner().resource_path)
+            .await
+            .map_err(|e| internal_error(format!("Get Resource failed: {e}")))?;
+
+        Ok(Response::new(GetResourceResponse {
+            resource: resource.to_vec(),
+        }))
+    }
+
+    async fn get_resources(
+        &self,
+        request: Request<GetResourcesRequest>,
+    ) -> Result<Response<GetResourcesResponse>, Status> {
+        let peer = authorize(self, &request, "get_resources")?;
+        let request = request.into_inner();
+        release(peer, "get_resource", &request.resource_paths)?;
+        let _permit = self
+            .limit(peer, "get_resources", request.resource_paths.len())
+            .await
+            .map_err(resource_exhausted)?;
+        debug!(
+            "get new gRPC GetResources request of {} resources",
+            request.resource_paths.len()
+        );
+        let results = server::get_resources(peer, request.resource_paths)
+            .await
+            .into_iter()
+            .map(|res| match res {
+                Ok(resource) => GetResourceResult {
+                    resource: resource.to_vec(),
+                    ..Default::default()
+                },
+                Err(e) => GetResourceResult {
+                    error: format!("[CDH] [ERROR]: Get Resource failed: {e}"),
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Ok(Response::new(GetResourcesResponse { results }))
+    }
+
+    async fn stream_resource(
+        &self,
+        request: Request<StreamResourceRequest>,
+    ) -> Result<Response<StreamResourceResponse>, Status> {
+        let peer = authorize(self, &request, "stream_resource")?;
+        release(
+            peer,
+            "stream_resource",
+            std::slice::from_ref(&request.get_ref().resource_path),
+        )?;
+        let _permit = self
+            .limit(peer, "stream_resource", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC StreamResource request");
+        let request = request.into_inner();
+        let size = server::stream_resource(peer, request.resource_path, &request.destination)
+            .await
+            .map_err(|e| internal_error(format!("Stream Resource failed: {e}")))?;
+
+        Ok(Response::new(StreamResourceResponse { size }))
+    }
+}
+
+#[tonic::async_trait]
+impl SignService for Server {
+    async fn sign(&self, request: Request<SignRequest>) -> Result<Response<SignResponse>, Status> {
+        let peer = authorize(self, &request, "sign")?;
+        let key = key_resource(&request.get_ref().provider, &request.get_ref().key_id);
+        release(peer, "sign", &[key])?;
+        let _permit = self
+            .limit(peer, "sign", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC Sign request");
+        let request = request.into_inner();
+        let provider_settings: ProviderSettings =
+            server::parse_json_object(&request.provider_settings).map_err(|e| {
+                Status::invalid_argument(format!("[CDH] [ERROR]: Illegal ProviderSettings: {e}"))
+            })?;
+        let annotations: Annotations =
+            server::parse_json_object(&request.annotations).map_err(|e| {
+                Status::invalid_argument(format!("[CDH] [ERROR]: Illegal Annotations: {e}"))
+            })?;
+        let signature = server::sign(
+            peer,
+            &request.provider,
+            provider_settings,
+            &request.key_id,
+            &request.message,
+            &annotations,
+        )
+        .await
+        .map_err(|e| internal_error(format!("Sign failed: {e}")))?;
+
+        Ok(Response::new(SignResponse { signature }))
+    }
+}
+
+#[tonic::async_trait]
+impl SecureMountService for Server {
+    async fn secure_mount(
+        &self,
+        request: Request<SecureMountRequest>,
+    ) -> Result<Response<SecureMountResponse>, Status> {
+        let peer = authorize(self, &request, "secure_mount")?;
+        let request = request.into_inner();
+        let storage = SecureMount {
+            volume_type: request.volume_type,
+            options: request.options,
+            flags: request.flags,
+            mount_point: request.mount_point,
+        };
+        release(peer, "secure_mount", &storage.resources())?;
+        let _permit = self
+            .limit(peer, "secure_mount", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC SecureMount request");
+        let mount_path = server::secure_mount(peer, storage)
+            .await
+            .map_err(|e| internal_error(format!("Secure Mount failed: {e}")))?;
+
+        Ok(Response::new(SecureMountResponse { mount_path }))
+    }
+
+    async fn unmount_secure_storage(
+        &self,
+        request: Request<UnmountSecureStorageRequest>,
+    ) -> Result<Response<UnmountSecureStorageResponse>, Status> {
+        let peer = authorize(self, &request, "unmount_secure_storage")?;
+        let _permit = self
+            .limit(peer, "unmount_secure_storage", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC UnmountSecureStorage request");
+        server::unmount_secure_storage(peer, &request.into_inner().mount_point)
+            .await
+            .map_err(|e| internal_error(format!("Unmount Secure Storage failed: {e}")))?;
+
+        Ok(Response::new(UnmountSecureStorageResponse {}))
+    }
+
+    async fn remount_secure_storage(
+        &self,
+        request: Request<RemountSecureStorageRequest>,
+    ) -> Result<Response<RemountSecureStorageResponse>, Status> {
+        let peer = authorize(self, &request, "remount_secure_storage")?;
+        let resources = server::mount_resources(&request.get_ref().mount_point).await;
+        release(peer, "secure_mount", &resources)?;
+        let _permit = self
+            .limit(peer, "remount_secure_storage", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC RemountSecureStorage request");
+        let mount_path = server::remount_secure_storage(peer, &request.into_inner().mount_point)
+            .await
+            .map_err(|e| internal_error(format!("Remount Secure Storage failed: {e}")))?;
+
+        Ok(Response::new(RemountSecureStorageResponse { mount_path }))
+    }
+}
+
+#[tonic::async_trait]
+impl ImagePullService for Server {
+    async fn pull_image(
+        &self,
+        request: Request<PullImageRequest>,
+    ) -> Result<Response<PullImageResponse>, Status> {
+        let peer = authorize(self, &request, "pull_image")?;
+        release(
+            peer,
+            "pull_image",
+            std::slice::from_ref(&request.get_ref().image_url),
+        )?;
+        let _permit = self
+            .limit(peer, "pull_image", 1)
+            .await
+            .map_err(resource_exhausted)?;
+        debug!("get new gRPC PullImage request");
+        let request = request.into_inner();
+        let image_id = server::pull_image(peer, &request.image_url, &request.bundle_path)
+            .await
+            .map_err(|e| internal_error(format!("Pull Image failed: {e}")))?;
+
+        Ok(Response::new(PullImageResponse { image_id }))
+    }
+}
+
+/// The backend of the ocicrypt keyprovider service, so that the ocicrypt
+/// implementations out of the guest can get the layer keys brokered by CDH
+/// as the `attestation-agent` keyprovider.
+struct Unwrapper;
+
+#[async_trait]
+impl KeyUnwrapper for Unwrapper {
+    async fn unwrap_key(&self, annotation: &[u8], extensions: &Extensions) -> Result<Vec<u8>> {
+        debug!("get new gRPC UnWrapKey request");
+        let peer = peer_credentials(extensions);
+        release::authorize(peer, "unwrap_key", &[release::kek_resource(annotation)])
+            .map_err(|e| anyhow::anyhow!("[CDH] [ERROR]: {e}"))?;
+        server::unwrap_key(peer, annotation)
+            .await
+            .map(|key| key.to_vec())
+            .map_err(|e| anyhow::anyhow!("[CDH] [ERROR]: Unwrap Key failed: {e}"))
+    }
+}
+
+async fn router(policy: Arc<PeerPolicy>, limiter: Arc<RateLimiter>) -> Result<Router> {
+    // The keyprovider service is generated by ocicrypt-rs, whose peers are
+    // authorized and rate limited before the requests reach it.
+    let keyprovider_server = Arc::new(Server::new(policy.clone(), limiter.clone()).await?);
+    let keyprovider = KeyProviderServiceServer::with_interceptor(
+        KeyProviderServer::new(Unwrapper),
+        move |request: Request<()>| {
+            let peer = authorize(&keyprovider_server, &request, "unwrap_key")?;
+            keyprovider_server
+                .check_rate(peer, "unwrap_key")
+                .map_err(resource_exhausted)?;
+            Ok(request)
+        },
+    );
+
+    Ok(TonicServer::builder()
+        .add_service(keyprovider)
+        .add_service(SealedSecretServiceServer::new(
+            Server::new(policy.clone(), limiter.clone()).await?,
+        ))
+        .add_service(GetResourceServiceServer::new(
+            Server::new(policy.clone(), limiter.clone()).await?,
+        ))
+        .add_service(SignServiceServer::new(
+            Server::new(policy.clone(), limiter.clone()).await?,
+        ))
+        .add_service(SecureMountServiceServer::new(
+            Server::new(policy.clone(), limiter.clone()).await?,
+        ))
+        .add_service(ImagePullServiceServer::new(
+            Server::new(policy, limiter).await?,
+        )))
+}
+
+/// Listen on `addr`, which is `<ip>:<port>` or `unix://<path>`, and serve
+/// the gRPC services to the peers allowed by the `policy` in background,
+/// within the limits of the `limiter`. The returned sender stops accepting
+/// new requests, while the requests in flight are still served.
+pub async fn serve(
+    addr: &str,
+    policy: Arc<PeerPolicy>,
+    limiter: Arc<RateLimiter>,
+) -> Result<oneshot::Sender<()>> {
+    let router = router(policy, limiter).await?;
+    let (stop, stopped) = oneshot::channel::<()>();
+    let stopped = async move {
+        let _ = stopped.await;
+    };
+    match addr.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Some(path) => {
+            // Remove the socket left by the last run.
+            let _ = fs::remove_file(path).await;
+            let listener = UnixListener::bind(path)
+                .with_context(|| format!("cannot bind cdh grpc service {addr}"))?;
+            info!("Serve gRPC at {addr}");
+            tokio::spawn(async move {
+                if let Err(e) = router
+                    .serve_with_incoming_shutdown(UnixListenerStream::new(listener), stopped)
+                    .await
+                {
+                    error!("cdh grpc service failed: {e}");
+                }
+            });
+        }
+        None => {
+            let addr: SocketAddr = addr
+                .parse()
+                .with_context(|| format!("illegal cdh grpc address {addr}"))?;
+            let listener = TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("cannot bind cdh grpc service {addr}"))?;
+            info!("Serve gRPC at {addr}");
+            tokio::spawn(async move {
+                if let Err(e) = router
+                    .serve_with_incoming_shutdown(TcpListenerStream::new(listener), stopped)
+                    .await
+                {
+                    error!("cdh grpc service failed: {e}");
+                }
+            });
+        }
+    }
+
+    Ok(stop)
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Limits of the RPCs using secrets, so that a misbehaving process
+//! inside the guest cannot hammer the KBS and the KMSes through CDH, and
+//! exhaust the quotas of the attestation services.
+//!
+//! Every peer has a token bucket of `burst` requests, refilled at
+//! `requests_per_sec`. The peers are told apart by their uids, as a process
+//! can fork to get new pids. The peers without credentials, e.g. over TCP,
+//! share one bucket. Besides, at most `max_concurrent` of the requests of
+//! all the peers are handled at the same time, and the others wait.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::{bail, Result};
+use confidential_data_hub::config::RateLimitConfig;
+use log::warn;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::peer::PeerCredentials;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// The limits of the RPCs using secrets or mounts. Nothing is limited by the
+/// default one.
+#[derive(Default)]
+pub struct RateLimiter {
+    /// The refill rate and the size of the buckets, if the rate is limited.
+    rate: Option<(f64, f64)>,
+    buckets: Mutex<HashMap<Option<u32>, Bucket>>,
+    concurrency: Option<Arc<Semaphore>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            rate: config
+                .requests_per_sec
+                .map(|rate| (rate, config.burst() as f64)),
+            buckets: Mutex::new(HashMap::new()),
+            concurrency: config
+                .max_concurrent
+                .map(|permits| Arc::new(Semaphore::new(permits))),
+        }
+    }
+
+    /// Take `cost` tokens, i.e. the number of the items of a request, from
+    /// the bucket of the `peer`.
+    pub fn check(&self, peer: Option<PeerCredentials>, cost: usize) -> Result<()> {
+        let Some((rate, burst)) = self.rate else {
+            return Ok(());
+        };
+
+        let cost = cost as f64;
+        if cost > burst {
+            bail!("request of {cost} items exceeds the burst {burst} of the rate limit");
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limiter poisoned");
+        let bucket = buckets.entry(peer.map(|peer| peer.uid)).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens < cost {
+            warn!("reject the request of rate limited peer {peer:?}");
+            bail!("rate limit of {rate} requests per second is exceeded");
+        }
+
+        bucket.tokens -= cost;
+        Ok(())
+    }
+
+    /// Check the rate of the `peer`, and wait until the request can be
+    /// handled within the concurrency limit. The request is handled while
+    /// the permit returned is held.
+    pub async fn acquire(
+        &self,
+        peer: Option<PeerCredentials>,
+        cost: usize,
+    ) -> Result<Option<OwnedSemaphorePermit>> {
+        self.check(peer, cost)?;
+        match &self.concurrency {
+            Some(semaphore) => Ok(Some(semaphore.clone().acquire_owned().await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use confidential_data_hub::config::RateLimitConfig;
+
+    use super::{PeerCredentials, RateLimiter};
+
+    const PEER: PeerCredentials = PeerCredentials {
+        pid: Some(100),
+        uid: 1000,
+        gid: 2000,
+    };
+
+    const ANOTHER: PeerCredentials = PeerCredentials {
+        pid: Some(100),
+        uid: 1001,
+        gid: 2000,
+    };
+
+    #[test]
+    fn rate_limit() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            requests_per_sec: Some(1.0),
+            burst: Some(2),
+            max_concurrent: None,
+        });
+
+        assert!(limiter.check(Some(PEER), 1).is_ok());
+        assert!(limiter.check(Some(PEER), 1).is_ok());
+        assert!(limiter.check(Some(PEER), 1).is_err());
+        assert!(limiter.check(Some(ANOTHER), 2).is_ok());
+        assert!(limiter.check(None, 3).is_err());
+        assert!(limiter.check(None, 2).is_ok());
+
+        // Refilled after a second.
+        limiter
+            .buckets
+            .lock()
+            .unwrap()
+            .get_mut(&Some(PEER.uid))
+            .unwrap()
+            .updated -= Duration::from_secs(1);
+        assert!(limiter.check(Some(PEER), 1).is_ok());
+        assert!(limiter.check(Some(PEER), 1).is_err());
+
+        let unlimited = RateLimiter::default();
+        for _ in 0..100 {
+            assert!(unlimited.check(Some(PEER), 10).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            requests_per_sec: None,
+            burst: None,
+            max_concurrent: Some(1),
+        });
+
+        let permit = limiter.acquire(Some(PEER), 1).await.unwrap();
+        assert!(permit.is_some());
+        let waiting = tokio::time::timeout(
+            Duration::from_millis(100),
+            limiter.acquire(Some(ANOTHER), 1),
+        );
+        assert!(waiting.await.is_err());
+
+        drop(permit);
+        assert!(limiter.acquire(Some(ANOTHER), 1).await.is_ok());
+    }
+}
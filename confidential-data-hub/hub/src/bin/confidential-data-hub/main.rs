@@ -3,12 +3,16 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::{path::Path, sync::Arc};
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
 
 use anyhow::{Context, Result};
 use api_ttrpc::create_sealed_secret_service;
+use audit::AuditLog;
 use clap::Parser;
-use log::info;
+use confidential_data_hub::config::{self, CdhConfig};
+use limit::RateLimiter;
+use log::{info, warn};
+use peer::PeerPolicy;
 use server::Server;
 use tokio::{
     fs,
@@ -16,10 +20,22 @@ use tokio::{
 };
 use ttrpc::r#async::Server as TtrpcServer;
 
-use crate::api_ttrpc::create_get_resource_service;
+use crate::api_ttrpc::{
+    create_get_resource_service, create_image_pull_service, create_secure_mount_service,
+    create_sign_service,
+};
 
 mod api;
 mod api_ttrpc;
+mod audit;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod limit;
+mod metrics;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod peer;
+mod release;
 mod server;
 
 const DEFAULT_UNIX_SOCKET_DIR: &str = "/run/confidential-containers";
@@ -33,13 +49,74 @@ struct Cli {
     /// CDH will listen to this unix socket address.
     ///
     /// `--socket unix:///tmp/cdh_keyprovider`
-    #[arg(default_value_t = DEFAULT_CDH_SOCKET_ADDR.to_string(), short)]
-    socket: String,
+    ///
+    /// By default `socket` of the config file, or
+    /// `unix:///run/confidential-containers/cdh.sock`.
+    #[arg(short)]
+    socket: Option<String>,
+
+    /// Path of the config file in TOML format.
+    ///
+    /// By default `/etc/confidential-data-hub/cdh.toml` if it exists, which
+    /// can be overridden by env `CDH_CONFIG_PATH`. The options given in the
+    /// commandline take precedence over the config file.
+    ///
+    /// `--config /etc/confidential-data-hub/cdh.toml`
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Check the config file and exit, without starting the services.
+    ///
+    /// `--validate-config --config cdh.toml`
+    #[arg(long)]
+    validate_config: bool,
+
+    /// Verify the hash chain of the audit log of the path and print its
+    /// digest, i.e. the seq and the hash of the last record, then exit.
+    ///
+    /// `--verify-audit-log /var/log/confidential-data-hub/audit.log`
+    #[arg(long)]
+    verify_audit_log: Option<String>,
+
+    /// Address of the Prometheus metrics endpoint.
+    ///
+    /// If given, CDH will serve the metrics at `http://<addr>/metrics`.
+    ///
+    /// `--metrics-addr 127.0.0.1:9100`
+    #[arg(long)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Address of the gRPC service, besides the ttRPC one.
+    ///
+    /// If given, CDH will also serve the same API over gRPC at this TCP
+    /// address or unix socket address.
+    ///
+    /// `--grpc-addr 127.0.0.1:50003` or `--grpc-addr unix:///run/confidential-containers/cdh-grpc.sock`
+    #[cfg(feature = "grpc")]
+    #[arg(long)]
+    grpc_addr: Option<String>,
+
+    /// Uids of the processes allowed to call the API.
+    ///
+    /// The credentials of the callers are got from the unix sockets by
+    /// `SO_PEERCRED`. A caller is allowed if its uid is in `--allowed-uids`
+    /// or its gid is in `--allowed-gids`. Any caller is allowed if neither
+    /// is given, here or in the config file.
+    ///
+    /// `--allowed-uids 0,1000`
+    #[arg(long, value_delimiter = ',')]
+    allowed_uids: Vec<u32>,
+
+    /// Gids of the processes allowed to call the API.
+    ///
+    /// `--allowed-gids 0`
+    #[arg(long, value_delimiter = ',')]
+    allowed_gids: Vec<u32>,
 }
 
 macro_rules! ttrpc_service {
-    ($func: expr) => {{
-        let server = Server::new().await?;
+    ($func: expr, $policy: expr, $limiter: expr) => {{
+        let server = Server::new($policy.clone(), $limiter.clone()).await?;
         let server = Arc::new(Box::new(server) as _);
         $func(server)
     }};
@@ -49,34 +126,136 @@ macro_rules! ttrpc_service {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if cli.validate_config {
+        let path = cli.config.unwrap_or_else(config::config_path);
+        CdhConfig::from_file(&path).await?;
+        println!("config {path} is valid");
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.verify_audit_log {
+        let digest = audit::verify(path)?;
+        println!("{}", serde_json::to_string(&digest)?);
+        return Ok(());
+    }
+
+    let config = match &cli.config {
+        Some(path) => CdhConfig::from_file(path).await?,
+        None => CdhConfig::load().await?,
+    };
+    kms::set_settings(config.settings());
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs());
+
+    #[cfg(feature = "otlp")]
+    if otlp::init()? {
+        info!("Export tracing spans via OTLP.");
+    }
+
+    if let Some(audit) = &config.audit {
+        audit::init(AuditLog::open(&audit.path)?);
+        info!(
+            "Audit the accesses to the secrets and the resources in {}",
+            audit.path
+        );
+        if let Some(uri) = &audit.kbs_uri {
+            audit::push_digest(uri.clone(), Duration::from_secs(audit.push_interval_secs()));
+        }
+    }
+
+    if let Some(release_policy) = &config.release_policy {
+        release::load(release_policy)
+            .await
+            .context("load release policy failed")?;
+    }
+
+    let stream_dir = config.stream_dir();
+    fs::create_dir_all(stream_dir)
+        .await
+        .with_context(|| format!("create stream dir {stream_dir} failed"))?;
+    server::set_stream_dir(
+        fs::canonicalize(stream_dir)
+            .await
+            .with_context(|| format!("resolve stream dir {stream_dir} failed"))?,
+    );
+
     if !Path::new(DEFAULT_UNIX_SOCKET_DIR).exists() {
         fs::create_dir_all(DEFAULT_UNIX_SOCKET_DIR)
             .await
             .context("create unix socket dir failed")?;
     }
 
-    let sealed_secret_service = ttrpc_service!(create_sealed_secret_service);
-    let get_resource_service = ttrpc_service!(create_get_resource_service);
+    let (allowed_uids, allowed_gids) = if cli.allowed_uids.is_empty() && cli.allowed_gids.is_empty()
+    {
+        (config.allowed_uids, config.allowed_gids)
+    } else {
+        (cli.allowed_uids, cli.allowed_gids)
+    };
+    let policy = Arc::new(PeerPolicy::new(allowed_uids, allowed_gids));
+    let limiter = Arc::new(
+        config
+            .rate_limit
+            .as_ref()
+            .map(RateLimiter::new)
+            .unwrap_or_default(),
+    );
+    let socket = cli
+        .socket
+        .or(config.socket)
+        .unwrap_or_else(|| DEFAULT_CDH_SOCKET_ADDR.to_string());
+    let sealed_secret_service = ttrpc_service!(create_sealed_secret_service, policy, limiter);
+    let get_resource_service = ttrpc_service!(create_get_resource_service, policy, limiter);
+    let sign_service = ttrpc_service!(create_sign_service, policy, limiter);
+    let secure_mount_service = ttrpc_service!(create_secure_mount_service, policy, limiter);
+    let image_pull_service = ttrpc_service!(create_image_pull_service, policy, limiter);
     let mut server = TtrpcServer::new()
-        .bind(&cli.socket)
+        .bind(&socket)
         .context("cannot bind cdh ttrpc service")?
         .register_service(sealed_secret_service)
-        .register_service(get_resource_service);
+        .register_service(get_resource_service)
+        .register_service(sign_service)
+        .register_service(secure_mount_service)
+        .register_service(image_pull_service);
 
     server.start().await?;
 
+    #[cfg(feature = "grpc")]
+    let grpc_server = match cli.grpc_addr.or(config.grpc_addr) {
+        Some(addr) => Some(grpc::serve(&addr, policy.clone(), limiter.clone()).await?),
+        None => None,
+    };
+
+    if let Some(addr) = cli.metrics_addr.or(config.metrics_addr) {
+        metrics::serve(addr).await?;
+    }
+
     let mut interrupt = signal(SignalKind::interrupt())?;
     let mut hangup = signal(SignalKind::hangup())?;
+    let mut terminate = signal(SignalKind::terminate())?;
     tokio::select! {
-        _ = hangup.recv() => {
-            info!("Client terminal disconnected.");
-            server.shutdown().await?;
-        }
-        _ = interrupt.recv() => {
-            info!("SIGINT received, gracefully shutdown.");
-            server.shutdown().await?;
-        }
+        _ = hangup.recv() => info!("Client terminal disconnected."),
+        _ = interrupt.recv() => info!("SIGINT received, gracefully shutdown."),
+        _ = terminate.recv() => info!("SIGTERM received, gracefully shutdown."),
     };
 
+    // Stop accepting new connections, let the requests in flight finish,
+    // and then release the mounts and the key material.
+    server.stop_listen().await;
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_server) = grpc_server {
+        let _ = grpc_server.send(());
+    }
+    if server::drain(shutdown_timeout).await {
+        info!("Requests in flight are drained.");
+    } else {
+        warn!(
+            "Requests still in flight after {}s, torn down anyway.",
+            shutdown_timeout.as_secs()
+        );
+    }
+    server.shutdown().await?;
+
+    #[cfg(feature = "otlp")]
+    otlp::shutdown();
+
     Ok(())
 }
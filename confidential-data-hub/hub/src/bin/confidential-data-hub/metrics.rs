@@ -0,0 +1,97 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! A minimal HTTP endpoint to serve the [`kms::metrics`] at `GET /metrics`
+//! to a Prometheus scraper.
+
+use std::net::SocketAddr;
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Max size of a request, the scraper only sends a short header.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Listen on `addr` and serve the metrics in background.
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("cannot bind metrics endpoint {addr}"))?;
+    info!("Serve metrics at http://{addr}/metrics");
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    debug!("accept metrics connection failed: {e}");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream).await {
+                    debug!("serve metrics failed: {e}");
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle(mut stream: TcpStream) -> Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_SIZE {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let response = match is_metrics_request(&request) {
+        true => {
+            let body = kms::metrics::render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        false => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Whether the request line is `GET /metrics`.
+fn is_metrics_request(request: &[u8]) -> bool {
+    let line = request.split(|c| *c == b'\n').next().unwrap_or_default();
+    let mut parts = line.split(|c| *c == b' ');
+    parts.next() == Some(b"GET") && parts.next() == Some(b"/metrics")
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::is_metrics_request;
+
+    #[rstest]
+    #[case(b"GET /metrics HTTP/1.1\r\nHost: cdh\r\n\r\n", true)]
+    #[case(b"POST /metrics HTTP/1.1\r\n\r\n", false)]
+    #[case(b"GET / HTTP/1.1\r\n\r\n", false)]
+    #[case(b"", false)]
+    fn metrics_request(#[case] request: &[u8], #[case] expected: bool) {
+        assert_eq!(is_metrics_request(request), expected);
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Export the tracing spans of CDH via OTLP, so that the time spent on the
+//! attestation, the KBS and the decryption can be seen in a tracing backend.
+//!
+//! The exporter is configured by the standard `OTEL_EXPORTER_OTLP_*`
+//! environment variables, and is only enabled when
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+
+use anyhow::{Context, Result};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{runtime, trace, Resource};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+const SERVICE_NAME: &str = "confidential-data-hub";
+
+/// Install the OTLP exporter if `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+/// Returns whether the exporter is installed.
+pub fn init() -> Result<bool> {
+    if std::env::var_os(OTLP_ENDPOINT_ENV).is_none() {
+        return Ok(false);
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                SERVICE_NAME,
+            )])),
+        )
+        .install_batch(runtime::Tokio)
+        .context("install OTLP exporter")?;
+
+    tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()
+        .context("init tracing subscriber")?;
+
+    Ok(true)
+}
+
+/// Export the remaining spans before exiting.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
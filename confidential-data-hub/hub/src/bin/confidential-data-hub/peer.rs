@@ -0,0 +1,127 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Authorization of the callers of the API by the credentials of the peer
+//! processes of the unix sockets, so that an arbitrary process inside the
+//! guest cannot get all of the secrets and the resources.
+
+use std::os::unix::io::RawFd;
+
+use anyhow::{bail, Result};
+use log::warn;
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials as PeerCred};
+
+/// The credentials of the peer process of a unix socket connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub pid: Option<i32>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl PeerCredentials {
+    /// Get the credentials of the peer of the unix socket `fd` by
+    /// `SO_PEERCRED`.
+    pub fn from_fd(fd: RawFd) -> nix::Result<Self> {
+        let cred = getsockopt(fd, PeerCred)?;
+        Ok(Self {
+            pid: Some(cred.pid()),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        })
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl From<tokio::net::unix::UCred> for PeerCredentials {
+    fn from(cred: tokio::net::unix::UCred) -> Self {
+        Self {
+            pid: cred.pid(),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        }
+    }
+}
+
+/// The peers allowed to call the API. A peer is allowed if its uid or its
+/// gid is in the allowlists, and any peer is allowed if both are empty.
+#[derive(Debug, Default)]
+pub struct PeerPolicy {
+    uids: Vec<u32>,
+    gids: Vec<u32>,
+}
+
+impl PeerPolicy {
+    pub fn new(uids: Vec<u32>, gids: Vec<u32>) -> Self {
+        Self { uids, gids }
+    }
+
+    /// Check whether the `peer` is allowed to call the API. A peer whose
+    /// credentials are unknown, e.g. over TCP, is only allowed if any peer
+    /// is allowed.
+    pub fn authorize(&self, peer: Option<PeerCredentials>) -> Result<()> {
+        if self.uids.is_empty() && self.gids.is_empty() {
+            return Ok(());
+        }
+
+        match peer {
+            Some(peer) if self.uids.contains(&peer.uid) || self.gids.contains(&peer.gid) => Ok(()),
+            Some(peer) => {
+                warn!("reject the request of unauthorized peer {peer:?}");
+                bail!(
+                    "peer of uid {} and gid {} is not authorized",
+                    peer.uid,
+                    peer.gid
+                )
+            }
+            None => {
+                warn!("reject the request of the peer without credentials");
+                bail!("peer without credentials is not authorized")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::{io::AsRawFd, net::UnixStream};
+
+    use nix::unistd::{getgid, getuid};
+    use rstest::rstest;
+
+    use super::{PeerCredentials, PeerPolicy};
+
+    const PEER: PeerCredentials = PeerCredentials {
+        pid: Some(100),
+        uid: 1000,
+        gid: 2000,
+    };
+
+    #[rstest]
+    #[case(vec![], vec![], Some(PEER), true)]
+    #[case(vec![], vec![], None, true)]
+    #[case(vec![0, 1000], vec![], Some(PEER), true)]
+    #[case(vec![], vec![2000], Some(PEER), true)]
+    #[case(vec![0], vec![0], Some(PEER), false)]
+    #[case(vec![1000], vec![], None, false)]
+    fn authorize(
+        #[case] uids: Vec<u32>,
+        #[case] gids: Vec<u32>,
+        #[case] peer: Option<PeerCredentials>,
+        #[case] allowed: bool,
+    ) {
+        let policy = PeerPolicy::new(uids, gids);
+        assert_eq!(policy.authorize(peer).is_ok(), allowed);
+    }
+
+    #[test]
+    fn peer_credentials() {
+        let (stream, _peer) = UnixStream::pair().unwrap();
+        let cred = PeerCredentials::from_fd(stream.as_raw_fd()).unwrap();
+        assert_eq!(cred.pid, Some(std::process::id() as i32));
+        assert_eq!(cred.uid, getuid().as_raw());
+        assert_eq!(cred.gid, getgid().as_raw());
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The in-guest release policy of the secrets and the resources, see
+//! [`confidential_data_hub::policy`]. The policy is got from the KBS, so
+//! that only the attested guests get it, and its digest is extended to the
+//! runtime measurements of the TEE, so that the relying parties can check
+//! which policy is enforced.
+
+use std::{
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use confidential_data_hub::{
+    config::ReleasePolicyConfig,
+    hub,
+    policy::{ReleasePolicy, ReleaseRequest},
+};
+use kbs_protocol::token_provider::AATokenProvider;
+use kms::{plugins::kbs::KbcClient, Annotations, Getter};
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+use crate::{audit, peer::PeerCredentials};
+
+/// Domain of the runtime measurement events of CDH.
+const MEASUREMENT_DOMAIN: &str = "github.com/confidential-containers/confidential-data-hub";
+
+/// Operation of the runtime measurement events of the release policies.
+const RELEASE_POLICY_OPERATION: &str = "ReleasePolicy";
+
+static RELEASE_POLICY: OnceLock<ReleasePolicy> = OnceLock::new();
+
+/// Get the release policy from the KBS, check and measure its digest, and
+/// enforce it on the requests after.
+pub async fn load(config: &ReleasePolicyConfig) -> Result<()> {
+    let policy = KbcClient::new()
+        .await?
+        .get_secret(&config.kbs_uri, &Annotations::default())
+        .await
+        .with_context(|| format!("get release policy {} failed", config.kbs_uri))?;
+
+    let digest = hex::encode(Sha256::digest(&policy));
+    if let Some(expected) = &config.sha256 {
+        if !expected.eq_ignore_ascii_case(&digest) {
+            bail!("release policy is of sha256 {digest}, not {expected}");
+        }
+    }
+    let policy = ReleasePolicy::from_slice(&policy)?;
+
+    let content = format!("sha256:{digest}");
+    if config.measure.unwrap_or(true) {
+        AATokenProvider::new()
+            .await?
+            .extend_runtime_measurement(MEASUREMENT_DOMAIN, RELEASE_POLICY_OPERATION, &content)
+            .await
+            .with_context(|| format!("measure release policy {content} failed"))?;
+    }
+    audit::record(None, "load_release_policy", &content, true);
+
+    if RELEASE_POLICY.set(policy).is_err() {
+        bail!("release policy is already loaded");
+    }
+    info!("Enforce release policy {content} from {}", config.kbs_uri);
+    Ok(())
+}
+
+/// Check whether the release policy, if any, allows the `operation` of the
+/// `peer` on all of the `resources`. An operation on no resource, e.g. the
+/// secure mount of a `scratch` device, is checked on the empty resource.
+/// A denied request is recorded to the audit log.
+pub fn authorize(
+    peer: Option<PeerCredentials>,
+    operation: &str,
+    resources: &[String],
+) -> Result<()> {
+    let Some(policy) = RELEASE_POLICY.get() else {
+        return Ok(());
+    };
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let no_resource = [String::new()];
+    let resources = if resources.is_empty() {
+        &no_resource[..]
+    } else {
+        resources
+    };
+    for resource in resources {
+        let request = ReleaseRequest {
+            operation,
+            resource,
+            uid: peer.map(|peer| peer.uid),
+            gid: peer.map(|peer| peer.gid),
+            time,
+        };
+        policy.authorize(&request).map_err(|e| {
+            warn!("reject the request of peer {peer:?} by release policy: {e}");
+            audit::record(peer, operation, resource, false);
+            anyhow!(e)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// The resource of the sealed `secret` in the release policy. An illegal
+/// sealed secret, which fails to be unsealed anyway, is of no resource.
+pub fn secret_resource(secret: &[u8]) -> String {
+    hub::secret_resource(secret).unwrap_or_default()
+}
+
+/// The resource of the KEK of a keyprovider `annotation` in the release
+/// policy, see [`secret_resource`].
+#[cfg(feature = "grpc")]
+pub fn kek_resource(annotation: &[u8]) -> String {
+    hub::kek_resource(annotation).unwrap_or_default()
+}
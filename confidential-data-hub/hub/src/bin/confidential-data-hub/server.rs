@@ -3,26 +3,105 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::sync::Arc;
+use std::{
+    future::Future,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use confidential_data_hub::{hub::Hub, DataHub};
+use confidential_data_hub::{
+    config::DEFAULT_STREAM_DIR, hub::Hub, policy::key_resource, DataHub, Error as HubError,
+    SecureMount,
+};
+use futures::{stream, StreamExt};
+use kms::{metrics, Annotations, ProviderSettings, SecretBytes};
 use lazy_static::lazy_static;
 use log::debug;
-use tokio::sync::RwLock;
+use tokio::{
+    fs::{self, OpenOptions},
+    io::{AsyncWrite, AsyncWriteExt},
+    net::UnixStream,
+    sync::{OwnedSemaphorePermit, RwLock},
+};
 use ttrpc::{asynchronous::TtrpcContext, Code, Error, Status};
 
 use crate::{
-    api::{GetResourceRequest, GetResourceResponse, UnsealSecretInput, UnsealSecretOutput},
-    api_ttrpc::{GetResourceService, SealedSecretService},
+    api::{
+        GetResourceRequest, GetResourceResponse, GetResourceResult, GetResourcesRequest,
+        GetResourcesResponse, PullImageRequest, PullImageResponse, RemountSecureStorageRequest,
+        RemountSecureStorageResponse, ResealSecretRequest, ResealSecretResponse,
+        SecureMountRequest, SecureMountResponse, SignRequest, SignResponse, StreamResourceRequest,
+        StreamResourceResponse, UnmountSecureStorageRequest, UnmountSecureStorageResponse,
+        UnsealSecretInput, UnsealSecretOutput, UnsealSecretResult, UnsealSecretsRequest,
+        UnsealSecretsResponse,
+    },
+    api_ttrpc::{
+        GetResourceService, ImagePullService, SealedSecretService, SecureMountService, SignService,
+    },
+    audit,
+    limit::RateLimiter,
+    peer::{PeerCredentials, PeerPolicy},
+    release,
 };
 
+/// Max number of the items of a batch request handled concurrently.
+const BATCH_CONCURRENCY: usize = 16;
+
 lazy_static! {
+    /// The hub handling the requests. Every request holds the read lock
+    /// while it is handled, so that [`drain`] waits for them by the write
+    /// lock.
     static ref HUB: Arc<RwLock<Option<Hub>>> = Arc::new(RwLock::new(None));
 }
 
-pub struct Server;
+/// Set once [`drain`] starts, after which new requests are rejected.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// The hub to handle a request, which is gone once CDH is shutting down.
+fn hub(hub: &Option<Hub>) -> confidential_data_hub::Result<&Hub> {
+    if SHUTTING_DOWN.load(Ordering::Acquire) {
+        return Err(HubError::ShuttingDown);
+    }
+
+    hub.as_ref().ok_or(HubError::ShuttingDown)
+}
+
+/// Reject the new requests, wait up to `timeout` for the requests in flight
+/// to finish, and then shut down the hub, see [`Hub::shutdown`]. The hub is
+/// shut down even if some requests are still in flight after `timeout`, so
+/// that the ephemeral storages and the cached secrets never outlive CDH.
+/// Returns whether all the requests are finished in time.
+pub async fn drain(timeout: Duration) -> bool {
+    SHUTTING_DOWN.store(true, Ordering::Release);
+    match tokio::time::timeout(timeout, HUB.write()).await {
+        Ok(mut writer) => {
+            if let Some(hub) = writer.take() {
+                hub.shutdown().await;
+            }
+            true
+        }
+        Err(_) => {
+            // The requests in flight hold the read lock, so share it with
+            // them to tear down.
+            if let Some(hub) = HUB.read().await.as_ref() {
+                hub.shutdown().await;
+            }
+            false
+        }
+    }
+}
+
+pub struct Server {
+    policy: Arc<PeerPolicy>,
+    limiter: Arc<RateLimiter>,
+}
 
 impl Server {
     async fn init() -> Result<()> {
@@ -35,56 +114,768 @@ impl Server {
         Ok(())
     }
 
-    pub async fn new() -> Result<Self> {
+    pub async fn new(policy: Arc<PeerPolicy>, limiter: Arc<RateLimiter>) -> Result<Self> {
         Self::init().await?;
-        Ok(Self)
+        Ok(Self { policy, limiter })
+    }
+
+    /// Check whether the `peer` is allowed to call the API for the
+    /// `operation`. A denied request is recorded to the audit log.
+    pub fn authorize(&self, peer: Option<PeerCredentials>, operation: &str) -> Result<()> {
+        self.policy
+            .authorize(peer)
+            .inspect_err(|_| audit::record(peer, operation, "", false))
+    }
+
+    /// Check the rate limit of the `peer` for an `operation` of `cost`
+    /// items, and wait for the concurrency limit. A request over the rate
+    /// limit is recorded to the audit log.
+    pub async fn limit(
+        &self,
+        peer: Option<PeerCredentials>,
+        operation: &str,
+        cost: usize,
+    ) -> Result<Option<OwnedSemaphorePermit>> {
+        self.limiter
+            .acquire(peer, cost)
+            .await
+            .inspect_err(|_| audit::record(peer, operation, "", false))
+    }
+
+    /// Check the rate limit of the `peer` only, for the requests which
+    /// cannot wait.
+    #[cfg(feature = "grpc")]
+    pub fn check_rate(&self, peer: Option<PeerCredentials>, operation: &str) -> Result<()> {
+        self.limiter
+            .check(peer, 1)
+            .inspect_err(|_| audit::record(peer, operation, "", false))
+    }
+
+    /// [`Self::limit`] of a ttRPC request.
+    async fn limit_ttrpc(
+        &self,
+        peer: Option<PeerCredentials>,
+        operation: &str,
+        cost: usize,
+    ) -> ::ttrpc::Result<Option<OwnedSemaphorePermit>> {
+        self.limit(peer, operation, cost)
+            .await
+            .map_err(|e| status_error(Code::RESOURCE_EXHAUSTED, e.to_string()))
+    }
+
+    /// Check whether the release policy allows the `operation` of the `peer`
+    /// on the `resources` of a ttRPC request.
+    fn release_ttrpc(
+        &self,
+        peer: Option<PeerCredentials>,
+        operation: &str,
+        resources: &[String],
+    ) -> ::ttrpc::Result<()> {
+        release::authorize(peer, operation, resources)
+            .map_err(|e| status_error(Code::PERMISSION_DENIED, e.to_string()))
+    }
+
+    /// Check whether the peer of the ttRPC connection is allowed to call the
+    /// API for the `operation`, and get its credentials for the audit log.
+    fn authorize_ttrpc(
+        &self,
+        ctx: &TtrpcContext,
+        operation: &str,
+    ) -> ::ttrpc::Result<Option<PeerCredentials>> {
+        let peer = PeerCredentials::from_fd(ctx.fd).ok();
+        self.authorize(peer, operation)
+            .map_err(|e| status_error(Code::PERMISSION_DENIED, e.to_string()))?;
+        Ok(peer)
     }
 }
 
+// The handlers below are shared by the ttRPC and gRPC services. They record
+// the metrics of the requests and the accesses of the `peer` to the audit
+// log, and leave the replies and the statuses to the services.
+
+/// Handle the request `name` by `handler`, and record its metrics.
+async fn observe<T>(
+    name: &str,
+    handler: impl Future<Output = confidential_data_hub::Result<T>>,
+) -> confidential_data_hub::Result<T> {
+    let start = Instant::now();
+    let res = handler.await;
+    metrics::observe_request(name, res.is_ok(), start.elapsed());
+    res
+}
+
+pub async fn unseal_secret(
+    peer: Option<PeerCredentials>,
+    secret: Vec<u8>,
+) -> confidential_data_hub::Result<SecretBytes> {
+    let target = audit::secret_target(&secret);
+    let res = observe("unseal_secret", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.unseal_secret(secret).await
+    })
+    .await;
+    audit::record(peer, "unseal_secret", &target, res.is_ok());
+    res
+}
+
+/// Unseal the `secrets` concurrently. The results are in the order of the
+/// secrets.
+pub async fn unseal_secrets(
+    peer: Option<PeerCredentials>,
+    secrets: Vec<Vec<u8>>,
+) -> Vec<confidential_data_hub::Result<SecretBytes>> {
+    let start = Instant::now();
+    let reader = HUB.read().await;
+    let Ok(reader) = hub(&reader) else {
+        return secrets
+            .iter()
+            .map(|_| Err(HubError::ShuttingDown))
+            .collect();
+    };
+    let targets: Vec<_> = secrets
+        .iter()
+        .map(|secret| audit::secret_target(secret))
+        .collect();
+    let results: Vec<_> = stream::iter(secrets)
+        .map(|secret| reader.unseal_secret(secret))
+        .buffered(BATCH_CONCURRENCY)
+        .collect()
+        .await;
+    for (target, res) in targets.iter().zip(&results) {
+        audit::record(peer, "unseal_secrets", target, res.is_ok());
+    }
+    metrics::observe_request(
+        "unseal_secrets",
+        results.iter().all(|res| res.is_ok()),
+        start.elapsed(),
+    );
+    results
+}
+
+/// Reseal the `secret` under `key_id`, or the same key if it is empty.
+pub async fn reseal_secret(
+    peer: Option<PeerCredentials>,
+    secret: Vec<u8>,
+    key_id: String,
+) -> confidential_data_hub::Result<Vec<u8>> {
+    let key_id = (!key_id.is_empty()).then_some(key_id);
+    let target = audit::secret_target(&secret);
+    let res = observe("reseal_secret", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.reseal_secret(secret, key_id).await
+    })
+    .await;
+    audit::record(peer, "reseal_secret", &target, res.is_ok());
+    res
+}
+
+pub async fn get_resource(
+    peer: Option<PeerCredentials>,
+    uri: String,
+) -> confidential_data_hub::Result<SecretBytes> {
+    let target = uri.clone();
+    let res = observe("get_resource", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.get_resource(uri).await
+    })
+    .await;
+    audit::record(peer, "get_resource", &target, res.is_ok());
+    res
+}
+
+/// Unwrap the LEK of the keyprovider `annotation` of an encrypted layer, for
+/// the keyprovider gRPC service.
+#[cfg(feature = "grpc")]
+pub async fn unwrap_key(
+    peer: Option<PeerCredentials>,
+    annotation: &[u8],
+) -> confidential_data_hub::Result<SecretBytes> {
+    let res = observe("unwrap_key", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.unwrap_key(annotation).await
+    })
+    .await;
+    audit::record(
+        peer,
+        "unwrap_key",
+        &audit::secret_target(annotation),
+        res.is_ok(),
+    );
+    res
+}
+
+/// Get the resources `uris` concurrently. The results are in the order of
+/// the uris.
+pub async fn get_resources(
+    peer: Option<PeerCredentials>,
+    uris: Vec<String>,
+) -> Vec<confidential_data_hub::Result<SecretBytes>> {
+    let start = Instant::now();
+    let reader = HUB.read().await;
+    let Ok(reader) = hub(&reader) else {
+        return uris.iter().map(|_| Err(HubError::ShuttingDown)).collect();
+    };
+    let results: Vec<_> = stream::iter(uris.clone())
+        .map(|uri| reader.get_resource(uri))
+        .buffered(BATCH_CONCURRENCY)
+        .collect()
+        .await;
+    for (uri, res) in uris.iter().zip(&results) {
+        audit::record(peer, "get_resources", uri, res.is_ok());
+    }
+    metrics::observe_request(
+        "get_resources",
+        results.iter().all(|res| res.is_ok()),
+        start.elapsed(),
+    );
+    results
+}
+
+pub async fn stream_resource(
+    peer: Option<PeerCredentials>,
+    uri: String,
+    destination: &str,
+) -> anyhow::Result<u64> {
+    let start = Instant::now();
+    let target = uri.clone();
+    let res = write_destination(uri, destination).await;
+    metrics::observe_request("stream_resource", res.is_ok(), start.elapsed());
+    audit::record(peer, "stream_resource", &target, res.is_ok());
+    res
+}
+
+pub async fn sign(
+    peer: Option<PeerCredentials>,
+    provider: &str,
+    provider_settings: ProviderSettings,
+    key_id: &str,
+    message: &[u8],
+    annotations: &Annotations,
+) -> confidential_data_hub::Result<Vec<u8>> {
+    let res = observe("sign", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader
+            .sign(provider, provider_settings, key_id, message, annotations)
+            .await
+    })
+    .await;
+    audit::record(peer, "sign", &key_resource(provider, key_id), res.is_ok());
+    res
+}
+
+pub async fn pull_image(
+    peer: Option<PeerCredentials>,
+    image_url: &str,
+    bundle_path: &str,
+) -> confidential_data_hub::Result<String> {
+    let res = observe("pull_image", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.pull_image(image_url, bundle_path).await
+    })
+    .await;
+    audit::record(peer, "pull_image", image_url, res.is_ok());
+    res
+}
+
+/// Mount the `storage`, and record the accesses to the resources it is
+/// mounted with, or to its mount point if there are none.
+pub async fn secure_mount(
+    peer: Option<PeerCredentials>,
+    storage: SecureMount,
+) -> confidential_data_hub::Result<String> {
+    let mut targets = storage.resources();
+    if targets.is_empty() {
+        targets.push(storage.mount_point.clone());
+    }
+    let res = observe("secure_mount", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.secure_mount(storage).await
+    })
+    .await;
+    for target in &targets {
+        audit::record(peer, "secure_mount", target, res.is_ok());
+    }
+    res
+}
+
+/// The resources of the storage at `mount_point` in the release policy, or
+/// none if it is not mounted, as it cannot be remounted anyway.
+pub async fn mount_resources(mount_point: &str) -> Vec<String> {
+    let reader = HUB.read().await;
+    match hub(&reader) {
+        Ok(reader) => reader
+            .mount_resources(mount_point)
+            .await
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub async fn unmount_secure_storage(
+    peer: Option<PeerCredentials>,
+    mount_point: &str,
+) -> confidential_data_hub::Result<()> {
+    let res = observe("unmount_secure_storage", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.unmount_secure_storage(mount_point).await
+    })
+    .await;
+    audit::record(peer, "unmount_secure_storage", mount_point, res.is_ok());
+    res
+}
+
+pub async fn remount_secure_storage(
+    peer: Option<PeerCredentials>,
+    mount_point: &str,
+) -> confidential_data_hub::Result<String> {
+    let res = observe("remount_secure_storage", async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        reader.remount_secure_storage(mount_point).await
+    })
+    .await;
+    audit::record(peer, "remount_secure_storage", mount_point, res.is_ok());
+    res
+}
+
 #[async_trait]
 impl SealedSecretService for Server {
     async fn unseal_secret(
         &self,
-        _ctx: &TtrpcContext,
+        ctx: &TtrpcContext,
         input: UnsealSecretInput,
     ) -> ::ttrpc::Result<UnsealSecretOutput> {
+        let peer = self.authorize_ttrpc(ctx, "unseal_secret")?;
+        let resource = release::secret_resource(&input.secret);
+        self.release_ttrpc(peer, "unseal_secret", &[resource])?;
+        let _permit = self.limit_ttrpc(peer, "unseal_secret", 1).await?;
         debug!("get new UnsealSecret request");
-        let reader = HUB.read().await;
-        let reader = reader.as_ref().expect("must be initialized");
-        let plaintext = reader.unseal_secret(input.secret).await.map_err(|e| {
-            let mut status = Status::new();
-            status.set_code(Code::INTERNAL);
-            status.set_message(format!("[CDH] [ERROR]: Unseal Secret failed: {e}"));
-            Error::RpcStatus(status)
-        })?;
+        let plaintext = unseal_secret(peer, input.secret)
+            .await
+            .map_err(|e| internal_error(format!("Unseal Secret failed: {e}")))?;
 
         let mut reply = UnsealSecretOutput::new();
-        reply.plaintext = plaintext;
+        reply.plaintext = plaintext.to_vec();
         debug!("send back plaintext of the sealed secret");
         Ok(reply)
     }
+
+    async fn unseal_secrets(
+        &self,
+        ctx: &TtrpcContext,
+        req: UnsealSecretsRequest,
+    ) -> ::ttrpc::Result<UnsealSecretsResponse> {
+        let peer = self.authorize_ttrpc(ctx, "unseal_secrets")?;
+        let resources: Vec<String> = req
+            .Secrets
+            .iter()
+            .map(|secret| release::secret_resource(secret))
+            .collect();
+        self.release_ttrpc(peer, "unseal_secret", &resources)?;
+        let _permit = self
+            .limit_ttrpc(peer, "unseal_secrets", req.Secrets.len())
+            .await?;
+        debug!(
+            "get new UnsealSecrets request of {} secrets",
+            req.Secrets.len()
+        );
+        let results = unseal_secrets(peer, req.Secrets).await;
+
+        let mut reply = UnsealSecretsResponse::new();
+        reply.Results = results
+            .into_iter()
+            .map(|res| {
+                let mut result = UnsealSecretResult::new();
+                match res {
+                    Ok(plaintext) => result.Plaintext = plaintext.to_vec(),
+                    Err(e) => result.Error = format!("[CDH] [ERROR]: Unseal Secret failed: {e}"),
+                }
+                result
+            })
+            .collect();
+        debug!("send back the results of the sealed secrets");
+        Ok(reply)
+    }
+
+    async fn reseal_secret(
+        &self,
+        ctx: &TtrpcContext,
+        req: ResealSecretRequest,
+    ) -> ::ttrpc::Result<ResealSecretResponse> {
+        let peer = self.authorize_ttrpc(ctx, "reseal_secret")?;
+        let resource = release::secret_resource(&req.Secret);
+        self.release_ttrpc(peer, "reseal_secret", &[resource])?;
+        let _permit = self.limit_ttrpc(peer, "reseal_secret", 1).await?;
+        debug!("get new ResealSecret request");
+        let secret = reseal_secret(peer, req.Secret, req.KeyId)
+            .await
+            .map_err(|e| internal_error(format!("Reseal Secret failed: {e}")))?;
+
+        let mut reply = ResealSecretResponse::new();
+        reply.Secret = secret;
+        debug!("send back the resealed secret");
+        Ok(reply)
+    }
 }
 
 #[async_trait]
 impl GetResourceService for Server {
     async fn get_resource(
         &self,
-        _ctx: &TtrpcContext,
+        ctx: &TtrpcContext,
         req: GetResourceRequest,
     ) -> ::ttrpc::Result<GetResourceResponse> {
+        let peer = self.authorize_ttrpc(ctx, "get_resource")?;
+        self.release_ttrpc(
+            peer,
+            "get_resource",
+            std::slice::from_ref(&req.ResourcePath),
+        )?;
+        let _permit = self.limit_ttrpc(peer, "get_resource", 1).await?;
         debug!("get new GetResource request");
-        let reader = HUB.read().await;
-        let reader = reader.as_ref().expect("must be initialized");
-        let resource = reader.get_resource(req.ResourcePath).await.map_err(|e| {
-            let mut status = Status::new();
-            status.set_code(Code::INTERNAL);
-            status.set_message(format!("[CDH] [ERROR]: Get Resource failed: {e}"));
-            Error::RpcStatus(status)
-        })?;
+        let resource = get_resource(peer, req.ResourcePath)
+            .await
+            .map_err(|e| internal_error(format!("Get Resource failed: {e}")))?;
 
         let mut reply = GetResourceResponse::new();
-        reply.Resource = resource;
+        reply.Resource = resource.to_vec();
         debug!("send back the resource");
         Ok(reply)
     }
+
+    async fn get_resources(
+        &self,
+        ctx: &TtrpcContext,
+        req: GetResourcesRequest,
+    ) -> ::ttrpc::Result<GetResourcesResponse> {
+        let peer = self.authorize_ttrpc(ctx, "get_resources")?;
+        self.release_ttrpc(peer, "get_resource", &req.ResourcePaths)?;
+        let _permit = self
+            .limit_ttrpc(peer, "get_resources", req.ResourcePaths.len())
+            .await?;
+        debug!(
+            "get new GetResources request of {} resources",
+            req.ResourcePaths.len()
+        );
+        let results = get_resources(peer, req.ResourcePaths).await;
+
+        let mut reply = GetResourcesResponse::new();
+        reply.Results = results
+            .into_iter()
+            .map(|res| {
+                let mut result = GetResourceResult::new();
+                match res {
+                    Ok(resource) => result.Resource = resource.to_vec(),
+                    Err(e) => result.Error = format!("[CDH] [ERROR]: Get Resource failed: {e}"),
+                }
+                result
+            })
+            .collect();
+        debug!("send back the resources");
+        Ok(reply)
+    }
+
+    async fn stream_resource(
+        &self,
+        ctx: &TtrpcContext,
+        req: StreamResourceRequest,
+    ) -> ::ttrpc::Result<StreamResourceResponse> {
+        let peer = self.authorize_ttrpc(ctx, "stream_resource")?;
+        self.release_ttrpc(
+            peer,
+            "stream_resource",
+            std::slice::from_ref(&req.ResourcePath),
+        )?;
+        let _permit = self.limit_ttrpc(peer, "stream_resource", 1).await?;
+        debug!("get new StreamResource request");
+        let size = stream_resource(peer, req.ResourcePath, &req.Destination)
+            .await
+            .map_err(|e| internal_error(format!("Stream Resource failed: {e}")))?;
+
+        let mut reply = StreamResourceResponse::new();
+        reply.Size = size;
+        debug!("the resource is written to the destination");
+        Ok(reply)
+    }
+}
+
+/// Prefix of a `Destination` of [`StreamResourceRequest`] which is a unix
+/// socket.
+const UNIX_SOCKET_PREFIX: &str = "unix://";
+
+/// Directory the streamed resources are written to as files.
+static STREAM_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set the directory of the files of [`StreamResourceRequest`], by default
+/// [`DEFAULT_STREAM_DIR`].
+pub fn set_stream_dir(dir: PathBuf) {
+    let _ = STREAM_DIR.set(dir);
+}
+
+fn stream_dir() -> &'static Path {
+    STREAM_DIR
+        .get()
+        .map(PathBuf::as_path)
+        .unwrap_or(Path::new(DEFAULT_STREAM_DIR))
+}
+
+/// Resolve the file `destination` of a streamed resource, which is relative
+/// to `dir` or an absolute path under it. CDH runs as root, so the files are
+/// never created out of `dir`: the destination must not contain `..`, and
+/// its parent is resolved without the symlinks before it is checked.
+async fn resolve_destination(dir: &Path, destination: &str) -> io::Result<PathBuf> {
+    let denied = |reason: &str| {
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("destination {destination} {reason}"),
+        )
+    };
+
+    let path = Path::new(destination);
+    if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir | Component::CurDir))
+    {
+        return Err(denied("must not contain `.` or `..`"));
+    }
+    let path = dir.join(path);
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(denied("is not a file"));
+    };
+
+    let dir = fs::canonicalize(dir).await?;
+    let parent = fs::canonicalize(parent).await?;
+    if !parent.starts_with(&dir) {
+        return Err(denied(&format!("is not under {}", dir.display())));
+    }
+    Ok(parent.join(name))
+}
+
+/// Open the `destination` of a streamed resource, and return the path of the
+/// file it is. A file is created only if it does not exist, which also never
+/// follows a symlink, and is only readable by the owner.
+async fn open_destination(
+    destination: &str,
+) -> io::Result<(Box<dyn AsyncWrite + Send + Unpin>, Option<PathBuf>)> {
+    match destination.strip_prefix(UNIX_SOCKET_PREFIX) {
+        Some(path) => Ok((Box::new(UnixStream::connect(path).await?), None)),
+        None => {
+            let path = resolve_destination(stream_dir(), destination).await?;
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+                .await?;
+            Ok((Box::new(file), Some(path)))
+        }
+    }
+}
+
+/// Write the resource `uri` to the `destination`. A partially written file
+/// is removed if the writing fails.
+async fn write_destination(uri: String, destination: &str) -> anyhow::Result<u64> {
+    let (mut writer, file) = open_destination(destination).await?;
+    let res = async {
+        let reader = HUB.read().await;
+        let reader = hub(&reader)?;
+        let size = reader.write_resource(uri, &mut writer).await?;
+        writer.shutdown().await?;
+        anyhow::Ok(size)
+    }
+    .await;
+
+    if let (Err(_), Some(file)) = (&res, file) {
+        let _ = fs::remove_file(file).await;
+    }
+    res
+}
+
+/// Parse the json object of a request field. An empty field is parsed as an
+/// empty object.
+pub fn parse_json_object(
+    json: &str,
+) -> serde_json::Result<serde_json::Map<String, serde_json::Value>> {
+    if json.is_empty() {
+        return Ok(Default::default());
+    }
+
+    serde_json::from_str(json)
+}
+
+fn status_error(code: Code, message: String) -> Error {
+    let mut status = Status::new();
+    status.set_code(code);
+    status.set_message(format!("[CDH] [ERROR]: {message}"));
+    Error::RpcStatus(status)
+}
+
+fn internal_error(message: String) -> Error {
+    status_error(Code::INTERNAL, message)
+}
+
+#[async_trait]
+impl SignService for Server {
+    async fn sign(&self, ctx: &TtrpcContext, req: SignRequest) -> ::ttrpc::Result<SignResponse> {
+        let peer = self.authorize_ttrpc(ctx, "sign")?;
+        self.release_ttrpc(peer, "sign", &[key_resource(&req.Provider, &req.KeyId)])?;
+        let _permit = self.limit_ttrpc(peer, "sign", 1).await?;
+        debug!("get new Sign request");
+        let provider_settings: ProviderSettings = parse_json_object(&req.ProviderSettings)
+            .map_err(|e| {
+                status_error(
+                    Code::INVALID_ARGUMENT,
+                    format!("Illegal ProviderSettings: {e}"),
+                )
+            })?;
+        let annotations: Annotations = parse_json_object(&req.Annotations).map_err(|e| {
+            status_error(Code::INVALID_ARGUMENT, format!("Illegal Annotations: {e}"))
+        })?;
+        let signature = sign(
+            peer,
+            &req.Provider,
+            provider_settings,
+            &req.KeyId,
+            &req.Message,
+            &annotations,
+        )
+        .await
+        .map_err(|e| internal_error(format!("Sign failed: {e}")))?;
+
+        let mut reply = SignResponse::new();
+        reply.Signature = signature;
+        debug!("send back the signature");
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl SecureMountService for Server {
+    async fn secure_mount(
+        &self,
+        ctx: &TtrpcContext,
+        req: SecureMountRequest,
+    ) -> ::ttrpc::Result<SecureMountResponse> {
+        let peer = self.authorize_ttrpc(ctx, "secure_mount")?;
+        let storage = SecureMount {
+            volume_type: req.VolumeType,
+            options: req.Options,
+            flags: req.Flags,
+            mount_point: req.MountPoint,
+        };
+        self.release_ttrpc(peer, "secure_mount", &storage.resources())?;
+        let _permit = self.limit_ttrpc(peer, "secure_mount", 1).await?;
+        debug!("get new SecureMount request");
+        let mount_path = secure_mount(peer, storage)
+            .await
+            .map_err(|e| internal_error(format!("Secure Mount failed: {e}")))?;
+
+        let mut reply = SecureMountResponse::new();
+        reply.MountPath = mount_path;
+        debug!("the storage is mounted");
+        Ok(reply)
+    }
+
+    async fn unmount_secure_storage(
+        &self,
+        ctx: &TtrpcContext,
+        req: UnmountSecureStorageRequest,
+    ) -> ::ttrpc::Result<UnmountSecureStorageResponse> {
+        let peer = self.authorize_ttrpc(ctx, "unmount_secure_storage")?;
+        let _permit = self.limit_ttrpc(peer, "unmount_secure_storage", 1).await?;
+        debug!("get new UnmountSecureStorage request");
+        unmount_secure_storage(peer, &req.MountPoint)
+            .await
+            .map_err(|e| internal_error(format!("Unmount Secure Storage failed: {e}")))?;
+
+        debug!("the storage is unmounted");
+        Ok(UnmountSecureStorageResponse::new())
+    }
+
+    async fn remount_secure_storage(
+        &self,
+        ctx: &TtrpcContext,
+        req: RemountSecureStorageRequest,
+    ) -> ::ttrpc::Result<RemountSecureStorageResponse> {
+        let peer = self.authorize_ttrpc(ctx, "remount_secure_storage")?;
+        let resources = mount_resources(&req.MountPoint).await;
+        self.release_ttrpc(peer, "secure_mount", &resources)?;
+        let _permit = self.limit_ttrpc(peer, "remount_secure_storage", 1).await?;
+        debug!("get new RemountSecureStorage request");
+        let mount_path = remount_secure_storage(peer, &req.MountPoint)
+            .await
+            .map_err(|e| internal_error(format!("Remount Secure Storage failed: {e}")))?;
+
+        let mut reply = RemountSecureStorageResponse::new();
+        reply.MountPath = mount_path;
+        debug!("the storage is remounted");
+        Ok(reply)
+    }
+}
+
+#[async_trait]
+impl ImagePullService for Server {
+    async fn pull_image(
+        &self,
+        ctx: &TtrpcContext,
+        req: PullImageRequest,
+    ) -> ::ttrpc::Result<PullImageResponse> {
+        let peer = self.authorize_ttrpc(ctx, "pull_image")?;
+        self.release_ttrpc(peer, "pull_image", std::slice::from_ref(&req.ImageUrl))?;
+        let _permit = self.limit_ttrpc(peer, "pull_image", 1).await?;
+        debug!("get new PullImage request");
+        let image_id = pull_image(peer, &req.ImageUrl, &req.BundlePath)
+            .await
+            .map_err(|e| internal_error(format!("Pull Image failed: {e}")))?;
+
+        let mut reply = PullImageResponse::new();
+        reply.ImageId = image_id;
+        debug!("the image is pulled");
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_destination;
+
+    #[tokio::test]
+    async fn destination_under_stream_dir() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("streams");
+        std::fs::create_dir_all(dir.join("model")).unwrap();
+        std::os::unix::fs::symlink(root.path(), dir.join("escape")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        for (destination, expected) in [
+            ("weights.bin", dir.join("weights.bin")),
+            ("model/weights.bin", dir.join("model/weights.bin")),
+        ] {
+            let path = resolve_destination(&dir, destination).await.unwrap();
+            assert_eq!(path, expected);
+        }
+        let absolute = dir.join("weights.bin");
+        let path = resolve_destination(&dir, absolute.to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(path, absolute);
+
+        for destination in [
+            "../weights.bin",
+            "model/../../weights.bin",
+            "escape/weights.bin",
+            "/etc/cdh",
+            "",
+        ] {
+            assert!(resolve_destination(&dir, destination).await.is_err());
+        }
+    }
 }
@@ -0,0 +1,588 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The config file of CDH in TOML format, e.g.
+//! ```toml
+//! socket = "unix:///run/confidential-containers/cdh.sock"
+//! allowed_uids = [0]
+//! shutdown_timeout_secs = 10
+//! stream_dir = "/run/confidential-containers/cdh/streams"
+//! credential_dir = "/run/confidential-containers/cdh/kms-credential"
+//!
+//! [kbc]
+//! name = "cc_kbc"
+//! kbs_host = "http://127.0.0.1:8080"
+//!
+//! [kbs_tls]
+//! root_ca = "/etc/kbs/ca.pem"
+//!
+//! [proxy]
+//! https_proxy = "http://proxy.internal:3128"
+//!
+//! [retry]
+//! max_attempts = 5
+//!
+//! [cache]
+//! ttl_secs = 600
+//!
+//! [audit]
+//! path = "/var/log/confidential-data-hub/audit.log"
+//! kbs_uri = "kbs:///default/audit/cdh"
+//!
+//! [rate_limit]
+//! requests_per_sec = 10
+//! max_concurrent = 32
+//!
+//! [release_policy]
+//! kbs_uri = "kbs:///default/cdh/release-policy"
+//! ```
+//! All the fields are optional. The settings given here take precedence over
+//! the per-setting config files, the env and the kernel commandline, which
+//! are still read for the settings not given. Unknown fields are rejected,
+//! so that typos are reported rather than silently ignored.
+
+use std::{net::SocketAddr, path::Path};
+
+use kms::{
+    plugins::{
+        kbs::{check_kbs_host, CachePolicy, RetryPolicy},
+        proxy::ProxyConfig,
+    },
+    KbsTlsPaths, Settings,
+};
+use serde::Deserialize;
+use tokio::fs;
+
+use crate::{Error, Result};
+
+/// Default path of the config file.
+pub const CDH_CONFIG_PATH: &str = "/etc/confidential-data-hub/cdh.toml";
+
+/// Environment variable to override [`CDH_CONFIG_PATH`].
+pub const CDH_CONFIG_PATH_ENV: &str = "CDH_CONFIG_PATH";
+
+/// Default interval to push the digest of the audit log to the KBS.
+pub const DEFAULT_AUDIT_PUSH_INTERVAL_SECS: u64 = 300;
+
+/// Default seconds to wait for the requests in flight at shutdown.
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 10;
+
+/// Default directory of the files streamed resources are written to.
+pub const DEFAULT_STREAM_DIR: &str = "/run/confidential-containers/cdh/streams";
+
+/// Names of the KBCs supported by the resource providers.
+const KBC_NAMES: [&str; 4] = [
+    "cc_kbc",
+    "online_sev_kbc",
+    "offline_fs_kbc",
+    "offline_ase_kbc",
+];
+
+/// Names of the KBCs connecting to a remote KBS, whose `kbs_host` are urls.
+const ONLINE_KBC_NAMES: [&str; 2] = ["cc_kbc", "online_sev_kbc"];
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CdhConfig {
+    /// Address of the ttRPC service, `unix://<path>` or `vsock://<cid>:<port>`.
+    pub socket: Option<String>,
+
+    /// Address of the gRPC service, `<ip>:<port>` or `unix://<path>`.
+    pub grpc_addr: Option<String>,
+
+    /// Address of the Prometheus metrics endpoint.
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Uids of the processes allowed to call the API.
+    #[serde(default)]
+    pub allowed_uids: Vec<u32>,
+
+    /// Gids of the processes allowed to call the API.
+    #[serde(default)]
+    pub allowed_gids: Vec<u32>,
+
+    /// Seconds to wait for the requests in flight at shutdown.
+    pub shutdown_timeout_secs: Option<u64>,
+
+    /// Directory of the files streamed resources are written to.
+    pub stream_dir: Option<String>,
+
+    /// Directory of the credentials of the KMS plugins.
+    pub credential_dir: Option<String>,
+
+    /// The KBC instance of the confidential resource providers.
+    pub kbc: Option<KbcConfig>,
+
+    /// TLS configuration of the connections to the KBS.
+    pub kbs_tls: Option<KbsTlsConfig>,
+
+    /// HTTP(S) proxy of the connections to the KBS and the KMSes.
+    pub proxy: Option<ProxyConfigFile>,
+
+    /// Retry policy of getting resources from the KBS.
+    pub retry: Option<RetryConfig>,
+
+    /// Cache policy of the resources got from the KBS.
+    pub cache: Option<CacheConfig>,
+
+    /// Audit log of the accesses to the secrets and the resources.
+    pub audit: Option<AuditConfig>,
+
+    /// Limits of the RPCs using secrets.
+    pub rate_limit: Option<RateLimitConfig>,
+
+    /// In-guest policy of releasing the secrets and the resources.
+    pub release_policy: Option<ReleasePolicyConfig>,
+}
+
+/// The `aa_kbc_params` in a structured form.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KbcConfig {
+    /// Name of the KBC, e.g. `cc_kbc`.
+    pub name: String,
+
+    /// Comma-separated urls of the KBSes, e.g. `http://kbs-0:8080,http://kbs-1:8080`.
+    pub kbs_host: String,
+}
+
+/// Paths of the PEM files used to connect to the KBS.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KbsTlsConfig {
+    pub root_ca: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfigFile {
+    pub https_proxy: Option<String>,
+    pub no_proxy: Option<String>,
+}
+
+/// Fields of [`RetryPolicy`], the ones not given are the defaults.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    pub max_attempts: Option<u32>,
+    pub backoff_base_ms: Option<u64>,
+    pub backoff_max_ms: Option<u64>,
+    pub attempt_timeout_secs: Option<u64>,
+}
+
+/// Fields of [`CachePolicy`], the ones not given are the defaults.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    pub ttl_secs: Option<u64>,
+    pub max_entries: Option<usize>,
+}
+
+/// The audit log, whose digest is pushed to the KBS resource `kbs_uri`
+/// every `push_interval_secs` if given.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AuditConfig {
+    pub path: String,
+    pub kbs_uri: Option<String>,
+    pub push_interval_secs: Option<u64>,
+}
+
+impl AuditConfig {
+    pub fn push_interval_secs(&self) -> u64 {
+        self.push_interval_secs
+            .unwrap_or(DEFAULT_AUDIT_PUSH_INTERVAL_SECS)
+    }
+}
+
+/// Limits of the RPCs using secrets. The requests of every peer are
+/// limited to `requests_per_sec` with bursts of `burst` requests, which is
+/// the rate rounded up by default, and at most `max_concurrent` requests
+/// of all the peers are handled at the same time. Nothing is limited if
+/// not given.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub requests_per_sec: Option<f64>,
+    pub burst: Option<u32>,
+    pub max_concurrent: Option<usize>,
+}
+
+impl RateLimitConfig {
+    pub fn burst(&self) -> u32 {
+        self.burst.unwrap_or_else(|| {
+            self.requests_per_sec
+                .map_or(1, |rate| (rate.ceil() as u32).max(1))
+        })
+    }
+}
+
+/// The [`crate::policy::ReleasePolicy`] got from the KBS resource `kbs_uri`.
+/// The policy must be of the hex `sha256` if given, and its digest is
+/// extended to the runtime measurements of the TEE unless `measure` is
+/// `false`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ReleasePolicyConfig {
+    pub kbs_uri: String,
+    pub sha256: Option<String>,
+    pub measure: Option<bool>,
+}
+
+impl RetryConfig {
+    fn policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(default.max_attempts),
+            backoff_base_ms: self.backoff_base_ms.unwrap_or(default.backoff_base_ms),
+            backoff_max_ms: self.backoff_max_ms.unwrap_or(default.backoff_max_ms),
+            attempt_timeout_secs: self
+                .attempt_timeout_secs
+                .unwrap_or(default.attempt_timeout_secs),
+        }
+    }
+}
+
+impl CacheConfig {
+    fn policy(&self) -> CachePolicy {
+        let default = CachePolicy::default();
+        CachePolicy {
+            ttl_secs: self.ttl_secs.unwrap_or(default.ttl_secs),
+            max_entries: self.max_entries.unwrap_or(default.max_entries),
+        }
+    }
+}
+
+impl CdhConfig {
+    /// Parse and validate the config in TOML format.
+    pub fn from_toml(config: &str) -> Result<Self> {
+        let config: Self =
+            toml::from_str(config).map_err(|e| Error::Config(format!("illegal toml: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read, parse and validate the config file of `path`.
+    pub async fn from_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .await
+            .map_err(|e| Error::Config(format!("read config file {path} failed: {e}")))?;
+        Self::from_toml(&content).map_err(|e| Error::Config(format!("{path}: {e}")))
+    }
+
+    /// Load the config file at [`CDH_CONFIG_PATH`] (or the path set by the
+    /// env [`CDH_CONFIG_PATH_ENV`]). If the file does not exist, the empty
+    /// config is returned.
+    pub async fn load() -> Result<Self> {
+        let path = config_path();
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+
+        Self::from_file(&path).await
+    }
+
+    /// Check the values of the config, without touching the network or the
+    /// files it refers to. All the problems found are reported together.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if let Some(socket) = &self.socket {
+            if !socket.starts_with("unix://") && !socket.starts_with("vsock://") {
+                problems.push(format!(
+                    "socket `{socket}` must start with `unix://` or `vsock://`"
+                ));
+            }
+        }
+
+        if let Some(addr) = &self.grpc_addr {
+            if !addr.starts_with("unix://") && addr.parse::<SocketAddr>().is_err() {
+                problems.push(format!(
+                    "grpc_addr `{addr}` must be `<ip>:<port>` or `unix://<path>`"
+                ));
+            }
+        }
+
+        if let Some(kbc) = &self.kbc {
+            if !KBC_NAMES.contains(&kbc.name.as_str()) {
+                problems.push(format!(
+                    "kbc.name `{}` is unknown, expected one of {}",
+                    kbc.name,
+                    KBC_NAMES.join(", ")
+                ));
+            }
+
+            let hosts: Vec<&str> = kbc
+                .kbs_host
+                .split(',')
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .collect();
+            if hosts.is_empty() {
+                problems.push("kbc.kbs_host is empty".into());
+            }
+            if ONLINE_KBC_NAMES.contains(&kbc.name.as_str()) {
+                for host in hosts {
+                    if let Err(e) = check_kbs_host(host) {
+                        problems.push(format!("kbc.kbs_host: {e}"));
+                    }
+                }
+            }
+        }
+
+        if let Some(tls) = &self.kbs_tls {
+            if tls.client_cert.is_some() != tls.client_key.is_some() {
+                problems.push(
+                    "kbs_tls.client_cert and kbs_tls.client_key must be given together".into(),
+                );
+            }
+            for (name, path) in [
+                ("kbs_tls.root_ca", &tls.root_ca),
+                ("kbs_tls.client_cert", &tls.client_cert),
+                ("kbs_tls.client_key", &tls.client_key),
+            ] {
+                check_absolute(name, path.as_deref(), &mut problems);
+            }
+        }
+
+        check_absolute(
+            "credential_dir",
+            self.credential_dir.as_deref(),
+            &mut problems,
+        );
+        check_absolute("stream_dir", self.stream_dir.as_deref(), &mut problems);
+
+        if let Some(proxy) = self.proxy.as_ref().and_then(|p| p.https_proxy.as_ref()) {
+            if !is_http_url(proxy) {
+                problems.push(format!("proxy.https_proxy `{proxy}` is not an http(s) url"));
+            }
+        }
+
+        if let Some(retry) = &self.retry {
+            let policy = retry.policy();
+            if policy.max_attempts == 0 {
+                problems.push("retry.max_attempts must be at least 1".into());
+            }
+            if policy.backoff_base_ms > policy.backoff_max_ms {
+                problems.push("retry.backoff_base_ms must not exceed retry.backoff_max_ms".into());
+            }
+        }
+
+        if let Some(audit) = &self.audit {
+            check_absolute("audit.path", Some(&audit.path), &mut problems);
+            if let Some(uri) = &audit.kbs_uri {
+                if !uri.starts_with("kbs://") {
+                    problems.push(format!("audit.kbs_uri `{uri}` is not a kbs resource uri"));
+                }
+            }
+            if audit.push_interval_secs() == 0 {
+                problems.push("audit.push_interval_secs must be at least 1".into());
+            }
+        }
+
+        if let Some(limit) = &self.rate_limit {
+            if let Some(rate) = limit.requests_per_sec {
+                if !(rate > 0.0 && rate.is_finite()) {
+                    problems.push("rate_limit.requests_per_sec must be positive".into());
+                }
+            }
+            if limit.burst == Some(0) {
+                problems.push("rate_limit.burst must be at least 1".into());
+            }
+            if limit.max_concurrent == Some(0) {
+                problems.push("rate_limit.max_concurrent must be at least 1".into());
+            }
+        }
+
+        if let Some(policy) = &self.release_policy {
+            if !policy.kbs_uri.starts_with("kbs://") {
+                problems.push(format!(
+                    "release_policy.kbs_uri `{}` is not a kbs resource uri",
+                    policy.kbs_uri
+                ));
+            }
+            if let Some(digest) = &policy.sha256 {
+                if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+                    problems.push(format!(
+                        "release_policy.sha256 `{digest}` is not a hex sha256 digest"
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::Config(problems.join("; ")))
+    }
+
+    pub fn shutdown_timeout_secs(&self) -> u64 {
+        self.shutdown_timeout_secs
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS)
+    }
+
+    pub fn stream_dir(&self) -> &str {
+        self.stream_dir.as_deref().unwrap_or(DEFAULT_STREAM_DIR)
+    }
+
+    /// The [`Settings`] of the KMS plugins given by the config.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            aa_kbc_params: self
+                .kbc
+                .as_ref()
+                .map(|kbc| format!("{}::{}", kbc.name, kbc.kbs_host)),
+            kbs_tls: self.kbs_tls.as_ref().map(|tls| KbsTlsPaths {
+                root_ca: tls.root_ca.clone(),
+                client_cert: tls.client_cert.clone(),
+                client_key: tls.client_key.clone(),
+            }),
+            proxy: self.proxy.as_ref().map(|proxy| ProxyConfig {
+                https_proxy: proxy.https_proxy.clone(),
+                no_proxy: proxy.no_proxy.clone(),
+            }),
+            retry: self.retry.as_ref().map(RetryConfig::policy),
+            cache: self.cache.as_ref().map(CacheConfig::policy),
+            credential_dir: self.credential_dir.clone(),
+        }
+    }
+}
+
+/// Path of the config file, [`CDH_CONFIG_PATH`] or the path set by the env
+/// [`CDH_CONFIG_PATH_ENV`].
+pub fn config_path() -> String {
+    std::env::var(CDH_CONFIG_PATH_ENV).unwrap_or_else(|_| CDH_CONFIG_PATH.into())
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn check_absolute(name: &str, path: Option<&str>, problems: &mut Vec<String>) {
+    if let Some(path) = path {
+        if !Path::new(path).is_absolute() {
+            problems.push(format!("{name} `{path}` must be an absolute path"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::CdhConfig;
+
+    const CONFIG: &str = r#"
+socket = "unix:///run/confidential-containers/cdh.sock"
+allowed_uids = [0, 1000]
+shutdown_timeout_secs = 3
+stream_dir = "/run/cdh/streams"
+
+[kbc]
+name = "cc_kbc"
+kbs_host = "http://kbs-0:8080,https://kbs-1:8080"
+
+[kbs_tls]
+root_ca = "/etc/kbs/ca.pem"
+
+[retry]
+max_attempts = 5
+
+[cache]
+ttl_secs = 0
+
+[audit]
+path = "/var/log/cdh/audit.log"
+kbs_uri = "kbs:///default/audit/cdh"
+
+[rate_limit]
+requests_per_sec = 2
+
+[release_policy]
+kbs_uri = "kbs:///default/cdh/release-policy"
+measure = false
+"#;
+
+    #[test]
+    fn parse_config() {
+        let config = CdhConfig::from_toml(CONFIG).unwrap();
+        assert_eq!(config.allowed_uids, vec![0, 1000]);
+        assert_eq!(config.shutdown_timeout_secs(), 3);
+        assert_eq!(config.stream_dir(), "/run/cdh/streams");
+
+        let settings = config.settings();
+        assert_eq!(
+            settings.aa_kbc_params.as_deref(),
+            Some("cc_kbc::http://kbs-0:8080,https://kbs-1:8080")
+        );
+        assert_eq!(
+            settings.kbs_tls.unwrap().root_ca.as_deref(),
+            Some("/etc/kbs/ca.pem")
+        );
+        let retry = settings.retry.unwrap();
+        assert_eq!(retry.max_attempts, 5);
+        assert_eq!(retry.backoff_max_ms, 10_000);
+        let cache = settings.cache.unwrap();
+        assert_eq!(cache.ttl_secs, 0);
+        assert_eq!(cache.max_entries, 128);
+        assert!(settings.proxy.is_none());
+        let audit = config.audit.unwrap();
+        assert_eq!(audit.kbs_uri.as_deref(), Some("kbs:///default/audit/cdh"));
+        assert_eq!(audit.push_interval_secs(), 300);
+        let rate_limit = config.rate_limit.unwrap();
+        assert_eq!(rate_limit.burst(), 2);
+        assert_eq!(rate_limit.max_concurrent, None);
+        let release_policy = config.release_policy.unwrap();
+        assert_eq!(release_policy.measure, Some(false));
+        assert_eq!(release_policy.sha256, None);
+
+        let config = CdhConfig::from_toml("").unwrap();
+        assert_eq!(config, CdhConfig::default());
+        assert_eq!(config.shutdown_timeout_secs(), 10);
+        assert_eq!(
+            config.stream_dir(),
+            "/run/confidential-containers/cdh/streams"
+        );
+    }
+
+    #[rstest]
+    #[case("sokcet = \"unix:///run/cdh.sock\"")]
+    #[case("[kbc]\nname = \"cc_kbc\"\nkbs_hots = \"http://kbs:8080\"")]
+    #[case("socket = \"/run/cdh.sock\"")]
+    #[case("grpc_addr = \"localhost\"")]
+    #[case("[kbc]\nname = \"cc-kbc\"\nkbs_host = \"http://kbs:8080\"")]
+    #[case("[kbc]\nname = \"cc_kbc\"\nkbs_host = \"kbs:8080\"")]
+    #[case("[kbc]\nname = \"cc_kbc\"\nkbs_host = \"http://fd00::1:8080\"")]
+    #[case("[kbc]\nname = \"offline_fs_kbc\"\nkbs_host = \"\"")]
+    #[case("[kbs_tls]\nclient_cert = \"/etc/kbs/client.pem\"")]
+    #[case("credential_dir = \"run/kms-credential\"")]
+    #[case("stream_dir = \"streams\"")]
+    #[case("[proxy]\nhttps_proxy = \"proxy:3128\"")]
+    #[case("[retry]\nmax_attempts = 0")]
+    #[case("[retry]\nbackoff_base_ms = 20000")]
+    #[case("[audit]\npath = \"audit.log\"")]
+    #[case("[audit]\npath = \"/var/log/audit.log\"\nkbs_uri = \"default/audit/cdh\"")]
+    #[case("[audit]\npath = \"/var/log/audit.log\"\npush_interval_secs = 0")]
+    #[case("[rate_limit]\nrequests_per_sec = 0")]
+    #[case("[rate_limit]\nrequests_per_sec = 10\nburst = 0")]
+    #[case("[rate_limit]\nmax_concurrent = 0")]
+    #[case("[release_policy]\nkbs_uri = \"/default/cdh/release-policy\"")]
+    #[case("[release_policy]\nkbs_uri = \"kbs:///default/cdh/release-policy\"\nsha256 = \"abc\"")]
+    fn illegal_config(#[case] config: &str) {
+        assert!(CdhConfig::from_toml(config).is_err());
+    }
+
+    #[test]
+    fn report_all_problems() {
+        let err = CdhConfig::from_toml("socket = \"/run/cdh.sock\"\n[retry]\nmax_attempts = 0")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("socket"));
+        assert!(err.contains("retry.max_attempts"));
+    }
+}
@@ -9,12 +9,36 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error("illegal config: {0}")]
+    Config(String),
+
     #[error("get resource failed: {0}")]
     GetResource(String),
 
     #[error("init Hub failed: {0}")]
     InitializationFailed(String),
 
+    #[error("release policy: {0}")]
+    Policy(String),
+
+    #[error("pull image failed: {0}")]
+    PullImage(String),
+
+    #[error("reseal secret failed: {0}")]
+    ResealSecret(String),
+
+    #[error("secure mount failed: {0}")]
+    SecureMount(String),
+
+    #[error("CDH is shutting down")]
+    ShuttingDown,
+
+    #[error("sign failed: {0}")]
+    Sign(String),
+
     #[error("unseal secret failed: {0}")]
     UnsealSecret(String),
+
+    #[error("unwrap key failed: {0}")]
+    UnwrapKey(String),
 }
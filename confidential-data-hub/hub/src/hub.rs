@@ -5,55 +5,73 @@
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD, Engine};
-use kms::{Annotations, ProviderSettings};
+use kms::{plugins::kbs::KbcClient, Annotations, ProviderSettings, SecretBytes};
 use secret::secret::Secret;
+use tokio::io::AsyncWrite;
+use tracing::instrument;
 
-use crate::{DataHub, Error, Result};
+use crate::{storage::Mounts, DataHub, Error, Result, SecureMount};
 
-pub struct Hub {}
+/// Prefix of the sealed secrets inside Kubernetes secrets, see
+/// <https://github.com/confidential-containers/guest-components/blob/main/confidential-data-hub/docs/SEALED_SECRET.md>
+pub(crate) const SEALED_PREFIX: &str = "sealed.";
+
+pub struct Hub {
+    mounts: Mounts,
+}
 
 impl Hub {
     pub async fn new() -> Result<Self> {
-        let mut hub = Self {};
+        let mut hub = Self {
+            mounts: Mounts::default(),
+        };
 
         hub.init().await?;
         Ok(hub)
     }
+
+    /// Release what must not outlive CDH, e.g. when the guest shuts down:
+    /// the ephemeral storages are torn down, the cached resources are
+    /// zeroized and the KBC instances with their keys and tokens are
+    /// dropped. The persistent storages are left to the guest to unmount.
+    pub async fn shutdown(&self) {
+        self.mounts.teardown_ephemeral().await;
+        KbcClient::close_all().await;
+    }
+
+    /// The resources the storage at `mount_point` is mounted with, which a
+    /// remount releases again, see [`SecureMount::resources`].
+    pub async fn mount_resources(&self, mount_point: &str) -> Result<Vec<String>> {
+        self.mounts.resources(mount_point).await
+    }
 }
 
 #[async_trait]
 impl DataHub for Hub {
-    async fn unseal_secret(&self, secret: Vec<u8>) -> Result<Vec<u8>> {
-        // TODO: verify the jws signature using the key specified by `kid`
-        // in header. Here we directly get the JWS payload
-        let payload = secret
-            .split(|c| *c == b'.')
-            .nth(1)
-            .ok_or_else(|| Error::UnsealSecret("illegal input sealed secret (not a JWS)".into()))?;
-
-        let secret_json = STANDARD.decode(payload).map_err(|e| {
-            Error::UnsealSecret(format!(
-                "illegal input sealed secret (JWS body is not standard base64 encoded): {e}"
-            ))
-        })?;
-        let secret: Secret = serde_json::from_slice(&secret_json).map_err(|e| {
-            Error::UnsealSecret(format!(
-                "illegal input sealed secret format (json deseralization failed): {e}"
-            ))
-        })?;
-
-        let res = secret
-            .unseal()
+    #[instrument(skip_all)]
+    async fn unseal_secret(&self, secret: Vec<u8>) -> Result<SecretBytes> {
+        unseal_secret(&secret).await
+    }
+
+    #[instrument(skip_all)]
+    async fn reseal_secret(&self, secret: Vec<u8>, key_id: Option<String>) -> Result<Vec<u8>> {
+        let secret = parse_sealed_secret(&secret)?;
+        let resealed = secret
+            .reseal(key_id.as_deref())
             .await
-            .map_err(|e| Error::UnsealSecret(format!("unseal failed: {e}")))?;
-        Ok(res)
+            .map_err(|e| Error::ResealSecret(format!("reseal failed: {e}")))?;
+        let json = serde_json::to_vec(&resealed)
+            .map_err(|e| Error::ResealSecret(format!("serialize secret failed: {e}")))?;
+        Ok(format!("{SEALED_PREFIX}{}", STANDARD.encode(json)).into_bytes())
     }
 
-    async fn unwrap_key(&self, _annotation: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    #[instrument(skip_all)]
+    async fn unwrap_key(&self, annotation: &[u8]) -> Result<SecretBytes> {
+        crate::image::unwrap_key(annotation).await
     }
 
-    async fn get_resource(&self, uri: String) -> Result<Vec<u8>> {
+    #[instrument(skip_all, fields(uri = %uri))]
+    async fn get_resource(&self, uri: String) -> Result<SecretBytes> {
         // to initialize a get_resource_provider client we do not need the ProviderSettings.
         let mut client = kms::new_getter("kbs", ProviderSettings::default())
             .await
@@ -66,4 +84,88 @@ impl DataHub for Hub {
             .map_err(|e| Error::GetResource(format!("get rersource failed: {e}")))?;
         Ok(res)
     }
+
+    #[instrument(skip_all, fields(uri = %uri))]
+    async fn write_resource(
+        &self,
+        uri: String,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let client = KbcClient::new()
+            .await
+            .map_err(|e| Error::GetResource(format!("create kbs client failed: {e}")))?;
+        let size = client
+            .write_resource(&uri, writer)
+            .await
+            .map_err(|e| Error::GetResource(format!("write resource failed: {e}")))?;
+        Ok(size)
+    }
+
+    #[instrument(skip_all, fields(provider = %provider, key_id = %key_id))]
+    async fn sign(
+        &self,
+        provider: &str,
+        provider_settings: ProviderSettings,
+        key_id: &str,
+        message: &[u8],
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>> {
+        let mut signer = kms::new_signer(provider, provider_settings)
+            .await
+            .map_err(|e| Error::Sign(format!("create {provider} client failed: {e}")))?;
+        let signature = signer
+            .sign(message, key_id, annotations)
+            .await
+            .map_err(|e| Error::Sign(format!("sign with {key_id} failed: {e}")))?;
+        Ok(signature)
+    }
+
+    #[instrument(skip_all, fields(image_url = %image_url))]
+    async fn pull_image(&self, image_url: &str, bundle_path: &str) -> Result<String> {
+        crate::image::pull_image(image_url, bundle_path).await
+    }
+
+    #[instrument(skip_all, fields(volume_type = %storage.volume_type))]
+    async fn secure_mount(&self, storage: SecureMount) -> Result<String> {
+        self.mounts.mount(storage).await
+    }
+
+    #[instrument(skip_all, fields(mount_point = %mount_point))]
+    async fn unmount_secure_storage(&self, mount_point: &str) -> Result<()> {
+        self.mounts.unmount(mount_point).await
+    }
+
+    #[instrument(skip_all, fields(mount_point = %mount_point))]
+    async fn remount_secure_storage(&self, mount_point: &str) -> Result<String> {
+        self.mounts.remount(mount_point).await
+    }
+}
+
+/// Parse the sealed secret, in format `sealed.<JWS payload>` or the whole
+/// JWS.
+fn parse_sealed_secret(secret: &[u8]) -> Result<Secret> {
+    Secret::from_sealed(secret).map_err(|e| Error::UnsealSecret(e.to_string()))
+}
+
+/// The resource of the KEK of a keyprovider `annotation` in the release
+/// policy, i.e. its KBS resource uri.
+pub fn kek_resource(annotation: &[u8]) -> Result<String> {
+    crate::image::kek_resource(annotation)
+}
+
+/// The resource of the sealed secret in the release policy, see
+/// [`Secret::resource`].
+pub fn secret_resource(secret: &[u8]) -> Result<String> {
+    Ok(parse_sealed_secret(secret)?.resource())
+}
+
+/// Unseal the sealed secret, in format `sealed.<JWS payload>` or the whole
+/// JWS.
+pub(crate) async fn unseal_secret(secret: &[u8]) -> Result<SecretBytes> {
+    let secret = parse_sealed_secret(secret)?;
+    let res = secret
+        .unseal()
+        .await
+        .map_err(|e| Error::UnsealSecret(format!("unseal failed: {e}")))?;
+    Ok(res)
 }
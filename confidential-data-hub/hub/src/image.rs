@@ -0,0 +1,138 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The images of the containers: unwrap the keys of the encrypted image
+//! layers, i.e. the annotations of the `attestation-agent` keyprovider of
+//! the layers, whose KEKs are KBS resources, and pull the images by image-rs
+//! with the `image-pull` feature.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto::WrapType;
+use kms::{Annotations, ProviderSettings, SecretBytes};
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use crate::{Error, Result};
+
+#[cfg(feature = "image-pull")]
+lazy_static::lazy_static! {
+    /// The image client, created by the first pull. The pulls are one at a
+    /// time, as they share the meta store and the snapshots of image-rs.
+    static ref IMAGE_CLIENT: tokio::sync::Mutex<Option<image_rs::image::ImageClient>> =
+        tokio::sync::Mutex::new(None);
+}
+
+/// The annotation of an encrypted layer, please refer to
+/// <https://github.com/confidential-containers/guest-components/blob/main/attestation-agent/docs/IMAGE_ENCRYPTION.md#annotation-packet>
+#[derive(Deserialize)]
+struct AnnotationPacket {
+    /// KBS resource URI of the KEK
+    kid: String,
+    /// Base64 of the wrapped LEK
+    wrapped_data: String,
+    /// Base64 of the IV
+    iv: String,
+    /// Algorithm of the wrapping, e.g. `A256GCM`
+    wrap_type: String,
+}
+
+fn parse_annotation(annotation: &[u8]) -> Result<AnnotationPacket> {
+    serde_json::from_slice(annotation)
+        .map_err(|e| Error::UnwrapKey(format!("illegal annotation packet: {e}")))
+}
+
+/// The KBS resource of the KEK of the annotation packet `annotation`.
+pub(crate) fn kek_resource(annotation: &[u8]) -> Result<String> {
+    Ok(parse_annotation(annotation)?.kid)
+}
+
+/// Unwrap the LEK of the annotation packet `annotation` by its KEK got
+/// from the KBS.
+pub(crate) async fn unwrap_key(annotation: &[u8]) -> Result<SecretBytes> {
+    let packet = parse_annotation(annotation)?;
+    let wrap_type = WrapType::try_from(&packet.wrap_type[..])
+        .map_err(|e| Error::UnwrapKey(format!("illegal wrap type: {e}")))?;
+    let wrapped_data = STANDARD
+        .decode(&packet.wrapped_data)
+        .map_err(|e| Error::UnwrapKey(format!("illegal wrapped data: {e}")))?;
+    let iv = STANDARD
+        .decode(&packet.iv)
+        .map_err(|e| Error::UnwrapKey(format!("illegal iv: {e}")))?;
+
+    let mut client = kms::new_getter("kbs", ProviderSettings::default())
+        .await
+        .map_err(|e| Error::UnwrapKey(format!("create kbs client failed: {e}")))?;
+    let kek = client
+        .get_secret(&packet.kid, &Annotations::default())
+        .await
+        .map_err(|e| Error::UnwrapKey(format!("get kek {} failed: {e}", packet.kid)))?;
+
+    crypto::decrypt(Zeroizing::new(kek.to_vec()), wrapped_data, iv, wrap_type)
+        .map(SecretBytes::from)
+        .map_err(|e| Error::UnwrapKey(format!("decrypt lek failed: {e}")))
+}
+
+/// Pull the image `image_url` by image-rs and unpack it to the bundle
+/// `bundle_path`. The layers are decrypted by the KBS of the
+/// `aa_kbc_params` of CDH, and the image is verified by the signature policy
+/// of the image-rs config.
+pub(crate) async fn pull_image(image_url: &str, bundle_path: &str) -> Result<String> {
+    if image_url.is_empty() || bundle_path.is_empty() {
+        return Err(Error::PullImage("empty image url or bundle path".into()));
+    }
+
+    #[cfg(feature = "image-pull")]
+    {
+        let (kbc, kbs_host) = kms::plugins::kbs::get_aa_params()
+            .await
+            .map_err(|e| Error::PullImage(format!("get aa_kbc_params failed: {e}")))?;
+        let decrypt_config = format!("provider:attestation-agent:{kbc}::{kbs_host}");
+
+        let mut client = IMAGE_CLIENT.lock().await;
+        let client = client.get_or_insert_with(image_rs::image::ImageClient::default);
+        client
+            .pull_image(
+                image_url,
+                std::path::Path::new(bundle_path),
+                &None,
+                &Some(decrypt_config.as_str()),
+            )
+            .await
+            .map_err(|e| Error::PullImage(format!("pull image {image_url} failed: {e:?}")))
+    }
+
+    #[cfg(not(feature = "image-pull"))]
+    Err(Error::PullImage(format!(
+        "`image-pull` feature not enabled, cannot pull image {image_url}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{pull_image, unwrap_key};
+
+    #[rstest]
+    #[case(b"not json")]
+    #[case(
+        br#"{"kid":"kbs:///default/key/1","wrapped_data":"AA==","iv":"AA==","wrap_type":"RC4"}"#
+    )]
+    #[case(
+        br#"{"kid":"kbs:///default/key/1","wrapped_data":"!","iv":"AA==","wrap_type":"A256GCM"}"#
+    )]
+    #[tokio::test]
+    async fn test_unwrap_key_illegal(#[case] annotation: &[u8]) {
+        assert!(unwrap_key(annotation).await.is_err());
+    }
+
+    #[rstest]
+    #[case("", "/run/bundle")]
+    #[case("docker.io/library/busybox:latest", "")]
+    #[tokio::test]
+    async fn test_pull_image_illegal(#[case] image_url: &str, #[case] bundle_path: &str) {
+        assert!(pull_image(image_url, bundle_path).await.is_err());
+    }
+}
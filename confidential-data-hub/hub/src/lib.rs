@@ -11,4 +11,13 @@ pub use api::*;
 
 pub mod hub;
 
+mod image;
+
 pub mod auth;
+
+pub mod config;
+
+pub mod policy;
+
+pub mod storage;
+pub use storage::SecureMount;
@@ -0,0 +1,347 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The in-guest policy of releasing the secrets and the resources, checked
+//! by CDH besides the policies of the KBS, e.g.
+//! ```json
+//! {
+//!     "default": "deny",
+//!     "rules": [
+//!         {
+//!             "effect": "allow",
+//!             "operations": ["get_resource", "unseal_secret"],
+//!             "resources": ["kbs:///default/app/*"],
+//!             "uids": [1000],
+//!             "hours": [8, 20]
+//!         }
+//!     ]
+//! }
+//! ```
+//! The first rule matching a request decides whether it is allowed, or the
+//! `default` if none does. A rule matches if all of its conditions do:
+//! - `operations`: the operations, i.e. `unseal_secret`, `reseal_secret`,
+//!   `get_resource`, `stream_resource`, `sign`, `secure_mount`,
+//!   `unwrap_key` and `pull_image`.
+//! - `resources`: the patterns of the resource uris, where `*` matches any
+//!   characters. The resource of a sealed secret is the key id of an
+//!   envelope secret or the name of a vault secret, as
+//!   `<provider>://<key id>` if it is not an uri, see [`key_resource`]. A
+//!   signing key is of the same form, a secure mount is of the resources
+//!   and the sealed secrets of its options, e.g. the key of a `luks`
+//!   volume, a keyprovider annotation is of its KEK, and an image pull is
+//!   of the image reference. An operation on no resource, e.g. the secure
+//!   mount of a `scratch` device, is of the empty resource, which the
+//!   pattern `*` matches.
+//! - `uids` and `gids`: the credentials of the requesters, which a requester
+//!   without credentials, e.g. over TCP, never matches.
+//! - `not_before` and `not_after`: the seconds since the unix epoch.
+//! - `hours`: the `[start, end)` hours of the day in UTC, which wraps around
+//!   the midnight if `start` is greater than `end`.
+//!
+//! The conditions not given match any request.
+
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Allow,
+    #[default]
+    Deny,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub effect: Effect,
+    #[serde(default)]
+    pub operations: Vec<String>,
+    #[serde(default)]
+    pub resources: Vec<String>,
+    #[serde(default)]
+    pub uids: Vec<u32>,
+    #[serde(default)]
+    pub gids: Vec<u32>,
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+    pub hours: Option<[u8; 2]>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ReleasePolicy {
+    #[serde(default)]
+    pub default: Effect,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// A request to release a secret or a resource.
+#[derive(Clone, Copy, Debug)]
+pub struct ReleaseRequest<'a> {
+    pub operation: &'a str,
+    pub resource: &'a str,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+
+    /// Seconds since the unix epoch.
+    pub time: u64,
+}
+
+/// The resource of the key or the secret `id` of the KMS `provider`, i.e.
+/// `id` itself if it is an uri, otherwise `<provider>://<id>`.
+pub fn key_resource(provider: &str, id: &str) -> String {
+    if id.contains("://") {
+        return id.to_string();
+    }
+
+    format!("{provider}://{id}")
+}
+
+impl ReleasePolicy {
+    /// Parse and validate the policy in json.
+    pub fn from_slice(policy: &[u8]) -> Result<Self> {
+        let policy: Self = serde_json::from_slice(policy)
+            .map_err(|e| Error::Policy(format!("illegal release policy: {e}")))?;
+        for (index, rule) in policy.rules.iter().enumerate() {
+            if let Some(hours) = rule.hours {
+                if hours.iter().any(|hour| *hour > 24) {
+                    return Err(Error::Policy(format!(
+                        "hours of rule {index} must be within 0 and 24"
+                    )));
+                }
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// The effect of the `request` due to the policy.
+    pub fn evaluate(&self, request: &ReleaseRequest) -> Effect {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(request))
+            .map_or(self.default, |rule| rule.effect)
+    }
+
+    /// Check whether the `request` is allowed by the policy.
+    pub fn authorize(&self, request: &ReleaseRequest) -> Result<()> {
+        match self.evaluate(request) {
+            Effect::Allow => Ok(()),
+            Effect::Deny => Err(Error::Policy(format!(
+                "{} of {} is denied",
+                request.operation, request.resource
+            ))),
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, request: &ReleaseRequest) -> bool {
+        if !self.operations.is_empty() && !self.operations.iter().any(|op| op == request.operation)
+        {
+            return false;
+        }
+
+        if !self.resources.is_empty()
+            && !self
+                .resources
+                .iter()
+                .any(|pattern| glob_match(pattern, request.resource))
+        {
+            return false;
+        }
+
+        if !self.uids.is_empty() && !request.uid.is_some_and(|uid| self.uids.contains(&uid)) {
+            return false;
+        }
+
+        if !self.gids.is_empty() && !request.gid.is_some_and(|gid| self.gids.contains(&gid)) {
+            return false;
+        }
+
+        if self.not_before.is_some_and(|time| request.time < time)
+            || self.not_after.is_some_and(|time| request.time > time)
+        {
+            return false;
+        }
+
+        if let Some([start, end]) = self.hours {
+            let hour = (request.time / 3600 % 24) as u8;
+            let within = match start <= end {
+                true => start <= hour && hour < end,
+                false => hour >= start || hour < end,
+            };
+            if !within {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Match the `text` against the `pattern`, where `*` matches any characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern.
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{glob_match, Effect, ReleasePolicy, ReleaseRequest};
+
+    const POLICY: &str = r#"{
+        "default": "deny",
+        "rules": [
+            {
+                "effect": "deny",
+                "resources": ["kbs:///default/app/admin-*"]
+            },
+            {
+                "effect": "allow",
+                "operations": ["get_resource", "unseal_secret"],
+                "resources": ["kbs:///default/app/*", "aliyun://key-*"],
+                "uids": [1000]
+            },
+            {
+                "effect": "allow",
+                "operations": ["stream_resource"],
+                "not_before": 1000,
+                "not_after": 2000
+            },
+            {
+                "effect": "allow",
+                "gids": [0],
+                "hours": [22, 2]
+            }
+        ]
+    }"#;
+
+    #[rstest]
+    #[case(
+        "get_resource",
+        "kbs:///default/app/key",
+        Some(1000),
+        None,
+        0,
+        Effect::Allow
+    )]
+    #[case("unseal_secret", "aliyun://key-1", Some(1000), None, 0, Effect::Allow)]
+    #[case(
+        "get_resource",
+        "kbs:///default/app/admin-key",
+        Some(1000),
+        None,
+        0,
+        Effect::Deny
+    )]
+    #[case(
+        "get_resource",
+        "kbs:///default/other/key",
+        Some(1000),
+        None,
+        0,
+        Effect::Deny
+    )]
+    #[case(
+        "get_resource",
+        "kbs:///default/app/key",
+        Some(1001),
+        None,
+        0,
+        Effect::Deny
+    )]
+    #[case("get_resource", "kbs:///default/app/key", None, None, 0, Effect::Deny)]
+    #[case(
+        "reseal_secret",
+        "kbs:///default/app/key",
+        Some(1000),
+        None,
+        0,
+        Effect::Deny
+    )]
+    #[case(
+        "stream_resource",
+        "kbs:///default/other/key",
+        None,
+        None,
+        1500,
+        Effect::Allow
+    )]
+    #[case(
+        "stream_resource",
+        "kbs:///default/other/key",
+        None,
+        None,
+        2500,
+        Effect::Deny
+    )]
+    #[case("reseal_secret", "kbs:///default/other/key", None, Some(0), 23 * 3600, Effect::Allow)]
+    #[case("reseal_secret", "kbs:///default/other/key", None, Some(0), 25 * 3600, Effect::Allow)]
+    #[case("reseal_secret", "kbs:///default/other/key", None, Some(0), 12 * 3600, Effect::Deny)]
+    fn evaluate(
+        #[case] operation: &str,
+        #[case] resource: &str,
+        #[case] uid: Option<u32>,
+        #[case] gid: Option<u32>,
+        #[case] time: u64,
+        #[case] effect: Effect,
+    ) {
+        let policy = ReleasePolicy::from_slice(POLICY.as_bytes()).unwrap();
+        let request = ReleaseRequest {
+            operation,
+            resource,
+            uid,
+            gid,
+            time,
+        };
+        assert_eq!(policy.evaluate(&request), effect);
+        assert_eq!(policy.authorize(&request).is_ok(), effect == Effect::Allow);
+    }
+
+    #[rstest]
+    #[case(r#"{"rules": [{"effect": "permit"}]}"#)]
+    #[case(r#"{"rules": [{"effect": "allow", "resource": ["*"]}]}"#)]
+    #[case(r#"{"rules": [{"effect": "allow", "hours": [0, 25]}]}"#)]
+    fn illegal_policy(#[case] policy: &str) {
+        assert!(ReleasePolicy::from_slice(policy.as_bytes()).is_err());
+    }
+
+    #[rstest]
+    #[case("*", "", true)]
+    #[case("kbs:///a/b/c", "kbs:///a/b/c", true)]
+    #[case("kbs:///a/b/c", "kbs:///a/b/cd", false)]
+    #[case("kbs:///a/*", "kbs:///a/b/c", true)]
+    #[case("kbs:///*/b/*", "kbs:///a/b/c", true)]
+    #[case("kbs:///*/b/*", "kbs:///a/c/b", false)]
+    #[case("*-key", "kbs:///a/b/admin-key", true)]
+    #[case("a*a", "a", false)]
+    fn glob(#[case] pattern: &str, #[case] text: &str, #[case] matched: bool) {
+        assert_eq!(glob_match(pattern, text), matched);
+    }
+}
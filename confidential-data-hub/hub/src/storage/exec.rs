@@ -0,0 +1,175 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `exec` volumes are mounted by a vetted mount helper, for the storage
+//! systems not supported natively. The helpers are the executables inside
+//! [`MOUNT_HELPERS_DIR`] of the guest image, so that no other program can
+//! be run.
+//!
+//! The options of the volume are
+//! - `helper`: file name of the helper inside [`MOUNT_HELPERS_DIR`].
+//! - any other option `<name>` is given to the helper as the env
+//!   `CDH_MOUNT_<NAME>`, with `<name>` in upper case. A value in format
+//!   `sealed.<...>` is a sealed secret, which is unsealed right before the
+//!   helper is run, so that e.g. credentials stay confidential.
+//!
+//! The helper is run as `<helper> <mount point>`, with the `Flags` joined by
+//! `,` in the env `CDH_MOUNT_FLAGS`, so no option can be named `flags`. The
+//! helper does not inherit the env of CDH, which may hold the credentials
+//! of the KMSes, but is only given the env above and the `PATH`
+//! [`HELPER_PATH`].
+
+use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::Path};
+
+use kms::SecretBytes;
+use log::info;
+use tokio::{fs, process::Command};
+
+use crate::{
+    hub::{secret_resource, unseal_secret, SEALED_PREFIX},
+    Error, Result,
+};
+
+use super::{SecureMount, Teardown};
+
+pub(super) const VOLUME_TYPE: &str = "exec";
+
+/// Directory of the vetted mount helpers.
+const MOUNT_HELPERS_DIR: &str = "/usr/libexec/confidential-data-hub/mount-helpers";
+
+const ENV_PREFIX: &str = "CDH_MOUNT_";
+
+/// Env of the flags of the storage.
+const FLAGS_ENV: &str = "CDH_MOUNT_FLAGS";
+
+/// `PATH` of the helpers.
+const HELPER_PATH: &str = "/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// The resources of the sealed secrets of the options of `storage`. An
+/// illegal sealed secret, which fails to be unsealed anyway, is of no
+/// resource.
+pub(super) fn resources(storage: &SecureMount) -> Vec<String> {
+    let mut resources: Vec<_> = storage
+        .options
+        .values()
+        .filter(|value| value.starts_with(SEALED_PREFIX))
+        .map(|value| secret_resource(value.as_bytes()).unwrap_or_default())
+        .collect();
+    resources.sort();
+    resources
+}
+
+/// Run the helper of `storage` with the unsealed options to mount it.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let helper = storage.required_option("helper")?;
+    if !is_legal_name(helper) {
+        return Err(Error::SecureMount(format!(
+            "exec: illegal helper name {helper}"
+        )));
+    }
+    storage.check_mount_point()?;
+
+    let path = Path::new(MOUNT_HELPERS_DIR).join(helper);
+    if !path.is_file() {
+        return Err(Error::SecureMount(format!(
+            "exec: helper {helper} is not found in {MOUNT_HELPERS_DIR}"
+        )));
+    }
+
+    let mut envs = Vec::with_capacity(storage.options.len());
+    for (name, value) in &storage.options {
+        if name == "helper" {
+            continue;
+        }
+        if !is_legal_name(name) || env_name(name) == FLAGS_ENV {
+            return Err(Error::SecureMount(format!(
+                "exec: illegal option name {name}"
+            )));
+        }
+
+        let value = if value.starts_with(SEALED_PREFIX) {
+            unseal_secret(value.as_bytes()).await.map_err(|e| {
+                Error::SecureMount(format!("exec: unseal option `{name}` failed: {e}"))
+            })?
+        } else {
+            SecretBytes::new(value.as_bytes())
+        };
+        envs.push((env_name(name), value));
+    }
+
+    fs::create_dir_all(&storage.mount_point)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!(
+                "exec: create mount point {} failed: {e}",
+                storage.mount_point
+            ))
+        })?;
+    let mut command = Command::new(&path);
+    command
+        .arg(&storage.mount_point)
+        .env_clear()
+        .env("PATH", HELPER_PATH)
+        .env(FLAGS_ENV, storage.flags.join(","));
+    for (name, value) in &envs {
+        command.env(name, OsStr::from_bytes(value));
+    }
+    let status = command
+        .status()
+        .await
+        .map_err(|e| Error::SecureMount(format!("exec: run helper {helper} failed: {e}")))?;
+    if !status.success() {
+        return Err(Error::SecureMount(format!(
+            "exec: helper {helper} exited with {status}"
+        )));
+    }
+
+    info!(
+        "exec: {} is mounted by helper {helper}",
+        storage.mount_point
+    );
+    Ok(Teardown::Unmount)
+}
+
+/// Names of the helpers and the options only consist of ascii
+/// alphanumerics, `-` and `_`, so that a helper cannot be out of
+/// [`MOUNT_HELPERS_DIR`].
+fn is_legal_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Env of the option `name`, e.g. `CDH_MOUNT_ACCESS_KEY` of `access-key`.
+fn env_name(name: &str) -> String {
+    format!(
+        "{ENV_PREFIX}{}",
+        name.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("ceph", true)]
+    #[case("my_helper-2", true)]
+    #[case("", false)]
+    #[case("../../bin/sh", false)]
+    #[case("/bin/sh", false)]
+    fn legal_name(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(super::is_legal_name(name), expected);
+    }
+
+    #[rstest]
+    #[case("user", "CDH_MOUNT_USER")]
+    #[case("access-key", "CDH_MOUNT_ACCESS_KEY")]
+    #[case("Flags", "CDH_MOUNT_FLAGS")]
+    fn env_name(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(super::env_name(name), expected);
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `luks` volumes are LUKS2-encrypted block devices whose passphrase is a
+//! KBS resource, so that the device can only be unlocked after attestation.
+//!
+//! The options of the volume are
+//! - `device`: path of the block device, e.g. `/dev/vdb`.
+//! - `key`: KBS resource URI of the passphrase, e.g. `kbs:///default/luks/pv-1`.
+//! - `fs_type`: type of the filesystem inside, `ext4` by default.
+//! - `format`: if `true`, a device which is not LUKS-encrypted yet is
+//!   formatted with the passphrase and a new filesystem is created inside.
+//!   The data on the device is lost.
+//! - `mapper_name`: name of the device mapper of the unlocked device. By
+//!   default derived from the device path.
+
+use std::path::Path;
+
+use log::{debug, info};
+use tokio::process::Command;
+
+use crate::{Error, Result};
+
+use super::{
+    get_kbs_resource, is_mounted, mount_device, run, SecureMount, Teardown, CRYPTSETUP_PATH,
+    MKFS_PATH,
+};
+
+pub(super) const VOLUME_TYPE: &str = "luks";
+
+/// The KBS resource of the key of `storage`.
+pub(super) fn resources(storage: &SecureMount) -> Vec<String> {
+    storage.options.get("key").into_iter().cloned().collect()
+}
+
+/// Unlock and mount the LUKS2 volume of `storage`. A device which is already
+/// unlocked or mounted is not unlocked or mounted again, e.g. after CDH is
+/// restarted.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let device = storage.required_option("device")?;
+    let key_uri = storage.required_option("key")?;
+    let format = storage.options.get("format").is_some_and(|v| v == "true");
+    let mapper_name = storage.mapper_name(device);
+    storage.check_mount_point()?;
+
+    let mapper = format!("/dev/mapper/{mapper_name}");
+    if is_mounted(&storage.mount_point).await {
+        debug!(
+            "luks: {mapper} is already mounted at {}",
+            storage.mount_point
+        );
+        return Ok(Teardown::CloseMapper(mapper_name));
+    }
+
+    let mut formatted = false;
+    if !Path::new(&mapper).exists() {
+        let key = get_kbs_resource(key_uri).await?;
+        if !is_luks(device).await? {
+            if !format {
+                return Err(Error::SecureMount(format!(
+                    "luks: {device} is not LUKS-encrypted, and `format` is not set"
+                )));
+            }
+
+            info!("luks: format {device} with LUKS2");
+            run(
+                CRYPTSETUP_PATH,
+                &[
+                    "luksFormat",
+                    "--type",
+                    "luks2",
+                    "--batch-mode",
+                    "--key-file=-",
+                    device,
+                ],
+                Some(&key),
+            )
+            .await?;
+            formatted = true;
+        }
+
+        run(
+            CRYPTSETUP_PATH,
+            &[
+                "open",
+                "--type",
+                "luks2",
+                "--key-file=-",
+                device,
+                &mapper_name,
+            ],
+            Some(&key),
+        )
+        .await?;
+    }
+
+    if formatted {
+        run(MKFS_PATH, &["-t", storage.fs_type(), &mapper], None).await?;
+    }
+
+    mount_device(storage, &mapper).await?;
+    info!("luks: {device} is mounted at {}", storage.mount_point);
+    Ok(Teardown::CloseMapper(mapper_name))
+}
+
+async fn is_luks(device: &str) -> Result<bool> {
+    let status = Command::new(CRYPTSETUP_PATH)
+        .args(["isLuks", device])
+        .status()
+        .await
+        .map_err(|e| Error::SecureMount(format!("luks: run cryptsetup failed: {e}")))?;
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::{storage::SecureMount, Error};
+
+    #[rstest]
+    #[case(&[("key", "kbs:///default/luks/pv-1")], "/mnt/pv")]
+    #[case(&[("device", "/dev/vdb")], "/mnt/pv")]
+    #[case(&[("device", "/dev/vdb"), ("key", "kbs:///default/luks/pv-1")], "")]
+    #[tokio::test]
+    async fn missing_options(#[case] options: &[(&str, &str)], #[case] mount_point: &str) {
+        let storage = SecureMount {
+            volume_type: "luks".into(),
+            options: options
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            flags: Vec::new(),
+            mount_point: mount_point.into(),
+        };
+        assert!(matches!(
+            super::mount(&storage).await,
+            Err(Error::SecureMount(_))
+        ));
+    }
+}
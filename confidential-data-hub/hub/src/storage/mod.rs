@@ -0,0 +1,468 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Secure mount of the storages which can only be used after attestation,
+//! e.g. encrypted persistent volumes whose keys are held by the KBS.
+
+use std::{collections::HashMap, path::Path, process::Stdio};
+
+use kms::{Annotations, ProviderSettings, SecretBytes};
+use log::{info, warn};
+use tokio::{fs, io::AsyncWriteExt, process::Command, sync::Mutex};
+
+use crate::{Error, Result};
+
+mod exec;
+mod luks;
+mod nfs;
+mod s3;
+mod scratch;
+mod secret_dir;
+
+const CRYPTSETUP_PATH: &str = "/sbin/cryptsetup";
+const MKFS_PATH: &str = "/sbin/mkfs";
+const MOUNT_PATH: &str = "/bin/mount";
+const UMOUNT_PATH: &str = "/bin/umount";
+const KILL_PATH: &str = "/bin/kill";
+
+const DEFAULT_FS_TYPE: &str = "ext4";
+
+/// A storage to be mounted by [`DataHub::secure_mount`](crate::DataHub::secure_mount).
+#[derive(Debug, Default)]
+pub struct SecureMount {
+    /// Type of the volume, e.g. `luks`.
+    pub volume_type: String,
+
+    /// Options of the volume, see the docs of the volume types.
+    pub options: HashMap<String, String>,
+
+    /// Options given to `mount`, e.g. `ro` or `nodev`.
+    pub flags: Vec<String>,
+
+    /// Path to mount the storage at.
+    pub mount_point: String,
+}
+
+impl SecureMount {
+    /// Get the option `name`, which must be given and not empty.
+    fn required_option(&self, name: &str) -> Result<&str> {
+        self.options
+            .get(name)
+            .filter(|value| !value.is_empty())
+            .map(String::as_str)
+            .ok_or_else(|| {
+                Error::SecureMount(format!("{}: option `{name}` is required", self.volume_type))
+            })
+    }
+
+    /// Type of the filesystem inside the device.
+    fn fs_type(&self) -> &str {
+        self.options
+            .get("fs_type")
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_FS_TYPE)
+    }
+
+    /// Name of the device mapper of the `device`, the option `mapper_name`
+    /// or by default derived from the path, e.g. `cdh-luks-dev-vdb` of
+    /// `/dev/vdb`.
+    fn mapper_name(&self, device: &str) -> String {
+        if let Some(name) = self.options.get("mapper_name") {
+            return name.clone();
+        }
+
+        format!("cdh-{}-{}", self.volume_type, escape_path(device))
+    }
+
+    /// The secrets and the resources the storage is mounted with, e.g. the
+    /// KBS resource of the key of a `luks` volume, in the form of the
+    /// resources of the [release policy](crate::policy).
+    pub fn resources(&self) -> Vec<String> {
+        match self.volume_type.as_str() {
+            exec::VOLUME_TYPE => exec::resources(self),
+            luks::VOLUME_TYPE => luks::resources(self),
+            nfs::VOLUME_TYPE => nfs::resources(self),
+            s3::VOLUME_TYPE => s3::resources(self),
+            secret_dir::VOLUME_TYPE => secret_dir::resources(self),
+            _ => Vec::new(),
+        }
+    }
+
+    fn check_mount_point(&self) -> Result<()> {
+        if self.mount_point.is_empty() {
+            return Err(Error::SecureMount(format!(
+                "{}: mount point is required",
+                self.volume_type
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// How to tear down a storage after it is unmounted.
+enum Teardown {
+    /// Nothing to do besides unmounting.
+    Unmount,
+
+    /// Close the device mapper of the name.
+    CloseMapper(String),
+
+    /// Stop the `stunnel` whose runtime files are in the directory, and
+    /// remove the directory.
+    StopStunnel(String),
+}
+
+/// A storage mounted by [`Mounts`].
+struct ActiveMount {
+    storage: SecureMount,
+    teardown: Teardown,
+}
+
+/// The secure mounts of CDH, tracked by their mount points to unmount or
+/// remount them.
+#[derive(Default)]
+pub struct Mounts {
+    active: Mutex<HashMap<String, ActiveMount>>,
+}
+
+impl Mounts {
+    /// Mount the `storage` and return the path it is mounted at. A mount
+    /// point already mounted by CDH must be unmounted or remounted instead,
+    /// so that the teardown of the storage mounted there is not lost.
+    pub async fn mount(&self, storage: SecureMount) -> Result<String> {
+        let mut active = self.active.lock().await;
+        if active.contains_key(&storage.mount_point) {
+            return Err(Error::SecureMount(format!(
+                "{} is already mounted by CDH",
+                storage.mount_point
+            )));
+        }
+
+        let teardown = mount(&storage).await?;
+        let mount_point = storage.mount_point.clone();
+        active.insert(mount_point.clone(), ActiveMount { storage, teardown });
+        Ok(mount_point)
+    }
+
+    /// The [`SecureMount::resources`] of the storage at `mount_point`.
+    pub async fn resources(&self, mount_point: &str) -> Result<Vec<String>> {
+        let active = self.active.lock().await;
+        let mount = active
+            .get(mount_point)
+            .ok_or_else(|| not_mounted(mount_point))?;
+        Ok(mount.storage.resources())
+    }
+
+    /// Unmount the storage at `mount_point` and tear it down, e.g. close the
+    /// encrypted device, e.g. when the pod is deleted.
+    pub async fn unmount(&self, mount_point: &str) -> Result<()> {
+        let mut active = self.active.lock().await;
+        let mount = active
+            .get(mount_point)
+            .ok_or_else(|| not_mounted(mount_point))?;
+        unmount(mount_point, &mount.teardown, false).await?;
+        active.remove(mount_point);
+        info!("{mount_point} is unmounted");
+        Ok(())
+    }
+
+    /// Unmount the storage at `mount_point` and mount it again, e.g. after
+    /// its device fails. The storage is detached lazily if it is still busy.
+    /// If the mount fails, the storage is still tracked to be remounted
+    /// later.
+    pub async fn remount(&self, mount_point: &str) -> Result<String> {
+        let mut active = self.active.lock().await;
+        let mount = active
+            .remove(mount_point)
+            .ok_or_else(|| not_mounted(mount_point))?;
+        if let Err(e) = unmount(mount_point, &mount.teardown, true).await {
+            warn!("unmount {mount_point} to remount failed, mount it anyway: {e}");
+        }
+
+        match self::mount(&mount.storage).await {
+            Ok(teardown) => {
+                active.insert(
+                    mount_point.to_string(),
+                    ActiveMount {
+                        storage: mount.storage,
+                        teardown,
+                    },
+                );
+                info!("{mount_point} is remounted");
+                Ok(mount_point.to_string())
+            }
+            Err(e) => {
+                active.insert(mount_point.to_string(), mount);
+                Err(e)
+            }
+        }
+    }
+
+    /// Unmount and tear down the ephemeral storages, i.e. the `scratch`
+    /// devices of ephemeral keys and the tmpfs `secret` directories, whose
+    /// contents are useless once CDH is gone. The busy ones are detached
+    /// lazily, and the failures are only logged.
+    pub async fn teardown_ephemeral(&self) {
+        let mut active = self.active.lock().await;
+        let ephemeral: Vec<String> = active
+            .iter()
+            .filter(|(_, mount)| is_ephemeral(&mount.storage.volume_type))
+            .map(|(mount_point, _)| mount_point.clone())
+            .collect();
+        for mount_point in ephemeral {
+            let mount = active.remove(&mount_point).expect("must be mounted");
+            match unmount(&mount_point, &mount.teardown, true).await {
+                Ok(()) => info!("{mount_point} is torn down"),
+                Err(e) => warn!("tear down {mount_point} failed: {e}"),
+            }
+        }
+    }
+}
+
+/// Whether the storages of the `volume_type` are gone with their keys, see
+/// [`Mounts::teardown_ephemeral`].
+fn is_ephemeral(volume_type: &str) -> bool {
+    volume_type == scratch::VOLUME_TYPE || volume_type == secret_dir::VOLUME_TYPE
+}
+
+fn not_mounted(mount_point: &str) -> Error {
+    Error::SecureMount(format!("{mount_point} is not mounted by CDH"))
+}
+
+async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    match storage.volume_type.as_str() {
+        exec::VOLUME_TYPE => exec::mount(storage).await,
+        luks::VOLUME_TYPE => luks::mount(storage).await,
+        nfs::VOLUME_TYPE => nfs::mount(storage).await,
+        s3::VOLUME_TYPE => s3::mount(storage).await,
+        scratch::VOLUME_TYPE => scratch::mount(storage).await,
+        secret_dir::VOLUME_TYPE => secret_dir::mount(storage).await,
+        other => Err(Error::SecureMount(format!(
+            "unsupported volume type {other}"
+        ))),
+    }
+}
+
+/// Unmount the `mount_point` if it is mounted, and do the `teardown`. A
+/// `lazy` unmount detaches a busy filesystem, and defers closing its device.
+async fn unmount(mount_point: &str, teardown: &Teardown, lazy: bool) -> Result<()> {
+    if is_mounted(mount_point).await {
+        let args: &[&str] = if lazy {
+            &["-l", mount_point]
+        } else {
+            &[mount_point]
+        };
+        run(UMOUNT_PATH, args, None).await?;
+    }
+
+    match teardown {
+        Teardown::Unmount => {}
+        Teardown::CloseMapper(name) => {
+            if Path::new(&format!("/dev/mapper/{name}")).exists() {
+                let args: &[&str] = if lazy {
+                    &["close", "--deferred", name]
+                } else {
+                    &["close", name]
+                };
+                run(CRYPTSETUP_PATH, args, None).await?;
+            }
+        }
+        Teardown::StopStunnel(dir) => {
+            if let Ok(pid) = fs::read_to_string(format!("{dir}/stunnel.pid")).await {
+                run(KILL_PATH, &[pid.trim()], None).await?;
+            }
+            if Path::new(dir).exists() {
+                fs::remove_dir_all(dir)
+                    .await
+                    .map_err(|e| Error::SecureMount(format!("remove {dir} failed: {e}")))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Escape the `path` to be a part of a file name, e.g. `dev-vdb` of
+/// `/dev/vdb`.
+fn escape_path(path: &str) -> String {
+    path.trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Get the resource `uri` from the KBS, e.g. a passphrase or a private key.
+async fn get_kbs_resource(uri: &str) -> Result<SecretBytes> {
+    let mut client = kms::new_getter("kbs", ProviderSettings::default())
+        .await
+        .map_err(|e| Error::SecureMount(format!("create kbs client failed: {e}")))?;
+    let resource = client
+        .get_secret(uri, &Annotations::default())
+        .await
+        .map_err(|e| Error::SecureMount(format!("get resource {uri} failed: {e}")))?;
+    Ok(resource)
+}
+
+/// Mount the filesystem of the `device` at the mount point of `storage`.
+async fn mount_device(storage: &SecureMount, device: &str) -> Result<()> {
+    fs::create_dir_all(&storage.mount_point)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!(
+                "create mount point {} failed: {e}",
+                storage.mount_point
+            ))
+        })?;
+    let mut args = vec!["-t", storage.fs_type()];
+    let flags = storage.flags.join(",");
+    if !flags.is_empty() {
+        args.extend(["-o", &flags]);
+    }
+    args.extend([device, storage.mount_point.as_str()]);
+    run(MOUNT_PATH, &args, None).await
+}
+
+/// Run the `program`, with the `input` written to its stdin.
+async fn run(program: &str, args: &[&str], input: Option<&[u8]>) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .spawn()
+        .map_err(|e| Error::SecureMount(format!("run {program} failed: {e}")))?;
+    if let Some(input) = input {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin
+            .write_all(input)
+            .await
+            .map_err(|e| Error::SecureMount(format!("write to {program} failed: {e}")))?;
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| Error::SecureMount(format!("run {program} failed: {e}")))?;
+    if !status.success() {
+        return Err(Error::SecureMount(format!(
+            "{program} {} exited with {status}",
+            args.first().unwrap_or(&"")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Whether a filesystem is mounted at `path`.
+async fn is_mounted(path: &str) -> bool {
+    let mounts = fs::read_to_string("/proc/self/mounts")
+        .await
+        .unwrap_or_default();
+    mounts
+        .lines()
+        .filter_map(|line| line.split_ascii_whitespace().nth(1))
+        .any(|mount_point| Path::new(mount_point) == Path::new(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{ActiveMount, Mounts, SecureMount, Teardown};
+    use crate::Error;
+
+    #[rstest]
+    #[case("luks", "/dev/vdb", "cdh-luks-dev-vdb")]
+    #[case(
+        "luks",
+        "/dev/disk/by-id/virtio-pv_1",
+        "cdh-luks-dev-disk-by-id-virtio-pv-1"
+    )]
+    #[case("scratch", "/dev/nvme0n1", "cdh-scratch-dev-nvme0n1")]
+    fn mapper_name(#[case] volume_type: &str, #[case] device: &str, #[case] expected: &str) {
+        let storage = SecureMount {
+            volume_type: volume_type.into(),
+            ..Default::default()
+        };
+        assert_eq!(storage.mapper_name(device), expected);
+    }
+
+    #[rstest]
+    #[case("luks", &[("device", "/dev/vdb"), ("key", "kbs:///default/luks/pv")], &["kbs:///default/luks/pv"])]
+    #[case("nfs", &[("ca_cert", "kbs:///default/nfs/ca"), ("client_key", "kbs:///default/nfs/key")], &["kbs:///default/nfs/ca", "kbs:///default/nfs/key"])]
+    #[case("s3", &[("credentials", "kbs:///default/s3/creds")], &["kbs:///default/s3/creds"])]
+    #[case("s3", &[("provider", "aliyun"), ("credentials", "s3-creds")], &["aliyun://s3-creds"])]
+    #[case("exec", &[("helper", "gcsfuse"), ("token", "sealed.illegal"), ("bucket", "data")], &[""])]
+    #[case("scratch", &[("device", "/dev/vdc")], &[])]
+    fn resources(
+        #[case] volume_type: &str,
+        #[case] options: &[(&str, &str)],
+        #[case] expected: &[&str],
+    ) {
+        let storage = SecureMount {
+            volume_type: volume_type.into(),
+            options: options
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(storage.resources(), expected);
+    }
+
+    #[tokio::test]
+    async fn not_mounted() {
+        let mounts = Mounts::default();
+        assert!(matches!(
+            mounts.unmount("/mnt/pv").await,
+            Err(Error::SecureMount(_))
+        ));
+        assert!(matches!(
+            mounts.remount("/mnt/pv").await,
+            Err(Error::SecureMount(_))
+        ));
+        assert!(matches!(
+            mounts.resources("/mnt/pv").await,
+            Err(Error::SecureMount(_))
+        ));
+
+        let storage = SecureMount {
+            volume_type: "unknown".into(),
+            mount_point: "/mnt/pv".into(),
+            ..Default::default()
+        };
+        assert!(mounts.mount(storage).await.is_err());
+        assert!(mounts.unmount("/mnt/pv").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn already_mounted() {
+        let mounts = Mounts::default();
+        mounts.active.lock().await.insert(
+            "/mnt/pv".into(),
+            ActiveMount {
+                storage: SecureMount::default(),
+                teardown: Teardown::CloseMapper("cdh-luks-dev-vdb".into()),
+            },
+        );
+
+        let storage = SecureMount {
+            volume_type: "scratch".into(),
+            mount_point: "/mnt/pv".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            mounts.mount(storage).await,
+            Err(Error::SecureMount(_))
+        ));
+        assert!(matches!(
+            mounts.active.lock().await["/mnt/pv"].teardown,
+            Teardown::CloseMapper(_)
+        ));
+    }
+}
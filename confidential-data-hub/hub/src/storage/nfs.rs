@@ -0,0 +1,196 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `nfs` volumes are NFS exports mounted over TLS. An in-guest `stunnel`
+//! connects to the TLS endpoint of the NFS server with the certificates got
+//! from the KBS, and the export is mounted through it, so that the traffic
+//! is never exposed to the host network.
+//!
+//! The options of the volume are
+//! - `server`: `<host>:<port>` of the TLS endpoint of the NFS server. The
+//!   certificate of the server is verified against `<host>`.
+//! - `export`: path of the export, e.g. `/exports/data`.
+//! - `ca_cert`: KBS resource URI of the PEM CA certificates to verify the
+//!   server with.
+//! - `client_cert` and `client_key`: KBS resource URIs of the PEM client
+//!   certificate and private key for mutual TLS, optional.
+//!
+//! The certificates and the config of `stunnel` are written to a directory
+//! under [`NFS_RUN_DIR`], which is on the memory of the guest.
+
+use std::net::TcpListener;
+
+use log::{debug, info};
+use tokio::fs::{self, DirBuilder, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+use crate::{Error, Result};
+
+use super::{escape_path, get_kbs_resource, is_mounted, run, SecureMount, Teardown, MOUNT_PATH};
+
+pub(super) const VOLUME_TYPE: &str = "nfs";
+
+const STUNNEL_PATH: &str = "/usr/bin/stunnel";
+
+/// Directory of the runtime files of the `nfs` volumes.
+const NFS_RUN_DIR: &str = "/run/confidential-containers/cdh/nfs";
+
+/// The KBS resources of the certificates and the key of `storage`.
+pub(super) fn resources(storage: &SecureMount) -> Vec<String> {
+    ["ca_cert", "client_cert", "client_key"]
+        .iter()
+        .filter_map(|name| storage.options.get(*name).cloned())
+        .collect()
+}
+
+/// Start a `stunnel` to the server of `storage` and mount the export
+/// through it. An export which is already mounted is kept.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let server = storage.required_option("server")?;
+    let export = storage.required_option("export")?;
+    let ca_cert = storage.required_option("ca_cert")?;
+    let client_cert = storage.options.get("client_cert");
+    let client_key = storage.options.get("client_key");
+    if client_cert.is_some() != client_key.is_some() {
+        return Err(Error::SecureMount(
+            "nfs: options `client_cert` and `client_key` must be given together".into(),
+        ));
+    }
+    let (host, _) = server
+        .rsplit_once(':')
+        .ok_or_else(|| Error::SecureMount(format!("nfs: illegal server {server}")))?;
+    storage.check_mount_point()?;
+
+    let dir = format!("{NFS_RUN_DIR}/{}", escape_path(&storage.mount_point));
+    if is_mounted(&storage.mount_point).await {
+        debug!(
+            "nfs: {server}:{export} is already mounted at {}",
+            storage.mount_point
+        );
+        return Ok(Teardown::StopStunnel(dir));
+    }
+
+    DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .await
+        .map_err(|e| Error::SecureMount(format!("nfs: create {dir} failed: {e}")))?;
+
+    let ca_path = format!("{dir}/ca.pem");
+    write_private(&ca_path, &get_kbs_resource(ca_cert).await?).await?;
+    let port = free_port()?;
+    let mut config = format!(
+        "foreground = no\n\
+         pid = {dir}/stunnel.pid\n\
+         [nfs]\n\
+         client = yes\n\
+         accept = 127.0.0.1:{port}\n\
+         connect = {server}\n\
+         verifyChain = yes\n\
+         CAfile = {ca_path}\n\
+         checkHost = {host}\n"
+    );
+    if let (Some(cert), Some(key)) = (client_cert, client_key) {
+        let cert_path = format!("{dir}/client.pem");
+        let key_path = format!("{dir}/client.key");
+        write_private(&cert_path, &get_kbs_resource(cert).await?).await?;
+        write_private(&key_path, &get_kbs_resource(key).await?).await?;
+        config.push_str(&format!("cert = {cert_path}\nkey = {key_path}\n"));
+    }
+    let config_path = format!("{dir}/stunnel.conf");
+    write_private(&config_path, config.as_bytes()).await?;
+    run(STUNNEL_PATH, &[&config_path], None).await?;
+
+    fs::create_dir_all(&storage.mount_point)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!(
+                "nfs: create mount point {} failed: {e}",
+                storage.mount_point
+            ))
+        })?;
+    let mut options = vec![format!("port={port}"), "proto=tcp".to_string()];
+    options.extend(storage.flags.iter().cloned());
+    run(
+        MOUNT_PATH,
+        &[
+            "-t",
+            "nfs4",
+            "-o",
+            &options.join(","),
+            &format!("127.0.0.1:{export}"),
+            &storage.mount_point,
+        ],
+        None,
+    )
+    .await?;
+
+    info!(
+        "nfs: {server}:{export} is mounted over TLS at {}",
+        storage.mount_point
+    );
+    Ok(Teardown::StopStunnel(dir))
+}
+
+/// Write the `content` to a new file only readable by the owner.
+async fn write_private(path: &str, content: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .await
+        .map_err(|e| Error::SecureMount(format!("nfs: create {path} failed: {e}")))?;
+    file.write_all(content)
+        .await
+        .map_err(|e| Error::SecureMount(format!("nfs: write {path} failed: {e}")))
+}
+
+/// A local port for `stunnel` to listen on.
+fn free_port() -> Result<u16> {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| Error::SecureMount(format!("nfs: get a free local port failed: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::{storage::SecureMount, Error};
+
+    #[rstest]
+    #[case(&[("export", "/data"), ("ca_cert", "kbs:///default/nfs/ca")])]
+    #[case(&[("server", "nfs:20049"), ("ca_cert", "kbs:///default/nfs/ca")])]
+    #[case(&[("server", "nfs:20049"), ("export", "/data")])]
+    #[case(&[("server", "nfs"), ("export", "/data"), ("ca_cert", "kbs:///default/nfs/ca")])]
+    #[case(&[
+        ("server", "nfs:20049"),
+        ("export", "/data"),
+        ("ca_cert", "kbs:///default/nfs/ca"),
+        ("client_cert", "kbs:///default/nfs/cert"),
+    ])]
+    #[tokio::test]
+    async fn illegal_options(#[case] options: &[(&str, &str)]) {
+        let storage = SecureMount {
+            volume_type: "nfs".into(),
+            options: options
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            flags: Vec::new(),
+            mount_point: "/mnt/nfs".into(),
+        };
+        assert!(matches!(
+            super::mount(&storage).await,
+            Err(Error::SecureMount(_))
+        ));
+    }
+}
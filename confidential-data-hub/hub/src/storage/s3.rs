@@ -0,0 +1,161 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `s3` volumes are buckets of S3 compatible object storages mounted by
+//! `s3fs`. The access keys are got from a KMS/Vault provider at mount time,
+//! so they never appear in the pod spec.
+//!
+//! The options of the volume are
+//! - `bucket`: name of the bucket, optionally with the path inside, e.g.
+//!   `models:/llama`.
+//! - `url`: url of the object storage, e.g. `https://s3.us-east-1.amazonaws.com`.
+//!   The default of `s3fs` is used if not given.
+//! - `credentials`: name of the secret of the access keys in the provider,
+//!   e.g. `kbs:///default/s3/models`. The secret is in the `s3fs` password
+//!   file format `<access key id>:<secret access key>`.
+//! - `provider`: the provider to get the `credentials` from, `kbs` by default.
+//! - `provider_settings` and `annotations`: json objects given to the
+//!   provider, see the docs of the providers.
+
+use kms::{Annotations, ProviderSettings};
+use log::{debug, info};
+use tokio::{fs, process::Command};
+
+use crate::{policy::key_resource, Error, Result};
+
+use super::{is_mounted, SecureMount, Teardown};
+
+pub(super) const VOLUME_TYPE: &str = "s3";
+
+const S3FS_PATH: &str = "/usr/bin/s3fs";
+
+const DEFAULT_PROVIDER: &str = "kbs";
+
+/// The credentials of `storage`.
+pub(super) fn resources(storage: &SecureMount) -> Vec<String> {
+    let provider = storage
+        .options
+        .get("provider")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_PROVIDER);
+    storage
+        .options
+        .get("credentials")
+        .map(|credentials| key_resource(provider, credentials))
+        .into_iter()
+        .collect()
+}
+
+/// Mount the bucket of `storage`. A bucket which is already mounted is kept.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let bucket = storage.required_option("bucket")?;
+    let credentials = storage.required_option("credentials")?;
+    let provider = storage
+        .options
+        .get("provider")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_PROVIDER);
+    let provider_settings: ProviderSettings = json_option(storage, "provider_settings")?;
+    let annotations: Annotations = json_option(storage, "annotations")?;
+    storage.check_mount_point()?;
+
+    if is_mounted(&storage.mount_point).await {
+        debug!("s3: {bucket} is already mounted at {}", storage.mount_point);
+        return Ok(Teardown::Unmount);
+    }
+
+    let mut getter = kms::new_getter(provider, provider_settings)
+        .await
+        .map_err(|e| Error::SecureMount(format!("s3: create {provider} client failed: {e}")))?;
+    let secret = getter
+        .get_secret(credentials, &annotations)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!("s3: get credentials {credentials} failed: {e}"))
+        })?;
+    let (access_key_id, secret_access_key) = parse_credentials(&secret)?;
+
+    fs::create_dir_all(&storage.mount_point)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!(
+                "s3: create mount point {} failed: {e}",
+                storage.mount_point
+            ))
+        })?;
+    let mut options = storage.flags.clone();
+    if let Some(url) = storage.options.get("url") {
+        options.push(format!("url={url}"));
+    }
+    let mut command = Command::new(S3FS_PATH);
+    command.args([bucket, &storage.mount_point]);
+    if !options.is_empty() {
+        command.args(["-o", &options.join(",")]);
+    }
+
+    // The access keys are given by envs rather than a password file, so that
+    // they are not written to the disk.
+    let status = command
+        .env("AWSACCESSKEYID", access_key_id)
+        .env("AWSSECRETACCESSKEY", secret_access_key)
+        .status()
+        .await
+        .map_err(|e| Error::SecureMount(format!("s3: run s3fs failed: {e}")))?;
+    if !status.success() {
+        return Err(Error::SecureMount(format!(
+            "s3: mount {bucket} failed: s3fs exited with {status}"
+        )));
+    }
+
+    info!("s3: {bucket} is mounted at {}", storage.mount_point);
+    Ok(Teardown::Unmount)
+}
+
+/// Parse the json object of the option `name`, or an empty one if not given.
+fn json_option(
+    storage: &SecureMount,
+    name: &str,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    match storage.options.get(name) {
+        Some(json) if !json.is_empty() => serde_json::from_str(json)
+            .map_err(|e| Error::SecureMount(format!("s3: illegal option `{name}`: {e}"))),
+        _ => Ok(Default::default()),
+    }
+}
+
+/// Split the `<access key id>:<secret access key>` credentials.
+fn parse_credentials(secret: &[u8]) -> Result<(&str, &str)> {
+    let illegal = || {
+        Error::SecureMount(
+            "s3: illegal credentials, `<access key id>:<secret access key>` expected".into(),
+        )
+    };
+    let secret = std::str::from_utf8(secret).map_err(|_| illegal())?;
+    let (access_key_id, secret_access_key) =
+        secret.trim_end().split_once(':').ok_or_else(illegal)?;
+    if access_key_id.is_empty() || secret_access_key.is_empty() {
+        return Err(illegal());
+    }
+
+    Ok((access_key_id, secret_access_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::parse_credentials;
+
+    #[rstest]
+    #[case(b"AKID:secret", Some(("AKID", "secret")))]
+    #[case(b"AKID:secret\n", Some(("AKID", "secret")))]
+    #[case(b"AKID:sec:ret", Some(("AKID", "sec:ret")))]
+    #[case(b"AKID", None)]
+    #[case(b":secret", None)]
+    #[case(b"\xff:secret", None)]
+    fn credentials(#[case] secret: &[u8], #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(parse_credentials(secret).ok(), expected);
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `scratch` volumes are local disks encrypted by dm-crypt with an ephemeral
+//! random key, e.g. for the writable layers of the containers. The key is
+//! read from `/dev/urandom` by `cryptsetup` and never leaves the kernel, so
+//! the data written is protected from the host and lost once the device is
+//! closed. No KBS is involved.
+//!
+//! The options of the volume are
+//! - `device`: path of the block device, e.g. `/dev/vdc`.
+//! - `fs_type`: type of the filesystem created inside, `ext4` by default.
+//! - `mapper_name`: name of the device mapper of the encrypted device. By
+//!   default derived from the device path.
+
+use std::path::Path;
+
+use log::{debug, info};
+
+use crate::Result;
+
+use super::{is_mounted, mount_device, run, SecureMount, Teardown, CRYPTSETUP_PATH, MKFS_PATH};
+
+pub(super) const VOLUME_TYPE: &str = "scratch";
+
+const CIPHER: &str = "aes-xts-plain64";
+
+/// Key size in bits. Xts uses two keys of 256 bits.
+const KEY_SIZE: &str = "512";
+
+/// Encrypt the device of `storage` with a random key, create a new
+/// filesystem inside and mount it. The data on the device is lost. A volume
+/// which is already mounted is kept.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let device = storage.required_option("device")?;
+    let mapper_name = storage.mapper_name(device);
+    storage.check_mount_point()?;
+
+    let mapper = format!("/dev/mapper/{mapper_name}");
+    if is_mounted(&storage.mount_point).await {
+        debug!(
+            "scratch: {mapper} is already mounted at {}",
+            storage.mount_point
+        );
+        return Ok(Teardown::CloseMapper(mapper_name));
+    }
+
+    if !Path::new(&mapper).exists() {
+        run(
+            CRYPTSETUP_PATH,
+            &[
+                "open",
+                "--type",
+                "plain",
+                "--cipher",
+                CIPHER,
+                "--key-size",
+                KEY_SIZE,
+                "--key-file",
+                "/dev/urandom",
+                device,
+                &mapper_name,
+            ],
+            None,
+        )
+        .await?;
+    }
+
+    run(MKFS_PATH, &["-t", storage.fs_type(), &mapper], None).await?;
+    mount_device(storage, &mapper).await?;
+    info!(
+        "scratch: {device} is encrypted and mounted at {}",
+        storage.mount_point
+    );
+    Ok(Teardown::CloseMapper(mapper_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{storage::SecureMount, Error};
+
+    #[tokio::test]
+    async fn missing_device() {
+        let storage = SecureMount {
+            volume_type: "scratch".into(),
+            mount_point: "/run/scratch".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            super::mount(&storage).await,
+            Err(Error::SecureMount(_))
+        ));
+    }
+}
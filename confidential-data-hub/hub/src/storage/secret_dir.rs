@@ -0,0 +1,296 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `secret` volumes are tmpfs directories of the unsealed secrets, like the
+//! Secret volumes projected by Kubernetes. The plaintext of the sealed
+//! secret is a json object, and every field of it is a file of the
+//! directory. A string value is written as is, and other values as json.
+//!
+//! The options of the volume are
+//! - `secret`: the sealed secret, in format `sealed.<...>`.
+//! - `mode`: octal mode of the files, `0400` by default.
+//! - `dir_mode`: octal mode of the directory, `0755` by default.
+//! - `uid` and `gid`: owner of the directory and the files, `0` by default.
+//!
+//! The tmpfs is mounted with `nodev,nosuid,noexec`, which the flags cannot
+//! override, and the contents are gone once it is unmounted. If the files
+//! fail to be written, the tmpfs is unmounted, so that no partial directory
+//! is left.
+
+use std::{os::unix::fs::chown, path::Path};
+
+use log::{debug, info, warn};
+use serde_json::{Map, Value};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use zeroize::Zeroizing;
+
+use crate::{
+    hub::{secret_resource, unseal_secret},
+    Error, Result,
+};
+
+use super::{is_mounted, run, SecureMount, Teardown, MOUNT_PATH, UMOUNT_PATH};
+
+pub(super) const VOLUME_TYPE: &str = "secret";
+
+const DEFAULT_MODE: u32 = 0o400;
+const DEFAULT_DIR_MODE: u32 = 0o755;
+
+/// Flags which would override `nodev,nosuid,noexec`.
+const ILLEGAL_FLAGS: [&str; 4] = ["dev", "suid", "exec", "defaults"];
+
+/// The resource of the sealed secret of `storage`, see
+/// [`exec::resources`](super::exec::resources).
+pub(super) fn resources(storage: &SecureMount) -> Vec<String> {
+    storage
+        .options
+        .get("secret")
+        .map(|secret| secret_resource(secret.as_bytes()).unwrap_or_default())
+        .into_iter()
+        .collect()
+}
+
+/// Mount a tmpfs and write the fields of the unsealed secret of `storage`
+/// into it. A directory which is already mounted is kept.
+pub(super) async fn mount(storage: &SecureMount) -> Result<Teardown> {
+    let secret = storage.required_option("secret")?;
+    let mode = octal_option(storage, "mode", DEFAULT_MODE)?;
+    let dir_mode = octal_option(storage, "dir_mode", DEFAULT_DIR_MODE)?;
+    let uid = id_option(storage, "uid")?;
+    let gid = id_option(storage, "gid")?;
+    check_flags(storage)?;
+    storage.check_mount_point()?;
+
+    if is_mounted(&storage.mount_point).await {
+        debug!("secret: {} is already mounted", storage.mount_point);
+        return Ok(Teardown::Unmount);
+    }
+
+    let plaintext = unseal_secret(secret.as_bytes())
+        .await
+        .map_err(|e| Error::SecureMount(format!("secret: unseal secret failed: {e}")))?;
+    let fields: Map<String, Value> = serde_json::from_slice(&plaintext).map_err(|e| {
+        Error::SecureMount(format!(
+            "secret: plaintext of the secret is not a json object: {e}"
+        ))
+    })?;
+    if let Some(name) = fields.keys().find(|name| !is_legal_file_name(name)) {
+        return Err(Error::SecureMount(format!(
+            "secret: illegal file name {name}"
+        )));
+    }
+
+    tokio::fs::create_dir_all(&storage.mount_point)
+        .await
+        .map_err(|e| {
+            Error::SecureMount(format!(
+                "secret: create mount point {} failed: {e}",
+                storage.mount_point
+            ))
+        })?;
+    let mut options = vec![
+        "nodev".to_string(),
+        "nosuid".to_string(),
+        "noexec".to_string(),
+        format!("mode={dir_mode:o}"),
+        format!("uid={}", uid.unwrap_or(0)),
+        format!("gid={}", gid.unwrap_or(0)),
+    ];
+    options.extend(storage.flags.iter().cloned());
+    run(
+        MOUNT_PATH,
+        &[
+            "-t",
+            "tmpfs",
+            "-o",
+            &options.join(","),
+            "tmpfs",
+            &storage.mount_point,
+        ],
+        None,
+    )
+    .await?;
+
+    if let Err(e) = write_files(Path::new(&storage.mount_point), fields, mode, uid, gid).await {
+        if let Err(e) = run(UMOUNT_PATH, &[&storage.mount_point], None).await {
+            warn!("secret: unmount {} failed: {e}", storage.mount_point);
+        }
+        return Err(e);
+    }
+    info!("secret: unsealed into {}", storage.mount_point);
+    Ok(Teardown::Unmount)
+}
+
+/// Write every field of `fields` to a file of `dir`.
+async fn write_files(
+    dir: &Path,
+    fields: Map<String, Value>,
+    mode: u32,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<()> {
+    for (name, value) in fields {
+        if !is_legal_file_name(&name) {
+            return Err(Error::SecureMount(format!(
+                "secret: illegal file name {name}"
+            )));
+        }
+
+        let content = Zeroizing::new(match value {
+            Value::String(value) => value.into_bytes(),
+            value => value.to_string().into_bytes(),
+        });
+        let path = dir.join(&name);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&path)
+            .await
+            .map_err(|e| Error::SecureMount(format!("secret: create file {name} failed: {e}")))?;
+        // The tokio file writes in the background, so the write is only
+        // complete once it is flushed.
+        file.write_all(&content)
+            .await
+            .map_err(|e| Error::SecureMount(format!("secret: write file {name} failed: {e}")))?;
+        file.flush()
+            .await
+            .map_err(|e| Error::SecureMount(format!("secret: write file {name} failed: {e}")))?;
+        if uid.is_some() || gid.is_some() {
+            chown(&path, uid, gid).map_err(|e| {
+                Error::SecureMount(format!("secret: change owner of file {name} failed: {e}"))
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn check_flags(storage: &SecureMount) -> Result<()> {
+    match storage
+        .flags
+        .iter()
+        .flat_map(|flags| flags.split(','))
+        .find(|flag| ILLEGAL_FLAGS.contains(&flag.trim()))
+    {
+        Some(flag) => Err(Error::SecureMount(format!("secret: illegal flag {flag}"))),
+        None => Ok(()),
+    }
+}
+
+/// A file name cannot be a path, nor a hidden file, which Kubernetes uses
+/// internally in the projected volumes.
+fn is_legal_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains('/') && !name.contains('\0')
+}
+
+fn octal_option(storage: &SecureMount, name: &str, default: u32) -> Result<u32> {
+    match storage.options.get(name) {
+        Some(value) => u32::from_str_radix(value, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777)
+            .ok_or_else(|| Error::SecureMount(format!("secret: illegal option `{name}`: {value}"))),
+        None => Ok(default),
+    }
+}
+
+fn id_option(storage: &SecureMount, name: &str) -> Result<Option<u32>> {
+    storage
+        .options
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| Error::SecureMount(format!("secret: illegal option `{name}`: {e}")))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use rstest::rstest;
+    use serde_json::json;
+
+    use crate::storage::SecureMount;
+
+    #[rstest]
+    #[case("password", true)]
+    #[case("tls.crt", true)]
+    #[case("", false)]
+    #[case(".hidden", false)]
+    #[case("..data", false)]
+    #[case("../etc/passwd", false)]
+    #[case("a/b", false)]
+    fn legal_file_name(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(super::is_legal_file_name(name), expected);
+    }
+
+    #[rstest]
+    #[case(&["ro"], true)]
+    #[case(&["ro,noatime", "size=1m"], true)]
+    #[case(&["exec"], false)]
+    #[case(&["ro,suid"], false)]
+    #[case(&["defaults"], false)]
+    fn legal_flags(#[case] flags: &[&str], #[case] expected: bool) {
+        let storage = SecureMount {
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(super::check_flags(&storage).is_ok(), expected);
+    }
+
+    #[rstest]
+    #[case(None, Some(0o400))]
+    #[case(Some("0440"), Some(0o440))]
+    #[case(Some("640"), Some(0o640))]
+    #[case(Some("0999"), None)]
+    #[case(Some("77777"), None)]
+    fn mode(#[case] value: Option<&str>, #[case] expected: Option<u32>) {
+        let mut storage = SecureMount::default();
+        if let Some(value) = value {
+            storage.options.insert("mode".into(), value.into());
+        }
+        assert_eq!(
+            super::octal_option(&storage, "mode", super::DEFAULT_MODE).ok(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn write_files() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let fields = json!({
+            "username": "admin",
+            "config": {"port": 8080},
+        });
+        let serde_json::Value::Object(fields) = fields else {
+            unreachable!()
+        };
+        super::write_files(dir.path(), fields, 0o440, None, None)
+            .await
+            .expect("write files");
+
+        let username = dir.path().join("username");
+        assert_eq!(std::fs::read(&username).unwrap(), b"admin");
+        assert_eq!(
+            std::fs::metadata(&username).unwrap().permissions().mode() & 0o7777,
+            0o440
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("config")).unwrap(),
+            br#"{"port":8080}"#
+        );
+
+        let fields = json!({"../escape": "x"});
+        let serde_json::Value::Object(fields) = fields else {
+            unreachable!()
+        };
+        assert!(super::write_files(dir.path(), fields, 0o440, None, None)
+            .await
+            .is_err());
+    }
+}
@@ -24,12 +24,20 @@
 //! - `Encrypter`: KMS's encrypt API.
 //! - `Getter`: Vault's get secret API.
 //! - `Setter`: Vault's set secret API.
+//! - `Signer`: KMS's sign API.
+//!
+//! The plaintexts of `Decrypter` and `Getter` are [`SecretBytes`], which are
+//! locked in memory and zeroed on drop. The data encryption keys of the
+//! envelopes are generated and wrapped by an `Encrypter` with
+//! [`DataKey`](crate::DataKey).
 //!
 //! The rationality to distinguish these four different traits:
 //! - `Decrypter` and `Getter` are used in-guest, while `Encrypter` and `Setter`
 //! are used userside. They do not need to be implemented by a same object.
+//! - `Signer` is used in-guest to sign with a private key that never leaves
+//! the KMS.
 
-use crate::Result;
+use crate::{Result, SecretBytes};
 
 use async_trait::async_trait;
 use serde_json::{Map, Value};
@@ -50,7 +58,7 @@ pub trait Decrypter: Send + Sync {
         ciphertext: &[u8],
         key_id: &str,
         crypto_context: &Annotations,
-    ) -> Result<Vec<u8>>;
+    ) -> Result<SecretBytes>;
 }
 
 #[async_trait]
@@ -75,5 +83,18 @@ pub trait Setter: Send + Sync {
 pub trait Getter: Send + Sync {
     /// Get secret. Different secret manager will use different parameters inside
     /// `annotations`.
-    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<Vec<u8>>;
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes>;
+}
+
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Use the private key of `key_id` to sign the `message` inside KMS, and
+    /// then return the signature. The signing algorithm and the other
+    /// parameters are given by the `annotations`.
+    async fn sign(
+        &mut self,
+        message: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>>;
 }
@@ -0,0 +1,115 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! # Envelope encryption
+//!
+//! Helpers of the digital envelope mechanism, shared by the sealing tools
+//! and the tests of all the providers. A random data encryption key (DEK)
+//! is generated locally and wrapped by the key encryption key (KEK) of a
+//! provider through its [`Encrypter`]. The data is then encrypted by the
+//! DEK outside of the KMS, and the wrapped DEK is unwrapped through the
+//! [`Decrypter`](crate::Decrypter) of the provider with the same
+//! annotations.
+
+use rand::Rng;
+use zeroize::Zeroizing;
+
+use crate::{Annotations, Encrypter, ProviderSettings, Result, SecretBytes};
+
+/// Length of a data encryption key, i.e. a key of AES-256.
+pub const DATA_KEY_LEN: usize = 32;
+
+/// A data encryption key and the key wrapped by a KEK.
+pub struct DataKey {
+    /// Plaintext of the DEK, to encrypt the data locally.
+    pub plaintext: SecretBytes,
+
+    /// The DEK wrapped by the KEK.
+    pub encrypted_key: Vec<u8>,
+
+    /// Parameters of the wrapping, needed to unwrap the DEK.
+    pub annotations: Annotations,
+}
+
+impl DataKey {
+    /// Generate a random DEK and wrap it by the KEK `key_id` of the
+    /// `encrypter`.
+    pub async fn generate(encrypter: &mut dyn Encrypter, key_id: &str) -> Result<Self> {
+        let mut key = Zeroizing::new([0u8; DATA_KEY_LEN]);
+        rand::thread_rng().fill(&mut key[..]);
+        let (encrypted_key, annotations) = encrypter.encrypt(&key[..], key_id).await?;
+
+        Ok(Self {
+            plaintext: SecretBytes::from(&key[..]),
+            encrypted_key,
+            annotations,
+        })
+    }
+
+    /// [`Self::generate`] by the provider `provider_name`, see
+    /// [`new_encryptor`](crate::new_encryptor).
+    pub async fn generate_by(
+        provider_name: &str,
+        provider_settings: ProviderSettings,
+        key_id: &str,
+    ) -> Result<Self> {
+        let mut encrypter = crate::new_encryptor(provider_name, provider_settings).await?;
+        Self::generate(encrypter.as_mut(), key_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use crate::{Annotations, Decrypter, Encrypter, Result, SecretBytes};
+
+    use super::{DataKey, DATA_KEY_LEN};
+
+    /// Wraps the keys by xor with the byte of the key id.
+    struct Xor;
+
+    #[async_trait]
+    impl Encrypter for Xor {
+        async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+            let mask = key_id.as_bytes()[0];
+            let mut annotations = Annotations::new();
+            annotations.insert("mask".into(), mask.into());
+            Ok((data.iter().map(|b| b ^ mask).collect(), annotations))
+        }
+    }
+
+    #[async_trait]
+    impl Decrypter for Xor {
+        async fn decrypt(
+            &mut self,
+            ciphertext: &[u8],
+            key_id: &str,
+            annotations: &Annotations,
+        ) -> Result<SecretBytes> {
+            assert_eq!(annotations["mask"], key_id.as_bytes()[0]);
+            let mask = key_id.as_bytes()[0];
+            Ok(ciphertext
+                .iter()
+                .map(|b| b ^ mask)
+                .collect::<Vec<u8>>()
+                .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn generate() {
+        let first = DataKey::generate(&mut Xor, "k").await.unwrap();
+        let second = DataKey::generate(&mut Xor, "k").await.unwrap();
+        assert_eq!(first.plaintext.len(), DATA_KEY_LEN);
+        assert_ne!(first.plaintext, second.plaintext);
+
+        let plaintext = Xor
+            .decrypt(&first.encrypted_key, "k", &first.annotations)
+            .await
+            .unwrap();
+        assert_eq!(plaintext, first.plaintext);
+    }
+}
@@ -13,9 +13,113 @@ pub enum Error {
     #[error("Aliyun KMS error: {0}")]
     AliyunKmsError(String),
 
+    #[cfg(feature = "aws")]
+    #[error("AWS KMS error: {0}")]
+    AwsKmsError(String),
+
+    #[cfg(feature = "azure-kv")]
+    #[error("Azure Key Vault error: {0}")]
+    AzureKvError(String),
+
+    #[cfg(feature = "ehsm")]
+    #[error("eHSM-KMS error: {0}")]
+    EhsmError(String),
+
+    #[cfg(feature = "gcp")]
+    #[error("GCP KMS error: {0}")]
+    GcpKmsError(String),
+
+    #[cfg(feature = "vault")]
+    #[error("HashiCorp Vault error: {0}")]
+    VaultError(String),
+
+    #[cfg(feature = "pkcs11")]
+    #[error("PKCS#11 error: {0}")]
+    Pkcs11Error(String),
+
+    #[cfg(feature = "tpm")]
+    #[error("TPM error: {0}")]
+    TpmError(String),
+
+    #[cfg(feature = "testing")]
+    #[error("Test KMS error: {0}")]
+    TestKmsError(String),
+
     #[error("Kbs client error: {0}")]
-    KbsClientError(String),
+    KbsClientError(#[from] KbsError),
 
     #[error("Unsupported provider: {0}")]
     UnsupportedProvider(String),
+
+    #[error("Provider already registered: {0}")]
+    ProviderAlreadyRegistered(String),
+}
+
+impl Error {
+    /// Whether the failed operation may succeed if retried. Only the errors
+    /// from the KBS are classified, all the others are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::KbsClientError(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+/// Errors of the KBS clients.
+#[derive(Error, Debug)]
+pub enum KbsError {
+    /// The KBS cannot be reached, or the request timed out.
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// The attestation or the authentication to the KBS failed.
+    #[error("authentication failed: {0}")]
+    Auth(String),
+
+    /// The request is denied by the policy of the KBS.
+    #[error("denied by policy: {0}")]
+    PolicyDenied(String),
+
+    /// The resource does not exist.
+    #[error("resource not found: {0}")]
+    NotFound(String),
+
+    /// The given kbs resource uri is illegal.
+    #[error("malformed resource uri: {0}")]
+    MalformedUri(String),
+
+    /// The KBC is not correctly configured, e.g. illegal `aa_kbc_params`
+    /// or provider settings.
+    #[error("configuration error: {0}")]
+    Config(String),
+
+    /// A key of the KBS is illegal, or the wrapping or the unwrapping by
+    /// it failed.
+    #[error("crypto error: {0}")]
+    Crypto(String),
+
+    /// The operation is not supported by the KBC.
+    #[error("unsupported operation: {0}")]
+    Unsupported(String),
+
+    /// The KBS failed to handle the request, or returned an illegal response.
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl KbsError {
+    /// Whether the failed operation may succeed if retried, e.g. after the
+    /// KBS recovers or with a new attestation.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            KbsError::Network(_) | KbsError::Auth(_) | KbsError::Internal(_) => true,
+            KbsError::PolicyDenied(_)
+            | KbsError::NotFound(_)
+            | KbsError::MalformedUri(_)
+            | KbsError::Config(_)
+            | KbsError::Crypto(_)
+            | KbsError::Unsupported(_) => false,
+        }
+    }
 }
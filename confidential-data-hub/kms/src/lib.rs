@@ -6,8 +6,23 @@
 pub mod api;
 pub use api::*;
 
+pub mod envelope;
+pub use envelope::DataKey;
+
 pub mod error;
 pub use error::*;
 
+pub mod metrics;
+
 pub mod plugins;
-pub use plugins::{new_decryptor, new_getter};
+pub use plugins::registry::{
+    register_decryptor, register_encryptor, register_getter, register_signer, DecryptorFactory,
+    EncryptorFactory, GetterFactory, SignerFactory,
+};
+pub use plugins::{new_decryptor, new_encryptor, new_getter, new_signer};
+
+pub mod secret_bytes;
+pub use secret_bytes::SecretBytes;
+
+pub mod settings;
+pub use settings::{set_settings, KbsTlsPaths, Settings};
@@ -0,0 +1,399 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Metrics of the secret retrieval, exposed in the Prometheus text format.
+//!
+//! The following metrics are collected in this process:
+//! - `cdh_requests_total` and `cdh_request_duration_seconds`: the requests
+//!   to the CDH APIs, by `method` and `result`.
+//! - `cdh_plugin_requests_total` and `cdh_plugin_request_duration_seconds`:
+//!   the requests to the KMS and KBS plugins, by `plugin`, `op` and `result`.
+//! - `cdh_kbs_cache_requests_total`: the lookups of the KBS resource cache,
+//!   by `kbc` and `result` (`hit` or `miss`).
+//! - `cdh_attestation_failures_total`: the failed attestations to the KBS,
+//!   by `kbc`.
+//! - `cdh_kbs_token_refreshes_total` and
+//!   `cdh_kbs_token_refresh_failures_total`: the background refreshes of
+//!   the `cc_kbc` tokens, see [`TokenRefreshMetrics`](crate::plugins::kbs::TokenRefreshMetrics).
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+
+use crate::{Annotations, Decrypter, Getter, Result, SecretBytes, Signer};
+
+/// Upper bounds of the buckets of the duration histograms in seconds.
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+lazy_static! {
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::default());
+}
+
+#[derive(Clone, Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    /// keyed by (method, result)
+    requests: BTreeMap<(String, &'static str), u64>,
+    /// keyed by method
+    request_durations: BTreeMap<String, Histogram>,
+    /// keyed by (plugin, op, result)
+    plugin_requests: BTreeMap<(String, &'static str, &'static str), u64>,
+    /// keyed by (plugin, op)
+    plugin_request_durations: BTreeMap<(String, &'static str), Histogram>,
+    /// keyed by (kbc, result)
+    cache_requests: BTreeMap<(String, &'static str), u64>,
+    /// keyed by kbc
+    attestation_failures: BTreeMap<String, u64>,
+}
+
+fn result_label(ok: bool) -> &'static str {
+    match ok {
+        true => "ok",
+        false => "error",
+    }
+}
+
+fn with_metrics(f: impl FnOnce(&mut Metrics)) {
+    let mut metrics = METRICS.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut metrics);
+}
+
+/// Record a request to the CDH API `method` that took `elapsed`.
+pub fn observe_request(method: &str, ok: bool, elapsed: Duration) {
+    with_metrics(|m| {
+        *m.requests
+            .entry((method.to_string(), result_label(ok)))
+            .or_default() += 1;
+        m.request_durations
+            .entry(method.to_string())
+            .or_default()
+            .observe(elapsed);
+    });
+}
+
+/// Record a request `op` to the KMS or KBS `plugin` that took `elapsed`.
+pub fn observe_plugin_request(plugin: &str, op: &'static str, ok: bool, elapsed: Duration) {
+    with_metrics(|m| {
+        *m.plugin_requests
+            .entry((plugin.to_string(), op, result_label(ok)))
+            .or_default() += 1;
+        m.plugin_request_durations
+            .entry((plugin.to_string(), op))
+            .or_default()
+            .observe(elapsed);
+    });
+}
+
+/// Record a lookup of the resource cache of the `kbc`.
+pub(crate) fn observe_cache(kbc: &str, hit: bool) {
+    let result = match hit {
+        true => "hit",
+        false => "miss",
+    };
+    with_metrics(|m| {
+        *m.cache_requests
+            .entry((kbc.to_string(), result))
+            .or_default() += 1
+    });
+}
+
+/// Record a failed attestation of the `kbc`.
+pub(crate) fn observe_attestation_failure(kbc: &str) {
+    with_metrics(|m| *m.attestation_failures.entry(kbc.to_string()).or_default() += 1);
+}
+
+/// Render all the metrics in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = {
+        let metrics = METRICS.lock().unwrap_or_else(|e| e.into_inner());
+        metrics.render()
+    };
+
+    #[cfg(feature = "kbs")]
+    {
+        let refresh = crate::plugins::kbs::token_refresh_metrics();
+        render_header(
+            &mut out,
+            "cdh_kbs_token_refreshes_total",
+            "Successful background refreshes of the KBS tokens.",
+            "counter",
+        );
+        let _ = writeln!(out, "cdh_kbs_token_refreshes_total {}", refresh.refreshes);
+        render_header(
+            &mut out,
+            "cdh_kbs_token_refresh_failures_total",
+            "Failed background refreshes of the KBS tokens.",
+            "counter",
+        );
+        let _ = writeln!(
+            out,
+            "cdh_kbs_token_refresh_failures_total {}",
+            refresh.failures
+        );
+    }
+
+    out
+}
+
+impl Metrics {
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        render_header(
+            &mut out,
+            "cdh_requests_total",
+            "Requests to the CDH APIs.",
+            "counter",
+        );
+        for ((method, result), count) in &self.requests {
+            let labels = labels(&[("method", method), ("result", result)]);
+            let _ = writeln!(out, "cdh_requests_total{{{labels}}} {count}");
+        }
+
+        render_header(
+            &mut out,
+            "cdh_request_duration_seconds",
+            "Latencies of the requests to the CDH APIs.",
+            "histogram",
+        );
+        for (method, histogram) in &self.request_durations {
+            render_histogram(
+                &mut out,
+                "cdh_request_duration_seconds",
+                &labels(&[("method", method)]),
+                histogram,
+            );
+        }
+
+        render_header(
+            &mut out,
+            "cdh_plugin_requests_total",
+            "Requests to the KMS and KBS plugins.",
+            "counter",
+        );
+        for ((plugin, op, result), count) in &self.plugin_requests {
+            let labels = labels(&[("plugin", plugin), ("op", op), ("result", result)]);
+            let _ = writeln!(out, "cdh_plugin_requests_total{{{labels}}} {count}");
+        }
+
+        render_header(
+            &mut out,
+            "cdh_plugin_request_duration_seconds",
+            "Latencies of the requests to the KMS and KBS plugins.",
+            "histogram",
+        );
+        for ((plugin, op), histogram) in &self.plugin_request_durations {
+            render_histogram(
+                &mut out,
+                "cdh_plugin_request_duration_seconds",
+                &labels(&[("plugin", plugin), ("op", op)]),
+                histogram,
+            );
+        }
+
+        render_header(
+            &mut out,
+            "cdh_kbs_cache_requests_total",
+            "Lookups of the KBS resource cache.",
+            "counter",
+        );
+        for ((kbc, result), count) in &self.cache_requests {
+            let labels = labels(&[("kbc", kbc), ("result", result)]);
+            let _ = writeln!(out, "cdh_kbs_cache_requests_total{{{labels}}} {count}");
+        }
+
+        render_header(
+            &mut out,
+            "cdh_attestation_failures_total",
+            "Failed attestations to the KBS.",
+            "counter",
+        );
+        for (kbc, count) in &self.attestation_failures {
+            let labels = labels(&[("kbc", kbc)]);
+            let _ = writeln!(out, "cdh_attestation_failures_total{{{labels}}} {count}");
+        }
+
+        out
+    }
+}
+
+fn render_header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn render_histogram(out: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.buckets) {
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {count}");
+    }
+    let _ = writeln!(
+        out,
+        "{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+        histogram.count
+    );
+    let _ = writeln!(out, "{name}_sum{{{labels}}} {}", histogram.sum);
+    let _ = writeln!(out, "{name}_count{{{labels}}} {}", histogram.count);
+}
+
+/// Format the label pairs, escaping the values.
+fn labels(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A KMS or KBS client that records the metrics of its requests under the
+/// given plugin name.
+pub(crate) struct Metered<T: ?Sized> {
+    plugin: String,
+    inner: Box<T>,
+}
+
+impl<T: ?Sized> Metered<T> {
+    pub fn new(plugin: &str, inner: Box<T>) -> Self {
+        Self {
+            plugin: plugin.to_string(),
+            inner,
+        }
+    }
+
+    fn observe<R>(&self, op: &'static str, res: &Result<R>, start: Instant) {
+        observe_plugin_request(&self.plugin, op, res.is_ok(), start.elapsed());
+    }
+}
+
+#[async_trait]
+impl Decrypter for Metered<dyn Decrypter> {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        crypto_context: &Annotations,
+    ) -> Result<SecretBytes> {
+        let start = Instant::now();
+        let res = self.inner.decrypt(ciphertext, key_id, crypto_context).await;
+        self.observe("decrypt", &res, start);
+        res
+    }
+}
+
+#[async_trait]
+impl Getter for Metered<dyn Getter> {
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes> {
+        let start = Instant::now();
+        let res = self.inner.get_secret(name, annotations).await;
+        self.observe("get_secret", &res, start);
+        res
+    }
+}
+
+#[async_trait]
+impl Signer for Metered<dyn Signer> {
+    async fn sign(
+        &mut self,
+        message: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let res = self.inner.sign(message, key_id, annotations).await;
+        self.observe("sign", &res, start);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{labels, Histogram, Metrics};
+
+    #[test]
+    fn histogram() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(20));
+        assert_eq!(histogram.buckets[..3], [0, 0, 1]);
+        assert_eq!(histogram.buckets[10], 1);
+        assert_eq!(histogram.count, 2);
+    }
+
+    #[test]
+    fn escape_labels() {
+        assert_eq!(
+            labels(&[("kbc", "cc_kbc"), ("uri", "a\"b\\c\n")]),
+            r#"kbc="cc_kbc",uri="a\"b\\c\n""#
+        );
+    }
+
+    #[test]
+    fn render() {
+        let mut metrics = Metrics::default();
+        *metrics
+            .plugin_requests
+            .entry(("aws".into(), "decrypt", "ok"))
+            .or_default() += 2;
+        metrics
+            .plugin_request_durations
+            .entry(("aws".into(), "decrypt"))
+            .or_default()
+            .observe(Duration::from_millis(300));
+        *metrics
+            .cache_requests
+            .entry(("cc_kbc".into(), "hit"))
+            .or_default() += 1;
+
+        let out = metrics.render();
+        assert!(out.contains("# TYPE cdh_plugin_requests_total counter\n"));
+        assert!(out.contains(
+            "cdh_plugin_requests_total{plugin=\"aws\",op=\"decrypt\",result=\"ok\"} 2\n"
+        ));
+        assert!(out.contains(
+            "cdh_plugin_request_duration_seconds_bucket{plugin=\"aws\",op=\"decrypt\",le=\"0.25\"} 0\n"
+        ));
+        assert!(out.contains(
+            "cdh_plugin_request_duration_seconds_bucket{plugin=\"aws\",op=\"decrypt\",le=\"0.5\"} 1\n"
+        ));
+        assert!(out.contains(
+            "cdh_plugin_request_duration_seconds_count{plugin=\"aws\",op=\"decrypt\"} 1\n"
+        ));
+        assert!(out.contains("cdh_kbs_cache_requests_total{kbc=\"cc_kbc\",result=\"hit\"} 1\n"));
+    }
+}
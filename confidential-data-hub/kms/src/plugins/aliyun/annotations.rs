@@ -5,10 +5,42 @@
 
 use serde::{Deserialize, Serialize};
 
-/// Serialized [`crate::Annotations`]
+/// The algorithm of the symmetric envelope encryption.
+pub const DEFAULT_ENCRYPTION_ALGORITHM: &str = "AES_GCM";
+
+/// The default type of a message to sign, i.e. the message is not digested.
+pub const DEFAULT_MESSAGE_TYPE: &str = "RAW";
+
+/// Serialized [`crate::Annotations`] of an encryption/decryption. The `iv` is
+/// only used by the symmetric algorithm `AES_GCM`. An asymmetric decryption,
+/// e.g. with algorithm `RSAES_OAEP_SHA_256` or `SM2PKE`, does not have one.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AliAnnotations {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub iv: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding_mode: Option<String>,
+}
+
+/// Serialized [`crate::Annotations`] of a signing operation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AliSignAnnotations {
+    /// The signing algorithm, e.g. `RSA_PSS_SHA_256`, `RSA_PKCS1_SHA_256`
+    /// or `SM2DSA`.
+    pub algorithm: String,
+
+    /// `RAW` if the message is the original data, or `DIGEST` if the message
+    /// is the digest of it.
+    #[serde(default = "default_message_type")]
+    pub message_type: String,
+}
+
+fn default_message_type() -> String {
+    DEFAULT_MESSAGE_TYPE.to_string()
 }
 
 /// Serialized [`crate::ProviderSettings`]
@@ -9,7 +9,6 @@ use async_trait::async_trait;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use chrono::Utc;
-use const_format::concatcp;
 use log::error;
 use prost::Message;
 use reqwest::Certificate;
@@ -18,12 +17,15 @@ use serde_json::Value;
 use sha2::{Digest, Sha256};
 use tokio::fs;
 
-use crate::plugins::aliyun::client::dkms_api::{DecryptRequest, EncryptRequest};
-use crate::plugins::_IN_GUEST_DEFAULT_KEY_PATH;
-use crate::{Annotations, Decrypter, Encrypter, ProviderSettings};
+use crate::plugins::aliyun::client::dkms_api::{DecryptRequest, EncryptRequest, SignRequest};
+use crate::plugins::credential_dir;
+use crate::plugins::proxy::ProxyConfig;
+use crate::{Annotations, Decrypter, Encrypter, ProviderSettings, SecretBytes, Signer};
 use crate::{Error, Result};
 
-use super::annotations::{AliAnnotations, AliProviderSettings};
+use super::annotations::{
+    AliAnnotations, AliProviderSettings, AliSignAnnotations, DEFAULT_ENCRYPTION_ALGORITHM,
+};
 use super::credential::Credential;
 
 pub mod dkms_api {
@@ -37,8 +39,6 @@ pub struct AliyunKmsClient {
     endpoint: String,
 }
 
-const ALIYUN_IN_GUEST_DEFAULT_KEY_PATH: &str = concatcp!(_IN_GUEST_DEFAULT_KEY_PATH, "/aliyun");
-
 impl AliyunKmsClient {
     fn read_kms_instance_cert(cert_pem: &[u8]) -> Result<Certificate> {
         let kms_instance_ca_cert = Certificate::from_pem(cert_pem)
@@ -57,7 +57,9 @@ impl AliyunKmsClient {
         })?;
         let endpoint = format!("https://{kms_instance_id}.cryptoservice.kms.aliyuncs.com");
         let cert = Self::read_kms_instance_cert(cert_pem.as_bytes())?;
-        let http_client = ClientBuilder::new()
+        let http_client = ProxyConfig::new()
+            .apply(ClientBuilder::new())
+            .map_err(|e| Error::AliyunKmsError(format!("set proxy failed: {e}")))?
             .add_root_certificate(cert)
             .build()
             .map_err(|e| Error::AliyunKmsError(format!("build http client failed: {e}")))?;
@@ -71,8 +73,8 @@ impl AliyunKmsClient {
     }
 
     /// This new function is used by a in-pod client. The side-effect is to read the
-    /// [`ALIYUN_IN_GUEST_DEFAULT_KEY_PATH`] which is the by default path where the credential
-    /// to access kms is saved.
+    /// [`credential_dir`] of the plugin, which is the path where the credential to
+    /// access kms is saved.
     pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
         let provider_settings: AliProviderSettings =
             serde_json::from_value(Value::Object(provider_settings.clone())).map_err(|e| {
@@ -80,15 +82,18 @@ impl AliyunKmsClient {
             })?;
 
         let cert_path = format!(
-            "{ALIYUN_IN_GUEST_DEFAULT_KEY_PATH}/PrivateKmsCA_{}.pem",
+            "{}/PrivateKmsCA_{}.pem",
+            credential_dir("aliyun"),
             provider_settings.kms_instance_id
         );
         let pswd_path = format!(
-            "{ALIYUN_IN_GUEST_DEFAULT_KEY_PATH}/password_{}.json",
+            "{}/password_{}.json",
+            credential_dir("aliyun"),
             provider_settings.client_key_id
         );
         let client_key_path = format!(
-            "{ALIYUN_IN_GUEST_DEFAULT_KEY_PATH}/clientKey_{}.json",
+            "{}/clientKey_{}.json",
+            credential_dir("aliyun"),
             provider_settings.client_key_id
         );
         let cert_pem = fs::read_to_string(cert_path).await.map_err(|e| {
@@ -135,7 +140,7 @@ impl Encrypter for AliyunKmsClient {
             aad: "".into(),
             iv: Vec::new(),
             key_id: key_id.into(),
-            algorithm: "AES_GCM".into(),
+            algorithm: DEFAULT_ENCRYPTION_ALGORITHM.into(),
             padding_mode: "".into(),
             plaintext: data.into(),
         };
@@ -162,6 +167,8 @@ impl Encrypter for AliyunKmsClient {
         })?;
         let annotations = AliAnnotations {
             iv: STANDARD.encode(encrypt_response.iv),
+            algorithm: None,
+            padding_mode: None,
         };
 
         let annotations = serde_json::to_value(annotations)
@@ -174,6 +181,9 @@ impl Encrypter for AliyunKmsClient {
     }
 }
 
+/// Both the symmetric envelope decryption and the asymmetric decryption with
+/// RSA or SM2 keys are supported. The algorithm is given by the `algorithm`
+/// annotation, which defaults to [`DEFAULT_ENCRYPTION_ALGORITHM`].
 #[async_trait]
 impl Decrypter for AliyunKmsClient {
     async fn decrypt(
@@ -181,7 +191,7 @@ impl Decrypter for AliyunKmsClient {
         ciphertext: &[u8],
         key_id: &str,
         annotations: &Annotations,
-    ) -> Result<Vec<u8>> {
+    ) -> Result<SecretBytes> {
         let secret_settings: AliAnnotations =
             serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
                 Error::AliyunKmsError(format!(
@@ -196,8 +206,10 @@ impl Decrypter for AliyunKmsClient {
             aad: vec![],
             iv,
             key_id: key_id.into(),
-            algorithm: "AES_GCM".into(),
-            padding_mode: "".into(),
+            algorithm: secret_settings
+                .algorithm
+                .unwrap_or_else(|| DEFAULT_ENCRYPTION_ALGORITHM.into()),
+            padding_mode: secret_settings.padding_mode.unwrap_or_default(),
             ciphertext_blob: ciphertext.to_vec(),
         };
         let mut body = Vec::new();
@@ -218,7 +230,46 @@ impl Decrypter for AliyunKmsClient {
                 "decode decrypt response using protobuf failed: {e}"
             ))
         })?;
-        Ok(decrypt_response.plaintext)
+        Ok(decrypt_response.plaintext.into())
+    }
+}
+
+#[async_trait]
+impl Signer for AliyunKmsClient {
+    async fn sign(
+        &mut self,
+        message: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<Vec<u8>> {
+        let sign_settings: AliSignAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::AliyunKmsError(format!("deserialize annotations for signing failed: {e}"))
+            })?;
+
+        let sign_request = SignRequest {
+            key_id: key_id.into(),
+            algorithm: sign_settings.algorithm,
+            message: message.to_vec(),
+            message_type: sign_settings.message_type,
+        };
+        let mut body = Vec::new();
+        sign_request.encode(&mut body).map_err(|e| {
+            Error::AliyunKmsError(format!("encode sign request using protobuf failed: {e}"))
+        })?;
+        let headers = self.build_headers("Sign", &body).map_err(|e| {
+            Error::AliyunKmsError(format!("build sign request http header failed: {e}"))
+        })?;
+
+        let res = self
+            .do_request(body, headers)
+            .await
+            .map_err(|e| Error::AliyunKmsError(format!("do request to kms server failed: {e}")))?;
+
+        let sign_response = dkms_api::SignResponse::decode(&res[..]).map_err(|e| {
+            Error::AliyunKmsError(format!("decode sign response using protobuf failed: {e}"))
+        })?;
+        Ok(sign_response.signature)
     }
 }
 
@@ -314,7 +365,32 @@ mod tests {
     use rstest::rstest;
     use serde_json::json;
 
-    use crate::{plugins::aliyun::client::AliyunKmsClient, Decrypter, Encrypter};
+    use crate::{
+        plugins::aliyun::{
+            annotations::{AliAnnotations, AliSignAnnotations},
+            client::AliyunKmsClient,
+        },
+        Decrypter, Encrypter,
+    };
+
+    #[test]
+    fn annotations() {
+        // annotations of the sealed secrets before asymmetric decryption
+        let annotations: AliAnnotations =
+            serde_json::from_value(json!({"iv": "aXY="})).expect("deserialize");
+        assert_eq!(annotations.algorithm, None);
+
+        let annotations: AliAnnotations =
+            serde_json::from_value(json!({"algorithm": "RSAES_OAEP_SHA_256"}))
+                .expect("deserialize");
+        assert_eq!(annotations.iv, "");
+        assert_eq!(annotations.algorithm.as_deref(), Some("RSAES_OAEP_SHA_256"));
+
+        let annotations: AliSignAnnotations =
+            serde_json::from_value(json!({"algorithm": "SM2DSA"})).expect("deserialize");
+        assert_eq!(annotations.message_type, "RAW");
+        assert!(serde_json::from_value::<AliSignAnnotations>(json!({})).is_err());
+    }
 
     #[ignore]
     #[rstest]
@@ -0,0 +1,52 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Serialized [`crate::Annotations`] for encryption/decryption
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AwsKmsAnnotations {
+    /// Encryption context used as additional authenticated data
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub encryption_context: HashMap<String, String>,
+
+    /// Encryption algorithm, e.g. `SYMMETRIC_DEFAULT` or `RSAES_OAEP_SHA_256`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption_algorithm: Option<String>,
+}
+
+/// Serialized [`crate::Annotations`] for getting secrets from Secrets Manager
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AwsSecretAnnotations {
+    /// Unique identifier of the version of the secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_id: Option<String>,
+
+    /// Staging label attached to the version, e.g. `AWSCURRENT`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_stage: Option<String>,
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AwsProviderSettings {
+    /// Region of the KMS, e.g. `us-east-1`
+    pub region: String,
+
+    /// Access key id of the credential, used to locate the credential file
+    pub access_key_id: String,
+
+    /// Custom endpoint of the KMS, e.g. a VPC endpoint. By default
+    /// `kms.<region>.amazonaws.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kms_endpoint: Option<String>,
+
+    /// Custom endpoint of the Secrets Manager. By default
+    /// `secretsmanager.<region>.amazonaws.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets_manager_endpoint: Option<String>,
+}
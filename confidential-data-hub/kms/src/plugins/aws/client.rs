@@ -0,0 +1,473 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use log::error;
+use reqwest::{header::HeaderMap, ClientBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::fs;
+
+use crate::plugins::credential_dir;
+use crate::plugins::proxy::ProxyConfig;
+use crate::{Annotations, Decrypter, Encrypter, Getter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{AwsKmsAnnotations, AwsProviderSettings, AwsSecretAnnotations};
+use super::credential::{Credential, SigningRequest};
+
+pub struct AwsKmsClient {
+    http_client: reqwest::Client,
+    credential: Credential,
+    region: String,
+    kms_endpoint: Option<String>,
+    secrets_manager_endpoint: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EncryptResponse {
+    ciphertext_blob: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DecryptResponse {
+    plaintext: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueResponse {
+    secret_binary: Option<String>,
+    secret_string: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(rename = "__type", default)]
+    r#type: String,
+    #[serde(alias = "Message", default)]
+    message: String,
+}
+
+impl AwsKmsClient {
+    pub fn new(
+        credential: &str,
+        region: &str,
+        kms_endpoint: Option<String>,
+        secrets_manager_endpoint: Option<String>,
+    ) -> Result<Self> {
+        let credential = Credential::new(credential)
+            .map_err(|e| Error::AwsKmsError(format!("create credential failed: {e}")))?;
+        let http_client = ProxyConfig::new()
+            .apply(ClientBuilder::new())
+            .map_err(|e| Error::AwsKmsError(format!("set proxy failed: {e}")))?
+            .build()
+            .map_err(|e| Error::AwsKmsError(format!("build http client failed: {e}")))?;
+
+        Ok(Self {
+            http_client,
+            credential,
+            region: region.to_string(),
+            kms_endpoint,
+            secrets_manager_endpoint,
+        })
+    }
+
+    /// This new function is used by a in-pod client. The side-effect is to read the
+    /// [`credential_dir`] of the plugin, which is the path where the credential to
+    /// access kms is saved.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let provider_settings: AwsProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| Error::AwsKmsError(format!("parse provider setting failed: {e}")))?;
+
+        let credential_path = format!(
+            "{}/credential_{}.json",
+            credential_dir("aws"),
+            provider_settings.access_key_id
+        );
+        let credential = fs::read_to_string(credential_path)
+            .await
+            .map_err(|e| Error::AwsKmsError(format!("read credential failed: {e}")))?;
+
+        Self::new(
+            &credential,
+            &provider_settings.region,
+            provider_settings.kms_endpoint,
+            provider_settings.secrets_manager_endpoint,
+        )
+    }
+
+    /// The [`ProviderSettings`] of the region, the access key id and the custom
+    /// endpoints of this client, to create the same client in the guest.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = AwsProviderSettings {
+            region: self.region.clone(),
+            access_key_id: self.credential.access_key_id.clone(),
+            kms_endpoint: self.kms_endpoint.clone(),
+            secrets_manager_endpoint: self.secrets_manager_endpoint.clone(),
+        };
+
+        let provider_settings = serde_json::to_value(provider_settings)
+            .map_err(|e| Error::AwsKmsError(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+}
+
+#[async_trait]
+impl Encrypter for AwsKmsClient {
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let body = json!({
+            "KeyId": key_id,
+            "Plaintext": STANDARD.encode(data),
+        });
+
+        let res = self
+            .do_request(Self::KMS_SERVICE, "TrentService.Encrypt", body)
+            .await
+            .map_err(|e| Error::AwsKmsError(format!("do request to kms server failed: {e}")))?;
+        let encrypt_response: EncryptResponse = serde_json::from_value(res)
+            .map_err(|e| Error::AwsKmsError(format!("illegal encrypt response: {e}")))?;
+        let ciphertext = STANDARD
+            .decode(encrypt_response.ciphertext_blob)
+            .map_err(|e| Error::AwsKmsError(format!("decode ciphertext blob failed: {e}")))?;
+
+        let annotations = serde_json::to_value(AwsKmsAnnotations::default())
+            .map_err(|e| Error::AwsKmsError(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((ciphertext, annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for AwsKmsClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: AwsKmsAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::AwsKmsError(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+
+        let body = Self::decrypt_body(ciphertext, key_id, annotations);
+        let res = self
+            .do_request(Self::KMS_SERVICE, "TrentService.Decrypt", body)
+            .await
+            .map_err(|e| Error::AwsKmsError(format!("do request to kms server failed: {e}")))?;
+        let decrypt_response: DecryptResponse = serde_json::from_value(res)
+            .map_err(|e| Error::AwsKmsError(format!("illegal decrypt response: {e}")))?;
+        let plaintext = STANDARD
+            .decode(decrypt_response.plaintext)
+            .map_err(|e| Error::AwsKmsError(format!("decode plaintext failed: {e}")))?;
+        Ok(plaintext.into())
+    }
+}
+
+#[async_trait]
+impl Getter for AwsKmsClient {
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes> {
+        let annotations: AwsSecretAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::AwsKmsError(format!(
+                    "deserialize annotations for get secret failed: {e}"
+                ))
+            })?;
+
+        let body = Self::get_secret_body(name, annotations);
+        let res = self
+            .do_request(
+                Self::SECRETS_MANAGER_SERVICE,
+                "secretsmanager.GetSecretValue",
+                body,
+            )
+            .await
+            .map_err(|e| {
+                Error::AwsKmsError(format!("do request to secrets manager failed: {e}"))
+            })?;
+        Self::secret_value(name, res)
+    }
+}
+
+impl AwsKmsClient {
+    const KMS_SERVICE: &str = "kms";
+    const SECRETS_MANAGER_SERVICE: &str = "secretsmanager";
+    const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+    /// The body of a `Decrypt` request of the `ciphertext`.
+    fn decrypt_body(ciphertext: &[u8], key_id: &str, annotations: AwsKmsAnnotations) -> Value {
+        let mut body = json!({
+            "KeyId": key_id,
+            "CiphertextBlob": STANDARD.encode(ciphertext),
+        });
+        if !annotations.encryption_context.is_empty() {
+            body["EncryptionContext"] = json!(annotations.encryption_context);
+        }
+        if let Some(algorithm) = annotations.encryption_algorithm {
+            body["EncryptionAlgorithm"] = json!(algorithm);
+        }
+        body
+    }
+
+    /// The body of a `GetSecretValue` request of the secret `name`.
+    fn get_secret_body(name: &str, annotations: AwsSecretAnnotations) -> Value {
+        let mut body = json!({ "SecretId": name });
+        if let Some(version_id) = annotations.version_id {
+            body["VersionId"] = json!(version_id);
+        }
+        if let Some(version_stage) = annotations.version_stage {
+            body["VersionStage"] = json!(version_stage);
+        }
+        body
+    }
+
+    /// The value of the secret `name` in a `GetSecretValue` response, i.e.
+    /// the decoded `SecretBinary`, or else the `SecretString`.
+    fn secret_value(name: &str, res: Value) -> Result<SecretBytes> {
+        let secret: GetSecretValueResponse = serde_json::from_value(res)
+            .map_err(|e| Error::AwsKmsError(format!("illegal get secret value response: {e}")))?;
+
+        match (secret.secret_binary, secret.secret_string) {
+            (Some(binary), _) => STANDARD
+                .decode(binary)
+                .map(SecretBytes::from)
+                .map_err(|e| Error::AwsKmsError(format!("decode secret binary failed: {e}"))),
+            (None, Some(string)) => Ok(string.into()),
+            (None, None) => Err(Error::AwsKmsError(format!(
+                "no secret value returned for {name}"
+            ))),
+        }
+    }
+
+    fn endpoint(&self, service: &str) -> String {
+        let custom = match service {
+            Self::KMS_SERVICE => &self.kms_endpoint,
+            _ => &self.secrets_manager_endpoint,
+        };
+        custom
+            .clone()
+            .unwrap_or_else(|| format!("{service}.{}.amazonaws.com", self.region))
+    }
+
+    fn build_headers(
+        &self,
+        service: &str,
+        host: &str,
+        target: &str,
+        body: &[u8],
+    ) -> anyhow::Result<HeaderMap> {
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut to_sign = BTreeMap::from([
+            ("content-type".to_string(), Self::CONTENT_TYPE.to_string()),
+            ("host".to_string(), host.to_string()),
+            ("x-amz-date".to_string(), amz_date.clone()),
+            ("x-amz-target".to_string(), target.to_string()),
+        ]);
+        if let Some(token) = &self.credential.session_token {
+            to_sign.insert("x-amz-security-token".to_string(), token.clone());
+        }
+
+        let authorization = self.credential.generate_authorization(&SigningRequest {
+            method: "POST",
+            path: "/",
+            query: "",
+            headers: &to_sign,
+            payload: body,
+            region: &self.region,
+            service,
+            amz_date: &amz_date,
+        })?;
+
+        let mut headers = HeaderMap::new();
+        for (k, v) in &to_sign {
+            headers.insert(k.parse::<reqwest::header::HeaderName>()?, v.parse()?);
+        }
+        headers.insert(
+            "user-agent",
+            Into::<String>::into(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .parse()?,
+        );
+        headers.insert("Authorization", authorization.parse()?);
+        Ok(headers)
+    }
+
+    async fn do_request(&self, service: &str, target: &str, body: Value) -> anyhow::Result<Value> {
+        let host = self.endpoint(service);
+        let body = serde_json::to_vec(&body)?;
+        let headers = self.build_headers(service, &host, target, &body)?;
+
+        let response = self
+            .http_client
+            .post(format!("https://{host}/"))
+            .headers(headers)
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            error!("aws kms: do request fail!");
+            let status = response.status();
+            let body_bytes = response.bytes().await?;
+            let error_msg = match serde_json::from_slice::<ErrorResponse>(&body_bytes) {
+                Ok(e) => format!(
+                    "status code: {status}, type: {}, message: {}",
+                    e.r#type, e.message
+                ),
+                Err(_) => format!(
+                    "status code: {status}, body: {}",
+                    String::from_utf8_lossy(&body_bytes)
+                ),
+            };
+            anyhow::bail!(error_msg);
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::AwsKmsClient;
+    use crate::plugins::aws::annotations::{AwsKmsAnnotations, AwsSecretAnnotations};
+
+    const CREDENTIAL: &str = r#"{
+        "Version": 1,
+        "AccessKeyId": "AKIDEXAMPLE",
+        "SecretAccessKey": "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "SessionToken": "token"
+    }"#;
+
+    #[rstest]
+    #[case(None, "kms.us-east-1.amazonaws.com")]
+    #[case(
+        Some("vpce-1.kms.us-east-1.vpce.amazonaws.com"),
+        "vpce-1.kms.us-east-1.vpce.amazonaws.com"
+    )]
+    fn endpoint(#[case] kms_endpoint: Option<&str>, #[case] expected: &str) {
+        let client = AwsKmsClient::new(
+            CREDENTIAL,
+            "us-east-1",
+            kms_endpoint.map(String::from),
+            None,
+        )
+        .expect("create client");
+        assert_eq!(client.endpoint(AwsKmsClient::KMS_SERVICE), expected);
+        assert_eq!(
+            client.endpoint(AwsKmsClient::SECRETS_MANAGER_SERVICE),
+            "secretsmanager.us-east-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn export_provider_settings() {
+        let client = AwsKmsClient::new(
+            CREDENTIAL,
+            "us-east-1",
+            None,
+            Some("secretsmanager.example.com".into()),
+        )
+        .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+        assert_eq!(
+            Value::Object(exported),
+            json!({
+                "region": "us-east-1",
+                "access_key_id": "AKIDEXAMPLE",
+                "secrets_manager_endpoint": "secretsmanager.example.com",
+            })
+        );
+    }
+
+    #[test]
+    fn headers() {
+        let client = AwsKmsClient::new(CREDENTIAL, "us-east-1", None, None).expect("create client");
+        let headers = client
+            .build_headers(
+                AwsKmsClient::KMS_SERVICE,
+                "kms.us-east-1.amazonaws.com",
+                "TrentService.Decrypt",
+                b"{}",
+            )
+            .expect("build headers");
+        assert_eq!(headers["x-amz-target"], "TrentService.Decrypt");
+        assert_eq!(headers["content-type"], AwsKmsClient::CONTENT_TYPE);
+        assert_eq!(headers["x-amz-security-token"], "token");
+
+        let authorization = headers["authorization"].to_str().unwrap();
+        let date = &headers["x-amz-date"].to_str().unwrap()[..8];
+        assert!(authorization.starts_with(&format!(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/{date}/us-east-1/kms/aws4_request, \
+            SignedHeaders=content-type;host;x-amz-date;x-amz-security-token;x-amz-target, "
+        )));
+    }
+
+    #[test]
+    fn decrypt_body() {
+        let body =
+            AwsKmsClient::decrypt_body(b"cipher", "alias/test", AwsKmsAnnotations::default());
+        assert_eq!(
+            body,
+            json!({ "KeyId": "alias/test", "CiphertextBlob": "Y2lwaGVy" })
+        );
+
+        let annotations = AwsKmsAnnotations {
+            encryption_context: HashMap::from([("purpose".into(), "test".into())]),
+            encryption_algorithm: Some("RSAES_OAEP_SHA_256".into()),
+        };
+        let body = AwsKmsClient::decrypt_body(b"cipher", "alias/test", annotations);
+        assert_eq!(body["EncryptionContext"], json!({ "purpose": "test" }));
+        assert_eq!(body["EncryptionAlgorithm"], "RSAES_OAEP_SHA_256");
+    }
+
+    #[test]
+    fn get_secret_body() {
+        let annotations = AwsSecretAnnotations {
+            version_id: None,
+            version_stage: Some("AWSPREVIOUS".into()),
+        };
+        assert_eq!(
+            AwsKmsClient::get_secret_body("db-password", annotations),
+            json!({ "SecretId": "db-password", "VersionStage": "AWSPREVIOUS" })
+        );
+    }
+
+    #[rstest]
+    #[case(json!({ "SecretBinary": "YmluYXJ5", "SecretString": "string" }), Some(&b"binary"[..]))]
+    #[case(json!({ "SecretString": "string" }), Some(&b"string"[..]))]
+    #[case(json!({ "Name": "db-password" }), None)]
+    #[case(json!({ "SecretBinary": "not base64!" }), None)]
+    fn secret_value(#[case] res: Value, #[case] expected: Option<&[u8]>) {
+        let value = AwsKmsClient::secret_value("db-password", res);
+        assert_eq!(value.ok().as_deref(), expected);
+    }
+}
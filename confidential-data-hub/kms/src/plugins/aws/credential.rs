@@ -0,0 +1,170 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Credentials to access AWS KMS, and the Signature Version 4 signing
+//! process. See <https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html>
+
+use std::collections::BTreeMap;
+
+use anyhow::*;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SIGNING_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+#[derive(Clone, Debug)]
+pub(crate) struct Credential {
+    pub(crate) access_key_id: String,
+    secret_access_key: Zeroizing<String>,
+    pub(crate) session_token: Option<String>,
+}
+
+/// The credential file follows the output format of the AWS CLI's
+/// `credential_process`.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CredentialFile {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+/// Parameters of a request to be signed.
+pub(crate) struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub query: &'a str,
+    /// Headers to be signed. The keys must be lowercase.
+    pub headers: &'a BTreeMap<String, String>,
+    pub payload: &'a [u8],
+    pub region: &'a str,
+    pub service: &'a str,
+    /// Timestamp in format `%Y%m%dT%H%M%SZ`
+    pub amz_date: &'a str,
+}
+
+impl Credential {
+    pub(crate) fn new(credential: &str) -> Result<Self> {
+        let cf: CredentialFile = serde_json::from_str(credential)?;
+        Ok(Self {
+            access_key_id: cf.access_key_id,
+            secret_access_key: Zeroizing::new(cf.secret_access_key),
+            session_token: cf.session_token,
+        })
+    }
+
+    fn signing_key(&self, date: &str, region: &str, service: &str) -> Result<Vec<u8>> {
+        let secret = Zeroizing::new(format!("AWS4{}", *self.secret_access_key));
+        let k_date = hmac_sha256(secret.as_bytes(), date.as_bytes())?;
+        let k_region = hmac_sha256(&k_date, region.as_bytes())?;
+        let k_service = hmac_sha256(&k_region, service.as_bytes())?;
+        hmac_sha256(&k_service, b"aws4_request")
+    }
+
+    /// Generate the value of `Authorization` header of the given request.
+    pub(crate) fn generate_authorization(&self, req: &SigningRequest) -> Result<String> {
+        if req.amz_date.len() < 8 {
+            bail!("illegal amz date {}", req.amz_date);
+        }
+        let date = &req.amz_date[..8];
+
+        let canonical_headers = req
+            .headers
+            .iter()
+            .map(|(k, v)| format!("{k}:{}\n", v.trim()))
+            .collect::<Vec<String>>()
+            .join("");
+        let signed_headers = req
+            .headers
+            .keys()
+            .map(|k| k.as_str())
+            .collect::<Vec<&str>>()
+            .join(";");
+        let payload_hash = hex::encode(Sha256::digest(req.payload));
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+            req.method, req.path, req.query
+        );
+
+        let scope = format!("{date}/{}/{}/aws4_request", req.region, req.service);
+        let string_to_sign = format!(
+            "{SIGNING_ALGORITHM}\n{}\n{scope}\n{}",
+            req.amz_date,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = self.signing_key(date, req.region, req.service)?;
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes())?);
+
+        Ok(format!(
+            "{SIGNING_ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.access_key_id
+        ))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = HmacSha256::new_from_slice(key)?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Credential, SigningRequest};
+
+    const CREDENTIAL: &str = r#"{
+        "Version": 1,
+        "AccessKeyId": "AKIDEXAMPLE",
+        "SecretAccessKey": "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY"
+    }"#;
+
+    #[test]
+    fn signing_key() {
+        // Example from <https://docs.aws.amazon.com/general/latest/gr/signature-v4-examples.html>
+        let credential = Credential::new(CREDENTIAL).expect("parse credential");
+        let key = credential
+            .signing_key("20120215", "us-east-1", "iam")
+            .expect("derive signing key");
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn authorization() {
+        let credential = Credential::new(CREDENTIAL).expect("parse credential");
+        let headers = BTreeMap::from([
+            ("host".to_string(), "example.amazon.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ]);
+        let req = SigningRequest {
+            method: "GET",
+            path: "/",
+            query: "",
+            headers: &headers,
+            payload: b"",
+            region: "us-east-1",
+            service: "service",
+            amz_date: "20150830T123600Z",
+        };
+        let authorization = credential
+            .generate_authorization(&req)
+            .expect("sign request");
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+            SignedHeaders=host;x-amz-date, \
+            Signature=7ab4567ae243ee168f6bf18206b2b40b61ce08277323168138fa113ed23c538e"
+        );
+    }
+}
@@ -0,0 +1,17 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is an AWS KMS implementation.
+//!
+//! AWS KMS uses KMS from Amazon Web Services to support envelope decryption,
+//! and AWS Secrets Manager to support getting secrets.
+//! The product details can be found here: <https://aws.amazon.com/kms/> and
+//! <https://aws.amazon.com/secrets-manager/>.
+
+mod annotations;
+mod client;
+mod credential;
+
+pub use client::AwsKmsClient;
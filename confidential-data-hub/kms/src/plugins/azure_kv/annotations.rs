@@ -0,0 +1,60 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// The default algorithm used to wrap/unwrap keys.
+pub const DEFAULT_WRAP_ALGORITHM: &str = "RSA-OAEP-256";
+
+/// Serialized [`crate::Annotations`] for wrapping/unwrapping keys
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AzureKvKeyAnnotations {
+    /// The algorithm used to wrap/unwrap, e.g. `RSA-OAEP-256`, `A256KW`
+    #[serde(default = "default_algorithm")]
+    pub algorithm: String,
+
+    /// Version of the key. By default the latest version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_version: Option<String>,
+
+    /// Release the exportable key into the guest by Secure Key Release and
+    /// unwrap locally, instead of by the `unwrapkey` operation of Key Vault.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub release: bool,
+}
+
+impl Default for AzureKvKeyAnnotations {
+    fn default() -> Self {
+        Self {
+            algorithm: default_algorithm(),
+            key_version: None,
+            release: false,
+        }
+    }
+}
+
+fn default_algorithm() -> String {
+    DEFAULT_WRAP_ALGORITHM.to_string()
+}
+
+/// Serialized [`crate::Annotations`] for getting secrets
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AzureKvSecretAnnotations {
+    /// Version of the secret. By default the latest version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AzureKvProviderSettings {
+    /// Url of the key vault, e.g. `https://myvault.vault.azure.net`
+    pub vault_url: String,
+
+    /// Client id of the user-assigned managed identity. If not given, the
+    /// system-assigned managed identity will be used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+}
@@ -0,0 +1,345 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use log::error;
+use reqwest::ClientBuilder;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+use crate::plugins::proxy::ProxyConfig;
+use crate::{Annotations, Decrypter, Encrypter, Getter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{
+    AzureKvKeyAnnotations, AzureKvProviderSettings, AzureKvSecretAnnotations,
+};
+use super::credential::{Credential, TokenSource};
+use super::skr::{ReleaseKey, ReleasedKey, RELEASE_ENCRYPTION};
+
+pub struct AzureKvClient {
+    http_client: reqwest::Client,
+    credential: Credential,
+    vault_url: String,
+}
+
+#[derive(Deserialize)]
+struct KeyOperationResult {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct SecretBundle {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Deserialize)]
+struct ErrorDetail {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl AzureKvClient {
+    const API_VERSION: &str = "7.4";
+
+    fn new(vault_url: &str, credential: Credential) -> Result<Self> {
+        let http_client = ProxyConfig::new()
+            .apply(ClientBuilder::new())
+            .map_err(|e| Error::AzureKvError(format!("set proxy failed: {e}")))?
+            .build()
+            .map_err(|e| Error::AzureKvError(format!("build http client failed: {e}")))?;
+
+        Ok(Self {
+            http_client,
+            credential,
+            vault_url: vault_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Create a client with a given access token. This is used at the user
+    /// side where IMDS is not accessible.
+    pub fn new_with_token(vault_url: &str, token: &str) -> Result<Self> {
+        let credential = Credential::new(TokenSource::Static(Zeroizing::new(token.to_string())));
+        Self::new(vault_url, credential)
+    }
+
+    /// This new function is used by a in-pod client. The access token is
+    /// obtained from the IMDS of the CVM using its managed identity, thus no
+    /// credential file is needed.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let provider_settings: AzureKvProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| Error::AzureKvError(format!("parse provider setting failed: {e}")))?;
+
+        let credential = Credential::new(TokenSource::ManagedIdentity {
+            client_id: provider_settings.client_id,
+        });
+        Self::new(&provider_settings.vault_url, credential)
+    }
+
+    /// The [`ProviderSettings`] of the vault url and the managed identity of
+    /// this client, to create the same client in the guest.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = AzureKvProviderSettings {
+            vault_url: self.vault_url.clone(),
+            client_id: self.credential.client_id(),
+        };
+
+        let provider_settings = serde_json::to_value(provider_settings)
+            .map_err(|e| Error::AzureKvError(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+
+    /// Do the `wrapkey` or `unwrapkey` operation of the key `key_id`.
+    async fn key_operation(
+        &mut self,
+        operation: &str,
+        data: &[u8],
+        key_id: &str,
+        annotations: &AzureKvKeyAnnotations,
+    ) -> Result<Vec<u8>> {
+        let url = self.key_url(key_id, annotations, operation);
+        let body = json!({
+            "alg": annotations.algorithm,
+            "value": URL_SAFE_NO_PAD.encode(data),
+        });
+
+        let res = self
+            .do_request(reqwest::Method::POST, &url, Some(body))
+            .await
+            .map_err(|e| Error::AzureKvError(format!("do request to key vault failed: {e}")))?;
+        Self::key_operation_value(operation, res)
+    }
+
+    /// The url of the `operation` of the key `key_id`, of the version given
+    /// by the `annotations` or else the latest one.
+    fn key_url(
+        &self,
+        key_id: &str,
+        annotations: &AzureKvKeyAnnotations,
+        operation: &str,
+    ) -> String {
+        match &annotations.key_version {
+            Some(version) => format!("{}/keys/{key_id}/{version}/{operation}", self.vault_url),
+            None => format!("{}/keys/{key_id}/{operation}", self.vault_url),
+        }
+    }
+
+    /// The decoded value of the response of a key `operation`.
+    fn key_operation_value(operation: &str, res: Value) -> Result<Vec<u8>> {
+        let result: KeyOperationResult = serde_json::from_value(res)
+            .map_err(|e| Error::AzureKvError(format!("illegal {operation} response: {e}")))?;
+        URL_SAFE_NO_PAD
+            .decode(result.value)
+            .map_err(|e| Error::AzureKvError(format!("decode {operation} result failed: {e}")))
+    }
+
+    /// Release the key `key_id` into the guest by Secure Key Release. The
+    /// key must be exportable, with a release policy the MAA token of the
+    /// guest satisfies.
+    async fn release_key(
+        &mut self,
+        key_id: &str,
+        annotations: &AzureKvKeyAnnotations,
+    ) -> Result<ReleasedKey> {
+        let release_key = ReleaseKey::new()
+            .map_err(|e| Error::AzureKvError(format!("create release key failed: {e}")))?;
+        let target = release_key
+            .attestation_token()
+            .await
+            .map_err(|e| Error::AzureKvError(format!("get attestation token failed: {e}")))?;
+
+        let url = self.key_url(key_id, annotations, "release");
+        let body = json!({
+            "target": target,
+            "enc": RELEASE_ENCRYPTION,
+        });
+
+        let res = self
+            .do_request(reqwest::Method::POST, &url, Some(body))
+            .await
+            .map_err(|e| Error::AzureKvError(format!("do request to key vault failed: {e}")))?;
+        let result: KeyOperationResult = serde_json::from_value(res)
+            .map_err(|e| Error::AzureKvError(format!("illegal release response: {e}")))?;
+        release_key
+            .unwrap_released_key(&result.value)
+            .map_err(|e| Error::AzureKvError(format!("unwrap released key failed: {e}")))
+    }
+
+    async fn do_request(
+        &mut self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Value> {
+        let token = Zeroizing::new(self.credential.get_token(&self.http_client).await?);
+        let mut request = self
+            .http_client
+            .request(method, url)
+            .query(&[("api-version", Self::API_VERSION)])
+            .bearer_auth(&*token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            error!("azure key vault: do request fail!");
+            let status = response.status();
+            let body_bytes = response.bytes().await?;
+            let error_msg = match serde_json::from_slice::<ErrorResponse>(&body_bytes) {
+                Ok(e) => format!(
+                    "status code: {status}, error code: {}, message: {}",
+                    e.error.code, e.error.message
+                ),
+                Err(_) => format!(
+                    "status code: {status}, body: {}",
+                    String::from_utf8_lossy(&body_bytes)
+                ),
+            };
+            anyhow::bail!(error_msg);
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl Encrypter for AzureKvClient {
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let annotations = AzureKvKeyAnnotations::default();
+        let ciphertext = self
+            .key_operation("wrapkey", data, key_id, &annotations)
+            .await?;
+
+        let annotations = serde_json::to_value(annotations)
+            .map_err(|e| Error::AzureKvError(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((ciphertext, annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for AzureKvClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: AzureKvKeyAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::AzureKvError(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+
+        if annotations.release {
+            let key = self.release_key(key_id, &annotations).await?;
+            return key
+                .unwrap_key(&annotations.algorithm, ciphertext)
+                .map(SecretBytes::from)
+                .map_err(|e| Error::AzureKvError(format!("unwrap by released key failed: {e}")));
+        }
+
+        self.key_operation("unwrapkey", ciphertext, key_id, &annotations)
+            .await
+            .map(SecretBytes::from)
+    }
+}
+
+#[async_trait]
+impl Getter for AzureKvClient {
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes> {
+        let annotations: AzureKvSecretAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::AzureKvError(format!(
+                    "deserialize annotations for get secret failed: {e}"
+                ))
+            })?;
+
+        let url = match annotations.version {
+            Some(version) => format!("{}/secrets/{name}/{version}", self.vault_url),
+            None => format!("{}/secrets/{name}", self.vault_url),
+        };
+        let res = self
+            .do_request(reqwest::Method::GET, &url, None)
+            .await
+            .map_err(|e| Error::AzureKvError(format!("do request to key vault failed: {e}")))?;
+        let secret: SecretBundle = serde_json::from_value(res)
+            .map_err(|e| Error::AzureKvError(format!("illegal get secret response: {e}")))?;
+        Ok(secret.value.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::AzureKvClient;
+    use crate::plugins::azure_kv::annotations::AzureKvKeyAnnotations;
+
+    const VAULT_URL: &str = "https://myvault.vault.azure.net";
+
+    #[rstest]
+    #[case(json!({ "vault_url": "https://myvault.vault.azure.net/" }), json!({ "vault_url": VAULT_URL }))]
+    #[case(
+        json!({ "vault_url": VAULT_URL, "client_id": "00000000-0000-0000-0000-000000000000" }),
+        json!({ "vault_url": VAULT_URL, "client_id": "00000000-0000-0000-0000-000000000000" })
+    )]
+    #[tokio::test]
+    async fn export_provider_settings(#[case] provider_settings: Value, #[case] expected: Value) {
+        let provider_settings = provider_settings.as_object().unwrap().to_owned();
+        let client = AzureKvClient::from_provider_settings(&provider_settings)
+            .await
+            .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+        assert_eq!(Value::Object(exported), expected);
+    }
+
+    #[rstest]
+    #[case(None, "wrapkey", "https://myvault.vault.azure.net/keys/kek/wrapkey")]
+    #[case(
+        Some("v2"),
+        "release",
+        "https://myvault.vault.azure.net/keys/kek/v2/release"
+    )]
+    fn key_url(#[case] key_version: Option<&str>, #[case] operation: &str, #[case] expected: &str) {
+        let client = AzureKvClient::new_with_token(VAULT_URL, "token").expect("create client");
+        let annotations = AzureKvKeyAnnotations {
+            key_version: key_version.map(String::from),
+            ..Default::default()
+        };
+        assert_eq!(client.key_url("kek", &annotations, operation), expected);
+    }
+
+    #[rstest]
+    #[case(json!({ "kid": "kek", "value": "a2V5" }), Some(&b"key"[..]))]
+    #[case(json!({ "kid": "kek", "value": "a2V5==" }), None)]
+    #[case(json!({ "kid": "kek" }), None)]
+    fn key_operation_value(#[case] res: Value, #[case] expected: Option<&[u8]>) {
+        let value = AzureKvClient::key_operation_value("unwrapkey", res);
+        assert_eq!(value.ok().as_deref(), expected);
+    }
+}
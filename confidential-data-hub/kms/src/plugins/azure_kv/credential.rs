@@ -0,0 +1,141 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Access tokens to access Azure Key Vault.
+//!
+//! Inside an Azure CVM the token is obtained from the Instance Metadata
+//! Service (IMDS) using the managed identity of the VM. See
+//! <https://learn.microsoft.com/en-us/entra/identity/managed-identities-azure-resources/how-to-use-vm-token>
+
+use anyhow::*;
+use chrono::Utc;
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+const IMDS_TOKEN_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const IMDS_API_VERSION: &str = "2018-02-01";
+const KEY_VAULT_RESOURCE: &str = "https://vault.azure.net";
+
+/// A cached token will be refreshed if it expires within this many seconds.
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+pub(crate) enum TokenSource {
+    /// Get the token from IMDS using the managed identity. If `client_id` is
+    /// given, the user-assigned managed identity will be used.
+    ManagedIdentity { client_id: Option<String> },
+
+    /// A token given directly by the user, used at the user side.
+    Static(Zeroizing<String>),
+}
+
+struct AccessToken {
+    token: Zeroizing<String>,
+    expires_on: i64,
+}
+
+#[derive(Deserialize)]
+struct ImdsTokenResponse {
+    access_token: String,
+    /// Unix timestamp in seconds, encoded as a string
+    expires_on: String,
+}
+
+pub(crate) struct Credential {
+    source: TokenSource,
+    cached: Option<AccessToken>,
+}
+
+impl Credential {
+    pub(crate) fn new(source: TokenSource) -> Self {
+        Self {
+            source,
+            cached: None,
+        }
+    }
+
+    pub(crate) fn client_id(&self) -> Option<String> {
+        match &self.source {
+            TokenSource::ManagedIdentity { client_id } => client_id.clone(),
+            TokenSource::Static(_) => None,
+        }
+    }
+
+    /// Get a valid access token for Key Vault. A cached token is reused
+    /// until it is about to expire.
+    pub(crate) async fn get_token(&mut self, http_client: &reqwest::Client) -> Result<String> {
+        let client_id = match &self.source {
+            TokenSource::Static(token) => return Ok(token.to_string()),
+            TokenSource::ManagedIdentity { client_id } => client_id.clone(),
+        };
+
+        if let Some(cached) = &self.cached {
+            if cached.expires_on - Utc::now().timestamp() > TOKEN_REFRESH_MARGIN_SECS {
+                return Ok(cached.token.to_string());
+            }
+        }
+
+        let mut query = vec![
+            ("api-version", IMDS_API_VERSION.to_string()),
+            ("resource", KEY_VAULT_RESOURCE.to_string()),
+        ];
+        if let Some(client_id) = client_id {
+            query.push(("client_id", client_id));
+        }
+
+        let response = http_client
+            .get(IMDS_TOKEN_ENDPOINT)
+            .header("Metadata", "true")
+            .query(&query)
+            .send()
+            .await
+            .context("request token from IMDS")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("get token from IMDS failed, status code: {status}, body: {body}");
+        }
+
+        let token = parse_token_response(&response.bytes().await?)?;
+        let res = token.token.to_string();
+        self.cached = Some(token);
+        Ok(res)
+    }
+}
+
+fn parse_token_response(body: &[u8]) -> Result<AccessToken> {
+    let response: ImdsTokenResponse =
+        serde_json::from_slice(body).context("illegal IMDS token response")?;
+    let expires_on = response
+        .expires_on
+        .parse::<i64>()
+        .context("illegal `expires_on` in IMDS token response")?;
+    Ok(AccessToken {
+        token: Zeroizing::new(response.access_token),
+        expires_on,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_token_response;
+
+    #[test]
+    fn token_response() {
+        let body = br#"{
+            "access_token": "eyJ0eXAi...",
+            "refresh_token": "",
+            "expires_in": "3599",
+            "expires_on": "1506484173",
+            "not_before": "1506480273",
+            "resource": "https://vault.azure.net",
+            "token_type": "Bearer"
+        }"#;
+        let token = parse_token_response(body).expect("parse token response");
+        assert_eq!(*token.token, "eyJ0eXAi...");
+        assert_eq!(token.expires_on, 1506484173);
+
+        assert!(parse_token_response(br#"{"access_token": "a", "expires_on": "x"}"#).is_err());
+    }
+}
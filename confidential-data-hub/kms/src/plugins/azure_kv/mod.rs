@@ -0,0 +1,20 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is an Azure Key Vault implementation.
+//!
+//! Secrets are fetched from and keys are unwrapped by Azure Key Vault. The
+//! access token is obtained from the Instance Metadata Service (IMDS) of the
+//! CVM using its managed identity.
+//! Exportable keys can instead be released into the guest by Secure Key
+//! Release with an MAA token, so that the keys are unwrapped locally.
+//! The product detail can be found here: <https://azure.microsoft.com/products/key-vault>.
+
+mod annotations;
+mod client;
+mod credential;
+mod skr;
+
+pub use client::AzureKvClient;
@@ -0,0 +1,285 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Secure Key Release (SKR) of Azure Key Vault.
+//!
+//! Keys marked exportable with a release policy are released by Key Vault
+//! to a guest presenting an MAA token that satisfies the policy. The guest
+//! binds a fresh RSA key to the token as the runtime claim
+//! `TpmEphemeralEncryptionKey`, and Key Vault wraps the released key with
+//! it by `RSA_AES_KEY_WRAP_256`, i.e. an ephemeral AES key wrapped by
+//! RSA-OAEP-256, followed by the key wrapped by the AES key (RFC 5649).
+//! See <https://learn.microsoft.com/en-us/azure/key-vault/keys/how-to-create-ephemeral-rsa-key>
+
+use anyhow::*;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use kbs_protocol::token_provider::AATokenProvider;
+use openssl::{
+    md::{Md, MdRef},
+    pkey::{PKey, Private},
+    pkey_ctx::PkeyCtx,
+    rsa::{Padding, Rsa},
+    symm::{Cipher, Crypter, Mode},
+};
+use serde::Deserialize;
+use serde_json::json;
+use zeroize::Zeroizing;
+
+/// The wrapping algorithm of the released keys.
+pub(super) const RELEASE_ENCRYPTION: &str = "RSA_AES_KEY_WRAP_256";
+
+/// The kid of the runtime claim Key Vault wraps the released keys with.
+const RELEASE_KEY_ID: &str = "TpmEphemeralEncryptionKey";
+
+const TOKEN_TYPE: &str = "maa";
+
+const RELEASE_KEY_BITS: u32 = 2048;
+
+/// Default initial value of RFC 3394.
+const KW_IV: [u8; 8] = [0xa6; 8];
+
+/// Prefix of the alternative initial value of RFC 5649.
+const KWP_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// The ephemeral key pair the key of a release is wrapped by.
+pub(super) struct ReleaseKey {
+    key: PKey<Private>,
+}
+
+#[derive(Deserialize)]
+struct ReleasePayload {
+    response: ReleaseResponse,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    key: KeyBundle,
+}
+
+#[derive(Deserialize)]
+struct KeyBundle {
+    key: JsonWebKey,
+}
+
+#[derive(Deserialize)]
+struct JsonWebKey {
+    kty: String,
+    key_hsm: String,
+}
+
+#[derive(Deserialize)]
+struct KeyHsm {
+    ciphertext: String,
+}
+
+/// A key released into the guest.
+pub(super) enum ReleasedKey {
+    Rsa(PKey<Private>),
+    Oct(Zeroizing<Vec<u8>>),
+}
+
+impl ReleaseKey {
+    pub fn new() -> Result<Self> {
+        let rsa = Rsa::generate(RELEASE_KEY_BITS).context("generate release key failed")?;
+        let key = PKey::from_rsa(rsa)?;
+        Ok(Self { key })
+    }
+
+    /// Get an MAA token from the attestation-agent, carrying the public key
+    /// as runtime data.
+    pub async fn attestation_token(&self) -> Result<String> {
+        let rsa = self.key.rsa()?;
+        let runtime_data = json!({
+            "keys": [{
+                "kid": RELEASE_KEY_ID,
+                "kty": "RSA",
+                "key_ops": ["encrypt"],
+                "e": URL_SAFE_NO_PAD.encode(rsa.e().to_vec()),
+                "n": URL_SAFE_NO_PAD.encode(rsa.n().to_vec()),
+            }]
+        });
+
+        let provider = AATokenProvider::new()
+            .await
+            .map_err(|e| anyhow!("connect to attestation-agent failed: {e}"))?;
+        let token = provider
+            .get_token_with_runtime_data(TOKEN_TYPE, runtime_data.to_string().into_bytes())
+            .await
+            .map_err(|e| anyhow!("get MAA token failed: {e}"))?;
+        String::from_utf8(token).context("illegal MAA token")
+    }
+
+    /// Unwrap the key in the `value` of the release response, which is a
+    /// JWS over the released key. The JWS is got from Key Vault over TLS,
+    /// thus its signature is not verified.
+    pub fn unwrap_released_key(&self, value: &str) -> Result<ReleasedKey> {
+        let payload = value
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| anyhow!("release response is not a JWS"))?;
+        let payload: ReleasePayload = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)
+            .context("illegal release response")?;
+        let jwk = payload.response.key.key;
+        let key_hsm: KeyHsm = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(jwk.key_hsm)?)
+            .context("illegal key_hsm of the released key")?;
+        let ciphertext = URL_SAFE_NO_PAD.decode(key_hsm.ciphertext)?;
+
+        let rsa_size = self.key.size();
+        if ciphertext.len() <= rsa_size {
+            bail!("ciphertext of the released key is too short");
+        }
+        let (wrapped_kek, wrapped_key) = ciphertext.split_at(rsa_size);
+        let kek = Zeroizing::new(rsa_oaep_decrypt(&self.key, Md::sha256(), wrapped_kek)?);
+        let key = aes_unwrap(&kek, wrapped_key, true)?;
+
+        match jwk.kty.as_str() {
+            "RSA" | "RSA-HSM" => {
+                let key = PKey::private_key_from_pkcs8(&key).context("illegal released RSA key")?;
+                Ok(ReleasedKey::Rsa(key))
+            }
+            "oct" | "oct-HSM" => Ok(ReleasedKey::Oct(key)),
+            kty => bail!("unsupported key type {kty} of the released key"),
+        }
+    }
+}
+
+impl ReleasedKey {
+    /// Unwrap the `ciphertext` by the released key locally, with the same
+    /// `algorithm` as the `unwrapkey` operation of Key Vault.
+    pub fn unwrap_key(&self, algorithm: &str, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        match (self, algorithm) {
+            (ReleasedKey::Rsa(key), "RSA-OAEP") => rsa_oaep_decrypt(key, Md::sha1(), ciphertext),
+            (ReleasedKey::Rsa(key), "RSA-OAEP-256") => {
+                rsa_oaep_decrypt(key, Md::sha256(), ciphertext)
+            }
+            (ReleasedKey::Oct(key), "A128KW" | "A192KW" | "A256KW") => {
+                if key.len() * 8 != algorithm[1..4].parse::<usize>()? {
+                    bail!("{algorithm} does not match the released key");
+                }
+                Ok(aes_unwrap(key, ciphertext, false)?.to_vec())
+            }
+            _ => bail!("unsupported algorithm {algorithm} for the released key"),
+        }
+    }
+}
+
+fn rsa_oaep_decrypt(key: &PKey<Private>, md: &MdRef, ciphertext: &[u8]) -> Result<Vec<u8>> {
+    let mut ctx = PkeyCtx::new(key)?;
+    ctx.decrypt_init()?;
+    ctx.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    ctx.set_rsa_oaep_md(md)?;
+
+    let mut plaintext = Vec::new();
+    ctx.decrypt_to_vec(ciphertext, &mut plaintext)
+        .context("RSA-OAEP decryption failed")?;
+    Ok(plaintext)
+}
+
+/// Unwrap the key `wrapped` by the `kek` due to RFC 3394, or RFC 5649 if
+/// `padded`, which also unwraps keys of lengths not multiples of 8.
+fn aes_unwrap(kek: &[u8], wrapped: &[u8], padded: bool) -> Result<Zeroizing<Vec<u8>>> {
+    let cipher = match kek.len() {
+        16 => Cipher::aes_128_ecb(),
+        24 => Cipher::aes_192_ecb(),
+        32 => Cipher::aes_256_ecb(),
+        len => bail!("illegal AES key wrap key length {len}"),
+    };
+    if !wrapped.len().is_multiple_of(8) || wrapped.len() < 16 || (!padded && wrapped.len() < 24) {
+        bail!("illegal wrapped key length {}", wrapped.len());
+    }
+
+    let mut crypter = Crypter::new(cipher, Mode::Decrypt, kek, None)?;
+    crypter.pad(false);
+    let mut decrypt_block = |block: &[u8; 16]| -> Result<[u8; 16]> {
+        let mut out = [0; 32];
+        crypter.update(block, &mut out)?;
+        Ok(out[..16].try_into()?)
+    };
+
+    let n = wrapped.len() / 8 - 1;
+    let mut a: [u8; 8] = wrapped[..8].try_into()?;
+    let mut r = Zeroizing::new(wrapped[8..].to_vec());
+    if n == 1 {
+        let block = Zeroizing::new(decrypt_block(wrapped.try_into()?)?);
+        a.copy_from_slice(&block[..8]);
+        r.copy_from_slice(&block[8..]);
+    } else {
+        let mut block = Zeroizing::new([0; 16]);
+        for j in (0..6).rev() {
+            for i in (1..=n).rev() {
+                let t = (n * j + i) as u64;
+                for (a, t) in a.iter_mut().zip(t.to_be_bytes()) {
+                    *a ^= t;
+                }
+                block[..8].copy_from_slice(&a);
+                block[8..].copy_from_slice(&r[(i - 1) * 8..i * 8]);
+                *block = decrypt_block(&block)?;
+                a.copy_from_slice(&block[..8]);
+                r[(i - 1) * 8..i * 8].copy_from_slice(&block[8..]);
+            }
+        }
+    }
+
+    if !padded {
+        if a != KW_IV {
+            bail!("integrity check of the wrapped key failed");
+        }
+        return Ok(r);
+    }
+
+    let len = u32::from_be_bytes(a[4..].try_into()?) as usize;
+    if a[..4] != KWP_IV_PREFIX
+        || len > r.len()
+        || len + 8 <= r.len()
+        || r[len..].iter().any(|b| *b != 0)
+    {
+        bail!("integrity check of the wrapped key failed");
+    }
+    r.truncate(len);
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::aes_unwrap;
+
+    #[rstest]
+    #[case(
+        "000102030405060708090a0b0c0d0e0f",
+        "1fa68b0a8112b447aef34bd8fb5a7b829d3e862371d2cfe5",
+        false,
+        "00112233445566778899aabbccddeeff"
+    )]
+    #[case(
+        "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7",
+        false,
+        "00112233445566778899aabbccddeeff"
+    )]
+    #[case(
+        "5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8",
+        "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+        true,
+        "c37b7e6492584340bed12207808941155068f738"
+    )]
+    #[case(
+        "5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8",
+        "afbeb0f07dfbf5419200f2ccb50bb24f",
+        true,
+        "466f7250617369"
+    )]
+    fn unwrap(#[case] kek: &str, #[case] wrapped: &str, #[case] padded: bool, #[case] key: &str) {
+        let kek = hex::decode(kek).unwrap();
+        let mut wrapped = hex::decode(wrapped).unwrap();
+        let unwrapped = aes_unwrap(&kek, &wrapped, padded).unwrap();
+        assert_eq!(*unwrapped, hex::decode(key).unwrap());
+
+        wrapped[0] ^= 1;
+        assert!(aes_unwrap(&kek, &wrapped, padded).is_err());
+    }
+}
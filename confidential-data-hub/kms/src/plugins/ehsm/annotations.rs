@@ -0,0 +1,33 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// Serialized [`crate::Annotations`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EhsmAnnotations {
+    /// Base64 encoded additional authenticated data of the encryption.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aad: Option<String>,
+
+    /// The app id to use instead of the one in the provider settings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EhsmProviderSettings {
+    /// Address of the eHSM-KMS service, e.g. `https://ehsm.example.com:9000`
+    pub endpoint: String,
+
+    /// The app id to authenticate with.
+    pub app_id: String,
+
+    /// The api key of the app id. If not given, it is read from the
+    /// credential file of the app id inside the guest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
@@ -0,0 +1,333 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::Utc;
+use log::error;
+use reqwest::{ClientBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::plugins::proxy::ProxyConfig;
+use crate::{Annotations, Decrypter, Encrypter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{EhsmAnnotations, EhsmProviderSettings};
+use super::credential::Credential;
+
+/// The `code` of a successful eHSM-KMS response.
+const SUCCESS_CODE: i64 = 200;
+
+pub struct EhsmClient {
+    http_client: reqwest::Client,
+    credential: Credential,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct EhsmResponse {
+    code: i64,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    result: Value,
+}
+
+#[derive(Deserialize)]
+struct EncryptResult {
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct DecryptResult {
+    plaintext: String,
+}
+
+impl EhsmClient {
+    fn new(endpoint: &str, credential: Credential) -> Result<Self> {
+        let http_client = ProxyConfig::new()
+            .apply(ClientBuilder::new())
+            .map_err(|e| Error::EhsmError(format!("set proxy failed: {e}")))?
+            .build()
+            .map_err(|e| Error::EhsmError(format!("build http client failed: {e}")))?;
+
+        Ok(Self {
+            http_client,
+            credential,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Create a client with the given app id and api key. This is used at
+    /// the user side.
+    pub fn new_with_api_key(endpoint: &str, app_id: &str, api_key: &str) -> Result<Self> {
+        Self::new(endpoint, Credential::new(app_id, api_key))
+    }
+
+    /// This new function is used by a in-pod client. The api key is taken
+    /// from the provider settings if given, or read from the credential file
+    /// of the app id.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings: EhsmProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| Error::EhsmError(format!("parse provider setting failed: {e}")))?;
+
+        let credential = match &settings.api_key {
+            Some(api_key) => Credential::new(&settings.app_id, api_key),
+            None => Credential::from_file(&settings.app_id)
+                .await
+                .map_err(|e| Error::EhsmError(format!("read credential failed: {e}")))?,
+        };
+        Self::new(&settings.endpoint, credential)
+    }
+
+    /// The [`ProviderSettings`] of the endpoint and the app id of this client.
+    /// The api key is not exported, but read from the credential file of the
+    /// app id in the guest.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = EhsmProviderSettings {
+            endpoint: self.endpoint.clone(),
+            app_id: self.credential.app_id.clone(),
+            api_key: None,
+        };
+
+        let provider_settings = serde_json::to_value(provider_settings)
+            .map_err(|e| Error::EhsmError(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+
+    /// The credential of the `app_id` given in the annotations, or the one
+    /// of this client.
+    async fn credential(&self, app_id: Option<&str>) -> anyhow::Result<Credential> {
+        match app_id {
+            Some(app_id) if app_id != self.credential.app_id => Credential::from_file(app_id).await,
+            _ => Ok(self.credential.clone()),
+        }
+    }
+
+    async fn do_request(
+        &self,
+        action: &str,
+        credential: &Credential,
+        payload: Map<String, Value>,
+    ) -> anyhow::Result<Value> {
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let body = Self::request_body(credential, payload, timestamp)?;
+        let response = self
+            .http_client
+            .post(format!("{}/ehsm", self.endpoint))
+            .query(&[("Action", action)])
+            .json(&body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body_bytes = response.bytes().await?;
+        Self::response_result(status, &body_bytes)
+    }
+
+    /// The body of a request of the `payload` sent at `timestamp` in
+    /// milliseconds, signed by the `credential`.
+    fn request_body(
+        credential: &Credential,
+        payload: Map<String, Value>,
+        timestamp: String,
+    ) -> anyhow::Result<Value> {
+        let sign = credential.sign(&payload, &timestamp)?;
+        Ok(json!({
+            "appid": credential.app_id,
+            "payload": payload,
+            "timestamp": timestamp,
+            "sign": sign,
+        }))
+    }
+
+    /// The `result` of a response, which succeeds only if both the http
+    /// `status` and the `code` of the body do.
+    fn response_result(status: StatusCode, body_bytes: &[u8]) -> anyhow::Result<Value> {
+        let response = match serde_json::from_slice::<EhsmResponse>(body_bytes) {
+            Ok(response) => response,
+            Err(_) => {
+                error!("ehsm: do request fail!");
+                anyhow::bail!(
+                    "status code: {status}, body: {}",
+                    String::from_utf8_lossy(body_bytes)
+                );
+            }
+        };
+        if !status.is_success() || response.code != SUCCESS_CODE {
+            error!("ehsm: do request fail!");
+            anyhow::bail!(
+                "status code: {status}, code: {}, message: {}",
+                response.code,
+                response.message
+            );
+        }
+
+        Ok(response.result)
+    }
+}
+
+#[async_trait]
+impl Encrypter for EhsmClient {
+    /// The `key_id` is the id of a symmetric CMK of eHSM-KMS.
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let payload = json!({
+            "keyid": key_id,
+            "plaintext": STANDARD.encode(data),
+            "aad": "",
+        });
+        let payload = payload.as_object().expect("must be an object").to_owned();
+        let res = self
+            .do_request("Encrypt", &self.credential, payload)
+            .await
+            .map_err(|e| Error::EhsmError(format!("do request to ehsm failed: {e}")))?;
+        let encrypt_result: EncryptResult = serde_json::from_value(res)
+            .map_err(|e| Error::EhsmError(format!("illegal encrypt response: {e}")))?;
+        let ciphertext = STANDARD
+            .decode(encrypt_result.ciphertext)
+            .map_err(|e| Error::EhsmError(format!("decode ciphertext failed: {e}")))?;
+
+        let annotations = serde_json::to_value(EhsmAnnotations::default())
+            .map_err(|e| Error::EhsmError(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((ciphertext, annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for EhsmClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: EhsmAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::EhsmError(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+        let credential = self
+            .credential(annotations.app_id.as_deref())
+            .await
+            .map_err(|e| Error::EhsmError(format!("read credential failed: {e}")))?;
+
+        let payload = json!({
+            "keyid": key_id,
+            "ciphertext": STANDARD.encode(ciphertext),
+            "aad": annotations.aad.unwrap_or_default(),
+        });
+        let payload = payload.as_object().expect("must be an object").to_owned();
+        let res = self
+            .do_request("Decrypt", &credential, payload)
+            .await
+            .map_err(|e| Error::EhsmError(format!("do request to ehsm failed: {e}")))?;
+        let decrypt_result: DecryptResult = serde_json::from_value(res)
+            .map_err(|e| Error::EhsmError(format!("illegal decrypt response: {e}")))?;
+        let plaintext = STANDARD
+            .decode(decrypt_result.plaintext)
+            .map_err(|e| Error::EhsmError(format!("decode plaintext failed: {e}")))?;
+        Ok(plaintext.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::EhsmClient;
+    use crate::plugins::ehsm::credential::Credential;
+
+    #[tokio::test]
+    async fn export_provider_settings() {
+        let provider_settings = json!({
+            "endpoint": "https://ehsm.example.com:9000/",
+            "app_id": "b6b6ed6f-7e3c-4e1d-8a4e-05d5fa4d4b12",
+            "api_key": "api-key",
+        });
+        let provider_settings = provider_settings.as_object().unwrap().to_owned();
+        let client = EhsmClient::from_provider_settings(&provider_settings)
+            .await
+            .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+
+        // the api key is not exported
+        assert_eq!(
+            Value::Object(exported),
+            json!({
+                "endpoint": "https://ehsm.example.com:9000",
+                "app_id": "b6b6ed6f-7e3c-4e1d-8a4e-05d5fa4d4b12",
+            })
+        );
+    }
+
+    #[test]
+    fn request_body() {
+        let credential = Credential::new("b6b6ed6f", "api-key");
+        let payload = json!({ "keyid": "key", "ciphertext": "Y2lwaGVy", "aad": "" });
+        let payload = payload.as_object().unwrap().to_owned();
+        let body = EhsmClient::request_body(&credential, payload.clone(), "1640000000000".into())
+            .expect("build body");
+        assert_eq!(
+            body,
+            json!({
+                "appid": "b6b6ed6f",
+                "payload": payload,
+                "timestamp": "1640000000000",
+                "sign": credential.sign(&payload, "1640000000000").unwrap(),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(StatusCode::OK, br#"{"code":200,"message":"success","result":{"plaintext":"cGxhaW4="}}"#, Some(json!({ "plaintext": "cGxhaW4=" })))]
+    #[case(
+        StatusCode::OK,
+        br#"{"code":400,"message":"keyid is invalid","result":{}}"#,
+        None
+    )]
+    #[case(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        br#"{"code":200,"message":"success"}"#,
+        None
+    )]
+    #[case(StatusCode::BAD_GATEWAY, b"bad gateway", None)]
+    fn response_result(
+        #[case] status: StatusCode,
+        #[case] body: &[u8],
+        #[case] expected: Option<Value>,
+    ) {
+        let result = EhsmClient::response_result(status, body);
+        assert_eq!(result.ok(), expected);
+    }
+
+    #[tokio::test]
+    async fn credential_of_annotations() {
+        let client =
+            EhsmClient::new_with_api_key("https://ehsm.example.com:9000", "app", "api-key")
+                .expect("create client");
+        for app_id in [None, Some("app")] {
+            let credential = client.credential(app_id).await.expect("get credential");
+            assert_eq!(credential.app_id, "app");
+        }
+
+        // the credential of another app id is read from its file
+        assert!(client.credential(Some("another-app")).await.is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Credentials to access eHSM-KMS, and the signing of the requests.
+//!
+//! Every request carries the app id, a timestamp and a signature. The
+//! signature is the base64 encoded HMAC-SHA256 of the sorted parameters with
+//! the api key, see <https://github.com/intel/ehsm/blob/main/docs/API_Reference.md>.
+
+use std::collections::BTreeMap;
+
+use anyhow::*;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+use crate::plugins::credential_dir;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone)]
+pub(crate) struct Credential {
+    pub(crate) app_id: String,
+    api_key: Zeroizing<String>,
+}
+
+impl Credential {
+    pub(crate) fn new(app_id: &str, api_key: &str) -> Self {
+        Self {
+            app_id: app_id.to_string(),
+            api_key: Zeroizing::new(api_key.to_string()),
+        }
+    }
+
+    /// Read the api key of the `app_id` from the credential file
+    /// `apikey_<app_id>` inside the guest.
+    pub(crate) async fn from_file(app_id: &str) -> Result<Self> {
+        let api_key_path = format!("{}/apikey_{app_id}", credential_dir("ehsm"));
+        let api_key = Zeroizing::new(
+            fs::read_to_string(&api_key_path)
+                .await
+                .context(format!("read api key from {api_key_path}"))?,
+        );
+        Ok(Self::new(app_id, api_key.trim()))
+    }
+
+    /// Sign the `payload` of a request sent at `timestamp` in milliseconds.
+    pub(crate) fn sign(&self, payload: &Map<String, Value>, timestamp: &str) -> Result<String> {
+        let string_to_sign = string_to_sign(&self.app_id, payload, timestamp);
+        let mut mac = HmacSha256::new_from_slice(self.api_key.as_bytes())?;
+        mac.update(string_to_sign.as_bytes());
+        Ok(STANDARD.encode(mac.finalize().into_bytes()))
+    }
+}
+
+/// The parameters sorted by name and joined as `name=value` with `&`. The
+/// payload is flattened in the same way.
+fn string_to_sign(app_id: &str, payload: &Map<String, Value>, timestamp: &str) -> String {
+    let payload = join_sorted(
+        payload
+            .iter()
+            .map(|(k, v)| (k.as_str(), value_to_string(v))),
+    );
+    join_sorted([
+        ("appid", app_id.to_string()),
+        ("payload", payload),
+        ("timestamp", timestamp.to_string()),
+    ])
+}
+
+fn join_sorted<'a>(params: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    params
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        others => others.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{string_to_sign, Credential};
+
+    #[test]
+    fn sign() {
+        let payload = json!({
+            "keyid": "3b5ec1c9-8ae6-4bd0-b3a1-1f4bd7fb2f8c",
+            "ciphertext": "Y2lwaGVy",
+            "aad": "",
+        });
+        let payload = payload.as_object().unwrap();
+        assert_eq!(
+            string_to_sign("b6b6ed6f", payload, "1640000000000"),
+            "appid=b6b6ed6f&payload=aad=&ciphertext=Y2lwaGVy&keyid=3b5ec1c9-8ae6-4bd0-b3a1-1f4bd7fb2f8c&timestamp=1640000000000"
+        );
+
+        let credential = Credential::new("b6b6ed6f", "api-key");
+        let signature = credential.sign(payload, "1640000000000").expect("sign");
+        assert_eq!(signature.len(), 44);
+        assert_eq!(
+            credential.sign(payload, "1640000000000").expect("sign"),
+            signature
+        );
+        assert_ne!(
+            Credential::new("b6b6ed6f", "another-key")
+                .sign(payload, "1640000000000")
+                .expect("sign"),
+            signature
+        );
+    }
+}
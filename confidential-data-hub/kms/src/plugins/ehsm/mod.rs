@@ -0,0 +1,17 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is an Intel eHSM-KMS implementation.
+//!
+//! eHSM-KMS is a KMS appliance whose keys are managed inside SGX enclaves.
+//! The REST API with the appid/apikey authentication is used to support
+//! envelope decryption. The product details can be found here:
+//! <https://github.com/intel/ehsm>.
+
+mod annotations;
+mod client;
+mod credential;
+
+pub use client::EhsmClient;
@@ -0,0 +1,42 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// Serialized [`crate::Annotations`] for encryption/decryption
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GcpKmsAnnotations {
+    /// Base64 encoded additional authenticated data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_authenticated_data: Option<String>,
+}
+
+/// Serialized [`crate::Annotations`] for getting secrets from Secret Manager
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GcpSecretAnnotations {
+    /// Version of the secret. By default `latest`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// How to get the access token to Google Cloud APIs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "credential_mode", rename_all = "snake_case")]
+pub enum CredentialMode {
+    /// Get the access token from the metadata server, e.g. with GKE
+    /// workload identity or the attached service account of the VM.
+    WorkloadIdentity,
+
+    /// Get the access token with a service account JSON key file. The file
+    /// is located by the id of the key.
+    ServiceAccount { private_key_id: String },
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GcpProviderSettings {
+    #[serde(flatten)]
+    pub credential: CredentialMode,
+}
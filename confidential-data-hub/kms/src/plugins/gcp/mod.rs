@@ -0,0 +1,17 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is a Google Cloud KMS implementation.
+//!
+//! Google Cloud KMS is used to support envelope decryption, and Google Cloud
+//! Secret Manager is used to support getting secrets.
+//! The product details can be found here: <https://cloud.google.com/kms> and
+//! <https://cloud.google.com/secret-manager>.
+
+mod annotations;
+mod client;
+mod credential;
+
+pub use client::GcpKmsClient;
@@ -0,0 +1,197 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! In-memory cache of the resources got from the KBS, so that repeated
+//! requests for the same [`ResourceUri`](super::ResourceUri) during the
+//! lifetime of a pod do not trigger a full attestation round-trip again.
+//! The resources are kept as [`SecretBytes`].
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::SecretBytes;
+
+/// Default time-to-live of a cached resource in seconds, i.e. the cache is
+/// opt-in, so that a rotated resource is got from the KBS at once.
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 0;
+
+/// Default max number of cached resources.
+pub const DEFAULT_CACHE_MAX_ENTRIES: usize = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CachePolicy {
+    /// Time-to-live of a cached resource in seconds. `0`, the default,
+    /// disables the cache.
+    pub ttl_secs: u64,
+
+    /// Max number of cached resources. When the cache is full, the expired
+    /// resources and then the oldest one will be evicted.
+    pub max_entries: usize,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl_secs: DEFAULT_CACHE_TTL_SECS,
+            max_entries: DEFAULT_CACHE_MAX_ENTRIES,
+        }
+    }
+}
+
+impl CachePolicy {
+    pub(crate) fn enabled(&self) -> bool {
+        self.ttl_secs > 0 && self.max_entries > 0
+    }
+}
+
+struct CacheEntry {
+    value: SecretBytes,
+    inserted_at: Instant,
+    expires_at: Instant,
+}
+
+pub(crate) struct ResourceCache<K> {
+    entries: HashMap<K, CacheEntry>,
+}
+
+impl<K: Hash + Eq + Clone> ResourceCache<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Get the cached resource if it is not expired.
+    pub(crate) fn get(&mut self, key: &K) -> Option<SecretBytes> {
+        let now = Instant::now();
+        match self.entries.get(key) {
+            Some(entry) if entry.expires_at > now => Some(entry.value.clone()),
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: SecretBytes, policy: &CachePolicy) {
+        if !policy.enabled() {
+            return;
+        }
+
+        // The policy may come from the provider settings of a sealed secret,
+        // so a ttl too large to be an instant is not cached rather than
+        // panicking.
+        let now = Instant::now();
+        let Some(expires_at) = now.checked_add(Duration::from_secs(policy.ttl_secs)) else {
+            return;
+        };
+        if !self.entries.contains_key(&key) && self.entries.len() >= policy.max_entries {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+        }
+
+        while !self.entries.contains_key(&key) && self.entries.len() >= policy.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.inserted_at)
+                .map(|(k, _)| k.clone())
+                .expect("must not be empty");
+            self.entries.remove(&oldest);
+        }
+
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: now,
+                expires_at,
+            },
+        );
+    }
+
+    /// Remove the cached resources that `f` returns `true` for.
+    pub(crate) fn invalidate(&mut self, f: impl Fn(&K) -> bool) {
+        self.entries.retain(|k, _| !f(k));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachePolicy, ResourceCache};
+
+    const POLICY: CachePolicy = CachePolicy {
+        ttl_secs: 60,
+        max_entries: 10,
+    };
+
+    #[test]
+    fn ttl() {
+        let mut cache = ResourceCache::new();
+        cache.insert(
+            "a",
+            b"a".to_vec().into(),
+            &CachePolicy {
+                ttl_secs: 0,
+                max_entries: 10,
+            },
+        );
+        assert_eq!(cache.get(&"a").as_deref(), None);
+
+        cache.insert("a", b"a".to_vec().into(), &CachePolicy::default());
+        assert_eq!(cache.get(&"a").as_deref(), None);
+
+        cache.insert(
+            "a",
+            b"a".to_vec().into(),
+            &CachePolicy {
+                ttl_secs: u64::MAX,
+                max_entries: 10,
+            },
+        );
+        assert_eq!(cache.get(&"a").as_deref(), None);
+
+        cache.insert("a", b"a".to_vec().into(), &POLICY);
+        assert_eq!(cache.get(&"a").as_deref(), Some(&b"a"[..]));
+    }
+
+    #[test]
+    fn evict_oldest() {
+        let policy = CachePolicy {
+            ttl_secs: 60,
+            max_entries: 2,
+        };
+        let mut cache = ResourceCache::new();
+        cache.insert("a", b"a".to_vec().into(), &policy);
+        cache.insert("b", b"b".to_vec().into(), &policy);
+        cache.insert("c", b"c".to_vec().into(), &policy);
+        assert_eq!(cache.get(&"a").as_deref(), None);
+        assert_eq!(cache.get(&"b").as_deref(), Some(&b"b"[..]));
+        assert_eq!(cache.get(&"c").as_deref(), Some(&b"c"[..]));
+
+        // updating an existing entry does not evict others
+        cache.insert("b", b"bb".to_vec().into(), &policy);
+        assert_eq!(cache.get(&"b").as_deref(), Some(&b"bb"[..]));
+        assert_eq!(cache.get(&"c").as_deref(), Some(&b"c"[..]));
+    }
+
+    #[test]
+    fn invalidate() {
+        let mut cache = ResourceCache::new();
+        cache.insert("a", b"a".to_vec().into(), &POLICY);
+        cache.insert("b", b"b".to_vec().into(), &POLICY);
+        cache.invalidate(|k| *k == "a");
+        assert_eq!(cache.get(&"a").as_deref(), None);
+        assert_eq!(cache.get(&"b").as_deref(), Some(&b"b"[..]));
+        cache.invalidate(|_| true);
+        assert_eq!(cache.get(&"b").as_deref(), None);
+    }
+}
@@ -3,32 +3,52 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use kbs_protocol::{
     client::KbsClient as KbsProtocolClient,
     token_provider::{AATokenProvider, TokenProvider},
     KbsClientCapabilities, ResourceUri,
 };
+use tokio::sync::Mutex;
 
-use crate::{Error, Result};
+use crate::{plugins::proxy::ProxyConfig, KbsError, Result};
 
-use super::Kbc;
+use super::{tls, token_refresh, Kbc};
 
 pub struct CcKbc {
-    client: KbsProtocolClient<Box<dyn TokenProvider>>,
+    client: Arc<Mutex<KbsProtocolClient<Box<dyn TokenProvider>>>>,
 }
 
 impl CcKbc {
     pub async fn new(kbs_host_url: &str) -> Result<Self> {
         let token_provider = AATokenProvider::new()
             .await
-            .map_err(|e| Error::KbsClientError(format!("create AA token provider failed: {e}")))?;
-        let client = kbs_protocol::KbsClientBuilder::with_token_provider(
+            .map_err(|e| KbsError::Network(format!("create AA token provider failed: {e}")))?;
+        let mut builder = kbs_protocol::KbsClientBuilder::with_token_provider(
             Box::new(token_provider),
             kbs_host_url,
-        )
-        .build()
-        .map_err(|e| Error::KbsClientError(format!("create kbs client failed: {e}")))?;
+        );
+
+        let tls_config = tls::get_kbs_tls_config().await?;
+        for cert in &tls_config.root_certs {
+            builder = builder.add_kbs_cert(cert);
+        }
+        if let Some((cert, key)) = &tls_config.client_identity {
+            builder = builder.set_client_identity(cert, key);
+        }
+
+        let proxy_config = ProxyConfig::new();
+        if let Some(https_proxy) = &proxy_config.https_proxy {
+            builder = builder.set_proxy(https_proxy, Some(&proxy_config.no_proxy()));
+        }
+
+        let client = builder
+            .build()
+            .map_err(|e| KbsError::Config(format!("create kbs client failed: {e}")))?;
+        let client = Arc::new(Mutex::new(client));
+        token_refresh::spawn(Arc::downgrade(&client));
         Ok(Self { client })
     }
 }
@@ -38,9 +58,53 @@ impl Kbc for CcKbc {
     async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
         let secret = self
             .client
+            .lock()
+            .await
             .get_resource(rid)
             .await
-            .map_err(|e| Error::KbsClientError(format!("get resource failed: {e}")))?;
+            .map_err(|e| kbs_error("get resource failed", e))?;
         Ok(secret)
     }
+
+    async fn set_resource(&mut self, rid: ResourceUri, content: Vec<u8>) -> Result<()> {
+        self.client
+            .lock()
+            .await
+            .set_resource(rid, content)
+            .await
+            .map_err(|e| kbs_error("set resource failed", e).into())
+    }
+}
+
+/// Classify the errors of the KBS protocol client.
+pub(super) fn kbs_error(context: &str, e: kbs_protocol::Error) -> KbsError {
+    let message = format!("{context}: {e}");
+    match e {
+        kbs_protocol::Error::HttpError(_) => KbsError::Network(message),
+        kbs_protocol::Error::AATokenProvider(_)
+        | kbs_protocol::Error::GetEvidence(_)
+        | kbs_protocol::Error::GetTokenFailed(_)
+        | kbs_protocol::Error::RcarHandshake(_)
+        | kbs_protocol::Error::UnAuthorized => KbsError::Auth(message),
+        kbs_protocol::Error::PolicyDenied(_) => KbsError::PolicyDenied(message),
+        kbs_protocol::Error::ResourceNotFound(_) => KbsError::NotFound(message),
+        _ => KbsError::Internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::kbs_error;
+
+    #[rstest]
+    #[case(kbs_protocol::Error::HttpError("connection refused".into()), true)]
+    #[case(kbs_protocol::Error::UnAuthorized, true)]
+    #[case(kbs_protocol::Error::PolicyDenied("denied".into()), false)]
+    #[case(kbs_protocol::Error::ResourceNotFound("default/key/1".into()), false)]
+    #[case(kbs_protocol::Error::KbsInternalError("internal".into()), true)]
+    fn retryable(#[case] e: kbs_protocol::Error, #[case] expected: bool) {
+        assert_eq!(kbs_error("get resource failed", e).is_retryable(), expected);
+    }
 }
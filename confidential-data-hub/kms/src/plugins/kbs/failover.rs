@@ -0,0 +1,102 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Failover between the KBS endpoints of a KBC instance.
+//!
+//! The `kbs_host` can be a comma-separated list of KBS urls, e.g. the
+//! replicas of a KBS, like `http://kbs-0:8080,http://kbs-1:8080`. The
+//! endpoints are tried in the given order. An endpoint that fails is marked
+//! unhealthy for [`UNHEALTHY_COOLDOWN`], during which it is only tried after
+//! all the healthy endpoints have failed.
+
+use std::time::{Duration, Instant};
+
+/// How long an endpoint is considered unhealthy after a failure.
+pub(crate) const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// The health of a KBS endpoint.
+#[derive(Default)]
+pub(crate) struct Health {
+    unhealthy_until: Option<Instant>,
+}
+
+impl Health {
+    pub fn is_healthy(&self, now: Instant) -> bool {
+        match self.unhealthy_until {
+            Some(until) => now >= until,
+            None => true,
+        }
+    }
+
+    pub fn mark_failed(&mut self, now: Instant) {
+        self.unhealthy_until = Some(now + UNHEALTHY_COOLDOWN);
+    }
+
+    pub fn mark_ok(&mut self) {
+        self.unhealthy_until = None;
+    }
+}
+
+/// Split the `kbs_host` into the list of KBS endpoints.
+pub(crate) fn split_kbs_hosts(kbs_host: &str) -> Vec<String> {
+    kbs_host
+        .split(',')
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The indexes of the endpoints in the order to try them, i.e. the healthy
+/// ones in the given order, followed by the unhealthy ones.
+pub(crate) fn try_order<'a>(healths: impl Iterator<Item = &'a Health>, now: Instant) -> Vec<usize> {
+    let (healthy, unhealthy): (Vec<_>, Vec<_>) = healths
+        .enumerate()
+        .partition(|(_, health)| health.is_healthy(now));
+    healthy
+        .into_iter()
+        .chain(unhealthy)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rstest::rstest;
+
+    use super::{split_kbs_hosts, try_order, Health, UNHEALTHY_COOLDOWN};
+
+    #[rstest]
+    #[case("http://kbs:8080", vec!["http://kbs:8080"])]
+    #[case(
+        "http://kbs-0:8080, http://kbs-1:8080,",
+        vec!["http://kbs-0:8080", "http://kbs-1:8080"]
+    )]
+    #[case("", vec![])]
+    fn kbs_hosts(#[case] kbs_host: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(split_kbs_hosts(kbs_host), expected);
+    }
+
+    #[test]
+    fn failover_order() {
+        let now = Instant::now();
+        let mut healths: Vec<Health> = (0..3).map(|_| Health::default()).collect();
+        assert_eq!(try_order(healths.iter(), now), vec![0, 1, 2]);
+
+        healths[0].mark_failed(now);
+        assert_eq!(try_order(healths.iter(), now), vec![1, 2, 0]);
+
+        // the endpoint is tried first again after the cooldown
+        assert_eq!(
+            try_order(healths.iter(), now + UNHEALTHY_COOLDOWN),
+            vec![0, 1, 2]
+        );
+
+        healths[0].mark_ok();
+        assert_eq!(try_order(healths.iter(), now), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Envelope encryption by the keys of the KBS. A key encryption key is a
+//! resource of the KBS holding an AES-256 key, e.g. `kbs:///default/kek/1`,
+//! and the data encryption keys are wrapped and unwrapped by it with
+//! AES-256-GCM once it is got from the KBS.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto::WrapType;
+use rand::Rng;
+use serde_json::Value;
+use zeroize::Zeroizing;
+
+use crate::{Annotations, KbsError, Result, SecretBytes};
+
+/// Annotation of the base64 encoded IV of a wrapped key.
+pub const IV_ANNOTATION: &str = "iv";
+
+/// Length of a key encryption key.
+const KEK_LEN: usize = 32;
+
+fn check_kek(kek: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if kek.len() != KEK_LEN {
+        return Err(KbsError::Crypto(format!(
+            "key encryption key must be {KEK_LEN} bytes, got {}",
+            kek.len()
+        ))
+        .into());
+    }
+
+    Ok(Zeroizing::new(kek.to_vec()))
+}
+
+/// Wrap the `data` by the `kek` with a random IV.
+pub(crate) fn wrap(kek: &[u8], data: &[u8]) -> Result<(Vec<u8>, Annotations)> {
+    let kek = check_kek(kek)?;
+    let mut iv = [0u8; 12];
+    rand::thread_rng().fill(&mut iv);
+    let ciphertext = crypto::encrypt(kek, data.to_vec(), iv.to_vec(), WrapType::Aes256Gcm)
+        .map_err(|e| KbsError::Crypto(format!("wrap key failed: {e}")))?;
+
+    let mut annotations = Annotations::new();
+    annotations.insert(IV_ANNOTATION.into(), STANDARD.encode(iv).into());
+    Ok((ciphertext, annotations))
+}
+
+/// Unwrap the `ciphertext` by the `kek` with the IV in the `annotations`.
+pub(crate) fn unwrap(
+    kek: &[u8],
+    ciphertext: &[u8],
+    annotations: &Annotations,
+) -> Result<SecretBytes> {
+    let kek = check_kek(kek)?;
+    let Some(Value::String(iv)) = annotations.get(IV_ANNOTATION) else {
+        return Err(KbsError::Crypto(format!("no annotation `{IV_ANNOTATION}` is given")).into());
+    };
+    let iv = STANDARD
+        .decode(iv)
+        .map_err(|e| KbsError::Crypto(format!("base64 decode iv failed: {e}")))?;
+    let plaintext = crypto::decrypt(kek, ciphertext.to_vec(), iv, WrapType::Aes256Gcm)
+        .map_err(|e| KbsError::Crypto(format!("unwrap key failed: {e}")))?;
+
+    Ok(plaintext.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use crate::Annotations;
+
+    use super::{unwrap, wrap, IV_ANNOTATION};
+
+    const KEK: [u8; 32] = [7; 32];
+
+    #[test]
+    fn wrap_unwrap() {
+        let (ciphertext, annotations) = wrap(&KEK, b"data key").unwrap();
+        assert_eq!(
+            unwrap(&KEK, &ciphertext, &annotations).unwrap(),
+            b"data key"
+        );
+
+        // another random iv
+        let (other, _) = wrap(&KEK, b"data key").unwrap();
+        assert_ne!(ciphertext, other);
+
+        assert!(unwrap(&[8; 32], &ciphertext, &annotations).is_err());
+        assert!(unwrap(&KEK, &ciphertext, &Annotations::new()).is_err());
+    }
+
+    #[rstest]
+    #[case(&[7; 16])]
+    #[case(&[])]
+    fn illegal_kek(#[case] kek: &[u8]) {
+        assert!(wrap(kek, b"data key").is_err());
+
+        let mut annotations = Annotations::new();
+        annotations.insert(IV_ANNOTATION.into(), "AAAAAAAAAAAAAAAA".into());
+        assert!(unwrap(kek, b"ciphertext", &annotations).is_err());
+    }
+}
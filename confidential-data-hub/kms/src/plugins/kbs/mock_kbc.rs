@@ -0,0 +1,53 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! A KBC of the background check mode with a mocked evidence, enabled by the
+//! feature `testing`. It needs neither a TEE nor an attestation agent, so
+//! the KBS protocol can be tested against a
+//! [`MockKbs`](crate::plugins::test::MockKbs).
+
+use async_trait::async_trait;
+use kbs_protocol::{
+    client::KbsClient as KbsProtocolClient,
+    evidence_provider::{EvidenceProvider, MockedEvidenceProvider},
+    KbsClientCapabilities, ResourceUri,
+};
+
+use crate::{KbsError, Result};
+
+use super::{cc_kbc::kbs_error, Kbc};
+
+pub struct MockKbc {
+    client: KbsProtocolClient<Box<dyn EvidenceProvider>>,
+}
+
+impl MockKbc {
+    pub async fn new(kbs_host_url: &str) -> Result<Self> {
+        let client = kbs_protocol::KbsClientBuilder::with_evidence_provider(
+            Box::<MockedEvidenceProvider>::default(),
+            kbs_host_url,
+        )
+        .build()
+        .map_err(|e| KbsError::Config(format!("create kbs client failed: {e}")))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Kbc for MockKbc {
+    async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
+        self.client
+            .get_resource(rid)
+            .await
+            .map_err(|e| kbs_error("get resource failed", e).into())
+    }
+
+    async fn set_resource(&mut self, rid: ResourceUri, content: Vec<u8>) -> Result<()> {
+        self.client
+            .set_resource(rid, content)
+            .await
+            .map_err(|e| kbs_error("set resource failed", e).into())
+    }
+}
@@ -5,124 +5,799 @@
 
 //! Abstraction for KBCs as a KMS plugin.
 
+mod aa_kbc_params;
+pub use aa_kbc_params::{
+    check_kbs_host, get_aa_params, parse_aa_kbc_params, AA_KBC_PARAMS_CONFIG_PATH,
+    AA_KBC_PARAMS_CONFIG_PATH_ENV, AA_KBC_PARAMS_ENV,
+};
+
+mod cache;
+pub use cache::CachePolicy;
+
 #[cfg(feature = "kbs")]
 mod cc_kbc;
 
+#[cfg(feature = "kbs")]
+mod kek;
+#[cfg(feature = "kbs")]
+pub use kek::IV_ANNOTATION;
+
 #[cfg(feature = "sev")]
 mod sev;
+#[cfg(feature = "sev")]
+pub use sev::{SECRETS_DIR, SEV_SECRET_TABLE_PATH_ENV};
+
+mod failover;
+
+#[cfg(feature = "testing")]
+mod mock_kbc;
+
+mod offline_ase;
+pub use offline_ase::{OFFLINE_ASE_KBC_CONFIG_PATH, OFFLINE_ASE_KBC_CONFIG_PATH_ENV};
 
 mod offline_fs;
 
-use std::sync::Arc;
+mod retry;
+pub use retry::RetryPolicy;
+
+#[cfg(feature = "kbs")]
+mod tls;
+#[cfg(feature = "kbs")]
+pub use tls::{KBS_TLS_CONFIG_PATH, KBS_TLS_CONFIG_PATH_ENV};
+
+#[cfg(feature = "kbs")]
+mod token_refresh;
+#[cfg(feature = "kbs")]
+pub use token_refresh::{token_refresh_metrics, TokenRefreshMetrics};
+
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
+use log::{debug, warn};
 pub use resource_uri::ResourceUri;
-use tokio::sync::Mutex;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::Mutex,
+};
+use tracing::instrument;
 
-use crate::{Annotations, Error, Getter, Result};
+use cache::ResourceCache;
+use failover::Health;
 
-enum RealClient {
+use crate::{
+    metrics, settings, Annotations, Error, Getter, KbsError, ProviderSettings, Result, SecretBytes,
+    Setter,
+};
+#[cfg(feature = "kbs")]
+use crate::{Decrypter, Encrypter};
+
+/// A KBC instance connecting to a single KBS endpoint.
+enum KbcInstance {
     #[cfg(feature = "kbs")]
     Cc(cc_kbc::CcKbc),
     #[cfg(feature = "sev")]
     Sev(sev::OnlineSevKbc),
     OfflineFs(offline_fs::OfflineFsKbc),
+    OfflineAse(offline_ase::OfflineAseKbc),
+    #[cfg(feature = "testing")]
+    Mock(mock_kbc::MockKbc),
 }
 
-impl RealClient {
-    async fn new() -> Result<Self> {
-        let (kbc, _kbs_host) = get_aa_params_from_cmdline().await?;
-        let c = match &kbc[..] {
+impl KbcInstance {
+    async fn new(kbc: &str, _kbs_host: &str) -> Result<Self> {
+        let c = match kbc {
             #[cfg(feature = "kbs")]
-            "cc_kbc" => RealClient::Cc(cc_kbc::CcKbc::new(&_kbs_host).await?),
+            "cc_kbc" => KbcInstance::Cc(cc_kbc::CcKbc::new(_kbs_host).await?),
             #[cfg(feature = "sev")]
-            "online_sev_kbc" => RealClient::Sev(sev::OnlineSevKbc::new(&_kbs_host).await?),
-            "offline_fs_kbc" => RealClient::OfflineFs(offline_fs::OfflineFsKbc::new().await?),
-            others => return Err(Error::KbsClientError(format!("unknown kbc name {others}, only support `cc_kbc`(feature `kbs`), `online_sev_kbc` (feature `sev`) and `offline_fs_kbc`."))),
+            "online_sev_kbc" => KbcInstance::Sev(sev::OnlineSevKbc::new(_kbs_host).await?),
+            "offline_fs_kbc" => KbcInstance::OfflineFs(offline_fs::OfflineFsKbc::new().await?),
+            "offline_ase_kbc" => KbcInstance::OfflineAse(offline_ase::OfflineAseKbc::new().await?),
+            #[cfg(feature = "testing")]
+            "mock_kbc" => KbcInstance::Mock(mock_kbc::MockKbc::new(_kbs_host).await?),
+            others => return Err(KbsError::Config(format!("unknown kbc name {others}, only support `cc_kbc`(feature `kbs`), `online_sev_kbc` (feature `sev`), `offline_fs_kbc`, `offline_ase_kbc` and `mock_kbc` (feature `testing`).")).into()),
         };
 
         Ok(c)
     }
+
+    async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "kbs")]
+            KbcInstance::Cc(c) => c.get_resource(rid).await,
+            #[cfg(feature = "sev")]
+            KbcInstance::Sev(c) => c.get_resource(rid).await,
+            KbcInstance::OfflineFs(c) => c.get_resource(rid).await,
+            KbcInstance::OfflineAse(c) => c.get_resource(rid).await,
+            #[cfg(feature = "testing")]
+            KbcInstance::Mock(c) => c.get_resource(rid).await,
+        }
+    }
+
+    async fn write_resource(
+        &mut self,
+        rid: ResourceUri,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        match self {
+            #[cfg(feature = "kbs")]
+            KbcInstance::Cc(c) => c.write_resource(rid, writer).await,
+            #[cfg(feature = "sev")]
+            KbcInstance::Sev(c) => c.write_resource(rid, writer).await,
+            KbcInstance::OfflineFs(c) => c.write_resource(rid, writer).await,
+            KbcInstance::OfflineAse(c) => c.write_resource(rid, writer).await,
+            #[cfg(feature = "testing")]
+            KbcInstance::Mock(c) => c.write_resource(rid, writer).await,
+        }
+    }
+
+    async fn set_resource(&mut self, rid: ResourceUri, content: Vec<u8>) -> Result<()> {
+        match self {
+            #[cfg(feature = "kbs")]
+            KbcInstance::Cc(c) => c.set_resource(rid, content).await,
+            #[cfg(feature = "sev")]
+            KbcInstance::Sev(c) => c.set_resource(rid, content).await,
+            KbcInstance::OfflineFs(c) => c.set_resource(rid, content).await,
+            KbcInstance::OfflineAse(c) => c.set_resource(rid, content).await,
+            #[cfg(feature = "testing")]
+            KbcInstance::Mock(c) => c.set_resource(rid, content).await,
+        }
+    }
 }
 
+/// A KBS endpoint of a [`RealClient`]. The [`KbcInstance`] is created
+/// when the endpoint is used for the first time.
+struct Endpoint {
+    kbs_host: String,
+    instance: Option<KbcInstance>,
+    health: Health,
+}
+
+impl Endpoint {
+    async fn instance(&mut self, kbc: &str) -> Result<&mut KbcInstance> {
+        if self.instance.is_none() {
+            self.instance = Some(KbcInstance::new(kbc, &self.kbs_host).await?);
+        }
+
+        Ok(self.instance.as_mut().expect("must be initialized"))
+    }
+
+    /// Update the health of the endpoint with the result of a request. Errors
+    /// that are not retryable, e.g. a resource that does not exist, do not
+    /// make the endpoint unhealthy.
+    fn track<T>(&mut self, res: Result<T>) -> Result<T> {
+        match &res {
+            Ok(_) => self.health.mark_ok(),
+            Err(e) if e.is_retryable() => {
+                warn!("kbs endpoint {} failed: {e}", self.kbs_host);
+                self.health.mark_failed(Instant::now());
+            }
+            Err(_) => {}
+        }
+
+        res
+    }
+}
+
+/// The KBC instances of a KBC name connecting to the KBS endpoints given
+/// by the `kbs_host`, with failover in order between them.
+struct RealClient {
+    kbc: String,
+    endpoints: Vec<Endpoint>,
+}
+
+impl RealClient {
+    /// Create the client. The endpoints are tried in order until a
+    /// [`KbcInstance`] is created. The instances of the remaining endpoints
+    /// are created when they are failed over to.
+    async fn new(key: &KbcKey) -> Result<Self> {
+        let mut endpoints: Vec<Endpoint> = failover::split_kbs_hosts(&key.kbs_host)
+            .into_iter()
+            .map(|kbs_host| Endpoint {
+                kbs_host,
+                instance: None,
+                health: Health::default(),
+            })
+            .collect();
+
+        let mut last_error = KbsError::Config("no kbs host given".into()).into();
+        for endpoint in &mut endpoints {
+            let res = endpoint.instance(&key.kbc).await.map(|_| ());
+            match endpoint.track(res) {
+                Ok(()) => {
+                    return Ok(Self {
+                        kbc: key.kbc.clone(),
+                        endpoints,
+                    })
+                }
+                Err(e) if e.is_retryable() => last_error = e,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
+        let mut last_error = None;
+        for index in failover::try_order(self.endpoints.iter().map(|e| &e.health), Instant::now()) {
+            let endpoint = &mut self.endpoints[index];
+            let res = match endpoint.instance(&self.kbc).await {
+                Ok(instance) => instance.get_resource(rid.clone()).await,
+                Err(e) => Err(e),
+            };
+            match endpoint.track(res) {
+                Ok(resource) => return Ok(resource),
+                Err(e) if e.is_retryable() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("must have at least one endpoint"))
+    }
+
+    /// Write the resource to the `writer`. An endpoint is only failed over
+    /// if nothing is written, as the errors of writing are not retryable.
+    async fn write_resource(
+        &mut self,
+        rid: ResourceUri,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let mut last_error = None;
+        for index in failover::try_order(self.endpoints.iter().map(|e| &e.health), Instant::now()) {
+            let endpoint = &mut self.endpoints[index];
+            let res = match endpoint.instance(&self.kbc).await {
+                Ok(instance) => instance.write_resource(rid.clone(), writer).await,
+                Err(e) => Err(e),
+            };
+            match endpoint.track(res) {
+                Ok(size) => return Ok(size),
+                Err(e) if e.is_retryable() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("must have at least one endpoint"))
+    }
+
+    async fn set_resource(&mut self, rid: ResourceUri, content: Vec<u8>) -> Result<()> {
+        let mut last_error = None;
+        for index in failover::try_order(self.endpoints.iter().map(|e| &e.health), Instant::now()) {
+            let endpoint = &mut self.endpoints[index];
+            let res = match endpoint.instance(&self.kbc).await {
+                Ok(instance) => instance.set_resource(rid.clone(), content.clone()).await,
+                Err(e) => Err(e),
+            };
+            match endpoint.track(res) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.is_retryable() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("must have at least one endpoint"))
+    }
+}
+
+/// The key of a KBC instance inside the pool, i.e. the KBC name and the KBS host,
+/// which can be a comma-separated list of KBS endpoints.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct KbcKey {
+    kbc: String,
+    kbs_host: String,
+}
+
+type PooledClient = Arc<Mutex<Option<RealClient>>>;
+
 lazy_static! {
-    static ref KBS_CLIENT: Arc<Mutex<Option<RealClient>>> = Arc::new(Mutex::new(None));
+    /// All the KBC instances created in this process. Each instance is
+    /// guarded by its own lock, so that a slow KBS does not block the
+    /// requests to the other ones.
+    static ref KBC_POOL: Mutex<HashMap<KbcKey, PooledClient>> = Mutex::new(HashMap::new());
+
+    /// Resources got by all the KBC instances, keyed by the KBC instance
+    /// and the resource uri.
+    static ref RESOURCE_CACHE: Mutex<ResourceCache<(KbcKey, String)>> =
+        Mutex::new(ResourceCache::new());
+}
+
+/// Get the KBC instance of the given key from [`KBC_POOL`]. The instance
+/// will be created if it does not exist. Only the instances created are
+/// kept in the pool, so that the keys failing to be created do not grow it.
+async fn pooled_client(key: &KbcKey) -> Result<PooledClient> {
+    loop {
+        let client = {
+            let mut pool = KBC_POOL.lock().await;
+            pool.entry(key.clone()).or_default().clone()
+        };
+
+        let mut c = client.lock().await;
+        if c.is_some() {
+            drop(c);
+            return Ok(client);
+        }
+
+        // The entry is removed by a failed creation while this one waited
+        // for it, so start over with the one in the pool.
+        let pooled = KBC_POOL
+            .lock()
+            .await
+            .get(key)
+            .is_some_and(|pooled| Arc::ptr_eq(pooled, &client));
+        if !pooled {
+            continue;
+        }
+
+        match RealClient::new(key).await {
+            Ok(real_client) => {
+                *c = Some(real_client);
+                drop(c);
+                return Ok(client);
+            }
+            Err(e) => {
+                let mut pool = KBC_POOL.lock().await;
+                if pool
+                    .get(key)
+                    .is_some_and(|pooled| Arc::ptr_eq(pooled, &client))
+                {
+                    pool.remove(key);
+                }
+                return Err(e);
+            }
+        }
+    }
 }
 
 #[async_trait]
 pub trait Kbc: Send + Sync {
     async fn get_resource(&mut self, _rid: ResourceUri) -> Result<Vec<u8>>;
+
+    /// Write the resource `rid` to the `writer`, and return the size of it.
+    /// By default the whole resource is got and then written. A KBC that
+    /// can read a resource in chunks should override this, so that large
+    /// resources are not buffered in memory. Errors of writing must not be
+    /// retryable, as a part of the resource may have been written.
+    async fn write_resource(
+        &mut self,
+        rid: ResourceUri,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let resource = self.get_resource(rid).await?;
+        writer
+            .write_all(&resource)
+            .await
+            .map_err(|e| KbsError::Internal(format!("write resource failed: {e}")))?;
+        Ok(resource.len() as u64)
+    }
+
+    /// Write the `content` to the resource `rid` of the KBS. Whether this is
+    /// allowed is up to the policy of the KBS. By default a KBC does not
+    /// support this.
+    async fn set_resource(&mut self, rid: ResourceUri, _content: Vec<u8>) -> Result<()> {
+        Err(KbsError::Unsupported(format!(
+            "set resource {} is not supported by this kbc",
+            rid.whole_uri()
+        ))
+        .into())
+    }
+}
+
+/// Serialized [`ProviderSettings`] to specify the KBC instance, the
+/// [`RetryPolicy`] and the [`CachePolicy`]. The KBC name and the KBS host that are not given are
+/// taken from the `aa_kbc_params`, and the KBS host given must be of the KBS hosts of it, see
+/// [`check_configured_hosts`].
+#[derive(Deserialize)]
+struct KbcProviderSettings {
+    #[serde(default)]
+    kbc: Option<String>,
+    #[serde(default)]
+    kbs_host: Option<String>,
+    #[serde(default)]
+    retry: Option<RetryPolicy>,
+    #[serde(default)]
+    cache: Option<CachePolicy>,
 }
 
-/// A fake KbcClient to carry the [`Getter`] semantics. The real `new()`
-/// and `get_resource()` will happen to the static variable [`KBS_CLIENT`].
+/// A fake KbcClient to carry the [`Getter`] and [`Setter`] semantics. The
+/// real `new()`, `get_resource()` and `set_resource()` will happen to the KBC instance inside the static
+/// pool [`KBC_POOL`], keyed by the KBC name and the KBS host.
 ///
 /// Why we use a static variable here is the initialization of kbc is not
 /// idempotent. For example online-sev-kbc will delete a file on local
 /// filesystem, so we should try to reuse the online-sev-kbc created at the
 /// first time.
-pub struct KbcClient;
+pub struct KbcClient {
+    key: KbcKey,
+    retry: RetryPolicy,
+    cache: CachePolicy,
+}
+
+/// Annotation of [`KbcClient::get_secret`] to get the secret by the KBC of
+/// this name instead of the one of the client, e.g. `offline_fs_kbc` for the
+/// bootstrap secrets while `cc_kbc` is used otherwise. The KBS host is
+/// always the one of the client, as the annotations of a vault secret are
+/// not authenticated, and must not point the guest to a KBS the operator
+/// has not configured.
+pub const KBC_ANNOTATION: &str = "kbc";
 
 #[async_trait]
 impl Getter for KbcClient {
-    async fn get_secret(&mut self, name: &str, _annotations: &Annotations) -> Result<Vec<u8>> {
+    /// Get the resource `name`, which is a kbs resource uri. The KBC
+    /// instance can be overridden for this request by the annotation
+    /// [`KBC_ANNOTATION`]. The instance is
+    /// created when it is used for the first time, and then shared like the
+    /// others in [`KBC_POOL`].
+    #[instrument(skip_all, fields(kbc = %self.key.kbc, kbs_host = %self.key.kbs_host, name = %name))]
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes> {
         let resource_uri = ResourceUri::try_from(name)
-            .map_err(|_| Error::KbsClientError(format!("illegal kbs resource uri: {name}")))?;
-        let real_client = KBS_CLIENT.clone();
-        let mut client = real_client.lock().await;
-
-        if client.is_none() {
-            let c = RealClient::new().await?;
-            *client = Some(c);
+            .map_err(|_| KbsError::MalformedUri(format!("illegal kbs resource uri: {name}")))?;
+        let key = self.request_key(annotations)?;
+        let cache_key = (key.clone(), resource_uri.whole_uri());
+        if self.cache.enabled() {
+            let cached = RESOURCE_CACHE.lock().await.get(&cache_key);
+            metrics::observe_cache(&key.kbc, cached.is_some());
+            if let Some(resource) = cached {
+                return Ok(resource);
+            }
         }
 
-        let client = client.as_mut().expect("must be initialized");
+        let resource = self.get_resource_with_retry(&key, resource_uri).await?;
+        RESOURCE_CACHE
+            .lock()
+            .await
+            .insert(cache_key, resource.clone(), &self.cache);
+        Ok(resource)
+    }
+}
 
-        match client {
-            #[cfg(feature = "kbs")]
-            RealClient::Cc(c) => c.get_resource(resource_uri).await,
-            #[cfg(feature = "sev")]
-            RealClient::Sev(c) => c.get_resource(resource_uri).await,
-            RealClient::OfflineFs(c) => c.get_resource(resource_uri).await,
-        }
+#[async_trait]
+impl Setter for KbcClient {
+    /// Write the `content` to the KBS resource `name`, which is a kbs
+    /// resource uri. The cached resource will be removed.
+    #[instrument(skip_all, fields(kbc = %self.key.kbc, kbs_host = %self.key.kbs_host, name = %name))]
+    async fn set_secret(&mut self, content: Vec<u8>, name: String) -> Result<Annotations> {
+        let resource_uri = ResourceUri::try_from(&name[..])
+            .map_err(|_| KbsError::MalformedUri(format!("illegal kbs resource uri: {name}")))?;
+        self.set_resource_with_retry(resource_uri, content).await?;
+        self.invalidate(&name).await?;
+        Ok(Annotations::default())
+    }
+}
+
+/// The key encryption key `key_id` is a kbs resource uri of an AES-256 key,
+/// which wraps the keys with AES-256-GCM. It is got from the KBS like any
+/// other resource, so the sealing side needs to reach it too, e.g. by the
+/// `offline_fs_kbc`.
+#[cfg(feature = "kbs")]
+#[async_trait]
+impl Encrypter for KbcClient {
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let kek = self.get_secret(key_id, &Annotations::default()).await?;
+        kek::wrap(&kek, data)
+    }
+}
+
+#[cfg(feature = "kbs")]
+#[async_trait]
+impl Decrypter for KbcClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let kek = self.get_secret(key_id, &Annotations::default()).await?;
+        kek::unwrap(&kek, ciphertext, annotations)
     }
 }
 
 impl KbcClient {
+    /// Create a client to the KBC instance given by the `aa_kbc_params`.
     pub async fn new() -> Result<Self> {
-        let client = KBS_CLIENT.clone();
-        let mut client = client.lock().await;
-        if client.is_none() {
-            let c = RealClient::new().await?;
-            *client = Some(c);
+        let (kbc, kbs_host) = aa_kbc_params::get_aa_params().await?;
+        Self::new_with_params(&kbc, &kbs_host).await
+    }
+
+    /// Create a client to the KBC instance of `kbc` name connecting to
+    /// `kbs_host`, which can be a comma-separated list of KBS urls to fail
+    /// over between in order. Different KBC instances can be used at the
+    /// same time.
+    pub async fn new_with_params(kbc: &str, kbs_host: &str) -> Result<Self> {
+        let key = KbcKey {
+            kbc: kbc.to_string(),
+            kbs_host: kbs_host.to_string(),
+        };
+        pooled_client(&key).await?;
+        let settings = settings::settings();
+        Ok(KbcClient {
+            key,
+            retry: settings.retry.unwrap_or_default(),
+            cache: settings.cache.unwrap_or_default(),
+        })
+    }
+
+    /// Set the [`RetryPolicy`] of getting resources.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the [`CachePolicy`] of the got resources.
+    pub fn with_cache_policy(mut self, cache: CachePolicy) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Write the resource `name`, which is a kbs resource uri, to the
+    /// `writer` and return the size of it. This is for large resources, e.g.
+    /// models, so that the resource is neither cached nor put into a message
+    /// as a whole. The request is failed over between the KBS endpoints, but
+    /// not retried.
+    #[instrument(skip_all, fields(kbc = %self.key.kbc, kbs_host = %self.key.kbs_host, name = %name))]
+    pub async fn write_resource(
+        &self,
+        name: &str,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let resource_uri = ResourceUri::try_from(name)
+            .map_err(|_| KbsError::MalformedUri(format!("illegal kbs resource uri: {name}")))?;
+        let real_client = pooled_client(&self.key).await?;
+        let mut client = real_client.lock().await;
+        let client = client.as_mut().expect("must be initialized");
+
+        let res = client.write_resource(resource_uri, writer).await;
+        if let Err(e) = &res {
+            Self::observe_error(&self.key.kbc, e);
         }
+        res
+    }
 
-        Ok(KbcClient {})
+    /// Remove the cached resource `name` of the KBC instance of this client,
+    /// so that the next request will get it from the KBS again.
+    pub async fn invalidate(&self, name: &str) -> Result<()> {
+        let resource_uri = ResourceUri::try_from(name)
+            .map_err(|_| KbsError::MalformedUri(format!("illegal kbs resource uri: {name}")))?
+            .whole_uri();
+        RESOURCE_CACHE
+            .lock()
+            .await
+            .invalidate(|(key, uri)| *key == self.key && *uri == resource_uri);
+        Ok(())
+    }
+
+    /// Remove all the cached resources of all KBC instances.
+    pub async fn invalidate_all() {
+        RESOURCE_CACHE.lock().await.invalidate(|_| true);
+    }
+
+    /// Remove all the cached resources and drop all the KBC instances, e.g.
+    /// at shutdown. The cached resources are zeroized, and the keys and the
+    /// tokens held by the instances are released. The instances are created
+    /// again by the next requests.
+    pub async fn close_all() {
+        Self::invalidate_all().await;
+        KBC_POOL.lock().await.clear();
+    }
+
+    /// The KBC instance to get a secret with the `annotations` from, see
+    /// [`KBC_ANNOTATION`].
+    fn request_key(&self, annotations: &Annotations) -> Result<KbcKey> {
+        let kbc = match annotations.get(KBC_ANNOTATION) {
+            None => return Ok(self.key.clone()),
+            Some(Value::String(kbc)) => kbc.clone(),
+            Some(_) => {
+                return Err(KbsError::Config(format!(
+                    "annotation `{KBC_ANNOTATION}` must be a string"
+                ))
+                .into())
+            }
+        };
+
+        if kbc != self.key.kbc {
+            debug!("get secret by kbc {kbc} given by the annotations");
+        }
+        Ok(KbcKey {
+            kbc,
+            kbs_host: self.key.kbs_host.clone(),
+        })
+    }
+
+    /// Record the failed attestation of the KBC `kbc` in the [`metrics`].
+    fn observe_error(kbc: &str, e: &Error) {
+        if matches!(e, Error::KbsClientError(KbsError::Auth(_))) {
+            metrics::observe_attestation_failure(kbc);
+        }
+    }
+
+    async fn get_resource_with_retry(
+        &self,
+        key: &KbcKey,
+        resource_uri: ResourceUri,
+    ) -> Result<SecretBytes> {
+        let real_client = pooled_client(key).await?;
+
+        // The instance is locked by each attempt only, so that the other
+        // requests of it are not blocked during the backoff.
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let res = {
+                let mut client = real_client.lock().await;
+                let client = client.as_mut().expect("must be initialized");
+                self.retry
+                    .attempt(client.get_resource(resource_uri.clone()))
+                    .await
+            };
+            match res {
+                Ok(resource) => return Ok(resource.into()),
+                Err(e) => {
+                    Self::observe_error(&key.kbc, &e);
+                    if !self.retry.wait_for_retry(attempt, &e).await {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn set_resource_with_retry(
+        &self,
+        resource_uri: ResourceUri,
+        content: Vec<u8>,
+    ) -> Result<()> {
+        let real_client = pooled_client(&self.key).await?;
+
+        // The instance is locked by each attempt only, so that the other
+        // requests of it are not blocked during the backoff.
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let res = {
+                let mut client = real_client.lock().await;
+                let client = client.as_mut().expect("must be initialized");
+                self.retry
+                    .attempt(client.set_resource(resource_uri.clone(), content.clone()))
+                    .await
+            };
+            match res {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    Self::observe_error(&self.key.kbc, &e);
+                    if !self.retry.wait_for_retry(attempt, &e).await {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Create a client with the `kbc`, `kbs_host`, `retry` and `cache` policy
+    /// given in the [`ProviderSettings`]. The KBC name and the KBS host that are not
+    /// given are taken from the `aa_kbc_params`, and the KBS host given must be of
+    /// the ones of it.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings: KbcProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| KbsError::Config(format!("parse provider setting failed: {e}")))?;
+
+        let (default_kbc, default_kbs_host) = aa_kbc_params::get_aa_params().await?;
+        let kbc = settings.kbc.unwrap_or(default_kbc);
+        let kbs_host = match settings.kbs_host {
+            Some(kbs_host) => {
+                check_configured_hosts(&kbs_host, &default_kbs_host)?;
+                kbs_host
+            }
+            None => default_kbs_host,
+        };
+
+        let client = Self::new_with_params(&kbc, &kbs_host).await?;
+        let client = match settings.retry {
+            Some(retry) => client.with_retry_policy(retry),
+            None => client,
+        };
+        let client = match settings.cache {
+            Some(cache) => client.with_cache_policy(cache),
+            None => client,
+        };
+        Ok(client)
+    }
+}
+
+/// Check that the KBS hosts `kbs_host` given by the provider settings of a
+/// sealed secret are of the `configured` ones of the `aa_kbc_params`. The
+/// provider settings of a vault secret are not authenticated, and must not
+/// point the guest to a KBS the operator has not configured.
+fn check_configured_hosts(kbs_host: &str, configured: &str) -> Result<()> {
+    let configured = failover::split_kbs_hosts(configured);
+    let kbs_hosts = failover::split_kbs_hosts(kbs_host);
+    if kbs_hosts.is_empty() {
+        return Err(KbsError::Config("no kbs host given".into()).into());
+    }
+
+    match kbs_hosts.iter().find(|host| !configured.contains(host)) {
+        Some(host) => Err(KbsError::Config(format!(
+            "kbs host {host} of the provider settings is not of the aa_kbc_params"
+        ))
+        .into()),
+        None => Ok(()),
     }
 }
 
-async fn get_aa_params_from_cmdline() -> Result<(String, String)> {
-    use tokio::fs;
-    let cmdline = fs::read_to_string("/proc/cmdline")
-        .await
-        .map_err(|e| Error::KbsClientError(format!("read kernel cmdline failed: {e}")))?;
-    let aa_kbc_params = cmdline
-        .split_ascii_whitespace()
-        .find(|para| para.starts_with("agent.aa_kbc_params="))
-        .ok_or(Error::KbsClientError(
-            "no `agent.aa_kbc_params` provided in kernel commandline!".into(),
-        ))?
-        .strip_prefix("agent.aa_kbc_params=")
-        .expect("must have a prefix")
-        .split("::")
-        .collect::<Vec<&str>>();
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::{KbcClient, KBC_POOL};
+
+    #[tokio::test]
+    async fn unknown_kbc() {
+        assert!(
+            KbcClient::new_with_params("unknown_kbc", "http://127.0.0.1:8080")
+                .await
+                .is_err()
+        );
 
-    if aa_kbc_params.len() != 2 {
-        return Err(Error::KbsClientError(
-            "Illegal `agent.aa_kbc_params` format provided in kernel commandline.".to_string(),
-        ));
+        // a failed initialization leaves no entry in the pool
+        let pool = KBC_POOL.lock().await;
+        assert!(!pool.keys().any(|k| k.kbc == "unknown_kbc"));
     }
 
-    Ok((aa_kbc_params[0].to_string(), aa_kbc_params[1].to_string()))
+    #[rstest]
+    #[case("http://kbs-1:8080", true)]
+    #[case("http://kbs-0:8080, http://kbs-1:8080", true)]
+    #[case("http://evil:8080", false)]
+    #[case("http://kbs-0:8080,http://evil:8080", false)]
+    #[case("", false)]
+    fn configured_hosts(#[case] kbs_host: &str, #[case] expected: bool) {
+        assert_eq!(
+            super::check_configured_hosts(kbs_host, "http://kbs-0:8080,http://kbs-1:8080").is_ok(),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn illegal_provider_settings() {
+        let provider_settings = json!({"kbc": 1}).as_object().unwrap().to_owned();
+        assert!(KbcClient::from_provider_settings(&provider_settings)
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn kbc_annotation() {
+        use crate::{plugins::test::MockKbs, Annotations, Getter};
+
+        let kbs = MockKbs::start().await.unwrap();
+        let other = MockKbs::start().await.unwrap();
+        kbs.set_resource("default/key/1", b"default".to_vec());
+        other.set_resource("default/key/1", b"other".to_vec());
+
+        let mut client = KbcClient::new_with_params("mock_kbc", kbs.url())
+            .await
+            .unwrap();
+        let secret = client
+            .get_secret("kbs:///default/key/1", &Annotations::default())
+            .await
+            .unwrap();
+        assert_eq!(secret, b"default");
+
+        // the kbs host of the client is always used
+        let annotations = json!({"kbc": "mock_kbc", "kbs_host": other.url()});
+        let secret = client
+            .get_secret("kbs:///default/key/1", annotations.as_object().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(secret, b"default");
+
+        for annotations in [json!({"kbc": "unknown_kbc"}), json!({"kbc": 1})] {
+            assert!(client
+                .get_secret("kbs:///default/key/1", annotations.as_object().unwrap())
+                .await
+                .is_err());
+        }
+    }
 }
@@ -0,0 +1,326 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `offline_ase_kbc` gets the resources from an attached LUKS-encrypted
+//! disk partition, for air-gapped deployments.
+//!
+//! The partition is unlocked with a key that is sealed by a KMS provider,
+//! e.g. `tpm`, and mounted read-only. A resource
+//! `kbs:///<repository>/<type>/<tag>` is the file `<repository>/<type>/<tag>`
+//! inside the filesystem of the partition. The configuration is a json file
+//! at [`OFFLINE_ASE_KBC_CONFIG_PATH`] (or the path set by the env
+//! [`OFFLINE_ASE_KBC_CONFIG_PATH_ENV`]), like
+//! ```json
+//! {
+//!     "device": "/dev/disk/by-partlabel/cdh-resources",
+//!     "sealed_key": {
+//!         "provider": "tpm",
+//!         "provider_settings": {},
+//!         "key_id": "cdh-resources",
+//!         "annotations": {},
+//!         "ciphertext": "<base64 encoded sealed key>"
+//!     }
+//! }
+//! ```
+
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+    process::Stdio,
+};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::debug;
+use resource_uri::ResourceUri;
+use serde::Deserialize;
+use tokio::{
+    fs,
+    io::{self, AsyncWrite, AsyncWriteExt},
+    process::Command,
+};
+
+use crate::{Annotations, KbsError, ProviderSettings, Result, SecretBytes};
+
+use super::Kbc;
+
+/// Default path of the config file of `offline_ase_kbc`.
+pub const OFFLINE_ASE_KBC_CONFIG_PATH: &str = "/etc/confidential-data-hub/offline_ase_kbc.json";
+
+/// Environment variable to override [`OFFLINE_ASE_KBC_CONFIG_PATH`].
+pub const OFFLINE_ASE_KBC_CONFIG_PATH_ENV: &str = "OFFLINE_ASE_KBC_CONFIG_PATH";
+
+const CRYPTSETUP_PATH: &str = "/sbin/cryptsetup";
+const MOUNT_PATH: &str = "/bin/mount";
+
+/// Name of the device mapper of the unlocked partition.
+const MAPPER_NAME: &str = "cdh-offline-ase";
+
+const DEFAULT_MOUNT_POINT: &str = "/run/confidential-containers/cdh/offline-ase";
+
+/// The key to unlock the partition, sealed by a KMS provider.
+#[derive(Deserialize)]
+struct SealedKey {
+    provider: String,
+    #[serde(default)]
+    provider_settings: ProviderSettings,
+    key_id: String,
+    #[serde(default)]
+    annotations: Annotations,
+    /// Base64 encoded sealed key.
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct OfflineAseConfig {
+    /// The LUKS-encrypted partition.
+    device: String,
+
+    #[serde(default = "default_mount_point")]
+    mount_point: String,
+
+    sealed_key: SealedKey,
+}
+
+fn default_mount_point() -> String {
+    DEFAULT_MOUNT_POINT.to_string()
+}
+
+pub struct OfflineAseKbc {
+    /// Mount point of the filesystem of the unlocked partition.
+    root: PathBuf,
+}
+
+#[async_trait]
+impl Kbc for OfflineAseKbc {
+    async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
+        let path = resource_path(&self.root, &rid)?;
+        match fs::read(&path).await {
+            Ok(resource) => Ok(resource),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(KbsError::NotFound(format!(
+                "offline-ase-kbc: resource not found {}",
+                rid.resource_path()
+            ))
+            .into()),
+            Err(e) => Err(KbsError::Internal(format!(
+                "offline-ase-kbc: read resource {} failed: {e}",
+                rid.resource_path()
+            ))
+            .into()),
+        }
+    }
+
+    /// Copy the file of the resource to the `writer` in chunks.
+    async fn write_resource(
+        &mut self,
+        rid: ResourceUri,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64> {
+        let path = resource_path(&self.root, &rid)?;
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(KbsError::NotFound(format!(
+                    "offline-ase-kbc: resource not found {}",
+                    rid.resource_path()
+                ))
+                .into())
+            }
+            Err(e) => {
+                return Err(KbsError::Internal(format!(
+                    "offline-ase-kbc: read resource {} failed: {e}",
+                    rid.resource_path()
+                ))
+                .into())
+            }
+        };
+
+        io::copy(&mut file, writer).await.map_err(|e| {
+            KbsError::Internal(format!(
+                "offline-ase-kbc: write resource {} failed: {e}",
+                rid.resource_path()
+            ))
+            .into()
+        })
+    }
+}
+
+impl OfflineAseKbc {
+    pub async fn new() -> Result<Self> {
+        let config_path = env::var(OFFLINE_ASE_KBC_CONFIG_PATH_ENV)
+            .unwrap_or_else(|_| OFFLINE_ASE_KBC_CONFIG_PATH.to_string());
+        let config = fs::read(&config_path).await.map_err(|e| {
+            KbsError::Config(format!("offline-ase-kbc: read {config_path} failed: {e}"))
+        })?;
+        let config: OfflineAseConfig = serde_json::from_slice(&config).map_err(|e| {
+            KbsError::Config(format!(
+                "offline-ase-kbc: illegal config file {config_path}: {e}"
+            ))
+        })?;
+
+        let mapper = format!("/dev/mapper/{MAPPER_NAME}");
+        if Path::new(&mapper).exists() {
+            debug!("offline-ase-kbc: {mapper} is already unlocked");
+        } else {
+            let key = unseal_key(&config.sealed_key).await?;
+            unlock(&config.device, &key).await?;
+        }
+
+        let root = PathBuf::from(&config.mount_point);
+        if !is_mounted(&root).await {
+            fs::create_dir_all(&root).await.map_err(|e| {
+                KbsError::Config(format!(
+                    "offline-ase-kbc: create mount point {} failed: {e}",
+                    config.mount_point
+                ))
+            })?;
+            let status = Command::new(MOUNT_PATH)
+                .args(["-o", "ro", &mapper, &config.mount_point])
+                .status()
+                .await
+                .map_err(|e| KbsError::Config(format!("offline-ase-kbc: run mount failed: {e}")))?;
+            if !status.success() {
+                return Err(KbsError::Config(format!(
+                    "offline-ase-kbc: mount {mapper} failed: mount exited with {status}"
+                ))
+                .into());
+            }
+        }
+
+        Ok(Self { root })
+    }
+}
+
+/// Decrypt the sealed key with its KMS provider.
+async fn unseal_key(sealed_key: &SealedKey) -> Result<SecretBytes> {
+    let ciphertext = STANDARD
+        .decode(&sealed_key.ciphertext)
+        .map_err(|e| KbsError::Config(format!("offline-ase-kbc: decode sealed key failed: {e}")))?;
+    // Boxed, as the provider can be the KBS, whose KBC can be this one.
+    let mut decryptor = Box::pin(crate::new_decryptor(
+        &sealed_key.provider,
+        sealed_key.provider_settings.clone(),
+    ))
+    .await?;
+    let key = decryptor
+        .decrypt(&ciphertext, &sealed_key.key_id, &sealed_key.annotations)
+        .await?;
+    Ok(key)
+}
+
+/// Open the LUKS `device` read-only with the `key`, which is passed via
+/// stdin so that it is never written to the disk.
+async fn unlock(device: &str, key: &[u8]) -> Result<()> {
+    let mut child = Command::new(CRYPTSETUP_PATH)
+        .args(["open", "--readonly", "--key-file=-", device, MAPPER_NAME])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| KbsError::Config(format!("offline-ase-kbc: run cryptsetup failed: {e}")))?;
+    {
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(key).await.map_err(|e| {
+            KbsError::Config(format!(
+                "offline-ase-kbc: pass key to cryptsetup failed: {e}"
+            ))
+        })?;
+    }
+
+    let status = child
+        .wait()
+        .await
+        .map_err(|e| KbsError::Config(format!("offline-ase-kbc: run cryptsetup failed: {e}")))?;
+    if !status.success() {
+        return Err(KbsError::Config(format!(
+            "offline-ase-kbc: unlock {device} failed: cryptsetup exited with {status}"
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Whether a filesystem is mounted at `path`.
+async fn is_mounted(path: &Path) -> bool {
+    let mounts = fs::read_to_string("/proc/self/mounts")
+        .await
+        .unwrap_or_default();
+    mounts
+        .lines()
+        .filter_map(|line| line.split_ascii_whitespace().nth(1))
+        .any(|mount_point| Path::new(mount_point) == path)
+}
+
+/// The path of the resource `rid` under `root`. Every part of the resource
+/// uri must be a plain file name, so that the path does not escape `root`.
+fn resource_path(root: &Path, rid: &ResourceUri) -> Result<PathBuf> {
+    let mut path = root.to_path_buf();
+    for part in [&rid.repository, &rid.r#type, &rid.tag] {
+        let mut components = Path::new(part).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => path.push(name),
+            _ => {
+                return Err(KbsError::MalformedUri(format!(
+                    "offline-ase-kbc: illegal resource path {}",
+                    rid.resource_path()
+                ))
+                .into())
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use resource_uri::ResourceUri;
+    use rstest::rstest;
+
+    use super::{resource_path, Kbc, OfflineAseKbc};
+
+    #[rstest]
+    #[case("default", "key", "1", Some("/mnt/default/key/1"))]
+    #[case("default", "..", "1", None)]
+    #[case("default", "key", "a/b", None)]
+    #[case("/etc", "key", "1", None)]
+    fn path(
+        #[case] repository: &str,
+        #[case] r#type: &str,
+        #[case] tag: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let rid = ResourceUri {
+            kbs_addr: "".into(),
+            repository: repository.into(),
+            r#type: r#type.into(),
+            tag: tag.into(),
+        };
+        let path = resource_path(Path::new("/mnt"), &rid).ok();
+        assert_eq!(path.as_deref(), expected.map(Path::new));
+    }
+
+    #[tokio::test]
+    async fn get_resource() {
+        let root = tempfile::tempdir().expect("create temp dir");
+        std::fs::create_dir_all(root.path().join("default/key")).unwrap();
+        std::fs::write(root.path().join("default/key/1"), b"secret").unwrap();
+
+        let mut kbc = OfflineAseKbc {
+            root: root.path().to_path_buf(),
+        };
+        let rid = ResourceUri::try_from("kbs:///default/key/1").unwrap();
+        assert_eq!(kbc.get_resource(rid.clone()).await.unwrap(), b"secret");
+
+        let mut written = Vec::new();
+        assert_eq!(kbc.write_resource(rid, &mut written).await.unwrap(), 6);
+        assert_eq!(written, b"secret");
+
+        let rid = ResourceUri::try_from("kbs:///default/key/2").unwrap();
+        assert!(kbc.get_resource(rid.clone()).await.is_err());
+        assert!(kbc.write_resource(rid, &mut written).await.is_err());
+    }
+}
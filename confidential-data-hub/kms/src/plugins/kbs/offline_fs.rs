@@ -4,15 +4,19 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::collections::HashMap;
+//! `offline_fs_kbc` gets the resources from json files on the local
+//! filesystem. The files are checked at every request, and the resources
+//! are reloaded once a file is modified, e.g. rotated by a sidecar.
+
+use std::{collections::HashMap, time::SystemTime};
 
 use async_trait::async_trait;
 use base64::{engine::general_purpose::STANDARD, Engine};
-use log::warn;
+use log::{debug, warn};
 use resource_uri::ResourceUri;
 use tokio::fs;
 
-use crate::{Error, Result};
+use crate::{KbsError, Result};
 
 use super::Kbc;
 
@@ -20,6 +24,12 @@ const KEYS_PATH: &str = "/etc/aa-offline_fs_kbc-keys.json";
 const RESOURCES_PATH: &str = "/etc/aa-offline_fs_kbc-resources.json";
 
 pub struct OfflineFsKbc {
+    /// Files to load the resources from
+    paths: Vec<String>,
+
+    /// Modification times of the `paths` when the resources were loaded
+    mtimes: Vec<SystemTime>,
+
     /// Stored resources, loaded from file system
     resources: HashMap<String, Vec<u8>>,
 }
@@ -27,45 +37,163 @@ pub struct OfflineFsKbc {
 #[async_trait]
 impl Kbc for OfflineFsKbc {
     async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
+        self.reload_if_modified().await;
+
         let resource_path = rid.resource_path();
-        self.resources
+        let resource = self
+            .resources
             .get(&resource_path)
-            .ok_or(Error::KbsClientError(format!(
+            .ok_or(KbsError::NotFound(format!(
                 "offline-fs-kbc: resource not found {resource_path}"
-            )))
-            .cloned()
+            )))?;
+        Ok(resource.clone())
     }
 }
 
 impl OfflineFsKbc {
     pub async fn new() -> Result<Self> {
-        let mut res = Self {
-            resources: HashMap::new(),
+        Self::with_files(&[KEYS_PATH, RESOURCES_PATH]).await
+    }
+
+    async fn with_files(paths: &[&str]) -> Result<Self> {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        let mtimes = mtimes(&paths).await?;
+        let resources = load(&paths).await?;
+
+        Ok(Self {
+            paths,
+            mtimes,
+            resources,
+        })
+    }
+
+    /// Reload the resources if any of the files is modified since the last
+    /// load. If the reload fails, e.g. a file is being rewritten, the old
+    /// resources are kept and the reload is tried again at the next request.
+    async fn reload_if_modified(&mut self) {
+        let mtimes = match mtimes(&self.paths).await {
+            Ok(mtimes) => mtimes,
+            Err(e) => {
+                warn!(
+                    "offline-fs-kbc: check resource files failed, keep the loaded resources: {e}"
+                );
+                return;
+            }
         };
+        if mtimes == self.mtimes {
+            return;
+        }
 
-        res.init_with_file(KEYS_PATH).await?;
-        res.init_with_file(RESOURCES_PATH).await?;
+        match load(&self.paths).await {
+            Ok(resources) => {
+                debug!("offline-fs-kbc: resource files are modified, reloaded");
+                self.resources = resources;
+                self.mtimes = mtimes;
+            }
+            Err(e) => {
+                warn!(
+                    "offline-fs-kbc: reload resource files failed, keep the loaded resources: {e}"
+                )
+            }
+        }
+    }
+}
 
-        Ok(res)
+async fn mtimes(paths: &[String]) -> Result<Vec<SystemTime>> {
+    let mut mtimes = Vec::with_capacity(paths.len());
+    for path in paths {
+        let mtime = fs::metadata(path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| KbsError::Config(format!("offline-fs-kbc: stat {path} failed: {e}")))?;
+        mtimes.push(mtime);
     }
 
-    async fn init_with_file(&mut self, path: &str) -> Result<()> {
-        let file = fs::read(path).await.map_err(|e| {
-            Error::KbsClientError(format!("offline-fs-kbc: read {path} failed: {e}"))
-        })?;
-        let map: HashMap<String, String> = serde_json::from_slice(&file).map_err(|e| {
-            Error::KbsClientError(format!("offline-fs-kbc: illegal resource file {path}: {e}"))
+    Ok(mtimes)
+}
+
+async fn load(paths: &[String]) -> Result<HashMap<String, Vec<u8>>> {
+    let mut resources = HashMap::new();
+    for path in paths {
+        load_file(&mut resources, path).await?;
+    }
+
+    Ok(resources)
+}
+
+async fn load_file(resources: &mut HashMap<String, Vec<u8>>, path: &str) -> Result<()> {
+    let file = fs::read(path)
+        .await
+        .map_err(|e| KbsError::Config(format!("offline-fs-kbc: read {path} failed: {e}")))?;
+    let map: HashMap<String, String> = serde_json::from_slice(&file).map_err(|e| {
+        KbsError::Config(format!("offline-fs-kbc: illegal resource file {path}: {e}"))
+    })?;
+    for (k, v) in &map {
+        let value = STANDARD.decode(v).map_err(|e| {
+            KbsError::Config(format!(
+                "offline-fs-kbc: decode value from file {path} failed: {e}"
+            ))
         })?;
-        for (k, v) in &map {
-            let value = STANDARD.decode(v).map_err(|e| {
-                Error::KbsClientError(format!(
-                    "offline-fs-kbc: decode value from file {path} failed: {e}"
-                ))
-            })?;
-            if self.resources.insert(k.to_owned(), value).is_some() {
-                warn!("detected duplicated resource definition {k} in file {path} when initializing offline-fs-kbc");
-            }
+        if resources.insert(k.to_owned(), value).is_some() {
+            warn!("detected duplicated resource definition {k} in file {path} when initializing offline-fs-kbc");
         }
-        Ok(())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use resource_uri::ResourceUri;
+
+    use super::{Kbc, OfflineFsKbc};
+
+    #[tokio::test]
+    async fn set_resource_unsupported() {
+        let mut kbc = OfflineFsKbc {
+            paths: Vec::new(),
+            mtimes: Vec::new(),
+            resources: HashMap::new(),
+        };
+        let rid = ResourceUri::try_from("kbs:///default/key/1").unwrap();
+        assert!(kbc.set_resource(rid, b"test".to_vec()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let path = dir.path().join("resources.json");
+        let write = |content: &[u8], mtime: SystemTime| {
+            std::fs::write(&path, content).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        };
+        let now = SystemTime::now();
+        write(br#"{"default/key/1": "dGVzdA=="}"#, now);
+
+        let mut kbc = OfflineFsKbc::with_files(&[path.to_str().unwrap()])
+            .await
+            .expect("create kbc");
+        let rid = ResourceUri::try_from("kbs:///default/key/1").unwrap();
+        assert_eq!(kbc.get_resource(rid.clone()).await.unwrap(), b"test");
+
+        // a resource file being rewritten keeps the loaded resources
+        write(br#"{"default/key/1": "#, now + Duration::from_secs(1));
+        assert_eq!(kbc.get_resource(rid.clone()).await.unwrap(), b"test");
+
+        write(
+            br#"{"default/key/1": "cm90YXRlZA=="}"#,
+            now + Duration::from_secs(2),
+        );
+        assert_eq!(kbc.get_resource(rid).await.unwrap(), b"rotated");
     }
 }
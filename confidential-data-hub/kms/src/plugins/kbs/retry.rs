@@ -0,0 +1,175 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Retry policy of getting resources from the KBS, so that a short outage of
+//! the KBS during the startup of a pod does not fail the whole workload.
+//!
+//! The delay before the n-th retry is chosen randomly from
+//! `[0, min(backoff_max_ms, backoff_base_ms * 2^(n-1))]` ("full jitter").
+
+use std::{future::Future, time::Duration};
+
+use log::warn;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{Error, KbsError, Result};
+
+/// Default max attempts, including the first one.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default base delay of the exponential backoff in milliseconds.
+pub const DEFAULT_BACKOFF_BASE_MS: u64 = 500;
+
+/// Default upper bound of a single backoff delay in milliseconds.
+pub const DEFAULT_BACKOFF_MAX_MS: u64 = 10_000;
+
+/// Default timeout of a single attempt in seconds.
+pub const DEFAULT_ATTEMPT_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Max attempts, including the first one. `1` means no retry.
+    pub max_attempts: u32,
+
+    /// Base delay of the exponential backoff in milliseconds
+    pub backoff_base_ms: u64,
+
+    /// Upper bound of a single backoff delay in milliseconds
+    pub backoff_max_ms: u64,
+
+    /// Timeout of a single attempt in seconds. `0` means no timeout.
+    pub attempt_timeout_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backoff_base_ms: DEFAULT_BACKOFF_BASE_MS,
+            backoff_max_ms: DEFAULT_BACKOFF_MAX_MS,
+            attempt_timeout_secs: DEFAULT_ATTEMPT_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The upper bound of the delay before the `retry`-th retry (start from 1).
+    fn backoff_cap(&self, retry: u32) -> u64 {
+        let exp = 1u64
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        self.backoff_base_ms
+            .saturating_mul(exp)
+            .min(self.backoff_max_ms)
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        let cap = self.backoff_cap(retry);
+        Duration::from_millis(rand::thread_rng().gen_range(0..=cap))
+    }
+
+    /// Run a single attempt with the per-attempt timeout.
+    pub(crate) async fn attempt<T>(&self, f: impl Future<Output = Result<T>>) -> Result<T> {
+        if self.attempt_timeout_secs == 0 {
+            return f.await;
+        }
+
+        tokio::time::timeout(Duration::from_secs(self.attempt_timeout_secs), f)
+            .await
+            .map_err(|_| {
+                KbsError::Network(format!(
+                    "attempt timeout after {}s",
+                    self.attempt_timeout_secs
+                ))
+            })?
+    }
+
+    /// Decide whether to retry after the `attempt`-th attempt (start from 1)
+    /// failed. If so, wait for the backoff delay and return `true`. Errors
+    /// that are not retryable are never retried.
+    pub(crate) async fn wait_for_retry(&self, attempt: u32, e: &Error) -> bool {
+        if attempt >= self.max_attempts || !e.is_retryable() {
+            return false;
+        }
+
+        let delay = self.backoff(attempt);
+        warn!(
+            "attempt {attempt}/{} failed: {e}, retry in {}ms",
+            self.max_attempts,
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use crate::{Error, KbsError};
+
+    use super::RetryPolicy;
+
+    #[rstest]
+    #[case(1, 500)]
+    #[case(2, 1000)]
+    #[case(3, 2000)]
+    #[case(6, 10_000)]
+    #[case(100, 10_000)]
+    fn backoff_cap(#[case] retry: u32, #[case] expected: u64) {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff_cap(retry), expected);
+        assert!(policy.backoff(retry) <= Duration::from_millis(expected));
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            backoff_base_ms: 1,
+            backoff_max_ms: 1,
+            attempt_timeout_secs: 1,
+        };
+
+        let mut attempt = 0;
+        let res: Result<(), Error> = loop {
+            attempt += 1;
+            let e = Error::KbsClientError(KbsError::Network("transient".into()));
+            if !policy.wait_for_retry(attempt, &e).await {
+                break Err(e);
+            }
+        };
+        assert!(res.is_err());
+        assert_eq!(attempt, 3);
+
+        let e = Error::KbsClientError(KbsError::NotFound("kbs:///default/key/1".into()));
+        assert!(!policy.wait_for_retry(1, &e).await);
+
+        let res = policy
+            .attempt(async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn deserialize() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"max_attempts": 5}"#).unwrap();
+        assert_eq!(
+            policy,
+            RetryPolicy {
+                max_attempts: 5,
+                ..Default::default()
+            }
+        );
+    }
+}
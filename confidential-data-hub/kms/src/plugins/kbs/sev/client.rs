@@ -11,18 +11,26 @@ use base64::{engine::general_purpose::STANDARD, Engine};
 use crypto::WrapType;
 use resource_uri::ResourceUri;
 use serde::Deserialize;
-use tokio::fs;
 use tonic::transport::Uri;
-use uuid::Uuid;
+use uuid::{uuid, Uuid};
 use zeroize::Zeroizing;
 
-use crate::{plugins::kbs::Kbc, Error, Result};
+use crate::{plugins::kbs::Kbc, KbsError, Result};
 
-use super::keybroker::{
-    key_broker_service_client::KeyBrokerServiceClient, OnlineSecretRequest, RequestDetails,
+use super::{
+    keybroker::{
+        key_broker_service_client::KeyBrokerServiceClient, OnlineSecretRequest, RequestDetails,
+    },
+    secret_table::SecretTable,
 };
 
-const KEYS_PATH: &str = "/sys/kernel/security/secrets/coco/1ee27366-0c87-43a6-af48-28543eaf7cb0";
+/// GUID of the entry of the secret table that contains the [`Connection`].
+const CONNECTION_GUID: Uuid = uuid!("1ee27366-0c87-43a6-af48-28543eaf7cb0");
+
+/// The resource type of the entries of the secret table. The tag of such a
+/// resource is the GUID of the entry, e.g.
+/// `kbs:///default/sev-secret/e6f5a162-d67f-4750-a67c-5d065f2a9910`.
+const SEV_SECRET_TYPE: &str = "sev-secret";
 
 #[derive(Deserialize, Clone)]
 struct Connection {
@@ -34,37 +42,58 @@ pub struct OnlineSevKbc {
     client_id: Uuid,
     key: Vec<u8>,
     kbs_uri: Uri,
+
+    /// The other entries of the secret table, injected together with the
+    /// connection before the attestation.
+    secrets: SecretTable,
 }
 
 impl OnlineSevKbc {
     pub async fn new(kbs_uri: &str) -> Result<Self> {
-        let connection_json = fs::read_to_string(KEYS_PATH)
-            .await
-            .map_err(|e| Error::KbsClientError(format!("online-sev-kbc: Read keys failed: {e}")))?;
-        fs::remove_file(KEYS_PATH)
-            .await
-            .expect("Failed to remove secret file");
+        let mut secrets = SecretTable::load().await?;
+        let connection_json = secrets.take(&CONNECTION_GUID).ok_or_else(|| {
+            KbsError::Config(format!(
+                "online-sev-kbc: no connection entry {CONNECTION_GUID} in sev secret table"
+            ))
+        })?;
 
-        let connection: Connection = serde_json::from_str(&connection_json).map_err(|e| {
-            Error::KbsClientError(format!("online-sev-kbc: deserialze keys failed: {e}"))
+        let connection: Connection = serde_json::from_slice(&connection_json).map_err(|e| {
+            KbsError::Config(format!("online-sev-kbc: deserialze keys failed: {e}"))
         })?;
 
         let key = STANDARD.decode(connection.key).map_err(|e| {
-            Error::KbsClientError(format!(
+            KbsError::Config(format!(
                 "online-sev-kbc: base64 decode connection key failed: {e}"
             ))
         })?;
 
-        let kbs_uri = format!("http://{kbs_uri}").parse::<Uri>().map_err(|e| {
-            Error::KbsClientError(format!("online-sev-kbc: parse kbs uri failed: {e}"))
-        })?;
+        let kbs_uri = format!("http://{kbs_uri}")
+            .parse::<Uri>()
+            .map_err(|e| KbsError::Config(format!("online-sev-kbc: parse kbs uri failed: {e}")))?;
         Ok(Self {
             client_id: connection.client_id,
             key,
             kbs_uri,
+            secrets,
         })
     }
 
+    /// Get the entry of the secret table given by the tag of `rid`.
+    fn get_injected_secret(&self, rid: &ResourceUri) -> Result<Vec<u8>> {
+        let guid: Uuid = rid.tag.parse().map_err(|e| {
+            KbsError::MalformedUri(format!(
+                "online-sev-kbc: tag {} of a {SEV_SECRET_TYPE} is not a guid: {e}",
+                rid.tag
+            ))
+        })?;
+        let secret = self.secrets.get(&guid).ok_or_else(|| {
+            KbsError::NotFound(format!(
+                "online-sev-kbc: no entry {guid} in sev secret table"
+            ))
+        })?;
+        Ok(secret.to_vec())
+    }
+
     async fn get_resource_from_kbs(
         &self,
         resource_uri: ResourceUri,
@@ -90,36 +119,34 @@ impl OnlineSevKbc {
             .get_online_secret(request)
             .await
             .map_err(|e| {
-                Error::KbsClientError(format!("online-sev-kbc: sev get online secret failed: {e}"))
+                KbsError::Network(format!("online-sev-kbc: sev get online secret failed: {e}"))
             })?
             .into_inner();
         let decrypted_payload = crypto::decrypt(
             Zeroizing::new(self.key.clone()),
             STANDARD.decode(response.payload).map_err(|e| {
-                Error::KbsClientError(format!(
+                KbsError::Internal(format!(
                     "online-sev-kbc: base64 decode response.payload failed: {e}"
                 ))
             })?,
             STANDARD.decode(response.iv).map_err(|e| {
-                Error::KbsClientError(format!(
+                KbsError::Internal(format!(
                     "online-sev-kbc: base64 decode response.iv failed: {e}"
                 ))
             })?,
             WrapType::Aes256Gcm,
         )
-        .map_err(|e| {
-            Error::KbsClientError(format!("online-sev-kbc: decrypt payload failed: {e}"))
-        })?;
+        .map_err(|e| KbsError::Internal(format!("online-sev-kbc: decrypt payload failed: {e}")))?;
 
         let payload_dict: HashMap<String, Vec<u8>> = bincode::deserialize(&decrypted_payload)
             .map_err(|e| {
-                Error::KbsClientError(format!(
+                KbsError::Internal(format!(
                     "online-sev-kbc: deserailize payload dictionary failed: {e}"
                 ))
             })?;
         let res = payload_dict
             .get(&guid)
-            .ok_or(Error::KbsClientError(format!(
+            .ok_or(KbsError::NotFound(format!(
                 "online-sev-kbc: No guid {guid} found in the returned payload dictionary."
             )))?
             .to_vec();
@@ -133,6 +160,7 @@ impl Kbc for OnlineSevKbc {
     async fn get_resource(&mut self, rid: ResourceUri) -> Result<Vec<u8>> {
         match &rid.r#type[..] {
             "client-id" => Ok(self.client_id.hyphenated().to_string().into_bytes()),
+            SEV_SECRET_TYPE => self.get_injected_secret(&rid),
             _ => self.get_resource_from_kbs(rid, "resource").await,
         }
     }
@@ -11,4 +11,7 @@ mod client;
 #[rustfmt::skip]
 mod keybroker;
 
+mod secret_table;
+pub use secret_table::{SECRETS_DIR, SEV_SECRET_TABLE_PATH_ENV};
+
 pub use client::OnlineSevKbc;
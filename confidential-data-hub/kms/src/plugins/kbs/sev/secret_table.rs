@@ -0,0 +1,198 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! The secret table injected by the SEV firmware before the attestation.
+//!
+//! OVMF reserves a secret area in the guest memory, in which the guest owner
+//! injects a GUID table of secrets, see
+//! <https://www.kernel.org/doc/html/next/security/secrets/coco.html>. The
+//! layout of the table is
+//! ```text
+//! | header guid (16) | total length (4) | entry 1 | entry 2 | ...
+//! ```
+//! and the layout of an entry is
+//! ```text
+//! | guid (16) | length (4), including the guid and the length | data |
+//! ```
+//! All the integers are little-endian. The `efi_secret` kernel module
+//! exposes every entry as a file named by its GUID under
+//! [`SECRETS_DIR`]. Alternatively the raw table can be given by the env
+//! [`SEV_SECRET_TABLE_PATH_ENV`].
+
+use std::{collections::HashMap, env, path::Path};
+
+use log::debug;
+use tokio::fs;
+use uuid::{uuid, Uuid};
+use zeroize::Zeroizing;
+
+use crate::{KbsError, Result};
+
+/// Directory where the `efi_secret` kernel module exposes the entries.
+pub const SECRETS_DIR: &str = "/sys/kernel/security/secrets/coco";
+
+/// Environment variable to give a file of the raw secret table to read
+/// instead of [`SECRETS_DIR`].
+pub const SEV_SECRET_TABLE_PATH_ENV: &str = "SEV_SECRET_TABLE_PATH";
+
+/// GUID of the header of the secret table.
+const SECRET_TABLE_HEADER_GUID: Uuid = uuid!("1e74f542-71dd-4d66-963e-ef4287ff173b");
+
+/// Size of the GUID and the length field of the header and every entry.
+const HEADER_SIZE: usize = 20;
+
+pub(crate) struct SecretTable {
+    entries: HashMap<Uuid, Zeroizing<Vec<u8>>>,
+}
+
+impl SecretTable {
+    /// Read all the entries of the injected secret table. The entries
+    /// exposed by the `efi_secret` module are removed once read, so that
+    /// the secrets are wiped from the guest memory.
+    pub async fn load() -> Result<Self> {
+        if let Ok(path) = env::var(SEV_SECRET_TABLE_PATH_ENV) {
+            debug!("read sev secret table from {path}");
+            let table = Zeroizing::new(fs::read(&path).await.map_err(|e| {
+                KbsError::Config(format!("read sev secret table {path} failed: {e}"))
+            })?);
+            return Self::parse(&table);
+        }
+
+        Self::from_dir(SECRETS_DIR).await
+    }
+
+    async fn from_dir(dir: &str) -> Result<Self> {
+        let mut entries = HashMap::new();
+        let mut dir_entries = fs::read_dir(dir)
+            .await
+            .map_err(|e| KbsError::Config(format!("read sev secrets dir {dir} failed: {e}")))?;
+        while let Some(entry) = dir_entries
+            .next_entry()
+            .await
+            .map_err(|e| KbsError::Config(format!("read sev secrets dir {dir} failed: {e}")))?
+        {
+            let guid = match entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                Some(guid) => guid,
+                None => continue,
+            };
+            let path = entry.path();
+            let data = Zeroizing::new(read_entry(&path).await?);
+            fs::remove_file(&path).await.map_err(|e| {
+                KbsError::Config(format!("remove sev secret {} failed: {e}", path.display()))
+            })?;
+            entries.insert(guid, data);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Parse the raw secret table.
+    pub fn parse(table: &[u8]) -> Result<Self> {
+        let (guid, total_length) = parse_header(table)
+            .ok_or_else(|| KbsError::Config("sev secret table is too short".into()))?;
+        if guid != SECRET_TABLE_HEADER_GUID {
+            return Err(
+                KbsError::Config(format!("illegal sev secret table header guid {guid}")).into(),
+            );
+        }
+        if total_length < HEADER_SIZE {
+            return Err(KbsError::Config(format!(
+                "illegal sev secret table length {total_length}"
+            ))
+            .into());
+        }
+        let table = table.get(..total_length).ok_or_else(|| {
+            KbsError::Config(format!(
+                "sev secret table length {total_length} exceeds the given {} bytes",
+                table.len()
+            ))
+        })?;
+
+        let mut entries = HashMap::new();
+        let mut rest = &table[HEADER_SIZE..];
+        while !rest.is_empty() {
+            let (guid, length) = parse_header(rest)
+                .ok_or_else(|| KbsError::Config("truncated entry in sev secret table".into()))?;
+            if length < HEADER_SIZE || length > rest.len() {
+                return Err(KbsError::Config(format!(
+                    "illegal length {length} of entry {guid} in sev secret table"
+                ))
+                .into());
+            }
+            entries.insert(guid, Zeroizing::new(rest[HEADER_SIZE..length].to_vec()));
+            rest = &rest[length..];
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Take the entry of `guid` out of the table.
+    pub fn take(&mut self, guid: &Uuid) -> Option<Zeroizing<Vec<u8>>> {
+        self.entries.remove(guid)
+    }
+
+    pub fn get(&self, guid: &Uuid) -> Option<&[u8]> {
+        self.entries.get(guid).map(|data| &data[..])
+    }
+}
+
+async fn read_entry(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).await.map_err(|e| {
+        KbsError::Config(format!("read sev secret {} failed: {e}", path.display())).into()
+    })
+}
+
+/// Parse the GUID and the length at the beginning of `data`.
+fn parse_header(data: &[u8]) -> Option<(Uuid, usize)> {
+    let guid: [u8; 16] = data.get(..16)?.try_into().ok()?;
+    let length: [u8; 4] = data.get(16..HEADER_SIZE)?.try_into().ok()?;
+    Some((
+        Uuid::from_bytes_le(guid),
+        u32::from_le_bytes(length) as usize,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::{uuid, Uuid};
+
+    use super::{SecretTable, HEADER_SIZE, SECRET_TABLE_HEADER_GUID};
+
+    fn entry(guid: Uuid, data: &[u8]) -> Vec<u8> {
+        let mut entry = guid.to_bytes_le().to_vec();
+        entry.extend_from_slice(&((HEADER_SIZE + data.len()) as u32).to_le_bytes());
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    #[test]
+    fn parse() {
+        let first = uuid!("1ee27366-0c87-43a6-af48-28543eaf7cb0");
+        let second = uuid!("e6f5a162-d67f-4750-a67c-5d065f2a9910");
+        let entries = [entry(first, b"connection"), entry(second, b"secret")].concat();
+        let mut table = entry(SECRET_TABLE_HEADER_GUID, &entries);
+        // the secret area is padded after the table
+        table.extend_from_slice(&[0; 32]);
+
+        let mut table = SecretTable::parse(&table).expect("parse table");
+        assert_eq!(table.get(&second), Some(&b"secret"[..]));
+        assert_eq!(table.take(&first).as_deref(), Some(&b"connection".to_vec()));
+        assert_eq!(table.get(&first), None);
+    }
+
+    #[test]
+    fn illegal_table() {
+        assert!(SecretTable::parse(b"short").is_err());
+
+        let table = entry(uuid!("e6f5a162-d67f-4750-a67c-5d065f2a9910"), b"");
+        assert!(SecretTable::parse(&table).is_err());
+
+        // the entry claims more bytes than the table has
+        let mut entries = entry(uuid!("e6f5a162-d67f-4750-a67c-5d065f2a9910"), b"secret");
+        entries.truncate(HEADER_SIZE + 2);
+        let table = entry(SECRET_TABLE_HEADER_GUID, &entries);
+        assert!(SecretTable::parse(&table).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! TLS configuration of the connections from `cc_kbc` to the KBS.
+//!
+//! A custom root CA bundle, and a client certificate and key for mutual TLS
+//! can be given, so that the KBS can be fronted by an internal PKI. All the
+//! values are paths to PEM files inside the guest. They are looked up from
+//! the following sources in order, and the first source that exists wins:
+//! 0. The [`kbs_tls`](crate::Settings::kbs_tls) setting, e.g. from the
+//!    config file of CDH.
+//! 1. A config file in json format at [`KBS_TLS_CONFIG_PATH`] (or the path
+//!    set by the env [`KBS_TLS_CONFIG_PATH_ENV`]), like
+//!    ```json
+//!    {
+//!        "root_ca": "/etc/kbs/ca.pem",
+//!        "client_cert": "/etc/kbs/client.pem",
+//!        "client_key": "/etc/kbs/client.key"
+//!    }
+//!    ```
+//! 2. The `agent.kbs_root_ca`, `agent.kbs_client_cert` and
+//!    `agent.kbs_client_key` parameters of the kernel commandline.
+
+use std::{env, path::Path};
+
+use log::debug;
+use serde::Deserialize;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+use crate::{settings, KbsError, KbsTlsPaths, Result};
+
+/// Default path of the config file that contains the KBS TLS configuration.
+pub const KBS_TLS_CONFIG_PATH: &str = "/etc/confidential-data-hub/kbs_tls.json";
+
+/// Environment variable to override [`KBS_TLS_CONFIG_PATH`].
+pub const KBS_TLS_CONFIG_PATH_ENV: &str = "KBS_TLS_CONFIG_PATH";
+
+const KERNEL_CMDLINE_PATH: &str = "/proc/cmdline";
+
+const PEM_CERT_END: &str = "-----END CERTIFICATE-----";
+
+/// Paths of the PEM files used to connect to the KBS.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+struct KbsTlsConfigPaths {
+    #[serde(default)]
+    root_ca: Option<String>,
+    #[serde(default)]
+    client_cert: Option<String>,
+    #[serde(default)]
+    client_key: Option<String>,
+}
+
+/// The loaded TLS configuration of the connections to the KBS.
+pub(crate) struct KbsTlsConfig {
+    /// Custom root certificates in PEM format, one certificate per item.
+    pub root_certs: Vec<String>,
+
+    /// Client certificate and private key in PEM format for mutual TLS.
+    pub client_identity: Option<(String, Zeroizing<String>)>,
+}
+
+/// Get the [`KbsTlsConfig`] from the layered configuration sources. If no
+/// source gives one, the default configuration is returned, i.e. only the
+/// publicly trusted roots are used and no client certificate is sent.
+pub(crate) async fn get_kbs_tls_config() -> Result<KbsTlsConfig> {
+    if let Some(paths) = settings::settings().kbs_tls {
+        debug!("get kbs tls config from settings");
+        return load(paths.into()).await;
+    }
+
+    let config_path =
+        env::var(KBS_TLS_CONFIG_PATH_ENV).unwrap_or_else(|_| KBS_TLS_CONFIG_PATH.to_string());
+    let paths = match from_config_file(&config_path).await? {
+        Some(paths) => {
+            debug!("get kbs tls config from config file {config_path}");
+            paths
+        }
+        None => from_cmdline(KERNEL_CMDLINE_PATH).await?,
+    };
+
+    load(paths).await
+}
+
+impl From<KbsTlsPaths> for KbsTlsConfigPaths {
+    fn from(paths: KbsTlsPaths) -> Self {
+        Self {
+            root_ca: paths.root_ca,
+            client_cert: paths.client_cert,
+            client_key: paths.client_key,
+        }
+    }
+}
+
+/// Read the [`KbsTlsConfigPaths`] from the given config file. If the file
+/// does not exist, `None` will be returned.
+async fn from_config_file(path: &str) -> Result<Option<KbsTlsConfigPaths>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read(path)
+        .await
+        .map_err(|e| KbsError::Config(format!("read kbs tls config file {path} failed: {e}")))?;
+    let config = serde_json::from_slice(&content)
+        .map_err(|e| KbsError::Config(format!("illegal kbs tls config file {path}: {e}")))?;
+
+    Ok(Some(config))
+}
+
+/// Read the [`KbsTlsConfigPaths`] from the given kernel commandline file.
+/// Missing parameters are left empty.
+async fn from_cmdline(path: &str) -> Result<KbsTlsConfigPaths> {
+    let cmdline = fs::read_to_string(path)
+        .await
+        .map_err(|e| KbsError::Config(format!("read kernel cmdline failed: {e}")))?;
+    let param = |name: &str| {
+        cmdline
+            .split_ascii_whitespace()
+            .find_map(|para| para.strip_prefix(name))
+            .map(str::to_string)
+    };
+
+    Ok(KbsTlsConfigPaths {
+        root_ca: param("agent.kbs_root_ca="),
+        client_cert: param("agent.kbs_client_cert="),
+        client_key: param("agent.kbs_client_key="),
+    })
+}
+
+/// Read the PEM files of the given [`KbsTlsConfigPaths`].
+async fn load(paths: KbsTlsConfigPaths) -> Result<KbsTlsConfig> {
+    let root_certs = match paths.root_ca {
+        Some(path) => {
+            let bundle = fs::read_to_string(&path)
+                .await
+                .map_err(|e| KbsError::Config(format!("read kbs root ca {path} failed: {e}")))?;
+            split_pem_bundle(&bundle)
+        }
+        None => Vec::new(),
+    };
+
+    let client_identity = match (paths.client_cert, paths.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = fs::read_to_string(&cert_path).await.map_err(|e| {
+                KbsError::Config(format!("read kbs client cert {cert_path} failed: {e}"))
+            })?;
+            let key = Zeroizing::new(fs::read_to_string(&key_path).await.map_err(|e| {
+                KbsError::Config(format!("read kbs client key {key_path} failed: {e}"))
+            })?);
+            Some((cert, key))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(KbsError::Config(
+                "kbs client cert and client key must be given together".into(),
+            )
+            .into())
+        }
+    };
+
+    Ok(KbsTlsConfig {
+        root_certs,
+        client_identity,
+    })
+}
+
+/// Split a PEM bundle into the single certificates inside it.
+fn split_pem_bundle(bundle: &str) -> Vec<String> {
+    bundle
+        .split_inclusive(PEM_CERT_END)
+        .filter(|cert| cert.contains(PEM_CERT_END))
+        .map(|cert| cert.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{from_cmdline, from_config_file, load, split_pem_bundle, KbsTlsConfigPaths};
+
+    #[test]
+    fn pem_bundle() {
+        let bundle = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+            -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+        let certs = split_pem_bundle(bundle);
+        assert_eq!(
+            certs,
+            vec![
+                "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----",
+                "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----",
+            ]
+        );
+        assert!(split_pem_bundle("").is_empty());
+    }
+
+    #[tokio::test]
+    async fn config_file() {
+        let mut file = tempfile::NamedTempFile::new().expect("create temp file");
+        file.write_all(br#"{"root_ca": "/etc/kbs/ca.pem"}"#)
+            .expect("write temp file");
+
+        let paths = from_config_file(file.path().to_str().unwrap())
+            .await
+            .expect("read config file");
+        assert_eq!(
+            paths,
+            Some(KbsTlsConfigPaths {
+                root_ca: Some("/etc/kbs/ca.pem".into()),
+                ..Default::default()
+            })
+        );
+
+        let paths = from_config_file("/this/config/does/not/exist")
+            .await
+            .expect("read non-existing config file");
+        assert_eq!(paths, None);
+    }
+
+    #[tokio::test]
+    async fn kernel_cmdline() {
+        let mut file = tempfile::NamedTempFile::new().expect("create temp file");
+        file.write_all(
+            b"console=hvc0 agent.kbs_client_cert=/etc/kbs/client.pem agent.kbs_client_key=/etc/kbs/client.key",
+        )
+        .expect("write temp file");
+
+        let paths = from_cmdline(file.path().to_str().unwrap())
+            .await
+            .expect("read cmdline");
+        assert_eq!(
+            paths,
+            KbsTlsConfigPaths {
+                root_ca: None,
+                client_cert: Some("/etc/kbs/client.pem".into()),
+                client_key: Some("/etc/kbs/client.key".into()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn client_cert_without_key() {
+        let paths = KbsTlsConfigPaths {
+            client_cert: Some("/etc/kbs/client.pem".into()),
+            ..Default::default()
+        };
+        assert!(load(paths).await.is_err());
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Background refresh of the attestation tokens of `cc_kbc`.
+//!
+//! The token got from the KBS expires. Without a refresh the next request
+//! after the expiry fails and pays the full attestation cost. A background
+//! task renews the token [`REFRESH_MARGIN`] before it expires, and the
+//! results are counted in [`TokenRefreshMetrics`].
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Weak,
+    },
+    time::Duration,
+};
+
+use kbs_protocol::{
+    client::KbsClient,
+    token_provider::{Token, TokenProvider},
+};
+use log::{debug, warn};
+use tokio::sync::Mutex;
+use tracing::Instrument;
+
+/// The token is renewed this long before it expires.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// How long to wait before checking again when there is no token to renew,
+/// or to retry after a failed refresh.
+const IDLE_INTERVAL: Duration = Duration::from_secs(10);
+
+static REFRESHES: AtomicU64 = AtomicU64::new(0);
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+static CONSECUTIVE_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Counters of the background token refreshes of all the `cc_kbc` instances.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenRefreshMetrics {
+    /// Number of successful refreshes.
+    pub refreshes: u64,
+
+    /// Number of failed refreshes.
+    pub failures: u64,
+
+    /// Number of failed refreshes since the last successful one.
+    pub consecutive_failures: u64,
+}
+
+/// Get the current [`TokenRefreshMetrics`].
+pub fn token_refresh_metrics() -> TokenRefreshMetrics {
+    TokenRefreshMetrics {
+        refreshes: REFRESHES.load(Ordering::Relaxed),
+        failures: FAILURES.load(Ordering::Relaxed),
+        consecutive_failures: CONSECUTIVE_FAILURES.load(Ordering::Relaxed),
+    }
+}
+
+type Client = KbsClient<Box<dyn TokenProvider>>;
+
+/// Spawn the task to refresh the token of the `client`. The task exits when
+/// the client is dropped.
+pub(crate) fn spawn(weak: Weak<Mutex<Client>>) {
+    tokio::spawn(async move {
+        let mut delay = IDLE_INTERVAL;
+        loop {
+            tokio::time::sleep(delay).await;
+            let client = match weak.upgrade() {
+                Some(client) => client,
+                None => {
+                    debug!("kbs client dropped, stop refreshing token");
+                    return;
+                }
+            };
+
+            let mut client = client.lock().await;
+            let expires_in = client.token().and_then(Token::expires_in);
+            if !matches!(expires_in, Some(expires_in) if expires_in <= REFRESH_MARGIN) {
+                delay = next_delay(expires_in);
+                continue;
+            }
+
+            let refresh = client
+                .refresh_token()
+                .instrument(tracing::info_span!("refresh_kbs_token"));
+            delay = match refresh.await {
+                Ok(()) => {
+                    REFRESHES.fetch_add(1, Ordering::Relaxed);
+                    CONSECUTIVE_FAILURES.store(0, Ordering::Relaxed);
+                    next_delay(client.token().and_then(Token::expires_in))
+                }
+                Err(e) => {
+                    FAILURES.fetch_add(1, Ordering::Relaxed);
+                    let failures = CONSECUTIVE_FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+                    warn!("refresh kbs token failed ({failures} times in a row): {e}");
+                    IDLE_INTERVAL
+                }
+            };
+        }
+    });
+}
+
+/// How long to wait before checking the token that expires in `expires_in`
+/// again. A token that is not got yet or never expires is checked again
+/// after [`IDLE_INTERVAL`].
+fn next_delay(expires_in: Option<Duration>) -> Duration {
+    match expires_in {
+        Some(expires_in) if expires_in > REFRESH_MARGIN => expires_in - REFRESH_MARGIN,
+        _ => IDLE_INTERVAL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use super::{next_delay, IDLE_INTERVAL};
+
+    #[rstest]
+    #[case(None, IDLE_INTERVAL)]
+    #[case(Some(Duration::from_secs(30)), IDLE_INTERVAL)]
+    #[case(Some(Duration::from_secs(65)), Duration::from_secs(5))]
+    #[case(Some(Duration::from_secs(3600)), Duration::from_secs(3540))]
+    fn delay(#[case] expires_in: Option<Duration>, #[case] expected: Duration) {
+        assert_eq!(next_delay(expires_in), expected);
+    }
+}
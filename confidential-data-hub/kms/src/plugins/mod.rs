@@ -5,33 +5,212 @@
 
 use strum::{AsRefStr, EnumString};
 
-use crate::{Decrypter, Error, Getter, ProviderSettings, Result};
+use crate::{
+    metrics::Metered, settings, Decrypter, Encrypter, Getter, ProviderSettings, Result, Signer,
+};
 
 const _IN_GUEST_DEFAULT_KEY_PATH: &str = "/run/confidential-containers/cdh/kms-credential";
 
+/// Directory of the credentials of the plugin `name` inside the guest, i.e.
+/// `<dir>/<name>` of the [`credential_dir`](crate::Settings::credential_dir)
+/// setting, or of the default directory.
+#[allow(dead_code)]
+pub(crate) fn credential_dir(name: &str) -> String {
+    let dir = settings::settings()
+        .credential_dir
+        .unwrap_or_else(|| _IN_GUEST_DEFAULT_KEY_PATH.to_string());
+    format!("{dir}/{name}")
+}
+
 #[cfg(feature = "aliyun")]
 pub mod aliyun;
 
+#[cfg(feature = "aws")]
+pub mod aws;
+
+#[cfg(feature = "azure-kv")]
+pub mod azure_kv;
+
+#[cfg(feature = "ehsm")]
+pub mod ehsm;
+
+#[cfg(feature = "gcp")]
+pub mod gcp;
+
+#[cfg(feature = "vault")]
+pub mod vault;
+
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+
+#[cfg(feature = "tpm")]
+pub mod tpm;
+
+#[cfg(feature = "testing")]
+pub mod test;
+
 pub mod kbs;
 
+pub mod proxy;
+
+pub mod registry;
+
 #[derive(AsRefStr, EnumString)]
 pub enum DecryptorProvider {
+    #[cfg(feature = "kbs")]
+    #[strum(ascii_case_insensitive)]
+    Kbs,
+
     #[cfg(feature = "aliyun")]
     Aliyun,
+
+    #[cfg(feature = "aws")]
+    #[strum(ascii_case_insensitive)]
+    Aws,
+
+    #[cfg(feature = "azure-kv")]
+    #[strum(serialize = "azure_kv", ascii_case_insensitive)]
+    AzureKv,
+
+    #[cfg(feature = "ehsm")]
+    #[strum(ascii_case_insensitive)]
+    Ehsm,
+
+    #[cfg(feature = "gcp")]
+    #[strum(ascii_case_insensitive)]
+    Gcp,
+
+    #[cfg(feature = "vault")]
+    #[strum(serialize = "vault", ascii_case_insensitive)]
+    HashiCorpVault,
+
+    #[cfg(feature = "pkcs11")]
+    #[strum(ascii_case_insensitive)]
+    Pkcs11,
+
+    #[cfg(feature = "tpm")]
+    #[strum(ascii_case_insensitive)]
+    Tpm,
+
+    #[cfg(feature = "testing")]
+    #[strum(ascii_case_insensitive)]
+    Test,
 }
 
-/// Create a new [`Decrypter`] by given provider name and [`ProviderSettings`]
+/// Create a new [`Decrypter`] by given provider name and [`ProviderSettings`].
+/// Providers registered by [`registry::register_decryptor`] are also supported.
+/// The requests of the [`Decrypter`] are recorded in the [`metrics`](crate::metrics).
 pub async fn new_decryptor(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Decrypter>> {
+    let decryptor = create_decryptor(provider_name, provider_settings).await?;
+    Ok(Box::new(Metered::new(provider_name, decryptor)))
+}
+
+async fn create_decryptor(
     provider_name: &str,
     _provider_settings: ProviderSettings,
 ) -> Result<Box<dyn Decrypter>> {
-    let provider = DecryptorProvider::try_from(provider_name)
-        .map_err(|_| Error::UnsupportedProvider(provider_name.to_string()))?;
+    let Ok(provider) = DecryptorProvider::try_from(provider_name) else {
+        return registry::create_decryptor(provider_name, _provider_settings).await;
+    };
     match provider {
+        #[cfg(feature = "kbs")]
+        DecryptorProvider::Kbs => Ok(Box::new(
+            kbs::KbcClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
         #[cfg(feature = "aliyun")]
         DecryptorProvider::Aliyun => Ok(Box::new(
             aliyun::AliyunKmsClient::from_provider_settings(&_provider_settings).await?,
         ) as Box<dyn Decrypter>),
+        #[cfg(feature = "aws")]
+        DecryptorProvider::Aws => Ok(Box::new(
+            aws::AwsKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "azure-kv")]
+        DecryptorProvider::AzureKv => Ok(Box::new(
+            azure_kv::AzureKvClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "ehsm")]
+        DecryptorProvider::Ehsm => Ok(Box::new(
+            ehsm::EhsmClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "gcp")]
+        DecryptorProvider::Gcp => Ok(Box::new(
+            gcp::GcpKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "vault")]
+        DecryptorProvider::HashiCorpVault => Ok(Box::new(
+            vault::VaultClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "pkcs11")]
+        DecryptorProvider::Pkcs11 => Ok(Box::new(
+            pkcs11::Pkcs11Client::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "tpm")]
+        DecryptorProvider::Tpm => Ok(Box::new(
+            tpm::TpmClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+        #[cfg(feature = "testing")]
+        DecryptorProvider::Test => Ok(Box::new(
+            test::TestKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Decrypter>),
+    }
+}
+
+/// Create a new [`Encrypter`] by given provider name and [`ProviderSettings`],
+/// which is used at the user side to seal secrets. The providers are the
+/// same as [`DecryptorProvider`], and the credentials are read in the same
+/// way as [`new_decryptor`].
+pub async fn new_encryptor(
+    provider_name: &str,
+    _provider_settings: ProviderSettings,
+) -> Result<Box<dyn Encrypter>> {
+    let Ok(provider) = DecryptorProvider::try_from(provider_name) else {
+        return registry::create_encryptor(provider_name, _provider_settings).await;
+    };
+    match provider {
+        #[cfg(feature = "kbs")]
+        DecryptorProvider::Kbs => Ok(Box::new(
+            kbs::KbcClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "aliyun")]
+        DecryptorProvider::Aliyun => Ok(Box::new(
+            aliyun::AliyunKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "aws")]
+        DecryptorProvider::Aws => Ok(Box::new(
+            aws::AwsKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "azure-kv")]
+        DecryptorProvider::AzureKv => Ok(Box::new(
+            azure_kv::AzureKvClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "ehsm")]
+        DecryptorProvider::Ehsm => Ok(Box::new(
+            ehsm::EhsmClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "gcp")]
+        DecryptorProvider::Gcp => Ok(Box::new(
+            gcp::GcpKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "vault")]
+        DecryptorProvider::HashiCorpVault => Ok(Box::new(
+            vault::VaultClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "pkcs11")]
+        DecryptorProvider::Pkcs11 => Ok(Box::new(
+            pkcs11::Pkcs11Client::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "tpm")]
+        DecryptorProvider::Tpm => Ok(Box::new(
+            tpm::TpmClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
+        #[cfg(feature = "testing")]
+        DecryptorProvider::Test => Ok(Box::new(
+            test::TestKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Encrypter>),
     }
 }
 
@@ -40,16 +219,101 @@ pub enum VaultProvider {
     #[cfg(feature = "kbs")]
     #[strum(ascii_case_insensitive)]
     Kbs,
+
+    #[cfg(feature = "aws")]
+    #[strum(ascii_case_insensitive)]
+    Aws,
+
+    #[cfg(feature = "azure-kv")]
+    #[strum(serialize = "azure_kv", ascii_case_insensitive)]
+    AzureKv,
+
+    #[cfg(feature = "gcp")]
+    #[strum(ascii_case_insensitive)]
+    Gcp,
+
+    #[cfg(feature = "vault")]
+    #[strum(serialize = "vault", ascii_case_insensitive)]
+    HashiCorpVault,
+
+    #[cfg(feature = "testing")]
+    #[strum(ascii_case_insensitive)]
+    Test,
 }
 
-/// Create a new [`Getter`] by given provider name and [`ProviderSettings`]
+/// Create a new [`Getter`] by given provider name and [`ProviderSettings`].
+/// Providers registered by [`registry::register_getter`] are also supported.
+/// The requests of the [`Getter`] are recorded in the [`metrics`](crate::metrics).
 pub async fn new_getter(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Getter>> {
+    let getter = create_getter(provider_name, provider_settings).await?;
+    Ok(Box::new(Metered::new(provider_name, getter)))
+}
+
+async fn create_getter(
     provider_name: &str,
     _provider_settings: ProviderSettings,
 ) -> Result<Box<dyn Getter>> {
-    let provider = VaultProvider::try_from(provider_name)
-        .map_err(|_| Error::UnsupportedProvider(provider_name.to_string()))?;
+    let Ok(provider) = VaultProvider::try_from(provider_name) else {
+        return registry::create_getter(provider_name, _provider_settings).await;
+    };
+    match provider {
+        VaultProvider::Kbs => Ok(Box::new(
+            kbs::KbcClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+        #[cfg(feature = "aws")]
+        VaultProvider::Aws => Ok(Box::new(
+            aws::AwsKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+        #[cfg(feature = "azure-kv")]
+        VaultProvider::AzureKv => Ok(Box::new(
+            azure_kv::AzureKvClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+        #[cfg(feature = "gcp")]
+        VaultProvider::Gcp => Ok(Box::new(
+            gcp::GcpKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+        #[cfg(feature = "vault")]
+        VaultProvider::HashiCorpVault => Ok(Box::new(
+            vault::VaultClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+        #[cfg(feature = "testing")]
+        VaultProvider::Test => Ok(Box::new(
+            test::TestKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Getter>),
+    }
+}
+
+#[derive(AsRefStr, EnumString)]
+pub enum SignerProvider {
+    #[cfg(feature = "aliyun")]
+    Aliyun,
+}
+
+/// Create a new [`Signer`] by given provider name and [`ProviderSettings`].
+/// Providers registered by [`registry::register_signer`] are also supported.
+/// The requests of the [`Signer`] are recorded in the [`metrics`](crate::metrics).
+pub async fn new_signer(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Signer>> {
+    let signer = create_signer(provider_name, provider_settings).await?;
+    Ok(Box::new(Metered::new(provider_name, signer)))
+}
+
+async fn create_signer(
+    provider_name: &str,
+    _provider_settings: ProviderSettings,
+) -> Result<Box<dyn Signer>> {
+    let Ok(provider) = SignerProvider::try_from(provider_name) else {
+        return registry::create_signer(provider_name, _provider_settings).await;
+    };
     match provider {
-        VaultProvider::Kbs => Ok(Box::new(kbs::KbcClient::new().await?) as Box<dyn Getter>),
+        #[cfg(feature = "aliyun")]
+        SignerProvider::Aliyun => Ok(Box::new(
+            aliyun::AliyunKmsClient::from_provider_settings(&_provider_settings).await?,
+        ) as Box<dyn Signer>),
     }
 }
@@ -0,0 +1,43 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// The mechanism used to encrypt/decrypt the data key inside the token
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Pkcs11Mechanism {
+    /// RSA OAEP with SHA-256 and MGF1-SHA256. The key pair is located by
+    /// the label of the public/private key objects.
+    #[default]
+    #[serde(rename = "RSA-PKCS-OAEP")]
+    RsaPkcsOaep,
+
+    /// RSA PKCS #1 v1.5. The key pair is located by the label of the
+    /// public/private key objects.
+    #[serde(rename = "RSA-PKCS")]
+    RsaPkcs,
+
+    /// AES key wrap with padding (RFC 5649). The key is located by the label
+    /// of the secret key object.
+    #[serde(rename = "AES-KEY-WRAP-PAD")]
+    AesKeyWrapPad,
+}
+
+/// Serialized [`crate::Annotations`] for encryption/decryption
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Pkcs11Annotations {
+    #[serde(default)]
+    pub mechanism: Pkcs11Mechanism,
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pkcs11ProviderSettings {
+    /// Path of the PKCS#11 module, e.g. `/usr/lib/softhsm/libsofthsm2.so`
+    pub module_path: String,
+
+    /// Label of the token to use
+    pub token_label: String,
+}
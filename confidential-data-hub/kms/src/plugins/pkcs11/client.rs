@@ -0,0 +1,278 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use async_trait::async_trait;
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::mechanism::rsa::{PkcsMgfType, PkcsOaepParams, PkcsOaepSource};
+use cryptoki::mechanism::{Mechanism, MechanismType};
+use cryptoki::object::{Attribute, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::slot::Slot;
+use cryptoki::types::AuthPin;
+use serde_json::Value;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+use crate::plugins::credential_dir;
+use crate::{Annotations, Decrypter, Encrypter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{Pkcs11Annotations, Pkcs11Mechanism, Pkcs11ProviderSettings};
+
+pub struct Pkcs11Client {
+    context: Pkcs11,
+    slot: Slot,
+    pin: AuthPin,
+    settings: Pkcs11ProviderSettings,
+}
+
+impl Pkcs11Client {
+    /// Create a client by loading the PKCS#11 module `module_path` and using
+    /// the token labeled `token_label` with the user `pin`.
+    pub fn new(module_path: &str, token_label: &str, pin: &str) -> Result<Self> {
+        let context = Pkcs11::new(module_path)
+            .map_err(|e| Error::Pkcs11Error(format!("load module {module_path} failed: {e}")))?;
+        context
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(|e| Error::Pkcs11Error(format!("initialize module failed: {e}")))?;
+
+        let mut slot = None;
+        for s in context
+            .get_slots_with_token()
+            .map_err(|e| Error::Pkcs11Error(format!("get slots failed: {e}")))?
+        {
+            let token_info = context
+                .get_token_info(s)
+                .map_err(|e| Error::Pkcs11Error(format!("get token info failed: {e}")))?;
+            if token_info.label() == token_label {
+                slot = Some(s);
+                break;
+            }
+        }
+        let slot =
+            slot.ok_or_else(|| Error::Pkcs11Error(format!("token `{token_label}` not found")))?;
+
+        Ok(Self {
+            context,
+            slot,
+            pin: AuthPin::new(pin.to_string()),
+            settings: Pkcs11ProviderSettings {
+                module_path: module_path.to_string(),
+                token_label: token_label.to_string(),
+            },
+        })
+    }
+
+    /// This new function is used by a in-pod client. The side-effect is to read the
+    /// [`credential_dir`] of the plugin, which is the path where the user pin of the
+    /// token is saved.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings: Pkcs11ProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| Error::Pkcs11Error(format!("parse provider setting failed: {e}")))?;
+
+        let pin_path = format!("{}/pin_{}", credential_dir("pkcs11"), settings.token_label);
+        let pin = Zeroizing::new(
+            fs::read_to_string(pin_path)
+                .await
+                .map_err(|e| Error::Pkcs11Error(format!("read user pin failed: {e}")))?,
+        );
+
+        Self::new(&settings.module_path, &settings.token_label, pin.trim())
+    }
+
+    /// The [`ProviderSettings`] of the module and the token of this client.
+    /// The user pin is not exported, but located by the token label.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = serde_json::to_value(&self.settings)
+            .map_err(|e| Error::Pkcs11Error(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+
+    /// Open a logged in session to the token.
+    fn open_session(&self) -> Result<Session> {
+        let session = self
+            .context
+            .open_ro_session(self.slot)
+            .map_err(|e| Error::Pkcs11Error(format!("open session failed: {e}")))?;
+        session
+            .login(UserType::User, Some(&self.pin))
+            .map_err(|e| Error::Pkcs11Error(format!("login failed: {e}")))?;
+        Ok(session)
+    }
+
+    /// Find the unique key object of `class` labeled `label`.
+    fn find_key(session: &Session, class: ObjectClass, label: &str) -> Result<ObjectHandle> {
+        let objects = session
+            .find_objects(&[
+                Attribute::Class(class),
+                Attribute::Label(label.as_bytes().to_vec()),
+            ])
+            .map_err(|e| Error::Pkcs11Error(format!("find key `{label}` failed: {e}")))?;
+        match objects[..] {
+            [key] => Ok(key),
+            [] => Err(Error::Pkcs11Error(format!("key `{label}` not found"))),
+            _ => Err(Error::Pkcs11Error(format!(
+                "more than one key labeled `{label}`"
+            ))),
+        }
+    }
+}
+
+fn oaep_params() -> PkcsOaepParams<'static> {
+    PkcsOaepParams::new(
+        MechanismType::SHA256,
+        PkcsMgfType::MGF1_SHA256,
+        PkcsOaepSource::empty(),
+    )
+}
+
+/// The class of the key object and the mechanism to encrypt, or to decrypt
+/// if `decrypt`, by the `mechanism`. The RSA key pairs encrypt by the public
+/// keys and decrypt by the private ones.
+fn key_mechanism(mechanism: Pkcs11Mechanism, decrypt: bool) -> (ObjectClass, Mechanism<'static>) {
+    let rsa_class = match decrypt {
+        true => ObjectClass::PRIVATE_KEY,
+        false => ObjectClass::PUBLIC_KEY,
+    };
+    match mechanism {
+        Pkcs11Mechanism::RsaPkcsOaep => (rsa_class, Mechanism::RsaPkcsOaep(oaep_params())),
+        Pkcs11Mechanism::RsaPkcs => (rsa_class, Mechanism::RsaPkcs),
+        Pkcs11Mechanism::AesKeyWrapPad => (ObjectClass::SECRET_KEY, Mechanism::AesKeyWrapPad),
+    }
+}
+
+#[async_trait]
+impl Encrypter for Pkcs11Client {
+    /// The `key_id` is the label of the key objects inside the token.
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let annotations = Pkcs11Annotations::default();
+        let session = self.open_session()?;
+
+        let (class, mechanism) = key_mechanism(annotations.mechanism, false);
+        let key = Self::find_key(&session, class, key_id)?;
+        let ciphertext = session
+            .encrypt(&mechanism, key, data)
+            .map_err(|e| Error::Pkcs11Error(format!("encrypt failed: {e}")))?;
+
+        let annotations = serde_json::to_value(annotations)
+            .map_err(|e| Error::Pkcs11Error(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((ciphertext, annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for Pkcs11Client {
+    /// The `key_id` is the label of the key objects inside the token.
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: Pkcs11Annotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::Pkcs11Error(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+        let session = self.open_session()?;
+
+        let (class, mechanism) = key_mechanism(annotations.mechanism, true);
+        let key = Self::find_key(&session, class, key_id)?;
+        session
+            .decrypt(&mechanism, key, ciphertext)
+            .map(SecretBytes::from)
+            .map_err(|e| Error::Pkcs11Error(format!("decrypt failed: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use cryptoki::mechanism::MechanismType;
+    use cryptoki::object::ObjectClass;
+
+    use super::Pkcs11Client;
+    use crate::plugins::pkcs11::annotations::{Pkcs11Annotations, Pkcs11Mechanism};
+
+    #[rstest]
+    #[case(json!({}), Pkcs11Mechanism::RsaPkcsOaep)]
+    #[case(json!({"mechanism": "RSA-PKCS-OAEP"}), Pkcs11Mechanism::RsaPkcsOaep)]
+    #[case(json!({"mechanism": "RSA-PKCS"}), Pkcs11Mechanism::RsaPkcs)]
+    #[case(json!({"mechanism": "AES-KEY-WRAP-PAD"}), Pkcs11Mechanism::AesKeyWrapPad)]
+    fn parse_annotations(#[case] annotations: Value, #[case] expected: Pkcs11Mechanism) {
+        let annotations: Pkcs11Annotations =
+            serde_json::from_value(annotations).expect("parse annotations");
+        assert_eq!(annotations.mechanism, expected);
+    }
+
+    #[test]
+    fn unknown_mechanism() {
+        assert!(serde_json::from_value::<Pkcs11Annotations>(json!({"mechanism": "DES"})).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        Pkcs11Mechanism::RsaPkcsOaep,
+        false,
+        ObjectClass::PUBLIC_KEY,
+        MechanismType::RSA_PKCS_OAEP
+    )]
+    #[case(
+        Pkcs11Mechanism::RsaPkcsOaep,
+        true,
+        ObjectClass::PRIVATE_KEY,
+        MechanismType::RSA_PKCS_OAEP
+    )]
+    #[case(
+        Pkcs11Mechanism::RsaPkcs,
+        false,
+        ObjectClass::PUBLIC_KEY,
+        MechanismType::RSA_PKCS
+    )]
+    #[case(
+        Pkcs11Mechanism::RsaPkcs,
+        true,
+        ObjectClass::PRIVATE_KEY,
+        MechanismType::RSA_PKCS
+    )]
+    #[case(
+        Pkcs11Mechanism::AesKeyWrapPad,
+        false,
+        ObjectClass::SECRET_KEY,
+        MechanismType::AES_KEY_WRAP_PAD
+    )]
+    #[case(
+        Pkcs11Mechanism::AesKeyWrapPad,
+        true,
+        ObjectClass::SECRET_KEY,
+        MechanismType::AES_KEY_WRAP_PAD
+    )]
+    fn key_mechanism(
+        #[case] mechanism: Pkcs11Mechanism,
+        #[case] decrypt: bool,
+        #[case] class: ObjectClass,
+        #[case] mechanism_type: MechanismType,
+    ) {
+        let (key_class, key_mechanism) = super::key_mechanism(mechanism, decrypt);
+        assert_eq!(key_class, class);
+        assert_eq!(key_mechanism.mechanism_type(), mechanism_type);
+    }
+
+    #[test]
+    fn missing_module() {
+        assert!(Pkcs11Client::new("/nonexistent/libpkcs11.so", "test-token", "1234").is_err());
+    }
+}
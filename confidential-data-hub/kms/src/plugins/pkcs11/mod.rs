@@ -0,0 +1,16 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is a PKCS#11 implementation.
+//!
+//! A PKCS#11 token, e.g. a HSM or a softhsm token available to the guest, is
+//! used to support envelope decryption. The keys to unwrap the data keys never
+//! leave the token. The spec can be found here:
+//! <https://docs.oasis-open.org/pkcs11/pkcs11-base/v2.40/os/pkcs11-base-v2.40-os.html>.
+
+mod annotations;
+mod client;
+
+pub use client::Pkcs11Client;
@@ -0,0 +1,145 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! HTTP(S) proxy configuration of the connections to the KBS and the KMSes.
+//!
+//! Guests in locked-down networks may only reach the outside through a
+//! proxy. The proxy is looked up from the following sources in order:
+//! 0. The [`proxy`](crate::Settings::proxy) setting, e.g. from the config
+//!    file of CDH.
+//! 1. The environment variables `https_proxy` (or `HTTPS_PROXY`) and
+//!    `no_proxy` (or `NO_PROXY`).
+//! 2. The `agent.https_proxy` and `agent.no_proxy` parameters of the kernel
+//!    commandline, the same as the ones used by kata-agent.
+//!
+//! Requests to `https` urls are tunneled through the proxy with `CONNECT`.
+//! The instance metadata endpoints are never proxied, as they are only
+//! reachable from inside the CVM.
+
+use std::env;
+
+use crate::settings;
+
+const KERNEL_CMDLINE_PATH: &str = "/proc/cmdline";
+
+/// Hosts of the instance metadata services of the clouds.
+const METADATA_HOSTS: &str = "169.254.169.254,metadata.google.internal";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProxyConfig {
+    /// Url of the proxy, e.g. `http://proxy.internal:3128`.
+    pub https_proxy: Option<String>,
+
+    /// Comma-separated hosts, domains and IP ranges that are not proxied.
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Get the [`ProxyConfig`] from the settings, the environment variables,
+    /// or from the kernel commandline if the environment does not give a
+    /// proxy.
+    pub fn new() -> Self {
+        if let Some(proxy) = settings::settings().proxy {
+            return proxy;
+        }
+
+        let from_env = |names: [&str; 2]| names.iter().find_map(|name| env::var(name).ok());
+        let https_proxy = from_env(["https_proxy", "HTTPS_PROXY"]);
+        if https_proxy.is_some() {
+            return Self {
+                https_proxy,
+                no_proxy: from_env(["no_proxy", "NO_PROXY"]),
+            };
+        }
+
+        let cmdline = std::fs::read_to_string(KERNEL_CMDLINE_PATH).unwrap_or_default();
+        Self::from_cmdline(&cmdline)
+    }
+
+    fn from_cmdline(cmdline: &str) -> Self {
+        let param = |name: &str| {
+            cmdline
+                .split_ascii_whitespace()
+                .find_map(|para| para.strip_prefix(name))
+                .map(str::to_string)
+        };
+
+        Self {
+            https_proxy: param("agent.https_proxy="),
+            no_proxy: param("agent.no_proxy="),
+        }
+    }
+
+    /// The hosts that are not proxied, including the instance metadata
+    /// endpoints.
+    pub fn no_proxy(&self) -> String {
+        match &self.no_proxy {
+            Some(no_proxy) if !no_proxy.is_empty() => format!("{no_proxy},{METADATA_HOSTS}"),
+            _ => METADATA_HOSTS.to_string(),
+        }
+    }
+
+    /// Set the proxy to the given http client builder. If no proxy is
+    /// configured the builder is returned unchanged.
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn apply(
+        &self,
+        builder: reqwest::ClientBuilder,
+    ) -> anyhow::Result<reqwest::ClientBuilder> {
+        let https_proxy = match &self.https_proxy {
+            Some(https_proxy) => https_proxy,
+            None => return Ok(builder),
+        };
+
+        let proxy = reqwest::Proxy::all(https_proxy)?
+            .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy()));
+        Ok(builder.proxy(proxy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::ProxyConfig;
+
+    #[rstest]
+    #[case("console=hvc0 quiet", None, None)]
+    #[case(
+        "agent.https_proxy=http://proxy:3128 agent.no_proxy=10.0.0.0/8,.svc",
+        Some("http://proxy:3128"),
+        Some("10.0.0.0/8,.svc")
+    )]
+    fn from_cmdline(
+        #[case] cmdline: &str,
+        #[case] https_proxy: Option<&str>,
+        #[case] no_proxy: Option<&str>,
+    ) {
+        let config = ProxyConfig::from_cmdline(cmdline);
+        assert_eq!(
+            config,
+            ProxyConfig {
+                https_proxy: https_proxy.map(str::to_string),
+                no_proxy: no_proxy.map(str::to_string),
+            }
+        );
+    }
+
+    #[test]
+    fn no_proxy() {
+        let config = ProxyConfig {
+            https_proxy: Some("http://proxy:3128".into()),
+            no_proxy: Some(".svc".into()),
+        };
+        assert_eq!(
+            config.no_proxy(),
+            ".svc,169.254.169.254,metadata.google.internal"
+        );
+        assert_eq!(
+            ProxyConfig::default().no_proxy(),
+            "169.254.169.254,metadata.google.internal"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Registry of the KMS/Vault providers implemented out of this crate.
+//!
+//! A downstream crate can register its own [`Decrypter`], [`Encrypter`],
+//! [`Getter`] or [`Signer`] by a provider name at startup, e.g.
+//! ```ignore
+//! kms::register_decryptor("my-kms", MyKmsFactory)?;
+//! ```
+//! After that [`new_decryptor`](crate::new_decryptor) with `my-kms` creates
+//! the [`Decrypter`] with `MyKmsFactory`. The name of an in-tree provider
+//! cannot be registered.
+
+use std::{collections::HashMap, sync::Arc, sync::RwLock};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+
+use crate::{Decrypter, Encrypter, Error, Getter, ProviderSettings, Result, Signer};
+
+use super::{DecryptorProvider, SignerProvider, VaultProvider};
+
+/// Creates a [`Decrypter`] of a registered provider.
+#[async_trait]
+pub trait DecryptorFactory: Send + Sync {
+    async fn create(&self, provider_settings: ProviderSettings) -> Result<Box<dyn Decrypter>>;
+}
+
+/// Creates an [`Encrypter`] of a registered provider.
+#[async_trait]
+pub trait EncryptorFactory: Send + Sync {
+    async fn create(&self, provider_settings: ProviderSettings) -> Result<Box<dyn Encrypter>>;
+}
+
+/// Creates a [`Getter`] of a registered provider.
+#[async_trait]
+pub trait GetterFactory: Send + Sync {
+    async fn create(&self, provider_settings: ProviderSettings) -> Result<Box<dyn Getter>>;
+}
+
+/// Creates a [`Signer`] of a registered provider.
+#[async_trait]
+pub trait SignerFactory: Send + Sync {
+    async fn create(&self, provider_settings: ProviderSettings) -> Result<Box<dyn Signer>>;
+}
+
+lazy_static! {
+    static ref DECRYPTORS: RwLock<HashMap<String, Arc<dyn DecryptorFactory>>> =
+        RwLock::new(HashMap::new());
+    static ref ENCRYPTORS: RwLock<HashMap<String, Arc<dyn EncryptorFactory>>> =
+        RwLock::new(HashMap::new());
+    static ref GETTERS: RwLock<HashMap<String, Arc<dyn GetterFactory>>> =
+        RwLock::new(HashMap::new());
+    static ref SIGNERS: RwLock<HashMap<String, Arc<dyn SignerFactory>>> =
+        RwLock::new(HashMap::new());
+}
+
+fn register<T: ?Sized>(
+    registry: &RwLock<HashMap<String, Arc<T>>>,
+    provider_name: &str,
+    factory: Arc<T>,
+) -> Result<()> {
+    let mut registry = registry.write().expect("registry lock poisoned");
+    if registry.contains_key(provider_name) {
+        return Err(Error::ProviderAlreadyRegistered(provider_name.to_string()));
+    }
+    registry.insert(provider_name.to_string(), factory);
+    Ok(())
+}
+
+fn lookup<T: ?Sized>(
+    registry: &RwLock<HashMap<String, Arc<T>>>,
+    provider_name: &str,
+) -> Result<Arc<T>> {
+    registry
+        .read()
+        .expect("registry lock poisoned")
+        .get(provider_name)
+        .cloned()
+        .ok_or_else(|| Error::UnsupportedProvider(provider_name.to_string()))
+}
+
+/// Register the [`DecryptorFactory`] of `provider_name`. It fails if the
+/// name is already taken by an in-tree or a registered provider.
+pub fn register_decryptor(
+    provider_name: &str,
+    factory: impl DecryptorFactory + 'static,
+) -> Result<()> {
+    if DecryptorProvider::try_from(provider_name).is_ok() {
+        return Err(Error::ProviderAlreadyRegistered(provider_name.to_string()));
+    }
+    register(&DECRYPTORS, provider_name, Arc::new(factory))
+}
+
+/// Register the [`EncryptorFactory`] of `provider_name`. It fails if the
+/// name is already taken by an in-tree or a registered provider.
+pub fn register_encryptor(
+    provider_name: &str,
+    factory: impl EncryptorFactory + 'static,
+) -> Result<()> {
+    if DecryptorProvider::try_from(provider_name).is_ok() {
+        return Err(Error::ProviderAlreadyRegistered(provider_name.to_string()));
+    }
+    register(&ENCRYPTORS, provider_name, Arc::new(factory))
+}
+
+/// Register the [`GetterFactory`] of `provider_name`. It fails if the name
+/// is already taken by an in-tree or a registered provider.
+pub fn register_getter(provider_name: &str, factory: impl GetterFactory + 'static) -> Result<()> {
+    if VaultProvider::try_from(provider_name).is_ok() {
+        return Err(Error::ProviderAlreadyRegistered(provider_name.to_string()));
+    }
+    register(&GETTERS, provider_name, Arc::new(factory))
+}
+
+/// Register the [`SignerFactory`] of `provider_name`. It fails if the name
+/// is already taken by an in-tree or a registered provider.
+pub fn register_signer(provider_name: &str, factory: impl SignerFactory + 'static) -> Result<()> {
+    if SignerProvider::try_from(provider_name).is_ok() {
+        return Err(Error::ProviderAlreadyRegistered(provider_name.to_string()));
+    }
+    register(&SIGNERS, provider_name, Arc::new(factory))
+}
+
+pub(crate) async fn create_decryptor(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Decrypter>> {
+    let factory = lookup(&DECRYPTORS, provider_name)?;
+    factory.create(provider_settings).await
+}
+
+pub(crate) async fn create_encryptor(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Encrypter>> {
+    let factory = lookup(&ENCRYPTORS, provider_name)?;
+    factory.create(provider_settings).await
+}
+
+pub(crate) async fn create_getter(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Getter>> {
+    let factory = lookup(&GETTERS, provider_name)?;
+    factory.create(provider_settings).await
+}
+
+pub(crate) async fn create_signer(
+    provider_name: &str,
+    provider_settings: ProviderSettings,
+) -> Result<Box<dyn Signer>> {
+    let factory = lookup(&SIGNERS, provider_name)?;
+    factory.create(provider_settings).await
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use crate::{Annotations, Decrypter, Error, Getter, ProviderSettings, Result, SecretBytes};
+
+    use super::{register_decryptor, register_getter, DecryptorFactory, GetterFactory};
+
+    struct Echo;
+
+    #[async_trait]
+    impl Decrypter for Echo {
+        async fn decrypt(
+            &mut self,
+            ciphertext: &[u8],
+            _key_id: &str,
+            _annotations: &Annotations,
+        ) -> Result<SecretBytes> {
+            Ok(ciphertext.into())
+        }
+    }
+
+    struct EchoFactory;
+
+    #[async_trait]
+    impl DecryptorFactory for EchoFactory {
+        async fn create(&self, _provider_settings: ProviderSettings) -> Result<Box<dyn Decrypter>> {
+            Ok(Box::new(Echo))
+        }
+    }
+
+    #[tokio::test]
+    async fn registered_decryptor() {
+        assert!(matches!(
+            crate::new_decryptor("echo", ProviderSettings::new()).await,
+            Err(Error::UnsupportedProvider(_))
+        ));
+
+        register_decryptor("echo", EchoFactory).expect("register");
+        assert!(matches!(
+            register_decryptor("echo", EchoFactory),
+            Err(Error::ProviderAlreadyRegistered(_))
+        ));
+
+        let mut decryptor = crate::new_decryptor("echo", ProviderSettings::new())
+            .await
+            .expect("create decryptor");
+        let plaintext = decryptor
+            .decrypt(b"data", "key", &Annotations::new())
+            .await
+            .unwrap();
+        assert_eq!(plaintext, b"data");
+    }
+
+    struct NoGetterFactory;
+
+    #[async_trait]
+    impl GetterFactory for NoGetterFactory {
+        async fn create(&self, provider_settings: ProviderSettings) -> Result<Box<dyn Getter>> {
+            Err(Error::UnsupportedProvider(format!("{provider_settings:?}")))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "kbs")]
+    fn in_tree_name() {
+        assert!(matches!(
+            register_getter("kbs", NoGetterFactory),
+            Err(Error::ProviderAlreadyRegistered(_))
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto::WrapType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::{
+    Annotations, Decrypter, Encrypter, Error, Getter, ProviderSettings, Result, SecretBytes,
+};
+
+/// Annotation of the base64 encoded IV of a ciphertext.
+const IV_ANNOTATION: &str = "iv";
+
+/// Serialized [`ProviderSettings`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct TestProviderSettings {
+    /// Seed of the keys, so that different tests do not share keys.
+    #[serde(default)]
+    seed: String,
+
+    /// The secrets got by their names.
+    #[serde(default)]
+    secrets: HashMap<String, String>,
+}
+
+/// A deterministic KMS for the tests. The key `key_id` is the sha256 of the
+/// seed and the key id, and the same data is always encrypted to the same
+/// ciphertext, so the sealed secrets can be checked in as test vectors.
+pub struct TestKmsClient {
+    settings: TestProviderSettings,
+}
+
+impl TestKmsClient {
+    /// Create a client whose keys are derived from the `seed`.
+    pub fn new(seed: &str) -> Self {
+        Self {
+            settings: TestProviderSettings {
+                seed: seed.to_string(),
+                secrets: HashMap::new(),
+            },
+        }
+    }
+
+    /// Add the secret `value` of `name` to be got.
+    pub fn with_secret(mut self, name: &str, value: &str) -> Self {
+        self.settings
+            .secrets
+            .insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Create a client from the [`ProviderSettings`] exported by
+    /// [`Self::export_provider_settings`]. No credential is needed.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings = serde_json::from_value(Value::Object(provider_settings.clone()))
+            .map_err(|e| Error::TestKmsError(format!("parse provider setting failed: {e}")))?;
+        Ok(Self { settings })
+    }
+
+    /// Export the [`ProviderSettings`] of the client, to be put into the
+    /// sealed secrets.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let Value::Object(provider_settings) = serde_json::to_value(&self.settings)
+            .map_err(|e| Error::TestKmsError(format!("serialize provider setting failed: {e}")))?
+        else {
+            unreachable!("provider settings are an object");
+        };
+        Ok(provider_settings)
+    }
+
+    fn key(&self, key_id: &str) -> Zeroizing<Vec<u8>> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.settings.seed.as_bytes());
+        hasher.update([0]);
+        hasher.update(key_id.as_bytes());
+        Zeroizing::new(hasher.finalize().to_vec())
+    }
+}
+
+#[async_trait]
+impl Encrypter for TestKmsClient {
+    /// The IV is derived from the key and the `data`.
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let key = self.key(key_id);
+        let mut hasher = Sha256::new();
+        hasher.update(&key[..]);
+        hasher.update(data);
+        let iv = hasher.finalize()[..12].to_vec();
+
+        let ciphertext = crypto::encrypt(key, data.to_vec(), iv.clone(), WrapType::Aes256Gcm)
+            .map_err(|e| Error::TestKmsError(format!("encrypt failed: {e}")))?;
+        let mut annotations = Annotations::new();
+        annotations.insert(IV_ANNOTATION.into(), STANDARD.encode(iv).into());
+        Ok((ciphertext, annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for TestKmsClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let Some(Value::String(iv)) = annotations.get(IV_ANNOTATION) else {
+            return Err(Error::TestKmsError(format!(
+                "no annotation `{IV_ANNOTATION}` is given"
+            )));
+        };
+        let iv = STANDARD
+            .decode(iv)
+            .map_err(|e| Error::TestKmsError(format!("base64 decode iv failed: {e}")))?;
+        let plaintext = crypto::decrypt(
+            self.key(key_id),
+            ciphertext.to_vec(),
+            iv,
+            WrapType::Aes256Gcm,
+        )
+        .map_err(|e| Error::TestKmsError(format!("decrypt failed: {e}")))?;
+        Ok(plaintext.into())
+    }
+}
+
+#[async_trait]
+impl Getter for TestKmsClient {
+    async fn get_secret(&mut self, name: &str, _annotations: &Annotations) -> Result<SecretBytes> {
+        self.settings
+            .secrets
+            .get(name)
+            .map(|value| SecretBytes::from(value.clone()))
+            .ok_or_else(|| Error::TestKmsError(format!("secret {name} not found")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Annotations, Decrypter, Encrypter, Getter};
+
+    use super::TestKmsClient;
+
+    #[tokio::test]
+    async fn deterministic() {
+        let mut client = TestKmsClient::new("seed").with_secret("name", "value");
+        let (ciphertext, annotations) = client.encrypt(b"data", "key").await.unwrap();
+        let (again, _) = client.encrypt(b"data", "key").await.unwrap();
+        assert_eq!(ciphertext, again);
+        let (other, _) = client.encrypt(b"data", "other key").await.unwrap();
+        assert_ne!(ciphertext, other);
+
+        // the client of the exported settings shares the keys and secrets
+        let provider_settings = client.export_provider_settings().unwrap();
+        let mut client = TestKmsClient::from_provider_settings(&provider_settings)
+            .await
+            .unwrap();
+        let plaintext = client
+            .decrypt(&ciphertext, "key", &annotations)
+            .await
+            .unwrap();
+        assert_eq!(plaintext, b"data");
+        assert!(client
+            .decrypt(&ciphertext, "other key", &annotations)
+            .await
+            .is_err());
+
+        let secret = client
+            .get_secret("name", &Annotations::new())
+            .await
+            .unwrap();
+        assert_eq!(secret, b"value");
+        assert!(client
+            .get_secret("other name", &Annotations::new())
+            .await
+            .is_err());
+
+        let mut client = TestKmsClient::new("other seed");
+        assert!(client
+            .decrypt(&ciphertext, "key", &annotations)
+            .await
+            .is_err());
+    }
+}
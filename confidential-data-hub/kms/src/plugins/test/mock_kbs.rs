@@ -0,0 +1,368 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! A KBS inside the process. It speaks the background check mode of the KBS
+//! protocol over HTTP, but accepts any evidence and issues unsigned tokens,
+//! so it must only be used in the tests.
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use crypto::{rsa::PaddingMode, WrapType};
+use hyper::{
+    header::{CONTENT_TYPE, COOKIE, SET_COOKIE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use kbs_types::{Attestation, Challenge, ErrorInformation, TeePubKey};
+use log::debug;
+use rand::Rng;
+use serde::Serialize;
+use tokio::sync::oneshot;
+use zeroize::Zeroizing;
+
+use crate::{Error, Result};
+
+/// Prefix of the paths of the KBS protocol.
+const KBS_PREFIX: &str = "/kbs/v0";
+
+/// Cookie of the sessions.
+const SESSION_COOKIE: &str = "kbs-session-id";
+
+/// Error types of the responses, as the ones of the KBS.
+const ERROR_TYPE_PREFIX: &str = "https://github.com/confidential-containers/kbs/errors/";
+
+#[derive(Default)]
+struct State {
+    /// Resources by their paths, i.e. `<repository>/<type>/<tag>`.
+    resources: HashMap<String, Vec<u8>>,
+
+    /// Paths of the resources denied by the policy.
+    denied: HashSet<String>,
+
+    /// The TEE public keys of the sessions, which is `None` until the
+    /// session is attested.
+    sessions: HashMap<String, Option<TeePubKey>>,
+}
+
+/// A mock KBS listening on a random port of the loopback. It is shut down
+/// when dropped.
+///
+/// ```no_run
+/// # async fn example() -> kms::Result<()> {
+/// use kms::{plugins::{kbs::KbcClient, test::MockKbs}, Annotations, Getter};
+///
+/// let kbs = MockKbs::start().await?;
+/// kbs.set_resource("default/key/1", b"secret");
+/// let mut client = KbcClient::new_with_params("mock_kbc", kbs.url()).await?;
+/// let secret = client
+///     .get_secret("kbs:///default/key/1", &Annotations::default())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MockKbs {
+    url: String,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockKbs {
+    /// Start the KBS inside the current tokio runtime.
+    pub async fn start() -> Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(|e| Error::TestKmsError(format!("bind mock kbs failed: {e}")))?
+            .serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        let (shutdown, rx) = oneshot::channel();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = rx.await;
+        }));
+        debug!("mock kbs listens on {url}");
+
+        Ok(Self {
+            url,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Url of the KBS, as the `kbs_host` of a KBC.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Set the resource of `path`, i.e. `<repository>/<type>/<tag>`.
+    pub fn set_resource(&self, path: &str, content: impl Into<Vec<u8>>) {
+        self.state
+            .lock()
+            .expect("lock poisoned")
+            .resources
+            .insert(path.to_string(), content.into());
+    }
+
+    /// The resource of `path`, e.g. to check the ones set by the clients.
+    pub fn resource(&self, path: &str) -> Option<Vec<u8>> {
+        self.state
+            .lock()
+            .expect("lock poisoned")
+            .resources
+            .get(path)
+            .cloned()
+    }
+
+    /// Deny the requests to the resource of `path` by the policy.
+    pub fn deny(&self, path: &str) {
+        self.state
+            .lock()
+            .expect("lock poisoned")
+            .denied
+            .insert(path.to_string());
+    }
+}
+
+impl Drop for MockKbs {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> std::result::Result<Response<Body>, Infallible> {
+    let path = request.uri().path().to_string();
+    let Some(path) = path.strip_prefix(KBS_PREFIX) else {
+        return Ok(error(StatusCode::NOT_FOUND, "NotFound", "unknown path"));
+    };
+    let session = session_id(&request);
+    let method = request.method().clone();
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => return Ok(error(StatusCode::BAD_REQUEST, "BadRequest", &e.to_string())),
+    };
+
+    let mut state = state.lock().expect("lock poisoned");
+    let response = match (method, path) {
+        (Method::POST, "/auth") => auth(&mut state),
+        (Method::POST, "/attest") => attest(&mut state, session, &body),
+        (Method::GET, path) if path.starts_with("/resource/") => {
+            get_resource(&state, session, &path["/resource/".len()..])
+        }
+        (Method::POST, path) if path.starts_with("/resource/") => {
+            set_resource(&mut state, session, &path["/resource/".len()..], &body)
+        }
+        _ => error(StatusCode::NOT_FOUND, "NotFound", "unknown path"),
+    };
+
+    Ok(response)
+}
+
+fn session_id(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .find_map(|cookie| {
+            let (name, value) = cookie.trim().split_once('=')?;
+            (name == SESSION_COOKIE).then(|| value.to_string())
+        })
+}
+
+fn auth(state: &mut State) -> Response<Body> {
+    let session = hex_random(16);
+    state.sessions.insert(session.clone(), None);
+    let challenge = Challenge {
+        nonce: hex_random(32),
+        extra_params: String::new(),
+    };
+
+    let mut response = json(StatusCode::OK, &challenge);
+    let cookie = format!("{SESSION_COOKIE}={session}; Path=/");
+    response
+        .headers_mut()
+        .insert(SET_COOKIE, cookie.parse().expect("legal cookie"));
+    response
+}
+
+fn attest(state: &mut State, session: Option<String>, body: &[u8]) -> Response<Body> {
+    let Some(tee_pubkey) = session.and_then(|session| state.sessions.get_mut(&session)) else {
+        return error(StatusCode::UNAUTHORIZED, "InvalidCookie", "no session");
+    };
+    let attestation: Attestation = match serde_json::from_slice(body) {
+        Ok(attestation) => attestation,
+        Err(e) => return error(StatusCode::BAD_REQUEST, "InvalidRequest", &e.to_string()),
+    };
+    *tee_pubkey = Some(attestation.tee_pubkey);
+
+    // An unsigned token without expiration.
+    let token = format!(
+        "{}.{}.",
+        URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+        URL_SAFE_NO_PAD.encode("{}")
+    );
+    json(StatusCode::OK, &serde_json::json!({ "token": token }))
+}
+
+/// The TEE public key of an attested `session`.
+fn attested<'a>(state: &'a State, session: &Option<String>) -> Option<&'a TeePubKey> {
+    state.sessions.get(session.as_ref()?)?.as_ref()
+}
+
+fn get_resource(state: &State, session: Option<String>, path: &str) -> Response<Body> {
+    let Some(tee_pubkey) = attested(state, &session) else {
+        return error(StatusCode::UNAUTHORIZED, "TokenNotFound", "not attested");
+    };
+    if state.denied.contains(path) {
+        return error(StatusCode::UNAUTHORIZED, "PolicyDeny", path);
+    }
+    let Some(resource) = state.resources.get(path) else {
+        return error(StatusCode::NOT_FOUND, "ResourceNotFound", path);
+    };
+
+    match encrypt_response(tee_pubkey, resource) {
+        Ok(response) => json(StatusCode::OK, &response),
+        Err(e) => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "JWEFailed",
+            &e.to_string(),
+        ),
+    }
+}
+
+fn set_resource(
+    state: &mut State,
+    session: Option<String>,
+    path: &str,
+    body: &[u8],
+) -> Response<Body> {
+    if attested(state, &session).is_none() {
+        return error(StatusCode::UNAUTHORIZED, "TokenNotFound", "not attested");
+    }
+    if state.denied.contains(path) {
+        return error(StatusCode::UNAUTHORIZED, "PolicyDeny", path);
+    }
+
+    state.resources.insert(path.to_string(), body.to_vec());
+    Response::new(Body::empty())
+}
+
+/// Encrypt the `resource` to the `tee_pubkey` as the KBS does, i.e. by a
+/// random AES-256-GCM key wrapped by the RSA public key.
+fn encrypt_response(
+    tee_pubkey: &TeePubKey,
+    resource: &[u8],
+) -> anyhow::Result<kbs_types::Response> {
+    let mode = PaddingMode::from_str(&tee_pubkey.alg)?;
+    let n = URL_SAFE_NO_PAD.decode(&tee_pubkey.k_mod)?;
+    let e = URL_SAFE_NO_PAD.decode(&tee_pubkey.k_exp)?;
+
+    let mut key = Zeroizing::new(vec![0u8; 32]);
+    rand::thread_rng().fill(&mut key[..]);
+    let mut iv = [0u8; 12];
+    rand::thread_rng().fill(&mut iv);
+    let encrypted_key = crypto::rsa::encrypt(&n, &e, mode, &key)?;
+    let ciphertext = crypto::encrypt(key, resource.to_vec(), iv.to_vec(), WrapType::Aes256Gcm)?;
+
+    let protected = serde_json::json!({
+        "alg": tee_pubkey.alg,
+        "enc": WrapType::Aes256Gcm,
+    });
+    Ok(kbs_types::Response {
+        protected: protected.to_string(),
+        encrypted_key: URL_SAFE_NO_PAD.encode(encrypted_key),
+        iv: URL_SAFE_NO_PAD.encode(iv),
+        ciphertext: URL_SAFE_NO_PAD.encode(ciphertext),
+        // The tag is a part of the ciphertext.
+        tag: String::new(),
+    })
+}
+
+fn json(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+    let body = serde_json::to_vec(body).expect("serialize response");
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        "application/json".parse().expect("legal header"),
+    );
+    response
+}
+
+fn error(status: StatusCode, error_type: &str, detail: &str) -> Response<Body> {
+    json(
+        status,
+        &ErrorInformation {
+            error_type: format!("{ERROR_TYPE_PREFIX}{error_type}"),
+            detail: detail.to_string(),
+        },
+    )
+}
+
+fn hex_random(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill(&mut bytes[..]);
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{plugins::kbs::KbcClient, Annotations, Error, Getter, KbsError, Setter};
+
+    use super::MockKbs;
+
+    #[tokio::test]
+    async fn mock_kbs() {
+        let kbs = MockKbs::start().await.unwrap();
+        kbs.set_resource("default/key/1", b"secret".to_vec());
+        kbs.set_resource("default/key/2", b"denied".to_vec());
+        kbs.deny("default/key/2");
+
+        let mut client = KbcClient::new_with_params("mock_kbc", kbs.url())
+            .await
+            .unwrap();
+        let secret = client
+            .get_secret("kbs:///default/key/1", &Annotations::default())
+            .await
+            .unwrap();
+        assert_eq!(secret, b"secret");
+
+        assert!(matches!(
+            client
+                .get_secret("kbs:///default/key/2", &Annotations::default())
+                .await,
+            Err(Error::KbsClientError(KbsError::PolicyDenied(_)))
+        ));
+        assert!(matches!(
+            client
+                .get_secret("kbs:///default/key/3", &Annotations::default())
+                .await,
+            Err(Error::KbsClientError(KbsError::NotFound(_)))
+        ));
+
+        client
+            .set_secret(b"new".to_vec(), "kbs:///default/key/3".into())
+            .await
+            .unwrap();
+        assert_eq!(kbs.resource("default/key/3").unwrap(), b"new");
+    }
+}
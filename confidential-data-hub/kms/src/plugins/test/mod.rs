@@ -0,0 +1,21 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is the backend for the tests, enabled by the feature `testing`.
+//!
+//! It runs the flows of getting and unsealing the secrets inside the
+//! process, without a TEE, a KMS or the network, so that the downstream
+//! crates and the CI can test them hermetically:
+//! - [`TestKmsClient`]: a deterministic KMS with provider name `test`,
+//!   whose keys are derived from a seed.
+//! - [`MockKbs`]: a KBS speaking the KBS protocol over HTTP on the
+//!   loopback, to be connected by the `mock_kbc` KBC, which attests with a
+//!   mocked evidence.
+
+mod client;
+mod mock_kbs;
+
+pub use client::TestKmsClient;
+pub use mock_kbs::MockKbs;
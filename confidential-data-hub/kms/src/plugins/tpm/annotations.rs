@@ -0,0 +1,45 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// The default PCRs that the data key is sealed to.
+pub const DEFAULT_PCRS: [u8; 1] = [7];
+
+/// The default PCR bank.
+pub const DEFAULT_PCR_BANK: &str = "sha256";
+
+fn default_pcrs() -> Vec<u8> {
+    DEFAULT_PCRS.to_vec()
+}
+
+fn default_pcr_bank() -> String {
+    DEFAULT_PCR_BANK.to_string()
+}
+
+/// Serialized [`crate::Annotations`] for sealing/unsealing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TpmAnnotations {
+    /// Base64 encoded marshalled `TPM2B_PUBLIC` of the sealed data object.
+    /// The `TPM2B_PRIVATE` is carried as the ciphertext.
+    pub public: String,
+
+    /// Indexes of the PCRs in the policy
+    #[serde(default = "default_pcrs")]
+    pub pcrs: Vec<u8>,
+
+    /// The PCR bank of the PCRs in the policy, `sha1`, `sha256` or `sha384`
+    #[serde(default = "default_pcr_bank")]
+    pub pcr_bank: String,
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TpmProviderSettings {
+    /// TCTI to connect to the TPM, e.g. `device:/dev/tpmrm0`. By default
+    /// the `TPM2TOOLS_TCTI` env or `device:/dev/tpmrm0` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcti: Option<String>,
+}
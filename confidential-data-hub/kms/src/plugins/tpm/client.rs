@@ -0,0 +1,357 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+use tss_esapi::attributes::ObjectAttributesBuilder;
+use tss_esapi::constants::SessionType;
+use tss_esapi::handles::{KeyHandle, ObjectHandle, SessionHandle};
+use tss_esapi::interface_types::algorithm::{HashingAlgorithm, PublicAlgorithm};
+use tss_esapi::interface_types::key_bits::RsaKeyBits;
+use tss_esapi::interface_types::resource_handles::Hierarchy;
+use tss_esapi::interface_types::session_handles::{AuthSession, PolicySession};
+use tss_esapi::structures::{
+    Digest, KeyedHashScheme, PcrSelectionList, PcrSlot, Private, Public, PublicBuilder,
+    PublicKeyedHashParameters, RsaExponent, SensitiveData, SymmetricDefinition,
+    SymmetricDefinitionObject,
+};
+use tss_esapi::traits::{Marshall, UnMarshall};
+use tss_esapi::utils::create_restricted_decryption_rsa_public;
+use tss_esapi::{Context, TctiNameConf};
+
+use crate::{Annotations, Decrypter, Encrypter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{TpmAnnotations, TpmProviderSettings, DEFAULT_PCRS, DEFAULT_PCR_BANK};
+
+const DEFAULT_TCTI: &str = "device:/dev/tpmrm0";
+
+/// Number of PCRs of a TPM following the PC Client Platform spec
+const PCR_COUNT: u8 = 24;
+
+pub struct TpmClient {
+    settings: TpmProviderSettings,
+
+    /// PCRs and bank used to seal new data
+    pcrs: Vec<u8>,
+    pcr_bank: String,
+}
+
+impl TpmClient {
+    /// Create a client that seals data to the given `pcrs` of the `pcr_bank`
+    /// of the TPM connected by `tcti`.
+    pub fn new(tcti: Option<&str>, pcrs: &[u8], pcr_bank: &str) -> Result<Self> {
+        // check the pcr selection at the very beginning
+        pcr_selection(pcrs, pcr_bank)?;
+        Ok(Self {
+            settings: TpmProviderSettings {
+                tcti: tcti.map(String::from),
+            },
+            pcrs: pcrs.to_vec(),
+            pcr_bank: pcr_bank.to_string(),
+        })
+    }
+
+    /// This new function is used by a in-pod client. The PCR policy to unseal
+    /// a secret is carried by the annotations of the secret itself.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings: TpmProviderSettings =
+            serde_json::from_value(Value::Object(provider_settings.clone()))
+                .map_err(|e| Error::TpmError(format!("parse provider setting failed: {e}")))?;
+
+        Self::new(settings.tcti.as_deref(), &DEFAULT_PCRS, DEFAULT_PCR_BANK)
+    }
+
+    /// The [`ProviderSettings`] of the TCTI of this client. The PCR policy is
+    /// not a setting of the client, but carried by the annotations.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = serde_json::to_value(&self.settings)
+            .map_err(|e| Error::TpmError(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+
+    fn context(&self) -> Result<Context> {
+        let tcti = match &self.settings.tcti {
+            Some(tcti) => TctiNameConf::from_str(tcti),
+            None => TctiNameConf::from_environment_variable()
+                .or_else(|_| TctiNameConf::from_str(DEFAULT_TCTI)),
+        }
+        .map_err(|e| Error::TpmError(format!("illegal tcti: {e}")))?;
+
+        Context::new(tcti).map_err(|e| Error::TpmError(format!("connect to TPM failed: {e}")))
+    }
+
+    /// The annotations of a data object sealed with the `public` part to
+    /// the PCR policy of this client.
+    fn annotations(&self, public: &Public) -> Result<TpmAnnotations> {
+        let public = public
+            .marshall()
+            .map_err(|e| Error::TpmError(format!("marshall public failed: {e}")))?;
+        Ok(TpmAnnotations {
+            public: STANDARD.encode(public),
+            pcrs: self.pcrs.clone(),
+            pcr_bank: self.pcr_bank.clone(),
+        })
+    }
+}
+
+/// The private and the public part of the sealed data object of the
+/// `ciphertext` and the `annotations`, and its PCR policy.
+fn sealed_object(
+    ciphertext: &[u8],
+    annotations: TpmAnnotations,
+) -> Result<(Private, Public, PcrSelectionList)> {
+    let pcr_selection = pcr_selection(&annotations.pcrs, &annotations.pcr_bank)?;
+    let public = STANDARD
+        .decode(annotations.public)
+        .map_err(|e| Error::TpmError(format!("decode public failed: {e}")))?;
+    let public = Public::unmarshall(&public)
+        .map_err(|e| Error::TpmError(format!("unmarshall public failed: {e}")))?;
+    let private = Private::try_from(ciphertext.to_vec())
+        .map_err(|e| Error::TpmError(format!("illegal sealed private: {e}")))?;
+    Ok((private, public, pcr_selection))
+}
+
+/// Parse the PCR indexes and bank into a [`PcrSelectionList`].
+fn pcr_selection(pcrs: &[u8], pcr_bank: &str) -> Result<PcrSelectionList> {
+    let bank = match pcr_bank {
+        "sha1" => HashingAlgorithm::Sha1,
+        "sha256" => HashingAlgorithm::Sha256,
+        "sha384" => HashingAlgorithm::Sha384,
+        other => return Err(Error::TpmError(format!("unsupported pcr bank `{other}`"))),
+    };
+    let slots = pcrs
+        .iter()
+        .map(|pcr| {
+            if *pcr >= PCR_COUNT {
+                return Err(Error::TpmError(format!("illegal pcr index {pcr}")));
+            }
+            PcrSlot::try_from(1u32 << pcr)
+                .map_err(|e| Error::TpmError(format!("illegal pcr index {pcr}: {e}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if slots.is_empty() {
+        return Err(Error::TpmError("no pcr given in the policy".into()));
+    }
+
+    PcrSelectionList::builder()
+        .with_selection(bank, &slots)
+        .build()
+        .map_err(|e| Error::TpmError(format!("build pcr selection failed: {e}")))
+}
+
+/// Create the storage root key under the owner hierarchy. The key is derived
+/// deterministically from the template, so the same key is got every time.
+fn create_srk(context: &mut Context) -> tss_esapi::Result<KeyHandle> {
+    let public = create_restricted_decryption_rsa_public(
+        SymmetricDefinitionObject::AES_128_CFB,
+        RsaKeyBits::Rsa2048,
+        RsaExponent::default(),
+    )?;
+    let primary = context.execute_with_nullauth_session(|ctx| {
+        ctx.create_primary(Hierarchy::Owner, public, None, None, None, None)
+    })?;
+    Ok(primary.key_handle)
+}
+
+/// Start a policy session (or a trial one to calculate the digest) and
+/// extend the PCR policy into it.
+fn start_pcr_policy(
+    context: &mut Context,
+    session_type: SessionType,
+    pcr_selection: PcrSelectionList,
+) -> tss_esapi::Result<AuthSession> {
+    let session = context
+        .start_auth_session(
+            None,
+            None,
+            None,
+            session_type,
+            SymmetricDefinition::AES_128_CFB,
+            HashingAlgorithm::Sha256,
+        )?
+        .ok_or(tss_esapi::Error::WrapperError(
+            tss_esapi::WrapperErrorKind::WrongValueFromTpm,
+        ))?;
+    let policy_session = PolicySession::try_from(session)?;
+    context.policy_pcr(policy_session, Digest::default(), pcr_selection)?;
+    Ok(session)
+}
+
+fn flush_session(context: &mut Context, session: AuthSession) -> tss_esapi::Result<()> {
+    context.flush_context(ObjectHandle::from(SessionHandle::from(session)))
+}
+
+/// The template of a sealed data object of the `policy_digest`.
+fn sealed_public(policy_digest: Digest) -> tss_esapi::Result<Public> {
+    let object_attributes = ObjectAttributesBuilder::new()
+        .with_fixed_tpm(true)
+        .with_fixed_parent(true)
+        .with_no_da(true)
+        .build()?;
+    PublicBuilder::new()
+        .with_public_algorithm(PublicAlgorithm::KeyedHash)
+        .with_name_hashing_algorithm(HashingAlgorithm::Sha256)
+        .with_object_attributes(object_attributes)
+        .with_auth_policy(policy_digest)
+        .with_keyed_hash_parameters(PublicKeyedHashParameters::new(KeyedHashScheme::Null))
+        .with_keyed_hash_unique_identifier(Digest::default())
+        .build()
+}
+
+/// Seal the `data` to the PCR policy, returning the private and the public
+/// part of the sealed data object.
+fn seal(
+    context: &mut Context,
+    data: &[u8],
+    pcr_selection: PcrSelectionList,
+) -> tss_esapi::Result<(Private, Public)> {
+    let trial = start_pcr_policy(context, SessionType::Trial, pcr_selection)?;
+    let policy_digest = context.policy_get_digest(PolicySession::try_from(trial)?);
+    flush_session(context, trial)?;
+    let public = sealed_public(policy_digest?)?;
+    let sensitive = SensitiveData::try_from(data.to_vec())?;
+
+    let srk = create_srk(context)?;
+    let sealed = context.execute_with_nullauth_session(|ctx| {
+        ctx.create(srk, public, None, Some(sensitive), None, None)
+    });
+    context.flush_context(srk.into())?;
+    let sealed = sealed?;
+
+    Ok((sealed.out_private, sealed.out_public))
+}
+
+/// Unseal the sealed data object with a policy session satisfying the PCR
+/// policy. This fails if the current PCR values do not match.
+fn unseal(
+    context: &mut Context,
+    private: Private,
+    public: Public,
+    pcr_selection: PcrSelectionList,
+) -> tss_esapi::Result<SecretBytes> {
+    let srk = create_srk(context)?;
+    let sealed = context.execute_with_nullauth_session(|ctx| ctx.load(srk, private, public));
+    context.flush_context(srk.into())?;
+    let sealed = sealed?;
+
+    let session = start_pcr_policy(context, SessionType::Policy, pcr_selection)?;
+    let data = context.execute_with_session(Some(session), |ctx| ctx.unseal(sealed.into()));
+    flush_session(context, session)?;
+    context.flush_context(sealed.into())?;
+
+    Ok(SecretBytes::new(data?.value()))
+}
+
+#[async_trait]
+impl Encrypter for TpmClient {
+    /// The `key_id` is not used, because the data is sealed by the storage
+    /// root key of the TPM.
+    async fn encrypt(&mut self, data: &[u8], _key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let pcr_selection = pcr_selection(&self.pcrs, &self.pcr_bank)?;
+        let mut context = self.context()?;
+        let (private, public) = seal(&mut context, data, pcr_selection)
+            .map_err(|e| Error::TpmError(format!("seal failed: {e}")))?;
+
+        let annotations = serde_json::to_value(self.annotations(&public)?)
+            .map_err(|e| Error::TpmError(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((private.value().to_vec(), annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for TpmClient {
+    /// The `ciphertext` is the marshalled `TPM2B_PRIVATE` of the sealed data
+    /// object. The `key_id` is not used.
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        _key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: TpmAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::TpmError(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+        let (private, public, pcr_selection) = sealed_object(ciphertext, annotations)?;
+
+        let mut context = self.context()?;
+        unseal(&mut context, private, public, pcr_selection)
+            .map_err(|e| Error::TpmError(format!("unseal failed: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::{json, Value};
+    use tss_esapi::structures::Digest;
+
+    use super::{pcr_selection, sealed_object, sealed_public, TpmClient};
+    use crate::plugins::tpm::annotations::TpmAnnotations;
+
+    #[rstest]
+    #[case(&[7], "sha256", true)]
+    #[case(&[0, 1, 2, 7], "sha1", true)]
+    #[case(&[23], "sha384", true)]
+    #[case(&[], "sha256", false)]
+    #[case(&[24], "sha256", false)]
+    #[case(&[7], "sm3", false)]
+    fn parse_pcr_selection(#[case] pcrs: &[u8], #[case] bank: &str, #[case] ok: bool) {
+        assert_eq!(pcr_selection(pcrs, bank).is_ok(), ok);
+    }
+
+    #[rstest]
+    #[case(json!({}))]
+    #[case(json!({ "tcti": "swtpm:host=127.0.0.1,port=2321" }))]
+    #[tokio::test]
+    async fn export_provider_settings(#[case] provider_settings: Value) {
+        let provider_settings = provider_settings.as_object().unwrap().to_owned();
+        let client = TpmClient::from_provider_settings(&provider_settings)
+            .await
+            .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+        assert_eq!(exported, provider_settings);
+    }
+
+    #[test]
+    fn annotations() {
+        let client = TpmClient::new(None, &[0, 7], "sha384").expect("create client");
+        let public = sealed_public(Digest::default()).expect("build public");
+        let annotations = client.annotations(&public).expect("annotations");
+        assert_eq!(annotations.pcrs, [0, 7]);
+        assert_eq!(annotations.pcr_bank, "sha384");
+
+        let (private, unmarshalled, selection) =
+            sealed_object(b"sealed private", annotations).expect("parse sealed object");
+        assert_eq!(private.value(), b"sealed private");
+        assert_eq!(unmarshalled, public);
+        assert_eq!(selection, pcr_selection(&[0, 7], "sha384").unwrap());
+    }
+
+    #[rstest]
+    #[case(json!({ "public": "not base64!" }))]
+    #[case(json!({ "public": "", "pcr_bank": "sm3" }))]
+    fn illegal_annotations(#[case] annotations: Value) {
+        let annotations: TpmAnnotations =
+            serde_json::from_value(annotations).expect("parse annotations");
+        assert!(sealed_object(b"sealed private", annotations).is_err());
+    }
+}
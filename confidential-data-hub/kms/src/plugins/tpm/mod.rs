@@ -0,0 +1,16 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is a TPM 2.0 implementation.
+//!
+//! The data key of an envelope secret is sealed by the TPM of the CVM to a
+//! PCR policy, thus it can only be unsealed inside the guest when the measured
+//! boot state matches. No KBS is needed to unseal such secrets.
+//! The spec can be found here: <https://trustedcomputinggroup.org/resource/tpm-library-specification/>.
+
+mod annotations;
+mod client;
+
+pub use client::TpmClient;
@@ -0,0 +1,86 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use serde::{Deserialize, Serialize};
+
+/// Serialized [`crate::Annotations`] for Transit encryption/decryption
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VaultTransitAnnotations {
+    /// Base64 encoded context for key derivation. Required if the key is
+    /// created with `derived` enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+/// Serialized [`crate::Annotations`] for getting secrets from KV-v2
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VaultKvAnnotations {
+    /// Version of the secret. By default the latest version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+
+    /// The field of the secret to return. If not given, the whole key-value
+    /// map of the secret is returned in JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+/// The auth method used to log in to Vault
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "auth_method", rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// Log in with the role id and a secret id. The secret id is read from
+    /// the credential file located by the role id.
+    Approle {
+        role_id: String,
+        #[serde(default = "default_approle_mount")]
+        auth_mount: String,
+    },
+
+    /// Log in with the service account token of the pod.
+    Kubernetes {
+        role: String,
+        #[serde(default = "default_kubernetes_mount")]
+        auth_mount: String,
+    },
+}
+
+fn default_approle_mount() -> String {
+    "approle".to_string()
+}
+
+fn default_kubernetes_mount() -> String {
+    "kubernetes".to_string()
+}
+
+fn default_kv_mount() -> String {
+    "secret".to_string()
+}
+
+fn default_transit_mount() -> String {
+    "transit".to_string()
+}
+
+/// Serialized [`crate::ProviderSettings`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VaultProviderSettings {
+    /// Address of the Vault server, e.g. `https://vault.example.com:8200`
+    pub address: String,
+
+    /// Vault enterprise namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Mount path of the KV-v2 secrets engine
+    #[serde(default = "default_kv_mount")]
+    pub kv_mount: String,
+
+    /// Mount path of the Transit secrets engine
+    #[serde(default = "default_transit_mount")]
+    pub transit_mount: String,
+
+    #[serde(flatten)]
+    pub auth: AuthMethod,
+}
@@ -0,0 +1,384 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::error;
+use reqwest::ClientBuilder;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use zeroize::Zeroizing;
+
+use crate::plugins::proxy::ProxyConfig;
+use crate::{Annotations, Decrypter, Encrypter, Getter, ProviderSettings, SecretBytes};
+use crate::{Error, Result};
+
+use super::annotations::{VaultKvAnnotations, VaultProviderSettings, VaultTransitAnnotations};
+use super::credential::{Credential, TokenSource};
+
+pub struct VaultClient {
+    http_client: reqwest::Client,
+    credential: Credential,
+    settings: VaultProviderSettings,
+}
+
+#[derive(Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct EncryptData {
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct DecryptData {
+    plaintext: String,
+}
+
+#[derive(Deserialize)]
+struct KvData {
+    data: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+impl VaultClient {
+    fn new(mut settings: VaultProviderSettings, credential: Credential) -> Result<Self> {
+        let http_client = ProxyConfig::new()
+            .apply(ClientBuilder::new())
+            .map_err(|e| Error::VaultError(format!("set proxy failed: {e}")))?
+            .build()
+            .map_err(|e| Error::VaultError(format!("build http client failed: {e}")))?;
+        settings.address = settings.address.trim_end_matches('/').to_string();
+
+        Ok(Self {
+            http_client,
+            credential,
+            settings,
+        })
+    }
+
+    fn parse_provider_settings(
+        provider_settings: &ProviderSettings,
+    ) -> Result<VaultProviderSettings> {
+        serde_json::from_value(Value::Object(provider_settings.clone()))
+            .map_err(|e| Error::VaultError(format!("parse provider setting failed: {e}")))
+    }
+
+    /// Create a client with a given Vault token. This is used at the user
+    /// side. The `provider_settings` is the one that the in-pod client will
+    /// use to log in, which is exported by [`Self::export_provider_settings`].
+    pub fn new_with_token(provider_settings: &ProviderSettings, token: &str) -> Result<Self> {
+        let settings = Self::parse_provider_settings(provider_settings)?;
+        let credential = Credential::new(TokenSource::Static(Zeroizing::new(token.to_string())));
+        Self::new(settings, credential)
+    }
+
+    /// This new function is used by a in-pod client. The token is got by
+    /// logging in to Vault with the auth method given in the provider settings.
+    pub async fn from_provider_settings(provider_settings: &ProviderSettings) -> Result<Self> {
+        let settings = Self::parse_provider_settings(provider_settings)?;
+        let credential = Credential::new(TokenSource::Login(settings.auth.clone()));
+        Self::new(settings, credential)
+    }
+
+    /// The [`ProviderSettings`] of the address, the mounts and the auth method
+    /// of this client, for the in-pod client to log in with the same role.
+    pub fn export_provider_settings(&self) -> Result<ProviderSettings> {
+        let provider_settings = serde_json::to_value(&self.settings)
+            .map_err(|e| Error::VaultError(format!("serialize ProviderSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+
+        Ok(provider_settings)
+    }
+
+    async fn do_request(
+        &mut self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<Value> {
+        let token = Zeroizing::new(
+            self.credential
+                .get_token(
+                    &self.http_client,
+                    &self.settings.address,
+                    self.settings.namespace.as_deref(),
+                )
+                .await?,
+        );
+        let url = format!("{}/v1/{path}", self.settings.address);
+        let mut request = self
+            .http_client
+            .request(method, url)
+            .header("X-Vault-Token", token.as_str());
+        if let Some(namespace) = &self.settings.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            error!("vault: do request fail!");
+            let status = response.status();
+            let body_bytes = response.bytes().await?;
+            let error_msg = match serde_json::from_slice::<ErrorResponse>(&body_bytes) {
+                Ok(e) => format!("status code: {status}, errors: {}", e.errors.join("; ")),
+                Err(_) => format!(
+                    "status code: {status}, body: {}",
+                    String::from_utf8_lossy(&body_bytes)
+                ),
+            };
+            anyhow::bail!(error_msg);
+        }
+
+        Ok(response.json().await?)
+    }
+
+    /// The body of a Transit `decrypt` request of the Vault `ciphertext`.
+    fn decrypt_body(ciphertext: &[u8], annotations: VaultTransitAnnotations) -> Result<Value> {
+        let ciphertext = std::str::from_utf8(ciphertext)
+            .map_err(|e| Error::VaultError(format!("illegal vault ciphertext: {e}")))?;
+
+        let mut body = json!({ "ciphertext": ciphertext });
+        if let Some(context) = annotations.context {
+            body["context"] = json!(context);
+        }
+        Ok(body)
+    }
+
+    /// The KV-v2 path of the version of the secret `name` given by the
+    /// `annotations`, by default the latest one.
+    fn kv_path(&self, name: &str, annotations: &VaultKvAnnotations) -> String {
+        match annotations.version {
+            Some(version) => format!("{}/data/{name}?version={version}", self.settings.kv_mount),
+            None => format!("{}/data/{name}", self.settings.kv_mount),
+        }
+    }
+
+    /// The `field` of the secret `name` in a KV-v2 response, or else its
+    /// whole key-value map in JSON. A field not of a string is in JSON too.
+    fn kv_secret(name: &str, field: Option<String>, res: Value) -> Result<SecretBytes> {
+        let secret: VaultResponse<KvData> = serde_json::from_value(res)
+            .map_err(|e| Error::VaultError(format!("illegal get secret response: {e}")))?;
+
+        match field {
+            Some(field) => match secret.data.data.get(&field) {
+                Some(Value::String(value)) => Ok(value.clone().into()),
+                Some(value) => Ok(value.to_string().into()),
+                None => Err(Error::VaultError(format!(
+                    "field `{field}` not found in secret `{name}`"
+                ))),
+            },
+            None => serde_json::to_vec(&secret.data.data)
+                .map(SecretBytes::from)
+                .map_err(|e| Error::VaultError(format!("serialize secret failed: {e}"))),
+        }
+    }
+}
+
+#[async_trait]
+impl Encrypter for VaultClient {
+    /// The `key_id` is the name of the Transit key. The returned ciphertext
+    /// is the Vault ciphertext string like `vault:v1:...` in bytes.
+    async fn encrypt(&mut self, data: &[u8], key_id: &str) -> Result<(Vec<u8>, Annotations)> {
+        let path = format!("{}/encrypt/{key_id}", self.settings.transit_mount);
+        let body = json!({ "plaintext": STANDARD.encode(data) });
+        let res = self
+            .do_request(reqwest::Method::POST, &path, Some(body))
+            .await
+            .map_err(|e| Error::VaultError(format!("do request to vault failed: {e}")))?;
+        let encrypt_response: VaultResponse<EncryptData> = serde_json::from_value(res)
+            .map_err(|e| Error::VaultError(format!("illegal encrypt response: {e}")))?;
+
+        let annotations = serde_json::to_value(VaultTransitAnnotations::default())
+            .map_err(|e| Error::VaultError(format!("serialize SecretSettings failed: {e}")))?
+            .as_object()
+            .expect("must be an object")
+            .to_owned();
+        Ok((encrypt_response.data.ciphertext.into_bytes(), annotations))
+    }
+}
+
+#[async_trait]
+impl Decrypter for VaultClient {
+    async fn decrypt(
+        &mut self,
+        ciphertext: &[u8],
+        key_id: &str,
+        annotations: &Annotations,
+    ) -> Result<SecretBytes> {
+        let annotations: VaultTransitAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::VaultError(format!(
+                    "deserialize SecretSettings for decryption failed: {e}"
+                ))
+            })?;
+        let path = format!("{}/decrypt/{key_id}", self.settings.transit_mount);
+        let body = Self::decrypt_body(ciphertext, annotations)?;
+        let res = self
+            .do_request(reqwest::Method::POST, &path, Some(body))
+            .await
+            .map_err(|e| Error::VaultError(format!("do request to vault failed: {e}")))?;
+        let decrypt_response: VaultResponse<DecryptData> = serde_json::from_value(res)
+            .map_err(|e| Error::VaultError(format!("illegal decrypt response: {e}")))?;
+        let plaintext = STANDARD
+            .decode(decrypt_response.data.plaintext)
+            .map_err(|e| Error::VaultError(format!("decode plaintext failed: {e}")))?;
+        Ok(plaintext.into())
+    }
+}
+
+#[async_trait]
+impl Getter for VaultClient {
+    /// The `name` is the path of the secret inside the KV-v2 secrets engine.
+    async fn get_secret(&mut self, name: &str, annotations: &Annotations) -> Result<SecretBytes> {
+        let annotations: VaultKvAnnotations =
+            serde_json::from_value(Value::Object(annotations.clone())).map_err(|e| {
+                Error::VaultError(format!(
+                    "deserialize annotations for get secret failed: {e}"
+                ))
+            })?;
+
+        let path = self.kv_path(name, &annotations);
+        let res = self
+            .do_request(reqwest::Method::GET, &path, None)
+            .await
+            .map_err(|e| Error::VaultError(format!("do request to vault failed: {e}")))?;
+        Self::kv_secret(name, annotations.field, res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    use super::VaultClient;
+    use crate::plugins::vault::annotations::{VaultKvAnnotations, VaultTransitAnnotations};
+
+    #[rstest]
+    #[case(json!({
+        "address": "https://vault.example.com:8200",
+        "kv_mount": "secret",
+        "transit_mount": "transit",
+        "auth_method": "approle",
+        "role_id": "db02de05-fa39-4855-059b-67221c5c2f63",
+        "auth_mount": "approle",
+    }))]
+    #[case(json!({
+        "address": "https://vault.example.com:8200",
+        "namespace": "ns1",
+        "kv_mount": "kv",
+        "transit_mount": "transit",
+        "auth_method": "kubernetes",
+        "role": "my-role",
+        "auth_mount": "kubernetes",
+    }))]
+    #[tokio::test]
+    async fn export_provider_settings(#[case] provider_settings: Value) {
+        let provider_settings = provider_settings.as_object().unwrap().to_owned();
+        let client = VaultClient::from_provider_settings(&provider_settings)
+            .await
+            .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+        assert_eq!(exported, provider_settings);
+    }
+
+    #[tokio::test]
+    async fn default_provider_settings() {
+        let provider_settings = json!({
+            "address": "https://vault.example.com:8200/",
+            "auth_method": "kubernetes",
+            "role": "my-role",
+        });
+        let provider_settings = provider_settings.as_object().unwrap().to_owned();
+        let client = VaultClient::from_provider_settings(&provider_settings)
+            .await
+            .expect("create client");
+        let exported = client
+            .export_provider_settings()
+            .expect("export provider settings");
+        assert_eq!(
+            Value::Object(exported),
+            json!({
+                "address": "https://vault.example.com:8200",
+                "kv_mount": "secret",
+                "transit_mount": "transit",
+                "auth_method": "kubernetes",
+                "role": "my-role",
+                "auth_mount": "kubernetes",
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(b"vault:v1:Y2lwaGVy", None, Some(json!({ "ciphertext": "vault:v1:Y2lwaGVy" })))]
+    #[case(
+        b"vault:v1:Y2lwaGVy",
+        Some("Y29udGV4dA=="),
+        Some(json!({ "ciphertext": "vault:v1:Y2lwaGVy", "context": "Y29udGV4dA==" }))
+    )]
+    #[case(b"\xff\xfe", None, None)]
+    fn decrypt_body(
+        #[case] ciphertext: &[u8],
+        #[case] context: Option<&str>,
+        #[case] expected: Option<Value>,
+    ) {
+        let annotations = VaultTransitAnnotations {
+            context: context.map(String::from),
+        };
+        let body = VaultClient::decrypt_body(ciphertext, annotations);
+        assert_eq!(body.ok(), expected);
+    }
+
+    #[rstest]
+    #[case(None, "kv/data/app/db")]
+    #[case(Some(2), "kv/data/app/db?version=2")]
+    fn kv_path(#[case] version: Option<u64>, #[case] expected: &str) {
+        let provider_settings = json!({
+            "address": "https://vault.example.com:8200",
+            "kv_mount": "kv",
+            "auth_method": "kubernetes",
+            "role": "my-role",
+        });
+        let client = VaultClient::new_with_token(provider_settings.as_object().unwrap(), "token")
+            .expect("create client");
+        let annotations = VaultKvAnnotations {
+            version,
+            field: None,
+        };
+        assert_eq!(client.kv_path("app/db", &annotations), expected);
+    }
+
+    #[rstest]
+    #[case(Some("password"), Some(&b"p@ss"[..]))]
+    #[case(Some("port"), Some(&b"5432"[..]))]
+    #[case(Some("user"), None)]
+    #[case(None, Some(&br#"{"password":"p@ss","port":5432}"#[..]))]
+    fn kv_secret(#[case] field: Option<&str>, #[case] expected: Option<&[u8]>) {
+        let res = json!({
+            "data": {
+                "data": { "password": "p@ss", "port": 5432 },
+                "metadata": { "version": 2 },
+            },
+        });
+        let secret = VaultClient::kv_secret("app/db", field.map(String::from), res);
+        assert_eq!(secret.ok().as_deref(), expected);
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Access tokens to access HashiCorp Vault.
+//!
+//! Two auth methods are supported:
+//! - AppRole: log in with a role id and a secret id, see
+//!   <https://developer.hashicorp.com/vault/docs/auth/approle>
+//! - Kubernetes: log in with the service account token of the pod, see
+//!   <https://developer.hashicorp.com/vault/docs/auth/kubernetes>
+
+use anyhow::*;
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::fs;
+use zeroize::Zeroizing;
+
+use crate::plugins::credential_dir;
+
+use super::annotations::AuthMethod;
+
+const KUBERNETES_SERVICE_ACCOUNT_TOKEN_PATH: &str =
+    "/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// A cached token will be refreshed if it expires within this many seconds.
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+pub(crate) enum TokenSource {
+    /// Log in to Vault with the given auth method.
+    Login(AuthMethod),
+
+    /// A token given directly by the user, used at the user side.
+    Static(Zeroizing<String>),
+}
+
+struct AccessToken {
+    token: Zeroizing<String>,
+    /// `None` means the token never expires
+    expires_on: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: String,
+    /// In seconds. `0` means the token never expires.
+    #[serde(default)]
+    lease_duration: i64,
+}
+
+pub(crate) struct Credential {
+    source: TokenSource,
+    cached: Option<AccessToken>,
+}
+
+impl Credential {
+    pub(crate) fn new(source: TokenSource) -> Self {
+        Self {
+            source,
+            cached: None,
+        }
+    }
+
+    /// Get a valid Vault token. A cached token is reused until it is about
+    /// to expire.
+    pub(crate) async fn get_token(
+        &mut self,
+        http_client: &reqwest::Client,
+        address: &str,
+        namespace: Option<&str>,
+    ) -> Result<String> {
+        let auth = match &self.source {
+            TokenSource::Static(token) => return Ok(token.to_string()),
+            TokenSource::Login(auth) => auth,
+        };
+
+        if let Some(cached) = &self.cached {
+            let valid = match cached.expires_on {
+                Some(expires_on) => expires_on - Utc::now().timestamp() > TOKEN_REFRESH_MARGIN_SECS,
+                None => true,
+            };
+            if valid {
+                return Ok(cached.token.to_string());
+            }
+        }
+
+        let (auth_mount, body) = match auth {
+            AuthMethod::Approle {
+                role_id,
+                auth_mount,
+            } => {
+                let secret_id_path =
+                    format!("{}/approle_secret_id_{role_id}", credential_dir("vault"));
+                let secret_id = Zeroizing::new(
+                    fs::read_to_string(&secret_id_path)
+                        .await
+                        .context(format!("read AppRole secret id from {secret_id_path}"))?,
+                );
+                (
+                    auth_mount,
+                    json!({"role_id": role_id, "secret_id": secret_id.trim()}),
+                )
+            }
+            AuthMethod::Kubernetes { role, auth_mount } => {
+                let jwt = Zeroizing::new(
+                    fs::read_to_string(KUBERNETES_SERVICE_ACCOUNT_TOKEN_PATH)
+                        .await
+                        .context("read kubernetes service account token")?,
+                );
+                (auth_mount, json!({"role": role, "jwt": jwt.trim()}))
+            }
+        };
+
+        let mut request = http_client
+            .post(format!("{address}/v1/auth/{auth_mount}/login"))
+            .json(&body);
+        if let Some(namespace) = namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request.send().await.context("request to log in to Vault")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!("log in to Vault failed, status code: {status}, body: {body}");
+        }
+
+        let token = parse_login_response(&response.bytes().await?)?;
+        let res = token.token.to_string();
+        self.cached = Some(token);
+        Ok(res)
+    }
+}
+
+fn parse_login_response(body: &[u8]) -> Result<AccessToken> {
+    let response: LoginResponse =
+        serde_json::from_slice(body).context("illegal Vault login response")?;
+    let expires_on = match response.auth.lease_duration {
+        0 => None,
+        lease_duration => Some(Utc::now().timestamp() + lease_duration),
+    };
+    Ok(AccessToken {
+        token: Zeroizing::new(response.auth.client_token),
+        expires_on,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_login_response;
+
+    #[test]
+    fn login_response() {
+        let body = br#"{
+            "auth": {
+                "renewable": true,
+                "lease_duration": 2764800,
+                "metadata": {},
+                "policies": ["default", "dev-policy"],
+                "accessor": "5d7fb475-07cb-4060-c2de-1ca3fcbf0c56",
+                "client_token": "98a4c7ab-b1fe-361b-ba0b-e307aacfd587"
+            }
+        }"#;
+        let token = parse_login_response(body).expect("parse login response");
+        assert_eq!(*token.token, "98a4c7ab-b1fe-361b-ba0b-e307aacfd587");
+        assert!(token.expires_on.is_some());
+
+        let token = parse_login_response(br#"{"auth": {"client_token": "a"}}"#)
+            .expect("parse login response");
+        assert!(token.expires_on.is_none());
+
+        assert!(parse_login_response(br#"{"auth": null}"#).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! This is a HashiCorp Vault implementation.
+//!
+//! The Transit secrets engine is used to support envelope decryption, and the
+//! KV version 2 secrets engine is used to support getting secrets. The access
+//! token is got by logging in with the AppRole or Kubernetes auth method.
+//! The product details can be found here: <https://developer.hashicorp.com/vault/docs>.
+
+mod annotations;
+mod client;
+mod credential;
+
+pub use client::VaultClient;
@@ -0,0 +1,228 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Buffers of the plaintext secrets, e.g. the keys decrypted by the KMSes,
+//! the secrets got from the vaults and the unsealed secrets.
+//!
+//! The bytes of a [`SecretBytes`] live in pages of their own, which are
+//! locked in memory so that they are never swapped out, and excluded from
+//! the core dumps. The pages are zeroed before they are freed. Locking is
+//! best-effort, e.g. it fails if `RLIMIT_MEMLOCK` is exceeded, in which case
+//! the secret is still zeroed on drop.
+//!
+//! A secret is copied into a [`SecretBytes`] once it is got, and the
+//! original buffer is zeroed if it is owned, e.g. by `From<Vec<u8>>`.
+
+use std::{
+    alloc::{self, Layout},
+    ffi::c_void,
+    fmt,
+    ops::Deref,
+    ptr::NonNull,
+    sync::Once,
+};
+
+use nix::sys::mman::{madvise, mlock, munlock, MmapAdvise};
+use zeroize::{Zeroize, Zeroizing};
+
+static LOCK_FAILED: Once = Once::new();
+
+/// Bytes of a plaintext secret, locked in memory and zeroed on drop.
+pub struct SecretBytes {
+    ptr: NonNull<u8>,
+    len: usize,
+
+    /// The size of the pages allocated, zero if none is.
+    size: usize,
+}
+
+// SAFETY: `SecretBytes` owns its bytes exclusively, like a `Vec<u8>`.
+unsafe impl Send for SecretBytes {}
+unsafe impl Sync for SecretBytes {}
+
+impl SecretBytes {
+    /// Copy the `bytes` into a new secret buffer.
+    pub fn new(bytes: &[u8]) -> Self {
+        if bytes.is_empty() {
+            return Self::default();
+        }
+
+        let page_size = page_size();
+        let size = bytes.len().div_ceil(page_size) * page_size;
+        let layout = Layout::from_size_align(size, page_size).expect("illegal secret layout");
+
+        // SAFETY: `layout` is of non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            alloc::handle_alloc_error(layout);
+        };
+
+        // SAFETY: the pages of `size` at `ptr` are allocated above, and
+        // owned by this buffer only.
+        unsafe {
+            let addr = ptr.as_ptr() as *mut c_void;
+            if let Err(e) = mlock(addr, size) {
+                LOCK_FAILED.call_once(|| {
+                    log::warn!("Failed to lock secrets in memory, they may be swapped out: {e}")
+                });
+            }
+            let _ = madvise(addr, size, MmapAdvise::MADV_DONTDUMP);
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr.as_ptr(), bytes.len());
+        }
+
+        Self {
+            ptr,
+            len: bytes.len(),
+            size,
+        }
+    }
+}
+
+impl Default for SecretBytes {
+    fn default() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            size: 0,
+        }
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        if self.size == 0 {
+            return;
+        }
+
+        // SAFETY: the pages are allocated by `new` with the same layout,
+        // and are not used after.
+        unsafe {
+            std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.size).zeroize();
+            let addr = self.ptr.as_ptr() as *mut c_void;
+            let _ = madvise(addr, self.size, MmapAdvise::MADV_DODUMP);
+            let _ = munlock(addr, self.size);
+            alloc::dealloc(
+                self.ptr.as_ptr(),
+                Layout::from_size_align_unchecked(self.size, page_size()),
+            );
+        }
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: `ptr` is valid for `len` bytes, or dangling with `len` 0.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Clone for SecretBytes {
+    fn clone(&self) -> Self {
+        Self::new(self)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::from(Zeroizing::new(bytes))
+    }
+}
+
+impl From<Zeroizing<Vec<u8>>> for SecretBytes {
+    fn from(bytes: Zeroizing<Vec<u8>>) -> Self {
+        Self::new(&bytes)
+    }
+}
+
+impl From<String> for SecretBytes {
+    fn from(string: String) -> Self {
+        Self::from(string.into_bytes())
+    }
+}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {}])", self.len)
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl PartialEq<[u8]> for SecretBytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        **self == *other
+    }
+}
+
+impl PartialEq<&[u8]> for SecretBytes {
+    fn eq(&self, other: &&[u8]) -> bool {
+        **self == **other
+    }
+}
+
+impl<const N: usize> PartialEq<&[u8; N]> for SecretBytes {
+    fn eq(&self, other: &&[u8; N]) -> bool {
+        **self == other[..]
+    }
+}
+
+fn page_size() -> usize {
+    // SAFETY: `sysconf` has no side effects.
+    match unsafe { nix::libc::sysconf(nix::libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{page_size, SecretBytes};
+
+    #[test]
+    fn secret_bytes() {
+        let secret = SecretBytes::from(b"secret".to_vec());
+        assert_eq!(secret, b"secret");
+        assert_eq!(secret.len(), 6);
+        assert_eq!(secret.ptr.as_ptr() as usize % page_size(), 0);
+        assert_eq!(secret.size, page_size());
+        assert_eq!(format!("{secret:?}"), "SecretBytes([REDACTED; 6])");
+
+        let cloned = secret.clone();
+        assert_eq!(cloned, secret);
+        assert_ne!(cloned.ptr, secret.ptr);
+
+        let large = SecretBytes::new(&vec![7; page_size() + 1]);
+        assert_eq!(large.size, 2 * page_size());
+        assert!(large.iter().all(|byte| *byte == 7));
+    }
+
+    #[test]
+    fn empty_secret_bytes() {
+        let secret = SecretBytes::from(String::new());
+        assert!(secret.is_empty());
+        assert_eq!(secret.size, 0);
+        assert_eq!(secret, SecretBytes::default());
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Settings of the plugins given by the process embedding them, e.g. from
+//! the config file of CDH, by [`set_settings`] at startup.
+//!
+//! A setting given here takes precedence over the config files, the env and
+//! the kernel commandline that the plugins read by themselves, which are
+//! still used for the settings not given.
+
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+
+use crate::plugins::{
+    kbs::{CachePolicy, RetryPolicy},
+    proxy::ProxyConfig,
+};
+
+/// Paths of the PEM files used to connect to the KBS, see the KBS TLS
+/// configuration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KbsTlsPaths {
+    pub root_ca: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// `aa_kbc_params` in format `<kbc_name>::<kbs_host>`.
+    pub aa_kbc_params: Option<String>,
+
+    /// TLS configuration of the connections to the KBS.
+    pub kbs_tls: Option<KbsTlsPaths>,
+
+    /// HTTP(S) proxy of the connections to the KBS and the KMSes.
+    pub proxy: Option<ProxyConfig>,
+
+    /// Default [`RetryPolicy`] of the KBC clients whose provider settings
+    /// give none.
+    pub retry: Option<RetryPolicy>,
+
+    /// Default [`CachePolicy`] of the KBC clients whose provider settings
+    /// give none.
+    pub cache: Option<CachePolicy>,
+
+    /// Directory of the credentials of the KMS plugins, in which each
+    /// plugin reads its own subdirectory, e.g. `<dir>/aliyun`.
+    pub credential_dir: Option<String>,
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<Settings> = RwLock::new(Settings::default());
+}
+
+/// Set the [`Settings`] of the plugins. It should be called before any
+/// plugin is created, as the clients created before keep the old settings.
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.write().expect("settings lock poisoned") = settings;
+}
+
+/// Get the [`Settings`] of the plugins.
+pub(crate) fn settings() -> Settings {
+    SETTINGS.read().expect("settings lock poisoned").clone()
+}
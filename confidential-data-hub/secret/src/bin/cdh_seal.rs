@@ -0,0 +1,162 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! `cdh-seal` creates sealed secrets for any KMS/Vault provider supported
+//! by CDH. The KMS client is created from the given provider settings in
+//! the same way as CDH does, so the credentials are read from the same
+//! paths, see the docs of the providers.
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use clap::{Args, Parser, Subcommand};
+use kms::ProviderSettings;
+use secret::secret::{layout::vault::VaultSecret, Secret, SecretContent, VERSION, VERSION_2};
+use tokio::fs;
+use zeroize::Zeroizing;
+
+/// Protected header of the unsigned JWS of the sealed secret.
+const JWS_HEADER: &str = r#"{"alg":"none"}"#;
+
+#[derive(Parser)]
+#[command(name = "cdh-seal")]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// print the json of the sealed secret rather than the JWS that CDH
+    /// unseals
+    #[arg(long)]
+    json: bool,
+
+    /// path to write the sealed secret to, or stdout if not given
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[command(subcommand)]
+    r#type: TypeArgs,
+}
+
+#[derive(Subcommand)]
+enum TypeArgs {
+    /// Encrypt the plaintext into an envelope secret
+    Envelope(EnvelopeArgs),
+
+    /// Refer to a secret stored in a vault
+    Vault(VaultArgs),
+}
+
+#[derive(Args)]
+struct ProviderArgs {
+    /// name of the provider, e.g. `aliyun`, `aws` or `kbs`
+    #[arg(short, long)]
+    provider: String,
+
+    /// path of the json file of the provider settings
+    #[arg(long)]
+    provider_settings: Option<String>,
+}
+
+#[derive(Args)]
+struct EnvelopeArgs {
+    #[command(flatten)]
+    provider: ProviderArgs,
+
+    /// id of the key in the KMS to seal the encryption key
+    #[arg(short, long)]
+    key_id: String,
+
+    /// path of the file which contains the plaintext to be sealed
+    #[arg(short, long)]
+    file_path: String,
+
+    /// seal in the version 0.2.0 format, in which all the fields of the
+    /// secret are authenticated together with the encrypted data
+    #[arg(long)]
+    authenticated: bool,
+}
+
+#[derive(Args)]
+struct VaultArgs {
+    #[command(flatten)]
+    provider: ProviderArgs,
+
+    /// name of the secret in the vault
+    #[arg(short, long)]
+    name: String,
+
+    /// path of the json file of the annotations to get the secret
+    #[arg(long)]
+    annotations: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let secret = match cli.r#type {
+        TypeArgs::Envelope(args) => seal_envelope(args).await,
+        TypeArgs::Vault(args) => Secret {
+            version: VERSION.into(),
+            r#type: SecretContent::Vault(VaultSecret {
+                name: args.name,
+                provider: args.provider.provider,
+                provider_settings: read_json_object(args.provider.provider_settings.as_deref())
+                    .await,
+                annotations: read_json_object(args.annotations.as_deref()).await,
+            }),
+        },
+    };
+
+    let json = serde_json::to_string(&secret).expect("serialize sealed secret failed");
+    let output = if cli.json {
+        json
+    } else {
+        format!(
+            "{}.{}.",
+            URL_SAFE_NO_PAD.encode(JWS_HEADER),
+            STANDARD.encode(json)
+        )
+    };
+    match cli.output {
+        Some(path) => fs::write(path, output)
+            .await
+            .expect("write sealed secret failed"),
+        None => println!("{output}"),
+    }
+}
+
+async fn seal_envelope(args: EnvelopeArgs) -> Secret {
+    let plaintext = Zeroizing::new(
+        fs::read(&args.file_path)
+            .await
+            .expect("read plaintext failed"),
+    );
+    let provider_settings = read_json_object(args.provider.provider_settings.as_deref()).await;
+    let version = if args.authenticated {
+        VERSION_2
+    } else {
+        VERSION
+    };
+    Secret::seal_envelope(
+        version,
+        args.provider.provider,
+        provider_settings,
+        args.key_id,
+        &plaintext,
+    )
+    .await
+    .expect("seal secret failed")
+}
+
+/// Read a json object from the file of `path`, or an empty one if no path
+/// is given. Both [`ProviderSettings`] and [`kms::Annotations`] are json
+/// objects.
+async fn read_json_object(path: Option<&str>) -> ProviderSettings {
+    let Some(path) = path else {
+        return ProviderSettings::new();
+    };
+
+    let content = fs::read(path).await.expect("read json file failed");
+    serde_json::from_slice(&content).expect("illegal json object")
+}
@@ -6,9 +6,9 @@
 use base64::{engine::general_purpose::STANDARD, Engine};
 use clap::{command, Args, Parser, Subcommand};
 use crypto::WrapType;
-use kms::{plugins::aliyun::AliyunKmsClient, Encrypter, ProviderSettings};
+use kms::{plugins::aliyun::AliyunKmsClient, DataKey, Encrypter, ProviderSettings};
 use rand::Rng;
-use secret::secret::{layout::envelope::Envelope, Secret, SecretContent, VERSION};
+use secret::secret::{layout::envelope::Envelope, Secret, SecretContent, VERSION, VERSION_2};
 use tokio::fs;
 use zeroize::Zeroizing;
 
@@ -31,6 +31,11 @@ struct SealArgs {
     #[arg(short, long)]
     file_path: String,
 
+    /// seal in the version 0.2.0 format, in which all the fields of the
+    /// secret are authenticated together with the encrypted data
+    #[arg(long)]
+    authenticated: bool,
+
     /// Type of the Secret, i.e. `vault` or `envelope`
     #[command(subcommand)]
     r#type: TypeArgs,
@@ -91,45 +96,50 @@ async fn main() {
             let blob = fs::read(para.file_path)
                 .await
                 .expect("failed to read sealed secret");
-            let sc = match &para.r#type {
+            let version = if para.authenticated {
+                VERSION_2
+            } else {
+                VERSION
+            };
+            let secret = match &para.r#type {
                 TypeArgs::Envelope(env) => {
                     let (mut encrypter, provider_settings, provider) =
                         handle_envelope_provider(&env.command).await;
                     let mut iv = [0u8; 12];
                     rand::thread_rng().fill(&mut iv);
-                    let mut key = [0u8; 32];
-                    rand::thread_rng().fill(&mut key);
-                    let encrypted_data = crypto::encrypt(
-                        Zeroizing::new(key.to_vec()),
-                        blob,
-                        iv.to_vec(),
-                        WrapType::Aes256Gcm,
-                    )
-                    .expect("encryption failed");
-
-                    let (encrypted_key, annotations) = encrypter
-                        .encrypt(&key, &env.key_id)
+                    let key = DataKey::generate(encrypter.as_mut(), &env.key_id)
                         .await
                         .expect("encrypt the key using kms failed");
 
-                    SecretContent::Envelope(Envelope {
-                        key_id: env.key_id.clone(),
-                        encrypted_key: STANDARD.encode(encrypted_key),
-                        encrypted_data: STANDARD.encode(encrypted_data),
-                        wrap_type: WrapType::Aes256Gcm,
-                        iv: STANDARD.encode(iv),
-                        provider,
-                        provider_settings,
-                        annotations,
-                    })
+                    let mut secret = Secret {
+                        version: version.into(),
+                        r#type: SecretContent::Envelope(Envelope {
+                            key_id: env.key_id.clone(),
+                            encrypted_key: STANDARD.encode(key.encrypted_key),
+                            encrypted_data: String::new(),
+                            wrap_type: WrapType::Aes256Gcm,
+                            iv: STANDARD.encode(iv),
+                            provider,
+                            provider_settings,
+                            annotations: key.annotations,
+                        }),
+                    };
+                    let key = Zeroizing::new(key.plaintext.to_vec());
+                    let encrypted_data = if para.authenticated {
+                        let aad = secret.aad().expect("get aad of the secret failed");
+                        crypto::encrypt_with_aad(key, blob, iv.to_vec(), &aad, WrapType::Aes256Gcm)
+                    } else {
+                        crypto::encrypt(key, blob, iv.to_vec(), WrapType::Aes256Gcm)
+                    }
+                    .expect("encryption failed");
+                    if let SecretContent::Envelope(envelope) = &mut secret.r#type {
+                        envelope.encrypted_data = STANDARD.encode(encrypted_data);
+                    }
+                    secret
                 }
                 TypeArgs::Vault => todo!(),
             };
 
-            let secret = Secret {
-                version: VERSION.into(),
-                r#type: sc,
-            };
             let json = serde_json::to_string(&secret).expect("serialize sealed secret failed");
             println!("{json}");
         }
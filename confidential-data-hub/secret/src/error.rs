@@ -9,6 +9,12 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error("illegal sealed secret: {0}")]
+    IllegalSecret(String),
+
+    #[error("seal envelope secret failed: {0}")]
+    SealEnvelopeFailed(String),
+
     #[error("unseal envelope secret failed: {0}")]
     UnsealEnvelopeFailed(String),
 
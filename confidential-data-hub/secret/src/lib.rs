@@ -3,9 +3,32 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+//! # Sealed secrets
+//!
+//! The core of this crate is the format of the sealed secrets: parsing them
+//! by [`Secret::from_sealed`](secret::Secret::from_sealed), checking their
+//! structure by [`Secret::validate`](secret::Secret::validate) and decrypting
+//! an envelope by its data encryption key by
+//! [`Envelope::open`](secret::layout::envelope::Envelope::open). It does not
+//! depend on the KMS providers and builds for `wasm32-unknown-unknown`, so
+//! that the policy tools and the admission webhooks check the sealed secrets
+//! by the same code as CDH.
+//!
+//! The feature `unseal`, enabled by default, adds the sealing and the
+//! unsealing through the KMS providers.
+
 pub mod error;
 pub mod secret;
 
 pub use error::*;
 
-pub use kms::{Annotations, ProviderSettings};
+#[cfg(feature = "unseal")]
+pub use kms::SecretBytes;
+
+/// Extra information to create the client of a provider, the same type as
+/// `kms::ProviderSettings`.
+pub type ProviderSettings = serde_json::Map<String, serde_json::Value>;
+
+/// Provider specific fields of a secret, the same type as
+/// `kms::Annotations`.
+pub type Annotations = serde_json::Map<String, serde_json::Value>;
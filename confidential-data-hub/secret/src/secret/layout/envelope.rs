@@ -3,15 +3,18 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-pub use kms::Annotations;
+pub use crate::Annotations;
 
 use base64::{engine::general_purpose::STANDARD, Engine};
 use crypto::WrapType;
-use kms::ProviderSettings;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "unseal")]
+use tracing::{info_span, instrument, Instrument};
 use zeroize::Zeroizing;
 
-use crate::{Error, Result};
+#[cfg(feature = "unseal")]
+use crate::SecretBytes;
+use crate::{Error, ProviderSettings, Result};
 
 /// An Envelope is a secret encrypted by digital envelope mechanism.
 /// It can be described as
@@ -23,7 +26,8 @@ use crate::{Error, Result};
 /// The fields inside this Struct will be flattened in a Secret wrapper.
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct Envelope {
-    /// key id to locate the key inside KMS
+    /// key id to locate the key inside KMS, can be namespaced as
+    /// `<provider>://<key id>`
     pub key_id: String,
 
     /// Encrypted DEK by key inside KMS
@@ -38,43 +42,175 @@ pub struct Envelope {
     /// IV of encrypted_data, if used
     pub iv: String,
 
-    /// decryptor driver of the secret
+    /// decryptor driver of the secret, can be omitted if `key_id` is
+    /// namespaced
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub provider: String,
 
     /// extra information to create a client
+    #[serde(default)]
     pub provider_settings: ProviderSettings,
 
     /// KMS specific fields to locate the Key inside KMS
+    #[serde(default)]
     pub annotations: Annotations,
 }
 
 impl Envelope {
-    pub(crate) async fn unseal(&self) -> Result<Vec<u8>> {
+    /// Check the structure of the envelope without unsealing it, i.e. the
+    /// provider can be decided and the fields are well encoded.
+    pub fn validate(&self) -> Result<()> {
+        super::route(&self.provider, &self.key_id).map_err(Error::IllegalSecret)?;
+        for (field, value) in [
+            ("encrypted_key", &self.encrypted_key),
+            ("encrypted_data", &self.encrypted_data),
+        ] {
+            STANDARD
+                .decode(value)
+                .map_err(|e| Error::IllegalSecret(format!("base64 decode {field} failed: {e}")))?;
+        }
+        self.decode_iv().map_err(Error::IllegalSecret)?;
+
+        Ok(())
+    }
+
+    /// Decrypt the `encrypted_data` by the plaintext data encryption key
+    /// `dek`, e.g. got from the KMS. If `aad` is given, the `encrypted_data`
+    /// must be authenticated together with it, see
+    /// [`Secret::aad`](crate::secret::Secret::aad).
+    pub fn open(&self, dek: &[u8], aad: Option<&[u8]>) -> Result<Zeroizing<Vec<u8>>> {
+        let dek = Zeroizing::new(dek.to_vec());
+        let iv = self.decode_iv().map_err(Error::UnsealEnvelopeFailed)?;
+        let encrypted_data = STANDARD.decode(&self.encrypted_data).map_err(|e| {
+            Error::UnsealEnvelopeFailed(format!("base64 decode encrypted_data failed: {e}"))
+        })?;
+        let plaintext = match aad {
+            Some(aad) => {
+                crypto::decrypt_with_aad(dek, encrypted_data, iv, aad, self.wrap_type.clone())
+            }
+            None => crypto::decrypt(dek, encrypted_data, iv, self.wrap_type.clone()),
+        }
+        .map_err(|e| Error::UnsealEnvelopeFailed(format!("decrypt envelope failed: {e}")))?;
+
+        Ok(Zeroizing::new(plaintext))
+    }
+
+    /// Decode the `iv` and check its length for the `wrap_type`, as the
+    /// ciphers panic on an illegal one.
+    fn decode_iv(&self) -> std::result::Result<Vec<u8>, String> {
+        let iv = STANDARD
+            .decode(&self.iv)
+            .map_err(|e| format!("base64 decode iv failed: {e}"))?;
+        let expected = match self.wrap_type {
+            WrapType::Aes256Gcm => 12,
+            WrapType::Aes256Ctr => 16,
+        };
+        if iv.len() != expected {
+            return Err(format!(
+                "iv of {} must be {expected} bytes, got {}",
+                self.wrap_type.as_ref(),
+                iv.len()
+            ));
+        }
+
+        Ok(iv)
+    }
+
+    /// Unseal the envelope. If `aad` is given, the `encrypted_data` must be
+    /// authenticated together with it, see [`Secret::aad`](crate::secret::Secret::aad).
+    #[cfg(feature = "unseal")]
+    #[instrument(skip_all, fields(provider = %self.provider, key_id = %self.key_id))]
+    pub(crate) async fn unseal(&self, aad: Option<&[u8]>) -> Result<SecretBytes> {
+        let (provider_name, key_id) =
+            super::route(&self.provider, &self.key_id).map_err(Error::UnsealEnvelopeFailed)?;
+
         // get encryption key
         let enc_dek = STANDARD.decode(&self.encrypted_key).map_err(|e| {
             Error::UnsealEnvelopeFailed(format!("base64 decode encrypted_key failed: {e}"))
         })?;
-        let mut provider = kms::new_decryptor(&self.provider, self.provider_settings.clone())
+        let mut provider = kms::new_decryptor(provider_name, self.provider_settings.clone())
             .await
             .map_err(|e| Error::UnsealEnvelopeFailed(format!("create provider failed: {e}")))?;
-        let dek = Zeroizing::new(
-            provider
-                .decrypt(&enc_dek, &self.key_id, &self.annotations)
-                .await
-                .map_err(|e| {
-                    Error::UnsealEnvelopeFailed(format!("decrypt encryption key failed: {e}"))
-                })?,
-        );
+        let dek = provider
+            .decrypt(&enc_dek, key_id, &self.annotations)
+            .instrument(info_span!("kms_decrypt"))
+            .await
+            .map_err(|e| {
+                Error::UnsealEnvelopeFailed(format!("decrypt encryption key failed: {e}"))
+            })?;
 
         // get plaintext of secret
-        let iv = STANDARD
-            .decode(&self.iv)
-            .map_err(|e| Error::UnsealEnvelopeFailed(format!("base64 decode iv failed: {e}")))?;
-        let encrypted_data = STANDARD.decode(&self.encrypted_data).map_err(|e| {
-            Error::UnsealEnvelopeFailed(format!("base64 decode encrypted_data failed: {e}"))
-        })?;
-        let plaintext = crypto::decrypt(dek, encrypted_data, iv, self.wrap_type.clone())
-            .map_err(|e| Error::UnsealEnvelopeFailed(format!("decrypt envelope failed: {e}")))?;
-        Ok(plaintext)
+        let plaintext = info_span!("decrypt_envelope").in_scope(|| self.open(&dek, aad))?;
+        Ok(SecretBytes::from(&plaintext[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use crypto::WrapType;
+    use rstest::rstest;
+    use zeroize::Zeroizing;
+
+    use crate::{Annotations, ProviderSettings};
+
+    use super::Envelope;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn envelope(wrap_type: WrapType, iv: &[u8], aad: Option<&[u8]>) -> Envelope {
+        let key = Zeroizing::new(KEY.to_vec());
+        let encrypted_data = match aad {
+            Some(aad) => crypto::encrypt_with_aad(
+                key,
+                b"secret".to_vec(),
+                iv.to_vec(),
+                aad,
+                wrap_type.clone(),
+            ),
+            None => crypto::encrypt(key, b"secret".to_vec(), iv.to_vec(), wrap_type.clone()),
+        }
+        .unwrap();
+        Envelope {
+            key_id: "aliyun://key-1".into(),
+            encrypted_key: STANDARD.encode(b"wrapped key"),
+            encrypted_data: STANDARD.encode(encrypted_data),
+            wrap_type,
+            iv: STANDARD.encode(iv),
+            provider: "".into(),
+            provider_settings: ProviderSettings::default(),
+            annotations: Annotations::default(),
+        }
+    }
+
+    #[rstest]
+    #[case(WrapType::Aes256Gcm, &[9; 12], None)]
+    #[case(WrapType::Aes256Gcm, &[9; 12], Some(&b"aad"[..]))]
+    #[case(WrapType::Aes256Ctr, &[9; 16], None)]
+    fn open(#[case] wrap_type: WrapType, #[case] iv: &[u8], #[case] aad: Option<&[u8]>) {
+        let envelope = envelope(wrap_type, iv, aad);
+        envelope.validate().unwrap();
+        assert_eq!(&envelope.open(&KEY, aad).unwrap()[..], b"secret");
+
+        // only the AEAD detects a wrong key
+        if envelope.wrap_type == WrapType::Aes256Gcm {
+            assert!(envelope.open(&[8; 32], aad).is_err());
+        }
+    }
+
+    #[test]
+    fn illegal_envelope() {
+        let mut illegal = envelope(WrapType::Aes256Gcm, &[9; 12], None);
+        illegal.iv = STANDARD.encode([9; 16]);
+        assert!(illegal.validate().is_err());
+        assert!(illegal.open(&KEY, None).is_err());
+
+        let mut illegal = envelope(WrapType::Aes256Gcm, &[9; 12], None);
+        illegal.key_id = "key-1".into();
+        assert!(illegal.validate().is_err());
+
+        let mut illegal = envelope(WrapType::Aes256Gcm, &[9; 12], None);
+        illegal.encrypted_key = "!!".into();
+        assert!(illegal.validate().is_err());
     }
 }
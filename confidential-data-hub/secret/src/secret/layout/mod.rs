@@ -5,3 +5,76 @@
 
 pub mod envelope;
 pub mod vault;
+
+/// Provider whose identifiers are already namespaced resource uris, like
+/// `kbs:///default/key/1`. The whole uri is given to its driver.
+const KBS_PROVIDER: &str = "kbs";
+
+/// Decide the provider to unseal a secret with, and the identifier to give
+/// to the driver of the provider.
+///
+/// An identifier (`key_id` or `name`) can be namespaced as
+/// `<provider>://<id>`. Then the `provider` field can be omitted. If a
+/// different `provider` is given, the identifier is not taken as namespaced,
+/// e.g. the url of a key.
+pub(crate) fn route<'a>(
+    provider: &'a str,
+    id: &'a str,
+) -> std::result::Result<(&'a str, &'a str), String> {
+    let Some((namespace, rest)) = split_namespace(id) else {
+        if provider.is_empty() {
+            return Err(format!(
+                "no provider is given, and the identifier {id} is not namespaced"
+            ));
+        }
+        return Ok((provider, id));
+    };
+
+    if !provider.is_empty() && !provider.eq_ignore_ascii_case(namespace) {
+        return Ok((provider, id));
+    }
+
+    if namespace.eq_ignore_ascii_case(KBS_PROVIDER) {
+        Ok((namespace, id))
+    } else {
+        Ok((namespace, rest))
+    }
+}
+
+/// Split `<namespace>://<id>`. The namespace is a provider name, which only
+/// consists of ascii alphanumerics, `-` and `_`.
+fn split_namespace(id: &str) -> Option<(&str, &str)> {
+    let (namespace, rest) = id.split_once("://")?;
+    let legal = !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    legal.then_some((namespace, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::route;
+
+    #[rstest]
+    #[case("aliyun", "key-1", Some(("aliyun", "key-1")))]
+    #[case("", "aliyun://key-1", Some(("aliyun", "key-1")))]
+    #[case("aliyun", "aliyun://key-1", Some(("aliyun", "key-1")))]
+    #[case("", "kbs:///default/key/1", Some(("kbs", "kbs:///default/key/1")))]
+    #[case("kbs", "kbs:///default/key/1", Some(("kbs", "kbs:///default/key/1")))]
+    #[case(
+        "vault",
+        "https://vault.example.com/key",
+        Some(("vault", "https://vault.example.com/key"))
+    )]
+    #[case("", "key-1", None)]
+    fn route_secret(
+        #[case] provider: &str,
+        #[case] id: &str,
+        #[case] expected: Option<(&str, &str)>,
+    ) {
+        assert_eq!(route(provider, id).ok(), expected);
+    }
+}
@@ -3,40 +3,76 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use kms::ProviderSettings;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "unseal")]
+use tracing::instrument;
 
-use crate::{Error, Result};
+#[cfg(feature = "unseal")]
+use crate::{secret::transform, SecretBytes};
+use crate::{
+    secret::transform::{Transform, TRANSFORMS_ANNOTATION},
+    Error, ProviderSettings, Result,
+};
 
-pub use kms::Annotations;
+pub use crate::Annotations;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct VaultSecret {
-    /// The id of this secret
+    /// The id of this secret, can be namespaced as `<provider>://<id>`
     pub name: String,
 
-    /// decryptor driver of the secret
+    /// decryptor driver of the secret, can be omitted if `name` is
+    /// namespaced
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub provider: String,
 
     /// extra information to create a client
+    #[serde(default)]
     pub provider_settings: ProviderSettings,
 
-    /// Other fields used to fetch the secret
+    /// Other fields used to fetch the secret. The annotation `transforms`
+    /// is the post-processing of the secret, see [`transform`].
+    #[serde(default)]
     pub annotations: Annotations,
 }
 
 impl VaultSecret {
-    pub(crate) async fn unseal(&self) -> Result<Vec<u8>> {
-        let mut provider = kms::new_getter(&self.provider, self.provider_settings.clone())
+    /// Check the structure of the vault secret without unsealing it, i.e.
+    /// the provider can be decided and the transformations are legal.
+    pub fn validate(&self) -> Result<()> {
+        super::route(&self.provider, &self.name).map_err(Error::IllegalSecret)?;
+        if let Some(transforms) = self.annotations.get(TRANSFORMS_ANNOTATION) {
+            Vec::<Transform>::deserialize(transforms).map_err(|e| {
+                Error::IllegalSecret(format!("illegal annotation `transforms`: {e}"))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "unseal")]
+    #[instrument(skip_all, fields(provider = %self.provider, name = %self.name))]
+    pub(crate) async fn unseal(&self) -> Result<SecretBytes> {
+        let (provider_name, name) =
+            super::route(&self.provider, &self.name).map_err(Error::UnsealVaultFailed)?;
+
+        let mut annotations = self.annotations.clone();
+        let transforms: Vec<Transform> = match annotations.remove(TRANSFORMS_ANNOTATION) {
+            Some(transforms) => serde_json::from_value(transforms).map_err(|e| {
+                Error::UnsealVaultFailed(format!("illegal annotation `transforms`: {e}"))
+            })?,
+            None => Vec::new(),
+        };
+
+        let mut provider = kms::new_getter(provider_name, self.provider_settings.clone())
             .await
             .map_err(|e| Error::UnsealVaultFailed(format!("create provider failed: {e}")))?;
 
-        let secret = provider
-            .get_secret(&self.name, &self.annotations)
-            .await
-            .map_err(|e| {
-                Error::UnsealVaultFailed(format!("get secret from provider failed: {e}"))
-            })?;
+        let secret = provider.get_secret(name, &annotations).await.map_err(|e| {
+            Error::UnsealVaultFailed(format!("get secret from provider failed: {e}"))
+        })?;
+        let secret = transform::apply(&transforms, secret)
+            .map_err(|e| Error::UnsealVaultFailed(format!("transform secret failed: {e}")))?;
         Ok(secret)
     }
 }
@@ -4,12 +4,25 @@
 //
 
 pub mod layout;
+pub mod transform;
 
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "unseal")]
+use crypto::WrapType;
+#[cfg(feature = "unseal")]
+use kms::DataKey;
+#[cfg(feature = "unseal")]
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+#[cfg(feature = "unseal")]
+use zeroize::Zeroizing;
 
 use self::layout::{envelope::Envelope, vault::VaultSecret};
 
 use crate::{Error, Result};
+#[cfg(feature = "unseal")]
+use crate::{ProviderSettings, SecretBytes};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type", rename_all = "lowercase")]
@@ -28,34 +41,218 @@ pub struct Secret {
 
 pub const VERSION: &str = "0.1.0";
 
+/// Version of the format whose whole structure is authenticated, see
+/// [`Secret::aad`]. Only envelope secrets are defined in this version.
+pub const VERSION_2: &str = "0.2.0";
+
+impl Secret {
+    /// Parse the sealed secret, in format `sealed.<JWS payload>` or the
+    /// whole JWS.
+    pub fn from_sealed(secret: &[u8]) -> Result<Self> {
+        // TODO: verify the jws signature using the key specified by `kid`
+        // in header. Here we directly get the JWS payload
+        let payload = secret
+            .split(|c| *c == b'.')
+            .nth(1)
+            .ok_or_else(|| Error::IllegalSecret("input sealed secret is not a JWS".into()))?;
+
+        let secret_json = STANDARD.decode(payload).map_err(|e| {
+            Error::IllegalSecret(format!("JWS body is not standard base64 encoded: {e}"))
+        })?;
+        serde_json::from_slice(&secret_json)
+            .map_err(|e| Error::IllegalSecret(format!("json deserialization failed: {e}")))
+    }
+
+    /// Check the structure of the secret without unsealing it, i.e. the
+    /// version and the type are supported, the provider can be decided and
+    /// the fields are well encoded. A valid secret can still fail to be
+    /// unsealed, e.g. by a wrong key.
+    pub fn validate(&self) -> Result<()> {
+        match (self.version.as_str(), &self.r#type) {
+            (VERSION | VERSION_2, SecretContent::Envelope(env)) => env.validate(),
+            (VERSION, SecretContent::Vault(v)) => v.validate(),
+            (VERSION_2, SecretContent::Vault(_)) => Err(Error::IllegalSecret(format!(
+                "vault secret is not defined in version {VERSION_2}"
+            ))),
+            (version, _) => Err(Error::IllegalSecret(format!(
+                "Unsupported secret version {version}. Only support {VERSION} and {VERSION_2} now."
+            ))),
+        }
+    }
+
+    /// The resource of the secret in the release policy of CDH, i.e. the key
+    /// id of an envelope secret or the name of a vault secret, as
+    /// `<provider>://<key id>` if it is not an uri.
+    pub fn resource(&self) -> String {
+        let (provider, id) = match &self.r#type {
+            SecretContent::Envelope(env) => (&env.provider, &env.key_id),
+            SecretContent::Vault(vault) => (&vault.provider, &vault.name),
+        };
+        if id.contains("://") {
+            return id.clone();
+        }
+
+        format!("{provider}://{id}")
+    }
+
+    #[cfg(feature = "unseal")]
+    pub async fn unseal(&self) -> Result<SecretBytes> {
+        match (self.version.as_str(), &self.r#type) {
+            (VERSION, SecretContent::Envelope(env)) => env.unseal(None).await,
+            (VERSION, SecretContent::Vault(v)) => v.unseal().await,
+            (VERSION_2, SecretContent::Envelope(env)) => env.unseal(Some(&self.aad()?)).await,
+            (VERSION_2, SecretContent::Vault(_)) => Err(Error::UnsealVaultFailed(format!(
+                "vault secret is not defined in version {VERSION_2}"
+            ))),
+            (version, _) => Err(Error::UnsealEnvelopeFailed(format!(
+                "Unsupported secret version {version}. Only support {VERSION} and {VERSION_2} now."
+            ))),
+        }
+    }
+
+    /// The additional authenticated data of a [`VERSION_2`] envelope secret,
+    /// which binds all the fields except `encrypted_data` to the AEAD
+    /// ciphertext, so that e.g. a tampered `annotations` fails the
+    /// decryption. It is the json of the secret without `encrypted_data`,
+    /// with sorted keys and no whitespaces.
+    pub fn aad(&self) -> Result<Vec<u8>> {
+        let mut secret = serde_json::to_value(self)
+            .map_err(|e| Error::UnsealEnvelopeFailed(format!("serialize secret failed: {e}")))?;
+        if let Value::Object(fields) = &mut secret {
+            fields.remove("encrypted_data");
+        }
+
+        Ok(canonicalize(secret).to_string().into_bytes())
+    }
+}
+
+#[cfg(feature = "unseal")]
 impl Secret {
-    pub async fn unseal(&self) -> Result<Vec<u8>> {
-        if self.version != VERSION {
-            return Err(Error::UnsealEnvelopeFailed(format!(
-                "Unsupported secret version {}. Only support {VERSION} now.",
-                self.version
+    /// Seal the `plaintext` into an envelope secret of `version`. A random
+    /// data encryption key is encrypted by the key `key_id` of the KMS
+    /// `provider`, and `key_id` can be namespaced as `<provider>://<key id>`.
+    pub async fn seal_envelope(
+        version: &str,
+        provider: String,
+        provider_settings: ProviderSettings,
+        key_id: String,
+        plaintext: &[u8],
+    ) -> Result<Self> {
+        if version != VERSION && version != VERSION_2 {
+            return Err(Error::SealEnvelopeFailed(format!(
+                "Unsupported secret version {version}. Only support {VERSION} and {VERSION_2} now."
             )));
         }
 
-        match &self.r#type {
-            SecretContent::Envelope(env) => env.unseal().await,
-            SecretContent::Vault(v) => v.unseal().await,
+        let (provider_name, kms_key_id) =
+            layout::route(&provider, &key_id).map_err(Error::SealEnvelopeFailed)?;
+        let mut encrypter = kms::new_encryptor(provider_name, provider_settings.clone())
+            .await
+            .map_err(|e| Error::SealEnvelopeFailed(format!("create provider failed: {e}")))?;
+
+        let mut iv = [0u8; 12];
+        rand::thread_rng().fill(&mut iv);
+        let key = DataKey::generate(encrypter.as_mut(), kms_key_id)
+            .await
+            .map_err(|e| {
+                Error::SealEnvelopeFailed(format!("encrypt encryption key failed: {e}"))
+            })?;
+
+        let mut secret = Secret {
+            version: version.into(),
+            r#type: SecretContent::Envelope(Envelope {
+                key_id,
+                encrypted_key: STANDARD.encode(key.encrypted_key),
+                encrypted_data: String::new(),
+                wrap_type: WrapType::Aes256Gcm,
+                iv: STANDARD.encode(iv),
+                provider,
+                provider_settings,
+                annotations: key.annotations,
+            }),
+        };
+
+        let key = Zeroizing::new(key.plaintext.to_vec());
+        let encrypted_data = if version == VERSION_2 {
+            let aad = secret.aad()?;
+            crypto::encrypt_with_aad(
+                key,
+                plaintext.to_vec(),
+                iv.to_vec(),
+                &aad,
+                WrapType::Aes256Gcm,
+            )
+        } else {
+            crypto::encrypt(key, plaintext.to_vec(), iv.to_vec(), WrapType::Aes256Gcm)
+        }
+        .map_err(|e| Error::SealEnvelopeFailed(format!("encrypt envelope failed: {e}")))?;
+        if let SecretContent::Envelope(envelope) = &mut secret.r#type {
+            envelope.encrypted_data = STANDARD.encode(encrypted_data);
+        }
+
+        Ok(secret)
+    }
+
+    /// Seal the plaintext of this envelope secret again with a new data
+    /// encryption key, encrypted by the key `key_id` or the same key if not
+    /// given, e.g. after the KMS rotates the key to a new version. The
+    /// version, the provider and the provider settings are kept.
+    pub async fn reseal(&self, key_id: Option<&str>) -> Result<Self> {
+        let SecretContent::Envelope(envelope) = &self.r#type else {
+            return Err(Error::SealEnvelopeFailed(
+                "only envelope secrets can be resealed".into(),
+            ));
+        };
+
+        let plaintext = self.unseal().await?;
+        Self::seal_envelope(
+            &self.version,
+            envelope.provider.clone(),
+            envelope.provider_settings.clone(),
+            key_id.unwrap_or(&envelope.key_id).to_string(),
+            &plaintext,
+        )
+        .await
+    }
+}
+
+/// Sort the keys of all the objects inside `value`.
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.into_iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| (k, canonicalize(v)))
+                    .collect::<Map<_, _>>(),
+            )
         }
+        Value::Array(values) => Value::Array(values.into_iter().map(canonicalize).collect()),
+        value => value,
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "unseal"))]
 mod tests {
     use assert_json_diff::assert_json_eq;
+    use async_trait::async_trait;
+    use base64::{engine::general_purpose::STANDARD, Engine};
     use crypto::WrapType;
+    use std::sync::Once;
+
+    use kms::{Decrypter, DecryptorFactory, Encrypter, EncryptorFactory};
     use rstest::rstest;
+    use serde_json::Value;
+    use zeroize::Zeroizing;
 
     use crate::{
         secret::layout::{envelope::Envelope, vault::VaultSecret},
-        Annotations, ProviderSettings,
+        Annotations, ProviderSettings, SecretBytes,
     };
 
-    use super::{Secret, SecretContent};
+    use super::{Secret, SecretContent, VERSION, VERSION_2};
 
     #[rstest]
     #[case(include_str!("../../test/envelope-1.json"), Secret {
@@ -80,6 +277,15 @@ mod tests {
             name: "xxx".into(),
         }),
     })]
+    #[case(include_str!("../../test/vault-2.json"), Secret {
+        version: "0.1.0".into(),
+        r#type: SecretContent::Vault(VaultSecret {
+            provider: "".into(),
+            provider_settings: ProviderSettings::default(),
+            annotations: Annotations::default(),
+            name: "kbs:///default/key/1".into(),
+        }),
+    })]
     fn serialize_deserialize(#[case] st: &str, #[case] origin: Secret) {
         let serialized = serde_json::to_string_pretty(&origin).expect("serialize failed");
         assert_json_eq!(st, serialized);
@@ -87,4 +293,214 @@ mod tests {
         let parsed: Secret = serde_json::from_str(st).expect("deserialize failed");
         assert_eq!(parsed, origin);
     }
+
+    #[rstest]
+    #[case(include_str!("../../test/vault-1.json"), true, "aliyun://xxx")]
+    #[case(include_str!("../../test/vault-2.json"), true, "kbs:///default/key/1")]
+    #[case(include_str!("../../test/envelope-1.json"), false, "aliyun://xxx")]
+    fn from_sealed(#[case] st: &str, #[case] valid: bool, #[case] resource: &str) {
+        let sealed = format!("fakeheader.{}.fakesignature", STANDARD.encode(st));
+        let secret = Secret::from_sealed(sealed.as_bytes()).expect("parse failed");
+        assert_eq!(secret.validate().is_ok(), valid);
+        assert_eq!(secret.resource(), resource);
+
+        assert!(Secret::from_sealed(st.as_bytes()).is_err());
+    }
+
+    /// A provider whose sealing is a no-op, i.e. the encrypted key is the
+    /// plaintext key.
+    struct Plain;
+
+    #[async_trait]
+    impl Decrypter for Plain {
+        async fn decrypt(
+            &mut self,
+            ciphertext: &[u8],
+            _key_id: &str,
+            _annotations: &Annotations,
+        ) -> kms::Result<SecretBytes> {
+            Ok(ciphertext.into())
+        }
+    }
+
+    #[async_trait]
+    impl DecryptorFactory for Plain {
+        async fn create(
+            &self,
+            _provider_settings: ProviderSettings,
+        ) -> kms::Result<Box<dyn Decrypter>> {
+            Ok(Box::new(Plain))
+        }
+    }
+
+    /// The key id is put into the annotations to check the key used.
+    #[async_trait]
+    impl Encrypter for Plain {
+        async fn encrypt(
+            &mut self,
+            data: &[u8],
+            key_id: &str,
+        ) -> kms::Result<(Vec<u8>, Annotations)> {
+            let mut annotations = Annotations::new();
+            annotations.insert("key_id".into(), Value::String(key_id.into()));
+            Ok((data.to_vec(), annotations))
+        }
+    }
+
+    #[async_trait]
+    impl EncryptorFactory for Plain {
+        async fn create(
+            &self,
+            _provider_settings: ProviderSettings,
+        ) -> kms::Result<Box<dyn Encrypter>> {
+            Ok(Box::new(Plain))
+        }
+    }
+
+    /// Register [`Plain`] as `test-plain` once for all the tests.
+    fn register_plain() {
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            kms::register_decryptor("test-plain", Plain).expect("register provider");
+            kms::register_encryptor("test-plain", Plain).expect("register provider");
+        });
+    }
+
+    #[tokio::test]
+    async fn authenticated_envelope() {
+        register_plain();
+        let key = [7u8; 32];
+        let iv = [9u8; 12];
+        let mut secret = Secret {
+            version: VERSION_2.into(),
+            r#type: SecretContent::Envelope(Envelope {
+                provider: "test-plain".into(),
+                provider_settings: ProviderSettings::default(),
+                key_id: "key".into(),
+                encrypted_key: STANDARD.encode(key),
+                encrypted_data: "".into(),
+                wrap_type: WrapType::Aes256Gcm,
+                iv: STANDARD.encode(iv),
+                annotations: Annotations::default(),
+            }),
+        };
+        let encrypted_data = crypto::encrypt_with_aad(
+            Zeroizing::new(key.to_vec()),
+            b"secret".to_vec(),
+            iv.to_vec(),
+            &secret.aad().unwrap(),
+            WrapType::Aes256Gcm,
+        )
+        .unwrap();
+        let SecretContent::Envelope(envelope) = &mut secret.r#type else {
+            unreachable!()
+        };
+        envelope.encrypted_data = STANDARD.encode(encrypted_data);
+        assert_eq!(secret.unseal().await.unwrap(), b"secret");
+
+        let SecretContent::Envelope(envelope) = &mut secret.r#type else {
+            unreachable!()
+        };
+        envelope
+            .annotations
+            .insert("tampered".into(), Value::Bool(true));
+        assert!(secret.unseal().await.is_err());
+    }
+
+    #[rstest]
+    #[case(VERSION)]
+    #[case(VERSION_2)]
+    #[tokio::test]
+    async fn reseal(#[case] version: &str) {
+        register_plain();
+        let secret = Secret::seal_envelope(
+            version,
+            "".into(),
+            ProviderSettings::default(),
+            "test-plain://key-1".into(),
+            b"secret",
+        )
+        .await
+        .expect("seal");
+        assert_eq!(secret.unseal().await.unwrap(), b"secret");
+
+        let resealed = secret
+            .reseal(Some("test-plain://key-2"))
+            .await
+            .expect("reseal");
+        assert_eq!(resealed.version, version);
+        let SecretContent::Envelope(envelope) = &resealed.r#type else {
+            unreachable!()
+        };
+        assert_eq!(envelope.key_id, "test-plain://key-2");
+        assert_eq!(envelope.annotations["key_id"], "key-2");
+        assert_ne!(resealed, secret);
+        assert_eq!(resealed.unseal().await.unwrap(), b"secret");
+    }
+
+    /// Unseal the secrets of the `test` provider and of a mock KBS, without
+    /// a TEE or the network.
+    #[cfg(feature = "testing")]
+    #[tokio::test]
+    async fn hermetic_unseal() {
+        use kms::plugins::test::{MockKbs, TestKmsClient};
+
+        let client = TestKmsClient::new("secret").with_secret("name", "vault secret");
+        let provider_settings = client.export_provider_settings().unwrap();
+        let secret = Secret::seal_envelope(
+            VERSION_2,
+            "test".into(),
+            provider_settings.clone(),
+            "key".into(),
+            b"envelope secret",
+        )
+        .await
+        .expect("seal");
+        assert_eq!(secret.unseal().await.unwrap(), b"envelope secret");
+
+        let secret = Secret {
+            version: VERSION.into(),
+            r#type: SecretContent::Vault(VaultSecret {
+                provider: "test".into(),
+                provider_settings,
+                annotations: Annotations::default(),
+                name: "name".into(),
+            }),
+        };
+        assert_eq!(secret.unseal().await.unwrap(), b"vault secret");
+
+        let kbs = MockKbs::start().await.unwrap();
+        kbs.set_resource("default/key/1", b"kbs secret".to_vec());
+        kbs.set_resource("default/kek/1", vec![7; 32]);
+        // the kbs host of the provider settings must be configured
+        kms::set_settings(kms::Settings {
+            aa_kbc_params: Some(format!("mock_kbc::{}", kbs.url())),
+            ..Default::default()
+        });
+        let provider_settings = serde_json::json!({"kbc": "mock_kbc", "kbs_host": kbs.url()})
+            .as_object()
+            .unwrap()
+            .to_owned();
+        let secret = Secret {
+            version: VERSION.into(),
+            r#type: SecretContent::Vault(VaultSecret {
+                provider: "kbs".into(),
+                provider_settings: provider_settings.clone(),
+                annotations: Annotations::default(),
+                name: "kbs:///default/key/1".into(),
+            }),
+        };
+        assert_eq!(secret.unseal().await.unwrap(), b"kbs secret");
+
+        let secret = Secret::seal_envelope(
+            VERSION_2,
+            "kbs".into(),
+            provider_settings,
+            "kbs:///default/kek/1".into(),
+            b"envelope secret",
+        )
+        .await
+        .expect("seal");
+        assert_eq!(secret.unseal().await.unwrap(), b"envelope secret");
+    }
 }
@@ -0,0 +1,159 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Post-processing of the secrets got from a vault, so that the caller gets
+//! exactly the value it needs, e.g. one field of a json secret. The steps
+//! are given by the annotation [`TRANSFORMS_ANNOTATION`] of a vault secret,
+//! and are applied in order, like
+//! ```json
+//! "transforms": [
+//!     { "type": "base64" },
+//!     { "type": "json_pointer", "pointer": "/password" }
+//! ]
+//! ```
+
+#[cfg(feature = "unseal")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+#[cfg(feature = "unseal")]
+use serde_json::Value;
+
+#[cfg(feature = "unseal")]
+use crate::SecretBytes;
+
+/// The annotation of the transformations. It is not given to the provider.
+pub const TRANSFORMS_ANNOTATION: &str = "transforms";
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Transform {
+    /// Decode the standard base64 encoded value.
+    Base64,
+
+    /// Take the field of the json value at the `pointer` (RFC 6901), e.g.
+    /// `/db/password`. A string field is taken as is, and other fields as
+    /// json.
+    JsonPointer { pointer: String },
+
+    /// Render the `template`, replacing every `{{<pointer>}}` with the field
+    /// of the json value at the pointer, taken like [`Transform::JsonPointer`],
+    /// e.g. `postgres://{{/user}}:{{/password}}@db:5432`. `{{}}` is the
+    /// whole value.
+    Template { template: String },
+}
+
+/// Apply the `transforms` to the `value` in order.
+#[cfg(feature = "unseal")]
+pub(crate) fn apply(
+    transforms: &[Transform],
+    mut value: SecretBytes,
+) -> std::result::Result<SecretBytes, String> {
+    for transform in transforms {
+        value = SecretBytes::from(match transform {
+            Transform::Base64 => STANDARD
+                .decode(&*value)
+                .map_err(|e| format!("base64 decode failed: {e}"))?,
+            Transform::JsonPointer { pointer } => {
+                let json = parse_json(&value)?;
+                field(&json, pointer)?
+            }
+            Transform::Template { template } => {
+                let json = parse_json(&value)?;
+                render(template, &json)?
+            }
+        });
+    }
+
+    Ok(value)
+}
+
+#[cfg(feature = "unseal")]
+fn parse_json(value: &[u8]) -> std::result::Result<Value, String> {
+    serde_json::from_slice(value).map_err(|e| format!("value is not json: {e}"))
+}
+
+#[cfg(feature = "unseal")]
+fn field(json: &Value, pointer: &str) -> std::result::Result<Vec<u8>, String> {
+    match json.pointer(pointer) {
+        Some(Value::String(field)) => Ok(field.clone().into_bytes()),
+        Some(field) => Ok(field.to_string().into_bytes()),
+        None => Err(format!("no field at json pointer `{pointer}`")),
+    }
+}
+
+#[cfg(feature = "unseal")]
+fn render(template: &str, json: &Value) -> std::result::Result<Vec<u8>, String> {
+    let mut rendered = Vec::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("unclosed placeholder in template at `{}`", &rest[start..]))?;
+        rendered.extend_from_slice(&rest.as_bytes()[..start]);
+        rendered.extend(field(json, rest[start + 2..start + end].trim())?);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.extend_from_slice(rest.as_bytes());
+
+    Ok(rendered)
+}
+
+#[cfg(all(test, feature = "unseal"))]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::{apply, Transform};
+
+    #[rstest]
+    #[case(json!([{"type": "base64"}]), b"c2VjcmV0".to_vec(), Some(b"secret".to_vec()))]
+    #[case(json!([{"type": "base64"}]), b"!!".to_vec(), None)]
+    #[case(
+        json!([{"type": "json_pointer", "pointer": "/db/password"}]),
+        br#"{"db": {"password": "p@ss"}}"#.to_vec(),
+        Some(b"p@ss".to_vec())
+    )]
+    #[case(
+        json!([{"type": "json_pointer", "pointer": "/db"}]),
+        br#"{"db": {"port": 5432}}"#.to_vec(),
+        Some(br#"{"port":5432}"#.to_vec())
+    )]
+    #[case(
+        json!([{"type": "json_pointer", "pointer": "/missing"}]),
+        br#"{"db": {}}"#.to_vec(),
+        None
+    )]
+    #[case(
+        json!([
+            {"type": "base64"},
+            {"type": "template", "template": "postgres://{{/user}}:{{ /password }}@db:{{/port}}"}
+        ]),
+        b"eyJ1c2VyIjogImFkbWluIiwgInBhc3N3b3JkIjogInMzY3IzdCIsICJwb3J0IjogNTQzMn0=".to_vec(),
+        Some(b"postgres://admin:s3cr3t@db:5432".to_vec())
+    )]
+    #[case(
+        json!([{"type": "template", "template": "token={{}}"}]),
+        br#""abc""#.to_vec(),
+        Some(b"token=abc".to_vec())
+    )]
+    #[case(
+        json!([{"type": "template", "template": "{{/user"}]),
+        br#"{"user": "admin"}"#.to_vec(),
+        None
+    )]
+    fn transform(
+        #[case] transforms: serde_json::Value,
+        #[case] value: Vec<u8>,
+        #[case] expected: Option<Vec<u8>>,
+    ) {
+        let transforms: Vec<Transform> = serde_json::from_value(transforms).unwrap();
+        assert_eq!(
+            apply(&transforms, value.into())
+                .ok()
+                .map(|value| value.to_vec()),
+            expected
+        );
+    }
+}
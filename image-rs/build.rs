@@ -23,5 +23,19 @@ fn main() -> Result<()> {
         .run()
         .context("ttrpc build")?;
 
+    #[cfg(all(feature = "ttrpc-codegen", feature = "measurement"))]
+    ttrpc_codegen::Codegen::new()
+        .out_dir("./src/measurement/ttrpc_proto")
+        .input("./protos/attestation_agent.proto")
+        .include("./protos")
+        .rust_protobuf()
+        .customize(ttrpc_codegen::Customize {
+            async_all: true,
+            ..Default::default()
+        })
+        .rust_protobuf_customize(ttrpc_codegen::ProtobufCustomize::default().gen_mod_rs(false))
+        .run()
+        .context("ttrpc build")?;
+
     Ok(())
 }
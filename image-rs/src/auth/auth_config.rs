@@ -53,6 +53,29 @@ pub fn credential_from_auth_config(
     Ok(RegistryAuth::Anonymous)
 }
 
+/// Get the credential helper of the registry of the image reference from
+/// the `credHelpers`, whose keys are registries.
+pub fn helper_for_reference<'a>(
+    reference: &Reference,
+    helpers: &'a HashMap<String, String>,
+) -> Option<&'a str> {
+    let image_registry = normalize_registry(reference.resolve_registry());
+    helpers
+        .iter()
+        .find(|(key, _)| normalize_key_to_registry(key) == image_registry)
+        .map(|(_, helper)| &helper[..])
+}
+
+/// Get the server URL of the registry of the image reference given to the
+/// credential helpers, which is `https://index.docker.io/v1/` for the
+/// docker hub as of docker.
+pub fn server_url(reference: &Reference) -> String {
+    match normalize_registry(reference.resolve_registry()) {
+        "index.docker.io" => "https://index.docker.io/v1/".to_string(),
+        registry => registry.to_string(),
+    }
+}
+
 /// full_name returns the full registry and repository.
 fn full_name(reference: &Reference) -> String {
     if reference.registry() == "" {
@@ -169,6 +192,33 @@ mod tests {
         // assert_eq!(got_auth, auth);
     }
 
+    #[rstest]
+    #[case(
+        "123456789012.dkr.ecr.us-east-1.amazonaws.com/app:v1",
+        Some("ecr-login")
+    )]
+    #[case("busybox:latest", Some("desktop"))]
+    #[case("quay.io/confidential-containers/image:latest", None)]
+    fn test_helper_for_reference(#[case] reference: &str, #[case] expected: Option<&str>) {
+        let reference = Reference::try_from(reference).expect("reference creation failed");
+        let helpers: HashMap<String, String> = serde_json::from_str(
+            r#"{
+                "123456789012.dkr.ecr.us-east-1.amazonaws.com": "ecr-login",
+                "https://index.docker.io/v1/": "desktop"
+            }"#,
+        )
+        .expect("deserialize credHelpers failed");
+        assert_eq!(super::helper_for_reference(&reference, &helpers), expected);
+    }
+
+    #[rstest]
+    #[case("busybox:latest", "https://index.docker.io/v1/")]
+    #[case("gcr.io/google-containers/busybox:1.27.2", "gcr.io")]
+    fn test_server_url(#[case] reference: &str, #[case] expected: &str) {
+        let reference = Reference::try_from(reference).expect("reference creation failed");
+        assert_eq!(super::server_url(&reference), expected);
+    }
+
     #[rstest]
     #[case("https://index.docker.io/v1/", "index.docker.io")]
     #[case("https://docker.io/v1/", "index.docker.io")]
@@ -0,0 +1,180 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Helps to get auths from the credential helpers of an `auth.json`, which
+//! follow the protocol of
+//! [docker-credential-helpers](https://github.com/docker/docker-credential-helpers).
+//!
+//! A helper `<name>` is the executable `docker-credential-<name>` in
+//! [`CREDENTIAL_HELPERS_DIR`]. It is run as `docker-credential-<name> get`
+//! with the server URL of the registry on its stdin, and prints the
+//! credential like `{"ServerURL": "...", "Username": "...", "Secret": "..."}`.
+//! So the short-lived credentials of cloud registries can be minted inside
+//! the guest on each pull, instead of being stored in the `auth.json`.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::*;
+use log::debug;
+use oci_distribution::secrets::RegistryAuth;
+use serde::Deserialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Directory of the executables of the credential helpers.
+pub const CREDENTIAL_HELPERS_DIR: &str = "/usr/local/bin";
+
+/// Prefix of the executables of the credential helpers.
+const HELPER_PREFIX: &str = "docker-credential-";
+
+/// Timeout of a credential helper.
+const HELPER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The error reported by the helpers when they have no credential of the
+/// server.
+const ERR_CREDENTIALS_NOT_FOUND: &str = "credentials not found in native keychain";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredential {
+    username: String,
+    secret: String,
+}
+
+/// Get the credential of the registry at `server` by the credential helper
+/// `helper` in `helpers_dir`. If the helper has no credential of the
+/// registry, returns an Anonymous credential.
+pub async fn credential_from_helper(
+    helpers_dir: &Path,
+    helper: &str,
+    server: &str,
+) -> Result<RegistryAuth> {
+    if !is_legal_name(helper) {
+        bail!("illegal credential helper name {helper:?}");
+    }
+
+    let path = helpers_dir.join(format!("{HELPER_PREFIX}{helper}"));
+    let mut child = Command::new(&path)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("run credential helper {helper} failed: {e}"))?;
+
+    // A helper failing early may not read its stdin, whose exit status is
+    // reported below.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    if let Err(e) = stdin.write_all(server.as_bytes()).await {
+        debug!("write to credential helper {helper} failed: {e}");
+    }
+    drop(stdin);
+
+    let output = tokio::time::timeout(HELPER_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("credential helper {helper} timed out"))?
+        .map_err(|e| anyhow!("run credential helper {helper} failed: {e}"))?;
+
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stdout);
+        if message.trim() == ERR_CREDENTIALS_NOT_FOUND {
+            debug!("no credential of {server} in credential helper {helper}");
+            return Ok(RegistryAuth::Anonymous);
+        }
+
+        bail!(
+            "credential helper {helper} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let credential: HelperCredential = serde_json::from_slice(&output.stdout)
+        .map_err(|e| anyhow!("invalid credential from credential helper {helper}: {e}"))?;
+    Ok(RegistryAuth::Basic(credential.username, credential.secret))
+}
+
+/// Names of the helpers only consist of ascii alphanumerics, `-` and `_`,
+/// so that a helper cannot be out of the directory of the helpers.
+fn is_legal_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use oci_distribution::secrets::RegistryAuth;
+    use rstest::rstest;
+
+    /// Install a helper `name` of the shell `script` in `dir`.
+    fn install_helper(dir: &std::path::Path, name: &str, script: &str) {
+        let path = dir.join(format!("{}{name}", super::HELPER_PREFIX));
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[rstest]
+    #[case("ecr-login", true)]
+    #[case("my_helper-2", true)]
+    #[case("", false)]
+    #[case("../../bin/sh", false)]
+    #[case("/bin/sh", false)]
+    fn test_is_legal_name(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(super::is_legal_name(name), expected);
+    }
+
+    #[tokio::test]
+    async fn test_credential_from_helper() {
+        let dir = tempfile::tempdir().unwrap();
+        install_helper(
+            dir.path(),
+            "test",
+            r#"[ "$1" = get ] || exit 1
+server=$(cat)
+[ "$server" = 123456789012.dkr.ecr.us-east-1.amazonaws.com ] || { echo "credentials not found in native keychain"; exit 1; }
+echo '{"ServerURL": "'"$server"'", "Username": "AWS", "Secret": "token"}'"#,
+        );
+
+        let auth = super::credential_from_helper(
+            dir.path(),
+            "test",
+            "123456789012.dkr.ecr.us-east-1.amazonaws.com",
+        )
+        .await
+        .unwrap();
+        assert!(
+            matches!(auth, RegistryAuth::Basic(username, secret) if username == "AWS" && secret == "token")
+        );
+
+        let auth = super::credential_from_helper(dir.path(), "test", "quay.io")
+            .await
+            .unwrap();
+        assert!(matches!(auth, RegistryAuth::Anonymous));
+
+        install_helper(dir.path(), "broken", "echo oops >&2; exit 2");
+        let e = super::credential_from_helper(dir.path(), "broken", "quay.io")
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("oops"));
+
+        assert!(
+            super::credential_from_helper(dir.path(), "missing", "quay.io")
+                .await
+                .is_err()
+        );
+        assert!(
+            super::credential_from_helper(dir.path(), "../test", "quay.io")
+                .await
+                .is_err()
+        );
+    }
+}
@@ -4,8 +4,10 @@
 //
 
 pub mod auth_config;
+pub mod credential_helper;
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use anyhow::*;
 use oci_distribution::{secrets::RegistryAuth, Reference};
@@ -16,8 +18,18 @@ pub const RESOURCE_DESCRIPTION: &str = "Credential";
 
 #[derive(Deserialize, Serialize)]
 pub struct DockerConfigFile {
+    #[serde(default)]
     auths: HashMap<String, DockerAuthConfig>,
-    // TODO: support credential helpers
+
+    /// The credential helpers by registry, like
+    /// `{"<account>.dkr.ecr.<region>.amazonaws.com": "ecr-login"}`.
+    #[serde(default, rename = "credHelpers")]
+    cred_helpers: HashMap<String, String>,
+
+    /// The credential helper of the registries neither in `credHelpers`
+    /// nor in `auths`.
+    #[serde(default, rename = "credsStore")]
+    creds_store: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -28,6 +40,9 @@ pub struct DockerAuthConfig {
 /// Get a credential (RegistryAuth) for the given Reference.
 /// The path can be from different places. Like `path://` or
 /// `kbs://`.
+///
+/// The credential helper of the registry in `credHelpers` is preferred,
+/// then the `auths`, then the `credsStore`.
 pub async fn credential_for_reference(
     reference: &Reference,
     auth_file_path: &str,
@@ -36,6 +51,17 @@ pub async fn credential_for_reference(
 
     let config: DockerConfigFile = serde_json::from_slice(&auth)?;
 
-    // TODO: support credential helpers
-    auth_config::credential_from_auth_config(reference, &config.auths)
+    let helpers_dir = Path::new(credential_helper::CREDENTIAL_HELPERS_DIR);
+    let server = auth_config::server_url(reference);
+    if let Some(helper) = auth_config::helper_for_reference(reference, &config.cred_helpers) {
+        return credential_helper::credential_from_helper(helpers_dir, helper, &server).await;
+    }
+
+    let auth = auth_config::credential_from_auth_config(reference, &config.auths)?;
+    match (auth, &config.creds_store) {
+        (RegistryAuth::Anonymous, Some(store)) => {
+            credential_helper::credential_from_helper(helpers_dir, store, &server).await
+        }
+        (auth, _) => Ok(auth),
+    }
 }
@@ -0,0 +1,198 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Budget shared by all the layers of an image pulled concurrently.
+//!
+//! The memory budget bounds the layer data fetched, decrypted and
+//! decompressed but not yet unpacked, so that a fast download does not
+//! pile up the whole image in the guest memory while the unpacking lags
+//! behind. The bandwidth budget bounds the overall rate the layer blobs
+//! are fetched from the registry.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Sleep;
+
+use crate::config::{DEFAULT_MAX_PULL_BANDWIDTH, DEFAULT_MAX_PULL_BUFFER_SIZE};
+
+/// Granularity of the memory budget in bytes.
+const MEMORY_UNIT: usize = 1024;
+
+/// The budget of a pull, cheap to clone and shared by the layer tasks.
+#[derive(Clone, Debug)]
+pub struct PullBudget {
+    memory: Arc<Semaphore>,
+    memory_units: usize,
+    bandwidth: Option<Arc<RateLimiter>>,
+}
+
+impl Default for PullBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PULL_BUFFER_SIZE, DEFAULT_MAX_PULL_BANDWIDTH)
+    }
+}
+
+impl PullBudget {
+    /// Create a budget of `max_buffer_size` bytes of in-flight layer data
+    /// and `max_bandwidth` bytes per second of download, `0` meaning an
+    /// unlimited bandwidth.
+    pub fn new(max_buffer_size: usize, max_bandwidth: u64) -> Self {
+        // Reservations take at most `u32::MAX` permits at once.
+        let max_units = Semaphore::MAX_PERMITS.min(u32::MAX as usize);
+        let memory_units = (max_buffer_size / MEMORY_UNIT).clamp(1, max_units);
+        let bandwidth = (max_bandwidth != 0).then(|| Arc::new(RateLimiter::new(max_bandwidth)));
+
+        PullBudget {
+            memory: Arc::new(Semaphore::new(memory_units)),
+            memory_units,
+            bandwidth,
+        }
+    }
+
+    /// Reserve `size` bytes of the memory budget, waiting for the other
+    /// layers to release theirs when it is exhausted. The reservation is
+    /// released when the returned permit is dropped.
+    pub async fn reserve(&self, size: usize) -> Result<OwnedSemaphorePermit> {
+        let units = size.div_ceil(MEMORY_UNIT).clamp(1, self.memory_units);
+        self.memory
+            .clone()
+            .acquire_many_owned(units as u32)
+            .await
+            .map_err(|e| anyhow!("failed to reserve memory budget {e}"))
+    }
+
+    /// Wrap `reader` so that the reads go through the bandwidth budget.
+    pub fn throttle<R: AsyncRead + Unpin>(&self, reader: R) -> ThrottledRead<R> {
+        ThrottledRead {
+            inner: reader,
+            limiter: self.bandwidth.clone(),
+            delay: None,
+        }
+    }
+}
+
+/// Schedules the reads of all the throttled readers on a shared timeline,
+/// so that their overall rate stays under the limit.
+#[derive(Debug)]
+struct RateLimiter {
+    bytes_per_sec: u64,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: u64) -> Self {
+        RateLimiter {
+            bytes_per_sec,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Account `n` bytes read and return how long the reader has to wait
+    /// before its next read.
+    fn consume(&self, n: usize) -> Duration {
+        let now = Instant::now();
+        let mut next = self.next.lock().expect("rate limiter lock poisoned");
+        let start = (*next).max(now);
+        *next = start + Duration::from_secs_f64(n as f64 / self.bytes_per_sec as f64);
+        next.saturating_duration_since(now)
+    }
+}
+
+/// An [`AsyncRead`] throttled by the bandwidth budget of a [`PullBudget`].
+pub struct ThrottledRead<R> {
+    inner: R,
+    limiter: Option<Arc<RateLimiter>>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ThrottledRead<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        if let Some(delay) = this.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            this.delay = None;
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        if let Some(limiter) = &this.limiter {
+            let wait = limiter.consume(buf.filled().len() - filled);
+            if !wait.is_zero() {
+                this.delay = Some(Box::pin(tokio::time::sleep(wait)));
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_memory_budget() {
+        let budget = PullBudget::new(4 * MEMORY_UNIT, 0);
+
+        let first = budget.reserve(3 * MEMORY_UNIT).await.unwrap();
+        let _second = budget.reserve(1).await.unwrap();
+
+        // The budget is exhausted until the first reservation is released.
+        let third = tokio::time::timeout(Duration::from_millis(100), budget.reserve(MEMORY_UNIT));
+        assert!(third.await.is_err());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(100), budget.reserve(MEMORY_UNIT));
+        assert!(third.await.is_ok());
+
+        // A reservation larger than the whole budget takes all of it.
+        let budget = PullBudget::new(MEMORY_UNIT, 0);
+        assert!(budget.reserve(16 * MEMORY_UNIT).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_budget() {
+        let data = vec![0u8; 64 * 1024];
+
+        let budget = PullBudget::new(DEFAULT_MAX_PULL_BUFFER_SIZE, 256 * 1024);
+        let mut reader = budget.throttle(data.as_slice());
+        let mut output = Vec::new();
+
+        let start = Instant::now();
+        reader.read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, data);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        // The readers of a budget share its bandwidth.
+        let mut first = budget.throttle(data.as_slice());
+        let mut second = budget.throttle(data.as_slice());
+        let (mut first_output, mut second_output) = (Vec::new(), Vec::new());
+        let start = Instant::now();
+        let (first, second) = tokio::join!(
+            first.read_to_end(&mut first_output),
+            second.read_to_end(&mut second_output)
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(450));
+
+        let budget = PullBudget::default();
+        let mut reader = budget.throttle(data.as_slice());
+        let start = Instant::now();
+        reader.read_to_end(&mut Vec::new()).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(200));
+    }
+}
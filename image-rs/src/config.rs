@@ -4,8 +4,10 @@
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs::File;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 
 use crate::snapshots::SnapshotType;
@@ -34,6 +36,27 @@ pub const AUTH_FILE_PATH: &str = "kbs:///default/credential/test";
 /// Default max concurrent download.
 pub const DEFAULT_MAX_CONCURRENT_DOWNLOAD: usize = 3;
 
+/// Default max size in bytes of the layer data buffered in memory during
+/// image pull, shared by all the layers pulled concurrently.
+pub const DEFAULT_MAX_PULL_BUFFER_SIZE: usize = 64 * 1024 * 1024;
+
+/// Default max bandwidth in bytes per second of image pull, `0` means
+/// unlimited.
+pub const DEFAULT_MAX_PULL_BANDWIDTH: u64 = 0;
+
+/// Default max size in bytes of the layers in the layer cache.
+pub const DEFAULT_LAYER_CACHE_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Default size in bytes of the encrypted volumes of the upperdirs, which
+/// are sparse so that only the blocks written take space.
+pub const DEFAULT_UPPERDIR_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Default max number of resumes in a row of a download of a layer blob.
+pub const DEFAULT_MAX_DOWNLOAD_RESUMES: u32 = 5;
+
+/// Default port of DNS-over-TLS.
+pub const DEFAULT_DOT_PORT: u16 = 853;
+
 /// Path to the configuration file to generate ImageConfiguration
 pub const CONFIGURATION_FILE_PATH: &str = "/var/lib/image-rs/config.json";
 
@@ -59,11 +82,67 @@ pub struct ImageConfig {
     )]
     pub file_paths: Paths,
 
+    /// The signature policy inline or in a local fallback, and its digest
+    /// pinned or measured.
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
     /// Maximum number of concurrent downloads to perform during image pull.
     ///
     /// This defaults to [`DEFAULT_MAX_CONCURRENT_DOWNLOAD`].
     pub max_concurrent_download: usize,
 
+    /// Maximum size in bytes of the layer data fetched, decrypted and
+    /// decompressed but not yet unpacked during image pull, shared by all
+    /// the layers pulled concurrently.
+    ///
+    /// This defaults to [`DEFAULT_MAX_PULL_BUFFER_SIZE`].
+    #[serde(default = "default_max_pull_buffer_size")]
+    pub max_pull_buffer_size: usize,
+
+    /// Maximum bandwidth in bytes per second of image pull, shared by all
+    /// the layers pulled concurrently. `0` means unlimited.
+    ///
+    /// This defaults to [`DEFAULT_MAX_PULL_BANDWIDTH`].
+    #[serde(default = "default_max_pull_bandwidth")]
+    pub max_pull_bandwidth: u64,
+
+    /// Lazily pull the eStargz layers, by mounting them with FUSE and
+    /// fetching their files when first read, instead of pulling and
+    /// unpacking them. It requires the `estargz` feature.
+    #[serde(default)]
+    pub lazy_pull: bool,
+
+    /// Persistent cache of the unpacked layers, shared by the images and
+    /// kept across restarts. No layer is cached if unset.
+    #[serde(default)]
+    pub layer_cache: Option<LayerCacheConfig>,
+
+    /// Resume the downloads of the layer blobs interrupted by the network,
+    /// by HTTP range requests. It requires the `resumable-download`
+    /// feature.
+    #[serde(default)]
+    pub resumable_download: Option<ResumableDownloadConfig>,
+
+    /// Mirrors and insecure registries, by registry host.
+    #[serde(default)]
+    pub registries: HashMap<String, RegistryConfig>,
+
+    /// In-process DNS resolver of the registries, instead of the DNS
+    /// provided by the host. It requires the `dns-resolver` feature.
+    #[serde(default)]
+    pub dns: Option<DnsConfig>,
+
+    /// Configuration of the `overlay` snapshot.
+    #[serde(default)]
+    pub overlay_snapshot: OverlaySnapshotConfig,
+
+    /// Configuration of the `verity` snapshot, which builds a read-only
+    /// image of the rootfs protected by dm-verity. It requires the
+    /// `snapshot-verity` feature.
+    #[serde(default)]
+    pub verity_snapshot: VeritySnapshotConfig,
+
     /// Nydus services configuration
     #[serde(rename = "nydus")]
     pub nydus_config: Option<NydusConfig>,
@@ -81,6 +160,14 @@ where
     Ok(opt.unwrap_or_default())
 }
 
+fn default_max_pull_buffer_size() -> usize {
+    DEFAULT_MAX_PULL_BUFFER_SIZE
+}
+
+fn default_max_pull_bandwidth() -> u64 {
+    DEFAULT_MAX_PULL_BANDWIDTH
+}
+
 impl Default for ImageConfig {
     // Construct a default instance of `ImageConfig`
     fn default() -> ImageConfig {
@@ -97,7 +184,17 @@ impl Default for ImageConfig {
             security_validate: false,
             auth: false,
             file_paths: Paths::default(),
+            policy: PolicyConfig::default(),
             max_concurrent_download: DEFAULT_MAX_CONCURRENT_DOWNLOAD,
+            max_pull_buffer_size: DEFAULT_MAX_PULL_BUFFER_SIZE,
+            max_pull_bandwidth: DEFAULT_MAX_PULL_BANDWIDTH,
+            lazy_pull: false,
+            layer_cache: None,
+            resumable_download: None,
+            registries: HashMap::new(),
+            dns: None,
+            overlay_snapshot: OverlaySnapshotConfig::default(),
+            verity_snapshot: VeritySnapshotConfig::default(),
             #[cfg(feature = "nydus")]
             nydus_config: Some(NydusConfig::default()),
             #[cfg(not(feature = "nydus"))]
@@ -133,6 +230,45 @@ impl TryFrom<&Path> for ImageConfig {
 impl ImageConfig {
     /// Validate the configuration object.
     pub fn validate(&self) -> bool {
+        // No layer could be pulled without any download or buffer.
+        if self.max_concurrent_download == 0 || self.max_pull_buffer_size == 0 {
+            return false;
+        }
+
+        if self.lazy_pull && !cfg!(feature = "estargz") {
+            return false;
+        }
+
+        if !self.policy.validate() {
+            return false;
+        }
+
+        if let Some(layer_cache) = self.layer_cache.as_ref() {
+            if layer_cache.max_size == 0 {
+                return false;
+            }
+        }
+
+        if let Some(resumable_download) = self.resumable_download.as_ref() {
+            if !cfg!(feature = "resumable-download") || !resumable_download.validate() {
+                return false;
+            }
+        }
+
+        if !self.registries.values().all(RegistryConfig::validate) {
+            return false;
+        }
+
+        if let Some(dns) = self.dns.as_ref() {
+            if !cfg!(feature = "dns-resolver") || !dns.validate() {
+                return false;
+            }
+        }
+
+        if !self.overlay_snapshot.validate() {
+            return false;
+        }
+
         if let Some(nydus_cfg) = self.nydus_config.as_ref() {
             if !nydus_cfg.validate() {
                 return false;
@@ -171,6 +307,278 @@ impl Default for Paths {
     }
 }
 
+/// Signature policy configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// The policy itself, used instead of the one at `policy_path` of the
+    /// [`Paths`].
+    pub inline: Option<serde_json::Value>,
+
+    /// Local path of the policy used if the one at `policy_path` fails to
+    /// be fetched, e.g. as the KBS is unreachable.
+    pub fallback_path: Option<PathBuf>,
+
+    /// The digest of the policy, like `sha256:<hex>`, so that any other
+    /// policy is refused.
+    pub digest: Option<String>,
+
+    /// Record the digest of the policy in the runtime measurements, by the
+    /// attestation agent. It requires the `measurement` feature.
+    pub measure: bool,
+}
+
+impl PolicyConfig {
+    /// Validate the configuration object.
+    pub fn validate(&self) -> bool {
+        if self.measure && !cfg!(feature = "measurement") {
+            return false;
+        }
+
+        match &self.digest {
+            Some(digest) => digest
+                .strip_prefix("sha256:")
+                .is_some_and(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())),
+            None => true,
+        }
+    }
+}
+
+/// Persistent layer cache configuration.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LayerCacheConfig {
+    /// Directory of the cache, on a local disk encrypted with dm-crypt.
+    pub dir: PathBuf,
+
+    /// Max size in bytes of the layers cached, beyond which the least
+    /// recently used ones are evicted.
+    ///
+    /// This defaults to [`DEFAULT_LAYER_CACHE_SIZE`].
+    #[serde(default = "default_layer_cache_size")]
+    pub max_size: u64,
+
+    /// Refuse a cache dir which is not on a dm-crypt device.
+    #[serde(default = "default_require_encrypted")]
+    pub require_encrypted: bool,
+}
+
+fn default_layer_cache_size() -> u64 {
+    DEFAULT_LAYER_CACHE_SIZE
+}
+
+fn default_require_encrypted() -> bool {
+    true
+}
+
+/// Resumable download configuration.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ResumableDownloadConfig {
+    /// Max number of resumes of a download in a row, without any byte
+    /// downloaded in between.
+    ///
+    /// This defaults to [`DEFAULT_MAX_DOWNLOAD_RESUMES`].
+    pub max_resumes: u32,
+
+    /// Directory of the checkpoints of the bytes downloaded, so that the
+    /// download of a blob in a later pull, after a failed one, resumes
+    /// from its checkpoint. Nothing is checkpointed if unset.
+    pub checkpoint_dir: Option<PathBuf>,
+}
+
+impl Default for ResumableDownloadConfig {
+    fn default() -> Self {
+        Self {
+            max_resumes: DEFAULT_MAX_DOWNLOAD_RESUMES,
+            checkpoint_dir: None,
+        }
+    }
+}
+
+impl ResumableDownloadConfig {
+    /// Validate the configuration object.
+    pub fn validate(&self) -> bool {
+        self.max_resumes > 0
+    }
+}
+
+/// Configuration of the `overlay` snapshot.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct OverlaySnapshotConfig {
+    /// Put the upperdir of each container on an ephemeral dm-crypt volume,
+    /// encrypted by a random key generated in the guest. It requires the
+    /// `encrypted-upperdir` feature.
+    pub encrypted_upperdir: bool,
+
+    /// Size in bytes of the encrypted volume of each upperdir, a multiple
+    /// of 512.
+    ///
+    /// This defaults to [`DEFAULT_UPPERDIR_SIZE`].
+    pub upperdir_size: u64,
+}
+
+impl Default for OverlaySnapshotConfig {
+    fn default() -> Self {
+        Self {
+            encrypted_upperdir: false,
+            upperdir_size: DEFAULT_UPPERDIR_SIZE,
+        }
+    }
+}
+
+impl OverlaySnapshotConfig {
+    /// Validate the configuration object.
+    pub fn validate(&self) -> bool {
+        if !self.encrypted_upperdir {
+            return true;
+        }
+
+        cfg!(feature = "encrypted-upperdir")
+            && self.upperdir_size != 0
+            && self.upperdir_size % 512 == 0
+    }
+}
+
+/// Configuration of the `verity` snapshot.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct VeritySnapshotConfig {
+    /// Filesystem of the read-only images of the rootfs.
+    pub fs_type: VerityFsType,
+}
+
+/// Filesystems of the read-only images of the rootfs.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VerityFsType {
+    /// Built by `mkfs.erofs` of erofs-utils.
+    #[default]
+    Erofs,
+
+    /// Built by `mksquashfs` of squashfs-tools.
+    Squashfs,
+}
+
+impl std::fmt::Display for VerityFsType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let out = match self {
+            Self::Erofs => "erofs",
+            Self::Squashfs => "squashfs",
+        };
+
+        write!(f, "{out}")
+    }
+}
+
+/// Registry configuration, like the `hosts.toml` of containerd.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RegistryConfig {
+    /// Mirrors tried in order before the registry itself.
+    pub mirrors: Vec<MirrorConfig>,
+
+    /// Never fall back on the registry itself, e.g. when air-gapped.
+    pub mirror_only: bool,
+
+    /// Pull from the registry by plain HTTP.
+    pub insecure: bool,
+
+    /// Skip the TLS certificate verification of the registry.
+    pub skip_verify: bool,
+}
+
+impl RegistryConfig {
+    /// Validate the configuration object.
+    pub fn validate(&self) -> bool {
+        if self.mirror_only && self.mirrors.is_empty() {
+            return false;
+        }
+
+        self.mirrors.iter().all(|mirror| !mirror.host.is_empty())
+    }
+}
+
+/// Registry mirror configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MirrorConfig {
+    /// Host of the mirror, optionally followed by a path prefixed to the
+    /// repositories, like `harbor.local/docker-proxy`.
+    pub host: String,
+
+    /// Pull from the mirror by plain HTTP.
+    pub insecure: bool,
+
+    /// Skip the TLS certificate verification of the mirror.
+    pub skip_verify: bool,
+
+    /// Auth of the mirror as `username:password`. If unset, the auth of the
+    /// mirror is got from `auth.json` when `auth` is enabled.
+    pub auth: Option<String>,
+}
+
+/// In-process DNS resolver configuration. The servers are queried in
+/// order, the DoH ones first, until one of them answers.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DnsConfig {
+    /// URLs of the DNS-over-HTTPS servers, like
+    /// `https://[2606:4700:4700::1111]/dns-query`. Their hosts should be IP
+    /// literals, as they are resolved by the system resolver otherwise.
+    pub doh: Vec<String>,
+
+    /// DNS-over-TLS servers.
+    pub dot: Vec<DotServerConfig>,
+}
+
+impl DnsConfig {
+    /// Validate the configuration object.
+    pub fn validate(&self) -> bool {
+        if self.doh.is_empty() && self.dot.is_empty() {
+            return false;
+        }
+
+        let doh_valid = self
+            .doh
+            .iter()
+            .all(|url| url::Url::parse(url).is_ok_and(|url| url.scheme() == "https"));
+        doh_valid
+            && self
+                .dot
+                .iter()
+                .all(|server| server.socket_addr().is_ok() && !server.tls_name.is_empty())
+    }
+}
+
+/// DNS-over-TLS server configuration.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DotServerConfig {
+    /// IP address of the server, optionally with the port, like
+    /// `[2606:4700:4700::1111]:853`. The port defaults to
+    /// [`DEFAULT_DOT_PORT`].
+    pub address: String,
+
+    /// Name of the server in its TLS certificate, like
+    /// `cloudflare-dns.com`.
+    pub tls_name: String,
+}
+
+impl DotServerConfig {
+    /// Get the socket address of the server.
+    pub fn socket_addr(&self) -> Result<SocketAddr> {
+        if let Ok(address) = self.address.parse::<SocketAddr>() {
+            return Ok(address);
+        }
+
+        let ip = self.address.trim_start_matches('[').trim_end_matches(']');
+        ip.parse::<IpAddr>()
+            .map(|ip| SocketAddr::new(ip, DEFAULT_DOT_PORT))
+            .map_err(|e| anyhow!("invalid DoT address {:?} {e}", self.address))
+    }
+}
+
 /// Nydus daemon service configuration
 /// support fs driver including fusedev and fscache.
 #[derive(Clone, Debug, Deserialize)]
@@ -189,6 +597,15 @@ pub struct NydusConfig {
     /// Fscache service configuration
     #[serde(rename = "fscache")]
     pub fscache_config: Option<FscacheConfig>,
+
+    /// Validate the digests of the data chunks fetched from the registry
+    /// against the bootstrap, as the registry is untrusted.
+    #[serde(default = "default_digest_validate")]
+    pub digest_validate: bool,
+}
+
+fn default_digest_validate() -> bool {
+    true
 }
 
 impl Default for NydusConfig {
@@ -198,6 +615,7 @@ impl Default for NydusConfig {
             id: None,
             fuse_config: Some(FuseConfig::default()),
             fscache_config: None,
+            digest_validate: true,
         }
     }
 }
@@ -317,6 +735,15 @@ mod tests {
             config.max_concurrent_download,
             DEFAULT_MAX_CONCURRENT_DOWNLOAD
         );
+        assert_eq!(config.max_pull_buffer_size, DEFAULT_MAX_PULL_BUFFER_SIZE);
+        assert_eq!(config.max_pull_bandwidth, DEFAULT_MAX_PULL_BANDWIDTH);
+        assert!(!config.lazy_pull);
+
+        let lazy_config = ImageConfig {
+            lazy_pull: true,
+            ..Default::default()
+        };
+        assert_eq!(lazy_config.validate(), cfg!(feature = "estargz"));
 
         let env_work_dir = "/tmp";
         std::env::set_var(CC_IMAGE_WORK_DIR, env_work_dir);
@@ -349,6 +776,8 @@ mod tests {
         assert_eq!(config.work_dir, work_dir);
         assert_eq!(config.default_snapshot, SnapshotType::Overlay);
         assert_eq!(config.max_concurrent_download, 1);
+        assert_eq!(config.max_pull_buffer_size, DEFAULT_MAX_PULL_BUFFER_SIZE);
+        assert_eq!(config.max_pull_bandwidth, DEFAULT_MAX_PULL_BANDWIDTH);
 
         let invalid_config_file = tempdir.path().join("does-not-exist");
         assert!(!invalid_config_file.exists());
@@ -357,6 +786,298 @@ mod tests {
         assert!(!invalid_config_file.exists());
     }
 
+    #[test]
+    fn test_pull_budget_config_from_file() {
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 4,
+            "max_pull_buffer_size": 1048576,
+            "max_pull_bandwidth": 10485760
+        }"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config_file = tempdir.path().join("config.json");
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        let config = ImageConfig::try_from(config_file.as_path()).unwrap();
+        assert_eq!(config.max_concurrent_download, 4);
+        assert_eq!(config.max_pull_buffer_size, 1048576);
+        assert_eq!(config.max_pull_bandwidth, 10485760);
+
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "max_pull_buffer_size": 0
+        }"#;
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        assert!(ImageConfig::try_from(config_file.as_path()).is_err());
+    }
+
+    #[test]
+    fn test_layer_cache_config_from_file() {
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "layer_cache": {
+                "dir": "/mnt/layer-cache"
+            }
+        }"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config_file = tempdir.path().join("config.json");
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        let config = ImageConfig::try_from(config_file.as_path()).unwrap();
+        let layer_cache = config.layer_cache.unwrap();
+        assert_eq!(layer_cache.dir, PathBuf::from("/mnt/layer-cache"));
+        assert_eq!(layer_cache.max_size, DEFAULT_LAYER_CACHE_SIZE);
+        assert!(layer_cache.require_encrypted);
+    }
+
+    #[test]
+    fn test_registries_config_from_file() {
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "registries": {
+                "docker.io": {
+                    "mirrors": [
+                        {"host": "mirror.local:5000", "insecure": true},
+                        {"host": "harbor.local/docker-proxy"}
+                    ],
+                    "mirror_only": true
+                },
+                "registry.local": {
+                    "skip_verify": true
+                }
+            }
+        }"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config_file = tempdir.path().join("config.json");
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        let config = ImageConfig::try_from(config_file.as_path()).unwrap();
+        let docker = &config.registries["docker.io"];
+        assert_eq!(docker.mirrors.len(), 2);
+        assert!(docker.mirrors[0].insecure);
+        assert!(!docker.mirrors[1].insecure);
+        assert!(docker.mirror_only);
+        assert!(config.registries["registry.local"].skip_verify);
+
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "registries": {
+                "docker.io": {
+                    "mirror_only": true
+                }
+            }
+        }"#;
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        assert!(ImageConfig::try_from(config_file.as_path()).is_err());
+    }
+
+    #[test]
+    fn test_dns_config_from_file() {
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "dns": {
+                "doh": ["https://[2606:4700:4700::1111]/dns-query"],
+                "dot": [
+                    {"address": "[2606:4700:4700::1111]", "tls_name": "cloudflare-dns.com"},
+                    {"address": "1.1.1.1:8853", "tls_name": "cloudflare-dns.com"}
+                ]
+            }
+        }"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config_file = tempdir.path().join("config.json");
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        let config = ImageConfig::try_from(config_file.as_path());
+        assert_eq!(config.is_ok(), cfg!(feature = "dns-resolver"));
+
+        let config: serde_json::Value = serde_json::from_str(data).unwrap();
+        let dns: DnsConfig = serde_json::from_value(config["dns"].clone()).unwrap();
+        assert!(dns.validate());
+        assert_eq!(
+            dns.dot[0].socket_addr().unwrap(),
+            "[2606:4700:4700::1111]:853".parse().unwrap()
+        );
+        assert_eq!(
+            dns.dot[1].socket_addr().unwrap(),
+            "1.1.1.1:8853".parse().unwrap()
+        );
+
+        assert!(!DnsConfig::default().validate());
+        assert!(!DnsConfig {
+            doh: vec!["http://[2606:4700:4700::1111]/dns-query".to_string()],
+            ..Default::default()
+        }
+        .validate());
+        assert!(!DnsConfig {
+            dot: vec![DotServerConfig {
+                address: "one.one.one.one".to_string(),
+                tls_name: "cloudflare-dns.com".to_string(),
+            }],
+            ..Default::default()
+        }
+        .validate());
+    }
+
+    #[test]
+    fn test_policy_config() {
+        let config: ImageConfig = serde_json::from_str(
+            r#"{
+                "work_dir": "/var/lib/image-rs/",
+                "default_snapshot": "overlay",
+                "security_validate": true,
+                "auth": false,
+                "max_concurrent_download": 1,
+                "policy": {
+                    "inline": {"default": [{"type": "reject"}]},
+                    "digest": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert!(config.validate());
+        assert_eq!(
+            config.policy.inline,
+            Some(serde_json::json!({"default": [{"type": "reject"}]}))
+        );
+        assert!(config.policy.fallback_path.is_none());
+        assert!(!config.policy.measure);
+
+        let policy: PolicyConfig = serde_json::from_str(r#"{"digest": "9f86d081"}"#).unwrap();
+        assert!(!policy.validate());
+
+        let policy: PolicyConfig = serde_json::from_str(r#"{"measure": true}"#).unwrap();
+        assert_eq!(policy.validate(), cfg!(feature = "measurement"));
+    }
+
+    #[test]
+    fn test_resumable_download_config() {
+        let config: ImageConfig = serde_json::from_str(
+            r#"{
+                "work_dir": "/var/lib/image-rs/",
+                "default_snapshot": "overlay",
+                "security_validate": false,
+                "auth": false,
+                "max_concurrent_download": 1,
+                "resumable_download": {
+                    "checkpoint_dir": "/run/image-rs/checkpoints"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.validate(), cfg!(feature = "resumable-download"));
+
+        let resumable_download = config.resumable_download.unwrap();
+        assert_eq!(resumable_download.max_resumes, DEFAULT_MAX_DOWNLOAD_RESUMES);
+        assert_eq!(
+            resumable_download.checkpoint_dir,
+            Some(PathBuf::from("/run/image-rs/checkpoints"))
+        );
+
+        let resumable_download: ResumableDownloadConfig =
+            serde_json::from_str(r#"{"max_resumes": 0}"#).unwrap();
+        assert!(!resumable_download.validate());
+    }
+
+    #[test]
+    fn test_overlay_snapshot_config() {
+        let config: OverlaySnapshotConfig =
+            serde_json::from_str(r#"{"encrypted_upperdir": true}"#).unwrap();
+        assert_eq!(config.upperdir_size, DEFAULT_UPPERDIR_SIZE);
+        assert_eq!(config.validate(), cfg!(feature = "encrypted-upperdir"));
+
+        let config: OverlaySnapshotConfig =
+            serde_json::from_str(r#"{"encrypted_upperdir": true, "upperdir_size": 1000}"#).unwrap();
+        assert!(!config.validate());
+
+        // The size is not used unless the upperdirs are encrypted.
+        let config: OverlaySnapshotConfig =
+            serde_json::from_str(r#"{"upperdir_size": 0}"#).unwrap();
+        assert!(config.validate());
+    }
+
+    #[test]
+    fn test_verity_snapshot_config_from_file() {
+        let data = r#"{
+            "work_dir": "/var/lib/image-rs/",
+            "default_snapshot": "overlay",
+            "security_validate": false,
+            "auth": false,
+            "max_concurrent_download": 1,
+            "verity_snapshot": {
+                "fs_type": "squashfs"
+            }
+        }"#;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config_file = tempdir.path().join("config.json");
+
+        File::create(&config_file)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .unwrap();
+
+        let config = ImageConfig::try_from(config_file.as_path()).unwrap();
+        assert_eq!(config.verity_snapshot.fs_type, VerityFsType::Squashfs);
+        assert_eq!(config.verity_snapshot.fs_type.to_string(), "squashfs");
+        assert_eq!(
+            ImageConfig::default().verity_snapshot.fs_type,
+            VerityFsType::Erofs
+        );
+    }
+
     #[test]
     fn test_nydus_config_from_file() {
         let data = r#"{
@@ -392,6 +1113,7 @@ mod tests {
         assert!(config.nydus_config.is_some());
         if let Ok(nydus_config) = config.get_nydus_config() {
             assert_eq!(nydus_config.id, Some("nydus_id".to_string()));
+            assert!(nydus_config.digest_validate);
 
             assert!(nydus_config.fuse_config.is_some());
             if let Ok(fuse_config) = nydus_config.get_fuse_config() {
@@ -5,12 +5,17 @@
 use std::convert::TryFrom;
 use std::fmt;
 use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use anyhow::{bail, Result};
+use async_compression::tokio::bufread::GzipDecoder;
 use oci_distribution::manifest;
 use oci_spec::image::MediaType;
 use serde::Deserialize;
-use tokio::io::{AsyncRead, BufReader};
+use tokio::io::{AsyncBufRead, AsyncRead, BufReader, ReadBuf};
+
+pub mod zstd_chunked;
 
 /// Error message for unhandled media type.
 pub const ERR_BAD_MEDIA_TYPE: &str = "unhandled media type";
@@ -56,17 +61,22 @@ impl Compression {
     }
 
     /// Create an `AsyncRead` to decode input stream.
+    ///
+    /// A zstd stream may hold several frames, e.g. one per file in the
+    /// zstd:chunked layers, which are all decoded.
+    ///
+    /// The input is always read up to its end before the end of the
+    /// decoded stream, so that a decrypted input verifies its HMAC.
     pub fn async_decompress<'a>(
         &self,
         input: (impl AsyncRead + Unpin + 'a + Send),
     ) -> Box<dyn AsyncRead + Unpin + 'a + Send> {
         match self {
-            Self::Gzip => Box::new(async_compression::tokio::bufread::GzipDecoder::new(
-                BufReader::new(input),
-            )),
-            Self::Zstd => Box::new(async_compression::tokio::bufread::ZstdDecoder::new(
-                BufReader::new(input),
-            )),
+            Self::Gzip => Box::new(DrainedGzipDecoder {
+                decoder: GzipDecoder::new(BufReader::new(input)),
+                drained: false,
+            }),
+            Self::Zstd => Box::new(Self::async_zstd_decompress(input)),
             Self::Uncompressed => Box::new(input),
         }
     }
@@ -76,9 +86,55 @@ impl Compression {
         async_compression::tokio::bufread::GzipDecoder::new(BufReader::new(input))
     }
 
-    /// Create an `AsyncRead` to decode input zstd stream.
+    /// Create an `AsyncRead` to decode input zstd stream of one or more
+    /// frames.
     pub fn async_zstd_decompress(input: (impl AsyncRead + Unpin)) -> impl AsyncRead + Unpin {
-        async_compression::tokio::bufread::ZstdDecoder::new(BufReader::new(input))
+        let mut decoder =
+            async_compression::tokio::bufread::ZstdDecoder::new(BufReader::new(input));
+        decoder.multiple_members(true);
+        decoder
+    }
+}
+
+// A gzip decoder reading its input up to the end, while the decoder of
+// async_compression stops once the gzip member is decoded, which may be
+// before the end of its input is read.
+struct DrainedGzipDecoder<R> {
+    decoder: GzipDecoder<BufReader<R>>,
+    drained: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DrainedGzipDecoder<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.drained {
+            return Poll::Ready(Ok(()));
+        }
+
+        let filled = buf.filled().len();
+        match Pin::new(&mut self.decoder).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) if buf.filled().len() == filled => {}
+            poll => return poll,
+        }
+
+        // The bytes after the gzip member are discarded.
+        loop {
+            let input = Pin::new(self.decoder.get_mut());
+            let n = match input.poll_fill_buf(cx) {
+                Poll::Ready(Ok(data)) => data.len(),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+            if n == 0 {
+                self.drained = true;
+                return Poll::Ready(Ok(()));
+            }
+
+            Pin::new(self.decoder.get_mut()).consume(n);
+        }
     }
 }
 
@@ -216,6 +272,38 @@ mod tests {
         assert_eq!(data, output);
     }
 
+    #[tokio::test]
+    async fn test_async_gzip_decode_drained() {
+        let data: Vec<u8> = b"This is some text!".to_vec();
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        let mut bytes = encoder.finish().unwrap();
+        bytes.extend_from_slice(b"trailing");
+
+        // The error at the end of the input, e.g. a mismatched HMAC, fails
+        // the decoded stream.
+        struct FailingRead;
+        impl AsyncRead for FailingRead {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                Poll::Ready(Err(io::Error::other("bad hmac")))
+            }
+        }
+
+        let input = tokio::io::AsyncReadExt::chain(bytes.as_slice(), FailingRead);
+        let mut output = Vec::new();
+        let mut reader = Compression::Gzip.async_decompress(input);
+        assert!(
+            tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut output)
+                .await
+                .is_err()
+        );
+        assert_eq!(data, output);
+    }
+
     #[tokio::test]
     async fn test_async_zstd_decode() {
         let data: Vec<u8> = b"This is some text!".to_vec();
@@ -241,6 +329,39 @@ mod tests {
         assert_eq!(data, output);
     }
 
+    #[tokio::test]
+    async fn test_async_zstd_chunked_decode() {
+        // Files compressed in separate frames, followed by the TOC and the
+        // footer in skippable frames as in a zstd:chunked layer.
+        let files: [&[u8]; 2] = [b"This is some text!", b"This is some more text!"];
+        let mut bytes = Vec::new();
+        for file in files {
+            bytes.extend(zstd::encode_all(file, 1).unwrap());
+        }
+        for skippable in [zstd::encode_all(&b"{}"[..], 1).unwrap(), vec![0u8; 64]] {
+            bytes.extend(0x184D2A50u32.to_le_bytes());
+            bytes.extend((skippable.len() as u32).to_le_bytes());
+            bytes.extend(skippable);
+        }
+
+        let data = files.concat();
+
+        let mut output = Vec::new();
+        let mut reader = Compression::Zstd.async_decompress(bytes.as_slice());
+        assert!(
+            tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut output)
+                .await
+                .is_ok()
+        );
+        assert_eq!(data, output);
+
+        let mut output = Vec::new();
+        assert!(Compression::Zstd
+            .decompress(bytes.as_slice(), &mut output)
+            .is_ok());
+        assert_eq!(data, output);
+    }
+
     #[tokio::test]
     async fn test_try_from_compression() {
         #[derive(Debug)]
@@ -0,0 +1,311 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Metadata of the zstd:chunked layers.
+//!
+//! A zstd:chunked layer is a regular `tar+zstd` layer whose files are
+//! compressed in separate zstd frames, followed by a table of contents
+//! (TOC) in a skippable frame that describes where each file lies in the
+//! blob. The layer can thus be pulled and decompressed as a whole like any
+//! zstd layer, while the TOC allows to only fetch part of the files.
+//!
+//! The position and checksum of the TOC are given by the annotations of
+//! the layer descriptor.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use sha2::Digest;
+
+use crate::digest::{DIGEST_SHA256_PREFIX, DIGEST_SHA512_PREFIX};
+
+/// Annotation of the digest of the compressed TOC.
+pub const ANNOTATION_MANIFEST_CHECKSUM: &str =
+    "io.github.containers.zstd-chunked.manifest-checksum";
+
+/// Annotation of the position of the compressed TOC, in format
+/// `<offset>:<length>:<uncompressed length>:<type>`.
+pub const ANNOTATION_MANIFEST_POSITION: &str =
+    "io.github.containers.zstd-chunked.manifest-position";
+
+/// The only supported type of the TOC.
+pub const MANIFEST_TYPE_CRFS: u64 = 1;
+
+/// Max uncompressed size of the TOC accepted.
+const MAX_MANIFEST_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Position of the compressed TOC in the layer blob.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestPosition {
+    pub offset: u64,
+    pub length: u64,
+    pub length_uncompressed: u64,
+    pub manifest_type: u64,
+}
+
+impl ManifestPosition {
+    fn parse(position: &str) -> Result<Self> {
+        let fields = position
+            .split(':')
+            .map(|field| field.parse::<u64>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .with_context(|| format!("invalid manifest position {position:?}"))?;
+
+        let [offset, length, length_uncompressed, manifest_type] = fields[..] else {
+            bail!("invalid manifest position {position:?}: expected 4 fields");
+        };
+
+        if manifest_type != MANIFEST_TYPE_CRFS {
+            bail!("unsupported manifest type {manifest_type}");
+        }
+
+        if offset.checked_add(length).is_none() {
+            bail!("invalid manifest position {position:?}: out of range");
+        }
+
+        if length_uncompressed > MAX_MANIFEST_SIZE {
+            bail!("manifest size {length_uncompressed} exceeds {MAX_MANIFEST_SIZE}");
+        }
+
+        Ok(ManifestPosition {
+            offset,
+            length,
+            length_uncompressed,
+            manifest_type,
+        })
+    }
+}
+
+/// The zstd:chunked metadata of a layer, given by its annotations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZstdChunkedMetadata {
+    /// Digest of the compressed TOC.
+    pub manifest_checksum: String,
+
+    /// Position of the compressed TOC in the layer blob.
+    pub manifest_position: ManifestPosition,
+}
+
+impl ZstdChunkedMetadata {
+    /// Get the zstd:chunked metadata from the annotations of a layer
+    /// descriptor. It returns `None` if the layer is not zstd:chunked.
+    pub fn from_annotations(annotations: Option<&HashMap<String, String>>) -> Result<Option<Self>> {
+        let Some(annotations) = annotations else {
+            return Ok(None);
+        };
+
+        let (checksum, position) = match (
+            annotations.get(ANNOTATION_MANIFEST_CHECKSUM),
+            annotations.get(ANNOTATION_MANIFEST_POSITION),
+        ) {
+            (None, None) => return Ok(None),
+            (Some(checksum), Some(position)) => (checksum, position),
+            _ => bail!(
+                "both {ANNOTATION_MANIFEST_CHECKSUM} and {ANNOTATION_MANIFEST_POSITION} are required"
+            ),
+        };
+
+        Ok(Some(ZstdChunkedMetadata {
+            manifest_checksum: checksum.clone(),
+            manifest_position: ManifestPosition::parse(position)?,
+        }))
+    }
+
+    /// Byte range of the compressed TOC in the layer blob, to fetch it
+    /// without the rest of the layer.
+    pub fn manifest_range(&self) -> Range<u64> {
+        let position = &self.manifest_position;
+        position.offset..position.offset + position.length
+    }
+
+    /// Parse the TOC from its compressed data, which is checked against
+    /// the checksum and the position of the metadata.
+    pub fn parse_manifest(&self, compressed: &[u8]) -> Result<Toc> {
+        if compressed.len() as u64 != self.manifest_position.length {
+            bail!(
+                "unexpected manifest length {}, expected {}",
+                compressed.len(),
+                self.manifest_position.length
+            );
+        }
+
+        let checksum = if self.manifest_checksum.starts_with(DIGEST_SHA256_PREFIX) {
+            format!(
+                "{}{:x}",
+                DIGEST_SHA256_PREFIX,
+                sha2::Sha256::digest(compressed)
+            )
+        } else if self.manifest_checksum.starts_with(DIGEST_SHA512_PREFIX) {
+            format!(
+                "{}{:x}",
+                DIGEST_SHA512_PREFIX,
+                sha2::Sha512::digest(compressed)
+            )
+        } else {
+            bail!("unsupported manifest checksum {:?}", self.manifest_checksum);
+        };
+        if checksum != self.manifest_checksum {
+            bail!(
+                "unequal manifest checksum {:?} expected {:?}",
+                checksum,
+                self.manifest_checksum
+            );
+        }
+
+        let manifest = zstd::bulk::decompress(
+            compressed,
+            self.manifest_position.length_uncompressed as usize,
+        )
+        .map_err(|e| anyhow!("failed to decompress manifest {e}"))?;
+
+        serde_json::from_slice(&manifest).map_err(|e| anyhow!("failed to parse manifest {e}"))
+    }
+}
+
+/// Table of contents of a zstd:chunked layer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Toc {
+    pub version: i32,
+
+    #[serde(default)]
+    pub entries: Vec<TocEntry>,
+}
+
+/// An entry of the TOC, i.e. a file of the layer or a chunk of a file.
+///
+/// The `offset` and `end_offset` give the range of the zstd frame of the
+/// entry in the layer blob.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TocEntry {
+    /// `reg`, `chunk`, `dir`, `symlink`, `hardlink`, `char`, `block` or
+    /// `fifo`.
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    pub name: String,
+    pub link_name: String,
+    pub mode: i64,
+    pub size: i64,
+    pub uid: i64,
+    pub gid: i64,
+    pub modtime: Option<String>,
+    pub offset: u64,
+    pub end_offset: u64,
+    pub digest: String,
+    pub chunk_offset: i64,
+    pub chunk_size: i64,
+    pub chunk_digest: String,
+}
+
+impl TocEntry {
+    /// Byte range of the zstd frame of the entry in the layer blob, empty
+    /// for the entries without content.
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.end_offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn annotations(checksum: Option<&str>, position: Option<&str>) -> HashMap<String, String> {
+        let mut annotations = HashMap::new();
+        if let Some(checksum) = checksum {
+            annotations.insert(
+                ANNOTATION_MANIFEST_CHECKSUM.to_string(),
+                checksum.to_string(),
+            );
+        }
+        if let Some(position) = position {
+            annotations.insert(
+                ANNOTATION_MANIFEST_POSITION.to_string(),
+                position.to_string(),
+            );
+        }
+        annotations
+    }
+
+    #[rstest]
+    #[case(Some("sha256:abc"), Some("100:20:40:1"), true)]
+    #[case(Some("sha256:abc"), Some("100:20:40"), false)]
+    #[case(Some("sha256:abc"), Some("100:20:40:2"), false)]
+    #[case(Some("sha256:abc"), Some("a:b:c:d"), false)]
+    #[case(Some("sha256:abc"), Some("100:20:1099511627776:1"), false)]
+    #[case(Some("sha256:abc"), None, false)]
+    #[case(None, Some("100:20:40:1"), false)]
+    fn test_from_annotations(
+        #[case] checksum: Option<&str>,
+        #[case] position: Option<&str>,
+        #[case] ok: bool,
+    ) {
+        let annotations = annotations(checksum, position);
+        let metadata = ZstdChunkedMetadata::from_annotations(Some(&annotations));
+        assert_eq!(metadata.is_ok(), ok);
+
+        if ok {
+            let metadata = metadata.unwrap().unwrap();
+            assert_eq!(metadata.manifest_checksum, "sha256:abc");
+            assert_eq!(metadata.manifest_range(), 100..120);
+        }
+    }
+
+    #[test]
+    fn test_not_chunked() {
+        assert_eq!(ZstdChunkedMetadata::from_annotations(None).unwrap(), None);
+
+        let annotations = HashMap::from([("foo".to_string(), "bar".to_string())]);
+        assert_eq!(
+            ZstdChunkedMetadata::from_annotations(Some(&annotations)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let toc = r#"{
+            "version": 1,
+            "entries": [
+                {"type": "dir", "name": "etc/", "mode": 493},
+                {
+                    "type": "reg",
+                    "name": "etc/hostname",
+                    "mode": 420,
+                    "size": 9,
+                    "offset": 0,
+                    "endOffset": 27,
+                    "digest": "sha256:d7f1f8134367b0a1e0bd5b9c7b4e1b7d6f2e3c4a5b6c7d8e9f0a1b2c3d4e5f6a"
+                }
+            ]
+        }"#;
+        let compressed = zstd::encode_all(toc.as_bytes(), 1).unwrap();
+        let checksum = format!(
+            "{}{:x}",
+            DIGEST_SHA256_PREFIX,
+            sha2::Sha256::digest(&compressed)
+        );
+        let position = format!("1024:{}:{}:1", compressed.len(), toc.len());
+
+        let annotations = annotations(Some(&checksum), Some(&position));
+        let metadata = ZstdChunkedMetadata::from_annotations(Some(&annotations))
+            .unwrap()
+            .unwrap();
+
+        let toc = metadata.parse_manifest(&compressed).unwrap();
+        assert_eq!(toc.version, 1);
+        assert_eq!(toc.entries.len(), 2);
+        assert_eq!(toc.entries[0].entry_type, "dir");
+        assert_eq!(toc.entries[1].name, "etc/hostname");
+        assert_eq!(toc.entries[1].range(), 0..27);
+
+        // A corrupted manifest is refused.
+        let mut corrupted = compressed.clone();
+        corrupted[0] ^= 0xff;
+        assert!(metadata.parse_manifest(&corrupted).is_err());
+        assert!(metadata.parse_manifest(&compressed[1..]).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fetch ranges of a layer blob from the registry.
+
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use oci_distribution::{secrets::RegistryAuth, Reference};
+use reqwest::header::{HeaderMap, HeaderValue, RANGE};
+use reqwest::StatusCode;
+use tokio::runtime::Runtime;
+
+use crate::registry::http::RegistryHttp;
+
+/// Read ranges of a blob.
+pub trait BlobReader: Send + 'static {
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>>;
+}
+
+/// A blob in a registry, read by ranges with the `Range` header.
+///
+/// The requests are made on a runtime of its own, as it is used by the
+/// FUSE threads.
+pub struct RegistryBlob {
+    runtime: Runtime,
+    http: RegistryHttp,
+    url: String,
+}
+
+impl RegistryBlob {
+    pub fn new(reference: &Reference, auth: RegistryAuth, digest: &str) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let http = RegistryHttp::new(reference, auth);
+        let url = http.url(&format!("blobs/{digest}"));
+
+        Ok(RegistryBlob { runtime, http, url })
+    }
+
+    async fn get(&self, range: &Range<u64>) -> Result<Vec<u8>> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RANGE,
+            HeaderValue::from_str(&format!("bytes={}-{}", range.start, range.end - 1))?,
+        );
+        let response = self.http.get(&self.url, headers).await?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            bail!(
+                "failed to fetch range {:?} of {}: {}",
+                range,
+                self.url,
+                response.status()
+            );
+        }
+
+        let data = response.bytes().await?;
+        if data.len() as u64 != range.end - range.start {
+            bail!("unexpected size {} of range {:?}", data.len(), range);
+        }
+
+        Ok(data.to_vec())
+    }
+}
+
+impl BlobReader for RegistryBlob {
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        self.runtime.block_on(self.get(&range))
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Read-only FUSE filesystem of a [`LazyLayer`].
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use fuser::{
+    BackgroundSession, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEntry, ReplyXattr, Request,
+};
+use log::error;
+
+use super::fetcher::BlobReader;
+use super::layer::{LazyLayer, Node};
+
+/// The layer never changes once mounted.
+const TTL: Duration = Duration::from_secs(3600);
+
+const BLOCK_SIZE: u32 = 4096;
+
+/// Mount the layer at `mountpoint` on a FUSE session of its own, which is
+/// unmounted when dropped.
+pub fn mount<B: BlobReader>(layer: LazyLayer<B>, mountpoint: &Path) -> Result<BackgroundSession> {
+    std::fs::create_dir_all(mountpoint)?;
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("estargz".to_string()),
+        MountOption::Subtype("estargz".to_string()),
+        MountOption::AllowOther,
+        MountOption::DefaultPermissions,
+    ];
+
+    fuser::spawn_mount2(EStargzFs { layer }, mountpoint, &options)
+        .map_err(|e| anyhow!("failed to mount eStargz layer at {:?}: {e}", mountpoint))
+}
+
+struct EStargzFs<B> {
+    layer: LazyLayer<B>,
+}
+
+impl<B: BlobReader> EStargzFs<B> {
+    fn kind(node: &Node) -> FileType {
+        match node.entry.entry_type.as_str() {
+            "dir" => FileType::Directory,
+            "symlink" => FileType::Symlink,
+            "char" => FileType::CharDevice,
+            "block" => FileType::BlockDevice,
+            "fifo" => FileType::NamedPipe,
+            _ => FileType::RegularFile,
+        }
+    }
+
+    fn attr(&self, ino: u64, node: &Node) -> FileAttr {
+        let kind = Self::kind(node);
+        let (size, nlink) = match kind {
+            FileType::RegularFile => (node.entry.size, node.nlink),
+            FileType::Symlink => (node.entry.link_name.len() as u64, node.nlink),
+            FileType::Directory => {
+                let subdirs = node
+                    .children
+                    .values()
+                    .filter(|child| self.layer.node(**child).map_or(false, Node::is_dir))
+                    .count();
+                (0, 2 + subdirs as u32)
+            }
+            _ => (0, node.nlink),
+        };
+
+        let mtime = chrono::DateTime::parse_from_rfc3339(&node.entry.modtime)
+            .ok()
+            .and_then(|time| u64::try_from(time.timestamp()).ok())
+            .map_or(UNIX_EPOCH, |secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm: (node.entry.mode & 0o7777) as u16,
+            nlink,
+            uid: node.entry.uid,
+            gid: node.entry.gid,
+            rdev: encode_dev(node.entry.dev_major, node.entry.dev_minor),
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    fn xattr(&self, ino: u64, name: &OsStr) -> Option<Vec<u8>> {
+        let value = self.layer.node(ino)?.entry.xattrs.get(name.to_str()?)?;
+        base64::engine::general_purpose::STANDARD.decode(value).ok()
+    }
+}
+
+/// Encode a device number as the kernel does for 32 bits.
+fn encode_dev(major: u32, minor: u32) -> u32 {
+    (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12)
+}
+
+/// Reply `data` to `getxattr` or `listxattr` of a buffer of `size` bytes.
+fn reply_xattr(reply: ReplyXattr, size: u32, data: &[u8]) {
+    if size == 0 {
+        reply.size(data.len() as u32);
+    } else if (size as usize) < data.len() {
+        reply.error(libc::ERANGE);
+    } else {
+        reply.data(data);
+    }
+}
+
+impl<B: BlobReader> Filesystem for EStargzFs<B> {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match self
+            .layer
+            .lookup(parent, name)
+            .and_then(|ino| Some((ino, self.layer.node(ino)?)))
+        {
+            Some((ino, node)) => reply.entry(&TTL, &self.attr(ino, node), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.layer.node(ino) {
+            Some(node) => reply.attr(&TTL, &self.attr(ino, node)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        match self.layer.node(ino) {
+            Some(node) if node.entry.entry_type == "symlink" => {
+                reply.data(node.entry.link_name.as_bytes())
+            }
+            Some(_) => reply.error(libc::EINVAL),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Ok(offset) = u64::try_from(offset) else {
+            reply.error(libc::EINVAL);
+            return;
+        };
+
+        match self.layer.read(ino, offset, size as u64) {
+            Ok(data) => reply.data(&data),
+            Err(e) => {
+                error!("failed to read inode {} of eStargz layer: {:?}", ino, e);
+                reply.error(libc::EIO);
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(node) = self.layer.node(ino).filter(|node| node.is_dir()) else {
+            reply.error(libc::ENOTDIR);
+            return;
+        };
+
+        let entries = [
+            (ino, FileType::Directory, OsStr::new(".")),
+            (node.parent, FileType::Directory, OsStr::new("..")),
+        ]
+        .into_iter()
+        .chain(node.children.iter().filter_map(|(name, child)| {
+            let kind = Self::kind(self.layer.node(*child)?);
+            Some((*child, kind, name.as_os_str()))
+        }));
+
+        for (index, (ino, kind, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+            if reply.add(ino, index as i64 + 1, kind, name) {
+                break;
+            }
+        }
+
+        reply.ok();
+    }
+
+    fn getxattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        name: &OsStr,
+        size: u32,
+        reply: ReplyXattr,
+    ) {
+        match self.xattr(ino, name) {
+            Some(value) => reply_xattr(reply, size, &value),
+            None => reply.error(libc::ENODATA),
+        }
+    }
+
+    fn listxattr(&mut self, _req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
+        let Some(node) = self.layer.node(ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+
+        let mut names = Vec::new();
+        for name in node.entry.xattrs.keys() {
+            names.extend(name.as_bytes());
+            names.push(0);
+        }
+
+        reply_xattr(reply, size, &names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_dev() {
+        assert_eq!(encode_dev(0, 0), 0);
+        assert_eq!(encode_dev(1, 3), 0x103);
+        assert_eq!(encode_dev(8, 0x123), 0x100823);
+    }
+}
@@ -0,0 +1,484 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! File tree of an eStargz layer, whose file chunks are fetched on demand.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::Read;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use sha2::Digest;
+
+use super::fetcher::BlobReader;
+use super::toc::{
+    parse_footer, parse_toc, TocEntry, FOOTER_SIZE, NO_PREFETCH_LANDMARK, PREFETCH_LANDMARK,
+};
+use crate::digest::DIGEST_SHA256_PREFIX;
+
+/// Inode of the root directory.
+pub const ROOT_INODE: u64 = 1;
+
+/// A chunk of a regular file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Chunk {
+    /// Offset of the chunk in the file.
+    file_offset: u64,
+    size: u64,
+    /// Range of the gzip member of the chunk in the blob, which may be
+    /// followed by the members of the next tar headers.
+    range: Range<u64>,
+    digest: String,
+}
+
+/// A file of the layer.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub entry: TocEntry,
+    pub parent: u64,
+    pub children: BTreeMap<OsString, u64>,
+    pub nlink: u32,
+    chunks: Vec<Chunk>,
+}
+
+impl Node {
+    fn new(entry: TocEntry, parent: u64) -> Self {
+        Node {
+            entry,
+            parent,
+            children: BTreeMap::new(),
+            nlink: 1,
+            chunks: Vec::new(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.entry.entry_type == "dir"
+    }
+}
+
+/// An eStargz layer, whose file chunks are read from the blob when first
+/// needed, checked and cached under the cache dir.
+pub struct LazyLayer<B> {
+    blob: B,
+    nodes: Vec<Node>,
+    cache_dir: PathBuf,
+}
+
+impl<B: BlobReader> LazyLayer<B> {
+    /// Open the layer of `blob_size` bytes by reading its TOC, which is
+    /// checked against `toc_digest`.
+    pub fn open(blob: B, blob_size: u64, toc_digest: &str, cache_dir: PathBuf) -> Result<Self> {
+        if blob_size < FOOTER_SIZE {
+            bail!("eStargz blob too small: {} bytes", blob_size);
+        }
+
+        let toc_end = blob_size - FOOTER_SIZE;
+        let toc_offset = parse_footer(&blob.read_range(toc_end..blob_size)?)?;
+        if toc_offset >= toc_end {
+            bail!("invalid eStargz TOC offset {}", toc_offset);
+        }
+
+        let toc = parse_toc(&blob.read_range(toc_offset..toc_end)?, toc_digest)?;
+        let nodes = build_tree(toc.entries, toc_offset)?;
+
+        fs::create_dir_all(&cache_dir)
+            .with_context(|| format!("failed to create cache dir {:?}", cache_dir))?;
+
+        Ok(LazyLayer {
+            blob,
+            nodes,
+            cache_dir,
+        })
+    }
+
+    pub fn node(&self, ino: u64) -> Option<&Node> {
+        ino.checked_sub(1)
+            .and_then(|index| self.nodes.get(index as usize))
+    }
+
+    pub fn lookup(&self, parent: u64, name: &OsStr) -> Option<u64> {
+        match name.to_str() {
+            Some(".") => Some(parent),
+            Some("..") => self.node(parent).map(|node| node.parent),
+            _ => self.node(parent)?.children.get(name).copied(),
+        }
+    }
+
+    /// Read up to `size` bytes at `offset` of a regular file.
+    pub fn read(&self, ino: u64, offset: u64, size: u64) -> Result<Vec<u8>> {
+        let node = self.node(ino).ok_or_else(|| anyhow!("no inode {}", ino))?;
+        if node.entry.entry_type != "reg" {
+            bail!("inode {} is not a regular file", ino);
+        }
+
+        let end = offset.saturating_add(size).min(node.entry.size);
+        let mut data = Vec::new();
+        for chunk in &node.chunks {
+            let chunk_end = chunk.file_offset + chunk.size;
+            if chunk_end <= offset || chunk.file_offset >= end {
+                continue;
+            }
+
+            let from = offset.max(chunk.file_offset);
+            let to = end.min(chunk_end);
+            let mut buffer = vec![0u8; (to - from) as usize];
+            let cached = fs::File::open(self.cached_chunk(chunk)?)?;
+            cached.read_exact_at(&mut buffer, from - chunk.file_offset)?;
+            data.extend(buffer);
+        }
+
+        Ok(data)
+    }
+
+    /// Get the path of the cached chunk, fetching it first if needed.
+    fn cached_chunk(&self, chunk: &Chunk) -> Result<PathBuf> {
+        let path = self.cache_dir.join(chunk_id(&chunk.digest)?);
+        if fs::metadata(&path).map_or(false, |metadata| metadata.len() == chunk.size) {
+            return Ok(path);
+        }
+
+        let compressed = self.blob.read_range(chunk.range.clone())?;
+        let mut data = Vec::with_capacity(chunk.size as usize);
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .take(chunk.size)
+            .read_to_end(&mut data)?;
+        if data.len() as u64 != chunk.size {
+            bail!(
+                "unexpected chunk size {} expected {}",
+                data.len(),
+                chunk.size
+            );
+        }
+
+        let digest = format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(&data));
+        if digest != chunk.digest {
+            bail!(
+                "unequal chunk digest {:?} expected {:?}",
+                digest,
+                chunk.digest
+            );
+        }
+
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, &data)?;
+        fs::rename(&temp, &path)?;
+
+        Ok(path)
+    }
+}
+
+/// Name of the cache file of a chunk, from its digest.
+fn chunk_id(digest: &str) -> Result<&str> {
+    digest
+        .strip_prefix(DIGEST_SHA256_PREFIX)
+        .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| anyhow!("unsupported chunk digest {:?}", digest))
+}
+
+/// Clean a TOC path into a relative path without `.` components, refusing
+/// the `..` components.
+fn clean_name(name: &str) -> Result<String> {
+    let mut components = Vec::new();
+    for component in name.split('/') {
+        match component {
+            "" | "." => continue,
+            ".." => bail!("invalid path {:?} in eStargz TOC", name),
+            component => components.push(component),
+        }
+    }
+
+    Ok(components.join("/"))
+}
+
+/// Build the inodes of the TOC entries, the root being [`ROOT_INODE`].
+fn build_tree(entries: Vec<TocEntry>, toc_offset: u64) -> Result<Vec<Node>> {
+    let root = TocEntry {
+        entry_type: "dir".to_string(),
+        mode: 0o755,
+        ..Default::default()
+    };
+    let mut tree = Tree {
+        nodes: vec![Node::new(root, ROOT_INODE)],
+        paths: HashMap::from([(String::new(), ROOT_INODE)]),
+    };
+
+    for mut entry in entries {
+        let path = clean_name(&entry.name)?;
+        match entry.entry_type.as_str() {
+            "dir" => {
+                let ino = tree.dir(&path)?;
+                let node = tree.node_mut(ino);
+                entry.name = path;
+                node.entry = entry;
+            }
+            "chunk" => {
+                let ino = tree
+                    .paths
+                    .get(&path)
+                    .copied()
+                    .ok_or_else(|| anyhow!("chunk of unknown file {:?}", path))?;
+                let node = tree.node_mut(ino);
+                if node.entry.entry_type != "reg" {
+                    bail!("chunk of non regular file {:?}", path);
+                }
+                node.chunks.push(chunk(&entry, node.entry.size));
+            }
+            "hardlink" => {
+                let target = clean_name(&entry.link_name)?;
+                let ino = tree
+                    .paths
+                    .get(&target)
+                    .copied()
+                    .ok_or_else(|| anyhow!("hardlink to unknown file {:?}", target))?;
+                tree.link(&path, ino)?;
+                tree.node_mut(ino).nlink += 1;
+            }
+            "reg" | "symlink" | "char" | "block" | "fifo" => {
+                // The landmarks only mark the files to prefetch.
+                if path == PREFETCH_LANDMARK || path == NO_PREFETCH_LANDMARK {
+                    continue;
+                }
+
+                let (parent_path, _) = split_path(&path);
+                let parent = tree.dir(parent_path)?;
+                entry.name = path.clone();
+                let mut node = Node::new(entry, parent);
+                if node.entry.entry_type == "reg" && node.entry.size > 0 {
+                    let first = chunk(&node.entry, node.entry.size);
+                    node.chunks.push(first);
+                }
+                tree.push(&path, node)?;
+            }
+            other => warn!("skip eStargz TOC entry {:?} of type {:?}", path, other),
+        }
+    }
+
+    let mut nodes = tree.nodes;
+
+    // A chunk member ends before the next member holding file content.
+    let mut offsets: Vec<u64> = nodes
+        .iter()
+        .flat_map(|node| node.chunks.iter().map(|chunk| chunk.range.start))
+        .collect();
+    offsets.sort_unstable();
+    offsets.dedup();
+    for node in nodes.iter_mut() {
+        let mut file_offset = 0;
+        for chunk in node.chunks.iter_mut() {
+            let next = offsets.partition_point(|offset| *offset <= chunk.range.start);
+            chunk.range.end = offsets.get(next).copied().unwrap_or(toc_offset);
+            if chunk.range.is_empty() || chunk.file_offset != file_offset {
+                bail!("invalid chunks of {:?} in eStargz TOC", node.entry.name);
+            }
+            file_offset += chunk.size;
+        }
+        if !node.chunks.is_empty() && file_offset != node.entry.size {
+            bail!("incomplete chunks of {:?} in eStargz TOC", node.entry.name);
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// The chunk of a TOC entry of a file of `file_size` bytes, whose range
+/// end is set once all the chunks are known.
+fn chunk(entry: &TocEntry, file_size: u64) -> Chunk {
+    let size = match entry.chunk_size {
+        0 => file_size.saturating_sub(entry.chunk_offset),
+        size => size,
+    };
+
+    Chunk {
+        file_offset: entry.chunk_offset,
+        size,
+        range: entry.offset..entry.offset,
+        digest: entry.chunk_digest.clone(),
+    }
+}
+
+fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+struct Tree {
+    nodes: Vec<Node>,
+    paths: HashMap<String, u64>,
+}
+
+impl Tree {
+    fn node_mut(&mut self, ino: u64) -> &mut Node {
+        &mut self.nodes[(ino - 1) as usize]
+    }
+
+    /// Get the directory at `path`, creating it and its parents if missing.
+    fn dir(&mut self, path: &str) -> Result<u64> {
+        if let Some(ino) = self.paths.get(path).copied() {
+            if !self.node_mut(ino).is_dir() {
+                bail!("{:?} is not a directory in eStargz TOC", path);
+            }
+            return Ok(ino);
+        }
+
+        let (parent_path, _) = split_path(path);
+        let parent = self.dir(parent_path)?;
+        let entry = TocEntry {
+            name: path.to_string(),
+            entry_type: "dir".to_string(),
+            mode: 0o755,
+            ..Default::default()
+        };
+        self.push(path, Node::new(entry, parent))
+    }
+
+    fn push(&mut self, path: &str, node: Node) -> Result<u64> {
+        self.nodes.push(node);
+        let ino = self.nodes.len() as u64;
+        self.link(path, ino)?;
+        Ok(ino)
+    }
+
+    fn link(&mut self, path: &str, ino: u64) -> Result<()> {
+        let (parent_path, name) = split_path(path);
+        let parent = self.dir(parent_path)?;
+        self.node_mut(parent)
+            .children
+            .insert(OsString::from(name), ino);
+        self.paths.insert(path.to_string(), ino);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::estargz::toc::tests::{footer, toc_member};
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    impl BlobReader for Vec<u8> {
+        fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>> {
+            self.get(range.start as usize..range.end as usize)
+                .map(|data| data.to_vec())
+                .ok_or_else(|| anyhow!("out of range"))
+        }
+    }
+
+    fn member(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn digest(data: &[u8]) -> String {
+        format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(data))
+    }
+
+    /// Build an eStargz blob holding `bin/hello` in two chunks, a symlink
+    /// and a hardlink to it.
+    fn blob() -> (Vec<u8>, String) {
+        let first = b"Hello, ";
+        let second = b"eStargz!";
+
+        // The tar headers and padding are not read, so any data will do.
+        let mut blob = member(b"tar headers");
+        let first_offset = blob.len();
+        blob.extend(member(first));
+        let second_offset = blob.len();
+        blob.extend(member(second));
+        blob.extend(member(b"tar headers"));
+
+        let toc = serde_json::json!({
+            "version": 1,
+            "entries": [
+                {"name": "./", "type": "dir", "mode": 0o755},
+                {
+                    "name": "bin/hello",
+                    "type": "reg",
+                    "size": first.len() + second.len(),
+                    "mode": 0o755,
+                    "offset": first_offset,
+                    "chunkSize": first.len(),
+                    "chunkDigest": digest(first),
+                },
+                {
+                    "name": "bin/hello",
+                    "type": "chunk",
+                    "offset": second_offset,
+                    "chunkOffset": first.len(),
+                    "chunkDigest": digest(second),
+                },
+                {"name": "bin/hi", "type": "symlink", "linkName": "hello"},
+                {"name": "usr/bin/hello", "type": "hardlink", "linkName": "bin/hello"},
+            ]
+        })
+        .to_string();
+
+        let toc_offset = blob.len() as u64;
+        blob.extend(toc_member(toc.as_bytes()));
+        blob.extend(footer(toc_offset));
+
+        (blob, digest(toc.as_bytes()))
+    }
+
+    #[test]
+    fn test_lazy_layer() {
+        let (blob, toc_digest) = blob();
+        let tempdir = tempfile::tempdir().unwrap();
+        let blob_size = blob.len() as u64;
+        let layer =
+            LazyLayer::open(blob, blob_size, &toc_digest, tempdir.path().join("cache")).unwrap();
+
+        let bin = layer.lookup(ROOT_INODE, OsStr::new("bin")).unwrap();
+        assert!(layer.node(bin).unwrap().is_dir());
+        let hello = layer.lookup(bin, OsStr::new("hello")).unwrap();
+        assert_eq!(layer.node(hello).unwrap().nlink, 2);
+
+        let usr_bin = layer.lookup(ROOT_INODE, OsStr::new("usr")).unwrap();
+        let usr_bin = layer.lookup(usr_bin, OsStr::new("bin")).unwrap();
+        assert_eq!(layer.lookup(usr_bin, OsStr::new("hello")), Some(hello));
+
+        let hi = layer.lookup(bin, OsStr::new("hi")).unwrap();
+        assert_eq!(layer.node(hi).unwrap().entry.link_name, "hello");
+        assert!(layer.lookup(bin, OsStr::new("missing")).is_none());
+
+        assert_eq!(layer.read(hello, 0, 100).unwrap(), b"Hello, eStargz!");
+        assert_eq!(layer.read(hello, 5, 4).unwrap(), b", eS");
+        assert_eq!(layer.read(hello, 100, 4).unwrap(), b"");
+        assert!(layer.read(hi, 0, 4).is_err());
+
+        // The chunks are cached once fetched.
+        assert_eq!(
+            fs::read_dir(tempdir.path().join("cache")).unwrap().count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_lazy_layer_bad_toc() {
+        let (blob, _) = blob();
+        let tempdir = tempfile::tempdir().unwrap();
+        let blob_size = blob.len() as u64;
+        assert!(LazyLayer::open(
+            blob,
+            blob_size,
+            &digest(b"another TOC"),
+            tempdir.path().join("cache")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_clean_name() {
+        assert_eq!(clean_name("./bin/hello").unwrap(), "bin/hello");
+        assert_eq!(clean_name("etc/").unwrap(), "etc");
+        assert_eq!(clean_name("./").unwrap(), "");
+        assert!(clean_name("../etc/passwd").is_err());
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lazy pulling of the eStargz layers.
+//!
+//! An eStargz layer is a regular `tar+gzip` layer whose files are
+//! compressed in separate gzip members, followed by a table of contents
+//! (TOC) and a footer pointing at it. Instead of being pulled and unpacked,
+//! such a layer is mounted by FUSE from its TOC, and the file chunks are
+//! fetched from the registry by ranges when first read. Each chunk is
+//! checked against its digest in the TOC, itself checked against the TOC
+//! digest annotation of the layer, before being cached in the guest.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use log::info;
+use oci_distribution::manifest::OciDescriptor;
+use oci_distribution::{secrets::RegistryAuth, Reference};
+
+use crate::decoder::Compression;
+use crate::image::LayerMeta;
+
+pub mod fetcher;
+pub mod fs;
+pub mod layer;
+pub mod toc;
+
+/// Annotation of the digest of the TOC of an eStargz layer.
+pub const TOC_DIGEST_ANNOTATION: &str = "containerd.io/snapshot/stargz/toc.digest";
+
+lazy_static::lazy_static! {
+    // The FUSE sessions of the mounted layers, which are unmounted when
+    // dropped.
+    static ref SESSIONS: Mutex<HashMap<String, fuser::BackgroundSession>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Check whether the layer can be lazily pulled, i.e. it is an unencrypted
+/// gzip layer with an eStargz TOC.
+pub fn is_lazy_pullable(layer: &OciDescriptor) -> bool {
+    let has_toc = layer.annotations.as_ref().map_or(false, |annotations| {
+        annotations.contains_key(TOC_DIGEST_ANNOTATION)
+    });
+
+    has_toc
+        && matches!(
+            Compression::try_from(layer.media_type.as_str()),
+            Ok(Compression::Gzip)
+        )
+}
+
+/// Mount the eStargz layer under `data_dir` instead of pulling it, and
+/// return its layer meta whose store path is the mountpoint.
+pub async fn mount_layer(
+    reference: &Reference,
+    auth: &RegistryAuth,
+    layer: &OciDescriptor,
+    diff_id: &str,
+    data_dir: &Path,
+) -> Result<LayerMeta> {
+    let toc_digest = layer
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.get(TOC_DIGEST_ANNOTATION))
+        .ok_or_else(|| anyhow!("no {} annotation", TOC_DIGEST_ANNOTATION))?
+        .clone();
+    let blob_size =
+        u64::try_from(layer.size).map_err(|_| anyhow!("invalid layer size {}", layer.size))?;
+
+    let blob_id = layer.digest.replace(':', "_");
+    let mountpoint = data_dir.join(&blob_id);
+    let cache_dir = data_dir.join(format!("{blob_id}.cache"));
+
+    let blob = fetcher::RegistryBlob::new(reference, auth.clone(), &layer.digest)?;
+    let target = mountpoint.clone();
+    let session = tokio::task::spawn_blocking(move || {
+        let layer = layer::LazyLayer::open(blob, blob_size, &toc_digest, cache_dir)?;
+        fs::mount(layer, &target)
+    })
+    .await
+    .map_err(|e| anyhow!("failed to mount eStargz layer {e}"))??;
+
+    info!("lazily pulling layer {} at {:?}", layer.digest, mountpoint);
+    SESSIONS
+        .lock()
+        .expect("eStargz sessions lock poisoned")
+        .insert(layer.digest.clone(), session);
+
+    Ok(LayerMeta {
+        decoder: Compression::Gzip,
+        encrypted: false,
+        compressed_digest: layer.digest.clone(),
+        uncompressed_digest: diff_id.to_string(),
+        store_path: mountpoint.display().to_string(),
+    })
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Footer and table of contents (TOC) of the eStargz layers.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+use sha2::Digest;
+
+use crate::digest::DIGEST_SHA256_PREFIX;
+
+/// Size of the footer at the end of an eStargz blob.
+pub const FOOTER_SIZE: u64 = 51;
+
+/// Name of the TOC in the tar of its gzip member.
+pub const TOC_TAR_NAME: &str = "stargz.index.json";
+
+/// Landmark file marking the end of the files to prefetch.
+pub const PREFETCH_LANDMARK: &str = ".prefetch.landmark";
+
+/// Landmark file marking that no file is to prefetch.
+pub const NO_PREFETCH_LANDMARK: &str = ".no.prefetch.landmark";
+
+/// Max size of the TOC accepted.
+const MAX_TOC_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Table of contents of an eStargz layer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct Toc {
+    pub version: i32,
+
+    #[serde(default)]
+    pub entries: Vec<TocEntry>,
+}
+
+/// An entry of the TOC, i.e. a file of the layer or a chunk of a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TocEntry {
+    /// Path of the file in the layer.
+    pub name: String,
+
+    /// `dir`, `reg`, `chunk`, `symlink`, `hardlink`, `char`, `block` or
+    /// `fifo`.
+    #[serde(rename = "type")]
+    pub entry_type: String,
+
+    /// Size of a regular file.
+    pub size: u64,
+
+    /// Modification time in RFC 3339.
+    pub modtime: String,
+
+    /// Target of a symlink or hardlink.
+    pub link_name: String,
+
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub dev_major: u32,
+    pub dev_minor: u32,
+
+    /// Extended attributes, with base64 values.
+    pub xattrs: HashMap<String, String>,
+
+    /// Offset in the blob of the gzip member of the chunk.
+    pub offset: u64,
+
+    /// Offset of the chunk in the file.
+    pub chunk_offset: u64,
+
+    /// Size of the chunk, `0` meaning up to the end of the file.
+    pub chunk_size: u64,
+
+    /// Digest of the chunk content.
+    pub chunk_digest: String,
+}
+
+/// Get the offset of the TOC from the footer of an eStargz blob.
+///
+/// The footer is an empty gzip member whose extra field holds
+/// `%016xSTARGZ` with the TOC offset.
+pub fn parse_footer(footer: &[u8]) -> Result<u64> {
+    if footer.len() as u64 != FOOTER_SIZE {
+        bail!("invalid eStargz footer size {}", footer.len());
+    }
+
+    if footer[..4] != [0x1f, 0x8b, 0x08, 0x04] || &footer[12..14] != b"SG" {
+        bail!("invalid eStargz footer header");
+    }
+
+    let payload = &footer[16..38];
+    if &payload[16..] != b"STARGZ" {
+        bail!("invalid eStargz footer magic");
+    }
+
+    let offset = std::str::from_utf8(&payload[..16])
+        .ok()
+        .and_then(|offset| u64::from_str_radix(offset, 16).ok())
+        .ok_or_else(|| anyhow!("invalid eStargz TOC offset"))?;
+
+    Ok(offset)
+}
+
+/// Parse the TOC from its gzip member, and check it against `toc_digest`.
+pub fn parse_toc(compressed: &[u8], toc_digest: &str) -> Result<Toc> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(compressed));
+    let mut toc = Vec::new();
+    let mut found = false;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()?.as_os_str() == TOC_TAR_NAME {
+            entry.take(MAX_TOC_SIZE + 1).read_to_end(&mut toc)?;
+            found = true;
+            break;
+        }
+    }
+
+    if !found {
+        bail!("no {} in eStargz TOC", TOC_TAR_NAME);
+    }
+
+    if toc.len() as u64 > MAX_TOC_SIZE {
+        bail!("eStargz TOC exceeds {} bytes", MAX_TOC_SIZE);
+    }
+
+    let digest = format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(&toc));
+    if digest != toc_digest {
+        bail!(
+            "unequal eStargz TOC digest {:?} expected {:?}",
+            digest,
+            toc_digest
+        );
+    }
+
+    serde_json::from_slice(&toc).map_err(|e| anyhow!("failed to parse eStargz TOC {e}"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    /// Build the footer pointing at a TOC at `offset`.
+    pub(crate) fn footer(offset: u64) -> Vec<u8> {
+        let mut footer = vec![0x1f, 0x8b, 0x08, 0x04, 0, 0, 0, 0, 0, 0xff, 26, 0];
+        footer.extend(b"SG");
+        footer.extend(22u16.to_le_bytes());
+        footer.extend(format!("{offset:016x}STARGZ").as_bytes());
+        footer.extend([0x01, 0x00, 0x00, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0]);
+        footer
+    }
+
+    /// Build the gzip member of a TOC.
+    pub(crate) fn toc_member(toc: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_ustar();
+        header.set_size(toc.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, TOC_TAR_NAME, toc).unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_parse_footer() {
+        let footer = footer(0x1234);
+        assert_eq!(footer.len() as u64, FOOTER_SIZE);
+        assert_eq!(parse_footer(&footer).unwrap(), 0x1234);
+
+        let mut bad_magic = footer.clone();
+        bad_magic[33] = b'X';
+        assert!(parse_footer(&bad_magic).is_err());
+        assert!(parse_footer(&footer[1..]).is_err());
+    }
+
+    #[test]
+    fn test_parse_toc() {
+        let toc = br#"{
+            "version": 1,
+            "entries": [
+                {"name": "etc/", "type": "dir", "mode": 493},
+                {
+                    "name": "etc/hostname",
+                    "type": "reg",
+                    "size": 6,
+                    "modtime": "2023-01-01T00:00:00Z",
+                    "mode": 420,
+                    "offset": 100,
+                    "chunkDigest": "sha256:e7d2f1e4e6f5e1f8c1a4f8a3e3a6e4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1"
+                }
+            ]
+        }"#;
+        let digest = format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(toc));
+        let member = toc_member(toc);
+
+        let parsed = parse_toc(&member, &digest).unwrap();
+        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].entry_type, "reg");
+        assert_eq!(parsed.entries[1].offset, 100);
+
+        // A TOC not matching its digest is refused.
+        assert!(parse_toc(&member, "sha256:0000").is_err());
+    }
+}
@@ -6,20 +6,25 @@ use anyhow::{anyhow, bail, Result};
 use log::warn;
 use oci_distribution::manifest::{OciDescriptor, OciImageManifest};
 use oci_distribution::secrets::RegistryAuth;
-use oci_distribution::Reference;
+use oci_distribution::{Client, Reference};
 use oci_spec::image::{ImageConfiguration, Os};
 use serde::Deserialize;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::Path;
 use std::sync::Arc;
 
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Mutex;
 
+use crate::budget::PullBudget;
 use crate::bundle::{create_runtime_config, BUNDLE_ROOTFS};
 use crate::config::{ImageConfig, CONFIGURATION_FILE_PATH};
 use crate::decoder::Compression;
+use crate::layer_cache::LayerCache;
+use crate::local::{LocalImage, LocalSource};
 use crate::meta_store::{MetaStore, METAFILE};
+use crate::progress::{ProgressReporter, PullProgress};
 use crate::pull::PullClient;
 use crate::snapshots::{SnapshotType, Snapshotter};
 
@@ -27,6 +32,8 @@ use crate::snapshots::{SnapshotType, Snapshotter};
 use crate::snapshots::occlum::unionfs::Unionfs;
 #[cfg(feature = "snapshot-overlayfs")]
 use crate::snapshots::overlay::OverlayFs;
+#[cfg(feature = "snapshot-verity")]
+use crate::snapshots::verity::VerityFs;
 
 #[cfg(feature = "nydus")]
 use crate::nydus::{service, utils};
@@ -92,6 +99,9 @@ pub struct ImageClient {
 
     /// The supported snapshots for `image-rs` client.
     pub snapshots: HashMap<SnapshotType, Box<dyn Snapshotter>>,
+
+    /// The persistent layer cache for `image-rs` client, if configured.
+    pub layer_cache: Option<Arc<LayerCache>>,
 }
 
 impl Default for ImageClient {
@@ -113,6 +123,7 @@ impl Default for ImageClient {
             let overlayfs = OverlayFs::new(
                 data_dir,
                 std::sync::atomic::AtomicUsize::new(*overlay_index),
+                &config.overlay_snapshot,
             );
             snapshots.insert(
                 SnapshotType::Overlay,
@@ -138,10 +149,43 @@ impl Default for ImageClient {
             );
         }
 
+        #[cfg(feature = "snapshot-verity")]
+        {
+            let verity_index = meta_store
+                .snapshot_db
+                .get(&SnapshotType::Verity.to_string())
+                .unwrap_or(&0);
+            let verityfs = VerityFs::new(
+                config.work_dir.join(SnapshotType::Verity.to_string()),
+                std::sync::atomic::AtomicUsize::new(*verity_index),
+                &config.verity_snapshot,
+            );
+            snapshots.insert(
+                SnapshotType::Verity,
+                Box::new(verityfs) as Box<dyn Snapshotter>,
+            );
+        }
+
+        let layer_cache = config.layer_cache.as_ref().and_then(|cache_config| {
+            LayerCache::open(cache_config)
+                .map_err(|e| warn!("layer cache disabled: {:?}", e))
+                .ok()
+                .map(Arc::new)
+        });
+
+        #[cfg(feature = "dns-resolver")]
+        if let Some(dns) = config.dns.as_ref() {
+            match crate::resolver::Resolver::new(dns) {
+                Ok(resolver) => crate::resolver::set_resolver(Some(resolver)),
+                Err(e) => warn!("in-process DNS resolver disabled: {:?}", e),
+            }
+        }
+
         ImageClient {
             config,
             meta_store: Arc::new(Mutex::new(meta_store)),
             snapshots,
+            layer_cache,
         }
     }
 }
@@ -159,6 +203,10 @@ impl ImageClient {
     /// When `auth_info` parameter is given and `auth` in self.config is also enabled,
     /// this function will only try to get auth from `auth_info`, and if fails then
     /// then returns an error.
+    ///
+    /// `image_url` may also be an image of a local source, the OCI image
+    /// layout `oci:<dir>:<reference>` or the `docker save` tarball
+    /// `docker-archive:<path>:<reference>`, see [`crate::local`].
     pub async fn pull_image(
         &mut self,
         image_url: &str,
@@ -166,7 +214,57 @@ impl ImageClient {
         auth_info: &Option<&str>,
         decrypt_config: &Option<&str>,
     ) -> Result<String> {
-        let reference = Reference::try_from(image_url)?;
+        self.do_pull_image(
+            image_url,
+            bundle_dir,
+            auth_info,
+            decrypt_config,
+            ProgressReporter::default(),
+        )
+        .await
+    }
+
+    /// Same as [`ImageClient::pull_image`], but the progress of the pull is
+    /// sent to `progress` as it goes, per layer and per stage. The pull
+    /// goes on even if the receiver of `progress` is dropped.
+    pub async fn pull_image_with_progress(
+        &mut self,
+        image_url: &str,
+        bundle_dir: &Path,
+        auth_info: &Option<&str>,
+        decrypt_config: &Option<&str>,
+        progress: UnboundedSender<PullProgress>,
+    ) -> Result<String> {
+        let reporter = ProgressReporter::new(progress);
+        let image_id = self
+            .do_pull_image(
+                image_url,
+                bundle_dir,
+                auth_info,
+                decrypt_config,
+                reporter.clone(),
+            )
+            .await?;
+
+        reporter.report(PullProgress::Completed {
+            image: image_url.to_string(),
+        });
+        Ok(image_id)
+    }
+
+    async fn do_pull_image(
+        &mut self,
+        image_url: &str,
+        bundle_dir: &Path,
+        auth_info: &Option<&str>,
+        decrypt_config: &Option<&str>,
+        progress: ProgressReporter,
+    ) -> Result<String> {
+        // The images of the local sources are verified by the policy as the
+        // images of their references.
+        let local = LocalSource::parse(image_url)?;
+        let image_reference = local.as_ref().map_or(image_url, LocalSource::reference);
+        let reference = crate::registry::parse_reference(image_reference)?;
 
         // Try to get auth using input param.
         let auth = if let Some(auth_info) = auth_info {
@@ -211,35 +309,67 @@ impl ImageClient {
         // auth from `auth.json` of given place.
         // If a proper auth is given, use this auth.
         // If no valid auth is given and config.auth is disabled, use Anonymous auth.
-        let auth = match (self.config.auth, auth.is_none()) {
-            (true, true) => {
-                match crate::auth::credential_for_reference(
-                    &reference,
-                    &self.config.file_paths.auth_file,
-                )
-                .await
-                {
-                    Ok(cred) => cred,
-                    Err(e) => {
-                        warn!(
-                            "get credential failed, use Anonymous auth instead: {}",
-                            e.to_string()
-                        );
-                        RegistryAuth::Anonymous
-                    }
+        let auth = match auth {
+            Some(auth) => auth,
+            None => self.credential(&reference).await,
+        };
+
+        // The mirrors of the registry are tried in order before the
+        // registry itself, each with its own auth, unless the image is of a
+        // local source.
+        let endpoints = match &local {
+            Some(_) => Vec::new(),
+            None => crate::registry::endpoints(&self.config.registries, &reference)?,
+        };
+        let mut endpoint_auths = Vec::new();
+        for endpoint in &endpoints {
+            let endpoint_auth = match (&endpoint.auth, endpoint.mirror) {
+                (Some(endpoint_auth), _) => endpoint_auth.clone(),
+                (None, true) => self.credential(&endpoint.reference).await,
+                (None, false) => auth.clone(),
+            };
+            endpoint_auths.push(endpoint_auth);
+        }
+
+        let mut pulled = None;
+        if let Some(source) = &local {
+            let mut client = self.pull_client(reference.clone(), &auth, &progress)?;
+            client.local = Some(Arc::new(LocalImage::open(source).await?));
+            let manifest = client.pull_manifest().await?;
+            pulled = Some((client, manifest));
+        }
+
+        for (endpoint, endpoint_auth) in endpoints.iter().zip(&endpoint_auths) {
+            let mut client =
+                self.pull_client(endpoint.reference.clone(), endpoint_auth, &progress)?;
+            client.client = Client::new(endpoint.client_config());
+
+            match client.pull_manifest().await {
+                Ok(manifest) => {
+                    pulled = Some((client, manifest));
+                    break;
                 }
+                Err(e) => warn!(
+                    "failed to pull manifest from {}: {:?}",
+                    endpoint.reference.registry(),
+                    e
+                ),
             }
-            (false, true) => RegistryAuth::Anonymous,
-            _ => auth.expect("unexpected uninitialized auth"),
-        };
+        }
 
-        let mut client = PullClient::new(
-            reference,
-            &self.config.work_dir.join("layers"),
-            &auth,
-            self.config.max_concurrent_download,
-        )?;
-        let (image_manifest, image_digest, image_config) = client.pull_manifest().await?;
+        #[allow(unused_mut)]
+        let (mut client, (image_manifest, image_digest, image_config)) = pulled
+            .ok_or_else(|| anyhow!("failed to pull manifest of {} from any registry", image_url))?;
+
+        progress.report(PullProgress::Manifest {
+            image: image_url.to_string(),
+            layers: image_manifest.layers.len(),
+            size: image_manifest
+                .layers
+                .iter()
+                .map(|layer| layer.size.max(0) as u64)
+                .sum(),
+        });
 
         let id = image_manifest.config.digest.clone();
 
@@ -265,10 +395,11 @@ impl ImageClient {
             #[cfg(feature = "signature")]
             if self.config.security_validate {
                 crate::signature::allows_image(
-                    image_url,
+                    image_reference,
                     &image_digest,
                     &auth,
                     &self.config.file_paths,
+                    &self.config.policy,
                 )
                 .await
                 .map_err(|e| anyhow!("Security validate failed: {:?}", e))?;
@@ -297,17 +428,18 @@ impl ImageClient {
         {
             let m = self.meta_store.lock().await;
             if let Some(image_data) = &m.image_db.get(&id) {
-                return create_bundle(image_data, bundle_dir, snapshot);
+                return create_bundle(image_data, bundle_dir, snapshot).await;
             }
         }
 
         #[cfg(feature = "signature")]
         if self.config.security_validate {
             crate::signature::allows_image(
-                image_url,
+                image_reference,
                 &image_digest,
                 &auth,
                 &self.config.file_paths,
+                &self.config.policy,
             )
             .await
             .map_err(|e| anyhow!("Security validate failed: {:?}", e))?;
@@ -346,7 +478,7 @@ impl ImageClient {
             );
         }
 
-        let image_id = create_bundle(&image_data, bundle_dir, snapshot)?;
+        let image_id = create_bundle(&image_data, bundle_dir, snapshot).await?;
 
         self.meta_store
             .lock()
@@ -357,6 +489,85 @@ impl ImageClient {
         Ok(image_id)
     }
 
+    /// Push the image of `manifest` to `image_url` from inside the guest,
+    /// with the blob of the config and the layers read from `layers`, in
+    /// the order of the layers of the manifest. Return the digest of the
+    /// manifest.
+    ///
+    /// The credential is `auth_info` in `<username>:<password>` if given,
+    /// or that of the registry in `auth.json` if `auth` in self.config is
+    /// enabled.
+    #[cfg(feature = "push")]
+    pub async fn push_image<R: tokio::io::AsyncRead + Unpin>(
+        &self,
+        image_url: &str,
+        manifest: &OciImageManifest,
+        config: &[u8],
+        layers: Vec<R>,
+        auth_info: &Option<&str>,
+    ) -> Result<String> {
+        let reference = crate::registry::parse_reference(image_url)?;
+        let auth = match auth_info {
+            Some(auth_info) => {
+                let (username, password) = auth_info
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid authentication info ({:?})", auth_info))?;
+                RegistryAuth::Basic(username.to_string(), password.to_string())
+            }
+            None => self.credential(&reference).await,
+        };
+
+        crate::push::Pusher::new(&reference, auth)
+            .push_image(manifest, config, layers)
+            .await
+    }
+
+    /// Create the client pulling the image of `reference`, with the config of
+    /// the pulls.
+    fn pull_client<'a>(
+        &self,
+        reference: Reference,
+        auth: &'a RegistryAuth,
+        progress: &ProgressReporter,
+    ) -> Result<PullClient<'a>> {
+        let mut client = PullClient::new(
+            reference,
+            &self.config.work_dir.join("layers"),
+            auth,
+            self.config.max_concurrent_download,
+            PullBudget::new(
+                self.config.max_pull_buffer_size,
+                self.config.max_pull_bandwidth,
+            ),
+        )?;
+        client.lazy_pull = self.config.lazy_pull;
+        client.layer_cache = self.layer_cache.clone();
+        client.resumable_download = self.config.resumable_download.clone();
+        client.progress = progress.clone();
+        Ok(client)
+    }
+
+    /// Get the credential of the registry of `reference` from `auth.json`
+    /// if `auth` is enabled, or anonymous.
+    async fn credential(&self, reference: &Reference) -> RegistryAuth {
+        if !self.config.auth {
+            return RegistryAuth::Anonymous;
+        }
+
+        match crate::auth::credential_for_reference(reference, &self.config.file_paths.auth_file)
+            .await
+        {
+            Ok(cred) => cred,
+            Err(e) => {
+                warn!(
+                    "get credential failed, use Anonymous auth instead: {}",
+                    e.to_string()
+                );
+                RegistryAuth::Anonymous
+            }
+        }
+    }
+
     #[cfg(feature = "nydus")]
     async fn do_pull_image_with_nydus<'a>(
         &mut self,
@@ -396,7 +607,7 @@ impl ImageClient {
             bail!("Failed to pull the bootstrap");
         }
 
-        let reference = Reference::try_from(image_data.reference.clone())?;
+        let reference = client.reference.clone();
         let nydus_config = self
             .config
             .get_nydus_config()
@@ -414,6 +625,7 @@ impl ImageClient {
         let image_id = service::start_nydus_service(
             image_data,
             reference,
+            client.auth,
             nydus_config,
             &work_dir,
             bundle_dir,
@@ -453,32 +665,32 @@ fn create_image_meta(
         bail!("Pulled number of layers mismatch with image config diff_ids");
     }
 
+    // The layers are deduplicated along with their diff ids, so that each
+    // layer is verified against its own diff id.
     let mut unique_layers = Vec::new();
-    let mut digests = BTreeSet::new();
-    for l in &image_manifest.layers {
-        if digests.contains(&l.digest) {
-            continue;
-        }
-
-        digests.insert(&l.digest);
-        unique_layers.push(l.clone());
-    }
-
     let mut unique_diff_ids = Vec::new();
-    let mut id_tree = BTreeSet::new();
-    for id in diff_ids {
-        if id_tree.contains(id.as_str()) {
-            continue;
+    let mut layer_diff_ids = HashMap::new();
+    for (layer, diff_id) in image_manifest.layers.iter().zip(diff_ids) {
+        match layer_diff_ids.get(&layer.digest) {
+            Some(&id) if id == diff_id => continue,
+            Some(id) => bail!(
+                "layer {} has diff_ids {:?} and {:?} in image config",
+                layer.digest,
+                id,
+                diff_id
+            ),
+            None => {}
         }
 
-        id_tree.insert(id.as_str());
-        unique_diff_ids.push(id.clone());
+        layer_diff_ids.insert(&layer.digest, diff_id);
+        unique_layers.push(layer.clone());
+        unique_diff_ids.push(diff_id.clone());
     }
 
     Ok((image_data, unique_layers, unique_diff_ids))
 }
 
-fn create_bundle(
+async fn create_bundle(
     image_data: &ImageMeta,
     bundle_dir: &Path,
     snapshot: &mut Box<dyn Snapshotter>,
@@ -490,7 +702,25 @@ fn create_bundle(
         .map(|l| l.store_path.as_str())
         .collect::<Vec<&str>>();
 
-    snapshot.mount(&layer_path, &bundle_dir.join(BUNDLE_ROOTFS))?;
+    #[allow(unused_variables)]
+    let mount_point = snapshot.mount(&layer_path, &bundle_dir.join(BUNDLE_ROOTFS))?;
+
+    // The rootfs is not used unless it is measured, so that it is always
+    // attested.
+    #[cfg(feature = "snapshot-verity")]
+    if let Some(root_hash) = &mount_point.root_hash {
+        if let Err(e) = crate::measurement::extend_measurement(
+            crate::measurement::VERITY_ROOTFS_OPERATION,
+            root_hash,
+        )
+        .await
+        {
+            if let Err(e) = snapshot.unmount(&mount_point) {
+                warn!("failed to unmount unmeasured rootfs: {:?}", e);
+            }
+            bail!("failed to measure rootfs {}: {:?}", root_hash, e);
+        }
+    }
 
     let image_config = image_data.image_config.clone();
     if image_config.os() != &Os::Linux {
@@ -509,6 +739,92 @@ mod tests {
 
     use test_utils::assert_retry;
 
+    #[test]
+    fn test_create_image_meta() {
+        let layer = |digest: &str| {
+            serde_json::json!({
+                "mediaType": "application/vnd.oci.image.layer.v1.tar+gzip",
+                "digest": format!("sha256:{}", digest.repeat(64)),
+                "size": 1,
+            })
+        };
+        let manifest = |layers: &[&str]| -> OciImageManifest {
+            serde_json::from_value(serde_json::json!({
+                "schemaVersion": 2,
+                "config": {
+                    "mediaType": "application/vnd.oci.image.config.v1+json",
+                    "digest": format!("sha256:{}", "c".repeat(64)),
+                    "size": 1,
+                },
+                "layers": layers.iter().map(|digest| layer(digest)).collect::<Vec<_>>(),
+            }))
+            .unwrap()
+        };
+        let config = |diff_ids: &[&str]| {
+            serde_json::json!({
+                "architecture": "amd64",
+                "os": "linux",
+                "rootfs": {
+                    "type": "layers",
+                    "diff_ids": diff_ids
+                        .iter()
+                        .map(|id| format!("sha256:{}", id.repeat(64)))
+                        .collect::<Vec<_>>(),
+                },
+            })
+            .to_string()
+        };
+
+        // A layer repeated with the same diff id is pulled once.
+        let (_, layers, diff_ids) = create_image_meta(
+            "id",
+            "busybox",
+            &manifest(&["a", "b", "a"]),
+            "digest",
+            &config(&["1", "2", "1"]),
+        )
+        .unwrap();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(
+            diff_ids,
+            vec![
+                format!("sha256:{}", "1".repeat(64)),
+                format!("sha256:{}", "2".repeat(64))
+            ]
+        );
+
+        // Different layers of the same diff id are each verified.
+        let (_, layers, diff_ids) = create_image_meta(
+            "id",
+            "busybox",
+            &manifest(&["a", "b"]),
+            "digest",
+            &config(&["1", "1"]),
+        )
+        .unwrap();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(diff_ids.len(), 2);
+
+        // A layer cannot have different diff ids.
+        assert!(create_image_meta(
+            "id",
+            "busybox",
+            &manifest(&["a", "a"]),
+            "digest",
+            &config(&["1", "2"]),
+        )
+        .is_err());
+
+        assert!(create_image_meta(
+            "id",
+            "busybox",
+            &manifest(&["a", "b"]),
+            "digest",
+            &config(&["1"]),
+        )
+        .is_err());
+    }
+
     #[tokio::test]
     async fn test_pull_image() {
         let work_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,344 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Persistent cache of the unpacked layers.
+//!
+//! The layers are stored by a key under the cache dir, which is expected on
+//! a local disk encrypted with dm-crypt, so that the layers shared by the
+//! pods of the CVM, or pulled before a restart, are neither downloaded nor
+//! decrypted again. A digest of the tree of each layer is recorded when it
+//! is cached, and checked again when it is reused. The least recently used
+//! layers are evicted beyond the max size of the cache, unless they are
+//! used since the cache was opened.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use walkdir::WalkDir;
+
+use crate::config::LayerCacheConfig;
+use crate::digest::DIGEST_SHA256_PREFIX;
+
+/// Index of the cached layers, in the cache dir.
+pub const INDEX_FILE: &str = "index.json";
+
+/// A cached layer.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    /// Digest of the tree of the unpacked layer.
+    tree_digest: String,
+
+    /// Size in bytes of the files of the layer.
+    size: u64,
+
+    /// Last time the layer was used, in seconds since the Unix epoch.
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+
+    /// Layers used since the cache was opened, which are never evicted as
+    /// they may be mounted.
+    in_use: HashSet<String>,
+}
+
+/// Content-addressed store of the unpacked layers.
+pub struct LayerCache {
+    dir: PathBuf,
+    max_size: u64,
+    index: Mutex<Index>,
+}
+
+impl LayerCache {
+    /// Open the cache, dropping the layers interrupted while being cached
+    /// and those missing from the index.
+    pub fn open(config: &LayerCacheConfig) -> Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        if config.require_encrypted && !is_dm_crypt(&config.dir)? {
+            bail!(
+                "layer cache dir {:?} is not on a dm-crypt device",
+                config.dir
+            );
+        }
+
+        let entries: HashMap<String, Entry> = match fs::read(config.dir.join(INDEX_FILE)) {
+            Ok(index) => serde_json::from_slice(&index)
+                .map_err(|e| anyhow!("failed to parse layer cache index {e}"))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let cache = LayerCache {
+            dir: config.dir.clone(),
+            max_size: config.max_size,
+            index: Mutex::new(Index::default()),
+        };
+
+        let mut index = cache.index.lock().expect("layer cache lock poisoned");
+        index.entries = entries
+            .into_iter()
+            .filter(|(key, _)| cache.path(key).is_dir())
+            .collect();
+
+        let known: HashSet<PathBuf> = index.entries.keys().map(|key| cache.path(key)).collect();
+        for dir_entry in fs::read_dir(&cache.dir)? {
+            let path = dir_entry?.path();
+            if path.is_dir() && !known.contains(&path) {
+                info!("remove stale cached layer {:?}", path);
+                fs::remove_dir_all(&path)?;
+            }
+        }
+
+        cache.save(&index)?;
+        drop(index);
+
+        Ok(cache)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key.replace(':', "_"))
+    }
+
+    /// Get the dir to unpack the layer to, before it is inserted.
+    pub fn staging_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!(".{}.tmp", key.replace(':', "_")))
+    }
+
+    /// Get the path of the cached layer, if its tree is unchanged since it
+    /// was cached. A changed layer is dropped from the cache.
+    pub fn get(&self, key: &str) -> Result<Option<PathBuf>> {
+        let Some(entry) = self.entry(key) else {
+            return Ok(None);
+        };
+
+        let path = self.path(key);
+        let (tree_digest, _) = tree_digest(&path)?;
+        let mut index = self.index.lock().expect("layer cache lock poisoned");
+        if tree_digest != entry.tree_digest {
+            warn!(
+                "cached layer {} is corrupted, got tree digest {:?} expected {:?}",
+                key, tree_digest, entry.tree_digest
+            );
+            index.entries.remove(key);
+            self.save(&index)?;
+            fs::remove_dir_all(&path)?;
+            return Ok(None);
+        }
+
+        if let Some(entry) = index.entries.get_mut(key) {
+            entry.last_used = now();
+        }
+        index.in_use.insert(key.to_string());
+        self.save(&index)?;
+
+        Ok(Some(path))
+    }
+
+    /// Move the layer unpacked at `staging` into the cache, evict the
+    /// least recently used layers beyond the max size, and return the path
+    /// of the cached layer.
+    pub fn insert(&self, key: &str, staging: &Path) -> Result<PathBuf> {
+        let (tree_digest, size) = tree_digest(staging)?;
+        let path = self.path(key);
+        if path.exists() {
+            fs::remove_dir_all(&path)?;
+        }
+        fs::rename(staging, &path)?;
+
+        let mut index = self.index.lock().expect("layer cache lock poisoned");
+        index.entries.insert(
+            key.to_string(),
+            Entry {
+                tree_digest,
+                size,
+                last_used: now(),
+            },
+        );
+        index.in_use.insert(key.to_string());
+        self.evict(&mut index)?;
+        self.save(&index)?;
+
+        Ok(path)
+    }
+
+    fn entry(&self, key: &str) -> Option<Entry> {
+        let index = self.index.lock().expect("layer cache lock poisoned");
+        index.entries.get(key).cloned()
+    }
+
+    fn evict(&self, index: &mut Index) -> Result<()> {
+        let mut total: u64 = index.entries.values().map(|entry| entry.size).sum();
+        let mut candidates: Vec<(String, Entry)> = index
+            .entries
+            .iter()
+            .filter(|(key, _)| !index.in_use.contains(*key))
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect();
+        candidates.sort_by_key(|(_, entry)| entry.last_used);
+
+        for (key, entry) in candidates {
+            if total <= self.max_size {
+                break;
+            }
+
+            info!("evict cached layer {}", key);
+            fs::remove_dir_all(self.path(&key))?;
+            index.entries.remove(&key);
+            total -= entry.size;
+        }
+
+        if total > self.max_size {
+            warn!(
+                "layer cache size {} exceeds {} with the layers in use",
+                total, self.max_size
+            );
+        }
+
+        Ok(())
+    }
+
+    fn save(&self, index: &Index) -> Result<()> {
+        let tmp = self.dir.join(format!(".{INDEX_FILE}"));
+        fs::write(&tmp, serde_json::to_vec(&index.entries)?)?;
+        fs::rename(&tmp, self.dir.join(INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Get the digest of the tree at `root`, over the path, type, mode,
+/// ownership and content of each file, and the total size of its files.
+fn tree_digest(root: &Path) -> Result<(String, u64)> {
+    let mut hasher = sha2::Sha256::new();
+    let mut size = 0;
+    let mut buf = vec![0; 64 * 1024];
+    for entry in WalkDir::new(root).follow_links(false).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        let meta = entry.metadata()?;
+
+        hasher.update(path.strip_prefix(root)?.as_os_str().as_bytes());
+        hasher.update([0]);
+        hasher.update(meta.mode().to_le_bytes());
+        hasher.update(meta.uid().to_le_bytes());
+        hasher.update(meta.gid().to_le_bytes());
+        hasher.update(meta.rdev().to_le_bytes());
+
+        if meta.file_type().is_symlink() {
+            hasher.update(fs::read_link(path)?.as_os_str().as_bytes());
+        } else if meta.file_type().is_file() {
+            hasher.update(meta.len().to_le_bytes());
+            let mut file = fs::File::open(path)?;
+            loop {
+                let n = file.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+            size += meta.len();
+        }
+    }
+
+    Ok((
+        format!("{}{:x}", DIGEST_SHA256_PREFIX, hasher.finalize()),
+        size,
+    ))
+}
+
+/// Check whether `path` is on a dm-crypt device.
+fn is_dm_crypt(path: &Path) -> Result<bool> {
+    let dev = fs::metadata(path)?.dev();
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0xff);
+
+    match fs::read_to_string(format!("/sys/dev/block/{major}:{minor}/dm/uuid")) {
+        Ok(uuid) => Ok(uuid.starts_with("CRYPT-")),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dir: &Path, max_size: u64) -> LayerCacheConfig {
+        LayerCacheConfig {
+            dir: dir.to_path_buf(),
+            max_size,
+            require_encrypted: false,
+        }
+    }
+
+    fn stage(cache: &LayerCache, key: &str, content: &[u8]) -> PathBuf {
+        let staging = cache.staging_path(key);
+        fs::create_dir_all(staging.join("etc")).unwrap();
+        fs::write(staging.join("etc/file"), content).unwrap();
+        staging
+    }
+
+    #[test]
+    fn test_layer_cache() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache = LayerCache::open(&config(tempdir.path(), 1024)).unwrap();
+        assert!(cache.get("sha256:a").unwrap().is_none());
+
+        let staging = stage(&cache, "sha256:a", b"a");
+        let path = cache.insert("sha256:a", &staging).unwrap();
+        assert!(!staging.exists());
+        assert_eq!(fs::read(path.join("etc/file")).unwrap(), b"a");
+        assert_eq!(cache.get("sha256:a").unwrap(), Some(path.clone()));
+
+        // The cache is reused after a restart, without the interrupted
+        // layers.
+        stage(&cache, "sha256:b", b"b");
+        drop(cache);
+        let cache = LayerCache::open(&config(tempdir.path(), 1024)).unwrap();
+        assert!(!cache.staging_path("sha256:b").exists());
+        assert_eq!(cache.get("sha256:a").unwrap(), Some(path.clone()));
+
+        // A corrupted layer is dropped.
+        fs::write(path.join("etc/file"), b"x").unwrap();
+        assert!(cache.get("sha256:a").unwrap().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_layer_cache_eviction() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache = LayerCache::open(&config(tempdir.path(), 1024)).unwrap();
+        let staging = stage(&cache, "sha256:a", &[0; 1000]);
+        let old = cache.insert("sha256:a", &staging).unwrap();
+        drop(cache);
+
+        // Only the layers not used since the cache was opened are evicted.
+        let cache = LayerCache::open(&config(tempdir.path(), 1024)).unwrap();
+        let staging = stage(&cache, "sha256:b", &[0; 1000]);
+        let new = cache.insert("sha256:b", &staging).unwrap();
+        assert!(!old.exists());
+        assert!(cache.get("sha256:a").unwrap().is_none());
+
+        let staging = stage(&cache, "sha256:c", &[0; 1000]);
+        cache.insert("sha256:c", &staging).unwrap();
+        assert_eq!(cache.get("sha256:b").unwrap(), Some(new));
+    }
+}
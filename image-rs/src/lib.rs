@@ -8,17 +8,32 @@ pub const CC_IMAGE_WORK_DIR: &str = "CC_IMAGE_WORK_DIR";
 pub const ERR_BAD_UNCOMPRESSED_DIGEST: &str = "unsupported uncompressed digest format";
 
 pub mod auth;
+pub mod budget;
 pub mod bundle;
 pub mod config;
 pub mod decoder;
 pub mod decrypt;
 pub mod digest;
+#[cfg(feature = "estargz")]
+pub mod estargz;
 pub mod image;
+pub mod layer_cache;
+pub mod local;
+#[cfg(feature = "measurement")]
+pub mod measurement;
 pub mod meta_store;
 #[cfg(feature = "nydus")]
 pub mod nydus;
+pub mod progress;
 pub mod pull;
+#[cfg(feature = "push")]
+pub mod push;
+pub mod registry;
+#[cfg(feature = "dns-resolver")]
+pub mod resolver;
 pub mod resource;
+#[cfg(feature = "resumable-download")]
+pub mod resume;
 #[cfg(feature = "signature")]
 pub mod signature;
 pub mod snapshots;
@@ -0,0 +1,749 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Images pulled from local sources instead of registries, for the
+//! air-gapped clusters staging the images on volumes attached to the guest:
+//!
+//! - `oci:<dir>:<reference>`, the image of `<reference>` in the OCI image
+//!   layout `<dir>`.
+//! - `docker-archive:<path>:<reference>`, the image of `<reference>` in
+//!   the tarball `<path>` of `docker save`.
+//!
+//! The blobs are verified as those pulled from the registries: the manifest
+//! and the config against their digests, the layers against their diff ids
+//! once unpacked.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use oci_distribution::manifest::{OciImageManifest, IMAGE_CONFIG_MEDIA_TYPE};
+use oci_distribution::Reference;
+use oci_spec::image::ImageConfiguration;
+use serde::Deserialize;
+use sha2::Digest;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::digest::{DigestHasher, LayerDigestHasher, DIGEST_SHA256_PREFIX, DIGEST_SHA512_PREFIX};
+
+/// Prefix of the images in OCI image layouts.
+pub const OCI_LAYOUT_PREFIX: &str = "oci:";
+
+/// Prefix of the images in tarballs of `docker save`.
+pub const DOCKER_ARCHIVE_PREFIX: &str = "docker-archive:";
+
+const OCI_IMAGE_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const OCI_IMAGE_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const DOCKER_MANIFEST_LIST_MEDIA_TYPE: &str =
+    "application/vnd.docker.distribution.manifest.list.v2+json";
+
+const OCI_LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
+const OCI_LAYER_GZIP_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+const OCI_LAYER_ZSTD_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+zstd";
+
+/// Annotation of the name of the images in the index of an OCI layout.
+const ANNOTATION_REF_NAME: &str = "org.opencontainers.image.ref.name";
+
+/// The local source of an image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LocalSource {
+    /// The image of `reference` in the OCI image layout `dir`.
+    OciLayout { dir: PathBuf, reference: String },
+
+    /// The image of `reference` in the `docker save` tarball `path`.
+    DockerArchive { path: PathBuf, reference: String },
+}
+
+impl LocalSource {
+    /// Parse the local source of `image_url`, or `None` if `image_url` is
+    /// an image of a registry.
+    ///
+    /// The path of a local source is absolute, so that e.g. `oci:5000/app`
+    /// is still the image `app` of the registry `oci:5000`.
+    pub fn parse(image_url: &str) -> Result<Option<Self>> {
+        let (oci_layout, source) = if let Some(source) = image_url.strip_prefix(OCI_LAYOUT_PREFIX) {
+            (true, source)
+        } else if let Some(source) = image_url.strip_prefix(DOCKER_ARCHIVE_PREFIX) {
+            (false, source)
+        } else {
+            return Ok(None);
+        };
+
+        if !source.starts_with('/') {
+            return Ok(None);
+        }
+
+        let (path, reference) = source
+            .split_once(':')
+            .ok_or_else(|| anyhow!("no reference of the image in {:?}", image_url))?;
+        crate::registry::parse_reference(reference)
+            .map_err(|e| anyhow!("invalid reference of the image in {:?}: {e}", image_url))?;
+
+        let path = PathBuf::from(path);
+        let reference = reference.to_string();
+        Ok(Some(if oci_layout {
+            LocalSource::OciLayout {
+                dir: path,
+                reference,
+            }
+        } else {
+            LocalSource::DockerArchive { path, reference }
+        }))
+    }
+
+    /// The reference of the image, by which the image is verified by the
+    /// policy.
+    pub fn reference(&self) -> &str {
+        match self {
+            LocalSource::OciLayout { reference, .. } => reference,
+            LocalSource::DockerArchive { reference, .. } => reference,
+        }
+    }
+}
+
+/// A blob of a local image, of `size` bytes at `offset` of the file `path`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Blob {
+    path: PathBuf,
+    offset: u64,
+    size: u64,
+}
+
+/// An image opened from its local source.
+#[derive(Clone, Debug)]
+pub struct LocalImage {
+    manifest: OciImageManifest,
+    digest: String,
+    config: String,
+    layers: HashMap<String, Blob>,
+}
+
+impl LocalImage {
+    /// Open the image of `source`, with its manifest and config verified.
+    pub async fn open(source: &LocalSource) -> Result<Self> {
+        let source = source.clone();
+        tokio::task::spawn_blocking(move || match &source {
+            LocalSource::OciLayout { dir, reference } => open_oci_layout(dir, reference),
+            LocalSource::DockerArchive { path, reference } => open_docker_archive(path, reference),
+        })
+        .await
+        .map_err(|e| anyhow!("local image task failed {e}"))?
+    }
+
+    /// Get the manifest, the digest of the manifest and the config of the
+    /// image, like [`crate::pull::PullClient::pull_manifest`].
+    pub fn manifest(&self) -> (OciImageManifest, String, String) {
+        (
+            self.manifest.clone(),
+            self.digest.clone(),
+            self.config.clone(),
+        )
+    }
+
+    /// Open the blob of the layer `digest`.
+    pub async fn open_layer(&self, digest: &str) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+        let blob = self
+            .layers
+            .get(digest)
+            .ok_or_else(|| anyhow!("no layer {digest} in local image"))?;
+        let mut file = tokio::fs::File::open(&blob.path)
+            .await
+            .map_err(|e| anyhow!("failed to open layer {digest}: {e}"))?;
+        file.seek(SeekFrom::Start(blob.offset)).await?;
+        Ok(Box::new(file.take(blob.size)))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciLayout {
+    image_layout_version: String,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    manifests: Vec<Descriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    #[serde(default)]
+    media_type: String,
+    digest: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+    platform: Option<Platform>,
+}
+
+#[derive(Deserialize)]
+struct Platform {
+    architecture: String,
+    os: String,
+}
+
+fn open_oci_layout(dir: &Path, reference: &str) -> Result<LocalImage> {
+    let layout: OciLayout = serde_json::from_slice(&std::fs::read(dir.join("oci-layout"))?)
+        .map_err(|e| anyhow!("invalid OCI layout {:?}: {e}", dir))?;
+    if !layout.image_layout_version.starts_with("1.") {
+        bail!(
+            "unsupported version {} of OCI layout {:?}",
+            layout.image_layout_version,
+            dir
+        );
+    }
+
+    let index: Index = serde_json::from_slice(&std::fs::read(dir.join("index.json"))?)
+        .map_err(|e| anyhow!("invalid index of OCI layout {:?}: {e}", dir))?;
+    let parsed = crate::registry::parse_reference(reference)?;
+    let mut descriptors = index
+        .manifests
+        .into_iter()
+        .filter(|descriptor| ref_name_matches(descriptor, reference, &parsed));
+    let mut descriptor = match (descriptors.next(), descriptors.next()) {
+        (Some(descriptor), None) => descriptor,
+        (None, _) => bail!("no image {reference} in OCI layout {:?}", dir),
+        (Some(_), Some(_)) => bail!("several images {reference} in OCI layout {:?}", dir),
+    };
+
+    let mut manifest = read_blob(dir, &descriptor.digest)?;
+    if [OCI_IMAGE_INDEX_MEDIA_TYPE, DOCKER_MANIFEST_LIST_MEDIA_TYPE]
+        .contains(&descriptor.media_type.as_str())
+    {
+        let index: Index = serde_json::from_slice(&manifest)
+            .map_err(|e| anyhow!("invalid index {}: {e}", descriptor.digest))?;
+        descriptor = index
+            .manifests
+            .into_iter()
+            .find(|descriptor| {
+                descriptor.platform.as_ref().is_some_and(|platform| {
+                    platform.os == "linux" && platform.architecture == goarch()
+                })
+            })
+            .ok_or_else(|| anyhow!("no image {reference} of linux/{}", goarch()))?;
+        manifest = read_blob(dir, &descriptor.digest)?;
+    }
+
+    let image_manifest: OciImageManifest = serde_json::from_slice(&manifest)
+        .map_err(|e| anyhow!("invalid manifest {}: {e}", descriptor.digest))?;
+    let config = String::from_utf8(read_blob(dir, &image_manifest.config.digest)?)
+        .map_err(|e| anyhow!("invalid config {}: {e}", image_manifest.config.digest))?;
+
+    let mut layers = HashMap::new();
+    for layer in &image_manifest.layers {
+        let path = blob_path(dir, &layer.digest)?;
+        let size = std::fs::metadata(&path)
+            .map_err(|e| anyhow!("failed to get layer {}: {e}", layer.digest))?
+            .len();
+        if layer.size >= 0 && layer.size as u64 != size {
+            bail!(
+                "size {size} of layer {} is not {} of manifest",
+                layer.digest,
+                layer.size
+            );
+        }
+
+        layers.insert(
+            layer.digest.clone(),
+            Blob {
+                path,
+                offset: 0,
+                size,
+            },
+        );
+    }
+
+    Ok(LocalImage {
+        manifest: image_manifest,
+        digest: descriptor.digest,
+        config,
+        layers,
+    })
+}
+
+/// Check whether the image of `descriptor` in an index is named
+/// `reference`, in whole or by its tag, as named by e.g. `skopeo copy`.
+fn ref_name_matches(descriptor: &Descriptor, reference: &str, parsed: &Reference) -> bool {
+    if parsed.digest() == Some(descriptor.digest.as_str()) {
+        return true;
+    }
+
+    let Some(name) = descriptor.annotations.get(ANNOTATION_REF_NAME) else {
+        return false;
+    };
+    name == reference || parsed.tag() == Some(name.as_str())
+}
+
+/// Get the `GOARCH` of the platform of the guest.
+fn goarch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "powerpc64" => "ppc64le",
+        arch => arch,
+    }
+}
+
+/// Get the path of the blob `digest` in the OCI layout `dir`.
+fn blob_path(dir: &Path, digest: &str) -> Result<PathBuf> {
+    match digest.split_once(':') {
+        Some((algorithm, encoded))
+            if !algorithm.is_empty()
+                && !encoded.is_empty()
+                && algorithm.bytes().all(|b| b.is_ascii_alphanumeric())
+                && encoded.bytes().all(|b| b.is_ascii_alphanumeric()) =>
+        {
+            Ok(dir.join("blobs").join(algorithm).join(encoded))
+        }
+        _ => bail!("invalid digest {:?}", digest),
+    }
+}
+
+/// Read the blob `digest` of the OCI layout `dir`, verified against its
+/// digest.
+fn read_blob(dir: &Path, digest: &str) -> Result<Vec<u8>> {
+    let data = std::fs::read(blob_path(dir, digest)?)
+        .map_err(|e| anyhow!("failed to read blob {digest}: {e}"))?;
+    verify(&data, digest)?;
+    Ok(data)
+}
+
+fn verify(data: &[u8], digest: &str) -> Result<()> {
+    let mut hasher = if digest.starts_with(DIGEST_SHA256_PREFIX) {
+        LayerDigestHasher::Sha256(sha2::Sha256::new())
+    } else if digest.starts_with(DIGEST_SHA512_PREFIX) {
+        LayerDigestHasher::Sha512(sha2::Sha512::new())
+    } else {
+        bail!("unsupported digest {:?}", digest);
+    };
+
+    hasher.digest_update(data);
+    let actual = hasher.digest_finalize();
+    if actual != digest {
+        bail!("digest {actual} of blob is not {digest}");
+    }
+
+    Ok(())
+}
+
+/// An image in the `manifest.json` of a `docker save` tarball.
+#[derive(Deserialize)]
+struct ArchiveManifest {
+    #[serde(rename = "Config")]
+    config: String,
+    #[serde(rename = "RepoTags", default)]
+    repo_tags: Option<Vec<String>>,
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+/// Open the image of `reference` in the `docker save` tarball `path`.
+///
+/// The tarball has no manifest of the image, whose manifest is derived
+/// from its `manifest.json`, with the uncompressed layers as written by
+/// `docker save`, whose digests are their diff ids.
+fn open_docker_archive(path: &Path, reference: &str) -> Result<LocalImage> {
+    let files = archive_files(path)?;
+    let read = |name: &str| -> Result<Vec<u8>> {
+        let blob = files
+            .get(name)
+            .ok_or_else(|| anyhow!("no {name} in docker archive {:?}", path))?;
+        read_at(blob, blob.size)
+    };
+
+    let manifests: Vec<ArchiveManifest> = serde_json::from_slice(&read("manifest.json")?)
+        .map_err(|e| anyhow!("invalid manifest.json of docker archive {:?}: {e}", path))?;
+    let parsed = crate::registry::parse_reference(reference)?;
+    let archive_manifest = manifests
+        .into_iter()
+        .find(|manifest| {
+            manifest.repo_tags.iter().flatten().any(|tag| {
+                crate::registry::parse_reference(tag).is_ok_and(|tag| tag.whole() == parsed.whole())
+            })
+        })
+        .ok_or_else(|| anyhow!("no image {reference} in docker archive {:?}", path))?;
+
+    let config = read(&archive_manifest.config)?;
+    let config_digest = format!(
+        "{}{:x}",
+        DIGEST_SHA256_PREFIX,
+        sha2::Sha256::digest(&config)
+    );
+    let image_config = ImageConfiguration::from_reader(&config[..])
+        .map_err(|e| anyhow!("invalid config of docker archive {:?}: {e}", path))?;
+    let diff_ids = image_config.rootfs().diff_ids();
+    if diff_ids.len() != archive_manifest.layers.len() {
+        bail!(
+            "{} layers for {} diff_ids in config of docker archive {:?}",
+            archive_manifest.layers.len(),
+            diff_ids.len(),
+            path
+        );
+    }
+
+    let mut layers = HashMap::new();
+    let mut descriptors = Vec::new();
+    for (name, diff_id) in archive_manifest.layers.iter().zip(diff_ids) {
+        let blob = files
+            .get(name.as_str())
+            .ok_or_else(|| anyhow!("no layer {name} in docker archive {:?}", path))?;
+
+        // The layers of `docker save` are uncompressed, but may be
+        // compressed in the tarballs from other tools.
+        let magic = read_at(blob, 4)?;
+        let (media_type, digest) = if magic.starts_with(&[0x1f, 0x8b]) {
+            (OCI_LAYER_GZIP_MEDIA_TYPE, digest_at(blob)?)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            (OCI_LAYER_ZSTD_MEDIA_TYPE, digest_at(blob)?)
+        } else {
+            (OCI_LAYER_MEDIA_TYPE, diff_id.clone())
+        };
+
+        descriptors.push(serde_json::json!({
+            "mediaType": media_type,
+            "digest": digest,
+            "size": blob.size,
+        }));
+        layers.insert(digest, blob.clone());
+    }
+
+    let manifest = serde_json::to_vec(&serde_json::json!({
+        "schemaVersion": 2,
+        "mediaType": OCI_IMAGE_MANIFEST_MEDIA_TYPE,
+        "config": {
+            "mediaType": IMAGE_CONFIG_MEDIA_TYPE,
+            "digest": config_digest,
+            "size": config.len(),
+        },
+        "layers": descriptors,
+    }))?;
+    let digest = format!(
+        "{}{:x}",
+        DIGEST_SHA256_PREFIX,
+        sha2::Sha256::digest(&manifest)
+    );
+
+    Ok(LocalImage {
+        manifest: serde_json::from_slice(&manifest)?,
+        digest,
+        config: String::from_utf8(config)
+            .map_err(|e| anyhow!("invalid config of docker archive {:?}: {e}", path))?,
+        layers,
+    })
+}
+
+/// Get the regular files of the tarball `path`, by their normalized names,
+/// including the links to them.
+fn archive_files(path: &Path) -> Result<HashMap<String, Blob>> {
+    let mut file =
+        File::open(path).map_err(|e| anyhow!("failed to open docker archive {:?}: {e}", path))?;
+    let mut magic = [0; 2];
+    if file.read(&mut magic)? == 2 && magic == [0x1f, 0x8b] {
+        bail!("compressed docker archive {:?} is unsupported", path);
+    }
+    file.rewind()?;
+
+    let mut files = HashMap::new();
+    let mut links = Vec::new();
+    let mut archive = tar::Archive::new(file);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let name = normalize(&entry.path()?);
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_file() {
+            files.insert(
+                name,
+                Blob {
+                    path: path.to_path_buf(),
+                    offset: entry.raw_file_position(),
+                    size: entry.size(),
+                },
+            );
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let Some(target) = entry.link_name()? else {
+                continue;
+            };
+
+            // A symbolic link is relative to its directory, a hard link to
+            // the root of the tarball.
+            let target = if entry_type.is_symlink() {
+                Path::new(&name)
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(target)
+            } else {
+                target.to_path_buf()
+            };
+            links.push((name, normalize(&target)));
+        }
+    }
+
+    for (name, target) in links {
+        if let Some(blob) = files.get(&target).cloned() {
+            files.insert(name, blob);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Normalize the name of a file in a tarball, without `.` and `..`.
+fn normalize(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => normalized.push(name),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+
+    normalized.to_string_lossy().to_string()
+}
+
+/// Read up to `len` bytes of `blob`.
+fn read_at(blob: &Blob, len: u64) -> Result<Vec<u8>> {
+    let mut file = File::open(&blob.path)?;
+    file.seek(SeekFrom::Start(blob.offset))?;
+    let mut data = Vec::new();
+    file.take(len.min(blob.size)).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Get the sha256 digest of `blob`.
+fn digest_at(blob: &Blob) -> Result<String> {
+    let mut file = File::open(&blob.path)?;
+    file.seek(SeekFrom::Start(blob.offset))?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file.take(blob.size), &mut hasher)?;
+    Ok(format!("{}{:x}", DIGEST_SHA256_PREFIX, hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(data: &[u8]) -> String {
+        format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(data))
+    }
+
+    fn write_blob(dir: &Path, data: &[u8]) -> String {
+        let digest = digest(data);
+        let path = blob_path(dir, &digest).unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+        digest
+    }
+
+    fn layer() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "hello", &b"hello"[..])
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    fn config(diff_id: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "architecture": "amd64",
+            "os": "linux",
+            "rootfs": {"type": "layers", "diff_ids": [diff_id]},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_local_source() {
+        assert_eq!(
+            LocalSource::parse("oci:/mnt/images:registry.io/app:v1").unwrap(),
+            Some(LocalSource::OciLayout {
+                dir: PathBuf::from("/mnt/images"),
+                reference: "registry.io/app:v1".to_string(),
+            })
+        );
+        assert_eq!(
+            LocalSource::parse("docker-archive:/mnt/app.tar:app:v1").unwrap(),
+            Some(LocalSource::DockerArchive {
+                path: PathBuf::from("/mnt/app.tar"),
+                reference: "app:v1".to_string(),
+            })
+        );
+        assert_eq!(LocalSource::parse("registry.io/app:v1").unwrap(), None);
+        assert_eq!(LocalSource::parse("oci:5000/app:v1").unwrap(), None);
+        assert!(LocalSource::parse("oci:/mnt/images").is_err());
+        assert!(LocalSource::parse("oci:/mnt/images:App").is_err());
+    }
+
+    #[test]
+    fn test_blob_path() {
+        let dir = Path::new("/layout");
+        assert_eq!(
+            blob_path(dir, "sha256:1234").unwrap(),
+            PathBuf::from("/layout/blobs/sha256/1234")
+        );
+        assert!(blob_path(dir, "sha256:../../etc/passwd").is_err());
+        assert!(blob_path(dir, "sha256").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oci_layout() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let dir = tempdir.path();
+        let layer = layer();
+        let diff_id = digest(&layer);
+        let config = config(&diff_id);
+        let config_digest = write_blob(dir, &config);
+        let layer_digest = write_blob(dir, &layer);
+        let manifest = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_IMAGE_MANIFEST_MEDIA_TYPE,
+            "config": {
+                "mediaType": IMAGE_CONFIG_MEDIA_TYPE,
+                "digest": config_digest,
+                "size": config.len(),
+            },
+            "layers": [{
+                "mediaType": OCI_LAYER_MEDIA_TYPE,
+                "digest": layer_digest,
+                "size": layer.len(),
+            }],
+        }))
+        .unwrap();
+        let manifest_digest = write_blob(dir, &manifest);
+        std::fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion": "1.0.0"}"#).unwrap();
+        std::fs::write(
+            dir.join("index.json"),
+            serde_json::to_vec(&serde_json::json!({
+                "schemaVersion": 2,
+                "manifests": [{
+                    "mediaType": OCI_IMAGE_MANIFEST_MEDIA_TYPE,
+                    "digest": manifest_digest,
+                    "size": manifest.len(),
+                    "annotations": {ANNOTATION_REF_NAME: "v1"},
+                }],
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let source = LocalSource::OciLayout {
+            dir: dir.to_path_buf(),
+            reference: "registry.io/app:v1".to_string(),
+        };
+        let image = LocalImage::open(&source).await.unwrap();
+        let (image_manifest, image_digest, image_config) = image.manifest();
+        assert_eq!(image_digest, manifest_digest);
+        assert_eq!(image_config.as_bytes(), &config[..]);
+        assert_eq!(image_manifest.layers[0].digest, layer_digest);
+
+        let mut data = Vec::new();
+        image
+            .open_layer(&layer_digest)
+            .await
+            .unwrap()
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, layer);
+
+        let source = LocalSource::OciLayout {
+            dir: dir.to_path_buf(),
+            reference: "registry.io/app:v2".to_string(),
+        };
+        assert!(LocalImage::open(&source).await.is_err());
+
+        // A tampered config is not of the digest in the manifest.
+        std::fs::write(blob_path(dir, &config_digest).unwrap(), b"{}").unwrap();
+        let source = LocalSource::OciLayout {
+            dir: dir.to_path_buf(),
+            reference: "registry.io/app:v1".to_string(),
+        };
+        assert!(LocalImage::open(&source).await.is_err());
+    }
+
+    /// Write a `docker save` tarball to `path`, of the image `app:v1` of
+    /// `config` and the layers named `layer_names`, whose layer is linked by
+    /// `link/layer.tar`.
+    fn write_archive(path: &Path, config: &[u8], layer: &[u8], layer_names: &[&str]) {
+        let manifest = serde_json::to_vec(&serde_json::json!([{
+            "Config": "config.json",
+            "RepoTags": ["app:v1"],
+            "Layers": layer_names,
+        }]))
+        .unwrap();
+
+        let mut builder = tar::Builder::new(File::create(path).unwrap());
+        for (name, data) in [
+            ("config.json", config),
+            ("layer/layer.tar", layer),
+            ("manifest.json", &manifest[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, data).unwrap();
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "link/layer.tar", "../layer/layer.tar")
+            .unwrap();
+        builder.finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_docker_archive() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("app.tar");
+        let layer = layer();
+        let diff_id = digest(&layer);
+        let config = config(&diff_id);
+        let source = LocalSource::DockerArchive {
+            path: path.clone(),
+            reference: "docker.io/library/app:v1".to_string(),
+        };
+
+        // The config has a diff id for each layer.
+        write_archive(
+            &path,
+            &config,
+            &layer,
+            &["layer/layer.tar", "link/layer.tar"],
+        );
+        assert!(LocalImage::open(&source).await.is_err());
+
+        write_archive(&path, &config, &layer, &["link/layer.tar"]);
+        let image = LocalImage::open(&source).await.unwrap();
+        let (image_manifest, _, image_config) = image.manifest();
+        assert_eq!(image_config.as_bytes(), config);
+        assert_eq!(image_manifest.config.digest, digest(&config));
+        assert_eq!(image_manifest.layers[0].digest, diff_id);
+        assert_eq!(image_manifest.layers[0].media_type, OCI_LAYER_MEDIA_TYPE);
+
+        let mut data = Vec::new();
+        image
+            .open_layer(&diff_id)
+            .await
+            .unwrap()
+            .read_to_end(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, layer);
+
+        let source = LocalSource::DockerArchive {
+            path,
+            reference: "app:v2".to_string(),
+        };
+        assert!(LocalImage::open(&source).await.is_err());
+    }
+}
@@ -0,0 +1,52 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Record the events of image-rs in the runtime measurements of the TEE,
+//! by the attestation agent, like the root hashes of the rootfs images and
+//! the digests of the signature policies.
+
+use anyhow::*;
+use ttrpc::context;
+
+use ttrpc_proto::attestation_agent::ExtendRuntimeMeasurementRequest;
+use ttrpc_proto::attestation_agent_ttrpc::AttestationAgentServiceClient;
+
+mod ttrpc_proto;
+
+const SOCKET_ADDR: &str =
+    "unix:///run/confidential-containers/attestation-agent/attestation-agent.sock";
+
+/// Domain of the events of image-rs.
+const MEASUREMENT_DOMAIN: &str = "github.com/confidential-containers/image-rs";
+
+/// Operation of the events of the rootfs images.
+pub const VERITY_ROOTFS_OPERATION: &str = "VerityRootfs";
+
+/// Operation of the events of the signature policies.
+pub const SIGNATURE_POLICY_OPERATION: &str = "SignaturePolicy";
+
+/// Timeout of a measurement, in nanoseconds.
+const MEASUREMENT_TIMEOUT: i64 = 50 * 1000 * 1000 * 1000;
+
+/// Extend the runtime measurements with the event
+/// `github.com/confidential-containers/image-rs <operation> <content>`,
+/// so that what image-rs relies on can be attested.
+pub async fn extend_measurement(operation: &str, content: &str) -> Result<()> {
+    let inner = ttrpc::asynchronous::Client::connect(SOCKET_ADDR)
+        .context("connect to attestation agent")?;
+    let client = AttestationAgentServiceClient::new(inner);
+
+    let req = ExtendRuntimeMeasurementRequest {
+        Domain: MEASUREMENT_DOMAIN.to_string(),
+        Operation: operation.to_string(),
+        Content: content.to_string(),
+        ..Default::default()
+    };
+    client
+        .extend_runtime_measurement(context::with_timeout(MEASUREMENT_TIMEOUT), &req)
+        .await
+        .context("ttrpc request error")?;
+    Ok(())
+}
@@ -0,0 +1,8 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+#![allow(clippy::redundant_field_names)]
+pub mod attestation_agent;
+pub mod attestation_agent_ttrpc;
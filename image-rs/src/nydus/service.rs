@@ -3,9 +3,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{anyhow, bail, Result};
+use base64::Engine;
 use log::{error, info};
 use nix::mount::MsFlags;
-use oci_distribution::Reference;
+use oci_distribution::{secrets::RegistryAuth, Reference};
 use oci_spec::image::Os;
 use std::convert::TryInto;
 use std::path::Path;
@@ -35,6 +36,7 @@ lazy_static::lazy_static! {
 pub async fn start_nydus_service(
     image_data: &ImageMeta,
     reference: Reference,
+    auth: &RegistryAuth,
     nydus_config: &NydusConfig,
     work_dir: &Path,
     bundle_dir: &Path,
@@ -48,6 +50,8 @@ pub async fn start_nydus_service(
     let mountpoint = bundle_dir.join(NYDUS_ROOTFS);
     let id = nydus_config.id.clone();
     let work_dir_buf = work_dir.to_owned();
+    let backend = registry_backend_config(&reference, auth);
+    let digest_validate = nydus_config.digest_validate;
 
     if nydus_config.is_fuse() {
         let fuse_config = nydus_config
@@ -57,7 +61,8 @@ pub async fn start_nydus_service(
         if let Err(e) = task::spawn_blocking(move || {
             process_fuse_daemon(
                 id,
-                reference,
+                &backend,
+                digest_validate,
                 &work_dir_buf,
                 &bootstrap,
                 &mountpoint,
@@ -93,6 +98,8 @@ pub async fn start_nydus_service(
             process_fscache_daemon(
                 id,
                 reference,
+                &backend,
+                digest_validate,
                 &blob_id,
                 &work_dir_buf,
                 &bootstrap,
@@ -129,10 +136,29 @@ pub async fn start_nydus_service(
     Ok(image_id)
 }
 
+/// Get the config of the nydus registry backend to fetch the blobs of the
+/// image, authenticated as the image itself is pulled.
+pub fn registry_backend_config(reference: &Reference, auth: &RegistryAuth) -> serde_json::Value {
+    let mut config = serde_json::json!({
+        "scheme": "https",
+        "host": reference.resolve_registry(),
+        "repo": reference.repository(),
+    });
+
+    if let RegistryAuth::Basic(username, password) = auth {
+        config["auth"] = base64::engine::general_purpose::STANDARD
+            .encode(format!("{username}:{password}"))
+            .into();
+    }
+
+    config
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn process_fuse_daemon(
     id: Option<String>,
-    reference: Reference,
+    backend: &serde_json::Value,
+    digest_validate: bool,
     work_dir: &Path,
     bootstrap: &Path,
     mountpoint: &Path,
@@ -144,11 +170,7 @@ pub fn process_fuse_daemon(
     "device": {{
         "backend": {{
             "type": "registry",
-            "config": {{
-                "scheme": "https",
-                "host": {:?},
-                "repo": {:?}
-            }}
+            "config": {}
         }},
         "cache": {{
             "type": "blobcache",
@@ -159,13 +181,13 @@ pub fn process_fuse_daemon(
         }}
     }},
     "mode": "direct",
-    "digest_validate": false,
+    "digest_validate": {},
     "iostats_files": false
 }}
 "###,
-        reference.registry(),
-        reference.repository(),
+        backend,
         work_dir.join("cache"),
+        digest_validate,
     );
 
     if !mountpoint.exists() {
@@ -226,6 +248,8 @@ pub fn process_fuse_daemon(
 pub fn process_fscache_daemon(
     id: Option<String>,
     reference: Reference,
+    backend: &serde_json::Value,
+    digest_validate: bool,
     blob_id: &str,
     work_dir: &Path,
     bootstrap: &Path,
@@ -244,11 +268,7 @@ pub fn process_fscache_daemon(
         "version": 2,
         "backend": {{
             "type": "registry",
-            "registry": {{
-                "scheme": "https",
-                "host": {:?},
-                "repo": {:?}
-            }}
+            "registry": {}
         }},
         "cache": {{
             "type": "fscache",
@@ -256,15 +276,18 @@ pub fn process_fscache_daemon(
                 "work_dir": {:?}
             }}
         }},
+        "rafs": {{
+            "validate": {}
+        }},
         "metadata_path": {:?}
     }}
 }}
 "###,
         blob_id,
         domain_id,
-        reference.registry(),
-        reference.repository(),
+        backend,
         &work_dir.join("cache"),
+        digest_validate,
         bootstrap,
     );
 
@@ -383,3 +406,21 @@ pub fn erofs_unmount(mount_point: &Path) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_backend_config() {
+        let reference = Reference::try_from("busybox:latest").unwrap();
+        let config = registry_backend_config(&reference, &RegistryAuth::Anonymous);
+        assert_eq!(config["host"], "index.docker.io");
+        assert_eq!(config["repo"], "library/busybox");
+        assert!(config.get("auth").is_none());
+
+        let auth = RegistryAuth::Basic("user".to_string(), "pass".to_string());
+        let config = registry_backend_config(&reference, &auth);
+        assert_eq!(config["auth"], "dXNlcjpwYXNz");
+    }
+}
@@ -0,0 +1,258 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Progress of the image pulls, reported per layer, so that the callers of
+//! image-rs can show how a pull of a large image goes, instead of a silent
+//! hang.
+//!
+//! The progress is sent to the channel given to
+//! [`ImageClient::pull_image_with_progress`](crate::image::ImageClient::pull_image_with_progress).
+//! The bytes of each stage of a layer are reported every
+//! [`PROGRESS_INTERVAL`] bytes and when the stage is done, so that a pull
+//! sends a bounded number of events.
+
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use serde::Serialize;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Bytes of a stage between two reports of its progress.
+pub const PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+/// Stages a layer goes through when it is pulled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStage {
+    /// The blob of the layer is downloaded from the registry.
+    Downloaded,
+
+    /// The blob of an encrypted layer is decrypted.
+    Decrypted,
+
+    /// The uncompressed tar of the layer is unpacked.
+    Unpacked,
+}
+
+/// Event of the progress of a pull.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "event")]
+pub enum PullProgress {
+    /// The manifest of the image is pulled, with the number of the layers
+    /// to pull and their total compressed size in bytes.
+    Manifest {
+        image: String,
+        layers: usize,
+        size: u64,
+    },
+
+    /// The layer starts to be pulled, of `size` compressed bytes.
+    LayerStarted { digest: String, size: u64 },
+
+    /// The stage of the layer has handled `bytes` so far.
+    LayerProgress {
+        digest: String,
+        stage: PullStage,
+        bytes: u64,
+    },
+
+    /// The layer is done, which may be found in the caches.
+    LayerCompleted { digest: String, cached: bool },
+
+    /// All the layers of the image are pulled, and the bundle is created.
+    Completed { image: String },
+}
+
+/// Reporter of the progress of a pull, cheap to clone and shared by the
+/// layer tasks. The default one reports nothing.
+#[derive(Clone, Debug, Default)]
+pub struct ProgressReporter {
+    tx: Option<UnboundedSender<PullProgress>>,
+}
+
+impl ProgressReporter {
+    pub fn new(tx: UnboundedSender<PullProgress>) -> Self {
+        ProgressReporter { tx: Some(tx) }
+    }
+
+    /// Report `progress`. A pull never fails because the receiver is gone.
+    pub fn report(&self, progress: PullProgress) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(progress);
+        }
+    }
+
+    /// The reporter of the progress of the layer `digest`.
+    pub fn layer(&self, digest: &str) -> LayerProgress {
+        LayerProgress {
+            reporter: self.clone(),
+            digest: digest.to_string(),
+        }
+    }
+}
+
+/// Reporter of the progress of a layer.
+#[derive(Clone, Debug, Default)]
+pub struct LayerProgress {
+    reporter: ProgressReporter,
+    digest: String,
+}
+
+impl LayerProgress {
+    pub fn started(&self, size: u64) {
+        self.reporter.report(PullProgress::LayerStarted {
+            digest: self.digest.clone(),
+            size,
+        });
+    }
+
+    pub fn completed(&self, cached: bool) {
+        self.reporter.report(PullProgress::LayerCompleted {
+            digest: self.digest.clone(),
+            cached,
+        });
+    }
+
+    /// Wrap `reader`, so that the bytes read from it are reported as the
+    /// progress of `stage`.
+    pub fn wrap<R>(&self, stage: PullStage, reader: R) -> ProgressRead<R> {
+        ProgressRead {
+            inner: reader,
+            progress: self.clone(),
+            stage,
+            bytes: 0,
+            reported: 0,
+        }
+    }
+}
+
+/// A reader reporting the bytes read as the progress of a stage, both as
+/// an [`AsyncRead`] and as a [`Read`].
+pub struct ProgressRead<R> {
+    inner: R,
+    progress: LayerProgress,
+    stage: PullStage,
+    bytes: u64,
+    reported: u64,
+}
+
+impl<R> ProgressRead<R> {
+    /// Account `n` bytes read, `0` meaning the end of the stage.
+    fn consume(&mut self, n: usize) {
+        self.bytes += n as u64;
+        let due = if n == 0 {
+            self.bytes != self.reported
+        } else {
+            self.bytes - self.reported >= PROGRESS_INTERVAL
+        };
+
+        if due {
+            self.reported = self.bytes;
+            self.progress.reporter.report(PullProgress::LayerProgress {
+                digest: self.progress.digest.clone(),
+                stage: self.stage,
+                bytes: self.bytes,
+            });
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ProgressRead<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let n = buf.filled().len() - filled;
+            self.consume(n);
+        }
+
+        poll
+    }
+}
+
+impl<R: Read> Read for ProgressRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        // An empty `buf` is no end of the stage.
+        if n != 0 || !buf.is_empty() {
+            self.consume(n);
+        }
+
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_progress_read() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let progress = ProgressReporter::new(tx).layer("sha256:layer");
+
+        let data = vec![0u8; 5 * PROGRESS_INTERVAL as usize / 2];
+        let mut reader = progress.wrap(PullStage::Downloaded, data.as_slice());
+        let mut buf = Vec::new();
+        AsyncReadExt::read_to_end(&mut reader, &mut buf)
+            .await
+            .unwrap();
+        drop(reader);
+        progress.completed(false);
+        drop(progress);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        let bytes: Vec<u64> = events
+            .iter()
+            .filter_map(|event| match event {
+                PullProgress::LayerProgress { stage, bytes, .. } => {
+                    assert_eq!(*stage, PullStage::Downloaded);
+                    Some(*bytes)
+                }
+                _ => None,
+            })
+            .collect();
+        assert!(bytes.len() >= 2 && bytes.len() <= 3);
+        assert!(bytes.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(bytes.last(), Some(&(data.len() as u64)));
+        assert_eq!(
+            events.last(),
+            Some(&PullProgress::LayerCompleted {
+                digest: "sha256:layer".to_string(),
+                cached: false
+            })
+        );
+
+        // The stage is reported done once only.
+        let mut reader = LayerProgress::default().wrap(PullStage::Unpacked, [1u8; 10].as_slice());
+        let mut buf = Vec::new();
+        Read::read_to_end(&mut reader, &mut buf).unwrap();
+        assert_eq!(reader.bytes, 10);
+        assert_eq!(reader.reported, 10);
+    }
+
+    #[test]
+    fn test_progress_serialize() {
+        let event = PullProgress::LayerProgress {
+            digest: "sha256:layer".to_string(),
+            stage: PullStage::Decrypted,
+            bytes: 1,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"layer_progress","digest":"sha256:layer","stage":"decrypted","bytes":1}"#
+        );
+    }
+}
@@ -4,17 +4,27 @@
 
 use anyhow::{anyhow, bail, Result};
 use futures_util::stream::{self, StreamExt, TryStreamExt};
+use log::warn;
 use oci_distribution::manifest::{OciDescriptor, OciImageManifest};
-use oci_distribution::{secrets::RegistryAuth, Client, Reference};
+use oci_distribution::{secrets::RegistryAuth, Client, Reference, RegistryOperation};
+use sha2::Digest;
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::budget::PullBudget;
+use crate::config::ResumableDownloadConfig;
+use crate::decoder::zstd_chunked::ZstdChunkedMetadata;
 use crate::decoder::Compression;
 use crate::decrypt::Decryptor;
+use crate::digest::DIGEST_SHA256_PREFIX;
 use crate::image::LayerMeta;
+use crate::layer_cache::LayerCache;
+use crate::local::LocalImage;
 use crate::meta_store::MetaStore;
+use crate::progress::{LayerProgress, ProgressReporter, PullStage};
 use crate::stream::stream_processing;
 
 const ERR_NO_DECRYPT_CFG: &str = "decrypt_config is None";
@@ -36,16 +46,37 @@ pub struct PullClient<'a> {
 
     /// Max number of concurrent downloads.
     pub max_concurrent_download: usize,
+
+    /// Memory and bandwidth budget shared by the layers pulled concurrently.
+    pub budget: PullBudget,
+
+    /// Lazily pull the eStargz layers, with the `estargz` feature.
+    pub lazy_pull: bool,
+
+    /// Persistent cache of the unpacked layers, if any.
+    pub layer_cache: Option<Arc<LayerCache>>,
+
+    /// Resume the downloads of the blobs, with the `resumable-download`
+    /// feature.
+    pub resumable_download: Option<ResumableDownloadConfig>,
+
+    /// Reporter of the progress of the layers.
+    pub progress: ProgressReporter,
+
+    /// The local image pulled instead of the image of the registry, if any.
+    pub local: Option<Arc<LocalImage>>,
 }
 
 impl<'a> PullClient<'a> {
     /// Constructs a new PullClient struct with provided image info,
-    /// data store dir and optional remote registry auth info.
+    /// data store dir, optional remote registry auth info and the
+    /// budget of the pull.
     pub fn new(
         reference: Reference,
         data_dir: &Path,
         auth: &'a RegistryAuth,
         max_concurrent_download: usize,
+        budget: PullBudget,
     ) -> Result<PullClient<'a>> {
         let client = Client::default();
 
@@ -55,11 +86,21 @@ impl<'a> PullClient<'a> {
             reference,
             data_dir: data_dir.to_path_buf(),
             max_concurrent_download,
+            budget,
+            lazy_pull: false,
+            layer_cache: None,
+            resumable_download: None,
+            progress: ProgressReporter::default(),
+            local: None,
         })
     }
 
     /// pull_manifest pulls an image manifest and config data.
     pub async fn pull_manifest(&mut self) -> Result<(OciImageManifest, String, String)> {
+        if let Some(local) = &self.local {
+            return Ok(local.manifest());
+        }
+
         self.client
             .pull_manifest_and_config(&self.reference, self.auth)
             .await
@@ -85,6 +126,9 @@ impl<'a> PullClient<'a> {
 
     /// async_pull_layers pulls an image layers and do ondemand decrypt/decompress.
     /// It returns the layer metadata for layer db to track.
+    ///
+    /// Up to `max_concurrent_download` layers are handled at the same time,
+    /// each on its own task, within the budget of the client.
     pub async fn async_pull_layers(
         &self,
         layer_descs: Vec<OciDescriptor>,
@@ -92,34 +136,33 @@ impl<'a> PullClient<'a> {
         decrypt_config: &Option<&str>,
         meta_store: Arc<Mutex<MetaStore>>,
     ) -> Result<Vec<LayerMeta>> {
+        if layer_descs.len() != diff_ids.len() {
+            bail!(
+                "{} layers for {} diff_ids in image config",
+                layer_descs.len(),
+                diff_ids.len()
+            );
+        }
+
+        // The layers are kept in order, to be checked against the diff ids
+        // and mounted in order.
         let layer_metas = stream::iter(layer_descs)
             .enumerate()
             .map(|(i, layer)| {
-                let client = &self.client;
-                let reference = &self.reference;
-                let ms = meta_store.clone();
+                let task = self.layer_task(decrypt_config, meta_store.clone());
+                let diff_id = diff_ids[i].clone();
 
                 async move {
-                    let layer_reader = client
-                        .async_pull_blob(reference, &layer.digest)
+                    tokio::spawn(task.pull_layer(layer, diff_id))
                         .await
-                        .map_err(|e| anyhow!("failed to async pull blob {}", e.to_string()))?;
-
-                    self.async_handle_layer(
-                        layer,
-                        diff_ids[i].clone(),
-                        decrypt_config,
-                        layer_reader,
-                        ms,
-                    )
-                    .await
-                    .map_err(|e| anyhow!("failed to handle layer: {:?}", e))
+                        .map_err(|e| anyhow!("layer task failed {e}"))?
                 }
             })
-            .buffer_unordered(self.max_concurrent_download)
+            .buffered(self.max_concurrent_download)
             .try_collect()
             .await?;
 
+        check_diff_ids(&layer_metas, diff_ids)?;
         Ok(layer_metas)
     }
 
@@ -131,22 +174,249 @@ impl<'a> PullClient<'a> {
         layer_reader: (impl tokio::io::AsyncRead + Unpin + Send),
         ms: Arc<Mutex<MetaStore>>,
     ) -> Result<LayerMeta> {
-        let layer_db = &ms.lock().await.layer_db;
-        if let Some(layer_meta) = layer_db.get(&layer.digest) {
-            return Ok(layer_meta.clone());
+        let task = self.layer_task(decrypt_config, ms);
+        if let Some(layer_meta) = task.cached_layer(&layer).await {
+            return Ok(layer_meta);
+        }
+
+        task.handle_layer(layer, diff_id, layer_reader).await
+    }
+
+    fn layer_task(&self, decrypt_config: &Option<&str>, ms: Arc<Mutex<MetaStore>>) -> LayerTask {
+        LayerTask {
+            client: self.client.clone(),
+            reference: self.reference.clone(),
+            data_dir: self.data_dir.clone(),
+            decrypt_config: decrypt_config.map(String::from),
+            budget: self.budget.clone(),
+            ms,
+            layer_cache: self.layer_cache.clone(),
+            auth: self.auth.clone(),
+            progress: self.progress.clone(),
+            local: self.local.clone(),
+            #[cfg(feature = "estargz")]
+            lazy_pull: self.lazy_pull,
+            #[cfg(feature = "resumable-download")]
+            resumable_download: self.resumable_download.clone(),
+        }
+    }
+}
+
+/// Everything needed to pull a single layer, owned so that the layers
+/// can be fetched, decrypted, decompressed and unpacked on their own
+/// tasks, in parallel.
+struct LayerTask {
+    client: Client,
+    reference: Reference,
+    data_dir: PathBuf,
+    decrypt_config: Option<String>,
+    budget: PullBudget,
+    ms: Arc<Mutex<MetaStore>>,
+    layer_cache: Option<Arc<LayerCache>>,
+    /// The registry auth, to authenticate again if the token expires.
+    auth: RegistryAuth,
+    /// Reporter of the progress of the layers.
+    progress: ProgressReporter,
+    /// The local image whose layers are read instead of pulled.
+    local: Option<Arc<LocalImage>>,
+    /// Lazily pull the eStargz layers.
+    #[cfg(feature = "estargz")]
+    lazy_pull: bool,
+    /// Resume the downloads of the blobs.
+    #[cfg(feature = "resumable-download")]
+    resumable_download: Option<ResumableDownloadConfig>,
+}
+
+impl LayerTask {
+    async fn pull_layer(mut self, layer: OciDescriptor, diff_id: String) -> Result<LayerMeta> {
+        let progress = self.progress.layer(&layer.digest);
+        progress.started(layer.size.max(0) as u64);
+
+        if let Some(layer_meta) = self.cached_layer(&layer).await {
+            progress.completed(true);
+            return Ok(layer_meta);
+        }
+
+        if let Some(layer_meta) = self.persisted_layer(&layer, &diff_id).await? {
+            progress.completed(true);
+            return Ok(layer_meta);
         }
 
+        #[cfg(feature = "estargz")]
+        if self.lazy_pull && self.local.is_none() && crate::estargz::is_lazy_pullable(&layer) {
+            let layer_meta = crate::estargz::mount_layer(
+                &self.reference,
+                &self.auth,
+                &layer,
+                &diff_id,
+                &self.data_dir,
+            )
+            .await?;
+            progress.completed(false);
+            return Ok(layer_meta);
+        }
+
+        let layer_reader = self.open_blob(&layer).await?;
+        let layer_reader = progress.wrap(PullStage::Downloaded, self.budget.throttle(layer_reader));
+
+        let layer_meta = self
+            .handle_layer(layer, diff_id, layer_reader)
+            .await
+            .map_err(|e| anyhow!("failed to handle layer: {:?}", e))?;
+        progress.completed(false);
+        Ok(layer_meta)
+    }
+
+    /// Open the blob of `layer` to download, whose download is resumed if
+    /// interrupted with the `resumable-download` feature, or read from the
+    /// local image if any.
+    async fn open_blob(
+        &mut self,
+        layer: &OciDescriptor,
+    ) -> Result<Box<dyn tokio::io::AsyncRead + Unpin + Send>> {
+        if let Some(local) = &self.local {
+            return local.open_layer(&layer.digest).await;
+        }
+
+        #[cfg(feature = "resumable-download")]
+        if let Some(config) = self.resumable_download.clone() {
+            use crate::resume::{Checkpoint, ResumableBlob};
+
+            let size = layer.size.max(0) as u64;
+            let checkpoint = match &config.checkpoint_dir {
+                Some(dir) => Some(Checkpoint::open(dir, &layer.digest, size)?),
+                None => None,
+            };
+
+            // A checkpointed blob is resumed from the checkpoint instead.
+            let blob: Option<Box<dyn tokio::io::AsyncRead + Unpin + Send>> =
+                match checkpoint.as_ref().map(Checkpoint::checkpointed) {
+                    Some(checkpointed) if checkpointed > 0 => None,
+                    _ => Some(Box::new(self.pull_blob(layer).await?)),
+                };
+
+            return Ok(Box::new(ResumableBlob::new(
+                &self.reference,
+                &self.auth,
+                &layer.digest,
+                size,
+                config.max_resumes,
+                checkpoint,
+                blob,
+            )));
+        }
+
+        Ok(Box::new(self.pull_blob(layer).await?))
+    }
+
+    /// Pull the blob of `layer`. If it fails, e.g. as the bearer token got
+    /// with the manifest has expired during a long pull, the client
+    /// authenticates again to renew the token, and retries once.
+    async fn pull_blob(
+        &mut self,
+        layer: &OciDescriptor,
+    ) -> Result<impl tokio::io::AsyncRead + Unpin + Send> {
+        match self
+            .client
+            .async_pull_blob(&self.reference, &layer.digest)
+            .await
+        {
+            Ok(layer_reader) => return Ok(layer_reader),
+            Err(e) => warn!(
+                "failed to async pull blob {}, authenticate again: {}",
+                layer.digest, e
+            ),
+        }
+
+        self.client
+            .auth(&self.reference, &self.auth, RegistryOperation::Pull)
+            .await
+            .map_err(|e| anyhow!("failed to authenticate to pull blob {e}"))?;
+
+        self.client
+            .async_pull_blob(&self.reference, &layer.digest)
+            .await
+            .map_err(|e| anyhow!("failed to async pull blob {}", e.to_string()))
+    }
+
+    async fn cached_layer(&self, layer: &OciDescriptor) -> Option<LayerMeta> {
+        // Only hold the meta store for the lookup, so that the other
+        // layers are not held back.
+        self.ms.lock().await.layer_db.get(&layer.digest).cloned()
+    }
+
+    /// Get the layer from the layer cache, where it may be from another
+    /// image or from before a restart.
+    async fn persisted_layer(
+        &self,
+        layer: &OciDescriptor,
+        diff_id: &str,
+    ) -> Result<Option<LayerMeta>> {
+        let Some(cache) = self.layer_cache.clone() else {
+            return Ok(None);
+        };
+
+        let key = layer_cache_key(layer, diff_id)?;
+        let path = tokio::task::spawn_blocking(move || cache.get(&key))
+            .await
+            .map_err(|e| anyhow!("layer cache task failed {e}"))??;
+        let Some(path) = path else {
+            return Ok(None);
+        };
+
+        // The plaintext of an encrypted layer is only reused by whoever
+        // can get its key.
+        let decryptor = Decryptor::from_media_type(&layer.media_type);
+        if decryptor.is_encrypted() {
+            let dc = self
+                .decrypt_config
+                .as_ref()
+                .ok_or_else(|| anyhow!(ERR_NO_DECRYPT_CFG))?;
+            decryptor
+                .get_decrypt_key(layer, dc)
+                .map_err(|e| anyhow!("failed to get decrypt key {e}"))?;
+        }
+
+        Ok(Some(LayerMeta {
+            encrypted: decryptor.is_encrypted(),
+            compressed_digest: layer.digest.clone(),
+            uncompressed_digest: diff_id.to_string(),
+            store_path: path.display().to_string(),
+            ..Default::default()
+        }))
+    }
+
+    async fn handle_layer(
+        &self,
+        layer: OciDescriptor,
+        diff_id: String,
+        layer_reader: (impl tokio::io::AsyncRead + Unpin + Send),
+    ) -> Result<LayerMeta> {
+        let progress = self.progress.layer(&layer.digest);
         let blob_id = layer.digest.to_string().replace(':', "_");
-        let destination = self.data_dir.join(blob_id);
+        let cache_key = layer_cache_key(&layer, &diff_id)?;
+        let destination = match &self.layer_cache {
+            Some(cache) => cache.staging_path(&cache_key),
+            None => self.data_dir.join(blob_id),
+        };
         let mut layer_meta = LayerMeta {
             compressed_digest: layer.digest.clone(),
             store_path: destination.display().to_string(),
             ..Default::default()
         };
 
+        // zstd:chunked layers are pulled as a whole like the other zstd
+        // layers, their metadata is only there for partial pulls.
+        if let Err(e) = ZstdChunkedMetadata::from_annotations(layer.annotations.as_ref()) {
+            warn!(
+                "ignore invalid zstd:chunked metadata of layer {}: {:?}",
+                layer.digest, e
+            );
+        }
+
         let decryptor = Decryptor::from_media_type(&layer.media_type);
         if decryptor.is_encrypted() {
-            if let Some(dc) = decrypt_config {
+            if let Some(dc) = &self.decrypt_config {
                 let decrypt_key = decryptor
                     .get_decrypt_key(&layer, dc)
                     .map_err(|e| anyhow!("failed to get decrypt key {}", e.to_string()))?;
@@ -155,10 +425,11 @@ impl<'a> PullClient<'a> {
                     .map_err(|e| anyhow!("failed to async_get_plaintext_layer: {:?}", e))?;
                 layer_meta.uncompressed_digest = self
                     .async_decompress_unpack_layer(
-                        plaintext_layer,
+                        progress.wrap(PullStage::Decrypted, plaintext_layer),
                         &diff_id,
                         &decryptor.media_type,
                         &destination,
+                        &progress,
                     )
                     .await?;
                 layer_meta.encrypted = true;
@@ -172,12 +443,17 @@ impl<'a> PullClient<'a> {
                     &diff_id,
                     &layer.media_type,
                     &destination,
+                    &progress,
                 )
                 .await?;
         }
 
         // uncompressed digest should equal to the diff_ids in image_config.
+        // The unpacked layer is removed otherwise, so that it is never used.
         if layer_meta.uncompressed_digest != diff_id {
+            if let Err(e) = tokio::fs::remove_dir_all(&destination).await {
+                warn!("failed to remove layer {}: {:?}", layer.digest, e);
+            }
             bail!(
                 "unequal uncompressed digest {:?} config diff_id {:?}",
                 layer_meta.uncompressed_digest,
@@ -185,6 +461,13 @@ impl<'a> PullClient<'a> {
             );
         }
 
+        if let Some(cache) = self.layer_cache.clone() {
+            let path = tokio::task::spawn_blocking(move || cache.insert(&cache_key, &destination))
+                .await
+                .map_err(|e| anyhow!("layer cache task failed {e}"))??;
+            layer_meta.store_path = path.display().to_string();
+        }
+
         Ok(layer_meta)
     }
 
@@ -194,11 +477,56 @@ impl<'a> PullClient<'a> {
         diff_id: &str,
         media_type: &str,
         destination: &Path,
+        progress: &LayerProgress,
     ) -> Result<String> {
         let decoder = Compression::try_from(media_type)?;
         let async_decoder = decoder.async_decompress(input_reader);
-        stream_processing(async_decoder, diff_id, destination).await
+        stream_processing(async_decoder, diff_id, destination, &self.budget, progress).await
+    }
+}
+
+/// Check that the uncompressed digest of each layer, including those got
+/// from the meta store and the layer cache, is the diff id of the layer in
+/// the image config, before the layers are mounted.
+///
+/// The check is a no-op for the lazily pulled eStargz layers: they are not
+/// unpacked, so their uncompressed digest is taken from the diff id of the
+/// config. Their chunks are verified against the TOC of the layer instead
+/// when read.
+fn check_diff_ids(layer_metas: &[LayerMeta], diff_ids: &[String]) -> Result<()> {
+    for (layer_meta, diff_id) in layer_metas.iter().zip(diff_ids) {
+        if layer_meta.uncompressed_digest != *diff_id {
+            bail!(
+                "unequal uncompressed digest {:?} of layer {} config diff_id {:?}",
+                layer_meta.uncompressed_digest,
+                layer_meta.compressed_digest,
+                diff_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the key of the layer in the layer cache.
+///
+/// An unencrypted layer is shared by its uncompressed digest. An encrypted
+/// layer is only shared with the same encrypted layer of the same diff id,
+/// by the digest of its descriptor including the wrapped keys, so that
+/// another layer claiming the same uncompressed digest never gets its
+/// plaintext, and the plaintext is never taken as of another diff id.
+fn layer_cache_key(layer: &OciDescriptor, diff_id: &str) -> Result<String> {
+    if !Decryptor::from_media_type(&layer.media_type).is_encrypted() {
+        return Ok(diff_id.to_string());
     }
+
+    let annotations: BTreeMap<_, _> = layer.annotations.iter().flatten().collect();
+    let descriptor = serde_json::to_vec(&(&layer.digest, &layer.media_type, annotations, diff_id))?;
+    Ok(format!(
+        "{}{:x}",
+        DIGEST_SHA256_PREFIX,
+        sha2::Sha256::digest(descriptor)
+    ))
 }
 
 #[cfg(test)]
@@ -231,6 +559,7 @@ mod tests {
                 tempdir.path(),
                 &RegistryAuth::Anonymous,
                 DEFAULT_MAX_CONCURRENT_DOWNLOAD,
+                PullBudget::default(),
             )
             .unwrap();
             let (image_manifest, _image_digest, image_config) =
@@ -267,6 +596,7 @@ mod tests {
                 tempdir.path(),
                 &RegistryAuth::Anonymous,
                 DEFAULT_MAX_CONCURRENT_DOWNLOAD,
+                PullBudget::default(),
             )
             .unwrap();
             let (image_manifest, _image_digest, image_config) =
@@ -332,6 +662,7 @@ mod tests {
             tempdir.path(),
             &RegistryAuth::Anonymous,
             DEFAULT_MAX_CONCURRENT_DOWNLOAD,
+            PullBudget::default(),
         )
         .unwrap();
 
@@ -432,6 +763,7 @@ mod tests {
                 tempdir.path(),
                 &RegistryAuth::Anonymous,
                 DEFAULT_MAX_CONCURRENT_DOWNLOAD,
+                PullBudget::default(),
             )
             .unwrap();
             let (image_manifest, _image_digest, image_config) =
@@ -451,4 +783,19 @@ mod tests {
                 .is_ok());
         }
     }
+
+    #[test]
+    fn test_check_diff_ids() {
+        let layer_meta = |digest: &str| LayerMeta {
+            compressed_digest: format!("sha256:{}", digest.repeat(64)),
+            uncompressed_digest: format!("sha256:{}", digest.repeat(64)),
+            ..Default::default()
+        };
+        let layer_metas = vec![layer_meta("a"), layer_meta("b")];
+        let diff_id = |digest: &str| format!("sha256:{}", digest.repeat(64));
+
+        assert!(check_diff_ids(&layer_metas, &[diff_id("a"), diff_id("b")]).is_ok());
+        assert!(check_diff_ids(&layer_metas, &[diff_id("b"), diff_id("a")]).is_err());
+        assert!(check_diff_ids(&layer_metas, &[diff_id("a"), diff_id("c")]).is_err());
+    }
 }
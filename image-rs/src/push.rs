@@ -0,0 +1,276 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Push images to the registries from the guest, like the images built or
+//! committed in the TEE, by the OCI distribution API.
+//!
+//! The blobs are uploaded in chunks, each one `PATCH`ed to the upload
+//! session, and the upload is closed by a `PUT` of the digest, once the
+//! digest of the data uploaded is checked, so that a corrupted blob is
+//! never committed in the registry.
+
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use oci_distribution::manifest::OciImageManifest;
+use oci_distribution::{secrets::RegistryAuth, Reference};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_RANGE, CONTENT_TYPE, LOCATION};
+use reqwest::{Method, Response, StatusCode};
+use sha2::Digest;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::digest::{DigestHasher, LayerDigestHasher, DIGEST_SHA256_PREFIX, DIGEST_SHA512_PREFIX};
+use crate::registry::http::RegistryHttp;
+
+const OCI_IMAGE_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+
+/// The default size of the chunks of the blob uploads.
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Client pushing the blobs and the manifests of a repository.
+pub struct Pusher {
+    reference: Reference,
+    http: RegistryHttp,
+    chunk_size: usize,
+}
+
+impl Pusher {
+    /// Create a pusher of the repository of `reference`, authenticated by
+    /// `auth`.
+    pub fn new(reference: &Reference, auth: RegistryAuth) -> Self {
+        Pusher {
+            reference: reference.clone(),
+            http: RegistryHttp::for_push(reference, auth),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Set the size of the chunks of the blob uploads.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Check whether the blob of `digest` is in the repository already.
+    pub async fn blob_exists(&self, digest: &str) -> Result<bool> {
+        let url = self.http.url(&format!("blobs/{digest}"));
+        let response = self
+            .http
+            .send(Method::HEAD, &url, HeaderMap::new(), None)
+            .await?;
+        match response.status() {
+            StatusCode::OK => Ok(true),
+            StatusCode::NOT_FOUND => Ok(false),
+            status => bail!("failed to check blob {digest}: {status}"),
+        }
+    }
+
+    /// Push the blob of `digest` read from `reader`, unless it is in the
+    /// repository already.
+    pub async fn push_blob(&self, reader: impl AsyncRead + Unpin, digest: &str) -> Result<()> {
+        if self.blob_exists(digest).await? {
+            return Ok(());
+        }
+
+        let hasher = hasher(digest)?;
+        let url = self.http.url("blobs/uploads/");
+        let response = self
+            .http
+            .send(Method::POST, &url, HeaderMap::new(), Some(Vec::new()))
+            .await?;
+        let mut location = self.location(response, StatusCode::ACCEPTED).await?;
+
+        let result = self
+            .upload_blob(reader, hasher, &mut location, digest)
+            .await;
+        if result.is_err() {
+            // Cancel the upload, which expires anyway if this fails.
+            if let Err(e) = self
+                .http
+                .send(Method::DELETE, &location, HeaderMap::new(), None)
+                .await
+            {
+                warn!("failed to cancel upload of blob {digest}: {e}");
+            }
+        }
+
+        result.map_err(|e| anyhow!("failed to push blob {digest}: {e}"))
+    }
+
+    async fn upload_blob(
+        &self,
+        mut reader: impl AsyncRead + Unpin,
+        mut hasher: LayerDigestHasher,
+        location: &mut String,
+        digest: &str,
+    ) -> Result<()> {
+        let mut offset = 0;
+        loop {
+            let mut chunk = Vec::with_capacity(self.chunk_size);
+            (&mut reader)
+                .take(self.chunk_size as u64)
+                .read_to_end(&mut chunk)
+                .await?;
+            if chunk.is_empty() {
+                break;
+            }
+
+            hasher.digest_update(&chunk);
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            );
+            headers.insert(CONTENT_RANGE, content_range(offset, chunk.len()).parse()?);
+            offset += chunk.len();
+
+            let response = self
+                .http
+                .send(Method::PATCH, location, headers, Some(chunk))
+                .await?;
+            *location = self.location(response, StatusCode::ACCEPTED).await?;
+        }
+
+        let actual = hasher.digest_finalize();
+        if actual != digest {
+            bail!("digest mismatch, got {actual}");
+        }
+
+        let mut url = url::Url::parse(location)?;
+        url.query_pairs_mut().append_pair("digest", digest);
+        let response = self
+            .http
+            .send(
+                Method::PUT,
+                url.as_str(),
+                HeaderMap::new(),
+                Some(Vec::new()),
+            )
+            .await?;
+        check(response, StatusCode::CREATED).await?;
+
+        Ok(())
+    }
+
+    /// Push `manifest` to the tag of the reference, or to its digest if
+    /// the reference is by digest. Return the digest of the manifest.
+    pub async fn push_manifest(&self, manifest: &OciImageManifest) -> Result<String> {
+        let data = serde_json::to_vec(manifest)?;
+        let digest = format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(&data));
+        let target = match (self.reference.digest(), self.reference.tag()) {
+            (Some(expected), _) if expected != digest => {
+                bail!("digest of manifest {digest} mismatches the reference {expected}")
+            }
+            (Some(expected), _) => expected,
+            (None, Some(tag)) => tag,
+            (None, None) => "latest",
+        };
+
+        let media_type = manifest
+            .media_type
+            .as_deref()
+            .unwrap_or(OCI_IMAGE_MANIFEST_MEDIA_TYPE);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, media_type.parse()?);
+        let url = self.http.url(&format!("manifests/{target}"));
+        let response = self
+            .http
+            .send(Method::PUT, &url, headers, Some(data))
+            .await?;
+        check(response, StatusCode::CREATED)
+            .await
+            .map_err(|e| anyhow!("failed to push manifest {digest}: {e}"))?;
+
+        Ok(digest)
+    }
+
+    /// Push the image of `manifest`, with the blob of the config and the
+    /// layers read from `layers`, in the order of the layers of the manifest.
+    /// Return the digest of the manifest.
+    pub async fn push_image<R: AsyncRead + Unpin>(
+        &self,
+        manifest: &OciImageManifest,
+        config: &[u8],
+        layers: Vec<R>,
+    ) -> Result<String> {
+        if layers.len() != manifest.layers.len() {
+            bail!(
+                "{} layers given for {} layers of manifest",
+                layers.len(),
+                manifest.layers.len()
+            );
+        }
+
+        self.push_blob(config, &manifest.config.digest).await?;
+        for (layer, reader) in manifest.layers.iter().zip(layers) {
+            self.push_blob(reader, &layer.digest).await?;
+        }
+
+        self.push_manifest(manifest).await
+    }
+
+    /// Get the URL of the upload session from the `Location` of `response`.
+    async fn location(&self, response: Response, expected: StatusCode) -> Result<String> {
+        let response = check(response, expected).await?;
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| anyhow!("no location of upload"))?;
+        self.http.location(location)
+    }
+}
+
+/// Check the status of `response`, with the error of the registry in the
+/// body if unexpected.
+async fn check(response: Response, expected: StatusCode) -> Result<Response> {
+    let status = response.status();
+    if status != expected {
+        let body = response.text().await.unwrap_or_default();
+        bail!("unexpected status {status}: {body}");
+    }
+
+    Ok(response)
+}
+
+fn hasher(digest: &str) -> Result<LayerDigestHasher> {
+    if digest.starts_with(DIGEST_SHA256_PREFIX) {
+        Ok(LayerDigestHasher::Sha256(sha2::Sha256::new()))
+    } else if digest.starts_with(DIGEST_SHA512_PREFIX) {
+        Ok(LayerDigestHasher::Sha512(sha2::Sha512::new()))
+    } else {
+        bail!("unsupported digest {digest:?}")
+    }
+}
+
+/// Get the `Content-Range` of a chunk of `len` bytes at `offset`, whose end
+/// is inclusive.
+fn content_range(offset: usize, len: usize) -> String {
+    format!("{}-{}", offset, offset + len - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(content_range(0, 1), "0-0");
+        assert_eq!(content_range(0, 1024), "0-1023");
+        assert_eq!(content_range(1024, 10), "1024-1033");
+    }
+
+    #[test]
+    fn test_hasher() {
+        let mut hasher =
+            hasher("sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                .unwrap();
+        hasher.digest_update(b"");
+        assert_eq!(
+            hasher.digest_finalize(),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(super::hasher("sha512:1234").is_ok());
+        assert!(super::hasher("md5:1234").is_err());
+    }
+}
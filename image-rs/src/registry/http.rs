@@ -0,0 +1,262 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Requests to the registry API beyond those of the registry client, like
+//! the ranges of the blobs, the referrers and the uploads.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use oci_distribution::{secrets::RegistryAuth, Reference};
+use reqwest::header::{HeaderMap, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Method, Response, StatusCode};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    token: Option<String>,
+    access_token: Option<String>,
+}
+
+/// HTTP client of a repository in a registry.
+///
+/// It authenticates on demand following the `WWW-Authenticate` challenge of
+/// the registry, so that expired tokens are renewed.
+pub struct RegistryHttp {
+    client: reqwest::Client,
+    registry: String,
+    repository: String,
+    auth: RegistryAuth,
+    actions: &'static str,
+    authorization: Mutex<Option<String>>,
+}
+
+impl RegistryHttp {
+    pub fn new(reference: &Reference, auth: RegistryAuth) -> Self {
+        RegistryHttp {
+            client: client(),
+            registry: reference.resolve_registry().to_string(),
+            repository: reference.repository().to_string(),
+            auth,
+            actions: "pull",
+            authorization: Mutex::new(None),
+        }
+    }
+
+    /// Create a client authenticating to both pull and push, if the
+    /// registry does not tell the scope.
+    pub fn for_push(reference: &Reference, auth: RegistryAuth) -> Self {
+        RegistryHttp {
+            actions: "pull,push",
+            ..RegistryHttp::new(reference, auth)
+        }
+    }
+
+    /// Get the URL of `path` under the repository, like `blobs/<digest>`.
+    pub fn url(&self, path: &str) -> String {
+        format!("https://{}/v2/{}/{}", self.registry, self.repository, path)
+    }
+
+    /// Get the URL of a `Location` header, which may be relative to the
+    /// registry, like `/v2/<repository>/blobs/uploads/<uuid>`.
+    pub fn location(&self, location: &str) -> Result<String> {
+        let base = url::Url::parse(&format!("https://{}/", self.registry))?;
+        Ok(base.join(location)?.to_string())
+    }
+
+    /// Send a GET request of `url` with `headers`, authenticating if the
+    /// registry requires it.
+    pub async fn get(&self, url: &str, headers: HeaderMap) -> Result<Response> {
+        self.send(Method::GET, url, headers, None).await
+    }
+
+    /// Send a request of `method` to `url` with `headers` and `body`,
+    /// authenticating if the registry requires it.
+    pub async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        body: Option<Vec<u8>>,
+    ) -> Result<Response> {
+        let mut response = self
+            .request(method.clone(), url, headers.clone(), body.clone())
+            .send()
+            .await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let challenge = response
+                .headers()
+                .get(WWW_AUTHENTICATE)
+                .and_then(|challenge| challenge.to_str().ok())
+                .ok_or_else(|| anyhow!("unauthorized without challenge"))?
+                .to_string();
+            let authorization = self.authenticate(&challenge).await?;
+            *self.authorization.lock().expect("lock poisoned") = Some(authorization);
+            response = self.request(method, url, headers, body).send().await?;
+        }
+
+        Ok(response)
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        url: &str,
+        headers: HeaderMap,
+        body: Option<Vec<u8>>,
+    ) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(method, url).headers(headers);
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+        match self.authorization.lock().expect("lock poisoned").as_ref() {
+            Some(authorization) => request.header(AUTHORIZATION, authorization),
+            None => request,
+        }
+    }
+
+    /// Get the `Authorization` header answering the challenge.
+    async fn authenticate(&self, challenge: &str) -> Result<String> {
+        let (scheme, params) = parse_challenge(challenge);
+        let basic = match &self.auth {
+            RegistryAuth::Basic(username, password) => Some((username, password)),
+            _ => None,
+        };
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            let (username, password) =
+                basic.ok_or_else(|| anyhow!("registry requires basic auth"))?;
+            let credential =
+                base64::engine::general_purpose::STANDARD.encode(format!("{username}:{password}"));
+            return Ok(format!("Basic {credential}"));
+        }
+
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            bail!("unsupported auth scheme {scheme}");
+        }
+
+        let realm = params
+            .get("realm")
+            .ok_or_else(|| anyhow!("no realm in challenge {challenge:?}"))?;
+        let default_scope = format!("repository:{}:{}", self.repository, self.actions);
+        let mut query = vec![(
+            "scope",
+            params.get("scope").unwrap_or(&default_scope).as_str(),
+        )];
+        if let Some(service) = params.get("service") {
+            query.push(("service", service.as_str()));
+        }
+
+        let mut request = self.client.get(realm).query(&query);
+        if let Some((username, password)) = basic {
+            request = request.basic_auth(username, Some(password));
+        }
+
+        let response: TokenResponse = request.send().await?.error_for_status()?.json().await?;
+        let token = response
+            .token
+            .or(response.access_token)
+            .ok_or_else(|| anyhow!("no token from {realm}"))?;
+
+        Ok(format!("Bearer {token}"))
+    }
+}
+
+/// Get an HTTP client resolving the hosts by the in-process resolver, if
+/// configured.
+fn client() -> reqwest::Client {
+    #[allow(unused_mut)]
+    let mut builder = reqwest::Client::builder();
+    #[cfg(feature = "dns-resolver")]
+    if let Some(resolver) = crate::resolver::resolver() {
+        builder = builder.dns_resolver(std::sync::Arc::new(resolver));
+    }
+
+    // Like `reqwest::Client::new()`, it only fails if the TLS backend
+    // cannot be initialized.
+    builder.build().expect("failed to create HTTP client")
+}
+
+/// Parse a `WWW-Authenticate` challenge like
+/// `Bearer realm="https://auth.io/token",service="registry.io"`.
+fn parse_challenge(challenge: &str) -> (String, HashMap<String, String>) {
+    let (scheme, rest) = challenge
+        .trim()
+        .split_once(' ')
+        .unwrap_or((challenge.trim(), ""));
+
+    let mut params = HashMap::new();
+    let mut chars = rest.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value = chars.by_ref().take_while(|c| *c != ',').collect();
+        }
+
+        params.insert(key.trim().to_lowercase(), value);
+    }
+
+    (scheme.to_string(), params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenge() {
+        let (scheme, params) = parse_challenge(
+            r#"Bearer realm="https://auth.docker.io/token",service="registry.docker.io",scope="repository:library/busybox:pull,push""#,
+        );
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(params["realm"], "https://auth.docker.io/token");
+        assert_eq!(params["service"], "registry.docker.io");
+        assert_eq!(params["scope"], "repository:library/busybox:pull,push");
+
+        let (scheme, params) = parse_challenge(r#"Basic realm="Registry Realm""#);
+        assert_eq!(scheme, "Basic");
+        assert_eq!(params["realm"], "Registry Realm");
+
+        let (scheme, params) = parse_challenge("Bearer realm=https://auth.io/token, service=io");
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(params["realm"], "https://auth.io/token");
+        assert_eq!(params["service"], "io");
+    }
+
+    #[test]
+    fn test_location() {
+        let reference = Reference::try_from("registry.io:5000/library/busybox:1.36").unwrap();
+        let http = RegistryHttp::for_push(&reference, RegistryAuth::Anonymous);
+        assert_eq!(http.actions, "pull,push");
+        assert_eq!(
+            http.location("/v2/library/busybox/blobs/uploads/1234?state=abc")
+                .unwrap(),
+            "https://registry.io:5000/v2/library/busybox/blobs/uploads/1234?state=abc"
+        );
+        assert_eq!(
+            http.location("https://storage.io/uploads/1234").unwrap(),
+            "https://storage.io/uploads/1234"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Registry mirrors and insecure registries.
+//!
+//! Like the `hosts.toml` of containerd, each registry may be configured with
+//! a list of mirrors, tried in order before the registry itself, so that
+//! the pulls are redirected to internal mirrors without changing the image
+//! references.
+//!
+//! The registries and the mirrors may be IPv6 literals, like
+//! `[fd00::1]:5000`.
+
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+
+use anyhow::{anyhow, bail, Result};
+use oci_distribution::client::{ClientConfig, ClientProtocol};
+use oci_distribution::{secrets::RegistryAuth, Reference};
+
+use crate::config::RegistryConfig;
+
+#[cfg(feature = "reqwest")]
+pub mod http;
+
+/// Placeholder registry of the references with IPv6 literals.
+const PLACEHOLDER_REGISTRY: &str = "registry.invalid";
+
+/// A place to pull an image from, either a mirror or the registry of the
+/// image itself.
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    /// The image reference on this endpoint.
+    pub reference: Reference,
+
+    /// Whether the endpoint is a mirror.
+    pub mirror: bool,
+
+    /// The auth of the mirror, if configured.
+    pub auth: Option<RegistryAuth>,
+
+    /// Pull by plain HTTP.
+    pub insecure: bool,
+
+    /// Skip the TLS certificate verification.
+    pub skip_verify: bool,
+}
+
+impl Endpoint {
+    /// Get the config of the registry client to pull from the endpoint.
+    pub fn client_config(&self) -> ClientConfig {
+        let protocol = if self.insecure {
+            ClientProtocol::Http
+        } else {
+            ClientProtocol::Https
+        };
+
+        ClientConfig {
+            protocol,
+            accept_invalid_certificates: self.skip_verify,
+            ..Default::default()
+        }
+    }
+}
+
+/// Get the endpoints to try in order to pull `reference`.
+pub fn endpoints(
+    registries: &HashMap<String, RegistryConfig>,
+    reference: &Reference,
+) -> Result<Vec<Endpoint>> {
+    let Some(config) = registries.get(reference.registry()) else {
+        return Ok(vec![Endpoint {
+            reference: reference.clone(),
+            mirror: false,
+            auth: None,
+            insecure: false,
+            skip_verify: false,
+        }]);
+    };
+
+    let mut endpoints = Vec::new();
+    for mirror in &config.mirrors {
+        let auth = match &mirror.auth {
+            Some(auth) => {
+                let (username, password) = auth
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("invalid auth of mirror {}", mirror.host))?;
+                Some(RegistryAuth::Basic(
+                    username.to_string(),
+                    password.to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        endpoints.push(Endpoint {
+            reference: mirror_reference(&mirror.host, reference)?,
+            mirror: true,
+            auth,
+            insecure: mirror.insecure,
+            skip_verify: mirror.skip_verify,
+        });
+    }
+
+    if !config.mirror_only {
+        endpoints.push(Endpoint {
+            reference: reference.clone(),
+            mirror: false,
+            auth: None,
+            insecure: config.insecure,
+            skip_verify: config.skip_verify,
+        });
+    }
+
+    Ok(endpoints)
+}
+
+/// Get the reference of the image on the mirror at `host`, which may have
+/// a path prefixed to the repository, like `harbor.local/docker-proxy`.
+fn mirror_reference(host: &str, reference: &Reference) -> Result<Reference> {
+    let (registry, repository) = match host.trim_end_matches('/').split_once('/') {
+        Some((registry, prefix)) => (registry, format!("{prefix}/{}", reference.repository())),
+        None => (host, reference.repository().to_string()),
+    };
+
+    if registry.is_empty() {
+        bail!("invalid mirror host {:?}", host);
+    }
+
+    Ok(with_registry(registry, repository, reference))
+}
+
+/// Parse an image reference, whose registry may be an IPv6 literal like
+/// `[fd00::1]:5000/busybox:1.36`, which the registry client does not parse.
+pub fn parse_reference(image: &str) -> Result<Reference> {
+    let Some(rest) = image.strip_prefix('[') else {
+        return Ok(Reference::try_from(image)?);
+    };
+
+    let (address, rest) = rest
+        .split_once(']')
+        .ok_or_else(|| anyhow!("invalid IPv6 registry of {:?}", image))?;
+    address
+        .parse::<Ipv6Addr>()
+        .map_err(|e| anyhow!("invalid IPv6 registry of {:?} {e}", image))?;
+
+    let (port, path) = rest
+        .split_once('/')
+        .ok_or_else(|| anyhow!("no repository in {:?}", image))?;
+    if !port.is_empty()
+        && port
+            .strip_prefix(':')
+            .and_then(|port| port.parse::<u16>().ok())
+            .is_none()
+    {
+        bail!("invalid port of registry of {:?}", image);
+    }
+
+    // The rest is parsed with a placeholder registry, which is not a
+    // registry of the defaults like `docker.io`.
+    let placeholder = Reference::try_from(format!("{PLACEHOLDER_REGISTRY}/{path}").as_str())?;
+    Ok(with_registry(
+        &format!("[{address}]{port}"),
+        placeholder.repository().to_string(),
+        &placeholder,
+    ))
+}
+
+/// Get the reference of the image of `reference` in another `registry` and
+/// `repository`.
+fn with_registry(registry: &str, repository: String, reference: &Reference) -> Reference {
+    match (reference.digest(), reference.tag()) {
+        (Some(digest), _) => {
+            Reference::with_digest(registry.to_string(), repository, digest.to_string())
+        }
+        (None, Some(tag)) => Reference::with_tag(registry.to_string(), repository, tag.to_string()),
+        (None, None) => Reference::with_tag(registry.to_string(), repository, "latest".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MirrorConfig;
+
+    #[test]
+    fn test_endpoints() {
+        let reference = Reference::try_from("busybox:1.36").unwrap();
+        let endpoints = endpoints(&HashMap::new(), &reference).unwrap();
+        assert_eq!(endpoints.len(), 1);
+        assert!(!endpoints[0].mirror);
+        assert_eq!(endpoints[0].reference, reference);
+
+        let mut registries = HashMap::new();
+        registries.insert(
+            "docker.io".to_string(),
+            RegistryConfig {
+                mirrors: vec![
+                    MirrorConfig {
+                        host: "mirror.local:5000".to_string(),
+                        insecure: true,
+                        auth: Some("user:pass".to_string()),
+                        ..Default::default()
+                    },
+                    MirrorConfig {
+                        host: "harbor.local/docker-proxy".to_string(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        let endpoints = super::endpoints(&registries, &reference).unwrap();
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(
+            endpoints[0].reference.whole(),
+            "mirror.local:5000/library/busybox:1.36"
+        );
+        assert!(endpoints[0].insecure);
+        assert!(matches!(
+            &endpoints[0].auth,
+            Some(RegistryAuth::Basic(username, password)) if username == "user" && password == "pass"
+        ));
+        assert_eq!(
+            endpoints[1].reference.whole(),
+            "harbor.local/docker-proxy/library/busybox:1.36"
+        );
+        assert!(!endpoints[1].insecure);
+        assert_eq!(endpoints[2].reference, reference);
+        assert!(!endpoints[2].mirror);
+
+        registries.get_mut("docker.io").unwrap().mirror_only = true;
+        let endpoints = super::endpoints(&registries, &reference).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert!(endpoints.iter().all(|endpoint| endpoint.mirror));
+
+        let reference = Reference::try_from(
+            "busybox@sha256:7b3ccabffc97de872a30dfd234fd972a66d247c8cfc69b0550f276481852627c",
+        )
+        .unwrap();
+        let endpoints = super::endpoints(&registries, &reference).unwrap();
+        assert_eq!(endpoints[0].reference.digest(), reference.digest());
+    }
+
+    #[test]
+    fn test_parse_reference() {
+        let reference = parse_reference("[fd00::1]:5000/library/busybox:1.36").unwrap();
+        assert_eq!(reference.registry(), "[fd00::1]:5000");
+        assert_eq!(reference.repository(), "library/busybox");
+        assert_eq!(reference.tag(), Some("1.36"));
+        assert_eq!(reference.whole(), "[fd00::1]:5000/library/busybox:1.36");
+
+        let reference = parse_reference(
+            "[fd00::1]/busybox@sha256:7b3ccabffc97de872a30dfd234fd972a66d247c8cfc69b0550f276481852627c",
+        )
+        .unwrap();
+        assert_eq!(reference.registry(), "[fd00::1]");
+        assert_eq!(reference.repository(), "busybox");
+        assert_eq!(
+            reference.digest(),
+            Some("sha256:7b3ccabffc97de872a30dfd234fd972a66d247c8cfc69b0550f276481852627c")
+        );
+
+        let reference = parse_reference("[fd00::1]:5000/busybox").unwrap();
+        assert_eq!(reference.tag(), Some("latest"));
+
+        let reference = parse_reference("busybox:1.36").unwrap();
+        assert_eq!(reference.whole(), "docker.io/library/busybox:1.36");
+
+        for image in [
+            "[fd00::1:5000/busybox",
+            "[fd00::g]:5000/busybox",
+            "[fd00::1]:port/busybox",
+            "[fd00::1]:5000",
+        ] {
+            assert!(parse_reference(image).is_err(), "{image}");
+        }
+
+        // Mirrors may be IPv6 literals too.
+        let mut registries = HashMap::new();
+        registries.insert(
+            "[fd00::1]:5000".to_string(),
+            RegistryConfig {
+                mirrors: vec![MirrorConfig {
+                    host: "[fd00::2]:5000/proxy".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+        let reference = parse_reference("[fd00::1]:5000/busybox:1.36").unwrap();
+        let endpoints = endpoints(&registries, &reference).unwrap();
+        assert_eq!(
+            endpoints[0].reference.whole(),
+            "[fd00::2]:5000/proxy/busybox:1.36"
+        );
+        assert_eq!(endpoints[1].reference, reference);
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The DNS messages of the queries of the addresses and their answers,
+//! refer to <https://www.rfc-editor.org/rfc/rfc1035#section-4>.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::{anyhow, bail, Result};
+
+/// Types of the records of the IPv4 and IPv6 addresses.
+pub const TYPE_A: u16 = 1;
+pub const TYPE_AAAA: u16 = 28;
+
+const TYPE_CNAME: u16 = 5;
+const CLASS_IN: u16 = 1;
+
+const HEADER_SIZE: usize = 12;
+
+/// Max number of the aliases of a name followed.
+const MAX_ALIASES: usize = 8;
+
+/// Max number of the compression pointers in a name.
+const MAX_POINTERS: usize = 16;
+
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// The addresses of a name in an answer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Answer {
+    pub addrs: Vec<IpAddr>,
+
+    /// The least TTL in seconds of the records of the addresses.
+    pub ttl: u32,
+}
+
+/// Encode a query of the records of `qtype` of `name`, with recursion
+/// desired.
+pub fn query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(HEADER_SIZE + name.len() + 6);
+    message.extend(id.to_be_bytes());
+    // RD, and one question.
+    message.extend([0x01, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0]);
+
+    let name = name.trim_end_matches('.');
+    if name.is_empty() || name.len() > 253 {
+        bail!("invalid DNS name {:?}", name);
+    }
+
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("invalid DNS name {:?}", name);
+        }
+        message.push(label.len() as u8);
+        message.extend(label.as_bytes());
+    }
+    message.push(0);
+
+    message.extend(qtype.to_be_bytes());
+    message.extend(CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+/// Parse the answer of the query `id` of the records of `qtype` of `name`,
+/// following the aliases of the name in the answer.
+pub fn parse_answer(id: u16, name: &str, qtype: u16, message: &[u8]) -> Result<Answer> {
+    let mut parser = Parser { message, offset: 0 };
+    if parser.u16()? != id {
+        bail!("unexpected DNS message id");
+    }
+
+    let flags = parser.u16()?;
+    if flags & 0x8000 == 0 {
+        bail!("DNS message is not a response");
+    }
+    if flags & 0x0200 != 0 {
+        bail!("DNS response is truncated");
+    }
+    match flags & 0x000f {
+        0 => {}
+        RCODE_NXDOMAIN => bail!("no such host {}", name),
+        rcode => bail!("DNS query of {} failed with rcode {}", name, rcode),
+    }
+
+    let questions = parser.u16()?;
+    let answers = parser.u16()?;
+    // The authority and additional records are skipped.
+    parser.skip(4)?;
+
+    for _ in 0..questions {
+        parser.name()?;
+        parser.skip(4)?;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let owner = parser.name()?;
+        let rtype = parser.u16()?;
+        let class = parser.u16()?;
+        let ttl = parser.u32()?;
+        let len = parser.u16()? as usize;
+        let rdata_offset = parser.offset;
+        let rdata = parser.bytes(len)?;
+        if class != CLASS_IN {
+            continue;
+        }
+
+        let data = match rtype {
+            t if t == qtype && t == TYPE_A => {
+                let octets: [u8; 4] = rdata
+                    .try_into()
+                    .map_err(|_| anyhow!("invalid A record of {}", owner))?;
+                Record::Addr(IpAddr::V4(Ipv4Addr::from(octets)))
+            }
+            t if t == qtype && t == TYPE_AAAA => {
+                let octets: [u8; 16] = rdata
+                    .try_into()
+                    .map_err(|_| anyhow!("invalid AAAA record of {}", owner))?;
+                Record::Addr(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            TYPE_CNAME => {
+                let mut rdata = Parser {
+                    message,
+                    offset: rdata_offset,
+                };
+                Record::Alias(rdata.name()?)
+            }
+            _ => continue,
+        };
+        records.push((owner, ttl, data));
+    }
+
+    // Only the records of the name and of its aliases are trusted.
+    let mut names = vec![name.trim_end_matches('.').to_ascii_lowercase()];
+    for _ in 0..MAX_ALIASES {
+        let alias = records.iter().find_map(|(owner, _, data)| match data {
+            Record::Alias(alias) if names.contains(owner) && !names.contains(alias) => {
+                Some(alias.clone())
+            }
+            _ => None,
+        });
+        match alias {
+            Some(alias) => names.push(alias),
+            None => break,
+        }
+    }
+
+    let mut answer = Answer {
+        addrs: Vec::new(),
+        ttl: u32::MAX,
+    };
+    for (owner, ttl, data) in &records {
+        if !names.contains(owner) {
+            continue;
+        }
+        answer.ttl = answer.ttl.min(*ttl);
+        if let Record::Addr(addr) = data {
+            answer.addrs.push(*addr);
+        }
+    }
+
+    if answer.addrs.is_empty() {
+        answer.ttl = 0;
+    }
+
+    Ok(answer)
+}
+
+enum Record {
+    Addr(IpAddr),
+    Alias(String),
+}
+
+struct Parser<'a> {
+    message: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .message
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| anyhow!("truncated DNS message"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Parse a name in lowercase, which may be compressed.
+    fn name(&mut self) -> Result<String> {
+        let mut labels: Vec<String> = Vec::new();
+        // The offset after the name, once a pointer is followed.
+        let mut end = None;
+        let mut pointers = 0;
+        loop {
+            let len = self.u8()?;
+            match len {
+                0 => break,
+                len if len & 0xc0 == 0xc0 => {
+                    let target = ((len as usize & 0x3f) << 8) | self.u8()? as usize;
+                    pointers += 1;
+                    if pointers > MAX_POINTERS || target >= self.message.len() {
+                        bail!("invalid DNS name compression");
+                    }
+                    end.get_or_insert(self.offset);
+                    self.offset = target;
+                }
+                len if len & 0xc0 == 0 => {
+                    let label = self.bytes(len as usize)?;
+                    labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+                }
+                _ => bail!("invalid DNS label type"),
+            }
+        }
+
+        if let Some(end) = end {
+            self.offset = end;
+        }
+
+        Ok(labels.join("."))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode an answer of the query `id`, whose answer section is `answers`
+    /// of (owner pointer, type, ttl, rdata).
+    fn answer(id: u16, name: &str, qtype: u16, answers: &[(u16, u16, u32, Vec<u8>)]) -> Vec<u8> {
+        let mut message = query(id, name, qtype).unwrap();
+        // QR, RD and RA, and the answer count.
+        message[2..4].copy_from_slice(&[0x81, 0x80]);
+        message[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for (owner, rtype, ttl, rdata) in answers {
+            message.extend(owner.to_be_bytes());
+            message.extend(rtype.to_be_bytes());
+            message.extend(CLASS_IN.to_be_bytes());
+            message.extend(ttl.to_be_bytes());
+            message.extend((rdata.len() as u16).to_be_bytes());
+            message.extend(rdata);
+        }
+        message
+    }
+
+    #[test]
+    fn test_query() {
+        let message = query(0xabcd, "ghcr.io.", TYPE_AAAA).unwrap();
+        assert_eq!(
+            message,
+            [
+                0xab, 0xcd, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 4, b'g',
+                b'h', b'c', b'r', 2, b'i', b'o', 0, 0x00, 0x1c, 0x00, 0x01
+            ]
+        );
+
+        assert!(query(0, "", TYPE_A).is_err());
+        assert!(query(0, "a..io", TYPE_A).is_err());
+        assert!(query(0, &format!("{}.io", "a".repeat(64)), TYPE_A).is_err());
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        // The name in the question is at offset 12.
+        let message = answer(
+            1,
+            "ghcr.io",
+            TYPE_AAAA,
+            &[
+                (
+                    0xc00c,
+                    TYPE_AAAA,
+                    300,
+                    Ipv6Addr::LOCALHOST.octets().to_vec(),
+                ),
+                (
+                    0xc00c,
+                    TYPE_AAAA,
+                    60,
+                    "fd00::1".parse::<Ipv6Addr>().unwrap().octets().to_vec(),
+                ),
+            ],
+        );
+        let parsed = parse_answer(1, "ghcr.io", TYPE_AAAA, &message).unwrap();
+        assert_eq!(
+            parsed,
+            Answer {
+                addrs: vec![IpAddr::V6(Ipv6Addr::LOCALHOST), "fd00::1".parse().unwrap()],
+                ttl: 60,
+            }
+        );
+
+        assert!(parse_answer(2, "ghcr.io", TYPE_AAAA, &message).is_err());
+        assert!(parse_answer(1, "ghcr.io", TYPE_AAAA, &message[..message.len() - 1]).is_err());
+
+        // The records of other types or of other names are skipped.
+        let parsed = parse_answer(1, "ghcr.io", TYPE_A, &message).unwrap();
+        assert!(parsed.addrs.is_empty());
+        let parsed = parse_answer(1, "quay.io", TYPE_AAAA, &message).unwrap();
+        assert!(parsed.addrs.is_empty());
+    }
+
+    #[test]
+    fn test_parse_answer_alias() {
+        // ghcr.io is an alias of cdn.ghcr.io, encoded by a label and a
+        // pointer to ghcr.io in the question.
+        let mut alias = vec![3, b'c', b'd', b'n'];
+        alias.extend(0xc00c_u16.to_be_bytes());
+        let mut message = answer(1, "ghcr.io", TYPE_A, &[(0xc00c, TYPE_CNAME, 3600, alias)]);
+        // The alias follows the header, the question and the fields of the
+        // first record, and the A record of it is appended.
+        let alias_offset = 12 + 9 + 4 + 12;
+        message[6..8].copy_from_slice(&2_u16.to_be_bytes());
+        message.extend((0xc000 | alias_offset as u16).to_be_bytes());
+        message.extend(TYPE_A.to_be_bytes());
+        message.extend(CLASS_IN.to_be_bytes());
+        message.extend(120_u32.to_be_bytes());
+        message.extend(4_u16.to_be_bytes());
+        message.extend([192, 0, 2, 1]);
+
+        let parsed = parse_answer(1, "GHCR.io", TYPE_A, &message).unwrap();
+        assert_eq!(
+            parsed,
+            Answer {
+                addrs: vec!["192.0.2.1".parse().unwrap()],
+                ttl: 120,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_answer_error() {
+        let mut message = answer(1, "ghcr.io", TYPE_A, &[]);
+        message[3] |= RCODE_NXDOMAIN as u8;
+        assert!(parse_answer(1, "ghcr.io", TYPE_A, &message)
+            .unwrap_err()
+            .to_string()
+            .contains("no such host"));
+
+        // A query is not an answer.
+        let message = query(1, "ghcr.io", TYPE_A).unwrap();
+        assert!(parse_answer(1, "ghcr.io", TYPE_A, &message).is_err());
+
+        // A looping compression pointer.
+        let mut message = answer(1, "ghcr.io", TYPE_A, &[]);
+        message[6..8].copy_from_slice(&1_u16.to_be_bytes());
+        let offset = message.len() as u16;
+        message.extend((0xc000 | offset).to_be_bytes());
+        assert!(parse_answer(1, "ghcr.io", TYPE_A, &message).is_err());
+    }
+}
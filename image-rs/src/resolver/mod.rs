@@ -0,0 +1,322 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-process DNS resolver of the registry hosts, by DNS-over-HTTPS and
+//! DNS-over-TLS servers, so that the DNS provided by the host is not
+//! trusted.
+//!
+//! Both the IPv6 and the IPv4 addresses of a host are resolved, the IPv6
+//! ones first, so that the connections race between them as of happy
+//! eyeballs and succeed on IPv6-only networks.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use log::{debug, warn};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+
+use crate::config::DnsConfig;
+
+pub mod message;
+
+use message::{Answer, TYPE_A, TYPE_AAAA};
+
+/// Media type of the DNS messages of DNS-over-HTTPS.
+const DNS_MESSAGE_MEDIA_TYPE: &str = "application/dns-message";
+
+/// Max size of a DNS message, as its length is encoded in two bytes.
+const MAX_MESSAGE_SIZE: usize = u16::MAX as usize;
+
+/// Timeout of a query to a server.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Max time the addresses are cached, whatever their TTL.
+const MAX_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// The resolver used by the HTTP clients of the registries, if configured.
+static RESOLVER: RwLock<Option<Resolver>> = RwLock::new(None);
+
+/// Set the resolver used by the HTTP clients of the registries created
+/// from now on, or unset it to use the system resolver.
+pub fn set_resolver(resolver: Option<Resolver>) {
+    *RESOLVER.write().expect("lock poisoned") = resolver;
+}
+
+/// Get the resolver of the HTTP clients of the registries, if any.
+pub fn resolver() -> Option<Resolver> {
+    RESOLVER.read().expect("lock poisoned").clone()
+}
+
+enum Server {
+    Https(String),
+    Tls {
+        address: SocketAddr,
+        name: ServerName,
+    },
+}
+
+impl std::fmt::Display for Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Server::Https(url) => write!(f, "{url}"),
+            Server::Tls { address, .. } => write!(f, "tls://{address}"),
+        }
+    }
+}
+
+struct CacheEntry {
+    addrs: Vec<IpAddr>,
+    expires: Instant,
+}
+
+struct Inner {
+    servers: Vec<Server>,
+    http: reqwest::Client,
+    tls: TlsConnector,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+}
+
+/// DNS resolver querying the configured servers in order, until one of
+/// them answers.
+#[derive(Clone)]
+pub struct Resolver {
+    inner: Arc<Inner>,
+}
+
+impl Resolver {
+    pub fn new(config: &DnsConfig) -> Result<Self> {
+        let mut servers = Vec::new();
+        for url in &config.doh {
+            let parsed = url::Url::parse(url).map_err(|e| anyhow!("invalid DoH url {url} {e}"))?;
+            if parsed.scheme() != "https" {
+                bail!("DoH url {} is not https", url);
+            }
+            servers.push(Server::Https(url.clone()));
+        }
+
+        for server in &config.dot {
+            let address = server.socket_addr()?;
+            let name = ServerName::try_from(server.tls_name.as_str())
+                .map_err(|e| anyhow!("invalid DoT name {} {e}", server.tls_name))?;
+            servers.push(Server::Tls { address, name });
+        }
+
+        if servers.is_empty() {
+            bail!("no DNS server is configured");
+        }
+
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        }));
+        let tls = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        // The DoH servers are expected to be IP literals, or else they are
+        // resolved by the system resolver.
+        let http = reqwest::Client::builder()
+            .timeout(QUERY_TIMEOUT)
+            .build()
+            .map_err(|e| anyhow!("failed to create DoH client {e}"))?;
+
+        Ok(Resolver {
+            inner: Arc::new(Inner {
+                servers,
+                http,
+                tls: TlsConnector::from(Arc::new(tls)),
+                cache: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Look up the addresses of `host`, the IPv6 ones first.
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if let Ok(addr) = host.parse::<IpAddr>() {
+            return Ok(vec![addr]);
+        }
+
+        if let Some(entry) = self.inner.cache.lock().expect("lock poisoned").get(&host) {
+            if entry.expires > Instant::now() {
+                return Ok(entry.addrs.clone());
+            }
+        }
+
+        let mut last_error = anyhow!("no DNS server is configured");
+        for server in &self.inner.servers {
+            match self.lookup_by(server, &host).await {
+                Ok((addrs, ttl)) => {
+                    let expires = Instant::now() + ttl.min(MAX_CACHE_TTL);
+                    self.inner.cache.lock().expect("lock poisoned").insert(
+                        host,
+                        CacheEntry {
+                            addrs: addrs.clone(),
+                            expires,
+                        },
+                    );
+                    return Ok(addrs);
+                }
+                Err(e) => {
+                    warn!("failed to resolve {} by {}: {:?}", host, server, e);
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Look up both the AAAA and the A records of `host` by `server`. It
+    /// succeeds if either of the queries succeeds.
+    async fn lookup_by(&self, server: &Server, host: &str) -> Result<(Vec<IpAddr>, Duration)> {
+        let (v6, v4) = futures_util::join!(
+            self.query(server, host, TYPE_AAAA),
+            self.query(server, host, TYPE_A)
+        );
+
+        let answers: Vec<Answer> = match (v6, v4) {
+            (Err(e), Err(_)) => return Err(e),
+            (v6, v4) => {
+                if let Err(e) = v6.as_ref().and(v4.as_ref()) {
+                    debug!("partial answer of {} by {}: {:?}", host, server, e);
+                }
+                v6.into_iter().chain(v4).collect()
+            }
+        };
+
+        let ttl = answers
+            .iter()
+            .filter(|answer| !answer.addrs.is_empty())
+            .map(|answer| answer.ttl)
+            .min()
+            .unwrap_or(0);
+        let addrs: Vec<IpAddr> = answers
+            .into_iter()
+            .flat_map(|answer| answer.addrs)
+            .collect();
+        if addrs.is_empty() {
+            bail!("no address of {}", host);
+        }
+
+        Ok((addrs, Duration::from_secs(ttl.into())))
+    }
+
+    async fn query(&self, server: &Server, host: &str, qtype: u16) -> Result<Answer> {
+        match server {
+            Server::Https(url) => {
+                // The id of DoH queries is 0, refer to RFC 8484.
+                let query = message::query(0, host, qtype)?;
+                let response = self
+                    .inner
+                    .http
+                    .post(url)
+                    .header(CONTENT_TYPE, DNS_MESSAGE_MEDIA_TYPE)
+                    .header(ACCEPT, DNS_MESSAGE_MEDIA_TYPE)
+                    .body(query)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                if response
+                    .content_length()
+                    .is_some_and(|len| len > MAX_MESSAGE_SIZE as u64)
+                {
+                    bail!("DoH response exceeds {} bytes", MAX_MESSAGE_SIZE);
+                }
+
+                let data = response.bytes().await?;
+                message::parse_answer(0, host, qtype, &data)
+            }
+            Server::Tls { address, name } => {
+                tokio::time::timeout(QUERY_TIMEOUT, self.query_tls(*address, name, host, qtype))
+                    .await
+                    .map_err(|_| anyhow!("DoT query timed out"))?
+            }
+        }
+    }
+
+    async fn query_tls(
+        &self,
+        address: SocketAddr,
+        name: &ServerName,
+        host: &str,
+        qtype: u16,
+    ) -> Result<Answer> {
+        // The id is random, so that it is not predictable by an off-path
+        // attacker sharing the host.
+        let id = rand::random::<u16>();
+        let query = message::query(id, host, qtype)?;
+
+        let stream = TcpStream::connect(address).await?;
+        let mut stream = self.inner.tls.connect(name.clone(), stream).await?;
+
+        // Each message is prefixed by its length over TCP.
+        let mut request = (query.len() as u16).to_be_bytes().to_vec();
+        request.extend(query);
+        stream.write_all(&request).await?;
+        stream.flush().await?;
+
+        let len = stream.read_u16().await? as usize;
+        let mut data = vec![0; len];
+        stream.read_exact(&mut data).await?;
+        message::parse_answer(id, host, qtype, &data)
+    }
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            // The port is set by the HTTP client.
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|addr| SocketAddr::new(addr, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DotServerConfig;
+
+    #[tokio::test]
+    async fn test_resolver() {
+        assert!(Resolver::new(&DnsConfig::default()).is_err());
+        assert!(Resolver::new(&DnsConfig {
+            doh: vec!["http://[2606:4700:4700::1111]/dns-query".to_string()],
+            ..Default::default()
+        })
+        .is_err());
+
+        let resolver = Resolver::new(&DnsConfig {
+            dot: vec![DotServerConfig {
+                address: "[2606:4700:4700::1111]".to_string(),
+                tls_name: "cloudflare-dns.com".to_string(),
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        // The literals are not resolved.
+        assert_eq!(
+            resolver.lookup("fd00::1").await.unwrap(),
+            vec!["fd00::1".parse::<IpAddr>().unwrap()]
+        );
+    }
+}
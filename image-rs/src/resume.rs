@@ -0,0 +1,448 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Downloads of the layer blobs resumed by HTTP range requests.
+//!
+//! When the connection of a download is reset, the rest of the blob is
+//! requested from the bytes already downloaded, instead of pulling the
+//! whole blob again. The bytes downloaded may also be checkpointed to disk,
+//! so that the download of the same blob in a later pull, after a failed
+//! one, resumes from its checkpoint.
+//!
+//! The resumed bytes are verified as the rest of the blob, against the
+//! diff id of the layer once unpacked.
+
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::Result;
+use futures_util::stream::{self, Stream, StreamExt};
+use log::{info, warn};
+use oci_distribution::{secrets::RegistryAuth, Reference};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_RANGE, RANGE};
+use reqwest::{Response, StatusCode};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf};
+
+use crate::registry::http::RegistryHttp;
+
+/// Size of the chunks read from the sources of a blob.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Delay before the first resume, doubled by each resume in a row.
+const RESUME_DELAY: Duration = Duration::from_millis(500);
+
+type BlobReader = Box<dyn AsyncRead + Unpin + Send>;
+
+/// Checkpoint of the bytes downloaded of a blob.
+pub struct Checkpoint {
+    path: PathBuf,
+    file: tokio::fs::File,
+    len: u64,
+}
+
+impl Checkpoint {
+    /// Open the checkpoint of the blob `digest` of `size` bytes in `dir`,
+    /// which is empty if the blob was not downloaded before.
+    pub fn open(dir: &Path, digest: &str, size: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(digest.replace(':', "_"));
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+
+        // A checkpoint longer than the blob is not of the blob.
+        let mut len = file.metadata()?.len();
+        if len > size {
+            warn!("discard checkpoint {:?} of {} bytes", path, len);
+            file.set_len(0)?;
+            len = 0;
+        }
+
+        Ok(Checkpoint {
+            path,
+            file: tokio::fs::File::from_std(file),
+            len,
+        })
+    }
+
+    /// The number of bytes checkpointed.
+    pub fn checkpointed(&self) -> u64 {
+        self.len
+    }
+}
+
+enum Source {
+    /// The bytes of the checkpoint, from the start of the blob.
+    Checkpoint,
+
+    /// The blob pulled by the registry client, from its start.
+    Blob(BlobReader),
+
+    /// The response to a range request.
+    Range(Response),
+
+    /// The rest of the blob is to be requested.
+    None,
+}
+
+/// State of the download of a blob.
+struct Download {
+    http: RegistryHttp,
+    url: String,
+    digest: String,
+    size: u64,
+
+    /// The bytes of the blob read so far.
+    offset: u64,
+
+    /// The bytes to skip from the source, if the registry ignores the range.
+    skip: u64,
+
+    source: Source,
+    checkpoint: Option<Checkpoint>,
+    resumes: u32,
+    max_resumes: u32,
+
+    /// Keep the checkpoint, as the download failed by the network.
+    keep_checkpoint: bool,
+}
+
+impl Download {
+    /// Get the next chunk of the blob, resuming the download on the errors
+    /// up to `max_resumes` times in a row.
+    async fn next(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            match self.read().await {
+                Ok(chunk) => {
+                    self.resumes = 0;
+                    return Ok(chunk);
+                }
+                // The data of the blob is wrong, which is not resumed.
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => return Err(e),
+                Err(e) if self.resumes < self.max_resumes => {
+                    self.resumes += 1;
+                    warn!(
+                        "failed to download blob {} at {} of {} bytes, resume {}/{}: {}",
+                        self.digest, self.offset, self.size, self.resumes, self.max_resumes, e
+                    );
+                    self.source = Source::None;
+                    tokio::time::sleep(RESUME_DELAY * 2u32.pow(self.resumes - 1)).await;
+                }
+                Err(e) => {
+                    if let Some(checkpoint) = self.checkpoint.as_mut() {
+                        checkpoint.file.flush().await?;
+                    }
+                    self.keep_checkpoint = true;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    async fn read(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let mut chunk = vec![0u8; CHUNK_SIZE];
+            let chunk = match &mut self.source {
+                Source::Checkpoint => {
+                    let checkpoint = self.checkpoint.as_mut().expect("no checkpoint to read");
+                    let n = checkpoint.file.read(&mut chunk).await?;
+                    if n == 0 {
+                        self.source = Source::None;
+                        continue;
+                    }
+
+                    // The checkpointed bytes are not written again.
+                    chunk.truncate(n);
+                    self.offset += n as u64;
+                    return Ok(Some(chunk));
+                }
+                Source::Blob(reader) => {
+                    let n = reader.read(&mut chunk).await?;
+                    chunk.truncate(n);
+                    (n != 0).then_some(chunk)
+                }
+                Source::Range(response) => response
+                    .chunk()
+                    .await
+                    .map_err(io::Error::other)?
+                    .map(|chunk| chunk.to_vec()),
+                Source::None if self.offset == self.size => return Ok(None),
+                Source::None => {
+                    self.source = Source::Range(self.request().await?);
+                    continue;
+                }
+            };
+
+            let Some(mut chunk) = chunk else {
+                if self.offset != self.size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("blob ends at {} of {} bytes", self.offset, self.size),
+                    ));
+                }
+
+                return Ok(None);
+            };
+
+            if self.skip > 0 {
+                let n = self.skip.min(chunk.len() as u64);
+                chunk.drain(..n as usize);
+                self.skip -= n;
+                if chunk.is_empty() {
+                    continue;
+                }
+            }
+
+            if self.offset + chunk.len() as u64 > self.size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("blob is longer than {} bytes", self.size),
+                ));
+            }
+
+            if let Some(checkpoint) = self.checkpoint.as_mut() {
+                checkpoint.file.write_all(&chunk).await?;
+            }
+
+            self.offset += chunk.len() as u64;
+            return Ok(Some(chunk));
+        }
+    }
+
+    /// Request the rest of the blob from `offset`.
+    async fn request(&mut self) -> io::Result<Response> {
+        // Drop the bytes of the checkpoint beyond the offset, e.g. those not
+        // read again after an error of the checkpoint itself.
+        if let Some(checkpoint) = self.checkpoint.as_mut() {
+            checkpoint.file.set_len(self.offset).await?;
+        }
+
+        let mut headers = HeaderMap::new();
+        let range =
+            HeaderValue::from_str(&format!("bytes={}-", self.offset)).map_err(io::Error::other)?;
+        headers.insert(RANGE, range);
+        let response = self
+            .http
+            .get(&self.url, headers)
+            .await
+            .map_err(io::Error::other)?;
+
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let start = response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|range| range.to_str().ok())
+                    .and_then(content_range_start);
+                if start != Some(self.offset) {
+                    return Err(io::Error::other(format!(
+                        "unexpected range from {start:?} of {}",
+                        self.url
+                    )));
+                }
+                self.skip = 0;
+            }
+            // The registry may ignore the range, and send the whole blob.
+            StatusCode::OK => self.skip = self.offset,
+            status => {
+                return Err(io::Error::other(format!(
+                    "failed to request {}: {}",
+                    self.url, status
+                )))
+            }
+        }
+
+        if self.offset > 0 {
+            info!(
+                "resume download of blob {} at {} of {} bytes",
+                self.digest, self.offset, self.size
+            );
+        }
+
+        Ok(response)
+    }
+}
+
+impl Drop for Download {
+    fn drop(&mut self) {
+        // The checkpoint is done with once the blob is downloaded, or once
+        // it fails to be handled, as its bytes may be wrong.
+        if let Some(checkpoint) = self.checkpoint.as_ref() {
+            if !self.keep_checkpoint {
+                if let Err(e) = fs::remove_file(&checkpoint.path) {
+                    warn!("failed to remove checkpoint {:?}: {}", checkpoint.path, e);
+                }
+            }
+        }
+    }
+}
+
+/// A blob of a layer whose download is resumed when interrupted.
+pub struct ResumableBlob {
+    chunks: Pin<Box<dyn Stream<Item = io::Result<Vec<u8>>> + Send>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl ResumableBlob {
+    /// Download the blob `digest` of `size` bytes of the image `reference`.
+    ///
+    /// `blob` is the blob pulled by the registry client, which is read from
+    /// its start unless the `checkpoint` is not empty, while the resumes
+    /// are requested by HTTPS.
+    pub fn new(
+        reference: &Reference,
+        auth: &RegistryAuth,
+        digest: &str,
+        size: u64,
+        max_resumes: u32,
+        checkpoint: Option<Checkpoint>,
+        blob: Option<BlobReader>,
+    ) -> Self {
+        let http = RegistryHttp::new(reference, auth.clone());
+        let url = http.url(&format!("blobs/{digest}"));
+        let source = match (&checkpoint, blob) {
+            (Some(checkpoint), _) if checkpoint.len > 0 => Source::Checkpoint,
+            (_, Some(blob)) => Source::Blob(blob),
+            _ => Source::None,
+        };
+
+        let download = Download {
+            http,
+            url,
+            digest: digest.to_string(),
+            size,
+            offset: 0,
+            skip: 0,
+            source,
+            checkpoint,
+            resumes: 0,
+            max_resumes,
+            keep_checkpoint: false,
+        };
+        let chunks = stream::unfold(Some(download), |download| async move {
+            let mut download = download?;
+            match download.next().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), Some(download))),
+                Ok(None) => None,
+                // Nothing is read after an error.
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+
+        ResumableBlob {
+            chunks: Box::pin(chunks),
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl AsyncRead for ResumableBlob {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.pos == self.chunk.len() {
+            match self.chunks.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e)),
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let n = buf.remaining().min(self.chunk.len() - self.pos);
+        let pos = self.pos;
+        buf.put_slice(&self.chunk[pos..pos + n]);
+        self.pos += n;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Get the start of a `Content-Range` like `bytes 100-199/200`.
+fn content_range_start(range: &str) -> Option<u64> {
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range_start() {
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes 0-199/*"), Some(0));
+        assert_eq!(content_range_start("bytes */200"), None);
+        assert_eq!(content_range_start("100-199/200"), None);
+    }
+
+    #[tokio::test]
+    async fn test_resumable_blob() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let reference: Reference = "example.com/busybox:latest".parse().unwrap();
+        let data: Vec<u8> = (0..3 * CHUNK_SIZE).map(|i| i as u8).collect();
+        let size = data.len() as u64;
+
+        // The blob is checkpointed while read, and the checkpoint is kept
+        // if the download fails.
+        let blob: BlobReader = Box::new(std::io::Cursor::new(data[..CHUNK_SIZE].to_vec()));
+        let checkpoint = Checkpoint::open(tempdir.path(), "sha256:blob", size).unwrap();
+        let mut reader = ResumableBlob::new(
+            &reference,
+            &RegistryAuth::Anonymous,
+            "sha256:blob",
+            size,
+            0,
+            Some(checkpoint),
+            Some(blob),
+        );
+        let mut buf = Vec::new();
+        assert!(reader.read_to_end(&mut buf).await.is_err());
+        assert_eq!(buf, data[..CHUNK_SIZE]);
+        drop(reader);
+
+        let checkpoint = Checkpoint::open(tempdir.path(), "sha256:blob", size).unwrap();
+        assert_eq!(checkpoint.checkpointed(), CHUNK_SIZE as u64);
+        drop(checkpoint);
+
+        // A complete checkpoint is read without any request, and removed
+        // once read.
+        let path = tempdir.path().join("sha256_blob");
+        fs::write(&path, &data).unwrap();
+        let checkpoint = Checkpoint::open(tempdir.path(), "sha256:blob", size).unwrap();
+        let blob: BlobReader = Box::new(tokio::io::empty());
+        let mut reader = ResumableBlob::new(
+            &reference,
+            &RegistryAuth::Anonymous,
+            "sha256:blob",
+            size,
+            1,
+            Some(checkpoint),
+            Some(blob),
+        );
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data);
+        drop(reader);
+        assert!(!path.exists());
+
+        // A checkpoint longer than the blob is discarded.
+        fs::write(&path, &data).unwrap();
+        let checkpoint = Checkpoint::open(tempdir.path(), "sha256:blob", size - 1).unwrap();
+        assert_eq!(checkpoint.checkpointed(), 0);
+    }
+}
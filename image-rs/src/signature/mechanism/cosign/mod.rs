@@ -13,12 +13,15 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "signature-cosign")]
 use sigstore::{
     cosign::{
-        verification_constraint::{PublicKeyVerifier, VerificationConstraintVec},
+        verification_constraint::{
+            CertSubjectEmailVerifier, CertSubjectUrlVerifier, PublicKeyVerifier,
+            VerificationConstraint, VerificationConstraintVec,
+        },
         verify_constraints, ClientBuilder, CosignCapabilities,
     },
     crypto::SigningScheme,
     errors::SigstoreVerifyConstraintsError,
-    registry::Auth,
+    registry::{Auth, Certificate, CertificateEncoding},
 };
 
 use super::SignScheme;
@@ -46,6 +49,28 @@ pub struct CosignParameters {
     #[serde(rename = "keyData")]
     pub key_data: Option<String>,
 
+    // Fulcio specifies the Fulcio CA and the identity of the signer
+    // certificates, for keyless signatures.
+    // Exactly one of KeyPath, KeyData and Fulcio can be specified.
+    //
+    // This field is optional.
+    #[serde(default, rename = "fulcio")]
+    pub fulcio: Option<FulcioParameters>,
+
+    // RekorPublicKeyPath is a pathname to a local file containing the
+    // public key of Rekor, which keyless signatures must be logged in.
+    // At most one of RekorPublicKeyPath and RekorPublicKeyData can be specified.
+    //
+    // This field is optional.
+    #[serde(default, rename = "rekorPublicKeyPath")]
+    pub rekor_public_key_path: Option<String>,
+    // RekorPublicKeyData contains the public key of Rekor.
+    // At most one of RekorPublicKeyPath and RekorPublicKeyData can be specified.
+    //
+    // This field is optional.
+    #[serde(default, rename = "rekorPublicKeyData")]
+    pub rekor_public_key_data: Option<String>,
+
     // SignedIdentity specifies what image identity the signature must be claiming about the image.
     // Defaults to "match-exact" if not specified.
     //
@@ -54,6 +79,31 @@ pub struct CosignParameters {
     pub signed_identity: Option<PolicyReqMatchType>,
 }
 
+#[derive(Deserialize, Debug, Eq, PartialEq, Serialize, Default)]
+pub struct FulcioParameters {
+    // CAPath is a pathname to a local file containing the Fulcio CA certificates.
+    // Exactly one of CAPath and CAData can be specified.
+    #[serde(rename = "caPath")]
+    pub ca_path: Option<String>,
+    // CAData contains the Fulcio CA certificates.
+    // Exactly one of CAPath and CAData can be specified.
+    #[serde(rename = "caData")]
+    pub ca_data: Option<String>,
+
+    // OIDCIssuer is the OIDC issuer the signer identity was proved to.
+    #[serde(rename = "oidcIssuer")]
+    pub oidc_issuer: String,
+
+    // SubjectEmail is the email of the signer.
+    // Exactly one of SubjectEmail and SubjectURL can be specified.
+    #[serde(rename = "subjectEmail")]
+    pub subject_email: Option<String>,
+    // SubjectURL is the URL of the signer, e.g. the workflow of a CI.
+    // Exactly one of SubjectEmail and SubjectURL can be specified.
+    #[serde(rename = "subjectURL")]
+    pub subject_url: Option<String>,
+}
+
 #[async_trait]
 impl SignScheme for CosignParameters {
     /// This initialization will:
@@ -115,23 +165,47 @@ impl CosignParameters {
     }
 
     /// Verify the cosign-signed image. There will be three steps:
-    /// * Get the pub key.
+    /// * Get the pub key, or the Fulcio CA and the Rekor pub key for a
+    /// keyless signature.
     /// * Download the cosign-signed image's manifest and its digest. Calculate its
     /// signature's image.
     /// * Download the signature image, gather the signatures and verify them
-    /// using the pubkey.
+    /// using the pubkey, or the signer certificate chained to the Fulcio CA
+    /// and logged in Rekor.
     /// If succeeds, the payloads of the signature will be returned.
     async fn verify_signature_and_get_payload(
         &self,
         image: &Image,
         auth: &RegistryAuth,
     ) -> Result<Vec<SigPayload>> {
-        // Get the pubkey
-        let key = match (&self.key_data, &self.key_path) {
-            (None, None) => bail!("Neither keyPath nor keyData is specified."),
-            (None, Some(key_path)) => resource::get_resource(key_path).await?,
-            (Some(key_data), None) => key_data.as_bytes().to_vec(),
-            (Some(_), Some(_)) => bail!("Both keyPath and keyData are specified."),
+        let rekor_pub_key = get_key_material(
+            &self.rekor_public_key_path,
+            &self.rekor_public_key_data,
+            "rekorPublicKey",
+        )
+        .await?
+        .map(String::from_utf8)
+        .transpose()
+        .context("rekor public key is not PEM")?;
+
+        let key = get_key_material(&self.key_path, &self.key_data, "key").await?;
+        let (identity, fulcio_ca) = match (&key, &self.fulcio) {
+            (None, None) => bail!("Neither keyPath, keyData nor fulcio is specified."),
+            (Some(_), Some(_)) => bail!("Both a key and fulcio are specified."),
+            (Some(_), None) => (None, None),
+            (None, Some(fulcio)) => {
+                // The signer certificate is only trusted if the
+                // signature is logged in Rekor while it was valid.
+                if rekor_pub_key.is_none() {
+                    bail!("Keyless verification needs rekorPublicKeyPath or rekorPublicKeyData.");
+                }
+
+                let identity = fulcio.identity_verifier()?;
+                let fulcio_ca = get_key_material(&fulcio.ca_path, &fulcio.ca_data, "ca")
+                    .await?
+                    .ok_or_else(|| anyhow!("Neither caPath nor caData of fulcio is specified."))?;
+                (Some(identity), Some(fulcio_ca))
+            }
         };
 
         let image_ref = image.reference.whole();
@@ -141,7 +215,17 @@ impl CosignParameters {
         let signature_layers = tokio::task::spawn_blocking(move || -> Result<_> {
             let auth = Auth::from(&auth);
 
-            let mut client = ClientBuilder::default().build()?;
+            let mut builder = ClientBuilder::default();
+            if let Some(rekor_pub_key) = &rekor_pub_key {
+                builder = builder.with_rekor_pub_key(rekor_pub_key);
+            }
+            if let Some(fulcio_ca) = fulcio_ca {
+                builder = builder.with_fulcio_certs(&[Certificate {
+                    encoding: CertificateEncoding::Pem,
+                    data: fulcio_ca,
+                }]);
+            }
+            let mut client = builder.build()?;
 
             // Get the cosign signature "image"'s uri and the signed image's digest
             //
@@ -164,19 +248,30 @@ impl CosignParameters {
         .context("tokio spawn")?
         .context("get signature layers")?;
 
-        // By default, the hashing algorithm is SHA256
-        let pub_key_verifier =
-            PublicKeyVerifier::new(&key, &SigningScheme::ECDSA_P256_SHA256_ASN1)?;
-
-        let verification_constraints: VerificationConstraintVec = vec![Box::new(pub_key_verifier)];
+        let verification_constraints: VerificationConstraintVec = match (key, identity) {
+            (Some(key), _) => {
+                // By default, the hashing algorithm is SHA256
+                let pub_key_verifier =
+                    PublicKeyVerifier::new(&key, &SigningScheme::ECDSA_P256_SHA256_ASN1)?;
+                vec![Box::new(pub_key_verifier)]
+            }
+            (None, Some(identity)) => vec![identity],
+            (None, None) => bail!("Neither keyPath, keyData nor fulcio is specified."),
+        };
 
         let res = verify_constraints(&signature_layers, verification_constraints.iter());
 
         match res {
             Ok(()) => {
-                // gather the payloads
+                // gather the payloads of the signatures satisfying all the
+                // constraints
                 let payloads = signature_layers
                     .iter()
+                    .filter(|layer| {
+                        verification_constraints
+                            .iter()
+                            .all(|constraint| constraint.verify(layer).unwrap_or(false))
+                    })
                     .map(|layer| SigPayload::from(layer.simple_signing.clone()))
                     .collect();
                 Ok(payloads)
@@ -188,6 +283,43 @@ impl CosignParameters {
     }
 }
 
+#[cfg(feature = "signature-cosign")]
+impl FulcioParameters {
+    /// Get the constraint on the identity of the signer certificate.
+    fn identity_verifier(&self) -> Result<Box<dyn VerificationConstraint + Send + Sync>> {
+        match (&self.subject_email, &self.subject_url) {
+            (Some(email), None) => Ok(Box::new(CertSubjectEmailVerifier {
+                email: email.clone(),
+                issuer: Some(self.oidc_issuer.clone()),
+            })),
+            (None, Some(url)) => Ok(Box::new(CertSubjectUrlVerifier {
+                url: url.clone(),
+                issuer: self.oidc_issuer.clone(),
+            })),
+            (None, None) => bail!("Neither subjectEmail nor subjectURL of fulcio is specified."),
+            (Some(_), Some(_)) => {
+                bail!("Both subjectEmail and subjectURL of fulcio are specified.")
+            }
+        }
+    }
+}
+
+/// Get the key material either from `path` or from `data`, `name` naming
+/// the fields in the errors.
+#[cfg(feature = "signature-cosign")]
+async fn get_key_material(
+    path: &Option<String>,
+    data: &Option<String>,
+    name: &str,
+) -> Result<Option<Vec<u8>>> {
+    match (data, path) {
+        (None, None) => Ok(None),
+        (None, Some(path)) => Ok(Some(resource::get_resource(path).await?)),
+        (Some(data), None) => Ok(Some(data.as_bytes().to_vec())),
+        (Some(_), Some(_)) => bail!("Both {name}Path and {name}Data are specified."),
+    }
+}
+
 #[cfg(feature = "signature-cosign")]
 #[cfg(test)]
 mod tests {
@@ -222,6 +354,7 @@ mod tests {
             ),
             key_data: None,
             signed_identity: None,
+            ..Default::default()
         },
         "registry.cn-hangzhou.aliyuncs.com/xynnn/cosign:latest",
     )]
@@ -238,6 +371,7 @@ mod tests {
             ),
             key_data: None,
             signed_identity: None,
+            ..Default::default()
         },
         "registry-1.docker.io/xynnn007/cosign:latest",
     )]
@@ -254,6 +388,7 @@ mod tests {
             ),
             key_data: None,
             signed_identity: None,
+            ..Default::default()
         },
         "quay.io/kata-containers/confidential-containers:cosign-signed",
     )]
@@ -299,6 +434,7 @@ mod tests {
             key_path: None,
             key_data: None,
             signed_identity: Some(policy_match),
+            ..Default::default()
         };
         assert_eq!(parameter.check_reference_rule_types().is_ok(), pass);
     }
@@ -440,4 +576,64 @@ mod tests {
             panic!("Must be a sigstoreSigned policy!");
         }
     }
+
+    #[test]
+    fn fulcio_identity_verifier_test() {
+        let mut fulcio = FulcioParameters {
+            oidc_issuer: "https://token.actions.githubusercontent.com".into(),
+            ..Default::default()
+        };
+        assert!(fulcio.identity_verifier().is_err());
+
+        fulcio.subject_url =
+            Some("https://github.com/org/repo/.github/workflows/sign.yml@refs/heads/main".into());
+        assert!(fulcio.identity_verifier().is_ok());
+
+        fulcio.subject_email = Some("signer@example.com".into());
+        assert!(fulcio.identity_verifier().is_err());
+
+        fulcio.subject_url = None;
+        assert!(fulcio.identity_verifier().is_ok());
+    }
+
+    #[tokio::test]
+    async fn keyless_policy_test() {
+        let policy = r#"{
+            "type": "sigstoreSigned",
+            "fulcio": {
+                "caData": "<FULCIO-CA-PEM>",
+                "oidcIssuer": "https://oauth2.sigstore.dev/auth",
+                "subjectEmail": "signer@example.com"
+            },
+            "signedIdentity": {
+                "type": "matchRepository"
+            }
+        }"#;
+        let PolicyReqType::Cosign(scheme) =
+            serde_json::from_str(policy).expect("deserialize PolicyReqType failed.")
+        else {
+            panic!("Must be a sigstoreSigned policy!");
+        };
+
+        let fulcio = scheme.fulcio.as_ref().expect("no fulcio");
+        assert_eq!(fulcio.oidc_issuer, "https://oauth2.sigstore.dev/auth");
+        assert_eq!(fulcio.subject_email.as_deref(), Some("signer@example.com"));
+
+        // A keyless signature is refused without the Rekor public key,
+        // before accessing the network.
+        let reference =
+            Reference::try_from("quay.io/kata-containers/confidential-containers:cosign-signed")
+                .expect("deserialize OCI Reference failed.");
+        let image = Image::default_with_reference(reference);
+        let res = scheme
+            .verify_signature_and_get_payload(
+                &image,
+                &oci_distribution::secrets::RegistryAuth::Anonymous,
+            )
+            .await;
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            "Keyless verification needs rekorPublicKeyPath or rekorPublicKeyData."
+        );
+    }
 }
@@ -23,6 +23,8 @@ use crate::config::Paths;
 use super::image::Image;
 
 pub mod cosign;
+pub mod notation;
+pub mod provenance;
 pub mod simple;
 
 /// The interface of a signing scheme
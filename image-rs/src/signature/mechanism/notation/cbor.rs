@@ -0,0 +1,272 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Minimal CBOR (RFC 8949) codec, enough to parse the `COSE_Sign1`
+//! envelopes of notation and to build the structure they sign.
+//!
+//! Only the definite-length items are supported, as COSE requires the
+//! deterministic encoding for the signed structures.
+
+use anyhow::{anyhow, bail, Result};
+
+/// Max nesting of the arrays, maps and tags accepted.
+const MAX_DEPTH: usize = 16;
+
+/// A CBOR data item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Integer(i128),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    /// Decode a data item, which must span the whole of `data`.
+    pub fn decode(data: &[u8]) -> Result<Value> {
+        let mut decoder = Decoder { data, pos: 0 };
+        let value = decoder.item(0)?;
+        if decoder.pos != data.len() {
+            bail!(
+                "trailing {} bytes after CBOR item",
+                data.len() - decoder.pos
+            );
+        }
+
+        Ok(value)
+    }
+
+    /// Encode the data item, with the shortest form of the lengths.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_to(&mut out);
+        out
+    }
+
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Integer(n) if *n >= 0 => head(out, 0, *n as u64),
+            Value::Integer(n) => head(out, 1, (-1 - *n) as u64),
+            Value::Bytes(bytes) => {
+                head(out, 2, bytes.len() as u64);
+                out.extend(bytes);
+            }
+            Value::Text(text) => {
+                head(out, 3, text.len() as u64);
+                out.extend(text.as_bytes());
+            }
+            Value::Array(items) => {
+                head(out, 4, items.len() as u64);
+                for item in items {
+                    item.encode_to(out);
+                }
+            }
+            Value::Map(entries) => {
+                head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.encode_to(out);
+                    value.encode_to(out);
+                }
+            }
+            Value::Tag(tag, item) => {
+                head(out, 6, *tag);
+                item.encode_to(out);
+            }
+            Value::Bool(false) => out.push(0xf4),
+            Value::Bool(true) => out.push(0xf5),
+            Value::Null => out.push(0xf6),
+        }
+    }
+
+    /// Get the value of `key` if the item is a map.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+fn head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..=23 => out.push(major | arg as u8),
+        24..=0xff => out.extend([major | 24, arg as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend(arg.to_be_bytes());
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| anyhow!("truncated CBOR item"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn arg(&mut self, info: u8) -> Result<u64> {
+        let arg = match info {
+            0..=23 => info as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into()?) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into()?) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into()?),
+            _ => bail!("unsupported CBOR additional info {}", info),
+        };
+
+        Ok(arg)
+    }
+
+    fn len(&mut self, info: u8) -> Result<usize> {
+        let len = usize::try_from(self.arg(info)?)?;
+        // Every item takes at least a byte, so that a forged length does
+        // not make us allocate more than the input.
+        if len > self.data.len() - self.pos {
+            bail!("truncated CBOR item");
+        }
+
+        Ok(len)
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            bail!("CBOR item nested deeper than {}", MAX_DEPTH);
+        }
+
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let value = match major {
+            0 => Value::Integer(self.arg(info)? as i128),
+            1 => Value::Integer(-1 - self.arg(info)? as i128),
+            2 => {
+                let len = self.len(info)?;
+                Value::Bytes(self.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.len(info)?;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|e| anyhow!("invalid CBOR text {e}"))?;
+                Value::Text(text.to_string())
+            }
+            4 => {
+                let len = self.len(info)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.item(depth + 1)?);
+                }
+                Value::Array(items)
+            }
+            5 => {
+                let len = self.len(info)?;
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.item(depth + 1)?;
+                    let value = self.item(depth + 1)?;
+                    entries.push((key, value));
+                }
+                Value::Map(entries)
+            }
+            6 => {
+                let tag = self.arg(info)?;
+                Value::Tag(tag, Box::new(self.item(depth + 1)?))
+            }
+            _ => match info {
+                20 => Value::Bool(false),
+                21 => Value::Bool(true),
+                22 => Value::Null,
+                _ => bail!("unsupported CBOR simple value or float {}", info),
+            },
+        };
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_codec() {
+        // Examples from the appendix A of RFC 8949.
+        let cases = [
+            (Value::Integer(0), "00"),
+            (Value::Integer(23), "17"),
+            (Value::Integer(24), "1818"),
+            (Value::Integer(1000), "1903e8"),
+            (Value::Integer(1000000), "1a000f4240"),
+            (Value::Integer(1000000000000), "1b000000e8d4a51000"),
+            (Value::Integer(-1), "20"),
+            (Value::Integer(-1000), "3903e7"),
+            (Value::Bytes(vec![1, 2, 3, 4]), "4401020304"),
+            (Value::Text("IETF".to_string()), "6449455446"),
+            (
+                Value::Array(vec![
+                    Value::Integer(1),
+                    Value::Array(vec![Value::Integer(2), Value::Integer(3)]),
+                ]),
+                "8201820203",
+            ),
+            (
+                Value::Map(vec![
+                    (Value::Text("a".to_string()), Value::Integer(1)),
+                    (
+                        Value::Text("b".to_string()),
+                        Value::Array(vec![Value::Integer(2), Value::Integer(3)]),
+                    ),
+                ]),
+                "a26161016162820203",
+            ),
+            (
+                Value::Tag(1, Box::new(Value::Integer(1363896240))),
+                "c11a514b67b0",
+            ),
+            (Value::Bool(true), "f5"),
+            (Value::Null, "f6"),
+        ];
+
+        for (value, encoded) in cases {
+            let encoded = hex::decode(encoded).unwrap();
+            assert_eq!(value.encode(), encoded);
+            assert_eq!(Value::decode(&encoded).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_cbor_decode_invalid() {
+        // Truncated, trailing, indefinite-length, float, and a length
+        // beyond the input.
+        for encoded in ["1903", "0000", "5f", "f93c00", "5bffffffffffffffff00"] {
+            assert!(Value::decode(&hex::decode(encoded).unwrap()).is_err());
+        }
+
+        let nested = [0x81; MAX_DEPTH + 2];
+        assert!(Value::decode(&nested).is_err());
+    }
+}
@@ -0,0 +1,666 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Signature envelopes of notation, in JWS or COSE, refer to
+//! <https://github.com/notaryproject/specifications/blob/main/specs/signature-specification.md>.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use openssl::{
+    bn::BigNum,
+    ecdsa::EcdsaSig,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{Id, PKey, Public},
+    rsa::Padding,
+    sign::{RsaPssSaltlen, Verifier},
+    x509::X509,
+};
+use serde::Deserialize;
+
+use super::cbor::Value;
+
+/// Media type of the JWS envelopes.
+pub const MEDIA_TYPE_JWS: &str = "application/jose+json";
+
+/// Media type of the COSE envelopes.
+pub const MEDIA_TYPE_COSE: &str = "application/cose";
+
+/// Content type of the payload of the envelopes.
+pub const PAYLOAD_CONTENT_TYPE: &str = "application/vnd.cncf.notary.payload.v1+json";
+
+/// Signing scheme whose signing time is only claimed by the signer.
+pub const SIGNING_SCHEME_X509: &str = "notary.x509";
+
+/// Signing scheme whose signing time is asserted by a signing authority.
+pub const SIGNING_SCHEME_X509_SIGNING_AUTHORITY: &str = "notary.x509.signingAuthority";
+
+const HEADER_SIGNING_SCHEME: &str = "io.cncf.notary.signingScheme";
+const HEADER_SIGNING_TIME: &str = "io.cncf.notary.signingTime";
+const HEADER_AUTHENTIC_SIGNING_TIME: &str = "io.cncf.notary.authenticSigningTime";
+const HEADER_EXPIRY: &str = "io.cncf.notary.expiry";
+
+/// The critical headers which are understood.
+const KNOWN_CRITICAL_HEADERS: [&str; 3] = [
+    HEADER_SIGNING_SCHEME,
+    HEADER_AUTHENTIC_SIGNING_TIME,
+    HEADER_EXPIRY,
+];
+
+/// COSE header labels, refer to RFC 9052 and RFC 9360.
+const COSE_ALG: i128 = 1;
+const COSE_CRIT: i128 = 2;
+const COSE_CONTENT_TYPE: i128 = 3;
+const COSE_X5CHAIN: i128 = 33;
+
+/// CBOR tags of `COSE_Sign1` and of the epoch-based date/time.
+const COSE_SIGN1_TAG: u64 = 18;
+const EPOCH_TIME_TAG: u64 = 1;
+
+/// Signature algorithms allowed by notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Ps256,
+    Ps384,
+    Ps512,
+    Es256,
+    Es384,
+    Es512,
+}
+
+impl Algorithm {
+    fn from_jws(alg: &str) -> Result<Self> {
+        match alg {
+            "PS256" => Ok(Algorithm::Ps256),
+            "PS384" => Ok(Algorithm::Ps384),
+            "PS512" => Ok(Algorithm::Ps512),
+            "ES256" => Ok(Algorithm::Es256),
+            "ES384" => Ok(Algorithm::Es384),
+            "ES512" => Ok(Algorithm::Es512),
+            alg => bail!("unsupported JWS algorithm {:?}", alg),
+        }
+    }
+
+    fn from_cose(alg: i128) -> Result<Self> {
+        match alg {
+            -37 => Ok(Algorithm::Ps256),
+            -38 => Ok(Algorithm::Ps384),
+            -39 => Ok(Algorithm::Ps512),
+            -7 => Ok(Algorithm::Es256),
+            -35 => Ok(Algorithm::Es384),
+            -36 => Ok(Algorithm::Es512),
+            alg => bail!("unsupported COSE algorithm {}", alg),
+        }
+    }
+
+    fn digest(&self) -> MessageDigest {
+        match self {
+            Algorithm::Ps256 | Algorithm::Es256 => MessageDigest::sha256(),
+            Algorithm::Ps384 | Algorithm::Es384 => MessageDigest::sha384(),
+            Algorithm::Ps512 | Algorithm::Es512 => MessageDigest::sha512(),
+        }
+    }
+
+    /// Get the curve and the size of a coordinate for ECDSA.
+    fn curve(&self) -> Option<(Nid, usize)> {
+        match self {
+            Algorithm::Es256 => Some((Nid::X9_62_PRIME256V1, 32)),
+            Algorithm::Es384 => Some((Nid::SECP384R1, 48)),
+            Algorithm::Es512 => Some((Nid::SECP521R1, 66)),
+            _ => None,
+        }
+    }
+}
+
+/// The signed payload of notation.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Payload {
+    #[serde(rename = "targetArtifact")]
+    pub target_artifact: TargetArtifact,
+}
+
+/// Descriptor of the signed artifact.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TargetArtifact {
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+/// A parsed signature envelope.
+pub struct Envelope {
+    pub algorithm: Algorithm,
+    pub signing_scheme: String,
+
+    /// Signing time claimed by the signer, in seconds since the Unix epoch.
+    pub signing_time: Option<i64>,
+
+    /// Signing time asserted by the signing authority, in seconds since the
+    /// Unix epoch.
+    pub authentic_signing_time: Option<i64>,
+
+    /// Time after which the signature is expired, in seconds since the
+    /// Unix epoch.
+    pub expiry: Option<i64>,
+
+    /// Certificate chain, from the signing certificate.
+    pub cert_chain: Vec<X509>,
+
+    payload: Vec<u8>,
+
+    /// The bytes which the signature is over.
+    signed: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct JwsEnvelope {
+    protected: String,
+    payload: String,
+    signature: String,
+    #[serde(default)]
+    header: JwsUnprotectedHeader,
+}
+
+#[derive(Default, Deserialize)]
+struct JwsUnprotectedHeader {
+    #[serde(default)]
+    x5c: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct JwsProtectedHeader {
+    alg: String,
+    #[serde(default)]
+    crit: Vec<String>,
+    cty: String,
+    #[serde(rename = "io.cncf.notary.signingScheme")]
+    signing_scheme: String,
+    #[serde(rename = "io.cncf.notary.signingTime")]
+    signing_time: Option<String>,
+    #[serde(rename = "io.cncf.notary.authenticSigningTime")]
+    authentic_signing_time: Option<String>,
+    #[serde(rename = "io.cncf.notary.expiry")]
+    expiry: Option<String>,
+}
+
+impl Envelope {
+    /// Parse an envelope of `media_type`.
+    pub fn parse(media_type: &str, data: &[u8]) -> Result<Self> {
+        match media_type {
+            MEDIA_TYPE_JWS => Self::parse_jws(data).context("invalid JWS envelope"),
+            MEDIA_TYPE_COSE => Self::parse_cose(data).context("invalid COSE envelope"),
+            media_type => bail!("unsupported signature envelope {:?}", media_type),
+        }
+    }
+
+    fn parse_jws(data: &[u8]) -> Result<Self> {
+        let envelope: JwsEnvelope = serde_json::from_slice(data)?;
+        let url = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let header: JwsProtectedHeader = serde_json::from_slice(&url.decode(&envelope.protected)?)?;
+
+        check_critical_headers(header.crit.iter().map(String::as_str))?;
+        let time = |time: &Option<String>| -> Result<Option<i64>> {
+            time.as_ref()
+                .map(|time| {
+                    chrono::DateTime::parse_from_rfc3339(time)
+                        .map(|time| time.timestamp())
+                        .map_err(|e| anyhow!("invalid time {:?}: {e}", time))
+                })
+                .transpose()
+        };
+
+        let cert_chain = envelope
+            .header
+            .x5c
+            .iter()
+            .map(|cert| {
+                let der = base64::engine::general_purpose::STANDARD.decode(cert)?;
+                Ok(X509::from_der(&der)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let envelope = Envelope {
+            algorithm: Algorithm::from_jws(&header.alg)?,
+            signing_scheme: header.signing_scheme,
+            signing_time: time(&header.signing_time)?,
+            authentic_signing_time: time(&header.authentic_signing_time)?,
+            expiry: time(&header.expiry)?,
+            cert_chain,
+            payload: url.decode(&envelope.payload)?,
+            signed: format!("{}.{}", envelope.protected, envelope.payload).into_bytes(),
+            signature: url.decode(&envelope.signature)?,
+        };
+
+        envelope.check_content_type(&header.cty)?;
+        Ok(envelope)
+    }
+
+    fn parse_cose(data: &[u8]) -> Result<Self> {
+        let sign1 = match Value::decode(data)? {
+            Value::Tag(COSE_SIGN1_TAG, sign1) => *sign1,
+            _ => bail!("not a tagged COSE_Sign1"),
+        };
+
+        let [Value::Bytes(protected), unprotected, Value::Bytes(payload), Value::Bytes(signature)] =
+            <[Value; 4]>::try_from(match sign1 {
+                Value::Array(items) => items,
+                _ => bail!("COSE_Sign1 is not an array"),
+            })
+            .map_err(|_| anyhow!("COSE_Sign1 is not an array of 4 items"))?
+        else {
+            bail!("invalid COSE_Sign1 items");
+        };
+
+        let header = Value::decode(&protected)?;
+        let label = |label: i128| header.get(&Value::Integer(label));
+        let text = |name: &str| header.get(&Value::Text(name.to_string()));
+
+        let crit = match label(COSE_CRIT) {
+            Some(Value::Array(crit)) => crit
+                .iter()
+                .map(|label| match label {
+                    Value::Text(label) => Ok(label.as_str()),
+                    label => bail!("unknown critical header {:?}", label),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            Some(_) => bail!("invalid crit header"),
+            None => Vec::new(),
+        };
+        check_critical_headers(crit.into_iter())?;
+
+        let algorithm = match label(COSE_ALG) {
+            Some(Value::Integer(alg)) => Algorithm::from_cose(*alg)?,
+            _ => bail!("no alg header"),
+        };
+
+        let Some(Value::Text(content_type)) = label(COSE_CONTENT_TYPE) else {
+            bail!("no content type header");
+        };
+
+        let Some(Value::Text(signing_scheme)) = text(HEADER_SIGNING_SCHEME) else {
+            bail!("no {} header", HEADER_SIGNING_SCHEME);
+        };
+
+        let time = |name: &str| -> Result<Option<i64>> {
+            match text(name) {
+                Some(Value::Tag(EPOCH_TIME_TAG, time)) => match **time {
+                    Value::Integer(time) => Ok(Some(i64::try_from(time)?)),
+                    _ => bail!("invalid {} header", name),
+                },
+                Some(_) => bail!("invalid {} header", name),
+                None => Ok(None),
+            }
+        };
+
+        let cert_chain = match unprotected.get(&Value::Integer(COSE_X5CHAIN)) {
+            Some(Value::Bytes(cert)) => vec![X509::from_der(cert)?],
+            Some(Value::Array(certs)) => certs
+                .iter()
+                .map(|cert| match cert {
+                    Value::Bytes(cert) => Ok(X509::from_der(cert)?),
+                    _ => bail!("invalid x5chain header"),
+                })
+                .collect::<Result<Vec<_>>>()?,
+            _ => Vec::new(),
+        };
+
+        // The Sig_structure signed for COSE_Sign1, with no external AAD.
+        let signed = Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(protected),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(payload.clone()),
+        ])
+        .encode();
+
+        let envelope = Envelope {
+            algorithm,
+            signing_scheme: signing_scheme.clone(),
+            signing_time: time(HEADER_SIGNING_TIME)?,
+            authentic_signing_time: time(HEADER_AUTHENTIC_SIGNING_TIME)?,
+            expiry: time(HEADER_EXPIRY)?,
+            cert_chain,
+            payload,
+            signed,
+            signature,
+        };
+
+        envelope.check_content_type(content_type)?;
+        Ok(envelope)
+    }
+
+    fn check_content_type(&self, content_type: &str) -> Result<()> {
+        if content_type != PAYLOAD_CONTENT_TYPE {
+            bail!("unsupported payload content type {:?}", content_type);
+        }
+
+        match self.signing_scheme.as_str() {
+            SIGNING_SCHEME_X509 => {}
+            SIGNING_SCHEME_X509_SIGNING_AUTHORITY => {
+                if self.authentic_signing_time.is_none() {
+                    bail!("no authentic signing time of the signing authority");
+                }
+            }
+            scheme => bail!("unsupported signing scheme {:?}", scheme),
+        }
+
+        if self.cert_chain.is_empty() {
+            bail!("no certificate chain");
+        }
+
+        Ok(())
+    }
+
+    /// Verify the signature against the signing certificate, i.e. the
+    /// integrity of the envelope, and get the payload signed.
+    pub fn verify_integrity(&self) -> Result<Payload> {
+        let key = self.cert_chain[0].public_key()?;
+        let signature = match self.algorithm.curve() {
+            Some((curve, size)) => {
+                check_ec_key(&key, curve)?;
+                // JWS and COSE encode the ECDSA signatures as `r || s`.
+                if self.signature.len() != size * 2 {
+                    bail!("invalid ECDSA signature size {}", self.signature.len());
+                }
+                let (r, s) = self.signature.split_at(size);
+                EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?
+                    .to_der()?
+            }
+            None => {
+                if key.id() != Id::RSA || key.bits() < 2048 {
+                    bail!("signing key is not an RSA key of at least 2048 bits");
+                }
+                self.signature.clone()
+            }
+        };
+
+        let mut verifier = Verifier::new(self.algorithm.digest(), &key)?;
+        if self.algorithm.curve().is_none() {
+            verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+            verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+            verifier.set_rsa_mgf1_md(self.algorithm.digest())?;
+        }
+        verifier.update(&self.signed)?;
+        if !verifier.verify(&signature)? {
+            bail!("signature does not match the signing certificate");
+        }
+
+        serde_json::from_slice(&self.payload).map_err(|e| anyhow!("invalid payload {e}"))
+    }
+}
+
+fn check_critical_headers<'a>(crit: impl Iterator<Item = &'a str>) -> Result<()> {
+    let mut signing_scheme = false;
+    for header in crit {
+        if !KNOWN_CRITICAL_HEADERS.contains(&header) {
+            bail!("unknown critical header {:?}", header);
+        }
+        signing_scheme |= header == HEADER_SIGNING_SCHEME;
+    }
+
+    if !signing_scheme {
+        bail!("{} is not a critical header", HEADER_SIGNING_SCHEME);
+    }
+
+    Ok(())
+}
+
+fn check_ec_key(key: &PKey<Public>, curve: Nid) -> Result<()> {
+    let key = key
+        .ec_key()
+        .map_err(|_| anyhow!("signing key is not an EC key"))?;
+    if key.group().curve_name() != Some(curve) {
+        bail!("signing key is not on the curve of the algorithm");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        bn::MsbOption,
+        ec::{EcGroup, EcKey},
+        pkey::Private,
+        rsa::Rsa,
+        sign::Signer,
+        x509::{X509Builder, X509NameBuilder},
+    };
+
+    pub(crate) const IMAGE_DIGEST: &str =
+        "sha256:7b3ccabffc97de872a30dfd234fd972a66d247c8cfc69b0550f276481852627c";
+
+    pub(crate) fn payload() -> Vec<u8> {
+        serde_json::json!({
+            "targetArtifact": {
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": IMAGE_DIGEST,
+                "size": 528
+            }
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Issue a certificate of `subject` for `key`, signed by `issuer`, or
+    /// self-signed.
+    pub(crate) fn certificate(
+        subject: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        days: u32,
+    ) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        for attr in subject.split(", ") {
+            let (field, value) = attr.split_once('=').unwrap();
+            name.append_entry_by_text(field, value).unwrap();
+        }
+        let name = name.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+        builder
+            .set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(days).unwrap())
+            .unwrap();
+
+        match issuer {
+            Some((issuer, issuer_key)) => {
+                builder.set_issuer_name(issuer.subject_name()).unwrap();
+                builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+            }
+            None => {
+                let ca = openssl::x509::extension::BasicConstraints::new()
+                    .critical()
+                    .ca()
+                    .build()
+                    .unwrap();
+                builder.append_extension(ca).unwrap();
+                builder.set_issuer_name(&name).unwrap();
+                builder.sign(key, MessageDigest::sha256()).unwrap();
+            }
+        }
+
+        builder.build()
+    }
+
+    pub(crate) fn rsa_key() -> PKey<Private> {
+        PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+    }
+
+    pub(crate) fn ec_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    /// Sign `payload` in a JWS envelope with PS256.
+    pub(crate) fn jws_envelope(
+        payload: &[u8],
+        key: &PKey<Private>,
+        chain: &[&X509],
+        expiry: Option<&str>,
+    ) -> Vec<u8> {
+        let url = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        let mut protected = serde_json::json!({
+            "alg": "PS256",
+            "crit": [HEADER_SIGNING_SCHEME],
+            "cty": PAYLOAD_CONTENT_TYPE,
+            HEADER_SIGNING_SCHEME: SIGNING_SCHEME_X509,
+            HEADER_SIGNING_TIME: "2023-08-24T17:18:15Z",
+        });
+        if let Some(expiry) = expiry {
+            protected[HEADER_EXPIRY] = expiry.into();
+            protected["crit"] = serde_json::json!([HEADER_SIGNING_SCHEME, HEADER_EXPIRY]);
+        }
+        let protected = url.encode(protected.to_string());
+        let payload = url.encode(payload);
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+        signer
+            .set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)
+            .unwrap();
+        signer.set_rsa_mgf1_md(MessageDigest::sha256()).unwrap();
+        signer
+            .update(format!("{protected}.{payload}").as_bytes())
+            .unwrap();
+        let signature = url.encode(signer.sign_to_vec().unwrap());
+
+        let x5c: Vec<String> = chain
+            .iter()
+            .map(|cert| base64::engine::general_purpose::STANDARD.encode(cert.to_der().unwrap()))
+            .collect();
+
+        serde_json::json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": signature,
+            "header": { "x5c": x5c },
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Sign `payload` in a COSE envelope with ES256.
+    pub(crate) fn cose_envelope(payload: &[u8], key: &PKey<Private>, chain: &[&X509]) -> Vec<u8> {
+        let protected = Value::Map(vec![
+            (Value::Integer(COSE_ALG), Value::Integer(-7)),
+            (
+                Value::Integer(COSE_CRIT),
+                Value::Array(vec![Value::Text(HEADER_SIGNING_SCHEME.to_string())]),
+            ),
+            (
+                Value::Integer(COSE_CONTENT_TYPE),
+                Value::Text(PAYLOAD_CONTENT_TYPE.to_string()),
+            ),
+            (
+                Value::Text(HEADER_SIGNING_SCHEME.to_string()),
+                Value::Text(SIGNING_SCHEME_X509.to_string()),
+            ),
+            (
+                Value::Text(HEADER_SIGNING_TIME.to_string()),
+                Value::Tag(EPOCH_TIME_TAG, Box::new(Value::Integer(1692897495))),
+            ),
+        ])
+        .encode();
+
+        let signed = Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(protected.clone()),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(payload.to_vec()),
+        ])
+        .encode();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(&signed).unwrap();
+        let signature = EcdsaSig::from_der(&signer.sign_to_vec().unwrap()).unwrap();
+        let mut raw = signature.r().to_vec_padded(32).unwrap();
+        raw.extend(signature.s().to_vec_padded(32).unwrap());
+
+        let x5chain = chain
+            .iter()
+            .map(|cert| Value::Bytes(cert.to_der().unwrap()))
+            .collect();
+
+        Value::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(Value::Array(vec![
+                Value::Bytes(protected),
+                Value::Map(vec![(Value::Integer(COSE_X5CHAIN), Value::Array(x5chain))]),
+                Value::Bytes(payload.to_vec()),
+                Value::Bytes(raw),
+            ])),
+        )
+        .encode()
+    }
+
+    #[test]
+    fn test_jws_envelope() {
+        let key = rsa_key();
+        let cert = certificate("C=US, ST=WA, O=example, CN=signer", &key, None, 1);
+        let data = jws_envelope(&payload(), &key, &[&cert], Some("2100-01-01T00:00:00Z"));
+
+        let envelope = Envelope::parse(MEDIA_TYPE_JWS, &data).unwrap();
+        assert_eq!(envelope.algorithm, Algorithm::Ps256);
+        assert_eq!(envelope.signing_scheme, SIGNING_SCHEME_X509);
+        assert_eq!(envelope.signing_time, Some(1692897495));
+        assert_eq!(envelope.expiry, Some(4102444800));
+        assert_eq!(envelope.cert_chain.len(), 1);
+
+        let payload = envelope.verify_integrity().unwrap();
+        assert_eq!(payload.target_artifact.digest, IMAGE_DIGEST);
+
+        // A signature by another key is refused.
+        let other = rsa_key();
+        let data = jws_envelope(&super::tests::payload(), &other, &[&cert], None);
+        let envelope = Envelope::parse(MEDIA_TYPE_JWS, &data).unwrap();
+        assert!(envelope.verify_integrity().is_err());
+    }
+
+    #[test]
+    fn test_cose_envelope() {
+        let key = ec_key();
+        let cert = certificate("C=US, ST=WA, O=example, CN=signer", &key, None, 1);
+        let data = cose_envelope(&payload(), &key, &[&cert]);
+
+        let envelope = Envelope::parse(MEDIA_TYPE_COSE, &data).unwrap();
+        assert_eq!(envelope.algorithm, Algorithm::Es256);
+        assert_eq!(envelope.signing_time, Some(1692897495));
+        assert_eq!(envelope.expiry, None);
+
+        let payload = envelope.verify_integrity().unwrap();
+        assert_eq!(payload.target_artifact.digest, IMAGE_DIGEST);
+
+        // A tampered payload is refused.
+        let mut tampered = data.clone();
+        let pos = tampered
+            .windows(IMAGE_DIGEST.len())
+            .position(|window| window == IMAGE_DIGEST.as_bytes())
+            .unwrap();
+        tampered[pos + IMAGE_DIGEST.len() - 1] ^= 1;
+        let envelope = Envelope::parse(MEDIA_TYPE_COSE, &tampered).unwrap();
+        assert!(envelope.verify_integrity().is_err());
+
+        assert!(Envelope::parse("application/pgp-signature", &data).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Notation (Notary v2) verification
+
+use std::collections::BTreeMap;
+#[cfg(feature = "signature-notation")]
+use std::collections::HashMap;
+
+#[cfg(feature = "signature-notation")]
+use anyhow::{anyhow, Context};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+#[cfg(feature = "signature-notation")]
+use log::warn;
+use oci_distribution::secrets::RegistryAuth;
+#[cfg(feature = "signature-notation")]
+use openssl::x509::X509;
+use serde::{Deserialize, Serialize};
+
+use super::SignScheme;
+#[cfg(feature = "signature-notation")]
+use crate::resource;
+#[cfg(feature = "signature-notation")]
+use crate::signature::image::get_image_repository_full_name;
+#[cfg(feature = "signature-notation")]
+use crate::signature::referrers::Referrers;
+use crate::signature::{image::Image, mechanism::Paths};
+#[cfg(feature = "signature-notation")]
+use envelope::Envelope;
+#[cfg(feature = "signature-notation")]
+use trust_policy::{TrustPolicyDocument, VerificationLevel};
+
+#[cfg(feature = "signature-notation")]
+mod cbor;
+#[cfg(feature = "signature-notation")]
+pub mod envelope;
+#[cfg(feature = "signature-notation")]
+pub mod trust_policy;
+#[cfg(feature = "signature-notation")]
+mod verify;
+
+/// Artifact type of the notation signatures.
+pub const NOTATION_ARTIFACT_TYPE: &str = "application/vnd.cncf.notary.signature";
+
+/// Max size of a signature envelope accepted.
+#[cfg(feature = "signature-notation")]
+const MAX_ENVELOPE_SIZE: i64 = 4 * 1024 * 1024;
+
+#[derive(Deserialize, Debug, Eq, PartialEq, Serialize, Default)]
+pub struct NotationParameters {
+    // TrustPolicyPath is the URL of the trust policy document of notation,
+    // i.e. `trustpolicy.json`, like `kbs:///default/notation/trustpolicy.json`.
+    #[serde(rename = "trustPolicyPath")]
+    pub trust_policy_path: String,
+
+    // TrustStores maps the trust stores named in the trust policy, like
+    // `ca:example`, to the URLs of their certificates in PEM.
+    #[serde(default, rename = "trustStores")]
+    pub trust_stores: BTreeMap<String, String>,
+}
+
+#[async_trait]
+impl SignScheme for NotationParameters {
+    async fn init(&mut self, _config: &Paths) -> Result<()> {
+        Ok(())
+    }
+
+    /// Judge whether an image is allowed by this SignScheme.
+    #[cfg(feature = "signature-notation")]
+    async fn allows_image(&self, image: &mut Image, auth: &RegistryAuth) -> Result<()> {
+        let document = resource::get_resource(&self.trust_policy_path)
+            .await
+            .context("get notation trust policy")?;
+        let document = TrustPolicyDocument::from_slice(&document)?;
+        let policy = document.policy_for(&get_image_repository_full_name(&image.reference))?;
+        if policy.signature_verification.level == VerificationLevel::Skip {
+            return Ok(());
+        }
+
+        let mut trust_stores = HashMap::new();
+        for store in &policy.trust_stores {
+            let Some(path) = self.trust_stores.get(store) else {
+                continue;
+            };
+            let certs = resource::get_resource(path)
+                .await
+                .with_context(|| format!("get notation trust store {store}"))?;
+            let certs = X509::stack_from_pem(&certs)
+                .map_err(|e| anyhow!("invalid certificates of trust store {store}: {e}"))?;
+            trust_stores.insert(store.clone(), certs);
+        }
+
+        let image_digest = image.manifest_digest.to_string();
+        let signatures = self.get_signatures(image, auth).await?;
+        if signatures.is_empty() {
+            bail!("no notation signature of image {}", image.reference.whole());
+        }
+
+        // The image is allowed by any signature verified.
+        for (media_type, signature) in signatures {
+            let result = Envelope::parse(&media_type, &signature).and_then(|envelope| {
+                verify::verify_envelope(&envelope, &image_digest, policy, &trust_stores)
+            });
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) => warn!("notation signature is refused: {:?}", e),
+            }
+        }
+
+        bail!(
+            "no notation signature of image {} is verified by trust policy {:?}",
+            image.reference.whole(),
+            policy.name
+        )
+    }
+
+    #[cfg(not(feature = "signature-notation"))]
+    async fn allows_image(&self, _image: &mut Image, _auth: &RegistryAuth) -> Result<()> {
+        bail!("feature \"signature-notation\" not enabled.")
+    }
+}
+
+#[cfg(feature = "signature-notation")]
+impl NotationParameters {
+    /// Get the notation signatures of the image with their media types,
+    /// which are referrers of the image.
+    async fn get_signatures(
+        &self,
+        image: &Image,
+        auth: &RegistryAuth,
+    ) -> Result<Vec<(String, Vec<u8>)>> {
+        let referrers = Referrers::new(image, auth);
+        let mut signatures = Vec::new();
+        for referrer in referrers.get(Some(NOTATION_ARTIFACT_TYPE)).await? {
+            // The artifact type is in the config media type, unless the
+            // registry supports the artifact type of the manifests.
+            if referrer.artifact_type.is_none()
+                && referrer.manifest.config.media_type != NOTATION_ARTIFACT_TYPE
+            {
+                continue;
+            }
+
+            let [layer] = &referrer.manifest.layers[..] else {
+                continue;
+            };
+            if ![envelope::MEDIA_TYPE_JWS, envelope::MEDIA_TYPE_COSE]
+                .contains(&layer.media_type.as_str())
+            {
+                continue;
+            }
+
+            let signature = referrers.pull_layer(layer, MAX_ENVELOPE_SIZE).await?;
+            signatures.push((layer.media_type.clone(), signature));
+        }
+
+        Ok(signatures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::policy::policy_requirement::PolicyReqType;
+
+    #[test]
+    fn deserialize_notation_policy() {
+        let json = r#"{
+            "type": "notationSigned",
+            "trustPolicyPath": "kbs:///default/notation/trustpolicy.json",
+            "trustStores": {
+                "ca:example": "kbs:///default/notation/example-ca.pem"
+            }
+        }"#;
+
+        let mut trust_stores = BTreeMap::new();
+        trust_stores.insert(
+            "ca:example".to_string(),
+            "kbs:///default/notation/example-ca.pem".to_string(),
+        );
+        let policy = PolicyReqType::Notation(NotationParameters {
+            trust_policy_path: "kbs:///default/notation/trustpolicy.json".to_string(),
+            trust_stores,
+        });
+
+        let parsed: PolicyReqType = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, policy);
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Trust policy of notation, refer to
+//! <https://github.com/notaryproject/specifications/blob/main/specs/trust-store-trust-policy.md>.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use serde::Deserialize;
+
+/// Scope matching any repository.
+const WILDCARD: &str = "*";
+
+/// Prefix of the trusted identities by the subject of the signing
+/// certificate.
+const X509_SUBJECT_PREFIX: &str = "x509.subject:";
+
+/// Trust store types, refer to the signing schemes of the envelopes.
+pub const TRUST_STORE_TYPE_CA: &str = "ca";
+pub const TRUST_STORE_TYPE_SIGNING_AUTHORITY: &str = "signingAuthority";
+
+/// A trust policy document, i.e. `trustpolicy.json` of notation.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustPolicyDocument {
+    pub version: String,
+    pub trust_policies: Vec<TrustPolicy>,
+}
+
+/// A trust policy for the repositories of its registry scopes.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustPolicy {
+    pub name: String,
+    pub registry_scopes: Vec<String>,
+    pub signature_verification: SignatureVerification,
+    #[serde(default)]
+    pub trust_stores: Vec<String>,
+    #[serde(default)]
+    pub trusted_identities: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignatureVerification {
+    pub level: VerificationLevel,
+
+    /// Actions overriding those of the level for some checks.
+    #[serde(default, rename = "override")]
+    pub overrides: HashMap<String, Action>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationLevel {
+    Strict,
+    Permissive,
+    Audit,
+    Skip,
+}
+
+/// What to do when a check of the signature fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// Refuse the signature.
+    Enforce,
+    /// Log the failure and accept the signature.
+    Log,
+    /// Skip the check.
+    Skip,
+}
+
+/// The checks of a signature with configurable actions. The integrity
+/// and the match of the signed digest are always enforced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// The signing certificate chains to the trust store and has a trusted
+    /// identity.
+    Authenticity,
+    /// The signature is not expired.
+    Expiry,
+}
+
+impl TrustPolicyDocument {
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let document: Self = serde_json::from_slice(data)
+            .map_err(|e| anyhow!("failed to parse notation trust policy {e}"))?;
+        document.validate()?;
+        Ok(document)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.version != "1.0" {
+            bail!("unsupported trust policy version {:?}", self.version);
+        }
+
+        let mut names = HashSet::new();
+        let mut scopes = HashSet::new();
+        for policy in &self.trust_policies {
+            if !names.insert(&policy.name) {
+                bail!("duplicated trust policy {:?}", policy.name);
+            }
+            policy.validate()?;
+
+            for scope in &policy.registry_scopes {
+                if !scopes.insert(scope) {
+                    bail!(
+                        "registry scope {:?} is in more than one trust policy",
+                        scope
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the trust policy of the `repository`, i.e. `registry/repo`,
+    /// preferring the policy naming it to the wildcard policy.
+    pub fn policy_for(&self, repository: &str) -> Result<&TrustPolicy> {
+        self.trust_policies
+            .iter()
+            .find(|policy| {
+                policy
+                    .registry_scopes
+                    .iter()
+                    .any(|scope| scope == repository)
+            })
+            .or_else(|| {
+                self.trust_policies
+                    .iter()
+                    .find(|policy| policy.registry_scopes.iter().any(|scope| scope == WILDCARD))
+            })
+            .ok_or_else(|| anyhow!("no notation trust policy for {}", repository))
+    }
+}
+
+impl TrustPolicy {
+    fn validate(&self) -> Result<()> {
+        if self.registry_scopes.is_empty() {
+            bail!("trust policy {:?} has no registry scope", self.name);
+        }
+
+        if self.registry_scopes.len() > 1 && self.registry_scopes.iter().any(|s| s == WILDCARD) {
+            bail!(
+                "wildcard registry scope of trust policy {:?} is not alone",
+                self.name
+            );
+        }
+
+        if self.signature_verification.level == VerificationLevel::Skip {
+            if !self.trust_stores.is_empty() || !self.trusted_identities.is_empty() {
+                bail!(
+                    "trust policy {:?} skipping the verification has trust stores or identities",
+                    self.name
+                );
+            }
+            return Ok(());
+        }
+
+        if self.trust_stores.is_empty() || self.trusted_identities.is_empty() {
+            bail!(
+                "trust policy {:?} needs trust stores and trusted identities",
+                self.name
+            );
+        }
+
+        for store in &self.trust_stores {
+            match store.split_once(':') {
+                Some((TRUST_STORE_TYPE_CA | TRUST_STORE_TYPE_SIGNING_AUTHORITY, name))
+                    if !name.is_empty() => {}
+                _ => bail!("invalid trust store {:?}", store),
+            }
+        }
+
+        for identity in &self.trusted_identities {
+            if identity != WILDCARD {
+                let subject = parse_x509_subject(identity)?;
+                for field in ["C", "ST", "O"] {
+                    if !subject.iter().any(|(name, _)| name == field) {
+                        bail!("trusted identity {:?} has no {} field", identity, field);
+                    }
+                }
+            }
+        }
+
+        for check in self.signature_verification.overrides.keys() {
+            match check.as_str() {
+                "authenticity" | "expiry" => {}
+                // The signing time is checked with the authenticity, and
+                // the revocation is not checked.
+                "authenticTimestamp" | "revocation" => {
+                    warn!("override of {} of notation is ignored", check)
+                }
+                "integrity" => bail!("integrity of trust policy {:?} is overridden", self.name),
+                check => bail!("unknown check {:?} of trust policy {:?}", check, self.name),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the action of a failed `check`.
+    pub fn action(&self, check: Check) -> Action {
+        let (name, action) = match (check, self.signature_verification.level) {
+            (_, VerificationLevel::Skip) => return Action::Skip,
+            (Check::Authenticity, VerificationLevel::Audit) => ("authenticity", Action::Log),
+            (Check::Authenticity, _) => ("authenticity", Action::Enforce),
+            (Check::Expiry, VerificationLevel::Strict) => ("expiry", Action::Enforce),
+            (Check::Expiry, _) => ("expiry", Action::Log),
+        };
+
+        self.signature_verification
+            .overrides
+            .get(name)
+            .copied()
+            .unwrap_or(action)
+    }
+
+    /// Get the names of the trust stores of `store_type`.
+    pub fn trust_stores_of<'a>(&'a self, store_type: &'a str) -> impl Iterator<Item = &'a str> {
+        self.trust_stores.iter().filter_map(move |store| {
+            store
+                .split_once(':')
+                .filter(|(t, _)| *t == store_type)
+                .map(|_| store.as_str())
+        })
+    }
+
+    /// Check whether the fields of the subject of a signing certificate
+    /// match a trusted identity, i.e. contain all of its fields.
+    pub fn is_trusted_identity(&self, subject: &[(String, String)]) -> Result<bool> {
+        for identity in &self.trusted_identities {
+            if identity == WILDCARD {
+                return Ok(true);
+            }
+
+            let trusted = parse_x509_subject(identity)?;
+            if trusted.iter().all(|field| subject.contains(field)) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/// Parse a trusted identity like `x509.subject: C=US, ST=WA, O=example`
+/// into the fields of the subject, where `\` escapes a `,` or `=`.
+fn parse_x509_subject(identity: &str) -> Result<Vec<(String, String)>> {
+    let subject = identity
+        .strip_prefix(X509_SUBJECT_PREFIX)
+        .ok_or_else(|| anyhow!("unsupported trusted identity {:?}", identity))?;
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = subject.chars();
+    let mut push = |field: &str| -> Result<()> {
+        let (name, value) = split_unescaped(field, '=')
+            .ok_or_else(|| anyhow!("invalid field {:?} of trusted identity", field))?;
+        fields.push((unescape(name.trim()), unescape(value.trim())));
+        Ok(())
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                field.push(c);
+                if let Some(c) = chars.next() {
+                    field.push(c);
+                }
+            }
+            ',' => {
+                push(&field)?;
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    push(&field)?;
+
+    Ok(fields)
+}
+
+fn split_unescaped(field: &str, separator: char) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in field.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => return Some((&field[..i], &field[i + 1..])),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRUST_POLICY: &str = r#"{
+        "version": "1.0",
+        "trustPolicies": [
+            {
+                "name": "example-images",
+                "registryScopes": ["docker.io/example/busybox"],
+                "signatureVerification": {
+                    "level": "permissive",
+                    "override": {"authenticity": "log"}
+                },
+                "trustStores": ["ca:example", "signingAuthority:signer"],
+                "trustedIdentities": [
+                    "x509.subject: C=US, ST=WA, O=example\\, inc., CN=signer"
+                ]
+            },
+            {
+                "name": "default",
+                "registryScopes": ["*"],
+                "signatureVerification": {"level": "strict"},
+                "trustStores": ["ca:example"],
+                "trustedIdentities": ["*"]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_trust_policy() {
+        let document = TrustPolicyDocument::from_slice(TRUST_POLICY.as_bytes()).unwrap();
+
+        let policy = document.policy_for("docker.io/example/busybox").unwrap();
+        assert_eq!(policy.name, "example-images");
+        assert_eq!(policy.action(Check::Authenticity), Action::Log);
+        assert_eq!(policy.action(Check::Expiry), Action::Log);
+        assert_eq!(
+            policy
+                .trust_stores_of(TRUST_STORE_TYPE_CA)
+                .collect::<Vec<_>>(),
+            ["ca:example"]
+        );
+
+        let subject = |fields: &[(&str, &str)]| -> Vec<(String, String)> {
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        assert!(policy
+            .is_trusted_identity(&subject(&[
+                ("C", "US"),
+                ("ST", "WA"),
+                ("L", "Seattle"),
+                ("O", "example, inc."),
+                ("CN", "signer"),
+            ]))
+            .unwrap());
+        assert!(!policy
+            .is_trusted_identity(&subject(&[("C", "US"), ("ST", "WA"), ("O", "example")]))
+            .unwrap());
+
+        let policy = document.policy_for("quay.io/example/busybox").unwrap();
+        assert_eq!(policy.name, "default");
+        assert_eq!(policy.action(Check::Authenticity), Action::Enforce);
+        assert_eq!(policy.action(Check::Expiry), Action::Enforce);
+        assert!(policy.is_trusted_identity(&[]).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_trust_policy() {
+        let invalid = [
+            // The wildcard is not alone.
+            r#"{"version": "1.0", "trustPolicies": [{"name": "a", "registryScopes": ["*", "docker.io/busybox"], "signatureVerification": {"level": "strict"}, "trustStores": ["ca:a"], "trustedIdentities": ["*"]}]}"#,
+            // No trust store.
+            r#"{"version": "1.0", "trustPolicies": [{"name": "a", "registryScopes": ["*"], "signatureVerification": {"level": "strict"}, "trustedIdentities": ["*"]}]}"#,
+            // An identity without the state.
+            r#"{"version": "1.0", "trustPolicies": [{"name": "a", "registryScopes": ["*"], "signatureVerification": {"level": "strict"}, "trustStores": ["ca:a"], "trustedIdentities": ["x509.subject: C=US, O=example"]}]}"#,
+            // The integrity is not overridable.
+            r#"{"version": "1.0", "trustPolicies": [{"name": "a", "registryScopes": ["*"], "signatureVerification": {"level": "strict", "override": {"integrity": "log"}}, "trustStores": ["ca:a"], "trustedIdentities": ["*"]}]}"#,
+            // A scope in two policies.
+            r#"{"version": "1.0", "trustPolicies": [{"name": "a", "registryScopes": ["*"], "signatureVerification": {"level": "skip"}}, {"name": "b", "registryScopes": ["*"], "signatureVerification": {"level": "skip"}}]}"#,
+        ];
+
+        for document in invalid {
+            assert!(TrustPolicyDocument::from_slice(document.as_bytes()).is_err());
+        }
+    }
+}
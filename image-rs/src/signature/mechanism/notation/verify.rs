@@ -0,0 +1,241 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Verification of a signature envelope against a trust policy.
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use openssl::{
+    stack::Stack,
+    x509::{
+        store::X509StoreBuilder, verify::X509VerifyParam, X509StoreContext, X509VerifyResult, X509,
+    },
+};
+
+use super::envelope::{Envelope, SIGNING_SCHEME_X509, SIGNING_SCHEME_X509_SIGNING_AUTHORITY};
+use super::trust_policy::{
+    Action, Check, TrustPolicy, TRUST_STORE_TYPE_CA, TRUST_STORE_TYPE_SIGNING_AUTHORITY,
+};
+
+/// Verify that `envelope` signs `image_digest` by the trust policy, with
+/// the certificates of the trust stores by name, e.g. `ca:example`.
+pub fn verify_envelope(
+    envelope: &Envelope,
+    image_digest: &str,
+    policy: &TrustPolicy,
+    trust_stores: &HashMap<String, Vec<X509>>,
+) -> Result<()> {
+    let payload = envelope.verify_integrity()?;
+    if payload.target_artifact.digest != image_digest {
+        bail!(
+            "signature is for digest {:?} rather than {:?}",
+            payload.target_artifact.digest,
+            image_digest
+        );
+    }
+
+    enforce(
+        policy,
+        Check::Authenticity,
+        verify_authenticity(envelope, policy, trust_stores),
+    )?;
+
+    let expired = match envelope.expiry {
+        Some(expiry) if expiry < now() => Err(anyhow!("signature expired at {}", expiry)),
+        _ => Ok(()),
+    };
+    enforce(policy, Check::Expiry, expired)?;
+
+    Ok(())
+}
+
+fn enforce(policy: &TrustPolicy, check: Check, result: Result<()>) -> Result<()> {
+    match (result, policy.action(check)) {
+        (Err(e), Action::Enforce) => Err(e),
+        (Err(e), Action::Log) => {
+            warn!(
+                "{:?} check of trust policy {:?} failed: {:?}",
+                check, policy.name, e
+            );
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn verify_authenticity(
+    envelope: &Envelope,
+    policy: &TrustPolicy,
+    trust_stores: &HashMap<String, Vec<X509>>,
+) -> Result<()> {
+    // The certificates of a signing authority are checked at the signing
+    // time it asserts, and the others at the present time.
+    let (store_type, time) = match envelope.signing_scheme.as_str() {
+        SIGNING_SCHEME_X509 => (TRUST_STORE_TYPE_CA, None),
+        SIGNING_SCHEME_X509_SIGNING_AUTHORITY => (
+            TRUST_STORE_TYPE_SIGNING_AUTHORITY,
+            envelope.authentic_signing_time,
+        ),
+        scheme => bail!("unsupported signing scheme {:?}", scheme),
+    };
+
+    let mut roots = Vec::new();
+    for store in policy.trust_stores_of(store_type) {
+        let certs = trust_stores
+            .get(store)
+            .ok_or_else(|| anyhow!("trust store {} is not configured", store))?;
+        roots.extend(certs.iter().cloned());
+    }
+
+    if roots.is_empty() {
+        bail!(
+            "no {} trust store in trust policy {:?}",
+            store_type,
+            policy.name
+        );
+    }
+
+    verify_chain(&envelope.cert_chain, &roots, time)?;
+
+    let subject: Vec<(String, String)> = envelope.cert_chain[0]
+        .subject_name()
+        .entries()
+        .filter_map(|entry| {
+            let name = entry.object().nid().short_name().ok()?;
+            let value = std::str::from_utf8(entry.data().as_slice()).ok()?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect();
+
+    if !policy.is_trusted_identity(&subject)? {
+        bail!(
+            "signing certificate {:?} is not a trusted identity",
+            subject
+        );
+    }
+
+    Ok(())
+}
+
+/// Verify that `chain`, from the signing certificate, chains to one of
+/// `roots`, at `time` in seconds since the Unix epoch or at present.
+fn verify_chain(chain: &[X509], roots: &[X509], time: Option<i64>) -> Result<()> {
+    let mut store = X509StoreBuilder::new()?;
+    for root in roots {
+        store.add_cert(root.clone())?;
+    }
+    if let Some(time) = time {
+        let mut param = X509VerifyParam::new()?;
+        param.set_time(time);
+        store.set_param(&param)?;
+    }
+    let store = store.build();
+
+    let mut intermediates = Stack::new()?;
+    for cert in &chain[1..] {
+        intermediates.push(cert.clone())?;
+    }
+
+    let mut context = X509StoreContext::new()?;
+    let result = context.init(&store, &chain[0], &intermediates, |context| {
+        context.verify_cert()?;
+        Ok(context.error())
+    })?;
+
+    if result != X509VerifyResult::OK {
+        bail!(
+            "signing certificate chain is not trusted: {}",
+            result.error_string()
+        );
+    }
+
+    Ok(())
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::envelope::{tests::*, MEDIA_TYPE_COSE, MEDIA_TYPE_JWS};
+    use super::super::trust_policy::TrustPolicyDocument;
+    use super::*;
+
+    fn policy(level: &str, identity: &str) -> TrustPolicy {
+        let document = serde_json::json!({
+            "version": "1.0",
+            "trustPolicies": [{
+                "name": "test",
+                "registryScopes": ["*"],
+                "signatureVerification": {"level": level},
+                "trustStores": ["ca:test"],
+                "trustedIdentities": [identity],
+            }],
+        });
+        TrustPolicyDocument::from_slice(document.to_string().as_bytes())
+            .unwrap()
+            .trust_policies
+            .remove(0)
+    }
+
+    #[test]
+    fn test_verify_envelope() {
+        let root_key = rsa_key();
+        let root = certificate("C=US, ST=WA, O=example, CN=root", &root_key, None, 10);
+        let key = ec_key();
+        let leaf = certificate(
+            "C=US, ST=WA, O=example, CN=signer",
+            &key,
+            Some((&root, &root_key)),
+            1,
+        );
+
+        let mut trust_stores = HashMap::new();
+        trust_stores.insert("ca:test".to_string(), vec![root.clone()]);
+        let strict = policy("strict", "x509.subject: C=US, ST=WA, O=example, CN=signer");
+
+        let data = cose_envelope(&payload(), &key, &[&leaf, &root]);
+        let envelope = Envelope::parse(MEDIA_TYPE_COSE, &data).unwrap();
+        verify_envelope(&envelope, IMAGE_DIGEST, &strict, &trust_stores).unwrap();
+
+        // Another image.
+        let other_digest =
+            "sha256:0000000000000000000000000000000000000000000000000000000000000000";
+        assert!(verify_envelope(&envelope, other_digest, &strict, &trust_stores).is_err());
+
+        // An untrusted identity is only logged in the audit level.
+        let other = policy("strict", "x509.subject: C=US, ST=WA, O=other");
+        assert!(verify_envelope(&envelope, IMAGE_DIGEST, &other, &trust_stores).is_err());
+        let audit = policy("audit", "x509.subject: C=US, ST=WA, O=other");
+        verify_envelope(&envelope, IMAGE_DIGEST, &audit, &trust_stores).unwrap();
+
+        // A certificate not chaining to the trust store.
+        let other_root_key = rsa_key();
+        let other_root = certificate("C=US, ST=WA, O=other, CN=root", &other_root_key, None, 10);
+        trust_stores.insert("ca:test".to_string(), vec![other_root]);
+        assert!(verify_envelope(&envelope, IMAGE_DIGEST, &strict, &trust_stores).is_err());
+        trust_stores.insert("ca:test".to_string(), vec![root]);
+
+        // An expired signature is only logged in the permissive level.
+        let key = rsa_key();
+        let leaf = certificate(
+            "C=US, ST=WA, O=example, CN=signer",
+            &key,
+            Some((&trust_stores["ca:test"][0], &root_key)),
+            1,
+        );
+        let data = jws_envelope(&payload(), &key, &[&leaf], Some("2023-01-01T00:00:00Z"));
+        let envelope = Envelope::parse(MEDIA_TYPE_JWS, &data).unwrap();
+        assert!(verify_envelope(&envelope, IMAGE_DIGEST, &strict, &trust_stores).is_err());
+        let permissive = policy("permissive", "*");
+        verify_envelope(&envelope, IMAGE_DIGEST, &permissive, &trust_stores).unwrap();
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! In-toto attestations in DSSE envelopes, refer to
+//! <https://github.com/in-toto/attestation/blob/main/spec/README.md> and
+//! <https://github.com/secure-systems-lab/dsse/blob/master/envelope.md>.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+use openssl::{
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{Id, PKey, Public},
+    sign::Verifier,
+};
+use serde::Deserialize;
+
+/// Payload type of the in-toto statements in DSSE envelopes.
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// Predicate types of the SLSA provenance.
+pub const SLSA_PROVENANCE_V02: &str = "https://slsa.dev/provenance/v0.2";
+pub const SLSA_PROVENANCE_V1: &str = "https://slsa.dev/provenance/v1";
+
+/// A DSSE envelope.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    payload_type: String,
+    payload: String,
+    signatures: Vec<Signature>,
+}
+
+#[derive(Deserialize)]
+struct Signature {
+    sig: String,
+}
+
+/// An in-toto statement.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    pub subject: Vec<Subject>,
+    pub predicate_type: String,
+    #[serde(default)]
+    pub predicate: serde_json::Value,
+}
+
+/// An artifact which a statement is about.
+#[derive(Debug, Deserialize)]
+pub struct Subject {
+    #[serde(default)]
+    pub name: String,
+    pub digest: HashMap<String, String>,
+}
+
+impl Statement {
+    /// Check whether the statement is about the artifact of `digest`,
+    /// like `sha256:<hex>`.
+    pub fn is_about(&self, digest: &str) -> bool {
+        let Some((algorithm, value)) = digest.split_once(':') else {
+            return false;
+        };
+
+        self.subject
+            .iter()
+            .any(|subject| subject.digest.get(algorithm).map(String::as_str) == Some(value))
+    }
+
+    /// Get the ID of the builder of a SLSA provenance.
+    pub fn builder_id(&self) -> Result<&str> {
+        let builder = match self.predicate_type.as_str() {
+            SLSA_PROVENANCE_V02 => &self.predicate["builder"],
+            SLSA_PROVENANCE_V1 => &self.predicate["runDetails"]["builder"],
+            predicate_type => bail!("unsupported provenance predicate {:?}", predicate_type),
+        };
+
+        builder["id"]
+            .as_str()
+            .ok_or_else(|| anyhow!("no builder id in the provenance"))
+    }
+}
+
+/// Check whether the builder `id` matches a `trusted` builder id, where
+/// one without `@<ref>` matches the builder at any ref.
+pub fn is_trusted_builder(id: &str, trusted: &str) -> bool {
+    id == trusted
+        || (!trusted.contains('@') && id.split_once('@').map(|(id, _)| id) == Some(trusted))
+}
+
+/// Verify a DSSE envelope of an in-toto statement signed by `key`, and get
+/// the statement.
+pub fn verify_envelope(data: &[u8], key: &PKey<Public>) -> Result<Statement> {
+    let envelope: Envelope =
+        serde_json::from_slice(data).map_err(|e| anyhow!("invalid DSSE envelope {e}"))?;
+    if envelope.payload_type != IN_TOTO_PAYLOAD_TYPE {
+        bail!("unsupported DSSE payload type {:?}", envelope.payload_type);
+    }
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let payload = engine.decode(&envelope.payload)?;
+    let signed = pae(&envelope.payload_type, &payload);
+
+    let mut verified = false;
+    for signature in &envelope.signatures {
+        let signature = engine.decode(&signature.sig)?;
+        if verify_signature(key, &signed, &signature)? {
+            verified = true;
+            break;
+        }
+    }
+
+    if !verified {
+        bail!("no signature of the DSSE envelope matches the key");
+    }
+
+    serde_json::from_slice(&payload).map_err(|e| anyhow!("invalid in-toto statement {e}"))
+}
+
+/// Get the pre-authentication encoding of DSSE.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    encoded.extend(payload);
+    encoded
+}
+
+/// Verify an ECDSA signature in DER, an RSA PKCS#1 v1.5 one, or an Ed25519
+/// one.
+fn verify_signature(key: &PKey<Public>, data: &[u8], signature: &[u8]) -> Result<bool> {
+    let mut verifier = match key.id() {
+        Id::EC => {
+            let digest = match key.ec_key()?.group().curve_name() {
+                Some(Nid::SECP384R1) => MessageDigest::sha384(),
+                Some(Nid::SECP521R1) => MessageDigest::sha512(),
+                _ => MessageDigest::sha256(),
+            };
+            Verifier::new(digest, key)?
+        }
+        Id::RSA => Verifier::new(MessageDigest::sha256(), key)?,
+        Id::ED25519 => {
+            let mut verifier = Verifier::new_without_digest(key)?;
+            return Ok(verifier.verify_oneshot(signature, data)?);
+        }
+        _ => bail!("unsupported attestation key type"),
+    };
+
+    verifier.update(data)?;
+    // A malformed signature is reported as an error by openssl.
+    Ok(verifier.verify(signature).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::{
+        ec::{EcGroup, EcKey},
+        pkey::Private,
+        sign::Signer,
+    };
+
+    const IMAGE_DIGEST: &str =
+        "sha256:7b3ccabffc97de872a30dfd234fd972a66d247c8cfc69b0550f276481852627c";
+
+    const BUILDER_ID: &str =
+        "https://github.com/slsa-framework/slsa-github-generator/.github/workflows/generator_container_slsa3.yml@refs/tags/v1.9.0";
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn public(key: &PKey<Private>) -> PKey<Public> {
+        PKey::public_key_from_pem(&key.public_key_to_pem().unwrap()).unwrap()
+    }
+
+    /// Sign a SLSA provenance v0.2 of the image built by `builder_id`.
+    fn envelope(key: &PKey<Private>, builder_id: &str) -> Vec<u8> {
+        let statement = serde_json::json!({
+            "_type": "https://in-toto.io/Statement/v0.1",
+            "subject": [{
+                "name": "docker.io/example/busybox",
+                "digest": {"sha256": IMAGE_DIGEST.strip_prefix("sha256:").unwrap()},
+            }],
+            "predicateType": SLSA_PROVENANCE_V02,
+            "predicate": {
+                "builder": {"id": builder_id},
+                "buildType": "https://github.com/slsa-framework/slsa-github-generator/container@v1",
+            },
+        })
+        .to_string();
+
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer
+            .update(&pae(IN_TOTO_PAYLOAD_TYPE, statement.as_bytes()))
+            .unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        serde_json::json!({
+            "payloadType": IN_TOTO_PAYLOAD_TYPE,
+            "payload": engine.encode(&statement),
+            "signatures": [{"keyid": "", "sig": engine.encode(signer.sign_to_vec().unwrap())}],
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_verify_envelope() {
+        let key = key();
+        let data = envelope(&key, BUILDER_ID);
+
+        let statement = verify_envelope(&data, &public(&key)).unwrap();
+        assert!(statement.is_about(IMAGE_DIGEST));
+        assert!(!statement
+            .is_about("sha256:0000000000000000000000000000000000000000000000000000000000000000"));
+        assert_eq!(statement.builder_id().unwrap(), BUILDER_ID);
+
+        // An envelope signed by another key is refused.
+        assert!(verify_envelope(&data, &public(&self::tests::key())).is_err());
+    }
+
+    #[test]
+    fn test_slsa_v1_builder_id() {
+        let statement: Statement = serde_json::from_value(serde_json::json!({
+            "subject": [],
+            "predicateType": SLSA_PROVENANCE_V1,
+            "predicate": {"runDetails": {"builder": {"id": BUILDER_ID}}},
+        }))
+        .unwrap();
+        assert_eq!(statement.builder_id().unwrap(), BUILDER_ID);
+    }
+
+    #[test]
+    fn test_is_trusted_builder() {
+        let (id, _) = BUILDER_ID.split_once('@').unwrap();
+        assert!(is_trusted_builder(BUILDER_ID, BUILDER_ID));
+        assert!(is_trusted_builder(BUILDER_ID, id));
+        assert!(!is_trusted_builder(
+            BUILDER_ID,
+            &format!("{id}@refs/tags/v1.8.0")
+        ));
+        assert!(!is_trusted_builder(
+            BUILDER_ID,
+            "https://github.com/slsa-framework"
+        ));
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! In-toto provenance attestation verification
+
+#[cfg(feature = "signature-provenance")]
+use anyhow::{anyhow, Context};
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+#[cfg(feature = "signature-provenance")]
+use log::{info, warn};
+use oci_distribution::secrets::RegistryAuth;
+#[cfg(feature = "signature-provenance")]
+use openssl::pkey::PKey;
+use serde::{Deserialize, Serialize};
+
+use super::SignScheme;
+#[cfg(feature = "signature-provenance")]
+use crate::resource;
+#[cfg(feature = "signature-provenance")]
+use crate::signature::referrers::Referrers;
+use crate::signature::{image::Image, mechanism::Paths};
+
+#[cfg(feature = "signature-provenance")]
+pub mod attestation;
+
+/// Media type of the layers of DSSE envelopes.
+pub const DSSE_MEDIA_TYPE: &str = "application/vnd.dsse.envelope.v1+json";
+
+/// Max size of an attestation accepted.
+#[cfg(feature = "signature-provenance")]
+const MAX_ATTESTATION_SIZE: i64 = 4 * 1024 * 1024;
+
+#[derive(Deserialize, Debug, Eq, PartialEq, Serialize, Default)]
+pub struct ProvenanceParameters {
+    // KeyPath is the URL of the public key in PEM which the attestations
+    // are signed with, like `kbs:///default/provenance/key.pub`.
+    #[serde(rename = "keyPath")]
+    pub key_path: String,
+
+    // BuilderIDs are the IDs of the trusted builders, one of which the SLSA
+    // provenance must be built by. An ID without `@<ref>` trusts the
+    // builder at any ref.
+    #[serde(rename = "builderIDs")]
+    pub builder_ids: Vec<String>,
+}
+
+#[async_trait]
+impl SignScheme for ProvenanceParameters {
+    async fn init(&mut self, _config: &Paths) -> Result<()> {
+        Ok(())
+    }
+
+    /// Judge whether an image is allowed by this SignScheme.
+    #[cfg(feature = "signature-provenance")]
+    async fn allows_image(&self, image: &mut Image, auth: &RegistryAuth) -> Result<()> {
+        if self.builder_ids.is_empty() {
+            bail!("No builderIDs of the provenance is specified.");
+        }
+
+        let key = resource::get_resource(&self.key_path)
+            .await
+            .context("get provenance attestation key")?;
+        let key = PKey::public_key_from_pem(&key)
+            .map_err(|e| anyhow!("invalid provenance attestation key {e}"))?;
+
+        let image_digest = image.manifest_digest.to_string();
+        let attestations = get_attestations(image, auth).await?;
+        for attestation in attestations {
+            let statement = match attestation::verify_envelope(&attestation, &key) {
+                Ok(statement) => statement,
+                Err(e) => {
+                    warn!("provenance attestation is refused: {:?}", e);
+                    continue;
+                }
+            };
+
+            if !statement.is_about(&image_digest) {
+                continue;
+            }
+
+            // Other attestations of the image, like SBOMs, are skipped.
+            let Ok(builder_id) = statement.builder_id() else {
+                continue;
+            };
+
+            if self
+                .builder_ids
+                .iter()
+                .any(|trusted| attestation::is_trusted_builder(builder_id, trusted))
+            {
+                return Ok(());
+            }
+
+            info!("provenance of image built by untrusted {}", builder_id);
+        }
+
+        bail!(
+            "no provenance attestation of image {} by a trusted builder",
+            image.reference.whole()
+        )
+    }
+
+    #[cfg(not(feature = "signature-provenance"))]
+    async fn allows_image(&self, _image: &mut Image, _auth: &RegistryAuth) -> Result<()> {
+        bail!("feature \"signature-provenance\" not enabled.")
+    }
+}
+
+/// Get the DSSE envelopes attesting the image, from its referrers and the
+/// attestations attached by cosign.
+#[cfg(feature = "signature-provenance")]
+async fn get_attestations(image: &Image, auth: &RegistryAuth) -> Result<Vec<Vec<u8>>> {
+    let referrers = Referrers::new(image, auth);
+    let mut artifacts = referrers.get(None).await?;
+    artifacts.extend(referrers.cosign_artifact("att").await?);
+
+    let mut attestations = Vec::new();
+    for artifact in &artifacts {
+        for layer in &artifact.manifest.layers {
+            if layer.media_type == DSSE_MEDIA_TYPE {
+                attestations.push(referrers.pull_layer(layer, MAX_ATTESTATION_SIZE).await?);
+            }
+        }
+    }
+
+    Ok(attestations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::policy::policy_requirement::PolicyReqType;
+
+    #[test]
+    fn deserialize_provenance_policy() {
+        let json = r#"{
+            "type": "provenanceAttested",
+            "keyPath": "kbs:///default/provenance/key.pub",
+            "builderIDs": [
+                "https://github.com/slsa-framework/slsa-github-generator/.github/workflows/generator_container_slsa3.yml"
+            ]
+        }"#;
+
+        let policy = PolicyReqType::Provenance(ProvenanceParameters {
+            key_path: "kbs:///default/provenance/key.pub".to_string(),
+            builder_ids: vec![
+                "https://github.com/slsa-framework/slsa-github-generator/.github/workflows/generator_container_slsa3.yml".to_string(),
+            ],
+        });
+
+        let parsed: PolicyReqType = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed, policy);
+    }
+}
@@ -7,12 +7,23 @@ pub mod image;
 pub mod mechanism;
 pub mod payload;
 pub mod policy;
+#[cfg(any(feature = "signature-notation", feature = "signature-provenance"))]
+pub mod referrers;
 
-use crate::{config::Paths, signature::policy::Policy};
-use std::convert::TryFrom;
+use crate::{
+    config::{Paths, PolicyConfig},
+    signature::policy::Policy,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use log::warn;
 use oci_distribution::secrets::RegistryAuth;
+use sha2::Digest;
+
+/// Digest of the policy last measured, so that the same policy is not
+/// measured again by each pull.
+#[cfg(feature = "measurement")]
+static MEASURED_POLICY: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
 
 /// `allows_image` will check all the `PolicyRequirements` suitable for
 /// the given image. The `PolicyRequirements` is defined in
@@ -23,16 +34,17 @@ pub async fn allows_image(
     image_digest: &str,
     auth: &RegistryAuth,
     file_paths: &Paths,
+    policy_config: &PolicyConfig,
 ) -> Result<()> {
-    use crate::{resource, signature::image::Image};
+    use crate::signature::image::Image;
 
-    let reference = oci_distribution::Reference::try_from(image_reference)?;
+    let reference = crate::registry::parse_reference(image_reference)?;
     let mut image = Image::default_with_reference(reference);
     image.set_manifest_digest(image_digest)?;
 
     // Read the set of signature schemes that need to be verified
     // of the image from the policy configuration.
-    let policy_json_string = resource::get_resource(&file_paths.policy_path).await?;
+    let policy_json_string = get_policy(file_paths, policy_config).await?;
     let mut policy = serde_json::from_slice::<Policy>(&policy_json_string)?;
     let schemes = policy.signature_schemes(&image);
 
@@ -46,3 +58,95 @@ pub async fn allows_image(
         .await
         .map_err(|e| anyhow::anyhow!("Validate image failed: {:?}", e))
 }
+
+/// Get the policy, either inline or from `policy_path`, falling back to
+/// the local policy if configured. Its digest is checked against the
+/// pinned one, and measured if configured, before it is used.
+async fn get_policy(file_paths: &Paths, config: &PolicyConfig) -> Result<Vec<u8>> {
+    use crate::resource;
+
+    let policy = match &config.inline {
+        Some(policy) => serde_json::to_vec(policy)?,
+        None => match resource::get_resource(&file_paths.policy_path).await {
+            Ok(policy) => policy,
+            Err(e) => {
+                let Some(fallback_path) = &config.fallback_path else {
+                    return Err(e.context(format!("get policy {}", file_paths.policy_path)));
+                };
+
+                warn!(
+                    "failed to get policy {}, fall back to {:?}: {:?}",
+                    file_paths.policy_path, fallback_path, e
+                );
+                resource::get_resource(&fallback_path.to_string_lossy())
+                    .await
+                    .context(format!("get fallback policy {:?}", fallback_path))?
+            }
+        },
+    };
+
+    let digest = format!("sha256:{}", hex::encode(sha2::Sha256::digest(&policy)));
+    if let Some(pinned) = &config.digest {
+        if !pinned.eq_ignore_ascii_case(&digest) {
+            bail!("digest {digest} of the policy is not the pinned {pinned}");
+        }
+    }
+
+    #[cfg(feature = "measurement")]
+    if config.measure {
+        measure_policy(&digest).await?;
+    }
+
+    Ok(policy)
+}
+
+/// Record the digest of the policy in the runtime measurements, unless it
+/// is the one last measured.
+#[cfg(feature = "measurement")]
+async fn measure_policy(digest: &str) -> Result<()> {
+    if MEASURED_POLICY.lock().expect("lock poisoned").as_deref() == Some(digest) {
+        return Ok(());
+    }
+
+    crate::measurement::extend_measurement(crate::measurement::SIGNATURE_POLICY_OPERATION, digest)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to measure policy {digest}: {:?}", e))?;
+    *MEASURED_POLICY.lock().expect("lock poisoned") = Some(digest.to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_policy() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let fallback_path = tempdir.path().join("policy.json");
+        let policy = br#"{"default":[{"type":"insecureAcceptAnything"}]}"#;
+        std::fs::write(&fallback_path, policy).unwrap();
+        let digest = format!("sha256:{}", hex::encode(sha2::Sha256::digest(policy)));
+
+        let file_paths = Paths {
+            policy_path: tempdir.path().join("missing.json").display().to_string(),
+            ..Default::default()
+        };
+        let mut config = PolicyConfig::default();
+        assert!(get_policy(&file_paths, &config).await.is_err());
+
+        config.fallback_path = Some(fallback_path);
+        config.digest = Some(digest.clone());
+        assert_eq!(get_policy(&file_paths, &config).await.unwrap(), policy);
+
+        // The inline policy is used instead, which is refused as it is not
+        // the pinned one.
+        config.inline = Some(serde_json::json!({"default": [{"type": "reject"}]}));
+        assert!(get_policy(&file_paths, &config).await.is_err());
+
+        config.digest = None;
+        assert_eq!(
+            get_policy(&file_paths, &config).await.unwrap(),
+            br#"{"default":[{"type":"reject"}]}"#
+        );
+    }
+}
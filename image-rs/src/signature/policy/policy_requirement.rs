@@ -8,13 +8,20 @@ use oci_distribution::secrets::RegistryAuth;
 use serde::*;
 
 use crate::signature::image::Image;
-use crate::signature::mechanism::{cosign::CosignParameters, simple::SimpleParameters, SignScheme};
+use crate::signature::mechanism::{
+    cosign::CosignParameters, notation::NotationParameters, provenance::ProvenanceParameters,
+    simple::SimpleParameters, SignScheme,
+};
 
 /// Policy Requirement Types.
 /// * `Accept`: s.t. `insecureAcceptAnything`, skip signature verification, accept the image unconditionally.
 /// * `Reject`: s.t. `reject`, reject the image directly.
 /// * `SignedBy`: s.t. `signBy`, means that the image is signed by `Simple Signing`,
 /// and the related parameters are inside the enum.
+/// * `Notation`: s.t. `notationSigned`, means that the image is signed by `notation`,
+/// and verified by the trust policy of notation.
+/// * `Provenance`: s.t. `provenanceAttested`, means that the image has an in-toto
+/// SLSA provenance attestation by a trusted builder.
 #[derive(Deserialize, Debug, PartialEq, Eq, Serialize)]
 #[serde(tag = "type")]
 pub enum PolicyReqType {
@@ -33,6 +40,14 @@ pub enum PolicyReqType {
     /// Signed by Cosign
     #[serde(rename = "sigstoreSigned")]
     Cosign(CosignParameters),
+
+    /// Signed by Notation
+    #[serde(rename = "notationSigned")]
+    Notation(NotationParameters),
+
+    /// Attested by an in-toto provenance
+    #[serde(rename = "provenanceAttested")]
+    Provenance(ProvenanceParameters),
     // TODO: Add more signature mechanism.
     //
     // Refer to issue: https://github.com/confidential-containers/image-rs/issues/7
@@ -46,6 +61,8 @@ impl PolicyReqType {
             PolicyReqType::Reject => Err(anyhow!(r#"The policy is "reject""#)),
             PolicyReqType::SimpleSigning(inner) => inner.allows_image(image, auth).await,
             PolicyReqType::Cosign(inner) => inner.allows_image(image, auth).await,
+            PolicyReqType::Notation(inner) => inner.allows_image(image, auth).await,
+            PolicyReqType::Provenance(inner) => inner.allows_image(image, auth).await,
         }
     }
 
@@ -55,6 +72,8 @@ impl PolicyReqType {
         match self {
             PolicyReqType::SimpleSigning(scheme) => Some(scheme as &mut dyn SignScheme),
             PolicyReqType::Cosign(scheme) => Some(scheme as &mut dyn SignScheme),
+            PolicyReqType::Notation(scheme) => Some(scheme as &mut dyn SignScheme),
+            PolicyReqType::Provenance(scheme) => Some(scheme as &mut dyn SignScheme),
             _ => None,
         }
     }
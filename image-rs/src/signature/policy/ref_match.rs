@@ -6,8 +6,8 @@
 use anyhow::{bail, Result};
 use oci_distribution::Reference;
 use serde::*;
-use std::convert::TryFrom;
 
+use crate::registry::parse_reference;
 use crate::signature::{image, policy::ErrorInfo};
 
 /// The `signedIdentity` field in simple signing. It is a JSON object, specifying what image
@@ -83,7 +83,7 @@ impl PolicyReqMatchType {
                 }
                 if origin.digest().is_some()
                     && image::get_image_repository_full_name(origin)
-                        != image::get_image_repository_full_name(&Reference::try_from(
+                        != image::get_image_repository_full_name(&parse_reference(
                             signed_image_ref,
                         )?)
                 {
@@ -92,9 +92,7 @@ impl PolicyReqMatchType {
             }
             PolicyReqMatchType::MatchRepository => {
                 if image::get_image_repository_full_name(origin)
-                    != image::get_image_repository_full_name(&Reference::try_from(
-                        signed_image_ref,
-                    )?)
+                    != image::get_image_repository_full_name(&parse_reference(signed_image_ref)?)
                 {
                     bail!(ErrorInfo::MatchReference.to_string());
                 }
@@ -105,7 +103,7 @@ impl PolicyReqMatchType {
                 }
             }
             PolicyReqMatchType::ExactRepository { docker_repository } => {
-                if image::get_image_repository_full_name(&Reference::try_from(signed_image_ref)?)
+                if image::get_image_repository_full_name(&parse_reference(signed_image_ref)?)
                     != *docker_repository
                 {
                     bail!(ErrorInfo::MatchReference.to_string());
@@ -121,14 +119,14 @@ impl PolicyReqMatchType {
                     origin_ref_string = format!("{signed_prefix}{ref_with_no_prefix}");
                 }
 
-                let new_origin_ref = Reference::try_from(origin_ref_string.as_str())?;
+                let new_origin_ref = parse_reference(&origin_ref_string)?;
 
                 if new_origin_ref.tag().is_some() && new_origin_ref.whole() != *signed_image_ref {
                     bail!(ErrorInfo::MatchReference.to_string());
                 }
                 if new_origin_ref.digest().is_some()
                     && image::get_image_repository_full_name(&new_origin_ref)
-                        != image::get_image_repository_full_name(&Reference::try_from(
+                        != image::get_image_repository_full_name(&parse_reference(
                             signed_image_ref,
                         )?)
                 {
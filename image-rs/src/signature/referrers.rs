@@ -0,0 +1,324 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Artifacts referring to an image in the registry, like the signatures
+//! and the attestations of the image.
+//!
+//! They are listed by the referrers API of OCI 1.1, or by the index of the
+//! referrers tag schema on the registries without the API.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use log::debug;
+use oci_distribution::{
+    manifest::{OciDescriptor, OciImageManifest},
+    secrets::RegistryAuth,
+};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use sha2::Digest;
+
+use crate::digest::DIGEST_SHA256_PREFIX;
+use crate::registry::http::RegistryHttp;
+use crate::signature::image::Image;
+
+const OCI_IMAGE_INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const OCI_IMAGE_MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const DOCKER_MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+
+/// Max size of an index or a manifest accepted.
+const MAX_MANIFEST_SIZE: usize = 4 * 1024 * 1024;
+
+/// The index of the referrers.
+#[derive(Deserialize)]
+struct ReferrersIndex {
+    #[serde(default)]
+    manifests: Vec<ReferrerDescriptor>,
+}
+
+/// Descriptor of a referrer in the index.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferrerDescriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: i64,
+    pub artifact_type: Option<String>,
+    #[serde(default)]
+    pub annotations: HashMap<String, String>,
+}
+
+/// An artifact referring to an image.
+pub struct Referrer {
+    /// Digest of the manifest of the artifact.
+    pub digest: String,
+
+    /// Artifact type of the referrer, if listed by the registry.
+    pub artifact_type: Option<String>,
+
+    pub manifest: OciImageManifest,
+}
+
+/// Client of the artifacts referring to an image.
+pub struct Referrers<'a> {
+    http: RegistryHttp,
+    image: &'a Image,
+}
+
+impl<'a> Referrers<'a> {
+    pub fn new(image: &'a Image, auth: &RegistryAuth) -> Self {
+        Referrers {
+            http: RegistryHttp::new(&image.reference, auth.clone()),
+            image,
+        }
+    }
+
+    /// Get the referrers of the image, those listed with another artifact
+    /// type than `artifact_type` being skipped.
+    pub async fn get(&self, artifact_type: Option<&str>) -> Result<Vec<Referrer>> {
+        let mut referrers = Vec::new();
+        for descriptor in self.list(artifact_type).await? {
+            if let (Some(expected), Some(listed)) = (artifact_type, &descriptor.artifact_type) {
+                if expected != listed {
+                    continue;
+                }
+            }
+
+            if ![OCI_IMAGE_MANIFEST_MEDIA_TYPE, DOCKER_MANIFEST_MEDIA_TYPE]
+                .contains(&descriptor.media_type.as_str())
+            {
+                continue;
+            }
+
+            referrers.push(Referrer {
+                manifest: self.manifest(&descriptor.digest).await?,
+                digest: descriptor.digest,
+                artifact_type: descriptor.artifact_type,
+            });
+        }
+
+        Ok(referrers)
+    }
+
+    /// List the descriptors of the referrers, by the referrers API, or by
+    /// the tag schema if the registry does not support it.
+    pub async fn list(&self, artifact_type: Option<&str>) -> Result<Vec<ReferrerDescriptor>> {
+        let digest = self.image.manifest_digest.to_string();
+        let mut url = self.http.url(&format!("referrers/{digest}"));
+        if let Some(artifact_type) = artifact_type {
+            url = format!(
+                "{url}?{}",
+                url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair("artifactType", artifact_type)
+                    .finish()
+            );
+        }
+
+        let response = self
+            .http
+            .get(&url, accept(OCI_IMAGE_INDEX_MEDIA_TYPE))
+            .await?;
+        match response.status() {
+            StatusCode::OK => {
+                let index: ReferrersIndex = serde_json::from_slice(&body(response).await?)
+                    .map_err(|e| anyhow!("invalid referrers index {e}"))?;
+                return Ok(index.manifests);
+            }
+            StatusCode::NOT_FOUND => {
+                debug!("no referrers API, fall back to the referrers tag schema");
+            }
+            status => bail!("failed to get referrers of {}: {}", digest, status),
+        }
+
+        let url = self.http.url(&format!("manifests/{}", self.digest_tag()));
+        let response = self
+            .http
+            .get(&url, accept(OCI_IMAGE_INDEX_MEDIA_TYPE))
+            .await?;
+        match response.status() {
+            StatusCode::OK => {
+                let index: ReferrersIndex = serde_json::from_slice(&body(response).await?)
+                    .map_err(|e| anyhow!("invalid referrers index {e}"))?;
+                Ok(index.manifests)
+            }
+            StatusCode::NOT_FOUND => Ok(Vec::new()),
+            status => bail!("failed to get referrers tag of {}: {}", digest, status),
+        }
+    }
+
+    /// Get the artifact tagged `<alg>-<digest>.<suffix>` by cosign, like the
+    /// attestations of `.att`, if any.
+    pub async fn cosign_artifact(&self, suffix: &str) -> Result<Option<Referrer>> {
+        let tag = format!("{}.{suffix}", self.digest_tag());
+        let headers = accept(&format!(
+            "{OCI_IMAGE_MANIFEST_MEDIA_TYPE}, {DOCKER_MANIFEST_MEDIA_TYPE}"
+        ));
+        let response = self
+            .http
+            .get(&self.http.url(&format!("manifests/{tag}")), headers)
+            .await?;
+        match response.status() {
+            StatusCode::OK => {}
+            StatusCode::NOT_FOUND => return Ok(None),
+            status => bail!("failed to get {}: {}", tag, status),
+        }
+
+        let data = body(response).await?;
+        let manifest =
+            serde_json::from_slice(&data).map_err(|e| anyhow!("invalid manifest of {tag} {e}"))?;
+        Ok(Some(Referrer {
+            digest: sha256(&data),
+            artifact_type: None,
+            manifest,
+        }))
+    }
+
+    /// Get the manifest of `digest`, and check its digest.
+    async fn manifest(&self, digest: &str) -> Result<OciImageManifest> {
+        let headers = accept(&format!(
+            "{OCI_IMAGE_MANIFEST_MEDIA_TYPE}, {DOCKER_MANIFEST_MEDIA_TYPE}"
+        ));
+        let response = self
+            .http
+            .get(&self.http.url(&format!("manifests/{digest}")), headers)
+            .await?;
+        if response.status() != StatusCode::OK {
+            bail!("failed to get manifest {}: {}", digest, response.status());
+        }
+
+        let data = body(response).await?;
+        check_digest(&data, digest)?;
+        serde_json::from_slice(&data).map_err(|e| anyhow!("invalid manifest {digest} {e}"))
+    }
+
+    /// Pull a layer of a referrer, of at most `max_size` bytes, and check
+    /// its digest.
+    pub async fn pull_layer(&self, layer: &OciDescriptor, max_size: i64) -> Result<Vec<u8>> {
+        if layer.size > max_size {
+            bail!(
+                "layer {} of {} bytes exceeds {} bytes",
+                layer.digest,
+                layer.size,
+                max_size
+            );
+        }
+
+        let response = self
+            .http
+            .get(
+                &self.http.url(&format!("blobs/{}", layer.digest)),
+                HeaderMap::new(),
+            )
+            .await?;
+        if response.status() != StatusCode::OK {
+            bail!(
+                "failed to get layer {}: {}",
+                layer.digest,
+                response.status()
+            );
+        }
+
+        let data = response.bytes().await?;
+        check_digest(&data, &layer.digest)?;
+        Ok(data.to_vec())
+    }
+
+    /// Get the tag of the image digest, i.e. `<alg>-<digest>`.
+    fn digest_tag(&self) -> String {
+        format!(
+            "{}-{}",
+            self.image.manifest_digest.algorithm(),
+            self.image.manifest_digest.value()
+        )
+    }
+}
+
+fn accept(media_types: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(media_types) {
+        headers.insert(ACCEPT, value);
+    }
+    headers
+}
+
+/// Read the body of a response of an index or a manifest.
+async fn body(response: reqwest::Response) -> Result<Vec<u8>> {
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_MANIFEST_SIZE as u64)
+    {
+        bail!("manifest exceeds {} bytes", MAX_MANIFEST_SIZE);
+    }
+
+    let data = response.bytes().await?;
+    if data.len() > MAX_MANIFEST_SIZE {
+        bail!("manifest exceeds {} bytes", MAX_MANIFEST_SIZE);
+    }
+
+    Ok(data.to_vec())
+}
+
+fn sha256(data: &[u8]) -> String {
+    format!("{}{:x}", DIGEST_SHA256_PREFIX, sha2::Sha256::digest(data))
+}
+
+fn check_digest(data: &[u8], expected: &str) -> Result<()> {
+    let digest = sha256(data);
+    if digest != expected {
+        bail!("unequal digest {:?} expected {:?}", digest, expected);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referrers_index() {
+        let index = r#"{
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": [
+                {
+                    "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                    "digest": "sha256:a0fc570a245b09ed752c42d600ee3bb5b4f77bbd70d8898780b7ab43454530eb",
+                    "size": 728,
+                    "artifactType": "application/vnd.cncf.notary.signature",
+                    "annotations": {
+                        "io.cncf.notary.x509chain.thumbprint#S256": "[\"a1b2\"]"
+                    }
+                },
+                {
+                    "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                    "digest": "sha256:4f4b2d5b2a2b0e4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d",
+                    "size": 512
+                }
+            ]
+        }"#;
+
+        let index: ReferrersIndex = serde_json::from_str(index).unwrap();
+        assert_eq!(index.manifests.len(), 2);
+        assert_eq!(
+            index.manifests[0].artifact_type.as_deref(),
+            Some("application/vnd.cncf.notary.signature")
+        );
+        assert!(index.manifests[1].artifact_type.is_none());
+
+        // An empty index of the referrers API.
+        let index: ReferrersIndex = serde_json::from_str(r#"{"schemaVersion": 2}"#).unwrap();
+        assert!(index.manifests.is_empty());
+    }
+
+    #[test]
+    fn test_check_digest() {
+        let digest = "sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+        check_digest(b"foo", digest).unwrap();
+        assert!(check_digest(b"bar", digest).is_err());
+    }
+}
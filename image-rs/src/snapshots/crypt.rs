@@ -0,0 +1,120 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Ephemeral dm-crypt volumes of the writable upperdirs of the containers.
+//!
+//! A volume is backed by a sparse file, and encrypted by a random key
+//! generated in the guest, which is never stored. So the writes of a
+//! container are only readable while its volume is open, by the guest
+//! kernel, and are lost once the volume is closed.
+
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use devicemapper::{DevId, DmName, DmOptions, DM};
+use log::warn;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Filesystem of the volumes.
+pub const VOLUME_FS_TYPE: &str = "ext4";
+
+/// Cipher of the volumes, refer to the crypt target of device mapper.
+const CIPHER: &str = "aes-xts-plain64";
+
+/// Size of the keys of [`CIPHER`], i.e. AES-256 in XTS mode.
+const KEY_SIZE: usize = 64;
+
+const SECTOR_SIZE: u64 = 512;
+
+/// Open the volume `name` of `size` bytes backed by the sparse file
+/// `backing_file`, encrypted by a new random key, and format it with
+/// [`VOLUME_FS_TYPE`]. Returns the path of the volume device.
+pub fn open_ephemeral_volume(name: &str, backing_file: &Path, size: u64) -> Result<String> {
+    if size == 0 || size % SECTOR_SIZE != 0 {
+        bail!("invalid volume size {size}");
+    }
+
+    File::create(backing_file)?.set_len(size)?;
+
+    // The loop device is detached once the volume is closed.
+    let loop_device = loopdev::LoopControl::open()?.next_free()?;
+    loop_device
+        .with()
+        .autoclear(true)
+        .attach(backing_file)
+        .context("attach volume backing file")?;
+    let loop_device_path = loop_device
+        .path()
+        .ok_or_else(|| anyhow!("failed to get loop device path"))?;
+
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    OsRng.fill_bytes(key.as_mut());
+    let key = Zeroizing::new(hex::encode(key.as_ref()));
+
+    // crypt parameters: <cipher> <key> <iv_offset> <device path> <offset>
+    let mut table = vec![(
+        0,
+        size / SECTOR_SIZE,
+        "crypt".to_string(),
+        format!(
+            "{} {} 0 {} 0",
+            CIPHER,
+            key.as_str(),
+            loop_device_path.display()
+        ),
+    )];
+
+    let dm = DM::new()?;
+    let dm_name = DmName::new(name)?;
+    let id = DevId::Name(dm_name);
+    dm.device_create(dm_name, None, DmOptions::default())?;
+    let loaded = dm
+        .table_load(&id, table.as_slice(), DmOptions::default())
+        .and_then(|_| dm.device_suspend(&id, DmOptions::default()));
+    table
+        .iter_mut()
+        .for_each(|(_, _, _, params)| params.zeroize());
+    if let Err(e) = loaded {
+        close_ephemeral_volume(name)?;
+        bail!("failed to load volume {}: {}", name, e);
+    }
+
+    let device_path = format!("/dev/mapper/{name}");
+    let output = Command::new(format!("mkfs.{VOLUME_FS_TYPE}"))
+        .args(["-q", "-F"])
+        .arg(&device_path)
+        .output();
+    let formatted = match output {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = formatted {
+        if let Err(e) = close_ephemeral_volume(name) {
+            warn!("failed to close volume {}: {:?}", name, e);
+        }
+        bail!("failed to format volume {}: {}", name, e);
+    }
+
+    Ok(device_path)
+}
+
+/// Close the volume `name`, whose data is lost along with its key.
+pub fn close_ephemeral_volume(name: &str) -> Result<()> {
+    let dm = DM::new()?;
+    let dm_name = DmName::new(name)?;
+
+    dm.device_remove(&DevId::Name(dm_name), DmOptions::default())
+        .context(format!("remove volume {}", name))?;
+
+    Ok(())
+}
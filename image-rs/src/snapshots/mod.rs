@@ -6,10 +6,14 @@ use anyhow::Result;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "encrypted-upperdir")]
+pub mod crypt;
 #[cfg(feature = "snapshot-unionfs")]
 pub mod occlum;
 #[cfg(feature = "snapshot-overlayfs")]
 pub mod overlay;
+#[cfg(feature = "snapshot-verity")]
+pub mod verity;
 
 /// Snapshot types.
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Hash)]
@@ -20,6 +24,8 @@ pub enum SnapshotType {
     Overlay,
     #[cfg(feature = "snapshot-unionfs")]
     OcclumUnionfs,
+    #[cfg(feature = "snapshot-verity")]
+    Verity,
 }
 
 impl std::fmt::Display for SnapshotType {
@@ -30,6 +36,8 @@ impl std::fmt::Display for SnapshotType {
             Self::Overlay => "overlay",
             #[cfg(feature = "snapshot-unionfs")]
             Self::OcclumUnionfs => "occlum_unionfs",
+            #[cfg(feature = "snapshot-verity")]
+            Self::Verity => "verity",
         };
 
         write!(f, "{out}")
@@ -47,6 +55,11 @@ pub struct MountPoint {
 
     /// The work dir generated by snapshot.
     pub work_dir: PathBuf,
+
+    /// The dm-verity root hash of the rootfs, to be recorded in the runtime
+    /// measurements, if the snapshot is protected by dm-verity.
+    #[serde(default)]
+    pub root_hash: Option<String>,
 }
 
 /// Trait to mount/umount image snapshots.
@@ -174,6 +174,7 @@ impl Snapshotter for Unionfs {
             r#type: fs_type,
             mount_path: mount_path.to_path_buf(),
             work_dir: self.data_dir.to_path_buf(),
+            root_hash: None,
         })
     }
 
@@ -8,34 +8,106 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::config::OverlaySnapshotConfig;
+#[cfg(feature = "encrypted-upperdir")]
+use crate::snapshots::crypt;
 use crate::snapshots::{MountPoint, SnapshotType, Snapshotter};
 
+/// Prefix of the names of the encrypted volumes of the upperdirs.
+#[cfg(feature = "encrypted-upperdir")]
+const VOLUME_NAME_PREFIX: &str = "image-rs-upper-";
+
 #[derive(Debug)]
 pub struct OverlayFs {
     data_dir: PathBuf,
     index: AtomicUsize,
+    /// Size of the encrypted volumes of the upperdirs, if encrypted.
+    #[cfg(feature = "encrypted-upperdir")]
+    upperdir_volume_size: Option<u64>,
 }
 
 impl OverlayFs {
     /// Create a new instance of [OverlayFs].
-    pub fn new(data_dir: PathBuf, index: AtomicUsize) -> Self {
-        OverlayFs { data_dir, index }
+    #[allow(unused_variables)]
+    pub fn new(data_dir: PathBuf, index: AtomicUsize, config: &OverlaySnapshotConfig) -> Self {
+        OverlayFs {
+            data_dir,
+            index,
+            #[cfg(feature = "encrypted-upperdir")]
+            upperdir_volume_size: config.encrypted_upperdir.then_some(config.upperdir_size),
+        }
+    }
+
+    /// Open the encrypted volume of the upperdir in `work_dir`, and mount
+    /// it at `<work_dir>/upper`, where the upperdir and the workdir of the
+    /// overlay are to be.
+    #[cfg(feature = "encrypted-upperdir")]
+    fn mount_upper_volume(&self, work_dir: &Path, size: u64) -> Result<PathBuf> {
+        let name = volume_name(work_dir)?;
+        let upper = work_dir.join("upper");
+        fs::create_dir_all(&upper)?;
+
+        let device = crypt::open_ephemeral_volume(&name, &work_dir.join("upper.img"), size)?;
+        if let Err(e) = nix::mount::mount(
+            Some(device.as_str()),
+            &upper,
+            Some(crypt::VOLUME_FS_TYPE),
+            MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+            None::<&str>,
+        ) {
+            if let Err(e) = crypt::close_ephemeral_volume(&name) {
+                log::warn!("failed to close volume {}: {:?}", name, e);
+            }
+            return Err(anyhow!("failed to mount {} to {:?}: {}", device, upper, e));
+        }
+
+        Ok(upper)
+    }
+
+    /// Unmount and close the encrypted volume of the upperdir in
+    /// `work_dir`, after which its content is lost.
+    #[cfg(feature = "encrypted-upperdir")]
+    fn unmount_upper_volume(&self, work_dir: &Path) -> Result<()> {
+        nix::mount::umount(&work_dir.join("upper"))?;
+        crypt::close_ephemeral_volume(&volume_name(work_dir)?)?;
+        fs::remove_file(work_dir.join("upper.img"))?;
+
+        Ok(())
     }
 }
 
+/// Name of the encrypted volume of the upperdir in `work_dir`, by the
+/// index of the work dir.
+#[cfg(feature = "encrypted-upperdir")]
+fn volume_name(work_dir: &Path) -> Result<String> {
+    let index = work_dir
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid work dir {:?}", work_dir))?;
+    Ok(format!("{VOLUME_NAME_PREFIX}{}", index.to_string_lossy()))
+}
+
 impl Snapshotter for OverlayFs {
     fn mount(&mut self, layer_path: &[&str], mount_path: &Path) -> Result<MountPoint> {
         let fs_type = SnapshotType::Overlay.to_string();
         let overlay_lowerdir = layer_path.join(":");
         let index = self.index.fetch_add(1, Ordering::SeqCst).to_string();
         let work_dir = self.data_dir.join(index);
-        let overlay_upperdir = work_dir.join("upperdir");
-        let overlay_workdir = work_dir.join("workdir");
 
         // TODO: enhance safety by safe-path
         if !self.data_dir.exists() {
             fs::create_dir_all(&self.data_dir)?;
         }
+
+        // The upperdir and the workdir must be on the same filesystem, i.e.
+        // on the encrypted volume if any.
+        #[allow(unused_mut)]
+        let mut upper = work_dir.clone();
+        #[cfg(feature = "encrypted-upperdir")]
+        if let Some(size) = self.upperdir_volume_size {
+            upper = self.mount_upper_volume(&work_dir, size)?;
+        }
+        let overlay_upperdir = upper.join("upperdir");
+        let overlay_workdir = upper.join("workdir");
         fs::create_dir_all(&overlay_upperdir)?;
         fs::create_dir_all(&overlay_workdir)?;
 
@@ -60,6 +132,13 @@ impl Snapshotter for OverlayFs {
             Some(options.as_str()),
         )
         .map_err(|e| {
+            #[cfg(feature = "encrypted-upperdir")]
+            if self.upperdir_volume_size.is_some() {
+                if let Err(e) = self.unmount_upper_volume(&work_dir) {
+                    log::warn!("failed to unmount upperdir volume: {:?}", e);
+                }
+            }
+
             anyhow!(
                 "failed to mount {:?} to {:?}, with error: {}",
                 source,
@@ -72,12 +151,18 @@ impl Snapshotter for OverlayFs {
             r#type: fs_type,
             mount_path: mount_path.to_path_buf(),
             work_dir,
+            root_hash: None,
         })
     }
 
     fn unmount(&self, mount_point: &MountPoint) -> Result<()> {
         nix::mount::umount(mount_point.mount_path.as_path())?;
 
+        #[cfg(feature = "encrypted-upperdir")]
+        if self.upperdir_volume_size.is_some() {
+            self.unmount_upper_volume(&mount_point.work_dir)?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,230 @@
+// Copyright (c) 2023 Alibaba Cloud
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+//! Snapshotter of read-only rootfs protected by dm-verity.
+//!
+//! The layers of an image are merged into a single erofs or squashfs image
+//! per container, whose dm-verity hash tree is appended to the image. The
+//! image is mounted read-only by its dm-verity device, so that any change
+//! to the image after it is built, even by a compromised process in the
+//! guest, fails the reads of the changed blocks. The writes of the
+//! container go to an overlay upon the image.
+//!
+//! The root hash of the image is returned in the [`MountPoint`], to be
+//! recorded in the runtime measurements by [`crate::measurement`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use nix::mount::MsFlags;
+
+use crate::config::{VerityFsType, VeritySnapshotConfig};
+use crate::snapshots::{MountPoint, SnapshotType, Snapshotter};
+use crate::verity::dmverity::{create_named_verity_device, destroy_verity_device};
+use crate::verity::hash_tree::format_hash_tree;
+
+/// Block size of both the data and the hash tree of the images.
+const VERITY_BLOCK_SIZE: u64 = 4096;
+
+/// Prefix of the names of the dm-verity devices.
+const DEVICE_NAME_PREFIX: &str = "image-rs-verity-";
+
+/// The fixed UUID of the images, so that their root hashes only depend on
+/// the files of the layers.
+const IMAGE_UUID: &str = "00000000-0000-0000-0000-000000000000";
+
+#[derive(Debug)]
+pub struct VerityFs {
+    data_dir: PathBuf,
+    index: AtomicUsize,
+    fs_type: VerityFsType,
+}
+
+impl VerityFs {
+    /// Create a new instance of [VerityFs].
+    pub fn new(data_dir: PathBuf, index: AtomicUsize, config: &VeritySnapshotConfig) -> Self {
+        VerityFs {
+            data_dir,
+            index,
+            fs_type: config.fs_type,
+        }
+    }
+
+    /// Build the image of the merged `layer_path` at `image`.
+    fn build_image(&self, layer_path: &[&str], merged: &Path, image: &Path) -> Result<()> {
+        // A single layer is the rootfs itself, while more layers are merged
+        // by a read-only overlay, which needs at least two layers.
+        let source = match layer_path {
+            [] => bail!("no layer to mount"),
+            [layer] => Path::new(layer),
+            _ => {
+                let options = format!("lowerdir={}", layer_path.join(":"));
+                nix::mount::mount(
+                    Some("overlay"),
+                    merged,
+                    Some("overlay"),
+                    MsFlags::MS_RDONLY,
+                    Some(options.as_str()),
+                )
+                .map_err(|e| anyhow!("failed to merge layers to {:?}: {}", merged, e))?;
+                merged
+            }
+        };
+
+        let output = match self.fs_type {
+            VerityFsType::Erofs => Command::new("mkfs.erofs")
+                .arg("-T0")
+                .arg(format!("-U{IMAGE_UUID}"))
+                .arg(image)
+                .arg(source)
+                .output(),
+            VerityFsType::Squashfs => Command::new("mksquashfs")
+                .arg(source)
+                .arg(image)
+                .args([
+                    "-noappend",
+                    "-no-progress",
+                    "-mkfs-time",
+                    "0",
+                    "-all-time",
+                    "0",
+                ])
+                .output(),
+        };
+
+        if source == merged {
+            if let Err(e) = nix::mount::umount(merged) {
+                warn!("failed to unmount merged layers {:?}: {}", merged, e);
+            }
+        }
+
+        let output =
+            output.map_err(|e| anyhow!("failed to run mkfs of {}: {}", self.fs_type, e))?;
+        if !output.status.success() {
+            bail!(
+                "failed to build {} image: {}",
+                self.fs_type,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Mount the dm-verity device `name` of `image` read-only at `lower`,
+    /// returning the root hash of the image.
+    fn mount_image(&self, name: &str, image: &Path, lower: &Path) -> Result<String> {
+        let verity_option = format_hash_tree(image, VERITY_BLOCK_SIZE)?;
+
+        // The loop device is detached once the dm-verity device is removed.
+        let loop_device = loopdev::LoopControl::open()?.next_free()?;
+        loop_device
+            .with()
+            .read_only(true)
+            .autoclear(true)
+            .attach(image)
+            .context("attach rootfs image")?;
+        let loop_device_path = loop_device
+            .path()
+            .ok_or_else(|| anyhow!("failed to get loop device path"))?;
+
+        let verity_device_path =
+            create_named_verity_device(name, &verity_option, &loop_device_path)?;
+        if let Err(e) = nix::mount::mount(
+            Some(verity_device_path.as_str()),
+            lower,
+            Some(self.fs_type.to_string().as_str()),
+            MsFlags::MS_RDONLY,
+            None::<&str>,
+        ) {
+            if let Err(e) = destroy_verity_device(name) {
+                warn!("failed to remove verity device {}: {:?}", name, e);
+            }
+            bail!(
+                "failed to mount {} to {:?}: {}",
+                verity_device_path,
+                lower,
+                e
+            );
+        }
+
+        Ok(verity_option.hash)
+    }
+}
+
+impl Snapshotter for VerityFs {
+    fn mount(&mut self, layer_path: &[&str], mount_path: &Path) -> Result<MountPoint> {
+        let index = self.index.fetch_add(1, Ordering::SeqCst).to_string();
+        let work_dir = self.data_dir.join(&index);
+        let merged = work_dir.join("merged");
+        let image = work_dir.join("rootfs.img");
+        let lower = work_dir.join("lower");
+        let upperdir = work_dir.join("upperdir");
+        let workdir = work_dir.join("workdir");
+
+        for dir in [&merged, &lower, &upperdir, &workdir] {
+            fs::create_dir_all(dir)?;
+        }
+        if !mount_path.exists() {
+            fs::create_dir_all(mount_path)?;
+        }
+
+        self.build_image(layer_path, &merged, &image)?;
+        let name = format!("{DEVICE_NAME_PREFIX}{index}");
+        let root_hash = self.mount_image(&name, &image, &lower)?;
+
+        let options = format!(
+            "lowerdir={},upperdir={},workdir={}",
+            lower.display(),
+            upperdir.display(),
+            workdir.display()
+        );
+        if let Err(e) = nix::mount::mount(
+            Some("overlay"),
+            mount_path,
+            Some("overlay"),
+            MsFlags::empty(),
+            Some(options.as_str()),
+        ) {
+            unmount_image(&name, &lower);
+            bail!("failed to mount overlay to {:?}: {}", mount_path, e);
+        }
+
+        Ok(MountPoint {
+            r#type: SnapshotType::Verity.to_string(),
+            mount_path: mount_path.to_path_buf(),
+            work_dir,
+            root_hash: Some(root_hash),
+        })
+    }
+
+    fn unmount(&self, mount_point: &MountPoint) -> Result<()> {
+        nix::mount::umount(mount_point.mount_path.as_path())?;
+
+        let index = mount_point
+            .work_dir
+            .file_name()
+            .ok_or_else(|| anyhow!("invalid work dir {:?}", mount_point.work_dir))?;
+        let name = format!("{DEVICE_NAME_PREFIX}{}", index.to_string_lossy());
+        unmount_image(&name, &mount_point.work_dir.join("lower"));
+        fs::remove_file(mount_point.work_dir.join("rootfs.img"))?;
+
+        Ok(())
+    }
+}
+
+/// Unmount the image at `lower` and remove its dm-verity device `name`.
+fn unmount_image(name: &str, lower: &Path) {
+    if let Err(e) = nix::mount::umount(lower) {
+        warn!("failed to unmount rootfs image {:?}: {}", lower, e);
+    }
+    if let Err(e) = destroy_verity_device(name) {
+        warn!("failed to remove verity device {}: {:?}", name, e);
+    }
+}
@@ -9,8 +9,11 @@ use std::io::{Cursor, Read};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::OwnedSemaphorePermit;
 
+use crate::budget::PullBudget;
 use crate::digest::{DigestHasher, LayerDigestHasher, DIGEST_SHA256_PREFIX, DIGEST_SHA512_PREFIX};
+use crate::progress::{LayerProgress, PullStage};
 use crate::unpack::unpack;
 use crate::ERR_BAD_UNCOMPRESSED_DIGEST;
 
@@ -19,16 +22,23 @@ const CAPACITY: usize = 32768;
 // Wrap a channel with [`Read`](std::io::Read) support.
 // This can bridge the [`AsyncRead`](tokio::io::AsyncRead) from
 // decrypt/decompress and impl Read for unpack.
+// Each buffer comes with its reservation of the memory budget, which is
+// released once the buffer is unpacked. An error received fails the
+// unpacking, instead of the end of the channel.
+type ChannelBuffer = std::io::Result<(Vec<u8>, OwnedSemaphorePermit)>;
+
 struct ChannelRead {
-    rx: Receiver<Vec<u8>>,
+    rx: Receiver<ChannelBuffer>,
     current: Cursor<Vec<u8>>,
+    permit: Option<OwnedSemaphorePermit>,
 }
 
 impl ChannelRead {
-    fn new(rx: Receiver<Vec<u8>>) -> ChannelRead {
+    fn new(rx: Receiver<ChannelBuffer>) -> ChannelRead {
         ChannelRead {
             rx,
             current: Cursor::new(vec![]),
+            permit: None,
         }
     }
 }
@@ -37,8 +47,18 @@ impl Read for ChannelRead {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         // Receive new buffer when we finish handled previous data.
         if self.current.position() == self.current.get_ref().len() as u64 {
-            if let Ok(buffer) = self.rx.recv() {
-                self.current = Cursor::new(buffer);
+            // Release the budget of the handled data before waiting for
+            // more, as the sender may be waiting for the budget.
+            self.current = Cursor::new(vec![]);
+            self.permit = None;
+
+            match self.rx.recv() {
+                Ok(Ok((buffer, permit))) => {
+                    self.current = Cursor::new(buffer);
+                    self.permit = Some(permit);
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {}
             }
 
             // When recv() finished or failed, the sender will close the channel
@@ -52,10 +72,14 @@ impl Read for ChannelRead {
 
 /// stream_processing will handle async uncompressed layer data and
 /// unpack to the destination, returns layer digest for verification.
+/// The data buffered for unpacking is bounded by the memory `budget`, and
+/// the data unpacked is reported to `progress`.
 pub async fn stream_processing(
     layer_reader: impl AsyncRead + Unpin,
     diff_id: &str,
     destination: &Path,
+    budget: &PullBudget,
+    progress: &LayerProgress,
 ) -> Result<String> {
     let dest = destination.to_path_buf();
     let hasher = if diff_id.starts_with(DIGEST_SHA256_PREFIX) {
@@ -66,7 +90,7 @@ pub async fn stream_processing(
         bail!("{}: {:?}", ERR_BAD_UNCOMPRESSED_DIGEST, diff_id);
     };
 
-    channel_processing(layer_reader, hasher, dest)
+    channel_processing(layer_reader, hasher, dest, budget, progress)
         .await
         .map_err(|e| anyhow!("hasher {} {:?}", DIGEST_SHA256_PREFIX, e))
 }
@@ -75,10 +99,14 @@ async fn channel_processing(
     mut layer_reader: (impl AsyncRead + Unpin),
     mut hasher: LayerDigestHasher,
     destination: PathBuf,
+    budget: &PullBudget,
+    progress: &LayerProgress,
 ) -> Result<String> {
-    let (tx, rx) = channel();
+    let (tx, rx) = channel::<ChannelBuffer>();
+    let progress = progress.clone();
+    let dest = destination.clone();
     let unpack_thread = std::thread::spawn(move || {
-        let mut input = ChannelRead::new(rx);
+        let mut input = progress.wrap(PullStage::Unpacked, ChannelRead::new(rx));
 
         if let Err(e) = unpack(&mut input, destination.as_path()) {
             // TODO
@@ -90,29 +118,51 @@ async fn channel_processing(
         Result::<()>::Ok(())
     });
 
-    loop {
-        let mut buffer = vec![0u8; CAPACITY];
-        let n = layer_reader
-            .read(&mut buffer)
-            .await
-            .map_err(|e| anyhow!("channel: read failed {:?}", e))?;
-        if n == 0 {
-            break;
+    let read = async {
+        loop {
+            let permit = budget.reserve(CAPACITY).await?;
+            let mut buffer = vec![0u8; CAPACITY];
+            let n = layer_reader
+                .read(&mut buffer)
+                .await
+                .map_err(|e| anyhow!("channel: read failed {:?}", e))?;
+            if n == 0 {
+                break;
+            }
+
+            buffer.resize(n, 0);
+            hasher.digest_update(&buffer);
+            tx.send(Ok((buffer, permit)))
+                .map_err(|e| anyhow!("channel: send failed {:?}", e))?;
         }
 
-        buffer.resize(n, 0);
-        hasher.digest_update(&buffer);
-        tx.send(buffer)
-            .map_err(|e| anyhow!("channel: send failed {:?}", e))?;
+        Result::<()>::Ok(())
+    }
+    .await;
+
+    // A failed read, e.g. of an encrypted layer whose HMAC mismatches at the
+    // end, fails the unpacking rather than ending it.
+    if let Err(e) = &read {
+        let _ = tx.send(Err(std::io::Error::other(e.to_string())));
     }
 
     // Close the channel to signal EOF.
     drop(tx);
 
-    tokio::task::spawn_blocking(|| unpack_thread.join())
+    let unpacked = tokio::task::spawn_blocking(|| unpack_thread.join())
         .await?
         .map_err(|e| anyhow!("channel: unpack thread failed {:?}", e))
-        .unwrap()?;
+        .unwrap();
+
+    // The unpacking may be done before the read fails, so that the data
+    // unpacked is removed here.
+    if let Err(e) = read {
+        if dest.exists() {
+            fs::remove_dir_all(&dest).context("Failed to roll back when unpacking")?;
+        }
+        return Err(e);
+    }
+    unpacked?;
 
     Ok(hasher.digest_finalize())
 }
@@ -152,10 +202,28 @@ mod tests {
 
         let hasher = LayerDigestHasher::Sha256(sha2::Sha256::new());
 
-        let layer_digest_new =
-            channel_processing(layer_data.as_slice(), hasher, file_path.to_path_buf())
-                .await
-                .unwrap();
+        let layer_digest_new = channel_processing(
+            layer_data.as_slice(),
+            hasher,
+            file_path.to_path_buf(),
+            &PullBudget::default(),
+            &LayerProgress::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(layer_digest, layer_digest_new);
+
+        // A budget of a single buffer still gets the layer through.
+        let hasher = LayerDigestHasher::Sha256(sha2::Sha256::new());
+        let layer_digest_new = channel_processing(
+            layer_data.as_slice(),
+            hasher,
+            tempdir.path().join("layer1"),
+            &PullBudget::new(CAPACITY, 0),
+            &LayerProgress::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(layer_digest, layer_digest_new);
 
         let file = File::open(file_path.join("file.txt")).unwrap();
@@ -167,6 +235,44 @@ mod tests {
         assert_eq!(data_digest, data_digest_new);
     }
 
+    #[tokio::test]
+    async fn test_channel_processing_read_error() {
+        struct FailingRead;
+        impl AsyncRead for FailingRead {
+            fn poll_read(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut std::task::Context<'_>,
+                _buf: &mut tokio::io::ReadBuf<'_>,
+            ) -> std::task::Poll<std::io::Result<()>> {
+                std::task::Poll::Ready(Err(std::io::Error::other("bad hmac")))
+            }
+        }
+
+        let mut ar = Builder::new(Vec::new());
+        let mut header = Header::new_gnu();
+        header.set_size(5);
+        header.set_cksum();
+        ar.append_data(&mut header, "file.txt", b"hello".as_slice())
+            .unwrap();
+        let layer_data = ar.into_inner().unwrap();
+
+        // The whole tar is read before the failure, which still fails the
+        // layer and removes what is unpacked.
+        let tempdir = tempfile::tempdir().unwrap();
+        let file_path = tempdir.path().join("layer0");
+        let hasher = LayerDigestHasher::Sha256(sha2::Sha256::new());
+        assert!(channel_processing(
+            layer_data.as_slice().chain(FailingRead),
+            hasher,
+            file_path.to_path_buf(),
+            &PullBudget::default(),
+            &LayerProgress::default(),
+        )
+        .await
+        .is_err());
+        assert!(!file_path.exists());
+    }
+
     #[tokio::test]
     async fn test_stream_processing() {
         let mut data = [0; 100000];
@@ -190,9 +296,15 @@ mod tests {
         let tempdir = tempfile::tempdir().unwrap();
         let file_path = tempdir.path().join("layer0");
 
-        let layer_digest_new = stream_processing(layer_data.as_slice(), &layer_digest, &file_path)
-            .await
-            .unwrap();
+        let layer_digest_new = stream_processing(
+            layer_data.as_slice(),
+            &layer_digest,
+            &file_path,
+            &PullBudget::default(),
+            &LayerProgress::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(layer_digest, layer_digest_new);
 
         let tempdir = tempfile::tempdir().unwrap();
@@ -203,9 +315,15 @@ mod tests {
             sha2::Sha512::digest(layer_data.as_slice())
         );
 
-        let layer_digest_new = stream_processing(layer_data.as_slice(), &layer_digest, &file_path)
-            .await
-            .unwrap();
+        let layer_digest_new = stream_processing(
+            layer_data.as_slice(),
+            &layer_digest,
+            &file_path,
+            &PullBudget::default(),
+            &LayerProgress::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(layer_digest, layer_digest_new);
     }
 }
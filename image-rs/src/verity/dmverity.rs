@@ -136,9 +136,21 @@ impl TryFrom<&String> for DmVerityOption {
 pub fn create_verity_device(
     verity_option: &DmVerityOption,
     source_device_path: &Path,
+) -> Result<String> {
+    create_named_verity_device(&verity_option.hash, verity_option, source_device_path)
+}
+
+/// Same as [`create_verity_device`], but the mapping is named by <name>
+/// instead of the root hash, so that the devices of the same data are not
+/// in conflict.
+/// It will return the verity block device Path "/dev/mapper/<name>"
+pub fn create_named_verity_device(
+    name: &str,
+    verity_option: &DmVerityOption,
+    source_device_path: &Path,
 ) -> Result<String> {
     let dm = DM::new()?;
-    let verity_name = DmName::new(&verity_option.hash)?;
+    let verity_name = DmName::new(name)?;
     let id = DevId::Name(verity_name);
     let opts = DmOptions::default().set_flags(DmFlags::DM_READONLY);
     let hash_start_block: u64 =
@@ -189,7 +201,7 @@ pub fn create_verity_device(
     dm.table_load(&id, verity_table.as_slice(), opts)?;
     dm.device_suspend(&id, opts)?;
 
-    Ok(format!("/dev/mapper/{}", &verity_option.hash))
+    Ok(format!("/dev/mapper/{name}"))
 }
 
 /// Destroy a DmVerity device with specified name.
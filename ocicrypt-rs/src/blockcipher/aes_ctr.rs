@@ -146,7 +146,9 @@ impl<R: Read> Read for AESCTRBlockCipher<R> {
         }
 
         let read_len = state.reader.read(buf)?;
-        if read_len == 0 {
+        // A decryption is only done once the HMAC is verified, so that the
+        // reads after a mismatch keep failing instead of returning EOF.
+        if read_len == 0 && self.encrypt {
             state.done = true;
         }
 
@@ -170,6 +172,7 @@ impl<R: Read> Read for AESCTRBlockCipher<R> {
                             ),
                         )
                     })?;
+                state.done = true;
             }
         } else if read_len > 0 {
             state.cipher.apply_keystream(&mut buf[0..read_len]);
@@ -215,7 +218,7 @@ impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AESCTRBlockCipher<R> {
             Poll::Ready(res) => res?,
         }
         let buf_filled = &mut buf.filled_mut()[start_pos..];
-        if buf_filled.is_empty() {
+        if buf_filled.is_empty() && encrypt {
             *done = true;
         }
 
@@ -235,6 +238,7 @@ impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AESCTRBlockCipher<R> {
                         ),
                     )
                 })?;
+                *done = true;
             }
         } else if !buf_filled.is_empty() {
             cipher.apply_keystream(buf_filled);
@@ -306,6 +310,9 @@ mod tests {
         assert!(aes_ctr_block_cipher
             .read_to_end(&mut plaintxt_data)
             .is_err());
+        // The mismatch is never taken as the end of the stream.
+        assert!(aes_ctr_block_cipher.read(&mut [0u8; 16]).is_err());
+        assert!(!aes_ctr_block_cipher.state.as_ref().unwrap().done);
 
         // Expected HMAC is right
         lbco.public.hmac = exp_hmac;
@@ -376,6 +383,11 @@ mod tests {
         assert!(aes_ctr_block_cipher
             .read_to_end(&mut plaintxt_data)
             .is_err());
+        assert!(
+            tokio::io::AsyncReadExt::read(&mut aes_ctr_block_cipher, &mut [0u8; 16])
+                .await
+                .is_err()
+        );
 
         // Expected HMAC is right
         lbco.public.hmac = exp_hmac;
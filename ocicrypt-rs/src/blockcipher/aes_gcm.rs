@@ -0,0 +1,424 @@
+// Copyright The ocicrypt Authors.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The AES GCM layer cipher. A layer is encrypted in segments of
+//! [`SEGMENT_SIZE`] bytes, each one sealed by AES-256-GCM with its own tag,
+//! so that a layer is streamed and authenticated segment by segment, rather
+//! than by an HMAC of the whole layer.
+//!
+//! The nonce of a segment is the nonce prefix of the layer, the index of the
+//! segment and a flag of the last segment, as in the STREAM construction, so
+//! that the segments can not be reordered, and the layer can not be cut.
+
+use std::io::{Error, ErrorKind, Read};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Result};
+
+use crate::blockcipher::{EncryptionFinalizer, LayerBlockCipher, LayerBlockCipherOptions};
+
+use super::rand::rand_bytes;
+
+const AES256_KEY_SIZE: usize = 32;
+const NONCE_PREFIX_SIZE: usize = 7;
+const TAG_SIZE: usize = 16;
+
+/// Size of the plaintext of a segment, but the last one which may be
+/// shorter.
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+
+struct Segments {
+    encrypt: bool,
+    cipher: Aes256Gcm,
+    nonce_prefix: Vec<u8>,
+    counter: u32,
+    // The input not sealed/opened yet, which holds one more byte than a
+    // segment to tell whether the segment is the last one.
+    input: Vec<u8>,
+    eof: bool,
+    output: Vec<u8>,
+    output_pos: usize,
+    done: bool,
+}
+
+impl Segments {
+    fn segment_size(&self) -> usize {
+        if self.encrypt {
+            SEGMENT_SIZE
+        } else {
+            SEGMENT_SIZE + TAG_SIZE
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.eof || self.input.len() > self.segment_size()
+    }
+
+    // Get the free space of the input buffer to read the data into.
+    fn spare_input(&mut self) -> &mut [u8] {
+        let len = self.input.len();
+        self.input.resize(self.segment_size() + 1, 0);
+        &mut self.input[len..]
+    }
+
+    // Take the `n` bytes read into the spare input.
+    fn fill_input(&mut self, len: usize, n: usize) {
+        self.input.truncate(len + n);
+        if n == 0 {
+            self.eof = true;
+        }
+    }
+
+    // Seal/open the next segment of the full input.
+    fn process(&mut self) -> std::io::Result<()> {
+        let last = self.input.len() <= self.segment_size();
+        let size = self.input.len().min(self.segment_size());
+
+        let mut nonce = self.nonce_prefix.clone();
+        nonce.extend_from_slice(&self.counter.to_be_bytes());
+        nonce.push(last as u8);
+        let nonce = Nonce::from_slice(&nonce);
+
+        let segment = &self.input[..size];
+        self.output = if self.encrypt {
+            self.cipher
+                .encrypt(nonce, segment)
+                .map_err(|_| Error::other("failed to encrypt layer segment"))?
+        } else {
+            self.cipher.decrypt(nonce, segment).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "failed decrypt byte stream, segment {} corrupted",
+                        self.counter
+                    ),
+                )
+            })?
+        };
+        self.output_pos = 0;
+        self.input.drain(..size);
+
+        if last {
+            self.done = true;
+        } else {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| Error::other("too many layer segments"))?;
+        }
+
+        Ok(())
+    }
+
+    // Copy the output into `buf`, returning the bytes copied.
+    fn read_output(&mut self, buf: &mut [u8]) -> usize {
+        let output = &self.output[self.output_pos..];
+        let n = output.len().min(buf.len());
+        buf[..n].copy_from_slice(&output[..n]);
+        self.output_pos += n;
+        n
+    }
+
+    fn has_output(&self) -> bool {
+        self.output_pos < self.output.len()
+    }
+}
+
+pin_project_lite::pin_project! {
+    struct AESGCMBlockCipherState<R> {
+        segments: Segments,
+        #[pin]
+        reader: R,
+    }
+}
+
+/// Implementation of the segmented AES GCM cipher.
+pub struct AESGCMBlockCipher<R> {
+    key_len: usize,
+    state: Option<AESGCMBlockCipherState<R>>,
+}
+
+impl<R> AESGCMBlockCipher<R> {
+    /// Create a new instance of `AESGCMBlockCipher`.
+    pub fn new(bits: usize) -> Result<AESGCMBlockCipher<R>> {
+        if bits != AES256_KEY_SIZE * 8 {
+            return Err(anyhow!("AES GCM bit count not supported"));
+        }
+
+        Ok(AESGCMBlockCipher {
+            key_len: AES256_KEY_SIZE,
+            state: None,
+        })
+    }
+
+    // init initializes an instance
+    fn init(&mut self, encrypt: bool, reader: R, opts: &mut LayerBlockCipherOptions) -> Result<()> {
+        let symmetric_key = &opts.private.symmetric_key;
+        if symmetric_key.len() != AES256_KEY_SIZE {
+            return Err(anyhow!(
+                "invalid key length of {} bytes; expect {} bytes",
+                symmetric_key.len(),
+                AES256_KEY_SIZE
+            ));
+        }
+
+        let mut nonce_prefix = vec![0u8; NONCE_PREFIX_SIZE];
+        match opts.get_opt("nonce") {
+            Some(v) => {
+                if v.len() != NONCE_PREFIX_SIZE {
+                    return Err(anyhow!(
+                        "invalid nonce length of {} bytes; need {} bytes",
+                        v.len(),
+                        NONCE_PREFIX_SIZE
+                    ));
+                }
+                nonce_prefix = v;
+            }
+            None if encrypt => rand_bytes(&mut nonce_prefix[..])?,
+            None => return Err(anyhow!("nonce is not provided for decryption process")),
+        }
+
+        let cipher = Aes256Gcm::new_from_slice(symmetric_key)
+            .map_err(|_| anyhow!("Failed to create AES GCM cipher"))?;
+
+        self.state = Some(AESGCMBlockCipherState {
+            segments: Segments {
+                encrypt,
+                cipher,
+                nonce_prefix: nonce_prefix.clone(),
+                counter: 0,
+                input: Vec::new(),
+                eof: false,
+                output: Vec::new(),
+                output_pos: 0,
+                done: false,
+            },
+            reader,
+        });
+
+        opts.private
+            .cipher_options
+            .entry("nonce".to_string())
+            .or_insert(nonce_prefix);
+
+        Ok(())
+    }
+}
+
+impl<R> LayerBlockCipher<R> for AESGCMBlockCipher<R> {
+    fn generate_key(&self) -> Result<Vec<u8>> {
+        let mut key = vec![0; self.key_len];
+        rand_bytes(&mut key[..])?;
+        Ok(key)
+    }
+
+    fn encrypt(&mut self, input: R, opts: &mut LayerBlockCipherOptions) -> Result<()> {
+        self.init(true, input, opts)
+    }
+
+    fn decrypt(&mut self, input: R, opts: &mut LayerBlockCipherOptions) -> Result<()> {
+        self.init(false, input, opts)
+    }
+}
+
+impl<R> EncryptionFinalizer for AESGCMBlockCipher<R> {
+    fn finalized_lbco(&self, opts: &mut LayerBlockCipherOptions) -> Result<()> {
+        let state = self
+            .state
+            .as_ref()
+            .ok_or_else(|| anyhow!("The AESGCMBlockCipher object hasn't been initialized yet"))?;
+        if !state.segments.done {
+            Err(anyhow!("Read()ing not complete, unable to finalize"))
+        } else {
+            // The segments are authenticated by their own tags.
+            opts.public.hmac = vec![];
+            Ok(())
+        }
+    }
+}
+
+impl<R: Read> Read for AESGCMBlockCipher<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let state = self
+            .state
+            .as_mut()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::Unsupported))?;
+        let segments = &mut state.segments;
+
+        loop {
+            if segments.has_output() {
+                return Ok(segments.read_output(buf));
+            }
+            if segments.done {
+                return Ok(0);
+            }
+
+            if segments.is_full() {
+                segments.process()?;
+                continue;
+            }
+
+            let len = segments.input.len();
+            let n = match state.reader.read(segments.spare_input()) {
+                Ok(n) => n,
+                Err(e) => {
+                    segments.input.truncate(len);
+                    return Err(e);
+                }
+            };
+            segments.fill_input(len, n);
+        }
+    }
+}
+
+#[cfg(feature = "async-io")]
+impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AESGCMBlockCipher<R> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        use std::task::Poll;
+
+        if self.state.is_none() {
+            return Poll::Ready(Err(std::io::Error::from(std::io::ErrorKind::Unsupported)));
+        }
+
+        // This is okay because `state` is pinned when `self` is.
+        let state = unsafe { self.map_unchecked_mut(|v| v.state.as_mut().unwrap()) };
+        let pinned_state = state.project();
+        let segments = pinned_state.segments;
+        let mut reader = pinned_state.reader;
+
+        loop {
+            if segments.has_output() {
+                let n = segments.read_output(buf.initialize_unfilled());
+                buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            if segments.done {
+                return Poll::Ready(Ok(()));
+            }
+
+            if segments.is_full() {
+                segments.process()?;
+                continue;
+            }
+
+            let len = segments.input.len();
+            let mut input = tokio::io::ReadBuf::new(segments.spare_input());
+            match reader.as_mut().poll_read(cx, &mut input) {
+                Poll::Ready(Ok(())) => {
+                    let n = input.filled().len();
+                    segments.fill_input(len, n);
+                }
+                Poll::Ready(Err(e)) => {
+                    segments.input.truncate(len);
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Pending => {
+                    segments.input.truncate(len);
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(layer_data: &[u8], lbco: &mut LayerBlockCipherOptions) -> Vec<u8> {
+        let mut aes_gcm_block_cipher = AESGCMBlockCipher::new(256).unwrap();
+        lbco.private.symmetric_key = aes_gcm_block_cipher.generate_key().unwrap();
+        assert!(aes_gcm_block_cipher.encrypt(layer_data, lbco).is_ok());
+
+        let mut encrypted_data: Vec<u8> = Vec::new();
+        assert!(aes_gcm_block_cipher
+            .read_to_end(&mut encrypted_data)
+            .is_ok());
+        assert!(aes_gcm_block_cipher.finalized_lbco(lbco).is_ok());
+        encrypted_data
+    }
+
+    fn decrypt(encrypted_data: &[u8], lbco: &mut LayerBlockCipherOptions) -> Result<Vec<u8>> {
+        let mut aes_gcm_block_cipher = AESGCMBlockCipher::new(256).unwrap();
+        aes_gcm_block_cipher.decrypt(encrypted_data, lbco)?;
+
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        aes_gcm_block_cipher.read_to_end(&mut plaintxt_data)?;
+        Ok(plaintxt_data)
+    }
+
+    #[test]
+    fn test_aes_gcm_block_cipher() {
+        assert!(AESGCMBlockCipher::<&[u8]>::new(128).is_err());
+
+        for size in [0, 1, SEGMENT_SIZE, SEGMENT_SIZE + 1, 3 * SEGMENT_SIZE - 1] {
+            let layer_data: Vec<u8> = (0..size).map(|i| i as u8).collect();
+            let mut lbco = LayerBlockCipherOptions::default();
+
+            // Error due to LayerBlockCipherOptions without symmetric key
+            let mut aes_gcm_block_cipher = AESGCMBlockCipher::new(256).unwrap();
+            assert!(aes_gcm_block_cipher
+                .encrypt(layer_data.as_slice(), &mut lbco)
+                .is_err());
+
+            let encrypted_data = encrypt(&layer_data, &mut lbco);
+            let segments = size.div_ceil(SEGMENT_SIZE).max(1);
+            assert_eq!(encrypted_data.len(), size + segments * TAG_SIZE);
+            assert!(lbco.public.hmac.is_empty());
+
+            assert_eq!(decrypt(&encrypted_data, &mut lbco).unwrap(), layer_data);
+
+            // Corrupted segment
+            let mut corrupted_data = encrypted_data.clone();
+            corrupted_data[0] ^= 1;
+            assert!(decrypt(&corrupted_data, &mut lbco).is_err());
+
+            // Cut layer
+            assert!(decrypt(&encrypted_data[..encrypted_data.len() - 1], &mut lbco).is_err());
+            if segments > 1 {
+                let cut = encrypted_data.len() - encrypted_data.len() % (SEGMENT_SIZE + TAG_SIZE);
+                assert!(decrypt(&encrypted_data[..cut], &mut lbco).is_err());
+            }
+
+            // Wrong key
+            let mut wrong_lbco = lbco.clone();
+            wrong_lbco.private.symmetric_key = vec![0; 32];
+            assert!(decrypt(&encrypted_data, &mut wrong_lbco).is_err());
+        }
+    }
+
+    #[cfg(feature = "async-io")]
+    #[tokio::test]
+    async fn test_async_aes_gcm_block_cipher() {
+        let layer_data: Vec<u8> = (0..2 * SEGMENT_SIZE + 7).map(|i| i as u8).collect();
+        let mut lbco = LayerBlockCipherOptions::default();
+        let encrypted_data = encrypt(&layer_data, &mut lbco);
+
+        let mut aes_gcm_block_cipher = AESGCMBlockCipher::new(256).unwrap();
+        assert!(aes_gcm_block_cipher
+            .decrypt(encrypted_data.as_slice(), &mut lbco)
+            .is_ok());
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut aes_gcm_block_cipher, &mut plaintxt_data)
+            .await
+            .unwrap();
+        assert_eq!(layer_data, plaintxt_data);
+
+        let mut aes_gcm_block_cipher = AESGCMBlockCipher::new(256).unwrap();
+        assert!(aes_gcm_block_cipher
+            .decrypt(&encrypted_data[..SEGMENT_SIZE + TAG_SIZE], &mut lbco)
+            .is_ok());
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        assert!(tokio::io::AsyncReadExt::read_to_end(
+            &mut aes_gcm_block_cipher,
+            &mut plaintxt_data
+        )
+        .await
+        .is_err());
+    }
+}
@@ -12,6 +12,9 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 mod aes_ctr;
 use aes_ctr::AESCTRBlockCipher;
 
+mod aes_gcm;
+use aes_gcm::AESGCMBlockCipher;
+
 mod rand;
 
 /// Type of the cipher algorithm used to encrypt/decrypt image layers.
@@ -21,6 +24,10 @@ pub type LayerCipherType = String;
 /// The default cipher algorithm for image layer encryption/decryption.
 pub const AES256CTR: &str = "AES_256_CTR_HMAC_SHA256";
 
+/// The cipher algorithm of the newer layer encryption scheme, authenticating the layer by
+/// segments of AES GCM rather than by an HMAC of the whole layer.
+pub const AES256GCM: &str = "AES_256_GCM";
+
 base64_serde_type!(Base64Vec, base64::engine::general_purpose::STANDARD);
 
 fn base64_hashmap_s<S>(value: &HashMap<String, Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error>
@@ -151,6 +158,8 @@ pub trait EncryptionFinalizer {
 pub enum LayerBlockCipherHandler<R> {
     /// AES_256_CTR_HMAC_SHA256
     Aes256Ctr(AESCTRBlockCipher<R>),
+    /// AES_256_GCM
+    Aes256Gcm(AESGCMBlockCipher<R>),
 }
 
 impl<R> LayerBlockCipherHandler<R> {
@@ -159,43 +168,72 @@ impl<R> LayerBlockCipherHandler<R> {
         let aes_ctr_block_cipher = AESCTRBlockCipher::new(256)?;
         Ok(LayerBlockCipherHandler::Aes256Ctr(aes_ctr_block_cipher))
     }
+
+    /// Create a [`LayerBlockCipherHandler`] object with the block cipher of `typ`.
+    pub fn with_cipher_type(typ: &str) -> Result<LayerBlockCipherHandler<R>> {
+        match typ {
+            AES256CTR => LayerBlockCipherHandler::new(),
+            AES256GCM => {
+                let aes_gcm_block_cipher = AESGCMBlockCipher::new(256)?;
+                Ok(LayerBlockCipherHandler::Aes256Gcm(aes_gcm_block_cipher))
+            }
+            _ => Err(anyhow!("unsupported cipher type {}", typ)),
+        }
+    }
+
+    fn cipher_type(&self) -> &'static str {
+        match self {
+            LayerBlockCipherHandler::Aes256Ctr(_) => AES256CTR,
+            LayerBlockCipherHandler::Aes256Gcm(_) => AES256GCM,
+        }
+    }
 }
 
 impl<R> LayerBlockCipherHandler<R> {
-    /// Setup the context for image layer encryption.
+    /// Setup the context for image layer encryption, switching to the block cipher of `typ`.
     pub fn encrypt(
         &mut self,
         plain_data_reader: R,
         typ: &str,
         opts: &mut LayerBlockCipherOptions,
     ) -> Result<()> {
+        if typ != self.cipher_type() {
+            *self = LayerBlockCipherHandler::with_cipher_type(typ)?;
+        }
+
         match self {
             LayerBlockCipherHandler::Aes256Ctr(block_cipher) => {
-                if typ != AES256CTR {
-                    return Err(anyhow!("unsupported cipher type {}", typ));
-                }
                 opts.private.symmetric_key = block_cipher.generate_key()?;
-                opts.public.cipher_type = AES256CTR.to_string();
+                block_cipher.encrypt(plain_data_reader, opts)?;
+            }
+            LayerBlockCipherHandler::Aes256Gcm(block_cipher) => {
+                opts.private.symmetric_key = block_cipher.generate_key()?;
                 block_cipher.encrypt(plain_data_reader, opts)?;
             }
         }
+        opts.public.cipher_type = typ.to_string();
 
         Ok(())
     }
 
-    /// Setup the context for image layer decryption.
+    /// Setup the context for image layer decryption, switching to the block cipher of the
+    /// cipher type in the public options, so that the layers of both the AES CTR and the
+    /// AES GCM schemes can be decrypted.
     pub fn decrypt(
         &mut self,
         enc_data_reader: R,
         opts: &mut LayerBlockCipherOptions,
     ) -> Result<()> {
-        let typ = &opts.public.cipher_type;
+        let typ = opts.public.cipher_type.as_str();
+        if typ != self.cipher_type() {
+            *self = LayerBlockCipherHandler::with_cipher_type(typ)?;
+        }
 
         match self {
             LayerBlockCipherHandler::Aes256Ctr(block_cipher) => {
-                if typ != AES256CTR {
-                    return Err(anyhow!("unsupported cipher type {}", typ));
-                }
+                block_cipher.decrypt(enc_data_reader, opts)?;
+            }
+            LayerBlockCipherHandler::Aes256Gcm(block_cipher) => {
                 block_cipher.decrypt(enc_data_reader, opts)?;
             }
         }
@@ -208,6 +246,7 @@ impl<R> EncryptionFinalizer for LayerBlockCipherHandler<R> {
     fn finalized_lbco(&self, opts: &mut LayerBlockCipherOptions) -> Result<()> {
         match self {
             LayerBlockCipherHandler::Aes256Ctr(block_cipher) => block_cipher.finalized_lbco(opts),
+            LayerBlockCipherHandler::Aes256Gcm(block_cipher) => block_cipher.finalized_lbco(opts),
         }
     }
 }
@@ -216,6 +255,7 @@ impl<R: Read> Read for LayerBlockCipherHandler<R> {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         match self {
             LayerBlockCipherHandler::Aes256Ctr(block_cipher) => block_cipher.read(buf),
+            LayerBlockCipherHandler::Aes256Gcm(block_cipher) => block_cipher.read(buf),
         }
     }
 }
@@ -228,12 +268,16 @@ impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for LayerBlockCipherHandler<R
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
         // This is okay because `block_cipher` is pinned when `self` is.
-        let aes_ctr_256 = unsafe {
-            self.map_unchecked_mut(|v| match v {
-                LayerBlockCipherHandler::Aes256Ctr(block_cipher) => block_cipher,
-            })
-        };
-        aes_ctr_256.poll_read(cx, buf)
+        unsafe {
+            match self.get_unchecked_mut() {
+                LayerBlockCipherHandler::Aes256Ctr(block_cipher) => {
+                    std::pin::Pin::new_unchecked(block_cipher).poll_read(cx, buf)
+                }
+                LayerBlockCipherHandler::Aes256Gcm(block_cipher) => {
+                    std::pin::Pin::new_unchecked(block_cipher).poll_read(cx, buf)
+                }
+            }
+        }
     }
 }
 
@@ -255,9 +299,8 @@ mod tests {
         assert!(lbch
             .encrypt(layer_data.as_slice(), AES256CTR, &mut lbco)
             .is_ok());
-        let LayerBlockCipherHandler::Aes256Ctr(mut encryptor) = lbch;
-        assert!(encryptor.read_to_end(&mut encrypted_data).is_ok());
-        assert!(encryptor.finalized_lbco(&mut lbco).is_ok());
+        assert!(lbch.read_to_end(&mut encrypted_data).is_ok());
+        assert!(lbch.finalized_lbco(&mut lbco).is_ok());
 
         let serialized_json = serde_json::to_string(&lbco).unwrap();
 
@@ -267,9 +310,8 @@ mod tests {
             serde_json::from_str(&serialized_json).unwrap_or_default();
 
         assert!(lbch.decrypt(encrypted_data.as_slice(), &mut lbco).is_ok());
-        let LayerBlockCipherHandler::Aes256Ctr(mut decryptor) = lbch;
         let mut plaintxt_data: Vec<u8> = Vec::new();
-        assert!(decryptor.read_to_end(&mut plaintxt_data).is_ok());
+        assert!(lbch.read_to_end(&mut plaintxt_data).is_ok());
 
         // Decrypted data should equal to original data
         assert_eq!(layer_data, plaintxt_data);
@@ -278,8 +320,44 @@ mod tests {
         let mut lbch = LayerBlockCipherHandler::new().unwrap();
         lbco.private.symmetric_key = vec![0; 32];
         assert!(lbch.decrypt(encrypted_data.as_slice(), &mut lbco).is_ok());
-        let LayerBlockCipherHandler::Aes256Ctr(mut decryptor) = lbch;
         let mut plaintxt_data: Vec<u8> = Vec::new();
-        assert!(decryptor.read_to_end(&mut plaintxt_data).is_err());
+        assert!(lbch.read_to_end(&mut plaintxt_data).is_err());
+    }
+
+    #[test]
+    fn test_layer_block_cipher_handler_aes_gcm() {
+        let layer_data: Vec<u8> = b"this is some data".to_vec();
+
+        let mut lbco = LayerBlockCipherOptions::default();
+        let mut lbch = LayerBlockCipherHandler::new().unwrap();
+        assert!(lbch
+            .encrypt(layer_data.as_slice(), "AES_128_GCM", &mut lbco)
+            .is_err());
+        assert!(lbch
+            .encrypt(layer_data.as_slice(), AES256GCM, &mut lbco)
+            .is_ok());
+        assert_eq!(lbco.public.cipher_type, AES256GCM);
+
+        let mut encrypted_data: Vec<u8> = Vec::new();
+        assert!(lbch.read_to_end(&mut encrypted_data).is_ok());
+        assert!(lbch.finalized_lbco(&mut lbco).is_ok());
+
+        let serialized_json = serde_json::to_string(&lbco).unwrap();
+
+        // The default handler follows the cipher type of the layer
+        let mut lbch = LayerBlockCipherHandler::new().unwrap();
+        let mut lbco: LayerBlockCipherOptions =
+            serde_json::from_str(&serialized_json).unwrap_or_default();
+
+        assert!(lbch.decrypt(encrypted_data.as_slice(), &mut lbco).is_ok());
+        assert!(matches!(lbch, LayerBlockCipherHandler::Aes256Gcm(_)));
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        assert!(lbch.read_to_end(&mut plaintxt_data).is_ok());
+        assert_eq!(layer_data, plaintxt_data);
+
+        // Decrypt as AES CTR
+        let mut lbch = LayerBlockCipherHandler::with_cipher_type(AES256GCM).unwrap();
+        lbco.public.cipher_type = AES256CTR.to_string();
+        assert!(lbch.decrypt(encrypted_data.as_slice(), &mut lbco).is_err());
     }
 }
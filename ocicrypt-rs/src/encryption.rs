@@ -33,13 +33,14 @@ impl EncLayerFinalizer {
         annotations: Option<&HashMap<String, String>>,
         finalizer: Option<&mut impl EncryptionFinalizer>,
     ) -> Result<HashMap<String, String>> {
-        let mut priv_opts = vec![];
-        let mut pub_opts = vec![];
         if let Some(finalizer) = finalizer {
             finalizer.finalized_lbco(&mut self.lbco)?;
-            priv_opts = serde_json::to_vec(&self.lbco.private)?;
-            pub_opts = serde_json::to_vec(&self.lbco.public)?;
         }
+        if self.lbco.private.symmetric_key.is_empty() {
+            return Err(anyhow!("no layer key to wrap"));
+        }
+        let priv_opts = serde_json::to_vec(&self.lbco.private)?;
+        let pub_opts = serde_json::to_vec(&self.lbco.public)?;
 
         let mut new_annotations = HashMap::new();
         let mut keys_wrapped = false;
@@ -50,10 +51,14 @@ impl EncLayerFinalizer {
                 b64_annotations = key_annotations.clone();
             }
 
+            // Only the key wrappers with recipients wrap the key, the keys wrapped
+            // already are kept.
             let key_wrapper = get_key_wrapper(scheme)?;
-            b64_annotations = pre_wrap_key(key_wrapper, ec, b64_annotations, &priv_opts)?;
-            if !b64_annotations.is_empty() {
+            if key_wrapper.probe_encrypt(&ec.param) {
+                b64_annotations = pre_wrap_key(key_wrapper, ec, b64_annotations, &priv_opts)?;
                 keys_wrapped = true;
+            }
+            if !b64_annotations.is_empty() {
                 new_annotations.insert(annotations_id.to_string(), b64_annotations);
             }
         }
@@ -207,6 +212,9 @@ pub fn decrypt_layer_key_opts_data(
 }
 
 /// encrypt_layer encrypts the layer by running one encryptor after the other
+///
+/// If the layer is encrypted already, no encryptor is returned, and the layer key is
+/// unwrapped by `ec.decrypt_config` to be wrapped for the new recipients.
 pub fn encrypt_layer<'a, R: 'a + Read>(
     ec: &EncryptConfig,
     layer_reader: R,
@@ -216,36 +224,56 @@ pub fn encrypt_layer<'a, R: 'a + Read>(
     Option<impl Read + EncryptionFinalizer + 'a>,
     EncLayerFinalizer,
 )> {
-    let mut encrypted = false;
-    for (annotations_id, _scheme) in KEY_WRAPPERS_ANNOTATIONS.iter() {
-        let anno = annotations.unwrap_or(&DEFAULT_ANNOTATION_MAP);
-        if anno.contains_key(annotations_id) {
-            if let Some(decrypt_config) = ec.decrypt_config.as_ref() {
-                decrypt_layer_key_opts_data(decrypt_config, annotations)?;
-                get_layer_pub_opts(anno)?;
-
-                // already encrypted!
-                encrypted = true;
-            } else {
-                return Err(anyhow!(
-                    "EncryptConfig::decrypt_config must not be None for encrypted layers"
-                ));
-            }
-        }
+    let anno = annotations.unwrap_or(&DEFAULT_ANNOTATION_MAP);
+    if KEY_WRAPPERS_ANNOTATIONS
+        .keys()
+        .any(|annotations_id| anno.contains_key(annotations_id))
+    {
+        // already encrypted!
+        let decrypt_config = ec.decrypt_config.as_ref().ok_or_else(|| {
+            anyhow!("EncryptConfig::decrypt_config must not be None for encrypted layers")
+        })?;
+        let priv_opts_data = decrypt_layer_key_opts_data(decrypt_config, annotations)?;
+        let pub_opts_data = get_layer_pub_opts(anno)?;
+        let lbco = LayerBlockCipherOptions {
+            public: serde_json::from_slice(&pub_opts_data)?,
+            private: serde_json::from_slice(&priv_opts_data)?,
+        };
+
+        return Ok((None, EncLayerFinalizer { lbco }));
     }
 
-    if !encrypted {
-        let mut lbch = LayerBlockCipherHandler::new()?;
-        let mut lbco = LayerBlockCipherOptions::default();
+    let mut lbch = LayerBlockCipherHandler::new()?;
+    let mut lbco = LayerBlockCipherOptions::default();
 
-        lbch.encrypt(layer_reader, AES256CTR, &mut lbco)?;
-        lbco.private.digest = digest.to_string();
-        let enc_layer_finalizer = EncLayerFinalizer { lbco };
+    lbch.encrypt(layer_reader, AES256CTR, &mut lbco)?;
+    lbco.private.digest = digest.to_string();
+    let enc_layer_finalizer = EncLayerFinalizer { lbco };
 
-        Ok((Some(lbch), enc_layer_finalizer))
-    } else {
-        Ok((None, EncLayerFinalizer::default()))
-    }
+    Ok((Some(lbch), enc_layer_finalizer))
+}
+
+/// This is a streaming version of [`encrypt_layer`] for the layers not encrypted yet, e.g.
+/// the layers built or modified in the guest, so that the plaintext layers are never
+/// pushed out of the guest.
+///
+/// The annotations of the encrypted layer can be got from [`EncLayerFinalizer`] once the
+/// encryptor is read to the end.
+#[cfg(feature = "async-io")]
+pub fn async_encrypt_layer<R: tokio::io::AsyncRead + Send>(
+    layer_reader: R,
+    digest: &str,
+) -> Result<(
+    impl tokio::io::AsyncRead + EncryptionFinalizer + Send,
+    EncLayerFinalizer,
+)> {
+    let mut lbch = LayerBlockCipherHandler::new()?;
+    let mut lbco = LayerBlockCipherOptions::default();
+
+    lbch.encrypt(layer_reader, AES256CTR, &mut lbco)?;
+    lbco.private.digest = digest.to_string();
+
+    Ok((lbch, EncLayerFinalizer { lbco }))
 }
 
 // decrypt_layer decrypts a layer trying one keywrapper after the other to see whether it
@@ -408,6 +436,115 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_reencrypt_layer() {
+        let path = load_data_path();
+        let pub_key = fs::read(format!("{}/{}", path, "public_key.pem")).unwrap();
+        let priv_key = fs::read(format!("{}/{}", path, "private_key.pem")).unwrap();
+        let pub_key_ec = fs::read(format!("{}/{}", path, "public_key_ec_p256.pem")).unwrap();
+        let priv_key_ec = fs::read(format!("{}/{}", path, "private_key_ec_p256.pem")).unwrap();
+
+        let mut ec = EncryptConfig::default();
+        assert!(ec.encrypt_with_jwe(vec![pub_key]).is_ok());
+
+        let layer_data: Vec<u8> = b"This is some text!".to_vec();
+        let digest = format!("sha256:{:x}", Sha256::digest(&layer_data));
+        let (layer_encryptor, mut elf) =
+            encrypt_layer(&ec, layer_data.as_slice(), None, &digest).unwrap();
+        let mut encrypted_data: Vec<u8> = Vec::new();
+        let mut encryptor = layer_encryptor.unwrap();
+        assert!(encryptor.read_to_end(&mut encrypted_data).is_ok());
+        let annotations = elf
+            .finalize_annotations(&ec, None, Some(&mut encryptor))
+            .unwrap();
+
+        // Wrap the layer key for a new recipient
+        let mut dc = DecryptConfig::default();
+        assert!(dc
+            .decrypt_with_priv_keys(vec![priv_key], vec![vec![]])
+            .is_ok());
+        let mut ec = EncryptConfig::default();
+        assert!(ec.encrypt_with_jwe(vec![pub_key_ec]).is_ok());
+        assert!(encrypt_layer(&ec, layer_data.as_slice(), Some(&annotations), &digest).is_err());
+
+        ec.decrypt_config = Some(dc);
+        let (layer_encryptor, mut elf) =
+            encrypt_layer(&ec, layer_data.as_slice(), Some(&annotations), &digest).unwrap();
+        assert!(layer_encryptor.is_none());
+        let new_annotations = elf
+            .finalize_annotations(
+                &ec,
+                Some(&annotations),
+                None::<&mut LayerBlockCipherHandler<&[u8]>>,
+            )
+            .unwrap();
+        assert_eq!(
+            new_annotations["org.opencontainers.image.enc.keys.jwe"]
+                .split(',')
+                .count(),
+            2
+        );
+
+        let mut dc = DecryptConfig::default();
+        assert!(dc
+            .decrypt_with_priv_keys(vec![priv_key_ec], vec![vec![]])
+            .is_ok());
+        let (layer_decryptor, dec_digest) = decrypt_layer(
+            &dc,
+            encrypted_data.as_slice(),
+            Some(&new_annotations),
+            false,
+        )
+        .unwrap();
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        assert!(layer_decryptor
+            .unwrap()
+            .read_to_end(&mut plaintxt_data)
+            .is_ok());
+        assert_eq!(layer_data, plaintxt_data);
+        assert_eq!(digest, dec_digest);
+    }
+
+    #[cfg(feature = "async-io")]
+    #[tokio::test]
+    async fn test_async_encrypt_layer() {
+        let path = load_data_path();
+        let pub_key = fs::read(format!("{}/{}", path, "public_key.pem")).unwrap();
+        let priv_key = fs::read(format!("{}/{}", path, "private_key.pem")).unwrap();
+
+        let mut ec = EncryptConfig::default();
+        assert!(ec.encrypt_with_jwe(vec![pub_key]).is_ok());
+
+        let mut dc = DecryptConfig::default();
+        assert!(dc
+            .decrypt_with_priv_keys(vec![priv_key], vec![vec![]])
+            .is_ok());
+
+        let layer_data: Vec<u8> = b"This is some text!".to_vec();
+        let digest = format!("sha256:{:x}", Sha256::digest(&layer_data));
+
+        let (mut encryptor, mut elf) = async_encrypt_layer(layer_data.as_slice(), &digest).unwrap();
+        let mut encrypted_data: Vec<u8> = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut encryptor, &mut encrypted_data)
+            .await
+            .unwrap();
+        let new_annotations = elf
+            .finalize_annotations(&ec, None, Some(&mut encryptor))
+            .unwrap();
+        assert!(new_annotations.contains_key("org.opencontainers.image.enc.pubopts"));
+
+        let key_opts = decrypt_layer_key_opts_data(&dc, Some(&new_annotations)).unwrap();
+        let (mut async_reader, dec_digest) =
+            async_decrypt_layer(encrypted_data.as_slice(), Some(&new_annotations), &key_opts)
+                .unwrap();
+        let mut plaintxt_data: Vec<u8> = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut async_reader, &mut plaintxt_data)
+            .await
+            .unwrap();
+        assert_eq!(layer_data, plaintxt_data);
+        assert_eq!(digest, dec_digest);
+    }
+
     fn load_data_path() -> String {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.push("data");
@@ -26,7 +26,7 @@ fn encrypter(pubkey: &[u8]) -> Result<Box<dyn JweEncrypter>> {
     }
 
     match key_info.alg() {
-        Some(KeyAlg::Ec { curve: Some(_) }) => {
+        Some(KeyAlg::Ec { curve: Some(_) }) | Some(KeyAlg::Ecx { curve: Some(_) }) => {
             let ecdh_encrypter = match key_info.format() {
                 KeyFormat::Der { raw: _ } => ECDH_ES_A256KW.encrypter_from_der(pubkey)?,
                 KeyFormat::Pem { traditional: _ } => ECDH_ES_A256KW.encrypter_from_pem(pubkey)?,
@@ -35,7 +35,7 @@ fn encrypter(pubkey: &[u8]) -> Result<Box<dyn JweEncrypter>> {
 
             Ok(ecdh_encrypter.box_clone())
         }
-        _ => {
+        Some(KeyAlg::Rsa) => {
             let rsa_encrypter = match key_info.format() {
                 KeyFormat::Der { raw: _ } => RSA_OAEP.encrypter_from_der(pubkey)?,
                 KeyFormat::Pem { traditional: _ } => RSA_OAEP.encrypter_from_pem(pubkey)?,
@@ -44,6 +44,7 @@ fn encrypter(pubkey: &[u8]) -> Result<Box<dyn JweEncrypter>> {
 
             Ok(rsa_encrypter.box_clone())
         }
+        alg => Err(anyhow!("jwe: unsupported public key algorithm {:?}", alg)),
     }
 }
 
@@ -56,7 +57,7 @@ fn decrypter(priv_key: &[u8]) -> Result<Box<dyn JweDecrypter>> {
     }
 
     match key_info.alg() {
-        Some(KeyAlg::Ec { curve: Some(_) }) => {
+        Some(KeyAlg::Ec { curve: Some(_) }) | Some(KeyAlg::Ecx { curve: Some(_) }) => {
             let ecdh_decrypter = match key_info.format() {
                 KeyFormat::Der { raw: _ } => ECDH_ES_A256KW.decrypter_from_der(priv_key)?,
                 KeyFormat::Pem { traditional: _ } => ECDH_ES_A256KW.decrypter_from_pem(priv_key)?,
@@ -65,7 +66,7 @@ fn decrypter(priv_key: &[u8]) -> Result<Box<dyn JweDecrypter>> {
 
             Ok(ecdh_decrypter.box_clone())
         }
-        _ => {
+        Some(KeyAlg::Rsa) => {
             let rsa_decrypter = match key_info.format() {
                 KeyFormat::Der { raw: _ } => RSA_OAEP.decrypter_from_der(priv_key)?,
                 KeyFormat::Pem { traditional: _ } => RSA_OAEP.decrypter_from_pem(priv_key)?,
@@ -74,9 +75,39 @@ fn decrypter(priv_key: &[u8]) -> Result<Box<dyn JweDecrypter>> {
 
             Ok(rsa_decrypter.box_clone())
         }
+        alg => Err(anyhow!("jwe: unsupported private key algorithm {:?}", alg)),
     }
 }
 
+// Split the general JSON serialization of a JWE into the flattened ones of
+// its recipients. The recipients of a JWE are only matched by their `alg`
+// when decrypted, so that a private key is tried against each recipient,
+// instead of only the first one of its algorithm, like the first of the
+// ECDH-ES+A256KW recipients of an image encrypted for several EC keys.
+fn flattened_jwes(data: &str) -> Result<Vec<String>> {
+    let mut jwe: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(data).map_err(|_e| anyhow!("jwe: invalid JSON serialization"))?;
+    let recipients = match jwe.remove("recipients") {
+        Some(serde_json::Value::Array(recipients)) => recipients,
+        Some(_) => return Err(anyhow!("jwe: invalid recipients")),
+        // Already in the flattened JSON serialization.
+        None => return Ok(vec![data.to_string()]),
+    };
+
+    recipients
+        .into_iter()
+        .map(|recipient| {
+            let serde_json::Value::Object(recipient) = recipient else {
+                return Err(anyhow!("jwe: invalid recipient"));
+            };
+
+            let mut flattened = jwe.clone();
+            flattened.extend(recipient);
+            Ok(serde_json::Value::Object(flattened).to_string())
+        })
+        .collect()
+}
+
 impl KeyWrapper for JweKeyWrapper {
     fn wrap_keys(&self, ec: &EncryptConfig, opts_data: &[u8]) -> Result<Vec<u8>> {
         let pubkeys = ec
@@ -108,10 +139,13 @@ impl KeyWrapper for JweKeyWrapper {
         let privkeys = self
             .private_keys(&dc.param)
             .ok_or_else(|| anyhow!("jwe: invalid configuration for keyunwrap"))?;
+        let jwes = flattened_jwes(data)?;
         for privkey in privkeys {
             let decrypter = decrypter(&privkey)?;
-            if let Ok((keys, _)) = deserialize_json(data, &*decrypter) {
-                return Ok(keys);
+            for jwe in jwes.iter() {
+                if let Ok((keys, _)) = deserialize_json(jwe, &*decrypter) {
+                    return Ok(keys);
+                }
             }
         }
 
@@ -126,6 +160,10 @@ impl KeyWrapper for JweKeyWrapper {
         dc_param.get("privkeys").is_some()
     }
 
+    fn probe_encrypt(&self, ec_param: &HashMap<String, Vec<Vec<u8>>>) -> bool {
+        ec_param.get("pubkeys").is_some()
+    }
+
     fn private_keys(&self, dc_param: &HashMap<String, Vec<Vec<u8>>>) -> Option<Vec<Vec<u8>>> {
         dc_param.get("privkeys").cloned()
     }
@@ -205,6 +243,62 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_keywrap_jwe_ecdh_es() {
+        let path = load_data_path();
+        let keys = [
+            ("public_key_ec.der", "private_key_ec.der"),
+            ("public_key_ec_p256.pem", "private_key_ec_p256.pem"),
+            ("public_key_x25519.pem", "private_key_x25519.pem"),
+            ("public_key.pem", "private_key.pem"),
+        ];
+        let payload = b"test".to_vec();
+        let jwe_key_wrapper = JweKeyWrapper {};
+
+        let mut ec = EncryptConfig::default();
+        let pubkeys = keys
+            .iter()
+            .map(|(pubkey, _)| fs::read(path.join(pubkey)).unwrap())
+            .collect();
+        assert!(ec.encrypt_with_jwe(pubkeys).is_ok());
+        let json = jwe_key_wrapper.wrap_keys(&ec, &payload).unwrap();
+
+        let jwe: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let algs: Vec<&str> = jwe["recipients"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|recipient| recipient["header"]["alg"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            algs,
+            [
+                "ECDH-ES+A256KW",
+                "ECDH-ES+A256KW",
+                "ECDH-ES+A256KW",
+                "RSA-OAEP"
+            ]
+        );
+
+        // Each private key alone decrypts its own recipient.
+        for (_, privkey) in keys {
+            let mut dc = DecryptConfig::default();
+            let privkeys = vec![fs::read(path.join(privkey)).unwrap()];
+            assert!(dc.decrypt_with_priv_keys(privkeys, vec![vec![]]).is_ok());
+            assert_eq!(jwe_key_wrapper.unwrap_keys(&dc, &json).unwrap(), payload);
+        }
+
+        // No recipient is of another EC key.
+        let mut ec = EncryptConfig::default();
+        let pubkeys = vec![fs::read(path.join("public_key_ec_p256.pem")).unwrap()];
+        assert!(ec.encrypt_with_jwe(pubkeys).is_ok());
+        let json = jwe_key_wrapper.wrap_keys(&ec, &payload).unwrap();
+        let mut dc = DecryptConfig::default();
+        let privkeys = vec![fs::read(path.join("private_key_x25519.pem")).unwrap()];
+        assert!(dc.decrypt_with_priv_keys(privkeys, vec![vec![]]).is_ok());
+        assert!(jwe_key_wrapper.unwrap_keys(&dc, &json).is_err());
+    }
+
     fn load_data_path() -> PathBuf {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.push("data");
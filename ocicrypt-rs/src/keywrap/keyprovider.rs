@@ -15,6 +15,9 @@ use crate::utils::{self, CommandExecuter};
 #[cfg(feature = "keywrap-keyprovider-native")]
 use attestation_agent::{AttestationAPIs, AttestationAgent};
 
+#[cfg(feature = "keywrap-keyprovider-grpc-server")]
+pub mod server;
+
 #[cfg(feature = "keywrap-keyprovider-native")]
 lazy_static! {
     pub static ref ATTESTATION_AGENT: std::sync::Arc<tokio::sync::Mutex<AttestationAgent>> =
@@ -572,6 +575,10 @@ impl KeyWrapper for KeyProviderKeyWrapper {
     fn probe(&self, _dc_param: &HashMap<String, Vec<Vec<u8>>>) -> bool {
         true
     }
+
+    fn probe_encrypt(&self, ec_param: &HashMap<String, Vec<Vec<u8>>>) -> bool {
+        ec_param.contains_key(&self.provider)
+    }
 }
 
 #[cfg(any(
@@ -0,0 +1,172 @@
+// Copyright The ocicrypt Authors.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The server side of the keyprovider gRPC protocol, which serves the key
+//! unwrap requests of the ocicrypt implementations, e.g. the host side
+//! tools for testing or alternative runtimes, by a [`KeyUnwrapper`] like
+//! CDH.
+
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use tonic::{Extensions, Request, Response, Status};
+
+use super::{
+    KeyProviderKeyWrapProtocolInput, KeyProviderKeyWrapProtocolOutput, KeyUnwrapResults, OpKey,
+};
+use crate::utils::grpc::keyprovider::key_provider_service_server::KeyProviderService;
+pub use crate::utils::grpc::keyprovider::key_provider_service_server::KeyProviderServiceServer;
+use crate::utils::grpc::keyprovider::{
+    KeyProviderKeyWrapProtocolInput as GrpcInput, KeyProviderKeyWrapProtocolOutput as GrpcOutput,
+};
+
+/// The backend unwrapping the keys of the keyprovider annotations.
+#[async_trait]
+pub trait KeyUnwrapper: Send + Sync + 'static {
+    /// Unwrap the layer key in `annotation`, the keyprovider annotation of
+    /// an encrypted layer, and return the private opts data of the layer.
+    /// The `extensions` are of the gRPC request, e.g. the connection info to
+    /// tell the peer.
+    async fn unwrap_key(&self, annotation: &[u8], extensions: &Extensions) -> Result<Vec<u8>>;
+}
+
+/// A keyprovider gRPC service backed by a [`KeyUnwrapper`]. Only the key
+/// unwrap operation is served, as the keys are wrapped when the images are
+/// built, out of the guest.
+pub struct KeyProviderServer<U> {
+    unwrapper: U,
+}
+
+impl<U: KeyUnwrapper> KeyProviderServer<U> {
+    /// Create a new instance of `KeyProviderServer`.
+    pub fn new(unwrapper: U) -> Self {
+        KeyProviderServer { unwrapper }
+    }
+
+    /// Handle the serialized `KeyProviderKeyWrapProtocolInput` of a key
+    /// unwrap request, returning the serialized output.
+    async fn unwrap_key(&self, input: &[u8], extensions: &Extensions) -> Result<Vec<u8>> {
+        let input: KeyProviderKeyWrapProtocolInput = serde_json::from_slice(input)
+            .map_err(|_| anyhow!("keyprovider: invalid input of {} operation", OpKey::Unwrap))?;
+        if input.op != OpKey::Unwrap.to_string() {
+            bail!("keyprovider: unexpected operation {}", input.op);
+        }
+
+        let annotation = input
+            .key_unwrap_params
+            .annotation
+            .ok_or_else(|| anyhow!("keyprovider: no annotation to unwrap"))?;
+        let annotation = base64::engine::general_purpose::STANDARD
+            .decode(annotation)
+            .map_err(|_| anyhow!("keyprovider: annotation is not base64 encoded"))?;
+        let opts_data = self.unwrapper.unwrap_key(&annotation, extensions).await?;
+
+        let output = KeyProviderKeyWrapProtocolOutput {
+            key_wrap_results: None,
+            key_unwrap_results: Some(KeyUnwrapResults { opts_data }),
+        };
+        serde_json::to_vec(&output).map_err(|_| {
+            anyhow!(
+                "keyprovider: error while serializing output of {} operation",
+                OpKey::Unwrap
+            )
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl<U: KeyUnwrapper> KeyProviderService for KeyProviderServer<U> {
+    async fn wrap_key(&self, _request: Request<GrpcInput>) -> Result<Response<GrpcOutput>, Status> {
+        Err(Status::unimplemented(format!(
+            "keyprovider: {} operation is not supported",
+            OpKey::Wrap
+        )))
+    }
+
+    async fn un_wrap_key(
+        &self,
+        request: Request<GrpcInput>,
+    ) -> Result<Response<GrpcOutput>, Status> {
+        let (_, extensions, input) = request.into_parts();
+        let output = self
+            .unwrap_key(&input.key_provider_key_wrap_protocol_input, &extensions)
+            .await
+            .map_err(|e| Status::internal(format!("{e}")))?;
+
+        Ok(Response::new(GrpcOutput {
+            key_provider_key_wrap_protocol_output: output,
+        }))
+    }
+}
+
+/// Serve the keyprovider gRPC service backed by `unwrapper` at `addr`,
+/// until the server fails.
+pub async fn serve(addr: SocketAddr, unwrapper: impl KeyUnwrapper) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(KeyProviderServiceServer::new(KeyProviderServer::new(
+            unwrapper,
+        )))
+        .serve(addr)
+        .await
+        .map_err(|e| anyhow!("keyprovider: grpc server failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DecryptConfig, KeyProviderAttrs};
+    use crate::keywrap::keyprovider::KeyProviderKeyWrapper;
+    use crate::keywrap::KeyWrapper;
+
+    struct TestUnwrapper {}
+
+    #[async_trait]
+    impl KeyUnwrapper for TestUnwrapper {
+        async fn unwrap_key(&self, annotation: &[u8], _: &Extensions) -> Result<Vec<u8>> {
+            match annotation {
+                b"wrapped key" => Ok(b"opts data".to_vec()),
+                _ => bail!("unknown key"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_keyprovider_server() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let addr: SocketAddr = "127.0.0.1:8995".parse().unwrap();
+        rt.spawn(serve(addr, TestUnwrapper {}));
+        // sleep for few seconds so that grpc server bootstraps
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let key_wrapper = KeyProviderKeyWrapper::new(
+            "keyprovider".to_string(),
+            KeyProviderAttrs {
+                cmd: None,
+                grpc: Some(addr.to_string()),
+                ttrpc: None,
+                native: None,
+            },
+            None,
+        );
+        let dc = DecryptConfig::default();
+        assert_eq!(
+            key_wrapper.unwrap_keys(&dc, b"wrapped key").unwrap(),
+            b"opts data"
+        );
+        assert!(key_wrapper.unwrap_keys(&dc, b"other key").is_err());
+
+        let server = KeyProviderServer::new(TestUnwrapper {});
+        let extensions = Extensions::default();
+        assert!(rt
+            .block_on(server.unwrap_key(b"invalid", &extensions))
+            .is_err());
+        let input = serde_json::to_vec(&KeyProviderKeyWrapProtocolInput {
+            op: OpKey::Wrap.to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(rt.block_on(server.unwrap_key(&input, &extensions)).is_err());
+    }
+}
@@ -11,6 +11,8 @@ use crate::config::{DecryptConfig, EncryptConfig};
 pub mod jwe;
 #[cfg(feature = "keywrap-keyprovider")]
 pub mod keyprovider;
+#[cfg(feature = "keywrap-pkcs11")]
+pub mod pkcs11;
 
 /// KeyWrapper is the interface used for wrapping keys using
 /// a specific encryption technology (pgp, jwe, pkcs7, pkcs11, keyprovider)
@@ -28,6 +30,12 @@ pub trait KeyWrapper: Send + Sync {
     /// Check whether the driver could handle the decryption request.
     fn probe(&self, dc_param: &HashMap<String, Vec<Vec<u8>>>) -> bool;
 
+    /// Check whether the driver has recipients to wrap the keys for in the encryption request.
+    /// If not implemented, the driver is asked to wrap the keys of every encryption request.
+    fn probe_encrypt(&self, ec_param: &HashMap<String, Vec<Vec<u8>>>) -> bool {
+        true
+    }
+
     /// private_keys (optional) gets the array of private keys. It is an optional implementation
     /// as in some key services, a private key may not be exportable (i.e. HSM)
     /// If not implemented, return `None`.
@@ -71,6 +79,11 @@ impl<W: KeyWrapper + ?Sized> KeyWrapper for Box<W> {
         (**self).probe(dc_param)
     }
 
+    #[inline]
+    fn probe_encrypt(&self, ec_param: &HashMap<String, Vec<Vec<u8>>>) -> bool {
+        (**self).probe_encrypt(ec_param)
+    }
+
     #[inline]
     fn private_keys(&self, dc_param: &HashMap<String, Vec<Vec<u8>>>) -> Option<Vec<Vec<u8>>> {
         (**self).private_keys(dc_param)
@@ -34,6 +34,14 @@ lazy_static! {
             );
         }
 
+        #[cfg(feature = "keywrap-pkcs11")]
+        {
+            m.insert(
+                "pkcs11".to_string(),
+                Box::new(crate::keywrap::pkcs11::Pkcs11KeyWrapper {}) as Box<dyn KeyWrapper>,
+            );
+        }
+
         #[cfg(feature = "keywrap-keyprovider")]
         {
             let ocicrypt_config =
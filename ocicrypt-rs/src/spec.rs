@@ -14,3 +14,21 @@ pub const MEDIA_TYPE_LAYER_NON_DISTRIBUTABLE_ENC: &str =
 /// MEDIA_TYPE_LAYER_NON_DISTRIBUTABLE_GZIP_ENC is MIME type used for non distributable encrypted compressed layers.
 pub const MEDIA_TYPE_LAYER_NON_DISTRIBUTABLE_GZIP_ENC: &str =
     "application/vnd.oci.image.layer.nondistributable.v1.tar+gzip+encrypted";
+
+/// Get the MIME type of the encrypted layers of `media_type`, or `None` if the layers of
+/// `media_type` can not be encrypted.
+pub fn encrypted_media_type(media_type: &str) -> Option<&'static str> {
+    match media_type {
+        "application/vnd.oci.image.layer.v1.tar" => Some(MEDIA_TYPE_LAYER_ENC),
+        "application/vnd.oci.image.layer.v1.tar+gzip"
+        | "application/vnd.docker.image.rootfs.diff.tar.gzip" => Some(MEDIA_TYPE_LAYER_GZIP_ENC),
+        "application/vnd.oci.image.layer.nondistributable.v1.tar" => {
+            Some(MEDIA_TYPE_LAYER_NON_DISTRIBUTABLE_ENC)
+        }
+        "application/vnd.oci.image.layer.nondistributable.v1.tar+gzip"
+        | "application/vnd.docker.image.rootfs.foreign.diff.tar.gzip" => {
+            Some(MEDIA_TYPE_LAYER_NON_DISTRIBUTABLE_GZIP_ENC)
+        }
+        _ => None,
+    }
+}